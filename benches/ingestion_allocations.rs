@@ -0,0 +1,155 @@
+//! Allocations and time per pending transaction on the ingestion path
+//!
+//! Run with `cargo bench --bench ingestion_allocations`. Each case first prints the heap
+//! allocations one operation makes, counted by a wrapping global allocator, then criterion
+//! times it.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ethers::types::{Address, Bytes, Transaction, H256, U256};
+use mev_capture::{
+    services::{
+        analytics::PendingTxObservation,
+        labels::TxLabels,
+        mempool::{decode_method, MempoolView},
+        transaction::{InclusionCandidate, TxSource},
+    },
+    utils::intern::address_str,
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// System allocator counting allocations
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+    
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Operations averaged over when counting allocations
+const COUNTED_RUNS: usize = 1_000;
+
+/// A Uniswap V2 style swap, the most common shape of pending transaction
+fn swap(nonce: u64) -> Transaction {
+    let mut input = vec![0x38, 0xed, 0x17, 0x39];
+    input.extend_from_slice(&[0u8; 160]);
+    
+    Transaction {
+        hash: H256::from_low_u64_be(nonce + 1),
+        nonce: U256::from(nonce),
+        from: Address::from_low_u64_be(0xbeef),
+        to: Some(Address::from_low_u64_be(0x7a25)),
+        value: U256::zero(),
+        gas: U256::from(200_000),
+        gas_price: Some(U256::from(30_000_000_000u64)),
+        max_priority_fee_per_gas: Some(U256::from(1_000_000_000u64)),
+        max_fee_per_gas: Some(U256::from(30_000_000_000u64)),
+        input: Bytes::from(input),
+        ..Default::default()
+    }
+}
+
+/// Print the mean allocations of `op` after a warm-up run has filled any interners
+fn report_allocations(name: &str, mut op: impl FnMut(u64)) {
+    op(0);
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for i in 0..COUNTED_RUNS {
+        op(i as u64);
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!("{}: {:.2} allocations per op", name, allocations as f64 / COUNTED_RUNS as f64);
+}
+
+fn bench_interning(c: &mut Criterion) {
+    let tx = swap(0);
+    
+    report_allocations("decode_method", |_| {
+        black_box(decode_method(&tx.input));
+    });
+    c.bench_function("decode_method", |b| b.iter(|| decode_method(black_box(&tx.input))));
+    
+    report_allocations("address_str", |_| {
+        black_box(address_str(tx.from));
+    });
+    c.bench_function("address_str", |b| b.iter(|| address_str(black_box(tx.from))));
+}
+
+fn bench_observation(c: &mut Criterion) {
+    let tx = swap(0);
+    
+    report_allocations("pending_tx_observation", |_| {
+        black_box(PendingTxObservation::from_transaction(&tx));
+    });
+    c.bench_function("pending_tx_observation", |b| {
+        b.iter(|| PendingTxObservation::from_transaction(black_box(&tx)))
+    });
+}
+
+fn bench_candidates(c: &mut Criterion) {
+    let tx = Arc::new(swap(0));
+    
+    // Candidates share the ingested transaction rather than copying it
+    report_allocations("inclusion_candidate", |_| {
+        black_box(InclusionCandidate {
+            tx: tx.clone(),
+            profit: U256::from(1_000_000),
+            source: TxSource::PublicMempool,
+            strategy: None,
+        });
+    });
+    c.bench_function("inclusion_candidate", |b| {
+        b.iter(|| InclusionCandidate {
+            tx: tx.clone(),
+            profit: U256::from(1_000_000),
+            source: TxSource::PublicMempool,
+            strategy: None,
+        })
+    });
+}
+
+fn bench_mempool_view(c: &mut Criterion) {
+    let view = MempoolView::new();
+    let txs: Vec<Transaction> = (0..COUNTED_RUNS as u64 + 1).map(swap).collect();
+    
+    report_allocations("mempool_view_insert", |i| {
+        view.insert(&txs[i as usize], TxSource::PublicMempool, TxLabels::default());
+    });
+    
+    let tx = swap(u64::MAX - 1);
+    c.bench_function("mempool_view_insert_remove", |b| {
+        b.iter(|| {
+            view.insert(black_box(&tx), TxSource::PublicMempool, TxLabels::default());
+            view.remove(&tx.hash);
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_interning,
+    bench_observation,
+    bench_candidates,
+    bench_mempool_view
+);
+criterion_main!(benches);
@@ -0,0 +1,7 @@
+-- Snapshot of in-flight builder state used for crash recovery
+CREATE TABLE IF NOT EXISTS inflight_state (
+    id TEXT PRIMARY KEY,
+    head_block BIGINT NOT NULL,
+    state JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,6 @@
+-- Per-head archive of in-flight builder state, used for slot replay debugging
+CREATE TABLE IF NOT EXISTS inflight_state_history (
+    head_block BIGINT PRIMARY KEY,
+    state JSONB NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
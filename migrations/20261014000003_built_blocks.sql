@@ -0,0 +1,10 @@
+-- Landed blocks containing our transactions, with the bundles we contributed
+CREATE TABLE IF NOT EXISTS built_blocks (
+    block_number BIGINT PRIMARY KEY,
+    block_hash TEXT NOT NULL,
+    value_wei TEXT NOT NULL,
+    bundles JSONB NOT NULL,
+    built_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS built_blocks_built_at_idx ON built_blocks (built_at);
@@ -0,0 +1,6 @@
+-- Operator-controlled desired state per subsystem
+CREATE TABLE IF NOT EXISTS subsystem_state (
+    name TEXT PRIMARY KEY,
+    paused BOOLEAN NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
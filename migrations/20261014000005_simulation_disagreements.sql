@@ -0,0 +1,14 @@
+-- Sampled transactions where the primary and shadow simulation engines disagreed
+CREATE TABLE IF NOT EXISTS simulation_disagreements (
+    id BIGSERIAL PRIMARY KEY,
+    tx_hash TEXT NOT NULL,
+    primary_engine TEXT NOT NULL,
+    shadow_engine TEXT NOT NULL,
+    primary_profit_wei TEXT NOT NULL,
+    shadow_profit_wei TEXT NOT NULL,
+    primary_success BOOLEAN NOT NULL,
+    shadow_success BOOLEAN NOT NULL,
+    observed_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS simulation_disagreements_observed_at_idx ON simulation_disagreements (observed_at);
@@ -0,0 +1,11 @@
+-- Searcher API keys owning at least one bundle in each built block, for per-searcher query scoping
+ALTER TABLE built_blocks ADD COLUMN IF NOT EXISTS owners TEXT[] NOT NULL DEFAULT '{}';
+
+UPDATE built_blocks
+SET owners = ARRAY(
+    SELECT DISTINCT bundle->'source'->>'id'
+    FROM jsonb_array_elements(bundles) AS bundle
+    WHERE bundle->'source'->>'kind' = 'searcher'
+);
+
+CREATE INDEX IF NOT EXISTS built_blocks_owners_idx ON built_blocks USING GIN (owners);
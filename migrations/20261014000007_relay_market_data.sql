@@ -0,0 +1,40 @@
+-- Bids we submitted to relays
+CREATE TABLE IF NOT EXISTS builder_bids (
+    id BIGSERIAL PRIMARY KEY,
+    slot BIGINT NOT NULL,
+    relay TEXT NOT NULL,
+    block_hash TEXT NOT NULL,
+    value_wei NUMERIC(78, 0) NOT NULL,
+    accepted BOOLEAN NOT NULL,
+    submitted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS builder_bids_slot_idx ON builder_bids (slot);
+
+-- Payloads relays report as delivered to proposers
+CREATE TABLE IF NOT EXISTS relay_delivered_payloads (
+    relay TEXT NOT NULL,
+    slot BIGINT NOT NULL,
+    block_number BIGINT NOT NULL,
+    block_hash TEXT NOT NULL,
+    builder_pubkey TEXT NOT NULL,
+    proposer_pubkey TEXT NOT NULL,
+    value_wei NUMERIC(78, 0) NOT NULL,
+    gas_used BIGINT NOT NULL,
+    num_tx BIGINT NOT NULL,
+    scraped_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (relay, slot)
+);
+
+-- Every bid relays report receiving for slots we scraped
+CREATE TABLE IF NOT EXISTS relay_bid_traces (
+    relay TEXT NOT NULL,
+    slot BIGINT NOT NULL,
+    block_hash TEXT NOT NULL,
+    builder_pubkey TEXT NOT NULL,
+    value_wei NUMERIC(78, 0) NOT NULL,
+    received_at_ms BIGINT NOT NULL,
+    PRIMARY KEY (relay, slot, block_hash)
+);
+
+CREATE INDEX IF NOT EXISTS relay_bid_traces_slot_idx ON relay_bid_traces (slot);
@@ -0,0 +1,11 @@
+-- Subsidies paid above extracted value on slots we won
+CREATE TABLE IF NOT EXISTS subsidy_ledger (
+    id BIGSERIAL PRIMARY KEY,
+    slot BIGINT NOT NULL,
+    block_hash TEXT NOT NULL UNIQUE,
+    rule TEXT NOT NULL,
+    amount_wei NUMERIC(78, 0) NOT NULL,
+    paid_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS subsidy_ledger_paid_at_idx ON subsidy_ledger (paid_at);
@@ -0,0 +1,17 @@
+-- End-of-slot reconciliation of our bids against relay, chain and P&L records
+CREATE TABLE IF NOT EXISTS slot_reconciliations (
+    slot BIGINT PRIMARY KEY,
+    won BOOLEAN NOT NULL,
+    block_number BIGINT,
+    block_hash TEXT,
+    relays TEXT[] NOT NULL DEFAULT '{}',
+    bid_value_wei NUMERIC(78, 0),
+    paid_wei NUMERIC(78, 0),
+    recorded_value_wei NUMERIC(78, 0),
+    subsidy_wei NUMERIC(78, 0),
+    discrepancies TEXT[] NOT NULL DEFAULT '{}',
+    reconciled_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS slot_reconciliations_discrepancies_idx
+    ON slot_reconciliations (slot) WHERE discrepancies <> '{}';
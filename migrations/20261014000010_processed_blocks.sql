@@ -0,0 +1,11 @@
+-- Processing state of every block we have booked, so overlapping catch-up, reorg handling
+-- and live processing book each block exactly once
+CREATE TABLE IF NOT EXISTS processed_blocks (
+    block_hash TEXT PRIMARY KEY,
+    block_number BIGINT NOT NULL,
+    state TEXT NOT NULL,
+    owner TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS processed_blocks_number_idx ON processed_blocks (block_number, state);
@@ -0,0 +1,40 @@
+-- Stake deposits booked per account
+CREATE TABLE IF NOT EXISTS stake_positions (
+    id BIGSERIAL PRIMARY KEY,
+    account TEXT NOT NULL,
+    validator_index BIGINT,
+    amount_wei NUMERIC(78, 0) NOT NULL,
+    shares NUMERIC(78, 0) NOT NULL,
+    tx_hash TEXT NOT NULL UNIQUE,
+    staked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS stake_positions_account_idx ON stake_positions (account);
+
+-- Rewards credited per account and epoch, with the principal they accrued on
+CREATE TABLE IF NOT EXISTS staking_rewards (
+    account TEXT NOT NULL,
+    epoch BIGINT NOT NULL,
+    consensus_wei NUMERIC(78, 0) NOT NULL,
+    mev_wei NUMERIC(78, 0) NOT NULL,
+    commission_wei NUMERIC(78, 0) NOT NULL,
+    principal_wei NUMERIC(78, 0) NOT NULL,
+    credited_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (account, epoch)
+);
+
+CREATE INDEX IF NOT EXISTS staking_rewards_credited_at_idx ON staking_rewards (credited_at);
+
+-- Unstake requests, pending until their claimable epoch
+CREATE TABLE IF NOT EXISTS staking_withdrawals (
+    id BIGSERIAL PRIMARY KEY,
+    account TEXT NOT NULL,
+    amount_wei NUMERIC(78, 0) NOT NULL,
+    shares NUMERIC(78, 0) NOT NULL,
+    requested_epoch BIGINT NOT NULL,
+    claimable_epoch BIGINT NOT NULL,
+    claimed_at TIMESTAMPTZ,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS staking_withdrawals_account_idx ON staking_withdrawals (account);
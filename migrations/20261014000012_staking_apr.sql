@@ -0,0 +1,16 @@
+-- Protocol-wide realized yield per epoch, derived from staking_rewards
+CREATE TABLE IF NOT EXISTS staking_apr (
+    epoch BIGINT PRIMARY KEY,
+    consensus_wei NUMERIC(78, 0) NOT NULL,
+    mev_wei NUMERIC(78, 0) NOT NULL,
+    commission_wei NUMERIC(78, 0) NOT NULL,
+    principal_wei NUMERIC(78, 0) NOT NULL,
+    gross_apr DOUBLE PRECISION NOT NULL,
+    net_apr DOUBLE PRECISION NOT NULL,
+    epoch_start TIMESTAMPTZ NOT NULL,
+    computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS staking_apr_epoch_start_idx ON staking_apr (epoch_start);
+
+CREATE INDEX IF NOT EXISTS staking_rewards_epoch_idx ON staking_rewards (epoch);
@@ -0,0 +1,20 @@
+-- Operator commission: one accrual per epoch, debited by payouts to the treasury
+CREATE TABLE IF NOT EXISTS commission_ledger (
+    id BIGSERIAL PRIMARY KEY,
+    -- accrual or payout
+    kind TEXT NOT NULL,
+    epoch BIGINT,
+    amount_wei NUMERIC(78, 0) NOT NULL,
+    treasury TEXT,
+    tx_hash TEXT,
+    -- accrued for accruals; pending, sent, confirmed or failed for payouts
+    status TEXT NOT NULL,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS commission_ledger_accrual_epoch_idx
+    ON commission_ledger (epoch) WHERE kind = 'accrual';
+
+CREATE INDEX IF NOT EXISTS commission_ledger_kind_status_idx ON commission_ledger (kind, status);
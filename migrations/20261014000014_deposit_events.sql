@@ -0,0 +1,30 @@
+-- Beacon deposit contract events for our withdrawal credentials
+CREATE TABLE IF NOT EXISTS deposit_events (
+    tx_hash TEXT NOT NULL,
+    log_index BIGINT NOT NULL,
+    block_number BIGINT NOT NULL,
+    pubkey TEXT NOT NULL,
+    withdrawal_credentials TEXT NOT NULL,
+    amount_wei NUMERIC(78, 0) NOT NULL,
+    deposit_index BIGINT NOT NULL,
+    indexed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tx_hash, log_index)
+);
+
+-- Last block scanned by the deposit indexer
+CREATE TABLE IF NOT EXISTS deposit_indexer_state (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    last_block BIGINT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Mismatches between on-chain deposits and booked stake positions
+CREATE TABLE IF NOT EXISTS deposit_discrepancies (
+    tx_hash TEXT NOT NULL,
+    -- missing_deposit, unknown_depositor or amount_mismatch
+    kind TEXT NOT NULL,
+    onchain_wei NUMERIC(78, 0),
+    booked_wei NUMERIC(78, 0),
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tx_hash, kind)
+);
@@ -0,0 +1,30 @@
+-- Non-sensitive pending transactions as observed, queryable while they stay pending
+CREATE TABLE IF NOT EXISTS mempool_transactions (
+    tx_hash TEXT PRIMARY KEY,
+    from_address TEXT NOT NULL,
+    to_address TEXT,
+    nonce NUMERIC(78, 0) NOT NULL,
+    value_wei NUMERIC(78, 0) NOT NULL,
+    gas BIGINT NOT NULL,
+    -- Legacy gas price or EIP-1559 max fee, the most the sender will pay per gas
+    gas_price_wei NUMERIC(78, 0) NOT NULL,
+    max_priority_fee_wei NUMERIC(78, 0),
+    tx_type SMALLINT NOT NULL,
+    -- First four bytes of calldata as 0x hex, NULL for plain transfers
+    method_selector TEXT,
+    input_size INTEGER NOT NULL,
+    source TEXT NOT NULL,
+    -- pending, included, replaced or dropped
+    status TEXT NOT NULL DEFAULT 'pending',
+    first_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    removed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS mempool_transactions_pending_idx
+    ON mempool_transactions (first_seen_at DESC) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS mempool_transactions_from_idx ON mempool_transactions (from_address, nonce);
+CREATE INDEX IF NOT EXISTS mempool_transactions_to_idx ON mempool_transactions (to_address);
+CREATE INDEX IF NOT EXISTS mempool_transactions_selector_idx ON mempool_transactions (method_selector);
+CREATE INDEX IF NOT EXISTS mempool_transactions_removed_idx
+    ON mempool_transactions (removed_at) WHERE status <> 'pending';
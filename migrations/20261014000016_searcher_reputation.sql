@@ -0,0 +1,14 @@
+-- Per-searcher track record behind simulation priority and load shedding
+CREATE TABLE IF NOT EXISTS searcher_reputation (
+    searcher TEXT PRIMARY KEY,
+    bundles_submitted BIGINT NOT NULL DEFAULT 0,
+    -- Bundles refused while the simulation queue was deep
+    bundles_throttled BIGINT NOT NULL DEFAULT 0,
+    txs_simulated BIGINT NOT NULL DEFAULT 0,
+    txs_failed BIGINT NOT NULL DEFAULT 0,
+    txs_landed BIGINT NOT NULL DEFAULT 0,
+    -- Simulated coinbase payment of landed transactions against the priority fees they paid
+    promised_wei NUMERIC(78, 0) NOT NULL DEFAULT 0,
+    paid_wei NUMERIC(78, 0) NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
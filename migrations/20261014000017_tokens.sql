@@ -0,0 +1,9 @@
+-- ERC-20 metadata read from chain on first use
+CREATE TABLE IF NOT EXISTS tokens (
+    address TEXT PRIMARY KEY,
+    symbol TEXT NOT NULL,
+    decimals SMALLINT NOT NULL,
+    -- Raw units as of fetched_at; refreshed once stale, unlike symbol and decimals
+    total_supply NUMERIC(78, 0) NOT NULL,
+    fetched_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
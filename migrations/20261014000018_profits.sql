@@ -0,0 +1,18 @@
+-- Realized MEV per landed bundle, valued in USD at inclusion
+CREATE TABLE IF NOT EXISTS profits (
+    block_number BIGINT NOT NULL,
+    block_hash TEXT NOT NULL,
+    -- Position of the bundle among ours in the block
+    bundle_index INTEGER NOT NULL,
+    tx_hashes TEXT[] NOT NULL,
+    strategy TEXT NOT NULL,
+    source TEXT NOT NULL,
+    profit_wei NUMERIC(78, 0) NOT NULL,
+    -- Absent when no ETH price was available at inclusion
+    eth_usd DOUBLE PRECISION,
+    profit_usd DOUBLE PRECISION,
+    included_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (block_number, bundle_index)
+);
+
+CREATE INDEX IF NOT EXISTS profits_included_at_idx ON profits (included_at);
@@ -0,0 +1,4 @@
+-- Which deployment placed each bid, for operators running several builders
+ALTER TABLE builder_bids ADD COLUMN IF NOT EXISTS builder_pubkey TEXT;
+ALTER TABLE builder_bids ADD COLUMN IF NOT EXISTS extra_data TEXT;
+ALTER TABLE builder_bids ADD COLUMN IF NOT EXISTS graffiti TEXT;
@@ -0,0 +1,19 @@
+-- Our ordering re-run over the transactions of blocks that beat us, one row per lost slot
+CREATE TABLE IF NOT EXISTS shadow_builds (
+    slot BIGINT PRIMARY KEY,
+    block_number BIGINT NOT NULL,
+    block_hash TEXT NOT NULL,
+    -- False when the delivered block was not canonical, leaving nothing to score
+    canonical BOOLEAN NOT NULL,
+    ordering TEXT NOT NULL,
+    landed_txs INTEGER NOT NULL,
+    shadow_txs INTEGER NOT NULL,
+    -- Fee recipient value of the landed order and of ours, both simulated on the parent state
+    landed_value_wei NUMERIC(78, 0),
+    shadow_value_wei NUMERIC(78, 0),
+    -- Shadow value over landed value, absent when the landed block was worth nothing
+    efficiency DOUBLE PRECISION,
+    scored_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS shadow_builds_scored_at_idx ON shadow_builds (scored_at);
@@ -0,0 +1,10 @@
+-- Known entities behind addresses, from imported label lists and operator entries
+CREATE TABLE IF NOT EXISTS address_labels (
+    address TEXT NOT NULL,
+    -- `manual` for operator entries, which win over lists, otherwise the list's name
+    source TEXT NOT NULL,
+    label TEXT NOT NULL,
+    category TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (address, source)
+);
@@ -0,0 +1,23 @@
+-- Gas the optimizer expects to save on each transaction we originate
+CREATE TABLE IF NOT EXISTS gas_savings (
+    tx_hash TEXT PRIMARY KEY,
+    -- Route the transaction belongs to, among the equivalent routes priced
+    route TEXT NOT NULL,
+    routes_considered INTEGER NOT NULL,
+    baseline_gas BIGINT NOT NULL,
+    optimized_gas BIGINT NOT NULL,
+    -- Gas the chosen route saves over the first route offered, booked on its first transaction
+    route_gas_saved BIGINT NOT NULL,
+    baseline_calldata_gas BIGINT NOT NULL,
+    optimized_calldata_gas BIGINT NOT NULL,
+    packed_calls INTEGER NOT NULL,
+    approvals_skipped INTEGER NOT NULL,
+    access_list BOOLEAN NOT NULL,
+    -- Gas saved at the base fee and tip the transaction was priced with
+    projected_savings_wei NUMERIC(78, 0) NOT NULL,
+    -- Signed but never sent
+    dry_run BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS gas_savings_created_at_idx ON gas_savings (created_at);
@@ -0,0 +1,22 @@
+-- What the builder decided for each slot it attempted, kept for audits after logs rotate
+CREATE TABLE IF NOT EXISTS build_decisions (
+    slot BIGINT PRIMARY KEY,
+    -- Last template built for the slot
+    candidates INTEGER,
+    included INTEGER,
+    -- Candidates left out because they touch state an included candidate touches
+    conflicts_resolved INTEGER,
+    -- keccak256 of the included transaction hashes in block order
+    ordering_hash TEXT,
+    value_wei NUMERIC(78, 0),
+    template_built_at TIMESTAMPTZ,
+    -- Last bid submitted for the slot
+    block_hash TEXT,
+    bid_wei NUMERIC(78, 0),
+    subsidy_wei NUMERIC(78, 0),
+    bids INTEGER NOT NULL DEFAULT 0,
+    -- Per-relay outcome of the last bid
+    submissions JSONB NOT NULL DEFAULT '[]',
+    first_submitted_at TIMESTAMPTZ,
+    last_submitted_at TIMESTAMPTZ
+);
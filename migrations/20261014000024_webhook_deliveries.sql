@@ -0,0 +1,18 @@
+-- Every attempt to deliver an alert to a named webhook, queried per webhook
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    webhook TEXT NOT NULL,
+    -- Shared by the attempts of one delivery, receivers deduplicate by it
+    delivery_id UUID NOT NULL,
+    alert_key TEXT NOT NULL,
+    attempt INTEGER NOT NULL,
+    -- delivered, replayed or failed
+    outcome TEXT NOT NULL,
+    -- Missing when the request got no response
+    status_code INTEGER,
+    error TEXT,
+    latency_ms BIGINT NOT NULL,
+    attempted_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS webhook_deliveries_webhook_idx ON webhook_deliveries (webhook, attempted_at);
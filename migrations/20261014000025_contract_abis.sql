@@ -0,0 +1,8 @@
+-- ABIs of verified contracts, fetched from Sourcify or Etherscan on first use
+CREATE TABLE IF NOT EXISTS contract_abis (
+    address TEXT PRIMARY KEY,
+    -- sourcify or etherscan, missing when neither had the contract verified
+    source TEXT,
+    abi JSONB,
+    fetched_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,31 @@
+-- Calldata of stored transactions, once per distinct content, zstd-compressed
+CREATE TABLE IF NOT EXISTS calldata_blobs (
+    -- keccak256 of the uncompressed calldata
+    hash BYTEA PRIMARY KEY,
+    data BYTEA NOT NULL,
+    size INTEGER NOT NULL,
+    -- Newest block referencing the calldata, blobs are pruned once it leaves the retention window
+    last_block BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS calldata_blobs_last_block_idx ON calldata_blobs (last_block);
+
+-- Confirmed blocks with their full transactions as zstd-compressed JSON, long calldata
+-- replaced by its hash in calldata_blobs
+CREATE TABLE IF NOT EXISTS block_bodies (
+    block_number BIGINT PRIMARY KEY,
+    block_hash TEXT NOT NULL,
+    body BYTEA NOT NULL,
+    raw_size INTEGER NOT NULL,
+    stored_size INTEGER NOT NULL,
+    stored_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Where each stored transaction sits, to find its body
+CREATE TABLE IF NOT EXISTS block_transactions (
+    tx_hash TEXT PRIMARY KEY,
+    block_number BIGINT NOT NULL,
+    tx_index INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS block_transactions_block_number_idx ON block_transactions (block_number);
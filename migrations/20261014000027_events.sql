@@ -0,0 +1,23 @@
+-- Decoded events of the contracts the event indexer follows
+CREATE TABLE IF NOT EXISTS events (
+    block_hash TEXT NOT NULL,
+    log_index INTEGER NOT NULL,
+    block_number BIGINT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    -- Configured name of the emitting contract
+    contract TEXT NOT NULL,
+    address TEXT NOT NULL,
+    event_name TEXT NOT NULL,
+    -- Canonical signature, e.g. Transfer(address,address,uint256)
+    signature TEXT NOT NULL,
+    -- Decoded parameters by name
+    params JSONB NOT NULL,
+    indexed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Keyed by block hash, so a log of a block that was reorged out only removes its own row
+    PRIMARY KEY (block_hash, log_index)
+);
+
+CREATE INDEX IF NOT EXISTS events_block_number_idx ON events (block_number);
+CREATE INDEX IF NOT EXISTS events_contract_idx ON events (contract, block_number);
+CREATE INDEX IF NOT EXISTS events_event_name_idx ON events (event_name, block_number);
+CREATE INDEX IF NOT EXISTS events_tx_hash_idx ON events (tx_hash);
@@ -0,0 +1,9 @@
+-- When a delivered payload's bid traces were stored, NULL until they all were, so a failed
+-- fetch is retried on the next scrape
+ALTER TABLE relay_delivered_payloads ADD COLUMN IF NOT EXISTS bids_scraped_at TIMESTAMPTZ;
+
+UPDATE relay_delivered_payloads SET bids_scraped_at = scraped_at WHERE bids_scraped_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS relay_delivered_payloads_pending_bids_idx
+    ON relay_delivered_payloads (relay, slot)
+    WHERE bids_scraped_at IS NULL;
@@ -0,0 +1,3 @@
+-- Fee recipient the proposer registered, where our payment transaction must go; NULL for
+-- payloads scraped before it was stored
+ALTER TABLE relay_delivered_payloads ADD COLUMN IF NOT EXISTS proposer_fee_recipient TEXT;
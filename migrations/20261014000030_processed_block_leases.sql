@@ -0,0 +1,3 @@
+-- When a processing claim lapses; a claim not completed by then can be taken over, whether
+-- its holder died or is still running
+ALTER TABLE processed_blocks ADD COLUMN IF NOT EXISTS lease_expires_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
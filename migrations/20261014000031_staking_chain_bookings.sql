@@ -0,0 +1,14 @@
+-- Execution-layer rewards of blocks proposed to the pool, credited to stakers per epoch
+CREATE TABLE IF NOT EXISTS staking_block_rewards (
+    block_number BIGINT PRIMARY KEY,
+    block_hash TEXT NOT NULL,
+    epoch BIGINT NOT NULL,
+    amount_wei NUMERIC(78, 0) NOT NULL,
+    booked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS staking_block_rewards_epoch_idx ON staking_block_rewards (epoch);
+
+-- Unstake requests booked from chain are keyed by the requesting transaction
+ALTER TABLE staking_withdrawals ADD COLUMN IF NOT EXISTS tx_hash TEXT;
+CREATE UNIQUE INDEX IF NOT EXISTS staking_withdrawals_tx_hash_idx ON staking_withdrawals (tx_hash);
@@ -0,0 +1,3 @@
+-- Sending account and nonce of a payout, so it settles through replacements of its transaction
+ALTER TABLE commission_ledger ADD COLUMN IF NOT EXISTS sender TEXT;
+ALTER TABLE commission_ledger ADD COLUMN IF NOT EXISTS nonce BIGINT;
@@ -0,0 +1,8 @@
+-- Deposits are matched to positions per log, and unbooked ones are reported by who sent them
+ALTER TABLE deposit_events ADD COLUMN IF NOT EXISTS depositor TEXT;
+ALTER TABLE stake_positions ADD COLUMN IF NOT EXISTS deposit_log_index BIGINT;
+
+ALTER TABLE deposit_discrepancies ADD COLUMN IF NOT EXISTS log_index BIGINT NOT NULL DEFAULT -1;
+ALTER TABLE deposit_discrepancies ADD COLUMN IF NOT EXISTS depositor TEXT;
+ALTER TABLE deposit_discrepancies DROP CONSTRAINT IF EXISTS deposit_discrepancies_pkey;
+ALTER TABLE deposit_discrepancies ADD PRIMARY KEY (tx_hash, log_index, kind);
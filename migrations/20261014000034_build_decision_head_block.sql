@@ -0,0 +1,2 @@
+-- Head block the slot's last template was built on, where slot replay finds its in-flight state
+ALTER TABLE build_decisions ADD COLUMN IF NOT EXISTS head_block BIGINT;
@@ -0,0 +1,4 @@
+-- Withdrawal claims booked from chain are keyed by the claiming log
+ALTER TABLE staking_withdrawals ADD COLUMN IF NOT EXISTS claim_tx_hash TEXT;
+ALTER TABLE staking_withdrawals ADD COLUMN IF NOT EXISTS claim_log_index BIGINT;
+CREATE UNIQUE INDEX IF NOT EXISTS staking_withdrawals_claim_idx ON staking_withdrawals (claim_tx_hash, claim_log_index);
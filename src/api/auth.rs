@@ -0,0 +1,362 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::{header, request::Parts, Request, StatusCode},
+    middleware::Next,
+    response::Response,
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+};
+use tracing::debug;
+
+use crate::{
+    api::encryption::EventPsk,
+    config::ApiConfig,
+    services::events::{StreamEvent, Topic},
+};
+
+/// Header carrying the caller's API key
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// Scheme of session tokens in the `Authorization` header
+const BEARER_PREFIX: &str = "Bearer ";
+
+/// Query parameter fallback for clients that cannot set headers, such as browser WebSockets
+const API_KEY_PARAM: &str = "api_key";
+
+/// What an API key is allowed to do, from least to most privileged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Read-only access to endpoints that change nothing
+    Readonly,
+    /// Access restricted to the key's own data and explicitly granted topics
+    Searcher,
+    /// Runtime controls such as pausing strategies and draining, without other searchers' data
+    Operator,
+    /// Operator access to every topic and every searcher's data
+    Admin,
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+    
+    fn from_str(role: &str) -> Result<Self> {
+        match role {
+            "admin" => Ok(Self::Admin),
+            "operator" => Ok(Self::Operator),
+            "searcher" => Ok(Self::Searcher),
+            "readonly" => Ok(Self::Readonly),
+            other => Err(anyhow!("Unknown role {}", other)),
+        }
+    }
+}
+
+/// The authenticated caller behind a request
+#[derive(Debug, Clone)]
+pub struct ApiPrincipal {
+    /// Name of the API key, used as the owner of its submissions
+    pub name: String,
+    pub role: Role,
+    /// Topics this key may subscribe to
+    topics: HashSet<Topic>,
+    /// Whether subscriptions must encrypt their event payloads, refused on streams that can't
+    pub encrypt_events: bool,
+    /// Key of the API key or session token presented, binding encrypted subscriptions to it
+    event_psk: EventPsk,
+}
+
+/// Why a subscription was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopicAuthError {
+    UnknownTopic(String),
+    Forbidden(Topic),
+    /// The key encrypts its events and the stream can't
+    EncryptionRequired(Topic),
+}
+
+impl TopicAuthError {
+    /// Machine-readable error code sent to clients
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnknownTopic(_) => "unknown_topic",
+            Self::Forbidden(_) => "forbidden",
+            Self::EncryptionRequired(_) => "encryption_required",
+        }
+    }
+    
+    /// Human-readable explanation sent to clients
+    pub fn message(&self) -> String {
+        match self {
+            Self::UnknownTopic(topic) => format!("Unknown topic: {}", topic),
+            Self::Forbidden(topic) => format!("API key is not authorized to subscribe to {}", topic),
+            Self::EncryptionRequired(topic) => {
+                format!("API key must subscribe to {} over /ws with a public_key to encrypt its events", topic)
+            }
+        }
+    }
+}
+
+impl ApiPrincipal {
+    pub fn is_admin(&self) -> bool {
+        self.role == Role::Admin
+    }
+    
+    /// Owner to scope data queries to, or None for admins who see everything
+    pub fn owner_filter(&self) -> Option<&str> {
+        if self.is_admin() {
+            None
+        } else {
+            Some(&self.name)
+        }
+    }
+    
+    /// Check that this key may subscribe to a topic
+    pub fn authorize_topic(&self, topic: &str) -> Result<Topic, TopicAuthError> {
+        let topic: Topic = topic
+            .parse()
+            .map_err(|_| TopicAuthError::UnknownTopic(topic.to_string()))?;
+        
+        if self.is_admin() || self.topics.contains(&topic) {
+            Ok(topic)
+        } else {
+            Err(TopicAuthError::Forbidden(topic))
+        }
+    }
+    
+    /// Key encrypted subscriptions of this caller are bound to
+    pub fn event_psk(&self) -> &EventPsk {
+        &self.event_psk
+    }
+    
+    /// Check that this key may subscribe to a topic on a stream whose events go out in plaintext
+    pub fn authorize_plaintext_topic(&self, topic: &str) -> Result<Topic, TopicAuthError> {
+        let topic = self.authorize_topic(topic)?;
+        if self.encrypt_events {
+            return Err(TopicAuthError::EncryptionRequired(topic));
+        }
+        Ok(topic)
+    }
+    
+    /// Whether an event on an authorized topic should be delivered to this key
+    pub fn may_receive(&self, event: &StreamEvent) -> bool {
+        match event.topic {
+            // Bundle statuses go only to the searcher that submitted the bundle
+            Topic::Bundles => self.owner_filter().map_or(true, |owner| event.owner.as_deref() == Some(owner)),
+            _ => true,
+        }
+    }
+}
+
+/// Session token contents
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    /// Name of the API key the token was issued for
+    sub: String,
+    role: Role,
+    topics: Vec<Topic>,
+    #[serde(default)]
+    encrypt_events: bool,
+    iat: i64,
+    exp: i64,
+}
+
+/// A freshly issued session token
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssuedToken {
+    pub token: String,
+    pub token_type: String,
+    pub role: Role,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// HMAC keys for session tokens
+struct JwtKeys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    ttl_seconds: u64,
+}
+
+/// Configured API keys, indexed by key, and the session token keys if enabled
+pub struct ApiKeys {
+    keys: HashMap<String, ApiPrincipal>,
+    jwt: Option<JwtKeys>,
+}
+
+impl ApiKeys {
+    pub fn from_config(config: &ApiConfig) -> Result<Self> {
+        let keys = config
+            .api_keys
+            .iter()
+            .map(|key| {
+                let principal = ApiPrincipal {
+                    name: key.name.clone(),
+                    role: key.role.parse().with_context(|| format!("API key {} has an invalid role", key.name))?,
+                    topics: key.topics.iter().filter_map(|t| t.parse().ok()).collect(),
+                    encrypt_events: key.encrypt_events,
+                    event_psk: EventPsk::from_credential(&key.key),
+                };
+                Ok((key.key.clone(), principal))
+            })
+            .collect::<Result<_>>()?;
+        
+        let jwt = config.jwt_secret.as_ref().map(|secret| JwtKeys {
+            encoding: EncodingKey::from_secret(secret.as_bytes()),
+            decoding: DecodingKey::from_secret(secret.as_bytes()),
+            ttl_seconds: config.jwt_ttl_seconds,
+        });
+        
+        Ok(Self { keys, jwt })
+    }
+    
+    /// Whether session tokens can be issued
+    pub fn tokens_enabled(&self) -> bool {
+        self.jwt.is_some()
+    }
+    
+    /// The principal behind an API key
+    pub fn authenticate(&self, key: &str) -> Option<&ApiPrincipal> {
+        self.keys.get(key)
+    }
+    
+    /// Issue a session token carrying a principal's role and topics
+    pub fn issue_token(&self, principal: &ApiPrincipal) -> Result<Option<IssuedToken>> {
+        let jwt = match &self.jwt {
+            Some(jwt) => jwt,
+            None => return Ok(None),
+        };
+        
+        let now = Utc::now();
+        let expires_at = now + chrono::Duration::seconds(jwt.ttl_seconds as i64);
+        let claims = Claims {
+            sub: principal.name.clone(),
+            role: principal.role,
+            topics: principal.topics.iter().copied().collect(),
+            encrypt_events: principal.encrypt_events,
+            iat: now.timestamp(),
+            exp: expires_at.timestamp(),
+        };
+        let token = jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &jwt.encoding)?;
+        
+        Ok(Some(IssuedToken {
+            token,
+            token_type: "Bearer".to_string(),
+            role: principal.role,
+            expires_at,
+        }))
+    }
+    
+    /// The principal a valid, unexpired session token was issued for
+    fn verify_token(&self, token: &str) -> Option<ApiPrincipal> {
+        let jwt = self.jwt.as_ref()?;
+        let validation = Validation::new(Algorithm::HS256);
+        let claims = match jsonwebtoken::decode::<Claims>(token, &jwt.decoding, &validation) {
+            Ok(data) => data.claims,
+            Err(e) => {
+                debug!("Rejected session token: {}", e);
+                return None;
+            }
+        };
+        
+        Some(ApiPrincipal {
+            name: claims.sub,
+            role: claims.role,
+            topics: claims.topics.into_iter().collect(),
+            encrypt_events: claims.encrypt_events,
+            event_psk: EventPsk::from_credential(token),
+        })
+    }
+}
+
+async fn authorize<B>(
+    required: Role,
+    principal: ApiPrincipal,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, StatusCode> {
+    if principal.role < required {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    
+    // Handlers extracting the principal reuse it instead of authenticating again
+    request.extensions_mut().insert(principal);
+    Ok(next.run(request).await)
+}
+
+/// Route layer admitting any authenticated caller
+pub async fn require_readonly<B>(
+    principal: ApiPrincipal,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, StatusCode> {
+    authorize(Role::Readonly, principal, request, next).await
+}
+
+/// Route layer admitting searchers and above
+pub async fn require_searcher<B>(
+    principal: ApiPrincipal,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, StatusCode> {
+    authorize(Role::Searcher, principal, request, next).await
+}
+
+/// Route layer admitting operators and admins
+pub async fn require_operator<B>(
+    principal: ApiPrincipal,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, StatusCode> {
+    authorize(Role::Operator, principal, request, next).await
+}
+
+/// Route layer admitting admins only, for responses holding every searcher's data
+pub async fn require_admin<B>(
+    principal: ApiPrincipal,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, StatusCode> {
+    authorize(Role::Admin, principal, request, next).await
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ApiPrincipal {
+    type Rejection = StatusCode;
+    
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(principal) = parts.extensions.get::<ApiPrincipal>() {
+            return Ok(principal.clone());
+        }
+        
+        let Extension(keys) = Extension::<Arc<ApiKeys>>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        
+        let bearer = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix(BEARER_PREFIX));
+        if let Some(token) = bearer {
+            return keys.verify_token(token.trim()).ok_or(StatusCode::UNAUTHORIZED);
+        }
+        
+        let key = match parts.headers.get(API_KEY_HEADER) {
+            Some(value) => value.to_str().map_err(|_| StatusCode::UNAUTHORIZED)?.to_string(),
+            None => Query::<HashMap<String, String>>::try_from_uri(&parts.uri)
+                .ok()
+                .and_then(|Query(mut params)| params.remove(API_KEY_PARAM))
+                .ok_or(StatusCode::UNAUTHORIZED)?,
+        };
+        
+        keys.authenticate(&key).cloned().ok_or(StatusCode::UNAUTHORIZED)
+    }
+}
@@ -0,0 +1,178 @@
+use anyhow::{anyhow, Context, Result};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Nonce,
+};
+use hkdf::Hkdf;
+use rand_core::OsRng;
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+/// HKDF context binding derived keys to this scheme
+const HKDF_INFO: &[u8] = b"mev-capture ws event v2";
+
+/// Domain of the pre-shared key derived from a credential
+const PSK_DOMAIN: &[u8] = b"mev-capture ws event psk v1";
+
+/// Key both ends derive from the credential a connection authenticated with
+///
+/// Mixed into every exchange on the connection, so keys swapped in by anyone relaying the
+/// subscription without the API key or session token yield a cipher neither end shares.
+#[derive(Clone)]
+pub struct EventPsk(Zeroizing<[u8; 32]>);
+
+impl EventPsk {
+    /// Key of an API key or session token
+    pub fn from_credential(credential: &str) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(PSK_DOMAIN);
+        hasher.update(credential.as_bytes());
+        Self(Zeroizing::new(hasher.finalize().into()))
+    }
+}
+
+impl std::fmt::Debug for EventPsk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EventPsk(..)")
+    }
+}
+
+/// An event payload encrypted to a subscription's key
+#[derive(Debug, Clone)]
+pub struct SealedPayload {
+    /// Hex-encoded 12-byte nonce
+    pub nonce: String,
+    /// Hex-encoded ciphertext including the authentication tag
+    pub ciphertext: String,
+}
+
+/// Key of one encrypted subscription, shared by server and client after the exchange
+///
+/// The client subscribes with an X25519 public key and the server answers with one of its
+/// own, generated for that subscription. The key is ChaCha20-Poly1305 under HKDF-SHA256 of
+/// the shared secret followed by the connection's [`EventPsk`], salted with the client and
+/// then the server public key. Each event's channel name is the associated data, so an event
+/// can't be passed off as another channel's. Only the server seals, with nonces counting up
+/// from zero.
+pub struct EventCipher {
+    cipher: ChaCha20Poly1305,
+    next_nonce: u64,
+}
+
+impl EventCipher {
+    /// Answer a client's public key, returning the cipher and the hex public key to send back
+    pub fn accept(client_public_key: &str, psk: &EventPsk) -> Result<(Self, String)> {
+        let client_public = parse_public_key(client_public_key)?;
+        let secret = StaticSecret::random_from_rng(OsRng);
+        let server_public = PublicKey::from(&secret);
+        let cipher = Self::derive(&secret, &client_public, &client_public, &server_public, psk)?;
+        
+        Ok((cipher, hex::encode(server_public.as_bytes())))
+    }
+    
+    fn derive(
+        secret: &StaticSecret,
+        peer: &PublicKey,
+        client: &PublicKey,
+        server: &PublicKey,
+        psk: &EventPsk,
+    ) -> Result<Self> {
+        let shared = secret.diffie_hellman(peer);
+        let mut salt = Vec::with_capacity(64);
+        salt.extend_from_slice(client.as_bytes());
+        salt.extend_from_slice(server.as_bytes());
+        let mut ikm = Zeroizing::new([0u8; 64]);
+        ikm[..32].copy_from_slice(shared.as_bytes());
+        ikm[32..].copy_from_slice(psk.0.as_ref());
+        
+        let mut key = Zeroizing::new([0u8; 32]);
+        Hkdf::<Sha256>::new(Some(&salt), ikm.as_ref())
+            .expand(HKDF_INFO, key.as_mut())
+            .map_err(|_| anyhow!("Failed to derive event key"))?;
+        
+        Ok(Self {
+            cipher: ChaCha20Poly1305::new(key.as_ref().into()),
+            next_nonce: 0,
+        })
+    }
+    
+    /// Encrypt an event's payload for its channel
+    pub fn seal(&mut self, channel: &str, payload: &[u8]) -> Result<SealedPayload> {
+        let mut nonce = [0u8; 12];
+        nonce[4..].copy_from_slice(&self.next_nonce.to_be_bytes());
+        self.next_nonce += 1;
+        
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: payload,
+                    aad: channel.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow!("Failed to encrypt event"))?;
+        
+        Ok(SealedPayload {
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+    
+    /// Decrypt an event's payload sealed for its channel
+    pub fn open(&self, channel: &str, nonce: &str, ciphertext: &str) -> Result<Vec<u8>> {
+        let nonce: [u8; 12] = decode_hex(nonce)?
+            .try_into()
+            .map_err(|_| anyhow!("Nonce must be 12 bytes"))?;
+        let ciphertext = decode_hex(ciphertext)?;
+        
+        self.cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: channel.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow!("Failed to decrypt event"))
+    }
+}
+
+/// A client's key for one encrypted subscription, kept until the server answers
+pub struct EventKeyPair {
+    secret: StaticSecret,
+    public: PublicKey,
+    /// Key of the credential the subscribing connection authenticated with
+    psk: EventPsk,
+}
+
+impl EventKeyPair {
+    pub fn generate(psk: EventPsk) -> Self {
+        let secret = StaticSecret::random_from_rng(OsRng);
+        let public = PublicKey::from(&secret);
+        Self { secret, public, psk }
+    }
+    
+    /// Hex public key to subscribe with
+    pub fn public_key(&self) -> String {
+        hex::encode(self.public.as_bytes())
+    }
+    
+    /// Cipher of the subscription, from the public key the server answered with
+    pub fn complete(&self, server_public_key: &str) -> Result<EventCipher> {
+        let server_public = parse_public_key(server_public_key)?;
+        EventCipher::derive(&self.secret, &server_public, &self.public, &server_public, &self.psk)
+    }
+}
+
+fn parse_public_key(value: &str) -> Result<PublicKey> {
+    let bytes: [u8; 32] = decode_hex(value)?
+        .try_into()
+        .map_err(|_| anyhow!("Public key must be 32 bytes"))?;
+    Ok(PublicKey::from(bytes))
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.trim_start_matches("0x")).context("Invalid hex encoding")
+}
@@ -0,0 +1,240 @@
+use axum::{
+    extract::{Extension, Path, Query},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{
+    api::handlers::db_error_status,
+    core::strategy::StrategyState,
+    services::{
+        commission::CommissionHistory,
+        controls::SubsystemState,
+        relay::RelayStatus,
+        risk::RiskStatus,
+        webhooks::DeliveryAttempt,
+        ServiceContext,
+    },
+};
+
+/// Most commission entries returned at once
+const MAX_COMMISSION_ENTRIES: i64 = 1000;
+
+/// Most webhook delivery attempts returned at once
+const MAX_DELIVERY_ATTEMPTS: i64 = 1000;
+
+#[derive(Serialize, Deserialize)]
+pub struct DrainResponse {
+    status: String,
+    already_draining: bool,
+}
+
+/// Start the drain sequence; the process exits once in-flight work completes
+pub async fn drain(
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Result<(StatusCode, Json<DrainResponse>), StatusCode> {
+    let started = services.drain_controller.request();
+    
+    let response = DrainResponse {
+        status: "draining".to_string(),
+        already_draining: !started,
+    };
+    
+    Ok((StatusCode::ACCEPTED, Json(response)))
+}
+
+/// List controllable subsystems and whether each is paused
+pub async fn list_subsystems(
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Result<Json<Vec<SubsystemState>>, StatusCode> {
+    Ok(Json(services.controls.list()))
+}
+
+/// Pause a subsystem until it is explicitly resumed
+pub async fn pause_subsystem(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(name): Path<String>,
+) -> Result<Json<SubsystemState>, StatusCode> {
+    set_subsystem_paused(&services, name, true).await
+}
+
+/// Resume a paused subsystem
+pub async fn resume_subsystem(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(name): Path<String>,
+) -> Result<Json<SubsystemState>, StatusCode> {
+    set_subsystem_paused(&services, name, false).await
+}
+
+async fn set_subsystem_paused(
+    services: &ServiceContext,
+    name: String,
+    paused: bool,
+) -> Result<Json<SubsystemState>, StatusCode> {
+    if !services.controls.is_known(&name) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    
+    services
+        .controls
+        .set_paused(&name, paused)
+        .await
+        .map_err(|e| {
+            error!("Failed to update subsystem {}: {}", name, e);
+            db_error_status(&e)
+        })?;
+    
+    Ok(Json(SubsystemState { name, paused }))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct HaltRequest {
+    reason: Option<String>,
+}
+
+/// Risk windows and whether live submission is halted
+pub async fn get_risk(
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Result<Json<RiskStatus>, StatusCode> {
+    Ok(Json(services.risk_manager.status()))
+}
+
+/// Kill switch: halt live submission, running as a dry run until resumed
+pub async fn halt_submission(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Json(request): Json<HaltRequest>,
+) -> Result<Json<RiskStatus>, StatusCode> {
+    let reason = request.reason.unwrap_or_else(|| "halted by an operator".to_string());
+    services.risk_manager.halt(&reason).await.map_err(|e| {
+        error!("Failed to halt live submission: {}", e);
+        db_error_status(&e)
+    })?;
+    
+    Ok(Json(services.risk_manager.status()))
+}
+
+/// Resume live submission after a manual or tripped halt
+pub async fn resume_submission(
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Result<Json<RiskStatus>, StatusCode> {
+    services.risk_manager.resume().await.map_err(|e| {
+        error!("Failed to resume live submission: {}", e);
+        db_error_status(&e)
+    })?;
+    
+    Ok(Json(services.risk_manager.status()))
+}
+
+/// List loaded strategies and whether each is enabled
+pub async fn list_strategies(
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Result<Json<Vec<StrategyState>>, StatusCode> {
+    Ok(Json(services.strategy_manager.list()))
+}
+
+/// Enable a loaded strategy
+pub async fn enable_strategy(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(name): Path<String>,
+) -> Result<Json<StrategyState>, StatusCode> {
+    set_strategy_enabled(&services, name, true).await
+}
+
+/// Disable a strategy until it is enabled again, across restarts
+pub async fn disable_strategy(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(name): Path<String>,
+) -> Result<Json<StrategyState>, StatusCode> {
+    set_strategy_enabled(&services, name, false).await
+}
+
+async fn set_strategy_enabled(
+    services: &ServiceContext,
+    name: String,
+    enabled: bool,
+) -> Result<Json<StrategyState>, StatusCode> {
+    if !services.strategy_manager.is_loaded(&name) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    
+    services.strategy_manager.set_enabled(&name, enabled).await.map_err(|e| {
+        error!("Failed to update strategy {}: {}", name, e);
+        db_error_status(&e)
+    })?;
+    
+    services
+        .strategy_manager
+        .list()
+        .into_iter()
+        .find(|state| state.name == name)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Per-relay submission outcomes, recent errors and backoff state
+pub async fn list_relays(
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Result<Json<Vec<RelayStatus>>, StatusCode> {
+    Ok(Json(services.relay_service.relay_statuses()))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CommissionQuery {
+    /// `accrual` or `payout`, both by default
+    kind: Option<String>,
+    limit: Option<i64>,
+}
+
+/// Outstanding operator commission with its accrual and payout history
+pub async fn list_commission(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Query(query): Query<CommissionQuery>,
+) -> Result<Json<CommissionHistory>, StatusCode> {
+    if let Some(kind) = query.kind.as_deref() {
+        if kind != "accrual" && kind != "payout" {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+    let limit = query.limit.unwrap_or(100).clamp(1, MAX_COMMISSION_ENTRIES);
+    
+    services
+        .commission_service
+        .history(query.kind.as_deref(), limit)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to load commission history: {}", e);
+            db_error_status(&e)
+        })
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WebhookDeliveriesQuery {
+    /// Number of most recent attempts, defaults to 100
+    limit: Option<i64>,
+}
+
+/// Recent delivery attempts of alerts to a configured webhook, newest first
+pub async fn list_webhook_deliveries(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(name): Path<String>,
+    Query(query): Query<WebhookDeliveriesQuery>,
+) -> Result<Json<Vec<DeliveryAttempt>>, StatusCode> {
+    if !services.config.alerting.webhooks.iter().any(|webhook| webhook.name == name) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let limit = query.limit.unwrap_or(100).clamp(1, MAX_DELIVERY_ATTEMPTS);
+    
+    services
+        .webhook_deliveries
+        .attempts(&name, limit)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to load deliveries of webhook {}: {}", name, e);
+            db_error_status(&e)
+        })
+}
@@ -0,0 +1,169 @@
+use axum::{
+    extract::{Extension, Path, Query},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, warn};
+
+use crate::{
+    api::handlers::db_error_status,
+    services::{
+        fee_backtest::{FeeBacktestReport, FeeBacktestRequest, FeeBacktestService},
+        gas_golf::GasSavingsReport, kpi::HourlyKpis, relay_scraper::SlotMarketComparison,
+        settlement::SlotReconciliation, shadow_build::EfficiencyReport, ServiceContext,
+    },
+};
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct MarketBidsQuery {
+    /// Number of most recent slots, defaults to 100
+    pub limit: Option<i64>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct ReconciliationQuery {
+    /// Number of most recent reconciled slots, defaults to 100
+    pub limit: Option<i64>,
+    /// Only return slots with discrepancies
+    #[serde(default)]
+    pub discrepancies_only: bool,
+}
+
+/// Proxy a named dashboard query to the ClickHouse sink
+pub async fn dashboard_query(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    if !services.analytics_sink.enabled() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+    
+    match services.analytics_sink.dashboard_query(&name).await {
+        Ok(Some(result)) => Ok(Json(result)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to run dashboard query {}: {}", name, e);
+            Err(StatusCode::BAD_GATEWAY)
+        }
+    }
+}
+
+/// Business KPIs over the trailing hour, including the breakdown by flow source; admins only
+pub async fn get_kpis(
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Result<Json<HourlyKpis>, StatusCode> {
+    services
+        .kpi_aggregator
+        .hourly_kpis()
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to compute KPIs: {}", e);
+            db_error_status(&e)
+        })
+}
+
+/// Our bids against the market-clearing bid for recent slots
+pub async fn get_market_bids(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Query(query): Query<MarketBidsQuery>,
+) -> Result<Json<Vec<SlotMarketComparison>>, StatusCode> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    
+    services
+        .relay_scraper
+        .slot_comparisons(limit)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to compare bids to market: {}", e);
+            db_error_status(&e)
+        })
+}
+
+/// Reconciliation of recent slots we bid on against relay, chain and P&L records
+pub async fn get_reconciliation(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Query(query): Query<ReconciliationQuery>,
+) -> Result<Json<Vec<SlotReconciliation>>, StatusCode> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    
+    services
+        .settlement_reconciler
+        .report(limit, query.discrepancies_only)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to load slot reconciliations: {}", e);
+            db_error_status(&e)
+        })
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct EfficiencyQuery {
+    /// Trailing days summarized, defaults to 30
+    pub days: Option<i64>,
+    /// Number of most recent lost slots listed, defaults to 100
+    pub limit: Option<i64>,
+}
+
+/// Our ordering against the winner's over the slots we lost, daily and per recent slot
+pub async fn get_builder_efficiency(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Query(query): Query<EfficiencyQuery>,
+) -> Result<Json<EfficiencyReport>, StatusCode> {
+    let days = query.days.unwrap_or(30).clamp(1, 366);
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    
+    services
+        .shadow_build_service
+        .report(days, limit)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to load builder efficiency: {}", e);
+            db_error_status(&e)
+        })
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct GasSavingsQuery {
+    /// Number of most recent optimized transactions listed, defaults to 100
+    pub limit: Option<i64>,
+}
+
+/// Gas the optimizer expects to have saved on our transactions, in total and per transaction
+pub async fn get_gas_savings(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Query(query): Query<GasSavingsQuery>,
+) -> Result<Json<GasSavingsReport>, StatusCode> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    
+    services.gas_golfer.report(limit).await.map(Json).map_err(|e| {
+        error!("Failed to load gas savings: {}", e);
+        db_error_status(&e)
+    })
+}
+
+/// Replay recent fee history under a hypothetical fee policy
+pub async fn fee_backtest(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Json(request): Json<FeeBacktestRequest>,
+) -> Result<Json<FeeBacktestReport>, StatusCode> {
+    if let Err(e) = FeeBacktestService::validate(&request) {
+        warn!("Rejected fee backtest: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    
+    services
+        .fee_backtest_service
+        .backtest(&request)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to backtest fee policy: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
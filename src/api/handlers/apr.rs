@@ -0,0 +1,43 @@
+use axum::{
+    extract::{Extension, Query},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{
+    api::{handlers::db_error_status, validation},
+    services::{staking_ledger::AprSummary, ServiceContext},
+};
+
+/// Longest APR window served
+const MAX_WINDOW_DAYS: u32 = 365;
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct AprQuery {
+    /// Trailing window such as `30d`, defaults to 30 days
+    pub window: Option<String>,
+}
+
+/// Realized staking APR over a trailing window, gross and net of commission
+pub async fn get_apr(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Query(query): Query<AprQuery>,
+) -> Result<Json<AprSummary>, Response> {
+    let days = match query.window.as_deref() {
+        Some(window) => {
+            validation::window_days("window", window, MAX_WINDOW_DAYS).map_err(IntoResponse::into_response)?
+        }
+        None => 30,
+    };
+    
+    match services.staking_ledger.apr_summary(days).await {
+        Ok(summary) => Ok(Json(summary)),
+        Err(e) => {
+            error!("Failed to load staking APR: {}", e);
+            Err(db_error_status(&e).into_response())
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use axum::{extract::Extension, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::api::auth::{ApiKeys, IssuedToken};
+
+#[derive(Serialize, Deserialize)]
+pub struct TokenRequest {
+    pub api_key: String,
+}
+
+/// Exchange an API key for a short-lived session token carrying its role
+pub async fn issue_token(
+    Extension(keys): Extension<Arc<ApiKeys>>,
+    Json(request): Json<TokenRequest>,
+) -> Result<Json<IssuedToken>, StatusCode> {
+    if !keys.tokens_enabled() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    
+    let principal = match keys.authenticate(&request.api_key) {
+        Some(principal) => principal,
+        None => {
+            warn!("Rejected token request with an unknown API key");
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    };
+    
+    match keys.issue_token(principal) {
+        Ok(Some(token)) => {
+            info!("Issued {:?} session token to {}", token.role, principal.name);
+            Ok(Json(token))
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to issue session token: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use axum::{
+    extract::Extension,
+    http::StatusCode,
+    Json,
+};
+use ethers::{
+    types::{Bytes, Transaction},
+    utils::rlp,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    api::auth::ApiPrincipal,
+    services::{
+        reputation::Throttled,
+        sealed_bundles::{SealedBundle, SealingKeyInfo},
+        simulation::BundleSimulationResult,
+        ServiceContext,
+    },
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct SealedBundleResponse {
+    pub bundle_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SimulateBundleRequest {
+    /// Signed raw transactions, hex-encoded, in execution order
+    pub txs: Vec<Bytes>,
+}
+
+/// Public keys searchers should seal bundles to, current key first
+pub async fn get_sealing_keys(
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Result<Json<Vec<SealingKeyInfo>>, StatusCode> {
+    if !services.sealed_bundle_service.enabled() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    
+    Ok(Json(services.sealed_bundle_service.public_keys()))
+}
+
+/// Submit an encrypted bundle, decrypted only when its target block is built
+pub async fn submit_sealed_bundle(
+    principal: ApiPrincipal,
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Json(bundle): Json<SealedBundle>,
+) -> Result<(StatusCode, Json<SealedBundleResponse>), StatusCode> {
+    if !services.sealed_bundle_service.enabled() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    
+    if services.drain_controller.is_draining() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+    
+    let bundle_id = services
+        .sealed_bundle_service
+        .submit(principal.name.clone(), bundle)
+        .map_err(|e| {
+            warn!("Rejected sealed bundle from {}: {}", principal.name, e);
+            match e.downcast_ref::<Throttled>() {
+                Some(_) => StatusCode::TOO_MANY_REQUESTS,
+                None => StatusCode::BAD_REQUEST,
+            }
+        })?;
+    
+    Ok((StatusCode::ACCEPTED, Json(SealedBundleResponse { bundle_id })))
+}
+
+/// Simulate raw transactions in order on top of the latest block, without submitting them
+pub async fn simulate_bundle(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Json(request): Json<SimulateBundleRequest>,
+) -> Result<Json<BundleSimulationResult>, StatusCode> {
+    if request.txs.is_empty() || request.txs.len() > services.config.services.bundles.max_bundle_txs {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    
+    let txs = request
+        .txs
+        .iter()
+        .map(|raw| rlp::decode::<Transaction>(raw))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    
+    services
+        .simulation_service
+        .simulate_bundle(txs)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            warn!("Failed to simulate bundle: {}", e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        })
+}
@@ -0,0 +1,34 @@
+use axum::{
+    extract::Extension,
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{
+    api::handlers::db_error_status,
+    services::{replay::SlotReplay, ServiceContext},
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct ReplaySlotRequest {
+    /// Beacon slot built for, as in `/api/blocks/:slot/decision`
+    slot: u64,
+}
+
+/// Reconstruct a past slot from persisted records and re-run the ordering
+pub async fn replay_slot(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Json(request): Json<ReplaySlotRequest>,
+) -> Result<Json<SlotReplay>, StatusCode> {
+    match services.replay_service.replay_slot(request.slot).await {
+        Ok(Some(replay)) => Ok(Json(replay)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to replay slot {}: {}", request.slot, e);
+            Err(db_error_status(&e))
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{
+    api::handlers::db_error_status,
+    services::{build_decisions::BuildDecision, ServiceContext},
+};
+
+/// What the builder decided for a slot: template, conflicts, value, bid and submission times
+pub async fn get_build_decision(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(slot): Path<u64>,
+) -> Result<Json<BuildDecision>, StatusCode> {
+    match services.build_decisions.get(slot).await {
+        Ok(Some(decision)) => Ok(Json(decision)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to fetch build decision for slot {}: {}", slot, e);
+            Err(db_error_status(&e))
+        }
+    }
+}
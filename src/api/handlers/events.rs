@@ -0,0 +1,91 @@
+use axum::{
+    extract::{Extension, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use ethers::types::H256;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, warn};
+
+use crate::{
+    api::validation::{self, ValidationError},
+    database::{
+        repositories::events::{EventPage, EventQuery},
+        resilience::is_connection_loss,
+    },
+    services::ServiceContext,
+};
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct EventsQuery {
+    /// Configured name of the emitting contract
+    pub contract: Option<String>,
+    /// Address of the emitting contract
+    pub address: Option<String>,
+    /// Event name, e.g. `Transfer`
+    pub event: Option<String>,
+    pub tx_hash: Option<String>,
+    /// First block included
+    pub from_block: Option<u64>,
+    /// Last block included
+    pub to_block: Option<u64>,
+    #[serde(default)]
+    pub offset: i64,
+    /// Page size, defaults to 100
+    pub limit: Option<i64>,
+}
+
+/// Query indexed contract events, newest first
+pub async fn list_events(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Query(query): Query<EventsQuery>,
+) -> Result<Json<EventPage>, Response> {
+    if !services.log_indexer.enabled() {
+        return Err(StatusCode::NOT_FOUND.into_response());
+    }
+    let filter = event_filter(query).map_err(IntoResponse::into_response)?;
+    
+    match services.log_indexer.query(&filter).await {
+        Ok(page) => Ok(Json(page)),
+        Err(e) if is_connection_loss(&e) => {
+            warn!("Database unavailable for event query: {}", e);
+            Err(StatusCode::SERVICE_UNAVAILABLE.into_response())
+        }
+        Err(e) => {
+            error!("Failed to query indexed events: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+fn event_filter(query: EventsQuery) -> Result<EventQuery, ValidationError> {
+    if query.offset < 0 {
+        return Err(ValidationError::new("offset", "must not be negative"));
+    }
+    if let (Some(from), Some(to)) = (query.from_block, query.to_block) {
+        if from > to {
+            return Err(ValidationError::new("from_block", "must not be after to_block"));
+        }
+    }
+    let tx_hash = query
+        .tx_hash
+        .as_deref()
+        .map(|raw| {
+            raw.parse::<H256>()
+                .map_err(|_| ValidationError::new("tx_hash", "must be 32 bytes of hex"))
+        })
+        .transpose()?;
+    
+    Ok(EventQuery {
+        contract: query.contract,
+        address: query.address.as_deref().map(|raw| validation::address("address", raw)).transpose()?,
+        event_name: query.event,
+        tx_hash,
+        from_block: query.from_block,
+        to_block: query.to_block,
+        offset: query.offset,
+        limit: query.limit.unwrap_or(100).clamp(1, 1000),
+    })
+}
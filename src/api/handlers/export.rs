@@ -0,0 +1,90 @@
+use axum::{
+    body::StreamBody,
+    extract::{Extension, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{
+    api::auth::ApiPrincipal,
+    services::{
+        analytics_export::ExportManifest,
+        export::{ExportFormat, ExportRange, MAX_EXPORT_RANGE_DAYS},
+        ServiceContext,
+    },
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct ExportQuery {
+    pub format: ExportFormat,
+    /// Start of the range, defaults to 24 hours ago
+    pub from: Option<DateTime<Utc>>,
+    /// End of the range, defaults to now
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Download landed blocks we built and their bundles, limited to the caller's own unless admin
+///
+/// Ranges longer than `MAX_EXPORT_RANGE_DAYS` are refused; both formats stream page by page.
+pub async fn export_blocks(
+    principal: ApiPrincipal,
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, StatusCode> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or_else(|| to - Duration::hours(24));
+    if from >= to || to - from > Duration::days(MAX_EXPORT_RANGE_DAYS) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let range = ExportRange { from, to };
+    let owner = principal.owner_filter().map(str::to_string);
+    
+    match query.format {
+        ExportFormat::Json => {
+            let body = StreamBody::new(services.export_service.stream_json(range, owner));
+            Ok((
+                [
+                    (header::CONTENT_TYPE, "application/x-ndjson"),
+                    (header::CONTENT_DISPOSITION, "attachment; filename=\"blocks.jsonl\""),
+                ],
+                body,
+            )
+                .into_response())
+        }
+        ExportFormat::Parquet => {
+            let stream = services.export_service.stream_parquet(range, owner).map_err(|e| {
+                error!("Failed to export blocks as Parquet: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+            let body = StreamBody::new(stream);
+            Ok((
+                [
+                    (header::CONTENT_TYPE, "application/vnd.apache.parquet"),
+                    (header::CONTENT_DISPOSITION, "attachment; filename=\"blocks.parquet\""),
+                ],
+                body,
+            )
+                .into_response())
+        }
+    }
+}
+
+/// List exported analytics partitions with their schema versions
+pub async fn get_manifest(
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Result<Json<ExportManifest>, StatusCode> {
+    services
+        .analytics_export_service
+        .load_manifest()
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to load export manifest: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
@@ -0,0 +1,25 @@
+use axum::{extract::Extension, http::StatusCode, Json};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{blockchain::fees::GasPriceReport, services::ServiceContext};
+
+/// Gas price report as of the latest head, the polling counterpart of the `gas_price` topic
+pub async fn get_latest(
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Result<Json<GasPriceReport>, StatusCode> {
+    if let Some(report) = services.fee_estimator.latest_report() {
+        return Ok(Json(report));
+    }
+    
+    // No head since startup yet
+    services
+        .fee_estimator
+        .refresh_report()
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to report gas prices: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
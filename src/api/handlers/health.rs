@@ -6,16 +6,18 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-use crate::services::ServiceContext;
+use crate::{database::resilience::DbHealthReport, services::ServiceContext};
 
 #[derive(Serialize, Deserialize)]
 pub struct HealthResponse {
-    status: String,
-    version: String,
-    uptime_seconds: u64,
-    blockchain_connected: bool,
-    database_connected: bool,
-    redis_connected: bool,
+    pub status: String,
+    pub version: String,
+    pub uptime_seconds: u64,
+    pub blockchain_connected: bool,
+    pub database_connected: bool,
+    pub redis_connected: bool,
+    /// Redis recently stopped answering and non-critical caching is skipped
+    pub redis_degraded: bool,
 }
 
 /// Health check endpoint
@@ -51,7 +53,30 @@ pub async fn health_check(
         blockchain_connected,
         database_connected: db_connected,
         redis_connected,
+        redis_degraded: services.redis.is_degraded(),
     };
     
     Ok(Json(response))
-} 
\ No newline at end of file
+}
+#[derive(Serialize, Deserialize)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    pub database: DbHealthReport,
+    /// Redis being degraded only skips caching, so it doesn't make us unready
+    pub redis_degraded: bool,
+}
+
+/// Readiness endpoint, 503 while the database is unreachable
+pub async fn readiness(
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    let ready = services.db_health.probe(&services.db_pool).await;
+    let response = ReadinessResponse {
+        ready,
+        database: services.db_health.report(),
+        redis_degraded: services.redis.is_degraded(),
+    };
+    
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(response))
+}
@@ -0,0 +1,91 @@
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    Json,
+};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{
+    api::handlers::db_error_status,
+    services::{
+        labels::{AddressLabel, LabelEntry, MANUAL_SOURCE},
+        ServiceContext,
+    },
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct ImportResponse {
+    source: String,
+    imported: usize,
+}
+
+/// Label of one address, an operator entry winning over imported lists
+pub async fn get_label(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(address): Path<String>,
+) -> Result<Json<AddressLabel>, StatusCode> {
+    let address: Address = address.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    
+    services.label_registry.lookup(&address).map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Every label from every source
+pub async fn list_labels(
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Result<Json<Vec<AddressLabel>>, StatusCode> {
+    Ok(Json(services.label_registry.list()))
+}
+
+/// Label an address by hand, overriding imported lists
+pub async fn set_label(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Json(entry): Json<LabelEntry>,
+) -> Result<Json<AddressLabel>, StatusCode> {
+    if entry.label.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    
+    services.label_registry.set_manual(entry).await.map(Json).map_err(|e| {
+        error!("Failed to store address label: {}", e);
+        db_error_status(&e)
+    })
+}
+
+/// Remove an operator label, leaving any imported label in place
+pub async fn remove_label(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(address): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let address: Address = address.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    
+    match services.label_registry.remove_manual(address).await {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to remove address label for {:?}: {}", address, e);
+            Err(db_error_status(&e))
+        }
+    }
+}
+
+/// Import a label list, replacing everything previously imported under its name
+pub async fn import_labels(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(source): Path<String>,
+    Json(entries): Json<Vec<LabelEntry>>,
+) -> Result<Json<ImportResponse>, StatusCode> {
+    if source == MANUAL_SOURCE {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    
+    match services.label_registry.import(&source, entries).await {
+        Ok(imported) => Ok(Json(ImportResponse { source, imported })),
+        Err(e) => {
+            error!("Failed to import label list {}: {}", source, e);
+            Err(db_error_status(&e))
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use axum::{
+    extract::{Extension, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, warn};
+
+use crate::{
+    api::{auth::ApiPrincipal, validation::{self, ValidationError}},
+    blockchain::fees::{FeeSuggestion, Urgency},
+    database::{
+        repositories::mempool::{MempoolPage, MempoolQuery},
+        resilience::is_connection_loss,
+    },
+    services::{
+        mempool::{MempoolStats, PendingTxFilter, PendingTxPage},
+        ServiceContext,
+    },
+};
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct PendingQuery {
+    /// Source kind, e.g. `public_mempool`
+    pub source: Option<String>,
+    /// Decoded method name or raw selector
+    pub method: Option<String>,
+    /// Minimum simulated profit in wei
+    pub min_profit_wei: Option<String>,
+    /// `original`, `replacement` or `replaced`
+    pub replacement: Option<String>,
+    /// Label category of the sender or recipient, e.g. `market_maker`
+    pub label: Option<String>,
+    #[serde(default)]
+    pub offset: usize,
+    /// Page size, defaults to 100
+    pub limit: Option<usize>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct StoredQuery {
+    pub sender: Option<String>,
+    /// Target contract
+    pub contract: Option<String>,
+    /// Minimum gas price or max fee per gas, in wei
+    pub min_gas_price_wei: Option<String>,
+    /// Four-byte selector such as `0xa9059cbb`
+    pub selector: Option<String>,
+    /// `pending` (default), `included`, `replaced` or `dropped`
+    pub status: Option<String>,
+    #[serde(default)]
+    pub offset: i64,
+    /// Page size, defaults to 100
+    pub limit: Option<i64>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct FeeQuery {
+    /// `low`, `normal`, `high` or `immediate`, defaults to `normal`
+    pub urgency: Option<String>,
+}
+
+/// Page through the live enriched mempool, hiding other searchers' private flow unless admin
+pub async fn list_pending(
+    principal: ApiPrincipal,
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Query(query): Query<PendingQuery>,
+) -> Result<Json<PendingTxPage>, StatusCode> {
+    let min_profit = query
+        .min_profit_wei
+        .as_deref()
+        .map(U256::from_dec_str)
+        .transpose()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let label = query
+        .label
+        .as_deref()
+        .map(str::parse)
+        .transpose()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    
+    let filter = PendingTxFilter {
+        source: query.source,
+        method: query.method,
+        min_profit,
+        replacement: query.replacement,
+        label,
+        offset: query.offset,
+        limit: query.limit.unwrap_or(100).clamp(1, 1000),
+    };
+    
+    Ok(Json(
+        services
+            .transaction_service
+            .pending_transactions(&filter, principal.owner_filter()),
+    ))
+}
+
+/// Query persisted pending transactions, hiding other searchers' private flow unless admin
+pub async fn query_stored(
+    principal: ApiPrincipal,
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Query(query): Query<StoredQuery>,
+) -> Result<Json<MempoolPage>, Response> {
+    let filter = stored_filter(query).map_err(IntoResponse::into_response)?;
+    
+    match services.mempool_repository.query(&filter, principal.owner_filter()).await {
+        Ok(page) => Ok(Json(page)),
+        Err(e) if is_connection_loss(&e) => {
+            warn!("Database unavailable for stored mempool query: {}", e);
+            Err(StatusCode::SERVICE_UNAVAILABLE.into_response())
+        }
+        Err(e) => {
+            error!("Failed to query stored mempool transactions: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+fn stored_filter(query: StoredQuery) -> Result<MempoolQuery, ValidationError> {
+    let status = query.status.unwrap_or_else(|| "pending".to_string());
+    if !matches!(status.as_str(), "pending" | "included" | "replaced" | "dropped") {
+        return Err(ValidationError::new("status", "must be pending, included, replaced or dropped"));
+    }
+    if query.offset < 0 {
+        return Err(ValidationError::new("offset", "must not be negative"));
+    }
+    
+    Ok(MempoolQuery {
+        from: query.sender.as_deref().map(|raw| validation::address("sender", raw)).transpose()?,
+        to: query.contract.as_deref().map(|raw| validation::address("contract", raw)).transpose()?,
+        min_gas_price: query
+            .min_gas_price_wei
+            .as_deref()
+            .map(|raw| {
+                U256::from_dec_str(raw)
+                    .map_err(|_| ValidationError::new("min_gas_price_wei", "must be a decimal integer"))
+            })
+            .transpose()?,
+        method_selector: query
+            .selector
+            .as_deref()
+            .map(|raw| validation::method_selector("selector", raw))
+            .transpose()?,
+        status,
+        offset: query.offset,
+        limit: query.limit.unwrap_or(100).clamp(1, 1000),
+    })
+}
+
+/// Rolling mempool statistics and congestion score
+pub async fn get_stats(
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Result<Json<MempoolStats>, StatusCode> {
+    Ok(Json(services.transaction_service.mempool_stats()))
+}
+
+/// Suggested EIP-1559 fees for the next block at the requested urgency
+pub async fn suggest_fees(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Query(query): Query<FeeQuery>,
+) -> Result<Json<FeeSuggestion>, StatusCode> {
+    let urgency = match query.urgency.as_deref() {
+        Some(urgency) => urgency.parse::<Urgency>().map_err(|_| StatusCode::BAD_REQUEST)?,
+        None => Urgency::Normal,
+    };
+    
+    services
+        .fee_estimator
+        .suggest_fees(urgency)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to suggest fees: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
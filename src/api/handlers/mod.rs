@@ -1,5 +1,39 @@
+pub mod admin;
+pub mod analytics;
+pub mod apr;
+pub mod auth;
+pub mod debug;
+pub mod decisions;
+pub mod events;
+pub mod export;
+pub mod gas;
 pub mod health;
+pub mod labels;
+pub mod mempool;
 pub mod metrics;
+pub mod opportunities;
+pub mod permit;
+pub mod portfolio;
+pub mod prices;
+pub mod profits;
+pub mod quote;
+pub mod tokens;
 pub mod blocks;
+pub mod bundles;
 pub mod transactions;
-pub mod staking; 
\ No newline at end of file
+pub mod simulation;
+pub mod staking;
+pub mod stream;
+
+use axum::http::StatusCode;
+
+use crate::database::resilience::is_connection_loss;
+
+/// Status of a failed database-backed request: 503 while the database is unreachable, 500 otherwise
+pub(crate) fn db_error_status(e: &anyhow::Error) -> StatusCode {
+    if is_connection_loss(e) {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
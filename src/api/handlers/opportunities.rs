@@ -0,0 +1,28 @@
+use axum::{
+    extract::Extension,
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+
+use crate::{
+    api::auth::ApiPrincipal,
+    services::{
+        transaction::{order_candidates, InclusionCandidate},
+        ServiceContext,
+    },
+};
+
+/// List pending inclusion candidates, limited to the caller's own unless admin
+pub async fn list_opportunities(
+    principal: ApiPrincipal,
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Result<Json<Vec<InclusionCandidate>>, StatusCode> {
+    let mut candidates = services
+        .transaction_service
+        .inclusion_candidates_owned_by(principal.owner_filter())
+        .await;
+    order_candidates(&mut candidates);
+    
+    Ok(Json(candidates))
+}
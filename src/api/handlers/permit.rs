@@ -0,0 +1,56 @@
+use axum::{
+    extract::Extension,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, warn};
+
+use crate::{
+    api::{auth::ApiPrincipal, validation::ValidationError},
+    services::{
+        permit_deposits::{PermitDeposit, PermitRejection},
+        ServiceContext,
+    },
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct PermitStakeResponse {
+    pub tx_hash: String,
+}
+
+/// Stake tokens with an EIP-2612 permit instead of a prior approval; we pay the gas
+pub async fn stake_with_permit(
+    principal: ApiPrincipal,
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Json(deposit): Json<PermitDeposit>,
+) -> Result<(StatusCode, Json<PermitStakeResponse>), Response> {
+    if !services.permit_deposit_service.enabled() {
+        return Err(StatusCode::NOT_FOUND.into_response());
+    }
+    if services.drain_controller.is_draining() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE.into_response());
+    }
+    
+    match services.permit_deposit_service.submit(&deposit).await {
+        Ok(tx_hash) => Ok((
+            StatusCode::ACCEPTED,
+            Json(PermitStakeResponse {
+                tx_hash: format!("{:?}", tx_hash),
+            }),
+        )),
+        Err(e) => match e.downcast_ref::<PermitRejection>() {
+            Some(PermitRejection::InFlight) => Err(StatusCode::CONFLICT.into_response()),
+            Some(rejection) => {
+                warn!("Rejected permit stake from {} for {:?}: {}", principal.name, deposit.owner, rejection);
+                Err(ValidationError::new("permit", rejection.to_string()).into_response())
+            }
+            None => {
+                error!("Failed to relay permit stake for {:?}: {}", deposit.owner, e);
+                Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+            }
+        },
+    }
+}
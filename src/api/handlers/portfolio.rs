@@ -0,0 +1,28 @@
+use axum::{
+    extract::{Extension, Path},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{
+    api::{handlers::db_error_status, validation},
+    services::{staking_ledger::StakingPortfolio, ServiceContext},
+};
+
+/// Positions, accrued rewards, pending withdrawals and recent APR of one staker
+pub async fn get_portfolio(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(address): Path<String>,
+) -> Result<Json<StakingPortfolio>, Response> {
+    let address = validation::address("address", &address).map_err(IntoResponse::into_response)?;
+    
+    match services.staking_ledger.portfolio(address).await {
+        Ok(portfolio) => Ok(Json(portfolio)),
+        Err(e) => {
+            error!("Failed to build staking portfolio for {:?}: {}", address, e);
+            Err(db_error_status(&e).into_response())
+        }
+    }
+}
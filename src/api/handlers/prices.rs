@@ -0,0 +1,26 @@
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::services::{prices::TokenPrice, ServiceContext};
+
+/// Aggregated USD price of a configured token, by symbol or address
+pub async fn get_price(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(token): Path<String>,
+) -> Result<Json<TokenPrice>, StatusCode> {
+    let prices = &services.price_service;
+    if !prices.enabled() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let address = prices.resolve(&token).ok_or(StatusCode::NOT_FOUND)?;
+    
+    prices.price(address).await.map(Json).map_err(|e| {
+        warn!("Failed to price {}: {}", token, e);
+        StatusCode::SERVICE_UNAVAILABLE
+    })
+}
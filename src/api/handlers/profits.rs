@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Extension, Query},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+use tracing::error;
+
+use crate::{
+    api::handlers::db_error_status,
+    services::{profits::ProfitSummary, ServiceContext},
+};
+
+/// Longest period a summary covers
+const MAX_PERIOD: Duration = Duration::from_secs(366 * 86_400);
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct ProfitSummaryQuery {
+    /// Trailing period such as `24h` or `7d`, defaults to `7d`
+    pub period: Option<String>,
+}
+
+/// Realized profit in ETH and USD over a trailing period, by strategy
+pub async fn get_summary(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Query(query): Query<ProfitSummaryQuery>,
+) -> Result<Json<ProfitSummary>, StatusCode> {
+    let period = parse_period(query.period.as_deref().unwrap_or("7d")).ok_or(StatusCode::BAD_REQUEST)?;
+    
+    services
+        .profit_service
+        .summary(period)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to summarize profits: {}", e);
+            db_error_status(&e)
+        })
+}
+
+/// A period as a number of minutes, hours, days or weeks, e.g. `30m` or `7d`
+fn parse_period(period: &str) -> Option<Duration> {
+    let (amount, unit) = period.split_at(period.find(|c: char| !c.is_ascii_digit())?);
+    let unit_seconds = match unit {
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => return None,
+    };
+    let seconds = amount.parse::<u64>().ok()?.checked_mul(unit_seconds)?;
+    
+    Some(Duration::from_secs(seconds)).filter(|period| !period.is_zero() && *period <= MAX_PERIOD)
+}
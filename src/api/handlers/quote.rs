@@ -0,0 +1,39 @@
+use axum::{
+    extract::Extension,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{
+    api::{handlers::db_error_status, validation},
+    services::{staking_ledger::StakeQuote, ServiceContext},
+};
+
+/// Trailing window realized APR is projected from
+const PROJECTION_WINDOW_DAYS: u32 = 30;
+
+#[derive(Serialize, Deserialize)]
+pub struct QuoteRequest {
+    /// Amount to stake, in ether
+    pub amount: String,
+}
+
+/// Preview a stake deposit from live accounting; nothing is booked or signed
+pub async fn quote_stake(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Json(request): Json<QuoteRequest>,
+) -> Result<Json<StakeQuote>, Response> {
+    let amount = validation::stake_amount(&services.config.services.liquid_staking, "amount", &request.amount)
+        .map_err(IntoResponse::into_response)?;
+    
+    match services.staking_ledger.quote(amount.wei(), PROJECTION_WINDOW_DAYS).await {
+        Ok(quote) => Ok(Json(quote)),
+        Err(e) => {
+            error!("Failed to quote stake of {} ETH: {}", amount, e);
+            Err(db_error_status(&e).into_response())
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    Json,
+};
+use ethers::types::H256;
+use std::sync::Arc;
+use tracing::{error, warn};
+
+use crate::services::{
+    simulation::{CalibrationReport, SimulationResult},
+    ServiceContext,
+};
+
+/// Cross-engine calibration report for the current process
+pub async fn get_calibration(
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Result<Json<CalibrationReport>, StatusCode> {
+    Ok(Json(services.simulation_service.calibration_report()))
+}
+
+/// Simulate a known transaction with the primary engine, including its execution trace if available
+pub async fn simulate_transaction(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(tx_hash): Path<H256>,
+) -> Result<Json<SimulationResult>, StatusCode> {
+    let tx = services
+        .transaction_service
+        .get_transaction(tx_hash)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch transaction {:?}: {}", tx_hash, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    
+    services
+        .simulation_service
+        .simulate_detailed(&tx)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            warn!("Failed to simulate transaction {:?}: {}", tx_hash, e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        })
+}
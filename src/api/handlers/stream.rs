@@ -0,0 +1,183 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Extension, Path,
+    },
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Response,
+    },
+    Json,
+};
+use futures::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, convert::Infallible, sync::Arc};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
+
+use crate::{
+    api::auth::{ApiPrincipal, TopicAuthError},
+    services::{events::Topic, ServiceContext},
+};
+
+/// Commands a WebSocket client may send
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe { topic: String },
+    Unsubscribe { topic: String },
+}
+
+/// Messages sent to WebSocket clients
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Subscribed { topic: Topic },
+    Unsubscribed { topic: Topic },
+    Event { topic: Topic, payload: serde_json::Value },
+    Error { code: String, topic: Option<String>, message: String },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SubscriptionError {
+    pub code: String,
+    pub message: String,
+}
+
+impl From<TopicAuthError> for SubscriptionError {
+    fn from(error: TopicAuthError) -> Self {
+        Self {
+            code: error.code().to_string(),
+            message: error.message(),
+        }
+    }
+}
+
+/// Stream one topic as server-sent events
+///
+/// Events go out in plaintext, so keys that must encrypt their events are refused.
+pub async fn stream_topic(
+    principal: ApiPrincipal,
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(topic): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, Json<SubscriptionError>)> {
+    let topic = principal.authorize_plaintext_topic(&topic).map_err(|e| {
+        let status = match e {
+            TopicAuthError::UnknownTopic(_) => StatusCode::NOT_FOUND,
+            TopicAuthError::Forbidden(_) | TopicAuthError::EncryptionRequired(_) => StatusCode::FORBIDDEN,
+        };
+        (status, Json(SubscriptionError::from(e)))
+    })?;
+    
+    let receiver = services.event_bus.subscribe();
+    let events = stream::unfold((receiver, principal), move |(mut receiver, principal)| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) if event.topic == topic && principal.may_receive(&event) => {
+                    let sse = Event::default()
+                        .event(topic.as_str())
+                        .json_data(&event.payload)
+                        .unwrap_or_default();
+                    return Some((Ok(sse), (receiver, principal)));
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("SSE subscriber {} lagged, skipped {} events", principal.name, skipped);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// Upgrade to a WebSocket that multiplexes topic subscriptions
+pub async fn subscribe(
+    ws: WebSocketUpgrade,
+    principal: ApiPrincipal,
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Response {
+    ws.on_upgrade(move |socket| handle_socket(socket, principal, services))
+}
+
+async fn handle_socket(mut socket: WebSocket, principal: ApiPrincipal, services: Arc<ServiceContext>) {
+    let mut events = services.event_bus.subscribe();
+    let mut topics: HashSet<Topic> = HashSet::new();
+    
+    loop {
+        let reply = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => handle_command(&principal, &mut topics, &text),
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+            event = events.recv() => match event {
+                Ok(event) if topics.contains(&event.topic) && principal.may_receive(&event) => {
+                    ServerMessage::Event { topic: event.topic, payload: event.payload }
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("WebSocket subscriber {} lagged, skipped {} events", principal.name, skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+        };
+        
+        let text = match serde_json::to_string(&reply) {
+            Ok(text) => text,
+            Err(e) => {
+                warn!("Failed to serialize WebSocket message: {}", e);
+                continue;
+            }
+        };
+        
+        if socket.send(Message::Text(text)).await.is_err() {
+            break;
+        }
+    }
+    
+    debug!("WebSocket subscriber {} disconnected", principal.name);
+}
+
+/// Apply a client command, authorizing subscriptions against the caller's API key
+fn handle_command(principal: &ApiPrincipal, topics: &mut HashSet<Topic>, text: &str) -> ServerMessage {
+    let command: ClientMessage = match serde_json::from_str(text) {
+        Ok(command) => command,
+        Err(e) => {
+            return ServerMessage::Error {
+                code: "invalid_message".to_string(),
+                topic: None,
+                message: e.to_string(),
+            }
+        }
+    };
+    
+    match command {
+        // Events go out in plaintext here, encrypted subscriptions are served on `/ws`
+        ClientMessage::Subscribe { topic: name } => match principal.authorize_plaintext_topic(&name) {
+            Ok(topic) => {
+                topics.insert(topic);
+                ServerMessage::Subscribed { topic }
+            }
+            Err(e) => ServerMessage::Error {
+                code: e.code().to_string(),
+                topic: Some(name),
+                message: e.message(),
+            },
+        },
+        ClientMessage::Unsubscribe { topic: name } => match name.parse::<Topic>() {
+            Ok(topic) => {
+                topics.remove(&topic);
+                ServerMessage::Unsubscribed { topic }
+            }
+            Err(e) => ServerMessage::Error {
+                code: "unknown_topic".to_string(),
+                topic: Some(name),
+                message: e.to_string(),
+            },
+        },
+    }
+}
@@ -0,0 +1,25 @@
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    Json,
+};
+use ethers::types::Address;
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::{models::TokenMetadata, services::ServiceContext};
+
+/// ERC-20 metadata of a token, read from chain on first use
+///
+/// Served from the readonly tier, so tokens read here are cached but not stored in Postgres.
+pub async fn get_token(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(address): Path<String>,
+) -> Result<Json<TokenMetadata>, StatusCode> {
+    let address: Address = address.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    
+    services.token_repository.peek(address).await.map(Json).map_err(|e| {
+        debug!("Failed to read token {:?}: {}", address, e);
+        StatusCode::NOT_FOUND
+    })
+}
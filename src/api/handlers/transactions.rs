@@ -0,0 +1,138 @@
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    Json,
+};
+use ethers::{
+    types::{Bytes, Transaction, TransactionReceipt, H256},
+    utils::rlp,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{debug, error, warn};
+
+use crate::{
+    api::auth::ApiPrincipal,
+    blockchain::monitor,
+    services::{
+        transaction::{TxPrivacy, TxSource, TxStatusSummary},
+        ServiceContext,
+    },
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct SubmitTransactionRequest {
+    /// Signed raw transaction, hex-encoded
+    pub raw_tx: Bytes,
+    /// Route to builders, the public mempool unless set
+    #[serde(default)]
+    pub privacy: TxPrivacy,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SubmitTransactionResponse {
+    pub tx_hash: H256,
+    pub privacy: TxPrivacy,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TransactionStatusRequest {
+    pub tx_hashes: Vec<H256>,
+}
+
+/// Look up a transaction by hash
+pub async fn get_transaction(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(tx_hash): Path<H256>,
+) -> Result<Json<Transaction>, StatusCode> {
+    services
+        .transaction_service
+        .get_transaction(tx_hash)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch transaction {:?}: {}", tx_hash, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Receipt of a mined transaction
+pub async fn get_transaction_receipt(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Path(tx_hash): Path<H256>,
+) -> Result<Json<TransactionReceipt>, StatusCode> {
+    services
+        .blockchain_client
+        .get_transaction_receipt(tx_hash)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch receipt for {:?}: {}", tx_hash, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Status and receipt summaries for a batch of transactions, in request order
+pub async fn get_transaction_statuses(
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Json(request): Json<TransactionStatusRequest>,
+) -> Result<Json<Vec<TxStatusSummary>>, StatusCode> {
+    if request.tx_hashes.is_empty() || request.tx_hashes.len() > services.config.api.max_status_hashes {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    
+    Ok(Json(services.transaction_service.transaction_statuses(&request.tx_hashes).await))
+}
+
+/// Submit a signed transaction to the public mempool, Flashbots Protect or MEV-Share
+pub async fn submit_transaction(
+    principal: ApiPrincipal,
+    Extension(services): Extension<Arc<ServiceContext>>,
+    Json(request): Json<SubmitTransactionRequest>,
+) -> Result<(StatusCode, Json<SubmitTransactionResponse>), StatusCode> {
+    // Nothing leaves for the network in a dry run
+    if services.drain_controller.is_draining() || services.transaction_service.is_dry_run() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+    
+    // Only public submissions are simulated and can land through us, so only they are scored
+    let privacy = request.privacy;
+    if privacy == TxPrivacy::Public {
+        let (queued, _) = services.simulation_service.queue_depth();
+        if let Err(e) = services.reputation_service.admit(&principal.name, queued) {
+            warn!("Rejected transaction from {}: {}", principal.name, e);
+            return Err(StatusCode::TOO_MANY_REQUESTS);
+        }
+    }
+    
+    let tx_hash = services
+        .transaction_service
+        .submit_transaction(request.raw_tx.to_vec(), privacy)
+        .await
+        .map_err(|e| {
+            warn!("Transaction from {} via {} was rejected: {}", principal.name, privacy.as_str(), e);
+            StatusCode::BAD_GATEWAY
+        })?;
+    
+    // A public submission is bound for the mempool anyway, ingest it now instead of when the node
+    // gossips it back. Protect and MEV-Share submissions stay out of our own strategies.
+    if privacy == TxPrivacy::Public {
+        services.reputation_service.record_bundle_submitted(&principal.name);
+        match rlp::decode::<Transaction>(&request.raw_tx) {
+            Ok(tx) => {
+                let source = TxSource::Searcher(principal.name.clone());
+                tokio::spawn(async move {
+                    let ingested = monitor::ingest_pending_transaction(&services, tx, source).await;
+                    if let Err(e) = ingested {
+                        debug!("Error ingesting submitted transaction {:?}: {}", tx_hash, e);
+                    }
+                });
+            }
+            Err(e) => debug!("Submitted transaction {:?} didn't decode for ingestion: {}", tx_hash, e),
+        }
+    }
+    
+    Ok((StatusCode::ACCEPTED, Json(SubmitTransactionResponse { tx_hash, privacy })))
+}
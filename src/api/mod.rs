@@ -1,36 +1,44 @@
 use anyhow::{Context, Result};
 use axum::{
     extract::Extension,
-    routing::{get, post},
+    routing::{delete, get, post},
     Router,
 };
-use std::sync::Arc;
-use std::time::Duration;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{sync::oneshot, task::JoinHandle};
 use tower::ServiceBuilder;
 use tower_http::{
     cors::{Any, CorsLayer},
     trace::TraceLayer,
 };
-use tracing::info;
+use tracing::{error, info};
 
 use crate::services::ServiceContext;
 
-mod handlers;
+pub mod auth;
+pub mod encryption;
+pub mod handlers;
 mod middleware;
-mod models;
-mod websocket;
+pub mod models;
+mod rate_limit;
+mod validation;
+pub mod websocket;
 
 /// API server handle for shutdown
 pub struct ApiServer {
-    server: axum::Server<hyper::server::conn::AddrIncoming, axum::routing::IntoMakeService<Router>>,
+    shutdown_sender: oneshot::Sender<()>,
+    task: JoinHandle<()>,
 }
 
 impl ApiServer {
-    /// Gracefully shutdown the API server
+    /// Gracefully shutdown the API server, letting in-flight requests complete
     pub async fn shutdown(self) -> Result<()> {
         info!("Shutting down API server");
-        // This would be implemented with a graceful shutdown mechanism
-        // In a real application, we would use the GracefulShutdown trait
+        
+        let _ = self.shutdown_sender.send(());
+        self.task.await.context("API server task panicked")?;
+        
+        info!("API server shut down successfully");
         Ok(())
     }
 }
@@ -41,60 +49,175 @@ pub async fn start_server(
     services: Arc<ServiceContext>,
 ) -> Result<ApiServer> {
     // Create router
-    let router = create_router(services);
+    let router = create_router(services)?;
     
     // Create server
     let addr = bind_address.parse()
         .context(format!("Failed to parse bind address: {}", bind_address))?;
     
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    
     let server = axum::Server::bind(&addr)
-        .serve(router.into_make_service());
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async {
+            shutdown_rx.await.ok();
+        });
+    
+    let task = tokio::spawn(async move {
+        if let Err(e) = server.await {
+            error!("API server error: {}", e);
+        }
+    });
     
     info!("API server listening on {}", bind_address);
     
-    Ok(ApiServer { server })
+    Ok(ApiServer {
+        shutdown_sender: shutdown_tx,
+        task,
+    })
 }
 
 /// Create the API router
-fn create_router(services: Arc<ServiceContext>) -> Router {
+fn create_router(services: Arc<ServiceContext>) -> Result<Router> {
     // CORS configuration
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
         .allow_headers(Any);
     
+    let api_keys = Arc::new(auth::ApiKeys::from_config(&services.config.api)?);
+    let rate_limiter = Arc::new(rate_limit::RateLimiter::new(
+        services.redis.clone(),
+        &services.config.api.rate_limit,
+    )?);
+    
     // Middleware stack
     let middleware = ServiceBuilder::new()
         .layer(TraceLayer::new_for_http())
         .layer(cors)
         .layer(Extension(services))
+        .layer(Extension(api_keys))
+        .layer(Extension(rate_limiter))
+        .layer(axum::middleware::from_fn(rate_limit::limit))
         .timeout(Duration::from_secs(30));
     
-    // Main router
-    Router::new()
-        // API endpoints
-        .route("/api/health", get(handlers::health::health_check))
-        .route("/api/metrics", get(handlers::metrics::metrics))
-        
+    // Endpoints that change nothing, open to every authenticated caller
+    let readonly = Router::new()
         // Block building endpoints
         .route("/api/blocks/latest", get(handlers::blocks::get_latest_block))
         .route("/api/blocks/:block_number", get(handlers::blocks::get_block_by_number))
+        // The segment is a slot here, it shares the name because sibling routes must
+        .route("/api/blocks/:block_number/decision", get(handlers::decisions::get_build_decision))
         .route("/api/blocks/simulate", post(handlers::blocks::simulate_block))
         
+        // Bundle endpoints
+        .route("/api/bundles/sealing-keys", get(handlers::bundles::get_sealing_keys))
+        .route("/api/bundles/simulate", post(handlers::bundles::simulate_bundle))
+        
+        // Simulation endpoints
+        .route("/api/simulation/calibration", get(handlers::simulation::get_calibration))
+        .route("/api/simulation/tx/:tx_hash", get(handlers::simulation::simulate_transaction))
+        
         // Transaction endpoints
-        .route("/api/transactions", post(handlers::transactions::submit_transaction))
+        .route("/api/transactions/status", post(handlers::transactions::get_transaction_statuses))
         .route("/api/transactions/:tx_hash", get(handlers::transactions::get_transaction))
         .route("/api/transactions/:tx_hash/receipt", get(handlers::transactions::get_transaction_receipt))
         
+        // Opportunity endpoints
+        .route("/api/opportunities", get(handlers::opportunities::list_opportunities))
+        .route("/api/mempool", get(handlers::mempool::query_stored))
+        .route("/api/mempool/pending", get(handlers::mempool::list_pending))
+        .route("/api/mempool/stats", get(handlers::mempool::get_stats))
+        .route("/api/mempool/fees", get(handlers::mempool::suggest_fees))
+        .route("/api/gas/latest", get(handlers::gas::get_latest))
+        .route("/api/events", get(handlers::events::list_events))
+        
+        // Price endpoints
+        .route("/api/prices/:token", get(handlers::prices::get_price))
+        .route("/api/tokens/:address", get(handlers::tokens::get_token))
+        .route("/api/labels/:address", get(handlers::labels::get_label))
+        
+        // Analytics endpoints
+        .route("/api/analytics/query/:name", get(handlers::analytics::dashboard_query))
+        .route("/api/analytics/market-bids", get(handlers::analytics::get_market_bids))
+        .route("/api/analytics/reconciliation", get(handlers::analytics::get_reconciliation))
+        .route("/api/analytics/builder-efficiency", get(handlers::analytics::get_builder_efficiency))
+        .route("/api/analytics/gas-savings", get(handlers::analytics::get_gas_savings))
+        .route("/api/profits/summary", get(handlers::profits::get_summary))
+        
+        // Export endpoints
+        .route("/api/export/blocks", get(handlers::export::export_blocks))
+        .route("/api/export/manifest", get(handlers::export::get_manifest))
+        
         // Liquid staking endpoints
         .route("/api/staking/validators", get(handlers::staking::get_validators))
-        .route("/api/staking/stake", post(handlers::staking::stake))
-        .route("/api/staking/unstake", post(handlers::staking::unstake))
         .route("/api/staking/rewards", get(handlers::staking::get_rewards))
+        .route("/api/staking/apr", get(handlers::apr::get_apr))
+        .route("/api/staking/portfolio/:address", get(handlers::portfolio::get_portfolio))
+        .route("/api/staking/quote", post(handlers::quote::quote_stake))
+        
+        // Streaming endpoints, authorized per topic
+        .route("/api/stream/:topic", get(handlers::stream::stream_topic))
         
         // WebSocket endpoints
         .route("/ws", get(websocket::handler))
+        .route("/ws/subscribe", get(handlers::stream::subscribe))
+        .route_layer(axum::middleware::from_fn(auth::require_readonly));
+    
+    // Endpoints that submit work
+    let searcher = Router::new()
+        .route("/api/bundles/sealed", post(handlers::bundles::submit_sealed_bundle))
+        .route("/api/transactions", post(handlers::transactions::submit_transaction))
+        .route("/api/staking/stake", post(handlers::staking::stake))
+        .route("/api/staking/unstake", post(handlers::staking::unstake))
+        .route("/api/staking/stake-with-permit", post(handlers::permit::stake_with_permit))
+        .route_layer(axum::middleware::from_fn(auth::require_searcher));
+    
+    // Runtime controls and costly diagnostics
+    let operator = Router::new()
+        // Admin endpoints
+        .route("/api/admin/drain", post(handlers::admin::drain))
+        .route("/api/admin/relays", get(handlers::admin::list_relays))
+        .route("/api/admin/commission", get(handlers::admin::list_commission))
+        .route("/api/admin/subsystems", get(handlers::admin::list_subsystems))
+        .route("/api/admin/subsystems/:name/pause", post(handlers::admin::pause_subsystem))
+        .route("/api/admin/subsystems/:name/resume", post(handlers::admin::resume_subsystem))
+        .route("/api/admin/risk", get(handlers::admin::get_risk))
+        .route("/api/admin/risk/halt", post(handlers::admin::halt_submission))
+        .route("/api/admin/risk/resume", post(handlers::admin::resume_submission))
+        .route("/api/admin/webhooks/:name/deliveries", get(handlers::admin::list_webhook_deliveries))
+        .route("/api/admin/labels", get(handlers::labels::list_labels).post(handlers::labels::set_label))
+        .route("/api/admin/labels/import/:source", post(handlers::labels::import_labels))
+        .route("/api/admin/labels/:address", delete(handlers::labels::remove_label))
+        .route("/api/admin/strategies", get(handlers::admin::list_strategies))
+        .route("/api/admin/strategies/:name/enable", post(handlers::admin::enable_strategy))
+        .route("/api/admin/strategies/:name/disable", post(handlers::admin::disable_strategy))
+        
+        // Debug endpoints
+        .route("/api/debug/replay-slot", post(handlers::debug::replay_slot))
+        .route("/api/analytics/fee-backtest", post(handlers::analytics::fee_backtest))
+        .route_layer(axum::middleware::from_fn(auth::require_operator));
+    
+    // Every searcher's data
+    let admin = Router::new()
+        // KPIs break profit down by source, which names each searcher
+        .route("/api/analytics/kpis", get(handlers::analytics::get_kpis))
+        .route_layer(axum::middleware::from_fn(auth::require_admin));
+    
+    // Main router
+    let router = Router::new()
+        // Unauthenticated endpoints
+        .route("/api/health", get(handlers::health::health_check))
+        .route("/api/ready", get(handlers::health::readiness))
+        .route("/api/metrics", get(handlers::metrics::metrics))
+        .route("/api/auth/token", post(handlers::auth::issue_token))
+        .merge(readonly)
+        .merge(searcher)
+        .merge(operator)
+        .merge(admin)
         
         // Apply middleware
-        .layer(middleware)
-} 
\ No newline at end of file
+        .layer(middleware);
+    
+    Ok(router)
+}
\ No newline at end of file
@@ -0,0 +1,143 @@
+use ethers::{
+    types::{Address, U256},
+    utils::to_checksum,
+};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+// Wire formats shared by every API response
+//
+// Addresses are EIP-55 checksummed and wei values are decimal strings, so clients never parse
+// ethers' hex quantities or lose precision in floats. Hashes are already serialized by ethers
+// as 0x-prefixed, fixed-length lowercase hex and are left as they are. Deserializers also
+// accept the hex quantities written before, so stored and in-flight payloads keep decoding.
+
+/// A wei amount, serialized as a decimal string
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Wei(pub U256);
+
+impl From<U256> for Wei {
+    fn from(value: U256) -> Self {
+        Self(value)
+    }
+}
+
+impl fmt::Display for Wei {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Serialize for Wei {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Wei {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Text(String),
+            Number(u64),
+        }
+        
+        match Raw::deserialize(deserializer)? {
+            Raw::Number(value) => Ok(Self(U256::from(value))),
+            Raw::Text(text) => match text.strip_prefix("0x") {
+                Some(hex) => U256::from_str_radix(hex, 16).map(Self).map_err(D::Error::custom),
+                None => U256::from_dec_str(&text).map(Self).map_err(D::Error::custom),
+            },
+        }
+    }
+}
+
+/// An address, serialized with its EIP-55 checksum
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChecksumAddress(pub Address);
+
+impl From<Address> for ChecksumAddress {
+    fn from(address: Address) -> Self {
+        Self(address)
+    }
+}
+
+impl Serialize for ChecksumAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_checksum(&self.0, None))
+    }
+}
+
+impl<'de> Deserialize<'de> for ChecksumAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Address::deserialize(deserializer).map(Self)
+    }
+}
+
+/// `#[serde(with = "crate::api::models::wei")]` for `U256` wei fields
+pub mod wei {
+    use super::*;
+    
+    pub fn serialize<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+        Wei(*value).serialize(serializer)
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+        Wei::deserialize(deserializer).map(|wei| wei.0)
+    }
+}
+
+/// `#[serde(with = "crate::api::models::wei_opt")]` for `Option<U256>` wei fields
+pub mod wei_opt {
+    use super::*;
+    
+    pub fn serialize<S: Serializer>(value: &Option<U256>, serializer: S) -> Result<S::Ok, S::Error> {
+        value.map(Wei).serialize(serializer)
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<U256>, D::Error> {
+        Option::<Wei>::deserialize(deserializer).map(|wei| wei.map(|wei| wei.0))
+    }
+}
+
+/// `#[serde(with = "crate::api::models::wei_map")]` for maps of wei values
+pub mod wei_map {
+    use super::*;
+    use std::collections::HashMap;
+    
+    pub fn serialize<S: Serializer>(value: &HashMap<String, U256>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(value.iter().map(|(key, value)| (key, Wei(*value))))
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<String, U256>, D::Error> {
+        let map = HashMap::<String, Wei>::deserialize(deserializer)?;
+        Ok(map.into_iter().map(|(key, value)| (key, value.0)).collect())
+    }
+}
+
+/// `#[serde(with = "crate::api::models::checksum")]` for `Address` fields
+pub mod checksum {
+    use super::*;
+    
+    pub fn serialize<S: Serializer>(value: &Address, serializer: S) -> Result<S::Ok, S::Error> {
+        ChecksumAddress(*value).serialize(serializer)
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+        Address::deserialize(deserializer)
+    }
+}
+
+/// `#[serde(with = "crate::api::models::checksum_opt")]` for `Option<Address>` fields
+pub mod checksum_opt {
+    use super::*;
+    
+    pub fn serialize<S: Serializer>(value: &Option<Address>, serializer: S) -> Result<S::Ok, S::Error> {
+        value.map(ChecksumAddress).serialize(serializer)
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Address>, D::Error> {
+        Option::<Address>::deserialize(deserializer)
+    }
+}
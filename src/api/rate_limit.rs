@@ -0,0 +1,246 @@
+use anyhow::{Context, Result};
+use axum::{
+    extract::{ConnectInfo, Extension},
+    http::{header, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use parking_lot::Mutex;
+use redis::Script;
+use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Instant};
+use tracing::{debug, warn};
+
+use crate::{
+    api::auth::ApiPrincipal,
+    config::{RateLimitBucket, RateLimitConfig},
+    database::RedisPool,
+};
+
+/// Probes and scrapers, never limited
+const EXEMPT_PATHS: &[&str] = &["/api/health", "/api/ready", "/api/metrics"];
+
+/// Header a trusted proxy puts the original client IP in
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Clients limited in process while Redis is down
+const MAX_LOCAL_BUCKETS: usize = 10_000;
+
+/// Refill then take one token, returning whether it was taken and the wait in ms otherwise
+///
+/// Runs atomically in Redis, so every API instance draws from the same bucket.
+const TOKEN_BUCKET_SCRIPT: &str = r#"
+local rate = tonumber(ARGV[1])
+local burst = tonumber(ARGV[2])
+local now = tonumber(ARGV[3])
+local state = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+local tokens = tonumber(state[1]) or burst
+local ts = tonumber(state[2]) or now
+tokens = math.min(burst, tokens + math.max(0, now - ts) / 1000 * rate)
+local allowed = 0
+local retry_ms = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+    allowed = 1
+else
+    retry_ms = math.ceil((1 - tokens) / rate * 1000)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', now)
+redis.call('PEXPIRE', KEYS[1], math.ceil(burst / rate * 1000) + 1000)
+return {allowed, retry_ms}
+"#;
+
+/// A bucket override for matching requests
+struct RouteBucket {
+    path_prefix: String,
+    method: Option<Method>,
+    bucket: RateLimitBucket,
+}
+
+/// Token buckets kept in process, limiting each instance on its own while Redis is down
+#[derive(Default)]
+struct LocalBuckets {
+    /// Tokens left and when they were counted, by bucket key
+    buckets: Mutex<HashMap<String, (f64, Instant)>>,
+}
+
+impl LocalBuckets {
+    /// Take a token, returning the milliseconds to wait if there was none
+    ///
+    /// Once `MAX_LOCAL_BUCKETS` clients are limited, clients whose buckets have refilled are
+    /// forgotten; a new client finding none to forget is refused.
+    fn take(&self, key: String, bucket: &RateLimitBucket) -> Option<u64> {
+        let now = Instant::now();
+        let rate = bucket.rate_per_second.max(f64::MIN_POSITIVE);
+        let burst = bucket.burst as f64;
+        let refilled = |tokens: f64, counted_at: Instant| {
+            burst.min(tokens + now.duration_since(counted_at).as_secs_f64() * rate)
+        };
+        
+        let mut buckets = self.buckets.lock();
+        if buckets.len() >= MAX_LOCAL_BUCKETS && !buckets.contains_key(&key) {
+            buckets.retain(|_, (tokens, counted_at)| refilled(*tokens, *counted_at) < burst);
+            if buckets.len() >= MAX_LOCAL_BUCKETS {
+                return Some((1000.0 / rate).ceil() as u64);
+            }
+        }
+        
+        let (tokens, counted_at) = buckets.entry(key).or_insert((burst, now));
+        *tokens = refilled(*tokens, *counted_at);
+        *counted_at = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            None
+        } else {
+            Some(((1.0 - *tokens) / rate * 1000.0).ceil() as u64)
+        }
+    }
+}
+
+/// Redis-backed token bucket limiter applied to every API request
+///
+/// Authenticated callers are limited per API key name, anyone else per client IP. When Redis
+/// is unreachable, or degraded, each instance limits against buckets of its own instead, so a
+/// cache outage neither turns into an API outage nor lifts the limits.
+pub struct RateLimiter {
+    redis: RedisPool,
+    script: Script,
+    /// Fallback buckets used while Redis is down
+    local: LocalBuckets,
+    enabled: bool,
+    default: RateLimitBucket,
+    /// Overrides, longest prefix first
+    routes: Vec<RouteBucket>,
+    trust_forwarded_for: bool,
+}
+
+impl RateLimiter {
+    pub fn new(redis: RedisPool, config: &RateLimitConfig) -> Result<Self> {
+        let mut routes = config
+            .routes
+            .iter()
+            .map(|route| {
+                let method = route
+                    .method
+                    .as_deref()
+                    .map(|method| method.to_ascii_uppercase().parse::<Method>())
+                    .transpose()
+                    .with_context(|| format!("Invalid rate limit method for {}", route.path_prefix))?;
+                Ok(RouteBucket {
+                    path_prefix: route.path_prefix.clone(),
+                    method,
+                    bucket: route.bucket.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        routes.sort_by(|a, b| b.path_prefix.len().cmp(&a.path_prefix.len()));
+        
+        Ok(Self {
+            redis,
+            script: Script::new(TOKEN_BUCKET_SCRIPT),
+            local: LocalBuckets::default(),
+            enabled: config.enabled,
+            default: config.default.clone(),
+            routes,
+            trust_forwarded_for: config.trust_forwarded_for,
+        })
+    }
+    
+    /// Bucket for a request and the name it is stored under
+    fn bucket_for(&self, method: &Method, path: &str) -> (&str, &RateLimitBucket) {
+        self.routes
+            .iter()
+            .find(|route| {
+                path.starts_with(&route.path_prefix) && route.method.as_ref().map_or(true, |m| m == method)
+            })
+            .map(|route| (route.path_prefix.as_str(), &route.bucket))
+            .unwrap_or(("default", &self.default))
+    }
+    
+    /// Take a token, returning the milliseconds to wait if there was none
+    async fn take(&self, bucket_name: &str, bucket: &RateLimitBucket, client: &str) -> Option<u64> {
+        let key = format!("ratelimit:{}:{}", bucket_name, client);
+        
+        // Don't add a Redis round trip to every request while it is known to be down
+        if self.redis.is_degraded() {
+            metrics::counter!("api_rate_limit_local_total", 1);
+            return self.local.take(key, bucket);
+        }
+        
+        match self.take_shared(&key, bucket).await {
+            Ok(retry_ms) => retry_ms,
+            Err(e) => {
+                warn!("{}, limiting {} in process", e, client);
+                metrics::counter!("api_rate_limiter_errors_total", 1);
+                self.local.take(key, bucket)
+            }
+        }
+    }
+    
+    /// Take a token from the bucket shared by every instance in Redis
+    async fn take_shared(&self, key: &str, bucket: &RateLimitBucket) -> Result<Option<u64>> {
+        let mut redis = self.redis.clone();
+        let (allowed, retry_ms): (i64, i64) = self
+            .script
+            .key(key)
+            .arg(bucket.rate_per_second)
+            .arg(bucket.burst)
+            .arg(chrono::Utc::now().timestamp_millis())
+            .invoke_async(&mut redis)
+            .await
+            .context("Rate limit check failed")?;
+        
+        Ok((allowed == 0).then(|| retry_ms.max(0) as u64))
+    }
+    
+    fn client_ip<B>(&self, request: &Request<B>, peer: SocketAddr) -> String {
+        if self.trust_forwarded_for {
+            let forwarded = request
+                .headers()
+                .get(FORWARDED_FOR_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.split(',').next())
+                .map(str::trim)
+                .filter(|ip| !ip.is_empty());
+            if let Some(ip) = forwarded {
+                return ip.to_string();
+            }
+        }
+        peer.ip().to_string()
+    }
+}
+
+/// Middleware rejecting requests over their client's limit with 429 and `Retry-After`
+pub async fn limit<B>(
+    Extension(limiter): Extension<Arc<RateLimiter>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    principal: Option<ApiPrincipal>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let path = request.uri().path();
+    if !limiter.enabled || EXEMPT_PATHS.contains(&path) {
+        return next.run(request).await;
+    }
+    
+    let client = match &principal {
+        Some(principal) => format!("key:{}", principal.name),
+        None => format!("ip:{}", limiter.client_ip(&request, peer)),
+    };
+    let (bucket_name, bucket) = limiter.bucket_for(request.method(), path);
+    
+    if let Some(retry_ms) = limiter.take(bucket_name, bucket, &client).await {
+        debug!("Rate limited {} on {}", client, bucket_name);
+        metrics::counter!("api_rate_limited_total", 1, "bucket" => bucket_name.to_string());
+        
+        let retry_after = ((retry_ms + 999) / 1000).max(1);
+        let mut response = StatusCode::TOO_MANY_REQUESTS.into_response();
+        response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        return response;
+    }
+    
+    // Route authorization reuses the principal instead of authenticating again
+    if let Some(principal) = principal {
+        request.extensions_mut().insert(principal);
+    }
+    next.run(request).await
+}
@@ -0,0 +1,108 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use ethers::{types::Address, utils::to_checksum};
+use serde::Serialize;
+
+use crate::{config::LiquidStakingConfig, utils::units::EthAmount};
+
+/// A request field that failed validation, returned to the caller as a 400
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(field: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            message: message.into(),
+        }
+    }
+}
+
+impl IntoResponse for ValidationError {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_REQUEST, Json(self)).into_response()
+    }
+}
+
+/// Parse an ether amount and check it lies within `min..=max`
+pub fn amount(
+    field: &'static str,
+    raw: &str,
+    min: Option<EthAmount>,
+    max: Option<EthAmount>,
+) -> Result<EthAmount, ValidationError> {
+    let amount: EthAmount = raw.parse().map_err(|e| ValidationError::new(field, format!("{}", e)))?;
+    
+    if let Some(min) = min.filter(|min| amount < *min) {
+        return Err(ValidationError::new(field, format!("must be at least {} ETH", min)));
+    }
+    if let Some(max) = max.filter(|max| amount > *max) {
+        return Err(ValidationError::new(field, format!("must be at most {} ETH", max)));
+    }
+    
+    Ok(amount)
+}
+
+/// Parse a stake or unstake amount against the configured staking bounds
+pub fn stake_amount(config: &LiquidStakingConfig, field: &'static str, raw: &str) -> Result<EthAmount, ValidationError> {
+    let amount = amount(field, raw, Some(config.min_stake_amount), config.max_stake_amount)?;
+    if amount.wei().is_zero() {
+        return Err(ValidationError::new(field, "must be greater than zero"));
+    }
+    Ok(amount)
+}
+
+/// Parse a hex address, enforcing the EIP-55 checksum when it is mixed case
+///
+/// All-lowercase addresses carry no checksum and are accepted as is.
+pub fn address(field: &'static str, raw: &str) -> Result<Address, ValidationError> {
+    let hex = raw
+        .strip_prefix("0x")
+        .ok_or_else(|| ValidationError::new(field, "address must start with 0x"))?;
+    if hex.len() != 40 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(ValidationError::new(field, "address must be 20 bytes of hex"));
+    }
+    
+    let address: Address = raw
+        .parse()
+        .map_err(|_| ValidationError::new(field, "address is not valid hex"))?;
+    
+    let has_upper = hex.bytes().any(|b| b.is_ascii_uppercase());
+    if has_upper && to_checksum(&address, None) != raw {
+        return Err(ValidationError::new(field, "address checksum does not match"));
+    }
+    
+    Ok(address)
+}
+
+/// Parse a lookback window given in days, e.g. `30d`, capped at `max_days`
+pub fn window_days(field: &'static str, raw: &str, max_days: u32) -> Result<u32, ValidationError> {
+    let days: u32 = raw
+        .strip_suffix('d')
+        .and_then(|days| days.parse().ok())
+        .ok_or_else(|| ValidationError::new(field, "window must be a number of days such as 30d"))?;
+    
+    if days == 0 || days > max_days {
+        return Err(ValidationError::new(field, format!("window must be between 1d and {}d", max_days)));
+    }
+    
+    Ok(days)
+}
+
+/// Parse a four-byte function selector, e.g. `0xa9059cbb`, returned lowercase
+pub fn method_selector(field: &'static str, raw: &str) -> Result<String, ValidationError> {
+    let hex = raw
+        .strip_prefix("0x")
+        .ok_or_else(|| ValidationError::new(field, "selector must start with 0x"))?;
+    if hex.len() != 8 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(ValidationError::new(field, "selector must be 4 bytes of hex"));
+    }
+    
+    Ok(raw.to_ascii_lowercase())
+}
@@ -0,0 +1,368 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Extension,
+    },
+    response::Response,
+};
+use ethers::types::{Address, U256};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::{
+    broadcast::error::RecvError,
+    mpsc::{self, error::TrySendError},
+};
+use tracing::{debug, warn};
+
+use crate::{
+    api::{auth::ApiPrincipal, encryption::EventCipher, models},
+    services::{events::Topic, labels::LabelCategory, ServiceContext},
+};
+
+/// Messages queued per connection before events are dropped for it
+const SEND_QUEUE_CAPACITY: usize = 256;
+
+/// Channel name a topic is subscribed to under
+pub fn channel_name(topic: Topic) -> &'static str {
+    match topic {
+        Topic::Blocks => "new_blocks",
+        Topic::Opportunities => "pending_profitable_txs",
+        Topic::Bundles => "bundle_status",
+        Topic::GasPrice => "gas_price",
+    }
+}
+
+pub fn parse_channel(name: &str) -> Option<Topic> {
+    match name {
+        "new_blocks" => Some(Topic::Blocks),
+        "pending_profitable_txs" => Some(Topic::Opportunities),
+        "bundle_status" => Some(Topic::Bundles),
+        "gas_price" => Some(Topic::GasPrice),
+        _ => None,
+    }
+}
+
+/// A subscription request with its optional filters
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SubscribeRequest {
+    pub topic: String,
+    /// Minimum transaction profit or bundle value in wei
+    pub min_profit_wei: Option<String>,
+    /// Only transactions sent to this contract
+    pub contract: Option<String>,
+    /// Only bundle updates with this status, e.g. `landed`
+    pub status: Option<String>,
+    /// Only transactions from or to an entity of this label category, e.g. `exploiter`
+    pub label: Option<String>,
+    /// Hex X25519 public key to encrypt this subscription's events to, see `EventCipher`
+    pub public_key: Option<String>,
+}
+
+/// Commands a client may send
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe(SubscribeRequest),
+    Unsubscribe { topic: String },
+}
+
+/// Messages sent to clients
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Subscribed {
+        topic: String,
+        /// Hex X25519 public key of the server's side of an encrypted subscription
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        public_key: Option<String>,
+    },
+    Unsubscribed { topic: String },
+    Event { topic: String, data: serde_json::Value },
+    /// An event of an encrypted subscription, its payload sealed with the subscription's key
+    EncryptedEvent { topic: String, nonce: String, ciphertext: String },
+    /// Events skipped because the client read too slowly
+    Lagged { dropped: u64 },
+    Error { code: String, topic: Option<String>, message: String },
+}
+
+impl ServerMessage {
+    fn error(code: &str, topic: Option<String>, message: impl Into<String>) -> Self {
+        Self::Error {
+            code: code.to_string(),
+            topic,
+            message: message.into(),
+        }
+    }
+}
+
+/// Server-side filter of one subscription
+#[derive(Debug, Default)]
+struct EventFilter {
+    min_profit: Option<U256>,
+    contract: Option<Address>,
+    status: Option<String>,
+    label: Option<LabelCategory>,
+}
+
+impl EventFilter {
+    fn parse(topic: Topic, request: &SubscribeRequest) -> Result<Self, String> {
+        let applies = |filter: &str, topics: &[Topic]| {
+            if topics.contains(&topic) {
+                Ok(())
+            } else {
+                Err(format!("{} does not apply to {}", filter, channel_name(topic)))
+            }
+        };
+        
+        let mut filter = Self::default();
+        if let Some(raw) = &request.min_profit_wei {
+            applies("min_profit_wei", &[Topic::Opportunities, Topic::Bundles])?;
+            let min_profit = U256::from_dec_str(raw).map_err(|_| "min_profit_wei must be a decimal integer")?;
+            filter.min_profit = Some(min_profit);
+        }
+        if let Some(raw) = &request.contract {
+            applies("contract", &[Topic::Opportunities])?;
+            filter.contract = Some(raw.parse().map_err(|_| "contract must be an address")?);
+        }
+        if let Some(status) = &request.status {
+            applies("status", &[Topic::Bundles])?;
+            filter.status = Some(status.clone());
+        }
+        if let Some(raw) = &request.label {
+            applies("label", &[Topic::Opportunities])?;
+            filter.label = Some(raw.parse().map_err(|_| format!("Unknown label category: {}", raw))?);
+        }
+        
+        Ok(filter)
+    }
+    
+    fn matches(&self, topic: Topic, payload: &serde_json::Value) -> bool {
+        let value_field = match topic {
+            Topic::Opportunities => "profit",
+            Topic::Bundles => "value",
+            _ => return true,
+        };
+        
+        if let Some(min_profit) = self.min_profit {
+            let value = serde_json::from_value::<models::Wei>(payload[value_field].clone())
+                .map(|wei| wei.0)
+                .unwrap_or_default();
+            if value < min_profit {
+                return false;
+            }
+        }
+        if let Some(contract) = self.contract {
+            let to = serde_json::from_value::<Option<Address>>(payload["tx"]["to"].clone()).ok().flatten();
+            if to != Some(contract) {
+                return false;
+            }
+        }
+        if let Some(status) = &self.status {
+            if payload["status"].as_str() != Some(status.as_str()) {
+                return false;
+            }
+        }
+        if let Some(label) = self.label {
+            let tagged = ["from", "to"]
+                .iter()
+                .any(|side| payload["labels"][*side]["category"].as_str() == Some(label.as_str()));
+            if !tagged {
+                return false;
+            }
+        }
+        
+        true
+    }
+}
+
+/// One topic subscription of a connection
+struct Subscription {
+    filter: EventFilter,
+    /// Set when the client asked for its events encrypted
+    cipher: Option<EventCipher>,
+}
+
+/// Upgrade to a WebSocket carrying filtered topic subscriptions
+pub async fn handler(
+    ws: WebSocketUpgrade,
+    principal: ApiPrincipal,
+    Extension(services): Extension<Arc<ServiceContext>>,
+) -> Response {
+    ws.on_upgrade(move |socket| handle_socket(socket, principal, services))
+}
+
+/// Serve one connection
+///
+/// Outbound messages go through a bounded queue drained by a writer task, so a slow client
+/// only loses its own events: when its queue is full events are dropped for it and it is
+/// told how many once it catches up. Only event payloads of encrypted subscriptions are
+/// encrypted; replies to commands and lag notices are not.
+async fn handle_socket(socket: WebSocket, principal: ApiPrincipal, services: Arc<ServiceContext>) {
+    let (mut sink, mut stream) = socket.split();
+    let (queue, mut outbound) = mpsc::channel::<ServerMessage>(SEND_QUEUE_CAPACITY);
+    
+    let writer = tokio::spawn(async move {
+        while let Some(message) = outbound.recv().await {
+            let text = match serde_json::to_string(&message) {
+                Ok(text) => text,
+                Err(e) => {
+                    warn!("Failed to serialize WebSocket message: {}", e);
+                    continue;
+                }
+            };
+            if sink.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+        }
+    });
+    
+    let mut events = services.event_bus.subscribe();
+    let mut subscriptions: HashMap<Topic, Subscription> = HashMap::new();
+    let mut dropped: u64 = 0;
+    
+    loop {
+        tokio::select! {
+            message = stream.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let reply = handle_command(&principal, &mut subscriptions, &text);
+                    if queue.send(reply).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if !principal.may_receive(&event) {
+                        continue;
+                    }
+                    let subscription = match subscriptions.get_mut(&event.topic) {
+                        Some(subscription) if subscription.filter.matches(event.topic, &event.payload) => {
+                            subscription
+                        }
+                        _ => continue,
+                    };
+                    
+                    if dropped > 0 {
+                        match queue.try_send(ServerMessage::Lagged { dropped }) {
+                            Ok(()) => dropped = 0,
+                            Err(TrySendError::Full(_)) => {
+                                dropped += 1;
+                                metrics::counter!("ws_events_dropped_total", 1);
+                                continue;
+                            }
+                            Err(TrySendError::Closed(_)) => break,
+                        }
+                    }
+                    
+                    let topic = channel_name(event.topic);
+                    let message = match &mut subscription.cipher {
+                        Some(cipher) => {
+                            let sealed = serde_json::to_vec(&event.payload)
+                                .map_err(anyhow::Error::from)
+                                .and_then(|payload| cipher.seal(topic, &payload));
+                            match sealed {
+                                Ok(sealed) => ServerMessage::EncryptedEvent {
+                                    topic: topic.to_string(),
+                                    nonce: sealed.nonce,
+                                    ciphertext: sealed.ciphertext,
+                                },
+                                Err(e) => {
+                                    warn!("Failed to encrypt {} event for {}: {}", topic, principal.name, e);
+                                    continue;
+                                }
+                            }
+                        }
+                        None => ServerMessage::Event {
+                            topic: topic.to_string(),
+                            data: event.payload,
+                        },
+                    };
+                    match queue.try_send(message) {
+                        Ok(()) => {
+                            let occupancy = SEND_QUEUE_CAPACITY - queue.capacity();
+                            metrics::histogram!("ws_send_queue_occupancy", occupancy as f64);
+                        }
+                        Err(TrySendError::Full(_)) => {
+                            dropped += 1;
+                            metrics::counter!("ws_events_dropped_total", 1);
+                        }
+                        Err(TrySendError::Closed(_)) => break,
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("WebSocket subscriber {} lagged, skipped {} events", principal.name, skipped);
+                    dropped += skipped;
+                }
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+    
+    drop(queue);
+    let _ = writer.await;
+    debug!("WebSocket subscriber {} disconnected", principal.name);
+}
+
+/// Apply a client command, authorizing subscriptions against the caller's API key
+fn handle_command(
+    principal: &ApiPrincipal,
+    subscriptions: &mut HashMap<Topic, Subscription>,
+    text: &str,
+) -> ServerMessage {
+    let command: ClientMessage = match serde_json::from_str(text) {
+        Ok(command) => command,
+        Err(e) => return ServerMessage::error("invalid_message", None, e.to_string()),
+    };
+    
+    match command {
+        ClientMessage::Subscribe(request) => {
+            let topic = match parse_channel(&request.topic) {
+                Some(topic) => topic,
+                None => {
+                    let message = format!("Unknown topic: {}", request.topic);
+                    return ServerMessage::error("unknown_topic", Some(request.topic), message);
+                }
+            };
+            if let Err(e) = principal.authorize_topic(topic.as_str()) {
+                return ServerMessage::error(e.code(), Some(request.topic), e.message());
+            }
+            let filter = match EventFilter::parse(topic, &request) {
+                Ok(filter) => filter,
+                Err(message) => return ServerMessage::error("invalid_filter", Some(request.topic), message),
+            };
+            let (cipher, public_key) = match &request.public_key {
+                Some(client_key) => match EventCipher::accept(client_key, principal.event_psk()) {
+                    Ok((cipher, public_key)) => (Some(cipher), Some(public_key)),
+                    Err(e) => {
+                        return ServerMessage::error("invalid_public_key", Some(request.topic), format!("{:#}", e));
+                    }
+                },
+                None if principal.encrypt_events => {
+                    let message = "This API key must subscribe with a public_key to encrypt its events";
+                    return ServerMessage::error("encryption_required", Some(request.topic), message);
+                }
+                None => (None, None),
+            };
+            
+            // Subscribing again replaces the filter and the key
+            subscriptions.insert(topic, Subscription { filter, cipher });
+            ServerMessage::Subscribed {
+                topic: channel_name(topic).to_string(),
+                public_key,
+            }
+        }
+        ClientMessage::Unsubscribe { topic: name } => match parse_channel(&name) {
+            Some(topic) => {
+                subscriptions.remove(&topic);
+                ServerMessage::Unsubscribed { topic: channel_name(topic).to_string() }
+            }
+            None => {
+                let message = format!("Unknown topic: {}", name);
+                ServerMessage::error("unknown_topic", Some(name), message)
+            }
+        },
+    }
+}
@@ -0,0 +1,261 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use ethers::{abi::Abi, types::Address, utils::to_checksum};
+use reqwest::StatusCode;
+use serde_json::Value;
+use sqlx::Row;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::{config::AbiSourceConfig, database::DbPool};
+
+/// Answer of Etherscan for a contract without verified source
+const ETHERSCAN_UNVERIFIED: &str = "Contract source code not verified";
+
+/// Registry an ABI was found in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbiSource {
+    Sourcify,
+    Etherscan,
+}
+
+impl AbiSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sourcify => "sourcify",
+            Self::Etherscan => "etherscan",
+        }
+    }
+}
+
+/// A stored lookup, without an ABI when no registry had the contract verified
+struct StoredAbi {
+    abi: Option<Value>,
+    fetched_at: DateTime<Utc>,
+}
+
+/// Looks up the ABIs of verified contracts, storing what it finds
+///
+/// Sourcify is asked first as it needs no key, then Etherscan when a key is configured. Calls
+/// to a proxy verified on Etherscan are decoded against its implementation's ABI. Contracts
+/// neither registry has verified are stored too and looked up again after
+/// `unverified_retry_seconds`; a lookup that failed is not stored, so the next one asks again.
+pub struct AbiResolver {
+    http: reqwest::Client,
+    chain_id: u64,
+    config: AbiSourceConfig,
+    /// Database the lookups are stored in, `None` for commands that run without one
+    db_pool: Option<DbPool>,
+}
+
+impl AbiResolver {
+    /// Create a new resolver for contracts on a chain
+    pub fn new(chain_id: u64, config: AbiSourceConfig, db_pool: Option<DbPool>) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.request_timeout_ms))
+            .build()
+            .context("Failed to create ABI source HTTP client")?;
+        
+        Ok(Self {
+            http,
+            chain_id,
+            config,
+            db_pool,
+        })
+    }
+    
+    /// ABI of a contract, `None` when no registry has it verified
+    pub async fn resolve(&self, address: Address) -> Result<Option<Abi>> {
+        let stored = match self.load(address).await {
+            Ok(stored) => stored,
+            Err(e) => {
+                debug!("Failed to load ABI of {:?}: {}", address, e);
+                None
+            }
+        };
+        let retry_after = ChronoDuration::seconds(self.config.unverified_retry_seconds as i64);
+        let abi = match stored {
+            Some(StoredAbi { abi: Some(abi), .. }) => abi,
+            Some(StoredAbi { abi: None, fetched_at }) if Utc::now() - fetched_at < retry_after => return Ok(None),
+            _ => {
+                let found = self.fetch(address).await?;
+                if let Err(e) = self.persist(address, found.as_ref()).await {
+                    warn!("Failed to store ABI of {:?}: {}", address, e);
+                }
+                match found {
+                    Some((_, abi)) => abi,
+                    None => return Ok(None),
+                }
+            }
+        };
+        
+        serde_json::from_value(abi).map(Some).context(format!("Invalid ABI of {:?}", address))
+    }
+    
+    /// Ask the registries in turn for a contract's ABI
+    async fn fetch(&self, address: Address) -> Result<Option<(AbiSource, Value)>> {
+        let mut failure = None;
+        for source in [AbiSource::Sourcify, AbiSource::Etherscan] {
+            let result = match (source, &self.config.sourcify_url, &self.config.etherscan_api_key) {
+                (AbiSource::Sourcify, Some(url), _) => self.fetch_sourcify(url, address).await,
+                (AbiSource::Etherscan, _, Some(api_key)) => self.fetch_etherscan(api_key, address).await,
+                _ => continue,
+            };
+            // Only ABIs we can decode against are kept
+            let result = result.and_then(|abi| match abi {
+                Some(abi) => serde_json::from_value::<Abi>(abi.clone())
+                    .map(|_| Some(abi))
+                    .context(format!("Invalid ABI from {}", source.as_str())),
+                None => Ok(None),
+            });
+            match result {
+                Ok(Some(abi)) => {
+                    metrics::counter!("abi_fetches_total", 1, "source" => source.as_str());
+                    debug!("Fetched ABI of {:?} from {}", address, source.as_str());
+                    return Ok(Some((source, abi)));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    debug!("{} lookup of {:?} failed: {}", source.as_str(), address, e);
+                    failure = Some(e);
+                }
+            }
+        }
+        
+        // Not verified as far as we know, unless a registry that might have it couldn't answer
+        match failure {
+            Some(e) => Err(e.context(format!("Failed to look up ABI of {:?}", address))),
+            None => Ok(None),
+        }
+    }
+    
+    async fn fetch_sourcify(&self, url: &str, address: Address) -> Result<Option<Value>> {
+        let response = self
+            .http
+            .get(format!("{}/v2/contract/{}/{}", url, self.chain_id, to_checksum(&address, None)))
+            .query(&[("fields", "abi")])
+            .send()
+            .await
+            .context("Sourcify request failed")?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        
+        let body: Value = response
+            .error_for_status()
+            .context("Sourcify lookup failed")?
+            .json()
+            .await
+            .context("Invalid Sourcify response")?;
+        match body.get("abi") {
+            Some(abi) if abi.is_array() => Ok(Some(abi.clone())),
+            _ => Err(anyhow!("Sourcify returned no ABI")),
+        }
+    }
+    
+    async fn fetch_etherscan(&self, api_key: &str, address: Address) -> Result<Option<Value>> {
+        let entry = self.etherscan_source(api_key, address).await?;
+        let abi = match etherscan_abi(&entry)? {
+            Some(abi) => abi,
+            None => return Ok(None),
+        };
+        
+        let implementation = entry["Implementation"]
+            .as_str()
+            .filter(|_| entry["Proxy"] == "1")
+            .and_then(|implementation| implementation.parse::<Address>().ok())
+            .filter(|implementation| *implementation != address && !implementation.is_zero());
+        if let Some(implementation) = implementation {
+            match etherscan_abi(&self.etherscan_source(api_key, implementation).await?)? {
+                Some(implementation_abi) => return Ok(Some(implementation_abi)),
+                None => debug!("Implementation {:?} of proxy {:?} is not verified", implementation, address),
+            }
+        }
+        
+        Ok(Some(abi))
+    }
+    
+    /// Etherscan's verified source entry of a contract
+    async fn etherscan_source(&self, api_key: &str, address: Address) -> Result<Value> {
+        let chain_id = self.chain_id.to_string();
+        let address_param = format!("{:?}", address);
+        let body: Value = self
+            .http
+            .get(&self.config.etherscan_url)
+            .query(&[
+                ("chainid", chain_id.as_str()),
+                ("module", "contract"),
+                ("action", "getsourcecode"),
+                ("address", address_param.as_str()),
+                ("apikey", api_key),
+            ])
+            .send()
+            .await
+            .context("Etherscan request failed")?
+            .error_for_status()
+            .context("Etherscan lookup failed")?
+            .json()
+            .await
+            .context("Invalid Etherscan response")?;
+        
+        // Rate limits and bad keys come back as a failed status with the reason as the result
+        if body["status"] != "1" {
+            return Err(anyhow!("Etherscan refused the lookup: {}", body["result"]));
+        }
+        body["result"]
+            .get(0)
+            .cloned()
+            .ok_or_else(|| anyhow!("Etherscan returned no contract"))
+    }
+    
+    async fn load(&self, address: Address) -> Result<Option<StoredAbi>> {
+        let db_pool = match &self.db_pool {
+            Some(db_pool) => db_pool,
+            None => return Ok(None),
+        };
+        
+        let row = sqlx::query("SELECT abi, fetched_at FROM contract_abis WHERE address = $1")
+            .bind(format!("{:?}", address))
+            .fetch_optional(db_pool)
+            .await
+            .context("Failed to load contract ABI")?;
+        
+        row.map(|row| {
+            Ok(StoredAbi {
+                abi: row.try_get("abi")?,
+                fetched_at: row.try_get("fetched_at")?,
+            })
+        })
+        .transpose()
+    }
+    
+    async fn persist(&self, address: Address, found: Option<&(AbiSource, Value)>) -> Result<()> {
+        let db_pool = match &self.db_pool {
+            Some(db_pool) => db_pool,
+            None => return Ok(()),
+        };
+        
+        sqlx::query(
+            "INSERT INTO contract_abis (address, source, abi, fetched_at)
+             VALUES ($1, $2, $3, NOW())
+             ON CONFLICT (address) DO UPDATE
+             SET source = EXCLUDED.source, abi = EXCLUDED.abi, fetched_at = EXCLUDED.fetched_at",
+        )
+        .bind(format!("{:?}", address))
+        .bind(found.map(|(source, _)| source.as_str()))
+        .bind(found.map(|(_, abi)| abi))
+        .execute(db_pool)
+        .await
+        .context("Failed to store contract ABI")?;
+        
+        Ok(())
+    }
+}
+
+/// ABI of an Etherscan source entry, `None` when the contract is not verified
+fn etherscan_abi(entry: &Value) -> Result<Option<Value>> {
+    match entry["ABI"].as_str() {
+        Some(ETHERSCAN_UNVERIFIED) | None => Ok(None),
+        Some(abi) => serde_json::from_str(abi).map(Some).context("Invalid ABI JSON from Etherscan"),
+    }
+}
@@ -0,0 +1,93 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::{
+    types::{Bytes, Transaction},
+    utils::rlp,
+};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    tungstenite::{client::IntoClientRequest, http::HeaderValue, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+
+use crate::config::BloxrouteStreamConfig;
+
+/// Notification of one `newTxs` subscription message
+#[derive(Debug, Deserialize)]
+struct Notification {
+    params: NotificationParams,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotificationParams {
+    result: NewTx,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NewTx {
+    raw_tx: Bytes,
+}
+
+/// Pending transactions from bloXroute's `newTxs` stream, delivered as signed raw transactions
+pub struct BloxrouteStream {
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl BloxrouteStream {
+    /// Connect to the gateway or Cloud API and subscribe to new transactions
+    pub async fn connect(config: &BloxrouteStreamConfig) -> Result<Self> {
+        let mut request = config
+            .url
+            .as_str()
+            .into_client_request()
+            .context(format!("Invalid bloXroute URL {}", config.url))?;
+        let auth = HeaderValue::from_str(&config.auth_header).context("Invalid bloXroute authorization header")?;
+        request.headers_mut().insert("authorization", auth);
+        
+        let (mut socket, _) = tokio_tungstenite::connect_async(request)
+            .await
+            .context(format!("Failed to connect to {}", config.url))?;
+        let subscribe = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "subscribe",
+            "params": ["newTxs", {"include": ["raw_tx"]}],
+        });
+        socket.send(Message::Text(subscribe.to_string())).await?;
+        
+        Ok(Self { socket })
+    }
+    
+    /// Next announced transaction, `None` once the stream closed
+    ///
+    /// Subscription replies and messages that don't decode are returned as errors, so one
+    /// malformed transaction doesn't end the stream.
+    pub async fn next(&mut self) -> Option<Result<Transaction>> {
+        loop {
+            let text = match self.socket.next().await? {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Ping(payload)) => {
+                    if let Err(e) = self.socket.send(Message::Pong(payload)).await {
+                        return Some(Err(e.into()));
+                    }
+                    continue;
+                }
+                Ok(Message::Close(_)) => return None,
+                Ok(_) => continue,
+                Err(e) => return Some(Err(e.into())),
+            };
+            
+            let notification: Notification = match serde_json::from_str(&text) {
+                Ok(notification) => notification,
+                Err(_) => return Some(Err(anyhow!("Unexpected bloXroute message: {}", text))),
+            };
+            return Some(
+                rlp::decode::<Transaction>(&notification.params.result.raw_tx)
+                    .map_err(|e| anyhow!("Invalid raw transaction from bloXroute: {}", e)),
+            );
+        }
+    }
+}
@@ -1,24 +1,37 @@
 use anyhow::{anyhow, Context, Result};
 use ethers::{
-    abi::Address,
+    abi::{self, Address, ParamType, Token},
     prelude::*,
-    providers::{Http, Middleware, Provider, PubsubClient, Ws},
+    providers::{Http, Middleware, Provider, PubsubClient, RpcError, Ws},
     types::{
-        Block, BlockNumber, Bytes, Filter, Transaction, TransactionReceipt, TransactionRequest, H256, U256,
+        transaction::eip2718::TypedTransaction, AccessListWithGasUsed, Block, BlockNumber, Bytes, FeeHistory,
+        Filter, Log, Transaction, TransactionReceipt, TransactionRequest, H256, U256,
     },
+    utils::id,
 };
+use futures::{Future, StreamExt, TryStreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::{
     collections::HashMap,
+    fmt::Debug,
+    ops::Range,
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
     time::Duration,
 };
-use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
 
-use crate::utils::metrics::MetricsTimer;
+use crate::{
+    blockchain::{
+        abi::AbiResolver,
+        rate_limit::{RateLimiter, RpcPriority},
+    },
+    config::{CacheSettings, ProviderBudget, RpcClassPolicy, RpcPolicyConfig, RpcRateLimitConfig},
+    utils::{cache::BoundedCache, metrics::MetricsTimer},
+};
 
 /// Client for interacting with the blockchain
 pub struct BlockchainClient {
@@ -26,6 +39,8 @@ pub struct BlockchainClient {
     http_provider: Provider<Http>,
     /// WebSocket provider for subscriptions
     ws_provider: Provider<Ws>,
+    /// HTTP provider for a second node, polled when our head goes stale
+    fallback_provider: Option<Provider<Http>>,
     /// Chain ID
     chain_id: u64,
     /// Number of confirmations to wait for transactions
@@ -33,7 +48,252 @@ pub struct BlockchainClient {
     /// Currently used gas price
     current_gas_price: AtomicU64,
     /// Cache for contract ABIs
-    abi_cache: RwLock<HashMap<Address, ethers::abi::Contract>>,
+    abi_cache: BoundedCache<Address, ethers::abi::Contract>,
+    /// Looks up ABIs missing from the cache
+    abi_resolver: AbiResolver,
+    /// Set once the node rejects `eth_getBlockReceipts`
+    block_receipts_unsupported: AtomicBool,
+    /// Set once the node rejects `eth_createAccessList`
+    access_lists_unsupported: AtomicBool,
+    /// Set once the node rejects `eth_blobBaseFee`
+    blob_base_fee_unsupported: AtomicBool,
+    /// Set once the node rejects `debug_traceBlockByNumber`
+    block_traces_unsupported: AtomicBool,
+    /// Set once the node rejects `alchemy_pendingTransactions` subscriptions
+    alchemy_pending_txs_unsupported: AtomicBool,
+    /// Set once the node rejects full-body `newPendingTransactions` subscriptions
+    full_pending_txs_unsupported: AtomicBool,
+    /// Posts JSON-RPC batches to the primary node, which the provider can't send
+    batch_http: reqwest::Client,
+    /// Set once the node answers a batch with anything but a list of responses
+    batch_unsupported: AtomicBool,
+    /// Multicall3 contract that batched contract reads go through
+    multicall_address: Address,
+    /// Set once a multicall finds no code at the Multicall3 address
+    multicall_unsupported: AtomicBool,
+    /// Deadlines and retries by method class
+    rpc_policies: RpcPolicyConfig,
+    /// Compute unit prices of the methods
+    rate_limits: RpcRateLimitConfig,
+    /// Budget of the primary node's provider
+    primary_limiter: RateLimiter,
+    /// Budget of the fallback node's provider
+    fallback_limiter: RateLimiter,
+}
+
+/// Receipt requests in flight when a node lacks `eth_getBlockReceipts`
+const RECEIPT_FETCH_CONCURRENCY: usize = 32;
+
+/// Backoff before the first retry, doubled for each further one
+const RPC_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Doublings of the retry backoff before it stops growing, 6.4s
+const MAX_RPC_BACKOFF_DOUBLINGS: u32 = 6;
+
+/// Retries of one request whatever its class policy asks for
+pub const MAX_RPC_RETRIES: u32 = 10;
+
+/// Requests per JSON-RPC batch for hash lookups, longer lists are split into several batches
+const MAX_BATCH_SIZE: usize = 100;
+
+/// Full blocks are large, so fewer go in one batch
+const MAX_BLOCK_BATCH_SIZE: usize = 10;
+
+/// Takes `(address target, bool allowFailure, bytes callData)[]`, returns `(bool success, bytes returnData)[]`
+const AGGREGATE3: &str = "aggregate3((address,bool,bytes)[])";
+
+/// Reads per Multicall3 call, longer lists are split to stay under the node's `eth_call` gas cap
+const MAX_MULTICALL_SIZE: usize = 200;
+
+/// Method classes with their own deadline and retry budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RpcClass {
+    FastRead,
+    Heavy,
+    Send,
+}
+
+impl RpcClass {
+    /// Class of a JSON-RPC method
+    fn of(method: &str) -> Self {
+        match method {
+            "eth_sendRawTransaction" | "eth_sendTransaction" => Self::Send,
+            "eth_call" | "eth_estimateGas" | "eth_getLogs" | "eth_getBlockReceipts" | "eth_feeHistory" => {
+                Self::Heavy
+            }
+            // Full blocks carry every transaction body, so they're tagged apart from header lookups
+            "eth_getBlockByNumber:full" => Self::Heavy,
+            _ if method.starts_with("debug_") || method.starts_with("trace_") => Self::Heavy,
+            _ => Self::FastRead,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::FastRead => "fast_read",
+            Self::Heavy => "heavy",
+            Self::Send => "send",
+        }
+    }
+}
+
+/// Whether a provider error means the RPC method is not implemented by the node
+fn is_method_not_found(error: &ProviderError) -> bool {
+    let message = error.to_string().to_ascii_lowercase();
+    message.contains("-32601") || message.contains("method not found") || message.contains("does not exist")
+}
+
+/// Whether a request failed because the node answered it with an error, rather than timing out
+/// or never reaching the node
+pub fn is_node_rejection(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<ProviderError>())
+        .any(|cause| cause.as_error_response().is_some())
+}
+
+/// Whether a call or gas estimate failed because the node executed it and it reverted
+pub fn is_execution_revert(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<ProviderError>())
+        .filter_map(|cause| cause.as_error_response())
+        .any(|response| response.code == 3 || response.message.to_lowercase().contains("revert"))
+}
+
+/// Calldata of a call to a function by signature
+pub fn calldata(signature: &str, args: &[Token]) -> Bytes {
+    let mut data = id(signature).to_vec();
+    data.extend(abi::encode(args));
+    Bytes::from(data)
+}
+
+/// First word of a call's output as an integer, `what` naming the call in the error
+pub fn first_word(what: &str, output: &[u8]) -> Result<U256> {
+    if output.len() < 32 {
+        return Err(anyhow!("{} returned {} bytes, expected a word", what, output.len()));
+    }
+    Ok(U256::from_big_endian(&output[..32]))
+}
+
+/// ETH a `callTracer` frame and the calls under it sent `recipient`, none from reverted frames
+fn transfers_to(frame: &Value, recipient: Address) -> U256 {
+    if frame.get("error").is_some() {
+        return U256::zero();
+    }
+    
+    let moves_value = matches!(frame["type"].as_str(), Some("CALL" | "CREATE" | "CREATE2" | "SELFDESTRUCT"));
+    let to = frame["to"].as_str().and_then(|to| to.parse::<Address>().ok());
+    let own = match (moves_value, to) {
+        (true, Some(to)) if to == recipient => frame["value"]
+            .as_str()
+            .and_then(|value| U256::from_str_radix(value.trim_start_matches("0x"), 16).ok())
+            .unwrap_or_default(),
+        _ => U256::zero(),
+    };
+    
+    frame["calls"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .fold(own, |sum, call| sum.saturating_add(transfers_to(call, recipient)))
+}
+
+/// Whether a provider error means the node offers no such subscription or rejects its parameters
+fn is_subscription_unsupported(error: &ProviderError) -> bool {
+    let message = error.to_string().to_ascii_lowercase();
+    is_method_not_found(error)
+        || message.contains("-32602")
+        || message.contains("invalid params")
+        || message.contains("no such subscription")
+        || message.contains("subscription in eth namespace")
+}
+
+/// Kind of pending transaction subscription the node accepted, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingTxFeed {
+    /// Alchemy's `alchemy_pendingTransactions`, with full bodies
+    Alchemy,
+    /// `newPendingTransactions` with the full-body flag, as on Geth and Erigon
+    FullBodies,
+    /// `newPendingTransactions` announcing hashes only
+    Hashes,
+}
+
+impl PendingTxFeed {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Alchemy => "alchemy",
+            Self::FullBodies => "full_bodies",
+            Self::Hashes => "hashes",
+        }
+    }
+}
+
+/// A pending transaction as a subscription announces it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum PendingTxAnnouncement {
+    /// The whole transaction, nothing left to fetch
+    Body(Box<Transaction>),
+    /// Only the hash, from a hash feed or a node that ignores the full-body flag
+    Hash(H256),
+}
+
+impl PendingTxAnnouncement {
+    pub fn hash(&self) -> H256 {
+        match self {
+            Self::Body(tx) => tx.hash,
+            Self::Hash(tx_hash) => *tx_hash,
+        }
+    }
+}
+
+/// Whether a node's answer to a batch says it takes no batches, rather than that the batch failed
+fn is_batch_unsupported(answer: &Value) -> bool {
+    let message = answer["error"]["message"].as_str().unwrap_or_default().to_ascii_lowercase();
+    message.contains("batch")
+        && ["not supported", "unsupported", "disabled", "not allowed"]
+            .iter()
+            .any(|phrase| message.contains(phrase))
+}
+
+/// Results of a batch in request order, as responses may come back in any order
+///
+/// A request the node failed or left unanswered comes back as `None`, like one it returned null
+/// for, so one bad item doesn't fail the rest.
+fn batch_results<T: DeserializeOwned>(
+    method: &str,
+    count: usize,
+    responses: Vec<Value>,
+) -> Result<Vec<Option<T>>> {
+    let mut results: Vec<Option<T>> = (0..count).map(|_| None).collect();
+    let mut failed = 0;
+    for response in responses {
+        let id = response["id"]
+            .as_u64()
+            .map(|id| id as usize)
+            .filter(|id| *id < count)
+            .ok_or_else(|| anyhow!("{} batch answered unknown request {}", method, response["id"]))?;
+        if let Some(error) = response.get("error") {
+            debug!("{} request {} in a batch failed: {}", method, id, error);
+            failed += 1;
+            continue;
+        }
+        
+        match serde_json::from_value(response["result"].clone()) {
+            Ok(result) => results[id] = result,
+            Err(e) => {
+                debug!("Invalid {} result for request {} in a batch: {}", method, id, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        metrics::counter!("rpc_batch_item_errors_total", failed, "method" => method.to_string());
+    }
+    
+    Ok(results)
 }
 
 impl BlockchainClient {
@@ -41,19 +301,221 @@ impl BlockchainClient {
     pub fn new(
         http_provider: Provider<Http>,
         ws_provider: Provider<Ws>,
+        fallback_provider: Option<Provider<Http>>,
         chain_id: u64,
         confirmations: u64,
+        abi_cache: &CacheSettings,
+        abi_resolver: AbiResolver,
+        rpc_policies: RpcPolicyConfig,
+        rate_limits: RpcRateLimitConfig,
+        multicall_address: Address,
     ) -> Self {
+        let limiter = |provider, budget: &ProviderBudget| {
+            let budget = if rate_limits.enabled { budget.clone() } else { ProviderBudget::default() };
+            RateLimiter::new(provider, &budget, rate_limits.bulk_reserve)
+        };
+        let primary_limiter = limiter("primary", &rate_limits.primary);
+        let fallback_limiter = limiter("fallback", &rate_limits.fallback);
+        
         Self {
             http_provider,
             ws_provider,
+            fallback_provider,
             chain_id,
             confirmations,
             current_gas_price: AtomicU64::new(0),
-            abi_cache: RwLock::new(HashMap::new()),
+            abi_cache: BoundedCache::new("abi", abi_cache),
+            abi_resolver,
+            block_receipts_unsupported: AtomicBool::new(false),
+            access_lists_unsupported: AtomicBool::new(false),
+            blob_base_fee_unsupported: AtomicBool::new(false),
+            block_traces_unsupported: AtomicBool::new(false),
+            alchemy_pending_txs_unsupported: AtomicBool::new(false),
+            full_pending_txs_unsupported: AtomicBool::new(false),
+            batch_http: reqwest::Client::new(),
+            batch_unsupported: AtomicBool::new(false),
+            multicall_address,
+            multicall_unsupported: AtomicBool::new(false),
+            rpc_policies,
+            rate_limits,
+            primary_limiter,
+            fallback_limiter,
+        }
+    }
+
+    fn policy(&self, class: RpcClass) -> &RpcClassPolicy {
+        match class {
+            RpcClass::FastRead => &self.rpc_policies.fast_reads,
+            RpcClass::Heavy => &self.rpc_policies.heavy,
+            RpcClass::Send => &self.rpc_policies.sends,
+        }
+    }
+
+    /// Compute units the provider charges for one request of a method
+    fn compute_units(&self, method: &str) -> u32 {
+        let method = method.split(':').next().unwrap_or(method);
+        self.rate_limits
+            .method_compute_units
+            .get(method)
+            .copied()
+            .unwrap_or(self.rate_limits.default_compute_units)
+    }
+    
+    /// Wait for a provider's budget to cover `requests` requests of a method
+    ///
+    /// Sends always go at critical priority, other requests at the priority of the calling task.
+    async fn throttle(&self, limiter: &RateLimiter, method: &str, requests: u32) {
+        let priority = match RpcClass::of(method) {
+            RpcClass::Send => RpcPriority::Critical,
+            _ => RpcPriority::current(),
+        };
+        limiter.acquire(priority, requests, self.compute_units(method) * requests).await;
+    }
+
+    /// Run an HTTP RPC request to the primary node, see `rpc_on`
+    async fn rpc<T, F, Fut>(&self, method: &'static str, request: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, ProviderError>>,
+    {
+        self.rpc_on(&self.primary_limiter, method, 1, request).await
+    }
+
+    /// Run an HTTP RPC request of `requests` requests within a provider's budget and under its
+    /// method class's deadline, retrying timeouts and transport failures within the class's budget
+    async fn rpc_on<T, F, Fut>(
+        &self,
+        limiter: &RateLimiter,
+        method: &'static str,
+        requests: u32,
+        request: F,
+    ) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, ProviderError>>,
+    {
+        let class = RpcClass::of(method);
+        let policy = self.policy(class);
+        let deadline = Duration::from_millis(policy.timeout_ms);
+        
+        let mut attempt = 0;
+        loop {
+            // Every attempt is charged, retries included
+            self.throttle(limiter, method, requests).await;
+            let timer = MetricsTimer::new("blockchain_request_duration_seconds");
+            let result = tokio::time::timeout(deadline, request()).await;
+            timer.stop();
+            
+            let error = match result {
+                Ok(Ok(value)) => return Ok(value),
+                // The node answered; asking again won't change its mind
+                Ok(Err(e)) if e.as_error_response().is_some() => return Err(e.into()),
+                Ok(Err(e)) => anyhow!("{} failed: {}", method, e),
+                Err(_) => {
+                    metrics::counter!("rpc_timeouts_total", 1, "class" => class.as_str());
+                    anyhow!("{} timed out after {:?}", method, deadline)
+                }
+            };
+            if attempt >= policy.retries.min(MAX_RPC_RETRIES) {
+                return Err(error);
+            }
+            
+            debug!("{}, retrying", error);
+            metrics::counter!("rpc_retries_total", 1, "class" => class.as_str());
+            tokio::time::sleep(RPC_RETRY_BACKOFF * 2u32.pow(attempt.min(MAX_RPC_BACKOFF_DOUBLINGS))).await;
+            attempt += 1;
+        }
+    }
+
+    /// Call one method once per set of params in JSON-RPC batches of up to `batch_size`
+    ///
+    /// Results come back in the order of `params`, `None` where the node returned null or failed
+    /// that request. Each batch runs under the method's class policy. Nodes that answer they take
+    /// no batches get the requests one at a time, with up to `RECEIPT_FETCH_CONCURRENCY` in flight.
+    async fn batch<P, T>(
+        &self,
+        method: &'static str,
+        params: Vec<P>,
+        batch_size: usize,
+    ) -> Result<Vec<Option<T>>>
+    where
+        P: Serialize + Debug + Clone + Send + Sync,
+        T: DeserializeOwned + Send,
+    {
+        // The class tag of full block fetches isn't part of the method the node knows
+        let rpc_method = method.split(':').next().unwrap_or(method);
+        
+        let mut results = Vec::with_capacity(params.len());
+        for chunk in params.chunks(batch_size) {
+            if !self.batch_unsupported.load(Ordering::Relaxed) {
+                let body: Vec<Value> = chunk
+                    .iter()
+                    .enumerate()
+                    .map(|(id, params)| {
+                        json!({ "jsonrpc": "2.0", "id": id, "method": rpc_method, "params": params })
+                    })
+                    .collect();
+                let requests = chunk.len() as u32;
+                match self.rpc_on(&self.primary_limiter, method, requests, || self.send_batch(&body)).await? {
+                    Some(responses) => {
+                        metrics::counter!("rpc_batches_total", 1, "method" => rpc_method);
+                        let requests = chunk.len() as u64;
+                        metrics::counter!("rpc_batched_requests_total", requests, "method" => rpc_method);
+                        results.extend(batch_results(method, chunk.len(), responses)?);
+                        continue;
+                    }
+                    None => {
+                        info!("Node does not support JSON-RPC batches, sending requests one at a time");
+                        self.batch_unsupported.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+            
+            let single: Vec<Option<T>> = futures::stream::iter(chunk.iter().cloned())
+                .map(|params| async move {
+                    self.rpc(method, || self.http_provider.request::<_, Option<T>>(rpc_method, params.clone()))
+                        .await
+                })
+                .buffered(RECEIPT_FETCH_CONCURRENCY)
+                .try_collect()
+                .await?;
+            results.extend(single);
+        }
+        
+        Ok(results)
+    }
+
+    /// Post a batch to the primary node, `None` when it answers that it takes no batches
+    ///
+    /// Any other answer that isn't a successful list of responses, such as a rate limit or a
+    /// gateway error, fails the batch so it is retried rather than giving up on batching.
+    async fn send_batch(&self, body: &[Value]) -> Result<Option<Vec<Value>>, ProviderError> {
+        let response = self
+            .batch_http
+            .post(self.http_provider.url().clone())
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::CustomError(e.to_string()))?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| ProviderError::CustomError(e.to_string()))?;
+        
+        match serde_json::from_str::<Value>(&text) {
+            Ok(Value::Array(responses)) if status.is_success() => Ok(Some(responses)),
+            Ok(answer) if is_batch_unsupported(&answer) => Ok(None),
+            _ => Err(ProviderError::CustomError(format!(
+                "Batch answered with status {}: {}",
+                status,
+                text.chars().take(200).collect::<String>()
+            ))),
         }
     }
 
+    /// HTTP provider for the primary node, used to fork state for simulation
+    pub fn http_provider(&self) -> &Provider<Http> {
+        &self.http_provider
+    }
+
     /// Get the current chain ID
     pub fn chain_id(&self) -> u64 {
         self.chain_id
@@ -61,70 +523,418 @@ impl BlockchainClient {
 
     /// Get the current block number
     pub async fn get_block_number(&self) -> Result<u64> {
-        let timer = MetricsTimer::new("blockchain_request_duration_seconds");
-        let block_number = self.http_provider.get_block_number().await?;
-        timer.stop();
+        let block_number = self
+            .rpc("eth_blockNumber", || self.http_provider.get_block_number())
+            .await?;
         
         Ok(block_number.as_u64())
     }
 
-    /// Get block by number
-    pub async fn get_block(&self, block_number: u64, with_txs: bool) -> Result<Option<Block<H256>>> {
-        let timer = MetricsTimer::new("blockchain_request_duration_seconds");
+    /// Number of the latest finalized block, `None` if the node has none yet
+    pub async fn get_finalized_block_number(&self) -> Result<Option<u64>> {
+        let block = self
+            .rpc("eth_getBlockByNumber", || self.http_provider.get_block(BlockNumber::Finalized))
+            .await?;
+        
+        Ok(block.and_then(|block| block.number).map(|number| number.as_u64()))
+    }
+
+    /// Get a block by number with only its transaction hashes
+    pub async fn get_block_with_hashes(&self, block_number: u64) -> Result<Option<Block<H256>>> {
+        let block = self
+            .rpc("eth_getBlockByNumber", || {
+                self.http_provider.get_block(BlockNumber::Number(block_number.into()))
+            })
+            .await?;
+        
+        Ok(block)
+    }
+
+    /// Get a block by number with its full transactions
+    pub async fn get_block_with_transactions(&self, block_number: u64) -> Result<Option<Block<Transaction>>> {
         let block = self
-            .http_provider
-            .get_block(BlockNumber::Number(block_number.into()))
+            .rpc("eth_getBlockByNumber:full", || {
+                self.http_provider.get_block_with_txs(BlockNumber::Number(block_number.into()))
+            })
             .await?;
-        timer.stop();
         
         Ok(block)
     }
 
+    /// Get every receipt of a block, in transaction order
+    ///
+    /// Uses `eth_getBlockReceipts` where the node supports it, otherwise fetches the receipts of
+    /// the block's transactions in batches.
+    pub async fn get_block_receipts(&self, block_number: u64) -> Result<Vec<TransactionReceipt>> {
+        if !self.block_receipts_unsupported.load(Ordering::Relaxed) {
+            let class = RpcClass::of("eth_getBlockReceipts");
+            let deadline = Duration::from_millis(self.policy(class).timeout_ms);
+            self.throttle(&self.primary_limiter, "eth_getBlockReceipts", 1).await;
+            let timer = MetricsTimer::new("blockchain_request_duration_seconds");
+            let result = tokio::time::timeout(
+                deadline,
+                self.http_provider.get_block_receipts(BlockNumber::Number(block_number.into())),
+            )
+            .await;
+            timer.stop();
+            
+            // Not retried here, the per-transaction fallback below is
+            match result {
+                Ok(Ok(receipts)) => return Ok(receipts),
+                Ok(Err(e)) if is_method_not_found(&e) => {
+                    info!("Node does not support eth_getBlockReceipts, fetching receipts per transaction");
+                    self.block_receipts_unsupported.store(true, Ordering::Relaxed);
+                }
+                Ok(Err(e)) => warn!("eth_getBlockReceipts failed for block {}: {}", block_number, e),
+                Err(_) => {
+                    metrics::counter!("rpc_timeouts_total", 1, "class" => class.as_str());
+                    warn!("eth_getBlockReceipts timed out for block {} after {:?}", block_number, deadline);
+                }
+            }
+        }
+        
+        let block = self
+            .get_block_with_hashes(block_number)
+            .await?
+            .ok_or_else(|| anyhow!("Block {} not found", block_number))?;
+        
+        let tx_hashes = block.transactions;
+        let receipts = self.get_receipts(tx_hashes.clone()).await?;
+        
+        tx_hashes
+            .iter()
+            .zip(receipts)
+            .map(|(tx_hash, receipt)| receipt.ok_or_else(|| anyhow!("Receipt for {:?} not found", tx_hash)))
+            .collect()
+    }
+
+    /// Get base fees and priority fee percentiles for the `block_count` blocks ending at `newest_block`
+    pub async fn get_fee_history(
+        &self,
+        block_count: u64,
+        newest_block: BlockNumber,
+        reward_percentiles: &[f64],
+    ) -> Result<FeeHistory> {
+        let history = self
+            .rpc("eth_feeHistory", || {
+                self.http_provider.fee_history(block_count, newest_block, reward_percentiles)
+            })
+            .await?;
+        
+        Ok(history)
+    }
+
+    /// Blob base fee of the next block, `None` where the node predates EIP-4844
+    pub async fn get_blob_base_fee(&self) -> Result<Option<U256>> {
+        if self.blob_base_fee_unsupported.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        
+        match self
+            .rpc("eth_blobBaseFee", || self.http_provider.request::<_, U256>("eth_blobBaseFee", ()))
+            .await
+        {
+            Ok(blob_base_fee) => Ok(Some(blob_base_fee)),
+            Err(e) if e.downcast_ref::<ProviderError>().map_or(false, is_method_not_found) => {
+                info!("Node does not support eth_blobBaseFee, gas reports go without a blob fee");
+                self.blob_base_fee_unsupported.store(true, Ordering::Relaxed);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// ETH each transaction of a block sent `recipient`, in transaction order
+    ///
+    /// Traced with `callTracer`, so transfers from inside contract calls count and those of
+    /// reverted calls don't. `None` where the node offers no `debug_traceBlockByNumber`.
+    pub async fn get_block_transfers_to(&self, block_number: u64, recipient: Address) -> Result<Option<Vec<U256>>> {
+        if self.block_traces_unsupported.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        
+        let params = (U64::from(block_number), json!({ "tracer": "callTracer" }));
+        match self
+            .rpc("debug_traceBlockByNumber", || {
+                self.http_provider.request::<_, Vec<Value>>("debug_traceBlockByNumber", params.clone())
+            })
+            .await
+        {
+            Ok(traces) => Ok(Some(
+                traces
+                    .iter()
+                    .map(|trace| transfers_to(trace.get("result").unwrap_or(trace), recipient))
+                    .collect(),
+            )),
+            Err(e) if e.downcast_ref::<ProviderError>().map_or(false, is_method_not_found) => {
+                info!("Node does not support debug_traceBlockByNumber, only plain transfers are traced");
+                self.block_traces_unsupported.store(true, Ordering::Relaxed);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// What each transaction of a block paid the block's fee recipient, by hash
+    ///
+    /// Priority fees from the receipts plus ETH sent to the fee recipient, traced where the node
+    /// traces blocks and otherwise only plain transfers to it.
+    pub async fn get_block_payments(
+        &self,
+        block: &Block<Transaction>,
+        receipts: &HashMap<H256, TransactionReceipt>,
+    ) -> Result<HashMap<H256, U256>> {
+        let block_number = block.number.unwrap_or_default().as_u64();
+        let base_fee = block.base_fee_per_gas.unwrap_or_default();
+        let coinbase = block.author.unwrap_or_default();
+        
+        let traced = self.get_block_transfers_to(block_number, coinbase).await?;
+        let transfers: HashMap<H256, U256> = match traced {
+            Some(traced) if traced.len() == block.transactions.len() => {
+                block.transactions.iter().map(|tx| tx.hash).zip(traced).collect()
+            }
+            _ => block
+                .transactions
+                .iter()
+                .filter(|tx| tx.to == Some(coinbase))
+                .filter(|tx| receipts.get(&tx.hash).and_then(|receipt| receipt.status) == Some(1.into()))
+                .map(|tx| (tx.hash, tx.value))
+                .collect(),
+        };
+        
+        Ok(block
+            .transactions
+            .iter()
+            .map(|tx| {
+                let fees = receipts
+                    .get(&tx.hash)
+                    .map(|receipt| {
+                        let price = receipt.effective_gas_price.unwrap_or_default();
+                        receipt.gas_used.unwrap_or_default().saturating_mul(price.saturating_sub(base_fee))
+                    })
+                    .unwrap_or_default();
+                (tx.hash, fees.saturating_add(transfers.get(&tx.hash).copied().unwrap_or_default()))
+            })
+            .collect())
+    }
+
+    /// Execute a read-only call against the latest block
+    pub async fn call(&self, to: Address, data: Bytes) -> Result<Bytes> {
+        let request: TypedTransaction = TransactionRequest::new().to(to).data(data).into();
+        let output = self.rpc("eth_call", || self.http_provider.call(&request, None)).await?;
+        
+        Ok(output)
+    }
+
+    /// Execute many read-only calls against the latest block through Multicall3
+    ///
+    /// Results come back in the order of `calls`, `None` for a call that failed. Up to
+    /// `MAX_MULTICALL_SIZE` calls go into one `eth_call`. On chains without Multicall3 the calls
+    /// are made one at a time, with up to `RECEIPT_FETCH_CONCURRENCY` in flight.
+    pub async fn multicall(&self, calls: &[(Address, Bytes)]) -> Result<Vec<Option<Bytes>>> {
+        let mut results = Vec::with_capacity(calls.len());
+        for chunk in calls.chunks(MAX_MULTICALL_SIZE) {
+            if !self.multicall_unsupported.load(Ordering::Relaxed) {
+                match self.aggregate3(chunk).await? {
+                    Some(outputs) => {
+                        metrics::counter!("multicalls_total", 1);
+                        metrics::counter!("multicall_reads_total", chunk.len() as u64);
+                        results.extend(outputs);
+                        continue;
+                    }
+                    None => {
+                        info!("Multicall3 is not deployed, making contract reads one at a time");
+                        self.multicall_unsupported.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+            
+            let single: Vec<Option<Bytes>> = futures::stream::iter(chunk.iter().cloned())
+                .map(|(to, data)| async move { self.call(to, data).await.ok() })
+                .buffered(RECEIPT_FETCH_CONCURRENCY)
+                .collect()
+                .await;
+            results.extend(single);
+        }
+        
+        Ok(results)
+    }
+
+    /// One `aggregate3` letting every call fail on its own, `None` when there is no Multicall3
+    async fn aggregate3(&self, calls: &[(Address, Bytes)]) -> Result<Option<Vec<Option<Bytes>>>> {
+        let calls = calls
+            .iter()
+            .map(|(target, data)| {
+                Token::Tuple(vec![Token::Address(*target), Token::Bool(true), Token::Bytes(data.to_vec())])
+            })
+            .collect();
+        // A call to an address without code succeeds with empty output
+        let output = self.call(self.multicall_address, calldata(AGGREGATE3, &[Token::Array(calls)])).await?;
+        if output.is_empty() {
+            return Ok(None);
+        }
+        
+        let result_type = ParamType::Tuple(vec![ParamType::Bool, ParamType::Bytes]);
+        let decoded = abi::decode(&[ParamType::Array(Box::new(result_type))], &output)
+            .map_err(|e| anyhow!("Failed to decode multicall results: {}", e))?;
+        let outputs = decoded
+            .into_iter()
+            .next()
+            .and_then(Token::into_array)
+            .ok_or_else(|| anyhow!("Multicall returned no results"))?
+            .into_iter()
+            .map(|result| match result.into_tuple().as_deref() {
+                Some([Token::Bool(true), Token::Bytes(output)]) => Some(Bytes::from(output.clone())),
+                _ => None,
+            })
+            .collect();
+        
+        Ok(Some(outputs))
+    }
+
+    /// Call a view function by signature against the latest block
+    pub async fn call_function(&self, to: Address, signature: &str, args: &[Token]) -> Result<Bytes> {
+        self.call(to, calldata(signature, args))
+            .await
+            .with_context(|| format!("Failed to call {} on {:?}", signature, to))
+    }
+
+    /// Call a view function returning a single word
+    pub async fn read_word(&self, to: Address, signature: &str, args: &[Token]) -> Result<H256> {
+        let output = self.call_function(to, signature, args).await?;
+        if output.len() < 32 {
+            return Err(anyhow!("{} on {:?} returned {} bytes, expected a word", signature, to, output.len()));
+        }
+        Ok(H256::from_slice(&output[..32]))
+    }
+
+    /// Call a view function returning an integer
+    pub async fn read_uint(&self, to: Address, signature: &str, args: &[Token]) -> Result<U256> {
+        Ok(U256::from_big_endian(self.read_word(to, signature, args).await?.as_bytes()))
+    }
+
+    /// Call a view function returning an address
+    pub async fn read_address(&self, to: Address, signature: &str, args: &[Token]) -> Result<Address> {
+        Ok(Address::from(self.read_word(to, signature, args).await?))
+    }
+
     /// Get transaction by hash
     pub async fn get_transaction(&self, tx_hash: H256) -> Result<Option<Transaction>> {
-        let timer = MetricsTimer::new("blockchain_request_duration_seconds");
-        let tx = self.http_provider.get_transaction(tx_hash).await?;
-        timer.stop();
+        let tx = self
+            .rpc("eth_getTransactionByHash", || self.http_provider.get_transaction(tx_hash))
+            .await?;
         
         Ok(tx)
     }
 
+    /// Number of transactions sent from an account, as of a block or including the pending pool
+    pub async fn get_transaction_count(&self, address: Address, block: BlockNumber) -> Result<u64> {
+        let count = self
+            .rpc("eth_getTransactionCount", || {
+                self.http_provider.get_transaction_count(address, Some(block.into()))
+            })
+            .await?;
+        
+        Ok(count.as_u64())
+    }
+
+    /// Deployed code of an account as of a block, empty for externally owned accounts
+    pub async fn get_code(&self, address: Address, block: BlockNumber) -> Result<Bytes> {
+        let code = self
+            .rpc("eth_getCode", || self.http_provider.get_code(address, Some(block.into())))
+            .await?;
+        
+        Ok(code)
+    }
+
+    /// Logs matching a filter
+    pub async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>> {
+        let logs = self.rpc("eth_getLogs", || self.http_provider.get_logs(filter)).await?;
+        
+        Ok(logs)
+    }
+
     /// Get transaction receipt
     pub async fn get_transaction_receipt(&self, tx_hash: H256) -> Result<Option<TransactionReceipt>> {
-        let timer = MetricsTimer::new("blockchain_request_duration_seconds");
-        let receipt = self.http_provider.get_transaction_receipt(tx_hash).await?;
-        timer.stop();
+        let receipt = self
+            .rpc("eth_getTransactionReceipt", || self.http_provider.get_transaction_receipt(tx_hash))
+            .await?;
         
         Ok(receipt)
     }
 
+    /// Get many transactions by hash in batched requests, `None` for those the node doesn't know
+    pub async fn get_transactions(&self, tx_hashes: Vec<H256>) -> Result<Vec<Option<Transaction>>> {
+        let params = tx_hashes.into_iter().map(|tx_hash| [tx_hash]).collect();
+        self.batch("eth_getTransactionByHash", params, MAX_BATCH_SIZE).await
+    }
+
+    /// Get many transaction receipts in batched requests, `None` for transactions not yet mined
+    pub async fn get_receipts(&self, tx_hashes: Vec<H256>) -> Result<Vec<Option<TransactionReceipt>>> {
+        let params = tx_hashes.into_iter().map(|tx_hash| [tx_hash]).collect();
+        self.batch("eth_getTransactionReceipt", params, MAX_BATCH_SIZE).await
+    }
+
+    /// Get a range of blocks with their full transactions in batched requests, in block order
+    pub async fn get_blocks(&self, range: Range<u64>) -> Result<Vec<Option<Block<Transaction>>>> {
+        let params = range
+            .map(|block_number| (BlockNumber::Number(block_number.into()), true))
+            .collect();
+        self.batch("eth_getBlockByNumber:full", params, MAX_BLOCK_BATCH_SIZE).await
+    }
+
     /// Send raw transaction
     pub async fn send_raw_transaction(&self, tx_bytes: Bytes) -> Result<H256> {
-        let timer = MetricsTimer::new("blockchain_request_duration_seconds");
-        let tx_hash = self.http_provider.send_raw_transaction(tx_bytes).await?;
-        timer.stop();
+        let pending_tx = self
+            .rpc("eth_sendRawTransaction", || self.http_provider.send_raw_transaction(tx_bytes.clone()))
+            .await?;
         
-        Ok(tx_hash)
+        Ok(*pending_tx)
     }
 
     /// Send transaction
-    pub async fn send_transaction(&self, tx: TransactionRequest) -> Result<PendingTransaction<Http>> {
-        let timer = MetricsTimer::new("blockchain_request_duration_seconds");
-        let pending_tx = self.http_provider.send_transaction(tx, None).await?;
-        timer.stop();
+    pub async fn send_transaction(&self, tx: TypedTransaction) -> Result<PendingTransaction<Http>> {
+        let pending_tx = self
+            .rpc("eth_sendTransaction", || self.http_provider.send_transaction(tx.clone(), None))
+            .await?;
         
         Ok(pending_tx)
     }
 
+    /// Estimate the gas a transaction uses against the latest state
+    pub async fn estimate_gas(&self, tx: &TypedTransaction) -> Result<U256> {
+        let gas = self.rpc("eth_estimateGas", || self.http_provider.estimate_gas(tx, None)).await?;
+        
+        Ok(gas)
+    }
+
+    /// Access list the node suggests for a transaction and the gas it uses with that list,
+    /// `None` where the node doesn't offer `eth_createAccessList`
+    pub async fn create_access_list(&self, tx: &TypedTransaction) -> Result<Option<AccessListWithGasUsed>> {
+        if self.access_lists_unsupported.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        
+        match self
+            .rpc("eth_createAccessList", || self.http_provider.create_access_list(tx, None))
+            .await
+        {
+            Ok(access_list) => Ok(Some(access_list)),
+            Err(e) if e.downcast_ref::<ProviderError>().map_or(false, is_method_not_found) => {
+                info!("Node does not support eth_createAccessList, transactions go without access lists");
+                self.access_lists_unsupported.store(true, Ordering::Relaxed);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
     /// Wait for transaction to be confirmed
     pub async fn wait_for_transaction(&self, tx_hash: H256) -> Result<TransactionReceipt> {
-        let timer = MetricsTimer::new("blockchain_request_duration_seconds");
         let receipt = self
-            .http_provider
             .get_transaction_receipt(tx_hash)
             .await?
             .ok_or_else(|| anyhow!("Transaction receipt not found"))?;
-        timer.stop();
         
         // Check confirmation count
         let current_block = self.get_block_number().await?;
@@ -152,20 +962,74 @@ impl BlockchainClient {
     }
 
     /// Subscribe to new blocks
-    pub async fn subscribe_blocks(&self) -> Result<ethers::providers::SubscriptionStream<Ws, Block<Transaction>>> {
+    ///
+    /// Heads carry no transactions; fetch the body with `get_block_with_transactions`.
+    pub async fn subscribe_blocks(&self) -> Result<ethers::providers::SubscriptionStream<Ws, Block<H256>>> {
         Ok(self.ws_provider.subscribe_blocks().await?)
     }
 
-    /// Subscribe to pending transactions
-    pub async fn subscribe_pending_txs(&self) -> Result<ethers::providers::SubscriptionStream<Ws, H256>> {
-        Ok(self.ws_provider.subscribe_pending_txs().await?)
+    /// Subscribe to pending transactions, with their bodies where the node offers it
+    ///
+    /// Tries `alchemy_pendingTransactions`, then `newPendingTransactions` with the full-body
+    /// flag, then plain hashes. A feed the node rejects isn't tried again; any other failure is
+    /// returned so the caller retries the same feed.
+    pub async fn subscribe_pending_txs(
+        &self,
+    ) -> Result<(ethers::providers::SubscriptionStream<Ws, PendingTxAnnouncement>, PendingTxFeed)> {
+        if !self.alchemy_pending_txs_unsupported.load(Ordering::Relaxed) {
+            match self
+                .ws_provider
+                .subscribe(("alchemy_pendingTransactions", json!({ "hashesOnly": false })))
+                .await
+            {
+                Ok(stream) => return Ok((stream, PendingTxFeed::Alchemy)),
+                Err(e) if is_subscription_unsupported(&e) => {
+                    debug!("Node does not offer alchemy_pendingTransactions: {}", e);
+                    self.alchemy_pending_txs_unsupported.store(true, Ordering::Relaxed);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        
+        if !self.full_pending_txs_unsupported.load(Ordering::Relaxed) {
+            match self.ws_provider.subscribe(("newPendingTransactions", true)).await {
+                Ok(stream) => return Ok((stream, PendingTxFeed::FullBodies)),
+                Err(e) if is_subscription_unsupported(&e) => {
+                    info!("Node does not offer full-body pending transactions, subscribing to hashes");
+                    self.full_pending_txs_unsupported.store(true, Ordering::Relaxed);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        
+        let stream = self.ws_provider.subscribe(["newPendingTransactions"]).await?;
+        Ok((stream, PendingTxFeed::Hashes))
+    }
+
+    /// Subscribe to the logs matching a filter as they are mined, and removed on reorgs
+    pub async fn subscribe_logs(&self, filter: &Filter) -> Result<ethers::providers::SubscriptionStream<Ws, Log>> {
+        Ok(self.ws_provider.subscribe_logs(filter).await?)
+    }
+
+    /// Get the latest block with transactions from the fallback provider, if one is configured
+    pub async fn get_fallback_head(&self) -> Result<Option<Block<Transaction>>> {
+        let provider = match &self.fallback_provider {
+            Some(provider) => provider,
+            None => return Ok(None),
+        };
+        
+        let block = self
+            .rpc_on(&self.fallback_limiter, "eth_getBlockByNumber:full", 1, || {
+                provider.get_block_with_txs(BlockNumber::Latest)
+            })
+            .await?;
+        
+        Ok(block)
     }
 
     /// Get the current gas price
     pub async fn get_gas_price(&self) -> Result<U256> {
-        let timer = MetricsTimer::new("blockchain_request_duration_seconds");
-        let gas_price = self.http_provider.get_gas_price().await?;
-        timer.stop();
+        let gas_price = self.rpc("eth_gasPrice", || self.http_provider.get_gas_price()).await?;
         
         // Cache the gas price
         self.current_gas_price.store(gas_price.as_u64(), Ordering::Relaxed);
@@ -207,37 +1071,56 @@ impl BlockchainClient {
         };
         
         // Execute call
-        let timer = MetricsTimer::new("blockchain_request_duration_seconds");
-        let result = self.http_provider.call(&tx, block).await?;
-        timer.stop();
+        let tx: TypedTransaction = tx.into();
+        let result = self
+            .rpc("eth_call", || self.http_provider.call(&tx, block.map(Into::into)))
+            .await?;
         
         Ok(result)
     }
 
+    /// Decode calldata sent to a verified contract into the function called and its arguments
+    ///
+    /// `None` when the contract's ABI has no function with the calldata's selector.
+    pub async fn decode_calldata(
+        &self,
+        address: Address,
+        data: &[u8],
+    ) -> Result<Option<(ethers::abi::Function, Vec<Token>)>> {
+        if data.len() < 4 {
+            return Ok(None);
+        }
+        
+        let contract = self.get_contract(address).await?;
+        let function = match contract.functions().find(|function| function.short_signature() == data[..4]) {
+            Some(function) => function.clone(),
+            None => return Ok(None),
+        };
+        let args = function
+            .decode_input(&data[4..])
+            .map_err(|e| anyhow!("Failed to decode call to {}: {}", function.name, e))?;
+        
+        Ok(Some((function, args)))
+    }
+
     /// Get a contract instance with ABI
+    ///
+    /// ABIs come from the cache, then the ABI store and verified contract registries.
     async fn get_contract(&self, address: Address) -> Result<ethers::abi::Contract> {
         // Check cache first
-        {
-            let cache = self.abi_cache.read().await;
-            if let Some(contract) = cache.get(&address) {
-                return Ok(contract.clone());
-            }
+        if let Some(contract) = self.abi_cache.get(&address) {
+            return Ok(contract);
         }
         
-        // If not in cache, fetch the ABI
-        // In a real implementation, this would use a registry or fetch from Etherscan
-        // For this example, we'll just create a dummy ABI
-        let contract = ethers::abi::Contract::load(
-            &[] as &[u8], // This would be the real ABI in production
-        )
-        .map_err(|e| anyhow!("Failed to load contract ABI: {}", e))?;
+        let contract = self
+            .abi_resolver
+            .resolve(address)
+            .await?
+            .ok_or_else(|| anyhow!("Contract {:?} is not verified on Sourcify or Etherscan", address))?;
         
         // Cache the contract
-        {
-            let mut cache = self.abi_cache.write().await;
-            cache.insert(address, contract.clone());
-        }
+        self.abi_cache.insert(address, contract.clone());
         
         Ok(contract)
     }
-} 
\ No newline at end of file
+}
\ No newline at end of file
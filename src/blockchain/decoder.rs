@@ -0,0 +1,700 @@
+use ethers::{
+    abi::{self, ParamType, Token},
+    types::{Address, U256},
+    utils::id,
+};
+use std::{collections::HashMap, sync::OnceLock};
+
+/// Universal Router command type, the low six bits of a command byte
+const COMMAND_TYPE_MASK: u8 = 0x3f;
+const V3_SWAP_EXACT_IN: u8 = 0x00;
+const V3_SWAP_EXACT_OUT: u8 = 0x01;
+const SWEEP: u8 = 0x04;
+const TRANSFER: u8 = 0x05;
+const V2_SWAP_EXACT_IN: u8 = 0x08;
+const V2_SWAP_EXACT_OUT: u8 = 0x09;
+const WRAP_ETH: u8 = 0x0b;
+const UNWRAP_WETH: u8 = 0x0c;
+
+/// Bytes of a token and of a fee in an encoded V3 path
+const PATH_TOKEN_BYTES: usize = 20;
+const PATH_FEE_BYTES: usize = 3;
+
+/// Which side of a swap the caller fixed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapKind {
+    /// Sells an exact amount, accepting at least `amount_out`
+    ExactInput,
+    /// Buys an exact amount, paying at most `amount_in`
+    ExactOutput,
+}
+
+/// A swap along a Uniswap V2 path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct V2Swap {
+    pub kind: SwapKind,
+    /// Tokens from the one sold to the one bought
+    pub path: Vec<Address>,
+    /// The amount sold, or the most that may be sold for an exact output
+    pub amount_in: U256,
+    /// The amount bought, or the least that may be bought for an exact input
+    pub amount_out: U256,
+    pub recipient: Address,
+    /// Missing when the router checks it for the whole call, as the Universal Router does
+    pub deadline: Option<U256>,
+    /// Called through the variant for tokens that take a fee on transfer
+    pub fee_on_transfer: bool,
+}
+
+/// A swap along a Uniswap V3 path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct V3Swap {
+    pub kind: SwapKind,
+    /// Tokens from the one sold to the one bought
+    pub path: Vec<Address>,
+    /// Fee tier of each pool along the path, in hundredths of a basis point
+    pub fees: Vec<u32>,
+    /// The amount sold, or the most that may be sold for an exact output
+    pub amount_in: U256,
+    /// The amount bought, or the least that may be bought for an exact input
+    pub amount_out: U256,
+    pub recipient: Address,
+    /// Missing for SwapRouter02 and the Universal Router, which check it for the whole call
+    pub deadline: Option<U256>,
+    /// Price at which a single-pool swap stops early, zero for none
+    pub sqrt_price_limit_x96: U256,
+}
+
+/// One command of a Universal Router `execute`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouterCommand {
+    V2Swap(V2Swap),
+    V3Swap(V3Swap),
+    WrapEth { recipient: Address, amount_min: U256 },
+    UnwrapWeth { recipient: Address, amount_min: U256 },
+    Sweep { token: Address, recipient: Address, amount_min: U256 },
+    Transfer { token: Address, recipient: Address, value: U256 },
+    /// A command we don't decode, such as a Permit2 call or an NFT purchase, by its type
+    Other(u8),
+}
+
+/// A swap through the 1inch aggregation router
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OneInchSwap {
+    /// Missing for direct pool swaps, which name the pools rather than the tokens
+    pub src_token: Option<Address>,
+    pub dst_token: Option<Address>,
+    pub amount: U256,
+    pub min_return: U256,
+    /// Missing when the proceeds go to the caller
+    pub recipient: Option<Address>,
+    /// Pools of a direct swap in order, empty for swaps through an executor
+    pub pools: Vec<Address>,
+}
+
+/// A call to a token or router we know, decoded from its calldata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodedCall {
+    Transfer { to: Address, amount: U256 },
+    TransferFrom { from: Address, to: Address, amount: U256 },
+    /// `approve`, or `increaseAllowance` when `increase` is set
+    Approve { spender: Address, amount: U256, increase: bool },
+    /// ERC-721 or ERC-1155 operator grant over a whole collection
+    ApprovalForAll { operator: Address, approved: bool },
+    UniswapV2(V2Swap),
+    UniswapV3(V3Swap),
+    /// Calls batched through a router's `multicall`, `None` for those we don't decode
+    Multicall { calls: Vec<Option<DecodedCall>>, deadline: Option<U256> },
+    UniversalRouter { commands: Vec<RouterCommand>, deadline: Option<U256> },
+    OneInch(OneInchSwap),
+}
+
+impl DecodedCall {
+    /// Decode calldata sent with `value` wei, `None` for an unknown selector or malformed arguments
+    ///
+    /// Calls are matched by selector alone, so check the target before trusting that a swap went
+    /// through the router it names.
+    pub fn decode(input: &[u8], value: U256) -> Option<Self> {
+        if input.len() < 4 {
+            return None;
+        }
+        let (selector, args) = input.split_at(4);
+        let method = *methods().get(selector)?;
+        
+        match method {
+            Method::Transfer => match decode_args(&[ParamType::Address, ParamType::Uint(256)], args)?.as_slice() {
+                [Token::Address(to), Token::Uint(amount)] => Some(Self::Transfer { to: *to, amount: *amount }),
+                _ => None,
+            },
+            Method::TransferFrom => {
+                let tokens = decode_args(&[ParamType::Address, ParamType::Address, ParamType::Uint(256)], args)?;
+                match tokens.as_slice() {
+                    [Token::Address(from), Token::Address(to), Token::Uint(amount)] => Some(Self::TransferFrom {
+                        from: *from,
+                        to: *to,
+                        amount: *amount,
+                    }),
+                    _ => None,
+                }
+            }
+            Method::Approve { increase } => {
+                match decode_args(&[ParamType::Address, ParamType::Uint(256)], args)?.as_slice() {
+                    [Token::Address(spender), Token::Uint(amount)] => Some(Self::Approve {
+                        spender: *spender,
+                        amount: *amount,
+                        increase,
+                    }),
+                    _ => None,
+                }
+            }
+            Method::ApprovalForAll => match decode_args(&[ParamType::Address, ParamType::Bool], args)?.as_slice() {
+                [Token::Address(operator), Token::Bool(approved)] => Some(Self::ApprovalForAll {
+                    operator: *operator,
+                    approved: *approved,
+                }),
+                _ => None,
+            },
+            Method::V2 {
+                kind,
+                eth_in,
+                fee_on_transfer,
+            } => decode_v2_router(kind, eth_in, fee_on_transfer, args, value).map(Self::UniswapV2),
+            Method::V3Single { kind, deadline } => decode_v3_single(kind, deadline, args).map(Self::UniswapV3),
+            Method::V3Path { kind, deadline } => decode_v3_path(kind, deadline, args).map(Self::UniswapV3),
+            Method::Multicall { deadline } => decode_multicall(deadline, args, value),
+            Method::UniversalRouter { deadline } => decode_universal_router(deadline, args),
+            Method::OneInchSwap { permit } => decode_one_inch_swap(permit, args).map(Self::OneInch),
+            Method::OneInchUnoswap => decode_one_inch_unoswap(args).map(Self::OneInch),
+            Method::OneInchUniswapV3 => decode_one_inch_uniswap_v3(args).map(Self::OneInch),
+        }
+    }
+    
+    /// The Uniswap swaps this call makes, in order, looking inside multicalls and router commands
+    pub fn uniswap_swaps(&self) -> Vec<UniswapSwap<'_>> {
+        match self {
+            Self::UniswapV2(swap) => vec![UniswapSwap::V2(swap)],
+            Self::UniswapV3(swap) => vec![UniswapSwap::V3(swap)],
+            Self::Multicall { calls, .. } => calls
+                .iter()
+                .flatten()
+                .flat_map(|call| call.uniswap_swaps())
+                .collect(),
+            Self::UniversalRouter { commands, .. } => commands
+                .iter()
+                .filter_map(|command| match command {
+                    RouterCommand::V2Swap(swap) => Some(UniswapSwap::V2(swap)),
+                    RouterCommand::V3Swap(swap) => Some(UniswapSwap::V3(swap)),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A swap through either Uniswap version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UniswapSwap<'a> {
+    V2(&'a V2Swap),
+    V3(&'a V3Swap),
+}
+
+/// What a selector calls, and how to read its arguments
+#[derive(Debug, Clone, Copy)]
+enum Method {
+    Transfer,
+    TransferFrom,
+    Approve { increase: bool },
+    ApprovalForAll,
+    /// Uniswap V2 router swap, `eth_in` for those paying with the call value
+    V2 { kind: SwapKind, eth_in: bool, fee_on_transfer: bool },
+    /// SwapRouter takes a deadline in the swap parameters, SwapRouter02 doesn't
+    V3Single { kind: SwapKind, deadline: bool },
+    V3Path { kind: SwapKind, deadline: bool },
+    Multicall { deadline: bool },
+    UniversalRouter { deadline: bool },
+    /// 1inch V5 takes a permit with the swap, V6 doesn't
+    OneInchSwap { permit: bool },
+    OneInchUnoswap,
+    OneInchUniswapV3,
+}
+
+const METHODS: &[(&str, Method)] = &[
+    ("transfer(address,uint256)", Method::Transfer),
+    ("transferFrom(address,address,uint256)", Method::TransferFrom),
+    ("approve(address,uint256)", Method::Approve { increase: false }),
+    ("increaseAllowance(address,uint256)", Method::Approve { increase: true }),
+    ("setApprovalForAll(address,bool)", Method::ApprovalForAll),
+    (
+        "swapExactTokensForTokens(uint256,uint256,address[],address,uint256)",
+        Method::V2 { kind: SwapKind::ExactInput, eth_in: false, fee_on_transfer: false },
+    ),
+    (
+        "swapExactTokensForETH(uint256,uint256,address[],address,uint256)",
+        Method::V2 { kind: SwapKind::ExactInput, eth_in: false, fee_on_transfer: false },
+    ),
+    (
+        "swapExactETHForTokens(uint256,address[],address,uint256)",
+        Method::V2 { kind: SwapKind::ExactInput, eth_in: true, fee_on_transfer: false },
+    ),
+    (
+        "swapTokensForExactTokens(uint256,uint256,address[],address,uint256)",
+        Method::V2 { kind: SwapKind::ExactOutput, eth_in: false, fee_on_transfer: false },
+    ),
+    (
+        "swapTokensForExactETH(uint256,uint256,address[],address,uint256)",
+        Method::V2 { kind: SwapKind::ExactOutput, eth_in: false, fee_on_transfer: false },
+    ),
+    (
+        "swapETHForExactTokens(uint256,address[],address,uint256)",
+        Method::V2 { kind: SwapKind::ExactOutput, eth_in: true, fee_on_transfer: false },
+    ),
+    (
+        "swapExactTokensForTokensSupportingFeeOnTransferTokens(uint256,uint256,address[],address,uint256)",
+        Method::V2 { kind: SwapKind::ExactInput, eth_in: false, fee_on_transfer: true },
+    ),
+    (
+        "swapExactTokensForETHSupportingFeeOnTransferTokens(uint256,uint256,address[],address,uint256)",
+        Method::V2 { kind: SwapKind::ExactInput, eth_in: false, fee_on_transfer: true },
+    ),
+    (
+        "swapExactETHForTokensSupportingFeeOnTransferTokens(uint256,address[],address,uint256)",
+        Method::V2 { kind: SwapKind::ExactInput, eth_in: true, fee_on_transfer: true },
+    ),
+    (
+        "exactInputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))",
+        Method::V3Single { kind: SwapKind::ExactInput, deadline: true },
+    ),
+    (
+        "exactInputSingle((address,address,uint24,address,uint256,uint256,uint160))",
+        Method::V3Single { kind: SwapKind::ExactInput, deadline: false },
+    ),
+    (
+        "exactOutputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))",
+        Method::V3Single { kind: SwapKind::ExactOutput, deadline: true },
+    ),
+    (
+        "exactOutputSingle((address,address,uint24,address,uint256,uint256,uint160))",
+        Method::V3Single { kind: SwapKind::ExactOutput, deadline: false },
+    ),
+    (
+        "exactInput((bytes,address,uint256,uint256,uint256))",
+        Method::V3Path { kind: SwapKind::ExactInput, deadline: true },
+    ),
+    (
+        "exactInput((bytes,address,uint256,uint256))",
+        Method::V3Path { kind: SwapKind::ExactInput, deadline: false },
+    ),
+    (
+        "exactOutput((bytes,address,uint256,uint256,uint256))",
+        Method::V3Path { kind: SwapKind::ExactOutput, deadline: true },
+    ),
+    (
+        "exactOutput((bytes,address,uint256,uint256))",
+        Method::V3Path { kind: SwapKind::ExactOutput, deadline: false },
+    ),
+    ("multicall(bytes[])", Method::Multicall { deadline: false }),
+    ("multicall(uint256,bytes[])", Method::Multicall { deadline: true }),
+    ("execute(bytes,bytes[],uint256)", Method::UniversalRouter { deadline: true }),
+    ("execute(bytes,bytes[])", Method::UniversalRouter { deadline: false }),
+    (
+        "swap(address,(address,address,address,address,uint256,uint256,uint256),bytes,bytes)",
+        Method::OneInchSwap { permit: true },
+    ),
+    (
+        "swap(address,(address,address,address,address,uint256,uint256,uint256),bytes)",
+        Method::OneInchSwap { permit: false },
+    ),
+    ("unoswap(address,uint256,uint256,uint256[])", Method::OneInchUnoswap),
+    ("uniswapV3Swap(uint256,uint256,uint256[])", Method::OneInchUniswapV3),
+];
+
+/// Known methods by selector, hashed once
+fn methods() -> &'static HashMap<[u8; 4], Method> {
+    static METHODS_BY_SELECTOR: OnceLock<HashMap<[u8; 4], Method>> = OnceLock::new();
+    METHODS_BY_SELECTOR.get_or_init(|| {
+        METHODS
+            .iter()
+            .map(|(signature, method)| (id(signature), *method))
+            .collect()
+    })
+}
+
+fn decode_args(types: &[ParamType], args: &[u8]) -> Option<Vec<Token>> {
+    abi::decode(types, args).ok()
+}
+
+fn address_array() -> ParamType {
+    ParamType::Array(Box::new(ParamType::Address))
+}
+
+fn addresses(token: &Token) -> Option<Vec<Address>> {
+    match token {
+        Token::Array(items) => items.iter().map(|item| item.clone().into_address()).collect(),
+        _ => None,
+    }
+}
+
+/// Swap through the V2 router, paid with the call value when `eth_in`
+fn decode_v2_router(
+    kind: SwapKind,
+    eth_in: bool,
+    fee_on_transfer: bool,
+    args: &[u8],
+    value: U256,
+) -> Option<V2Swap> {
+    let (fixed, bound, path, recipient, deadline) = if eth_in {
+        let types = [ParamType::Uint(256), address_array(), ParamType::Address, ParamType::Uint(256)];
+        match decode_args(&types, args)?.as_slice() {
+            [Token::Uint(amount), path, Token::Address(recipient), Token::Uint(deadline)] => {
+                (*amount, value, addresses(path)?, *recipient, *deadline)
+            }
+            _ => return None,
+        }
+    } else {
+        let types = [
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+            address_array(),
+            ParamType::Address,
+            ParamType::Uint(256),
+        ];
+        match decode_args(&types, args)?.as_slice() {
+            [Token::Uint(fixed), Token::Uint(bound), path, Token::Address(recipient), Token::Uint(deadline)] => {
+                (*fixed, *bound, addresses(path)?, *recipient, *deadline)
+            }
+            _ => return None,
+        }
+    };
+    if path.len() < 2 {
+        return None;
+    }
+    
+    // The fixed amount comes first, and swaps paid in ETH take the call value as their input
+    let (amount_in, amount_out) = if eth_in || kind == SwapKind::ExactOutput {
+        (bound, fixed)
+    } else {
+        (fixed, bound)
+    };
+    
+    Some(V2Swap {
+        kind,
+        path,
+        amount_in,
+        amount_out,
+        recipient,
+        deadline: Some(deadline),
+        fee_on_transfer,
+    })
+}
+
+fn decode_v3_single(kind: SwapKind, deadline: bool, args: &[u8]) -> Option<V3Swap> {
+    let mut types = vec![ParamType::Address, ParamType::Address, ParamType::Uint(24), ParamType::Address];
+    if deadline {
+        types.push(ParamType::Uint(256));
+    }
+    types.extend([ParamType::Uint(256), ParamType::Uint(256), ParamType::Uint(160)]);
+    
+    let mut fields = decode_args(&[ParamType::Tuple(types)], args)?.pop()?.into_tuple()?.into_iter();
+    let token_in = fields.next()?.into_address()?;
+    let token_out = fields.next()?.into_address()?;
+    let fee = fields.next()?.into_uint()?.low_u32();
+    let recipient = fields.next()?.into_address()?;
+    let deadline = if deadline { Some(fields.next()?.into_uint()?) } else { None };
+    let fixed = fields.next()?.into_uint()?;
+    let bound = fields.next()?.into_uint()?;
+    let sqrt_price_limit_x96 = fields.next()?.into_uint()?;
+    let (amount_in, amount_out) = match kind {
+        SwapKind::ExactInput => (fixed, bound),
+        SwapKind::ExactOutput => (bound, fixed),
+    };
+    
+    Some(V3Swap {
+        kind,
+        path: vec![token_in, token_out],
+        fees: vec![fee],
+        amount_in,
+        amount_out,
+        recipient,
+        deadline,
+        sqrt_price_limit_x96,
+    })
+}
+
+fn decode_v3_path(kind: SwapKind, deadline: bool, args: &[u8]) -> Option<V3Swap> {
+    let mut types = vec![ParamType::Bytes, ParamType::Address];
+    if deadline {
+        types.push(ParamType::Uint(256));
+    }
+    types.extend([ParamType::Uint(256), ParamType::Uint(256)]);
+    
+    let mut fields = decode_args(&[ParamType::Tuple(types)], args)?.pop()?.into_tuple()?.into_iter();
+    let path = fields.next()?.into_bytes()?;
+    let recipient = fields.next()?.into_address()?;
+    let deadline = if deadline { Some(fields.next()?.into_uint()?) } else { None };
+    let fixed = fields.next()?.into_uint()?;
+    let bound = fields.next()?.into_uint()?;
+    
+    v3_swap(kind, &path, fixed, bound, recipient, deadline)
+}
+
+/// A V3 swap along an encoded path, which exact-output swaps give from the token bought
+fn v3_swap(
+    kind: SwapKind,
+    encoded_path: &[u8],
+    fixed: U256,
+    bound: U256,
+    recipient: Address,
+    deadline: Option<U256>,
+) -> Option<V3Swap> {
+    let (mut path, mut fees) = decode_v3_path_bytes(encoded_path)?;
+    let (amount_in, amount_out) = match kind {
+        SwapKind::ExactInput => (fixed, bound),
+        SwapKind::ExactOutput => {
+            path.reverse();
+            fees.reverse();
+            (bound, fixed)
+        }
+    };
+    
+    Some(V3Swap {
+        kind,
+        path,
+        fees,
+        amount_in,
+        amount_out,
+        recipient,
+        deadline,
+        sqrt_price_limit_x96: U256::zero(),
+    })
+}
+
+/// Tokens and fees of a path packed as token, fee, token, ... with 20-byte tokens and 3-byte fees
+fn decode_v3_path_bytes(encoded: &[u8]) -> Option<(Vec<Address>, Vec<u32>)> {
+    let hop = PATH_TOKEN_BYTES + PATH_FEE_BYTES;
+    if encoded.len() < PATH_TOKEN_BYTES + hop || (encoded.len() - PATH_TOKEN_BYTES) % hop != 0 {
+        return None;
+    }
+    
+    let mut path = vec![Address::from_slice(&encoded[..PATH_TOKEN_BYTES])];
+    let mut fees = Vec::new();
+    for chunk in encoded[PATH_TOKEN_BYTES..].chunks(hop) {
+        let (fee, token) = chunk.split_at(PATH_FEE_BYTES);
+        fees.push(u32::from_be_bytes([0, fee[0], fee[1], fee[2]]));
+        path.push(Address::from_slice(token));
+    }
+    
+    Some((path, fees))
+}
+
+fn decode_multicall(deadline: bool, args: &[u8], value: U256) -> Option<DecodedCall> {
+    let calls_type = ParamType::Array(Box::new(ParamType::Bytes));
+    let mut tokens = if deadline {
+        decode_args(&[ParamType::Uint(256), calls_type], args)?
+    } else {
+        decode_args(&[calls_type], args)?
+    }
+    .into_iter();
+    let deadline = if deadline { Some(tokens.next()?.into_uint()?) } else { None };
+    
+    // Every call sees the whole value, as the router spends it from its own balance
+    let calls = tokens
+        .next()?
+        .into_array()?
+        .into_iter()
+        .map(|call| call.into_bytes().map(|call| DecodedCall::decode(&call, value)))
+        .collect::<Option<_>>()?;
+    
+    Some(DecodedCall::Multicall { calls, deadline })
+}
+
+fn decode_universal_router(deadline: bool, args: &[u8]) -> Option<DecodedCall> {
+    let mut types = vec![ParamType::Bytes, ParamType::Array(Box::new(ParamType::Bytes))];
+    if deadline {
+        types.push(ParamType::Uint(256));
+    }
+    let mut tokens = decode_args(&types, args)?.into_iter();
+    let commands = tokens.next()?.into_bytes()?;
+    let inputs = tokens.next()?.into_array()?;
+    let deadline = if deadline { Some(tokens.next()?.into_uint()?) } else { None };
+    if commands.len() != inputs.len() {
+        return None;
+    }
+    
+    let commands = commands
+        .iter()
+        .zip(inputs)
+        .map(|(command, input)| decode_router_command(command & COMMAND_TYPE_MASK, &input.into_bytes()?))
+        .collect::<Option<_>>()?;
+    
+    Some(DecodedCall::UniversalRouter { commands, deadline })
+}
+
+fn decode_router_command(command: u8, input: &[u8]) -> Option<RouterCommand> {
+    match command {
+        V3_SWAP_EXACT_IN | V3_SWAP_EXACT_OUT => {
+            let types = [
+                ParamType::Address,
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Bytes,
+                ParamType::Bool,
+            ];
+            match decode_args(&types, input)?.as_slice() {
+                [Token::Address(recipient), Token::Uint(fixed), Token::Uint(bound), Token::Bytes(path), _] => {
+                    let kind = match command {
+                        V3_SWAP_EXACT_IN => SwapKind::ExactInput,
+                        _ => SwapKind::ExactOutput,
+                    };
+                    v3_swap(kind, path, *fixed, *bound, *recipient, None).map(RouterCommand::V3Swap)
+                }
+                _ => None,
+            }
+        }
+        V2_SWAP_EXACT_IN | V2_SWAP_EXACT_OUT => {
+            let types = [
+                ParamType::Address,
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                address_array(),
+                ParamType::Bool,
+            ];
+            match decode_args(&types, input)?.as_slice() {
+                [Token::Address(recipient), Token::Uint(fixed), Token::Uint(bound), path, _] => {
+                    let path = addresses(path)?;
+                    if path.len() < 2 {
+                        return None;
+                    }
+                    // Unlike V3 paths, V2 paths run from the token sold in both directions
+                    let (kind, amount_in, amount_out) = if command == V2_SWAP_EXACT_IN {
+                        (SwapKind::ExactInput, *fixed, *bound)
+                    } else {
+                        (SwapKind::ExactOutput, *bound, *fixed)
+                    };
+                    Some(RouterCommand::V2Swap(V2Swap {
+                        kind,
+                        path,
+                        amount_in,
+                        amount_out,
+                        recipient: *recipient,
+                        deadline: None,
+                        fee_on_transfer: false,
+                    }))
+                }
+                _ => None,
+            }
+        }
+        WRAP_ETH | UNWRAP_WETH => {
+            match decode_args(&[ParamType::Address, ParamType::Uint(256)], input)?.as_slice() {
+                [Token::Address(recipient), Token::Uint(amount_min)] => {
+                    let (recipient, amount_min) = (*recipient, *amount_min);
+                    Some(match command {
+                        WRAP_ETH => RouterCommand::WrapEth { recipient, amount_min },
+                        _ => RouterCommand::UnwrapWeth { recipient, amount_min },
+                    })
+                }
+                _ => None,
+            }
+        }
+        SWEEP | TRANSFER => {
+            let tokens = decode_args(&[ParamType::Address, ParamType::Address, ParamType::Uint(256)], input)?;
+            match tokens.as_slice() {
+                [Token::Address(token), Token::Address(recipient), Token::Uint(amount)] => {
+                    let (token, recipient) = (*token, *recipient);
+                    Some(match command {
+                        SWEEP => RouterCommand::Sweep { token, recipient, amount_min: *amount },
+                        _ => RouterCommand::Transfer { token, recipient, value: *amount },
+                    })
+                }
+                _ => None,
+            }
+        }
+        other => Some(RouterCommand::Other(other)),
+    }
+}
+
+/// Swap through a 1inch executor, described by its tokens and receivers
+fn decode_one_inch_swap(permit: bool, args: &[u8]) -> Option<OneInchSwap> {
+    let description = ParamType::Tuple(vec![
+        ParamType::Address,
+        ParamType::Address,
+        ParamType::Address,
+        ParamType::Address,
+        ParamType::Uint(256),
+        ParamType::Uint(256),
+        ParamType::Uint(256),
+    ]);
+    let mut types = vec![ParamType::Address, description, ParamType::Bytes];
+    if permit {
+        types.push(ParamType::Bytes);
+    }
+    
+    let mut fields = decode_args(&types, args)?.into_iter().nth(1)?.into_tuple()?.into_iter();
+    let src_token = fields.next()?.into_address()?;
+    let dst_token = fields.next()?.into_address()?;
+    let _src_receiver = fields.next()?;
+    let dst_receiver = fields.next()?.into_address()?;
+    
+    Some(OneInchSwap {
+        src_token: Some(src_token),
+        dst_token: Some(dst_token),
+        amount: fields.next()?.into_uint()?,
+        min_return: fields.next()?.into_uint()?,
+        recipient: Some(dst_receiver).filter(|receiver| !receiver.is_zero()),
+        pools: Vec::new(),
+    })
+}
+
+fn decode_one_inch_unoswap(args: &[u8]) -> Option<OneInchSwap> {
+    let types = [
+        ParamType::Address,
+        ParamType::Uint(256),
+        ParamType::Uint(256),
+        ParamType::Array(Box::new(ParamType::Uint(256))),
+    ];
+    match decode_args(&types, args)?.as_slice() {
+        [Token::Address(src_token), Token::Uint(amount), Token::Uint(min_return), pools] => Some(OneInchSwap {
+            src_token: Some(*src_token),
+            dst_token: None,
+            amount: *amount,
+            min_return: *min_return,
+            recipient: None,
+            pools: pool_addresses(pools)?,
+        }),
+        _ => None,
+    }
+}
+
+fn decode_one_inch_uniswap_v3(args: &[u8]) -> Option<OneInchSwap> {
+    let types = [
+        ParamType::Uint(256),
+        ParamType::Uint(256),
+        ParamType::Array(Box::new(ParamType::Uint(256))),
+    ];
+    match decode_args(&types, args)?.as_slice() {
+        [Token::Uint(amount), Token::Uint(min_return), pools] => Some(OneInchSwap {
+            src_token: None,
+            dst_token: None,
+            amount: *amount,
+            min_return: *min_return,
+            recipient: None,
+            pools: pool_addresses(pools)?,
+        }),
+        _ => None,
+    }
+}
+
+/// Pools of a 1inch direct swap, each packed with flags above its address
+fn pool_addresses(pools: &Token) -> Option<Vec<Address>> {
+    match pools {
+        Token::Array(pools) => pools
+            .iter()
+            .map(|pool| {
+                let mut word = [0u8; 32];
+                pool.clone().into_uint()?.to_big_endian(&mut word);
+                Some(Address::from_slice(&word[12..]))
+            })
+            .collect(),
+        _ => None,
+    }
+}
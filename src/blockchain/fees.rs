@@ -0,0 +1,380 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use ethers::types::{Block, BlockNumber, Transaction, U256};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, str::FromStr, sync::Arc};
+use tracing::debug;
+
+use crate::{api::models, blockchain::BlockchainClient};
+
+/// Priority fee percentiles tracked per block
+const TIP_PERCENTILES: [f64; 4] = [10.0, 50.0, 75.0, 90.0];
+
+/// EIP-1559 bounds the base fee change per block to 1/8
+const BASE_FEE_CHANGE_DENOMINATOR: u64 = 8;
+
+/// EIP-1559 targets half of the gas limit
+const ELASTICITY_MULTIPLIER: u64 = 2;
+
+/// How quickly a transaction should land, trading fee for inclusion probability
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Urgency {
+    Low,
+    Normal,
+    High,
+    Immediate,
+}
+
+impl Urgency {
+    /// Every urgency, least urgent first
+    pub const ALL: [Urgency; 4] = [Self::Low, Self::Normal, Self::High, Self::Immediate];
+    
+    /// Index into `TIP_PERCENTILES`
+    fn percentile_index(&self) -> usize {
+        match self {
+            Self::Low => 0,
+            Self::Normal => 1,
+            Self::High => 2,
+            Self::Immediate => 3,
+        }
+    }
+    
+    /// Consecutive full blocks of base fee growth the max fee absorbs
+    fn headroom_blocks(&self) -> u32 {
+        match self {
+            Self::Low => 1,
+            Self::Normal => 3,
+            Self::High => 6,
+            Self::Immediate => 8,
+        }
+    }
+}
+
+impl FromStr for Urgency {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "low" => Ok(Self::Low),
+            "normal" => Ok(Self::Normal),
+            "high" => Ok(Self::High),
+            "immediate" => Ok(Self::Immediate),
+            other => Err(anyhow!("Unknown urgency {}", other)),
+        }
+    }
+}
+
+/// Fees to set on an EIP-1559 transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeSuggestion {
+    pub urgency: Urgency,
+    /// Predicted base fee of the next block
+    #[serde(with = "models::wei")]
+    pub next_base_fee: U256,
+    #[serde(with = "models::wei")]
+    pub max_priority_fee_per_gas: U256,
+    #[serde(with = "models::wei")]
+    pub max_fee_per_gas: U256,
+    /// Latest block the suggestion is based on
+    pub based_on_block: u64,
+}
+
+/// Tip paid at one percentile of a block's transactions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorityFeePercentile {
+    pub percentile: f64,
+    /// Median across the window of each block's tip at this percentile
+    #[serde(with = "models::wei")]
+    pub fee: U256,
+}
+
+/// Fee market as of the latest block, pushed on the `gas_price` topic and served at `/api/gas/latest`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasPriceReport {
+    /// Latest block the report is based on
+    pub block_number: u64,
+    #[serde(with = "models::wei")]
+    pub base_fee: U256,
+    /// Predicted base fee of the next block
+    #[serde(with = "models::wei")]
+    pub next_base_fee: U256,
+    /// Blob base fee of the next block, absent where the node predates EIP-4844
+    #[serde(with = "models::wei_opt")]
+    pub blob_base_fee: Option<U256>,
+    /// Share of the latest block's gas limit it used
+    pub gas_used_ratio: f64,
+    pub priority_fee_percentiles: Vec<PriorityFeePercentile>,
+    /// Fees for a transaction targeting the next block, by urgency
+    pub next_block: Vec<FeeSuggestion>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Fee data of one block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BlockFees {
+    number: u64,
+    base_fee: U256,
+    gas_used: U256,
+    gas_limit: U256,
+    /// Tips paid at `TIP_PERCENTILES`
+    tips: [U256; 4],
+}
+
+/// Fee window as saved for a warm restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeWindowSnapshot {
+    /// Oldest first
+    blocks: Vec<BlockFees>,
+}
+
+/// EIP-1559 fee estimator over recent blocks
+///
+/// Blocks are recorded by the monitor as they arrive; until enough have been seen the window
+/// is seeded from `eth_feeHistory`.
+#[derive(Clone)]
+pub struct FeeEstimator {
+    /// Blockchain client, seeds history on first use
+    blockchain_client: Arc<BlockchainClient>,
+    /// Recent blocks, oldest first
+    history: Arc<RwLock<VecDeque<BlockFees>>>,
+    /// Blocks kept in the window
+    window: usize,
+    /// Report as of the latest head
+    latest_report: Arc<RwLock<Option<GasPriceReport>>>,
+}
+
+impl FeeEstimator {
+    /// Create a new fee estimator
+    pub fn new(blockchain_client: Arc<BlockchainClient>, window: usize) -> Self {
+        Self {
+            blockchain_client,
+            history: Arc::new(RwLock::new(VecDeque::with_capacity(window))),
+            window: window.max(1),
+            latest_report: Arc::new(RwLock::new(None)),
+        }
+    }
+    
+    /// Record the fees paid in a new block
+    pub fn record_block(&self, block: &Block<Transaction>) {
+        let base_fee = match block.base_fee_per_gas {
+            Some(base_fee) => base_fee,
+            None => return,
+        };
+        
+        let mut tips: Vec<U256> = block
+            .transactions
+            .iter()
+            .map(|tx| effective_tip(tx, base_fee))
+            .collect();
+        tips.sort_unstable();
+        
+        self.push(BlockFees {
+            number: block.number.unwrap_or_default().as_u64(),
+            base_fee,
+            gas_used: block.gas_used,
+            gas_limit: block.gas_limit,
+            tips: TIP_PERCENTILES.map(|percentile| percentile_of(&tips, percentile)),
+        });
+    }
+    
+    /// Recorded blocks, to be restored by the next process
+    pub fn snapshot(&self) -> FeeWindowSnapshot {
+        FeeWindowSnapshot {
+            blocks: self.history.read().iter().cloned().collect(),
+        }
+    }
+    
+    /// Fill an empty window from a snapshot, returning how many blocks were restored
+    pub fn restore(&self, snapshot: FeeWindowSnapshot) -> usize {
+        let mut history = self.history.write();
+        if !history.is_empty() {
+            return 0;
+        }
+        
+        let skip = snapshot.blocks.len().saturating_sub(self.window);
+        history.extend(snapshot.blocks.into_iter().skip(skip));
+        history.len()
+    }
+    
+    /// Suggest EIP-1559 fees for a transaction targeting the next block
+    pub async fn suggest_fees(&self, urgency: Urgency) -> Result<FeeSuggestion> {
+        if self.history.read().is_empty() {
+            self.seed().await?;
+        }
+        
+        suggestion(&self.history.read(), urgency).ok_or_else(|| anyhow!("No fee history available"))
+    }
+    
+    /// Rebuild the gas price report from the window and the node's blob base fee
+    ///
+    /// Called on every head; the last report is kept for `latest_report`.
+    pub async fn refresh_report(&self) -> Result<GasPriceReport> {
+        if self.history.read().is_empty() {
+            self.seed().await?;
+        }
+        let blob_base_fee = match self.blockchain_client.get_blob_base_fee().await {
+            Ok(blob_base_fee) => blob_base_fee,
+            Err(e) => {
+                debug!("Failed to fetch blob base fee: {}", e);
+                None
+            }
+        };
+        
+        let report = {
+            let history = self.history.read();
+            let latest = history.back().ok_or_else(|| anyhow!("No fee history available"))?;
+            let gas_used_ratio = if latest.gas_limit.is_zero() {
+                0.0
+            } else {
+                latest.gas_used.as_u128() as f64 / latest.gas_limit.as_u128() as f64
+            };
+            
+            GasPriceReport {
+                block_number: latest.number,
+                base_fee: latest.base_fee,
+                next_base_fee: next_base_fee(latest.base_fee, latest.gas_used, latest.gas_limit),
+                blob_base_fee,
+                gas_used_ratio,
+                priority_fee_percentiles: TIP_PERCENTILES
+                    .iter()
+                    .enumerate()
+                    .map(|(index, percentile)| PriorityFeePercentile {
+                        percentile: *percentile,
+                        fee: median_tip(&history, index),
+                    })
+                    .collect(),
+                next_block: Urgency::ALL
+                    .iter()
+                    .filter_map(|urgency| suggestion(&history, *urgency))
+                    .collect(),
+                updated_at: Utc::now(),
+            }
+        };
+        
+        *self.latest_report.write() = Some(report.clone());
+        Ok(report)
+    }
+    
+    /// Report as of the latest head, if one arrived since startup
+    pub fn latest_report(&self) -> Option<GasPriceReport> {
+        self.latest_report.read().clone()
+    }
+    
+    /// Fill the window from `eth_feeHistory`
+    async fn seed(&self) -> Result<()> {
+        let history = self
+            .blockchain_client
+            .get_fee_history(self.window as u64, BlockNumber::Latest, &TIP_PERCENTILES)
+            .await?;
+        
+        let oldest = history.oldest_block.as_u64();
+        for (offset, rewards) in history.reward.iter().enumerate() {
+            let base_fee = history.base_fee_per_gas.get(offset);
+            let gas_used_ratio = history.gas_used_ratio.get(offset);
+            let (base_fee, gas_used_ratio) = match (base_fee, gas_used_ratio) {
+                (Some(base_fee), Some(ratio)) => (*base_fee, *ratio),
+                _ => break,
+            };
+            
+            // Fee history reports usage as a ratio, so express it against a nominal limit
+            let gas_limit = U256::from(1_000_000u64);
+            let gas_used = U256::from((gas_used_ratio.clamp(0.0, 1.0) * 1_000_000.0) as u64);
+            
+            let mut tips = [U256::zero(); 4];
+            for (tip, reward) in tips.iter_mut().zip(rewards) {
+                *tip = *reward;
+            }
+            
+            self.push(BlockFees {
+                number: oldest + offset as u64,
+                base_fee,
+                gas_used,
+                gas_limit,
+                tips,
+            });
+        }
+        
+        Ok(())
+    }
+    
+    fn push(&self, fees: BlockFees) {
+        let mut history = self.history.write();
+        
+        // Reorgs and seeding can deliver blocks we already hold
+        if history.back().map_or(false, |latest| latest.number >= fees.number) {
+            history.retain(|block| block.number < fees.number);
+        }
+        history.push_back(fees);
+        while history.len() > self.window {
+            history.pop_front();
+        }
+        
+        if let Some(latest) = history.back() {
+            let next = next_base_fee(latest.base_fee, latest.gas_used, latest.gas_limit);
+            metrics::gauge!("fee_estimator_next_base_fee_gwei", next.low_u128() as f64 / 1e9);
+        }
+    }
+}
+
+/// Fees for a transaction targeting the block after the latest in the window
+fn suggestion(history: &VecDeque<BlockFees>, urgency: Urgency) -> Option<FeeSuggestion> {
+    let latest = history.back()?;
+    let next_base_fee = next_base_fee(latest.base_fee, latest.gas_used, latest.gas_limit);
+    let max_priority_fee_per_gas = median_tip(history, urgency.percentile_index());
+    
+    // Full blocks raise the base fee by 1/8 each
+    let mut headroom = next_base_fee;
+    for _ in 0..urgency.headroom_blocks() {
+        headroom = headroom + headroom / BASE_FEE_CHANGE_DENOMINATOR;
+    }
+    
+    Some(FeeSuggestion {
+        urgency,
+        next_base_fee,
+        max_priority_fee_per_gas,
+        max_fee_per_gas: headroom + max_priority_fee_per_gas,
+        based_on_block: latest.number,
+    })
+}
+
+/// Median across the window of one of `TIP_PERCENTILES`, so one odd block doesn't set the tip
+fn median_tip(history: &VecDeque<BlockFees>, index: usize) -> U256 {
+    let mut tips: Vec<U256> = history.iter().map(|block| block.tips[index]).collect();
+    tips.sort_unstable();
+    tips.get(tips.len() / 2).copied().unwrap_or_default()
+}
+
+/// Tip a transaction pays per gas at the given base fee
+fn effective_tip(tx: &Transaction, base_fee: U256) -> U256 {
+    match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+        (Some(max_fee), Some(max_priority_fee)) => max_priority_fee.min(max_fee.saturating_sub(base_fee)),
+        _ => tx.gas_price.unwrap_or_default().saturating_sub(base_fee),
+    }
+}
+
+/// Nearest-rank percentile of sorted values, zero when empty
+fn percentile_of(sorted: &[U256], percentile: f64) -> U256 {
+    if sorted.is_empty() {
+        return U256::zero();
+    }
+    let rank = ((percentile / 100.0) * (sorted.len() - 1) as f64).round() as usize;
+    sorted[rank.min(sorted.len() - 1)]
+}
+
+/// EIP-1559 base fee of the next block
+pub fn next_base_fee(base_fee: U256, gas_used: U256, gas_limit: U256) -> U256 {
+    let target = gas_limit / ELASTICITY_MULTIPLIER;
+    if target.is_zero() || gas_used == target {
+        return base_fee;
+    }
+    
+    if gas_used > target {
+        let delta = base_fee * (gas_used - target) / target / BASE_FEE_CHANGE_DENOMINATOR;
+        base_fee + delta.max(U256::one())
+    } else {
+        let delta = base_fee * (target - gas_used) / target / BASE_FEE_CHANGE_DENOMINATOR;
+        base_fee.saturating_sub(delta)
+    }
+}
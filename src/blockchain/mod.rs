@@ -1,23 +1,36 @@
 use anyhow::{Context, Result};
-use ethers::{
-    providers::{Http, Provider, Ws},
-    signers::LocalWallet,
-};
+use ethers::providers::{Http, Provider, Ws};
 use std::sync::Arc;
 use tracing::info;
 
-use crate::config::BlockchainConfig;
+use crate::{
+    config::{BlockchainConfig, CacheConfig},
+    database::DbPool,
+};
 
+pub mod abi;
+pub mod bloxroute;
 pub mod client;
+pub mod decoder;
+pub mod fees;
 pub mod monitor;
+pub mod rate_limit;
+pub mod signer;
 pub mod transaction;
 pub mod block;
 pub mod simulator;
 
+pub use abi::AbiResolver;
 pub use client::BlockchainClient;
 
 /// Create a new blockchain client from configuration
-pub async fn create_client(config: &BlockchainConfig) -> Result<Arc<BlockchainClient>> {
+///
+/// Contract ABIs are stored in the database when one is given.
+pub async fn create_client(
+    config: &BlockchainConfig,
+    caches: &CacheConfig,
+    db_pool: Option<DbPool>,
+) -> Result<Arc<BlockchainClient>> {
     info!("Initializing blockchain client");
     
     // Create HTTP provider
@@ -29,15 +42,29 @@ pub async fn create_client(config: &BlockchainConfig) -> Result<Arc<BlockchainCl
         .await
         .context("Failed to connect to WebSocket endpoint")?;
     
+    // Create fallback provider, used only when our head goes stale
+    let fallback_provider = config
+        .fallback_rpc_url
+        .as_ref()
+        .map(|url| Provider::<Http>::try_from(url.as_str()))
+        .transpose()
+        .context("Failed to create fallback HTTP provider")?;
+    
     // Create client
     let client = BlockchainClient::new(
         http_provider,
         ws_provider,
+        fallback_provider,
         config.chain_id,
         config.confirmation_blocks,
+        &caches.abi,
+        AbiResolver::new(config.chain_id, config.abi_sources.clone(), db_pool)?,
+        config.rpc_policies.clone(),
+        config.rate_limits.clone(),
+        config.multicall_address.parse().context("Invalid Multicall3 address")?,
     );
     
     info!("Blockchain client initialized successfully");
     
     Ok(Arc::new(client))
-} 
\ No newline at end of file
+}
\ No newline at end of file
@@ -1,7 +1,15 @@
-use anyhow::Result;
-use ethers::types::{Block, Transaction, H256};
+use anyhow::{anyhow, Result};
+use chrono::{TimeZone, Utc};
+use ethers::types::{Address, Block, Transaction, TransactionReceipt, H256};
 use futures::stream::StreamExt;
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use tokio::{
     sync::mpsc,
     task::JoinHandle,
@@ -10,11 +18,50 @@ use tokio::{
 use tracing::{debug, error, info, warn};
 
 use crate::{
-    blockchain::BlockchainClient,
-    services::ServiceContext,
+    api::models,
+    blockchain::{
+        bloxroute::BloxrouteStream,
+        client::PendingTxAnnouncement,
+        fees::Urgency,
+        rate_limit::{with_priority, RpcPriority},
+        BlockchainClient,
+    },
+    core::{
+        arbitrage::{ArbitrageEngine, ArbitrageOpportunity},
+        liquidation::{LiquidationMonitor, LiquidationOpportunity},
+        opportunities::SandwichOpportunity,
+        strategy::{Opportunity, Strategy},
+    },
+    relay::BidRequest,
+    services::{
+        bundle::Bundle,
+        events::Topic,
+        export::ExportBundle,
+        subsidy::SlotContext,
+        transaction::{TransactionService, TxSource},
+        ServiceContext,
+    },
     utils::metrics::MetricsTimer,
 };
 
+/// Expected interval between new heads, used for the block monitor heartbeat
+const BLOCK_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(12);
+
+/// Expected interval between pending transactions, used for the transaction monitor heartbeat
+const PENDING_TX_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Most pending announcements handled in one batch
+const PENDING_TX_BATCH_SIZE: usize = 100;
+
+/// Most logs of indexed events stored in one batch
+const LOG_BATCH_SIZE: usize = 100;
+
+/// Wait before resubscribing when indexing events failed
+const LOG_CATCH_UP_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Most sandwich bundles submitted for one block
+const MAX_SANDWICH_BUNDLES: usize = 8;
+
 /// Handle for the blockchain monitor
 pub struct BlockchainMonitorHandle {
     shutdown_sender: mpsc::Sender<()>,
@@ -51,8 +98,21 @@ pub async fn start(
     // Channel for shutdown signal
     let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
     
+    // Confirmed transactions are processed behind the head, off the block loop
+    let (confirmed_queue, confirmed_task) = spawn_confirmed_block_worker(blockchain_client.clone(), services.clone());
+    
+    // Fill the gap left while we were offline before following live heads
+    if let Err(e) = catch_up(blockchain_client.as_ref(), services.as_ref(), &confirmed_queue).await {
+        error!("Failed to catch up on missed blocks: {}", e);
+    }
+    
     // Start block monitor
-    let block_task = spawn_block_monitor(blockchain_client.clone(), services.clone(), shutdown_rx.clone());
+    let block_task = spawn_block_monitor(
+        blockchain_client.clone(),
+        services.clone(),
+        confirmed_queue.clone(),
+        shutdown_rx.clone(),
+    );
     
     // Start transaction monitor
     let tx_task = spawn_transaction_monitor(blockchain_client.clone(), services.clone(), shutdown_rx.clone());
@@ -60,12 +120,32 @@ pub async fn start(
     // Start gas price monitor
     let gas_task = spawn_gas_price_monitor(blockchain_client.clone(), services.clone(), shutdown_rx.clone());
     
+    // Start stale head monitor
+    let stale_task = spawn_stale_head_monitor(
+        blockchain_client.clone(),
+        services.clone(),
+        confirmed_queue,
+        shutdown_rx.clone(),
+    );
+    
+    let mut tasks = vec![block_task, tx_task, gas_task, stale_task, confirmed_task];
+    
+    // Start event monitor
+    if services.log_indexer.enabled() {
+        tasks.push(spawn_event_monitor(blockchain_client.clone(), services.clone(), shutdown_rx.clone()));
+    }
+    
+    // Start bloXroute stream monitor
+    if services.config.services.bloxroute_stream.enabled {
+        tasks.push(spawn_bloxroute_monitor(services.clone(), shutdown_rx.clone()));
+    }
+    
     info!("Blockchain monitor started successfully");
     
     // Return handle for shutdown
     Ok(BlockchainMonitorHandle {
         shutdown_sender: shutdown_tx,
-        tasks: vec![block_task, tx_task, gas_task],
+        tasks,
     })
 }
 
@@ -73,10 +153,12 @@ pub async fn start(
 fn spawn_block_monitor(
     blockchain_client: Arc<BlockchainClient>,
     services: Arc<ServiceContext>,
+    confirmed_queue: ConfirmedBlockQueue,
     mut shutdown_rx: mpsc::Receiver<()>,
 ) -> JoinHandle<()> {
     tokio::spawn(async move {
         info!("Block monitor started");
+        services.heartbeats.register("block_monitor", BLOCK_HEARTBEAT_INTERVAL);
         
         let mut retry_count = 0;
         let max_retries = 10;
@@ -89,9 +171,44 @@ fn spawn_block_monitor(
                     
                     loop {
                         tokio::select! {
-                            Some(block) = stream.next() => {
+                            Some(header) = stream.next() => {
+                                services.heartbeats.beat("block_monitor");
+                                let block_number = header.number.unwrap_or_default().as_u64();
+                                let previous_head = services.head_tracker.head_number();
+                                if !services.head_tracker.record_head(block_number, header.hash.unwrap_or_default(), false) {
+                                    debug!("Skipping block already processed from fallback provider");
+                                    continue;
+                                }
+                                
+                                // Heads skipped while the subscription was reconnecting
+                                if let Some(previous_head) = previous_head.filter(|head| block_number > head + 1) {
+                                    if let Err(e) = backfill(
+                                        blockchain_client.as_ref(),
+                                        services.as_ref(),
+                                        &confirmed_queue,
+                                        previous_head + 1,
+                                        block_number - 1,
+                                    ).await {
+                                        error!("Failed to backfill blocks before #{}: {}", block_number, e);
+                                    }
+                                }
+                                
+                                // Heads arrive without transactions, fetch the body before processing
+                                let fetch = blockchain_client.get_block_with_transactions(block_number);
+                                let block = match with_priority(RpcPriority::Critical, fetch).await {
+                                    Ok(Some(block)) => block,
+                                    Ok(None) => {
+                                        warn!("Block #{} not found after its head was announced", block_number);
+                                        continue;
+                                    }
+                                    Err(e) => {
+                                        error!("Failed to fetch block #{}: {}", block_number, e);
+                                        continue;
+                                    }
+                                };
                                 let timer = MetricsTimer::new("block_processing_time_seconds");
-                                if let Err(e) = process_new_block(blockchain_client.as_ref(), services.as_ref(), block).await {
+                                let processing = process_new_block(services.as_ref(), &confirmed_queue, block);
+                                if let Err(e) = with_priority(RpcPriority::Critical, processing).await {
                                     error!("Error processing new block: {}", e);
                                 }
                                 timer.stop();
@@ -120,14 +237,211 @@ fn spawn_block_monitor(
             }
         }
         
+        services.heartbeats.unregister("block_monitor");
         info!("Block monitor stopped");
     })
 }
 
+/// Spawn a task that switches to the fallback provider's head when ours stops advancing
+fn spawn_stale_head_monitor(
+    blockchain_client: Arc<BlockchainClient>,
+    services: Arc<ServiceContext>,
+    confirmed_queue: ConfirmedBlockQueue,
+    mut shutdown_rx: mpsc::Receiver<()>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("Stale head monitor started");
+        let mut interval = interval(Duration::from_secs(
+            services.config.blockchain.slot_duration_seconds.max(1),
+        ));
+        
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if !services.head_tracker.check().await {
+                        continue;
+                    }
+                    
+                    let fetch = blockchain_client.get_fallback_head();
+                    let block = match with_priority(RpcPriority::Critical, fetch).await {
+                        Ok(Some(block)) => block,
+                        Ok(None) => continue,
+                        Err(e) => {
+                            warn!("Failed to fetch head from fallback provider: {}", e);
+                            continue;
+                        }
+                    };
+                    
+                    let block_number = block.number.unwrap_or_default().as_u64();
+                    if !services.head_tracker.record_head(block_number, block.hash.unwrap_or_default(), true) {
+                        continue;
+                    }
+                    
+                    info!("Building on fallback provider head #{}", block_number);
+                    let processing = process_new_block(services.as_ref(), &confirmed_queue, block);
+                    if let Err(e) = with_priority(RpcPriority::Critical, processing).await {
+                        error!("Error processing fallback block: {}", e);
+                    }
+                }
+                _ = shutdown_rx.recv() => {
+                    break;
+                }
+            }
+        }
+        
+        info!("Stale head monitor stopped");
+    })
+}
+
+/// Process the blocks between the last checkpoint and the current head
+///
+/// Blocks the workers had not finished before the restart are picked up again. Blocks older
+/// than `max_block_history` are skipped; the head itself is processed as a live block so
+/// building resumes on it.
+async fn catch_up(
+    blockchain_client: &BlockchainClient,
+    services: &ServiceContext,
+    confirmed_queue: &ConfirmedBlockQueue,
+) -> Result<()> {
+    let last_processed = match services.recovery_service.last_checkpoint_block().await? {
+        Some(block_number) => block_number,
+        None => {
+            info!("No processed blocks recorded, starting from the live head");
+            return Ok(());
+        }
+    };
+    
+    let head = blockchain_client.get_block_number().await?;
+    
+    // Blocks still queued for the workers when we stopped were never booked
+    let window_start = head.saturating_sub(services.config.blockchain.max_block_history);
+    let from = services
+        .processed_blocks
+        .first_unprocessed(window_start, last_processed.min(head))
+        .await?
+        .unwrap_or(last_processed + 1);
+    if from > head {
+        return Ok(());
+    }
+    
+    info!("Catching up from block #{} to head #{}", from, head);
+    if head > from {
+        backfill(blockchain_client, services, confirmed_queue, from, head - 1).await?;
+    }
+    
+    let block = match blockchain_client.get_block_with_transactions(head).await? {
+        Some(block) => block,
+        None => return Ok(()),
+    };
+    if services.head_tracker.record_head(head, block.hash.unwrap_or_default(), false) {
+        process_new_block(services, confirmed_queue, block).await?;
+    }
+    
+    Ok(())
+}
+
+/// Process confirmed blocks `from..=to` that were missed, without building on them
+///
+/// A block the node doesn't return is logged, counted and skipped; it stays unprocessed, so
+/// the next catch-up picks it up again.
+async fn backfill(
+    blockchain_client: &BlockchainClient,
+    services: &ServiceContext,
+    confirmed_queue: &ConfirmedBlockQueue,
+    from: u64,
+    to: u64,
+) -> Result<()> {
+    let max_blocks = services.config.blockchain.max_block_history.max(1);
+    let start = from.max(to.saturating_sub(max_blocks - 1));
+    if start > from {
+        warn!(
+            "Skipping blocks #{} to #{}, beyond max_block_history of {}",
+            from,
+            start - 1,
+            max_blocks
+        );
+    }
+    
+    // Missed blocks can wait, the live head's requests go first
+    let blocks = with_priority(RpcPriority::Bulk, blockchain_client.get_blocks(start..to + 1)).await?;
+    let mut missing = 0;
+    for (block_number, block) in (start..=to).zip(blocks) {
+        let block = match block {
+            Some(block) => block,
+            None => {
+                warn!("Block #{} not found, skipping it in the backfill", block_number);
+                metrics::counter!("blocks_backfill_missing_total", 1);
+                missing += 1;
+                continue;
+            }
+        };
+        
+        debug!("Backfilling block #{}", block_number);
+        record_confirmed_block(services, confirmed_queue, &block).await;
+        metrics::counter!("blocks_backfilled_total", 1);
+    }
+    
+    info!("Backfilled {} blocks up to #{}, {} not found", to + 1 - start - missing, to, missing);
+    Ok(())
+}
+
+/// Bookkeeping every confirmed block gets, whether live or backfilled
+///
+/// In-memory state is updated every time; persisted bookkeeping only by whichever pass
+/// claims the block first.
+async fn record_confirmed_block(
+    services: &ServiceContext,
+    confirmed_queue: &ConfirmedBlockQueue,
+    block: &Block<Transaction>,
+) {
+    let block_number = block.number.unwrap_or_default().as_u64();
+    let block_hash = block.hash.unwrap_or_default();
+    
+    if let Some(base_fee) = block.base_fee_per_gas {
+        services
+            .transaction_service
+            .record_block_fees(base_fee, block.gas_used, block.gas_limit);
+    }
+    services.fee_estimator.record_block(block);
+    
+    // Confirmed transactions stop being candidates before anything is built on this head
+    let included: Vec<H256> = block.transactions.iter().map(|tx| tx.hash).collect();
+    let landed = services.transaction_service.evict_confirmed(&included).await;
+    for candidate in &landed {
+        let candidate = candidate.expose();
+        if let TxSource::Searcher(searcher) = &candidate.source {
+            services
+                .reputation_service
+                .record_landed(searcher, candidate.tx.hash, candidate.profit, block_number);
+        }
+    }
+    services.sandwich_detector.prune(block_number, &included);
+    
+    let claimed = match services.processed_blocks.claim(block_number, block_hash).await {
+        Ok(claimed) => claimed,
+        Err(e) => {
+            // The writes below are idempotent, booking twice beats not booking at all
+            warn!("Failed to claim block {}, processing unclaimed: {}", block_number, e);
+            true
+        }
+    };
+    if !claimed {
+        return;
+    }
+    
+    // Book the subsidy if this is one of our subsidized blocks
+    if let Err(e) = services.subsidy_service.settle_block(block_hash).await {
+        warn!("Failed to settle subsidy for block {}: {}", block_number, e);
+    }
+    
+    // Landed-candidate attribution and per-transaction bookkeeping run on the workers
+    confirmed_queue.enqueue(block.clone()).await;
+}
+
 /// Process a new block
 async fn process_new_block(
-    blockchain_client: &BlockchainClient,
     services: &ServiceContext,
+    confirmed_queue: &ConfirmedBlockQueue,
     block: Block<Transaction>,
 ) -> Result<()> {
     let block_number = block.number.unwrap_or_default().as_u64();
@@ -139,15 +453,357 @@ async fn process_new_block(
     // Update block metrics
     metrics::gauge!("blockchain_current_block", block_number as f64);
     
-    // Process transactions in the block
-    for tx in block.transactions {
-        if let Err(e) = services.transaction_service.process_confirmed_transaction(tx).await {
-            warn!("Failed to process confirmed transaction: {}", e);
+    services.event_bus.publish(
+        Topic::Blocks,
+        None,
+        &serde_json::json!({
+            "blockNumber": block_number,
+            "blockHash": block_hash,
+            "txCount": tx_count,
+        }),
+    );
+    
+    record_confirmed_block(services, confirmed_queue, &block).await;
+    
+    // The fee window now includes this block
+    match services.fee_estimator.refresh_report().await {
+        Ok(report) => services.event_bus.publish(Topic::GasPrice, None, &report),
+        Err(e) => debug!("Failed to report gas prices for block {}: {}", block_number, e),
+    }
+    
+    // Strategies react to the head off the block path, so a slow one can't delay bundling
+    if services.strategy_manager.any_enabled() {
+        let strategy_manager = services.strategy_manager.clone();
+        let liquidation_monitor = services.liquidation_monitor.clone();
+        let arbitrage_engine = services.arbitrage_engine.clone();
+        let transaction_service = services.transaction_service.clone();
+        let block = block.clone();
+        tokio::spawn(async move {
+            let found = strategy_manager.on_new_block(&block).await;
+            send_liquidations(&liquidation_monitor, &transaction_service, &found, block_number).await;
+            send_arbitrage(&arbitrage_engine, &transaction_service, &found, block_number).await;
+        });
+    }
+    
+    // Sensitive candidates that missed their block are dropped before the next one is built
+    services.transaction_service.expire_sensitive_candidates(block_number + 1).await;
+    
+    // Open sealed bundles targeting the next block now that it is being built
+    match services.sealed_bundle_service.include_for_block(block_number + 1).await {
+        Ok(0) => {}
+        Ok(count) => debug!("Included {} sealed bundles for block {}", count, block_number + 1),
+        Err(e) => warn!("Failed to include sealed bundles for block {}: {}", block_number + 1, e),
+    }
+    
+    // Bundle the most profitable candidates for the next block, and each sandwich around its victim
+    if services.bundle_service.enabled() && !services.controls.is_paused("bundle_submitter") {
+        send_sandwiches(services, block_number + 1).await;
+        if let Err(e) = services.bundle_service.submit_for_block(block_number + 1).await {
+            warn!("Failed to submit bundle for block {}: {}", block_number + 1, e);
         }
     }
     
     // Trigger block processing in services
-    services.block_building_service.process_new_block(block).await?;
+    services.block_building_service.process_new_block(block.clone()).await?;
+    
+    // Bid the next slot's block to the relays
+    if !services.config.relays.is_empty() && !services.controls.is_paused("bidder") {
+        let bid = match block.hash {
+            Some(parent_hash) => bid_for_next_slot(services, block_number, parent_hash).await,
+            None => Err(anyhow!("Parent block has no hash")),
+        };
+        if let Err(e) = bid {
+            warn!("Failed to bid on block {}: {}", block_number, e);
+        }
+    }
+    
+    // Checkpoint in-flight state so a crash can resume from this head
+    if let Err(e) = services.recovery_service.checkpoint(block_number).await {
+        warn!("Failed to checkpoint in-flight state: {}", e);
+    }
+    
+    Ok(())
+}
+
+/// Sender side of the confirmed block queue
+#[derive(Clone)]
+struct ConfirmedBlockQueue {
+    sender: mpsc::Sender<Block<Transaction>>,
+}
+
+impl ConfirmedBlockQueue {
+    /// Queue a block's confirmed transactions, waiting only if the workers are far behind
+    async fn enqueue(&self, block: Block<Transaction>) {
+        let block = match self.sender.try_send(block) {
+            Ok(()) => {
+                self.record_depth();
+                return;
+            }
+            Err(mpsc::error::TrySendError::Full(block)) => block,
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                warn!("Confirmed block worker stopped, dropping block");
+                return;
+            }
+        };
+        
+        warn!("Confirmed block queue is full, waiting for the workers");
+        metrics::counter!("confirmed_block_queue_full_total", 1);
+        if self.sender.send(block).await.is_err() {
+            warn!("Confirmed block worker stopped, dropping block");
+        }
+        self.record_depth();
+    }
+    
+    fn record_depth(&self) {
+        let depth = self.sender.max_capacity() - self.sender.capacity();
+        metrics::gauge!("confirmed_block_queue_depth", depth as f64);
+    }
+}
+
+/// Spawn the worker processing confirmed blocks behind the head
+///
+/// The worker exits once every monitor holding the queue has stopped and the queue is drained.
+fn spawn_confirmed_block_worker(
+    blockchain_client: Arc<BlockchainClient>,
+    services: Arc<ServiceContext>,
+) -> (ConfirmedBlockQueue, JoinHandle<()>) {
+    let config = &services.config.services.tx_ordering;
+    let concurrency = config.confirmed_tx_concurrency.max(1);
+    let (sender, mut receiver) = mpsc::channel::<Block<Transaction>>(config.confirmed_tx_queue_blocks.max(1));
+    
+    let task = tokio::spawn(async move {
+        info!("Confirmed block worker started");
+        
+        while let Some(block) = receiver.recv().await {
+            let block_number = block.number.unwrap_or_default().as_u64();
+            let block_hash = block.hash.unwrap_or_default();
+            let timer = MetricsTimer::new("confirmed_block_processing_seconds");
+            
+            // Every step is idempotent, so a failed block is released whole for the next claim
+            let booked = AtomicBool::new(true);
+            
+            // Record which of our candidates landed in this block
+            if let Err(e) = record_landed_candidates(blockchain_client.as_ref(), services.as_ref(), &block).await {
+                warn!("Failed to record landed candidates for block {}: {}", block_number, e);
+                booked.store(false, Ordering::Relaxed);
+            }
+            
+            if let Err(e) = services.reputation_service.settle_block(blockchain_client.as_ref(), &block).await {
+                warn!("Failed to settle searcher landings for block {}: {}", block_number, e);
+                booked.store(false, Ordering::Relaxed);
+            }
+            
+            if let Err(e) = services.block_bodies.store(&block).await {
+                warn!("Failed to store body of block {}: {}", block_number, e);
+                booked.store(false, Ordering::Relaxed);
+            }
+            
+            if let Err(e) = services.staking_ledger.book_block(blockchain_client.as_ref(), &block).await {
+                warn!("Failed to book staking ledger for block {}: {}", block_number, e);
+                booked.store(false, Ordering::Relaxed);
+            }
+            
+            // Process transactions in the block
+            let (context, booked_ref) = (services.as_ref(), &booked);
+            futures::stream::iter(block.transactions)
+                .for_each_concurrent(concurrency, |tx| async move {
+                    if let Err(e) = context.transaction_service.process_confirmed_transaction(tx).await {
+                        warn!("Failed to process confirmed transaction: {}", e);
+                        booked_ref.store(false, Ordering::Relaxed);
+                    }
+                })
+                .await;
+            
+            if booked.into_inner() {
+                if let Err(e) = services.processed_blocks.complete(block_hash).await {
+                    warn!("Failed to mark block {} processed: {}", block_number, e);
+                }
+            } else {
+                metrics::counter!("confirmed_blocks_released_total", 1);
+                if let Err(e) = services.processed_blocks.release(block_hash).await {
+                    warn!("Failed to release block {}: {}", block_number, e);
+                }
+            }
+            
+            timer.stop();
+        }
+        
+        info!("Confirmed block worker stopped");
+    });
+    
+    (ConfirmedBlockQueue { sender }, task)
+}
+
+/// Record the candidates from the parent block's template that landed in this block
+///
+/// Profit and the built-block export are booked only for blocks paying our fee recipient;
+/// candidates landing in other builders' blocks are counted apart.
+async fn record_landed_candidates(
+    blockchain_client: &BlockchainClient,
+    services: &ServiceContext,
+    block: &Block<Transaction>,
+) -> Result<()> {
+    let block_number = block.number.unwrap_or_default().as_u64();
+    let snapshot = match services.recovery_service.load_snapshot(block_number.saturating_sub(1)).await? {
+        Some(snapshot) => snapshot,
+        None => return Ok(()),
+    };
+    
+    let included: HashSet<H256> = block.transactions.iter().map(|tx| tx.hash).collect();
+    let landed: Vec<_> = snapshot
+        .candidates
+        .into_iter()
+        .filter(|candidate| included.contains(&candidate.tx.hash))
+        .collect();
+    
+    if landed.is_empty() {
+        return Ok(());
+    }
+    
+    // Reverted candidates landed but extracted nothing; one receipts call covers the block
+    let receipts: HashMap<H256, TransactionReceipt> = blockchain_client
+        .get_block_receipts(block_number)
+        .await?
+        .into_iter()
+        .map(|receipt| (receipt.transaction_hash, receipt))
+        .collect();
+    let reverted: HashSet<H256> = receipts
+        .values()
+        .filter(|receipt| receipt.status.map_or(false, |status| status.is_zero()))
+        .map(|receipt| receipt.transaction_hash)
+        .collect();
+    
+    // Candidates simulated as profitable, so any that reverted surprised the simulator
+    let unexpected_reverts = landed.iter().filter(|candidate| reverted.contains(&candidate.tx.hash)).count();
+    services.risk_manager.record_landed(landed.len(), unexpected_reverts).await;
+    
+    let landed: Vec<_> = landed
+        .into_iter()
+        .filter(|candidate| !reverted.contains(&candidate.tx.hash))
+        .collect();
+    if landed.is_empty() {
+        return Ok(());
+    }
+    
+    // Booked at what the candidates paid on chain rather than what they simulated to
+    let payments = blockchain_client.get_block_payments(block, &receipts).await?;
+    let bundles: Vec<ExportBundle> = landed
+        .into_iter()
+        .map(|candidate| ExportBundle {
+            tx_hashes: vec![candidate.tx.hash],
+            raw_txs: vec![candidate.tx.rlp()],
+            value: payments.get(&candidate.tx.hash).copied().unwrap_or_default(),
+            source: candidate.source,
+            strategy: candidate.strategy,
+        })
+        .collect();
+    
+    for bundle in &bundles {
+        services.event_bus.publish(
+            Topic::Bundles,
+            bundle.source.owner(),
+            &serde_json::json!({
+                "status": "landed",
+                "blockNumber": block_number,
+                "txs": bundle.tx_hashes,
+                "value": models::Wei(bundle.value),
+            }),
+        );
+    }
+    
+    // Candidates in another builder's block landed, but the block and its value aren't ours
+    let fee_recipient = services.config.services.block_building.fee_recipient();
+    if block.author != Some(fee_recipient) {
+        debug!(
+            "{} candidates landed in block {} built by {:?}, not booking them",
+            bundles.len(),
+            block_number,
+            block.author
+        );
+        metrics::counter!("candidates_landed_elsewhere_total", bundles.len() as u64);
+        return Ok(());
+    }
+    
+    let included_at = Utc.timestamp_opt(block.timestamp.as_u64() as i64, 0).single().unwrap_or_else(Utc::now);
+    if let Err(e) = services
+        .profit_service
+        .record_block(block_number, block.hash.unwrap_or_default(), included_at, &bundles)
+        .await
+    {
+        warn!("Failed to book profits for block {}: {}", block_number, e);
+    }
+    
+    services
+        .export_service
+        .record_built_block(block_number, block.hash.unwrap_or_default(), bundles)
+        .await
+}
+
+/// Build the next slot's block on a head from the current template and bid it to every relay
+///
+/// Rebuilt on every head, so a bid on a newer parent or template replaces the earlier one at
+/// relays that allow cancellations.
+pub(crate) async fn bid_for_next_slot(
+    services: &ServiceContext,
+    parent_number: u64,
+    parent_hash: H256,
+) -> Result<()> {
+    let slot = services.relay_service.current_slot() + 1;
+    let duty = match services.relay_service.proposer_duty(slot) {
+        Some(duty) => duty,
+        None => {
+            debug!("No registered proposer for slot {}, not bidding", slot);
+            return Ok(());
+        }
+    };
+    let (template, summary) = services.transaction_service.summarized_block_template().await;
+    if template.is_empty() {
+        debug!("Nothing to build for slot {}", slot);
+        return Ok(());
+    }
+    
+    // Logged off the bid path, as the relay service logs the bid
+    let decisions = services.build_decisions.clone();
+    let recorded = summary.clone();
+    tokio::spawn(async move {
+        if let Err(e) = decisions.record_template(slot, parent_number, &recorded).await {
+            warn!("Failed to record the template for slot {}: {}", slot, e);
+        }
+    });
+    
+    // Strategic slots are bid above what the template extracts, within the subsidy budget
+    let subsidy = services.subsidy_service.subsidy_for(&SlotContext {
+        slot,
+        proposer_pubkey: duty.pubkey.clone(),
+    });
+    let value = match &subsidy {
+        Some(subsidy) => summary.value.saturating_add(subsidy.amount),
+        None => summary.value,
+    };
+    
+    let bid = services
+        .block_building_service
+        .build_bid(BidRequest {
+            slot,
+            parent_hash,
+            duty: &duty,
+            transactions: &template,
+            value,
+            subsidy,
+            identity: services.relay_service.identity(),
+        })
+        .await?;
+    
+    let outcomes = services.relay_service.submit_bid(&bid, true).await?;
+    let accepted = outcomes.iter().filter(|outcome| outcome.result.is_ok()).count();
+    debug!(
+        "Bid {} wei for slot {} on {:?}, accepted by {} of {} relays",
+        bid.value,
+        slot,
+        parent_hash,
+        accepted,
+        outcomes.len()
+    );
     
     Ok(())
 }
@@ -160,22 +816,31 @@ fn spawn_transaction_monitor(
 ) -> JoinHandle<()> {
     tokio::spawn(async move {
         info!("Transaction monitor started");
+        services.heartbeats.register("transaction_monitor", PENDING_TX_HEARTBEAT_INTERVAL);
+        services.controls.register("transaction_monitor");
         
         let mut retry_count = 0;
         let max_retries = 10;
         
         'outer: loop {
             match blockchain_client.subscribe_pending_txs().await {
-                Ok(mut stream) => {
+                Ok((stream, feed)) => {
                     retry_count = 0;
-                    info!("Successfully subscribed to pending transactions");
+                    info!("Successfully subscribed to pending transactions ({})", feed.as_str());
                     
+                    // Announcements already waiting are fetched together, a quiet mempool one at a time
+                    let mut stream = stream.ready_chunks(PENDING_TX_BATCH_SIZE);
                     loop {
                         tokio::select! {
-                            Some(tx_hash) = stream.next() => {
+                            Some(announcements) = stream.next() => {
+                                services.heartbeats.beat("transaction_monitor");
+                                if services.controls.is_paused("transaction_monitor") {
+                                    continue;
+                                }
+                                
                                 let timer = MetricsTimer::new("transaction_processing_time_seconds");
-                                if let Err(e) = process_pending_transaction(blockchain_client.as_ref(), services.as_ref(), tx_hash).await {
-                                    debug!("Error processing pending transaction {}: {}", tx_hash, e);
+                                if let Err(e) = process_pending_transactions(blockchain_client.as_ref(), services.as_ref(), announcements).await {
+                                    debug!("Error processing pending transactions: {}", e);
                                 }
                                 timer.stop();
                             }
@@ -203,25 +868,423 @@ fn spawn_transaction_monitor(
             }
         }
         
+        services.heartbeats.unregister("transaction_monitor");
         info!("Transaction monitor stopped");
     })
 }
 
-/// Process a pending transaction
-async fn process_pending_transaction(
+/// Spawn a task ingesting the transactions bloXroute announces before our node sees them
+fn spawn_bloxroute_monitor(services: Arc<ServiceContext>, mut shutdown_rx: mpsc::Receiver<()>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("bloXroute monitor started");
+        services.heartbeats.register("bloxroute_monitor", PENDING_TX_HEARTBEAT_INTERVAL);
+        services.controls.register("bloxroute_monitor");
+        
+        let config = services.config.services.bloxroute_stream.clone();
+        let mut retry_count = 0;
+        let max_retries = 10;
+        
+        'outer: loop {
+            match BloxrouteStream::connect(&config).await {
+                Ok(mut stream) => {
+                    retry_count = 0;
+                    info!("Subscribed to bloXroute new transactions");
+                    
+                    loop {
+                        tokio::select! {
+                            next = stream.next() => {
+                                let tx = match next {
+                                    Some(Ok(tx)) => tx,
+                                    Some(Err(e)) => {
+                                        debug!("Skipping bloXroute message: {}", e);
+                                        continue;
+                                    }
+                                    None => {
+                                        warn!("bloXroute stream closed, reconnecting");
+                                        continue 'outer;
+                                    }
+                                };
+                                services.heartbeats.beat("bloxroute_monitor");
+                                if services.controls.is_paused("bloxroute_monitor") {
+                                    continue;
+                                }
+                                
+                                // The node's mempool usually announces the same transactions shortly after
+                                if services.transaction_service.is_known(&tx.hash) {
+                                    metrics::counter!("bloxroute_txs_duplicate_total", 1);
+                                    continue;
+                                }
+                                metrics::counter!("bloxroute_txs_received_total", 1);
+                                
+                                let tx_hash = tx.hash;
+                                if let Err(e) = ingest_pending_transaction(services.as_ref(), tx, TxSource::Bloxroute).await {
+                                    debug!("Error processing bloXroute transaction {}: {}", tx_hash, e);
+                                }
+                            }
+                            _ = shutdown_rx.recv() => {
+                                info!("Received shutdown signal, stopping bloXroute monitor");
+                                break 'outer;
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to subscribe to bloXroute: {}", e);
+                    retry_count += 1;
+                    
+                    if retry_count > max_retries {
+                        error!("Exceeded maximum retry count for bloXroute subscription, stopping monitor");
+                        break;
+                    }
+                    
+                    // Exponential backoff
+                    let delay = Duration::from_secs(2u64.pow(retry_count.min(6) as u32));
+                    warn!("Retrying bloXroute subscription in {:?}", delay);
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+        
+        services.heartbeats.unregister("bloxroute_monitor");
+        info!("bloXroute monitor stopped");
+    })
+}
+
+/// Process a batch of pending transaction announcements
+///
+/// Announcements carrying the body are ingested as they are; only bare hashes are fetched.
+async fn process_pending_transactions(
     blockchain_client: &BlockchainClient,
     services: &ServiceContext,
-    tx_hash: H256,
+    announcements: Vec<PendingTxAnnouncement>,
 ) -> Result<()> {
-    // Get the full transaction
-    if let Some(tx) = blockchain_client.get_transaction(tx_hash).await? {
-        // Process the transaction
-        services.transaction_service.process_pending_transaction(tx).await?;
+    // Skip transactions we already hold or just saw in a block body
+    let announced = announcements.len();
+    let announcements: Vec<PendingTxAnnouncement> = announcements
+        .into_iter()
+        .filter(|announcement| !services.transaction_service.is_known(&announcement.hash()))
+        .collect();
+    if announcements.len() < announced {
+        metrics::counter!("pending_tx_fetches_skipped_total", (announced - announcements.len()) as u64);
+    }
+    
+    let mut txs = Vec::with_capacity(announcements.len());
+    let mut tx_hashes = Vec::new();
+    for announcement in announcements {
+        match announcement {
+            PendingTxAnnouncement::Body(tx) => txs.push(*tx),
+            PendingTxAnnouncement::Hash(tx_hash) => tx_hashes.push(tx_hash),
+        }
+    }
+    if !txs.is_empty() {
+        metrics::counter!("pending_tx_bodies_received_total", txs.len() as u64);
+    }
+    
+    // Get the full transactions of bare hashes, those already gone from the pool come back empty
+    if !tx_hashes.is_empty() {
+        txs.extend(blockchain_client.get_transactions(tx_hashes).await?.into_iter().flatten());
+    }
+    
+    for tx in txs {
+        let tx_hash = tx.hash;
+        if let Err(e) = ingest_pending_transaction(services, tx, TxSource::PublicMempool).await {
+            debug!("Error processing pending transaction {}: {}", tx_hash, e);
+        }
     }
     
     Ok(())
 }
 
+/// Send the liquidations strategies found in a block from the configured liquidator
+///
+/// Each applies its route, approving the debt asset unless the allowance is in place, and a
+/// position already sent is left alone until it had time to land.
+async fn send_liquidations(
+    liquidation_monitor: &LiquidationMonitor,
+    transaction_service: &TransactionService,
+    found: &[Opportunity],
+    block_number: u64,
+) {
+    let Some(account) = liquidation_monitor.liquidator() else {
+        return;
+    };
+    let liquidations: Vec<&LiquidationOpportunity> = found
+        .iter()
+        .filter_map(|opportunity| match opportunity {
+            Opportunity::Liquidation(liquidation) => Some(liquidation),
+            _ => None,
+        })
+        .filter(|liquidation| liquidation_monitor.claim_send(liquidation, block_number))
+        .collect();
+    if liquidations.is_empty() {
+        return;
+    }
+    let liquidator = match transaction_service.account(account) {
+        Ok(liquidator) => liquidator,
+        Err(e) => {
+            warn!("Not sending {} liquidations: {}", liquidations.len(), e);
+            return;
+        }
+    };
+    
+    for liquidation in liquidations {
+        let route = liquidation.route(liquidator);
+        match transaction_service.send_route(liquidation_monitor.name(), vec![route], Urgency::High).await {
+            Ok(tx_hashes) => info!(
+                "Sent liquidation of {:?} on {:?} in {:?}",
+                liquidation.account, liquidation.market, tx_hashes
+            ),
+            Err(e) => warn!(
+                "Failed to send liquidation of {:?} on {:?}: {}",
+                liquidation.account, liquidation.market, e
+            ),
+        }
+    }
+}
+
+/// Send the arbitrage cycles found on a block from the configured trader
+///
+/// Each cycle is sent as its route, one swap per hop, and a cycle already sent is left alone
+/// until it had time to land.
+async fn send_arbitrage(
+    arbitrage_engine: &ArbitrageEngine,
+    transaction_service: &TransactionService,
+    found: &[Opportunity],
+    block_number: u64,
+) {
+    let Some(account) = arbitrage_engine.trader() else {
+        return;
+    };
+    let cycles: Vec<&ArbitrageOpportunity> = found
+        .iter()
+        .filter_map(|opportunity| match opportunity {
+            Opportunity::Arbitrage(cycle) => Some(cycle),
+            _ => None,
+        })
+        .filter(|cycle| arbitrage_engine.claim_send(cycle, block_number))
+        .collect();
+    if cycles.is_empty() {
+        return;
+    }
+    let trader = match transaction_service.account(account) {
+        Ok(trader) => trader,
+        Err(e) => {
+            warn!("Not sending {} arbitrage cycles: {}", cycles.len(), e);
+            return;
+        }
+    };
+    
+    for cycle in cycles {
+        let route = match arbitrage_engine.route(cycle, trader) {
+            Ok(route) => route,
+            Err(e) => {
+                warn!("Failed to route arbitrage cycle from {:?}: {}", cycle.input_token, e);
+                continue;
+            }
+        };
+        match transaction_service.send_route(arbitrage_engine.name(), vec![route], Urgency::High).await {
+            Ok(tx_hashes) => info!(
+                "Sent {}-hop arbitrage cycle from {:?} in {:?}",
+                cycle.hops.len(), cycle.input_token, tx_hashes
+            ),
+            Err(e) => warn!("Failed to send arbitrage cycle from {:?}: {}", cycle.input_token, e),
+        }
+    }
+}
+
+/// Bundle the open sandwiches targeting a block around their victims and submit them
+///
+/// At most `MAX_SANDWICH_BUNDLES`, the most profitable first and one per pool. Each bundle is
+/// the front-run, the victim as seen pending and the back-run, signed by the searcher account.
+async fn send_sandwiches(services: &ServiceContext, target_block: u64) {
+    let detector = &services.sandwich_detector;
+    let Some(account) = detector.searcher() else {
+        return;
+    };
+    let opportunities = detector.opportunities_for_block(target_block);
+    if opportunities.is_empty() {
+        return;
+    }
+    let searcher = match services.transaction_service.account(account) {
+        Ok(searcher) => searcher,
+        Err(e) => {
+            warn!("Not bundling {} sandwiches: {}", opportunities.len(), e);
+            return;
+        }
+    };
+    
+    for (sandwich, victim) in opportunities.into_iter().take(MAX_SANDWICH_BUNDLES) {
+        let bundle = match sandwich_bundle(services, account, searcher, &sandwich, &victim).await {
+            Ok(bundle) => bundle,
+            Err(e) => {
+                warn!("Failed to bundle the sandwich around {:?}: {}", sandwich.victim, e);
+                continue;
+            }
+        };
+        match services.bundle_service.submit(&bundle).await {
+            Ok(receipt) => debug!(
+                "Submitted sandwich around {:?} for block {} as {:?}",
+                sandwich.victim, target_block, receipt.bundle_hash
+            ),
+            Err(e) => warn!("Failed to submit the sandwich around {:?}: {}", sandwich.victim, e),
+        }
+    }
+}
+
+/// Sign a sandwich's legs and place them either side of the victim
+async fn sandwich_bundle(
+    services: &ServiceContext,
+    account: &str,
+    searcher: Address,
+    sandwich: &SandwichOpportunity,
+    victim: &Transaction,
+) -> Result<Bundle> {
+    let (front, back) = services.sandwich_detector.legs(sandwich, searcher).await?;
+    let front_len = front.len();
+    let mut signed = services
+        .transaction_service
+        .sign_bundle(account, front.into_iter().chain(back).collect(), Urgency::High)
+        .await?;
+    let back = signed.split_off(front_len);
+    signed.push((victim.hash, victim.rlp()));
+    signed.extend(back);
+    
+    Ok(Bundle {
+        tx_hashes: signed.iter().map(|(tx_hash, _)| *tx_hash).collect(),
+        txs: signed.into_iter().map(|(_, raw)| raw).collect(),
+        target_block: sandwich.target_block,
+        profit: sandwich.expected_profit,
+    })
+}
+
+/// Hand a fetched pending transaction to the services and strategies, returning what they found
+///
+/// Also the entry point of `replay`, which feeds recorded transactions through here.
+pub async fn ingest_pending_transaction(
+    services: &ServiceContext,
+    tx: Transaction,
+    source: TxSource,
+) -> Result<Vec<Opportunity>> {
+    let pending = services.strategy_manager.any_enabled().then(|| tx.clone());
+    
+    // Screened off the ingestion path, it may simulate
+    if services.exploit_detector.enabled() {
+        let exploit_detector = services.exploit_detector.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move { exploit_detector.inspect(&tx).await });
+    }
+    
+    // Process the transaction
+    services.transaction_service.process_pending_transaction(tx, source).await?;
+    
+    // Let the strategies look for opportunities around the public transaction
+    match pending {
+        Some(pending) => Ok(services.strategy_manager.on_pending_tx(&pending).await),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Spawn a task indexing the events of the configured contracts
+///
+/// The subscription only delivers logs mined after it starts, so on every (re)subscription the
+/// blocks since the last indexed one are fetched with `eth_getLogs` first. A first run starts
+/// at the head.
+fn spawn_event_monitor(
+    blockchain_client: Arc<BlockchainClient>,
+    services: Arc<ServiceContext>,
+    mut shutdown_rx: mpsc::Receiver<()>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("Event monitor started");
+        let indexer = services.log_indexer.clone();
+        let filter = indexer.filter();
+        
+        // Blocks up to this one are indexed
+        let mut indexed_through = match indexer.latest_block().await {
+            Ok(latest) => latest,
+            Err(e) => {
+                warn!("Failed to read the latest indexed block, starting at the head: {}", e);
+                None
+            }
+        };
+        let mut retry_count = 0;
+        let max_retries = 10;
+        
+        'outer: loop {
+            match blockchain_client.subscribe_logs(&filter).await {
+                Ok(stream) => {
+                    retry_count = 0;
+                    info!("Successfully subscribed to indexed contract logs");
+                    
+                    // A gap left unfilled would never be revisited, so failing here resubscribes
+                    let caught_up = match blockchain_client.get_block_number().await {
+                        Ok(head) => match indexed_through.map(|block| block + 1) {
+                            Some(from) if from <= head => indexer
+                                .catch_up(blockchain_client.as_ref(), from, head)
+                                .await
+                                .map(|_| head)
+                                .map_err(|e| anyhow!("Failed to index events since #{}: {}", from, e)),
+                            _ => Ok(head),
+                        },
+                        Err(e) => Err(anyhow!("Failed to fetch the head to catch up on events: {}", e)),
+                    };
+                    match caught_up {
+                        Ok(head) => indexed_through = Some(indexed_through.map_or(head, |block| block.max(head))),
+                        Err(e) => {
+                            error!("{}", e);
+                            tokio::time::sleep(LOG_CATCH_UP_RETRY_DELAY).await;
+                            continue 'outer;
+                        }
+                    }
+                    
+                    let mut stream = stream.ready_chunks(LOG_BATCH_SIZE);
+                    loop {
+                        tokio::select! {
+                            logs = stream.next() => {
+                                let logs = match logs {
+                                    Some(logs) => logs,
+                                    None => {
+                                        warn!("Log subscription ended, resubscribing");
+                                        continue 'outer;
+                                    }
+                                };
+                                if let Err(e) = indexer.ingest(&logs).await {
+                                    // Fetched again by the catch-up after resubscribing
+                                    error!("Failed to index {} logs, resubscribing: {}", logs.len(), e);
+                                    tokio::time::sleep(LOG_CATCH_UP_RETRY_DELAY).await;
+                                    continue 'outer;
+                                }
+                                let newest = logs.iter().filter_map(|log| log.block_number).max();
+                                indexed_through = indexed_through.max(newest.map(|block| block.as_u64()));
+                            }
+                            _ = shutdown_rx.recv() => {
+                                info!("Received shutdown signal, stopping event monitor");
+                                break 'outer;
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to subscribe to indexed contract logs: {}", e);
+                    retry_count += 1;
+                    
+                    if retry_count > max_retries {
+                        error!("Exceeded maximum retry count for log subscription, stopping monitor");
+                        break;
+                    }
+                    
+                    // Exponential backoff
+                    let delay = Duration::from_secs(2u64.pow(retry_count.min(6) as u32));
+                    warn!("Retrying log subscription in {:?}", delay);
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+        
+        info!("Event monitor stopped");
+    })
+}
+
 /// Spawn a task to monitor gas prices
 fn spawn_gas_price_monitor(
     blockchain_client: Arc<BlockchainClient>,
@@ -237,12 +1300,20 @@ fn spawn_gas_price_monitor(
         );
         
         let mut interval = interval(refresh_interval);
+        services.heartbeats.register("gas_price_monitor", refresh_interval);
+        services.controls.register("gas_price_monitor");
         
         loop {
             tokio::select! {
                 _ = interval.tick() => {
-                    if let Err(e) = update_gas_price(blockchain_client.as_ref(), services.as_ref()).await {
-                        warn!("Failed to update gas price: {}", e);
+                    if services.controls.is_paused("gas_price_monitor") {
+                        services.heartbeats.beat("gas_price_monitor");
+                        continue;
+                    }
+                    
+                    match update_gas_price(blockchain_client.as_ref(), services.as_ref()).await {
+                        Ok(()) => services.heartbeats.beat("gas_price_monitor"),
+                        Err(e) => warn!("Failed to update gas price: {}", e),
                     }
                 }
                 _ = shutdown_rx.recv() => {
@@ -252,6 +1323,7 @@ fn spawn_gas_price_monitor(
             }
         }
         
+        services.heartbeats.unregister("gas_price_monitor");
         info!("Gas price monitor stopped");
     })
 }
@@ -265,4 +1337,4 @@ async fn update_gas_price(blockchain_client: &BlockchainClient, services: &Servi
     services.transaction_service.update_gas_price(gas_price).await?;
     
     Ok(())
-} 
\ No newline at end of file
+}
\ No newline at end of file
@@ -0,0 +1,216 @@
+use parking_lot::Mutex;
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+use tokio::sync::Notify;
+
+use crate::config::ProviderBudget;
+
+tokio::task_local! {
+    /// Priority of the RPC requests made by the current task
+    static PRIORITY: RpcPriority;
+}
+
+/// Priority class of RPC requests, deciding which go first when a provider budget runs low
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RpcPriority {
+    /// Head processing and transaction submission, which can't wait for the next slot
+    Critical,
+    Normal,
+    /// Backfill and analytics reads, which only spend what the others leave
+    Bulk,
+}
+
+impl RpcPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Critical => "critical",
+            Self::Normal => "normal",
+            Self::Bulk => "bulk",
+        }
+    }
+    
+    /// Priority of the current task's requests, normal outside `with_priority`
+    pub fn current() -> Self {
+        PRIORITY.try_with(|priority| *priority).unwrap_or(Self::Normal)
+    }
+}
+
+/// Make the RPC requests of a future at a priority
+///
+/// The priority belongs to the task polling the future, tasks it spawns run at normal priority.
+pub async fn with_priority<F: Future>(priority: RpcPriority, future: F) -> F::Output {
+    PRIORITY.scope(priority, future).await
+}
+
+/// Token bucket refilled continuously, holding at most one second of its rate
+struct Bucket {
+    /// Tokens per second, 0 for no limit
+    rate: f64,
+    /// Goes negative when a batch costs more than the bucket holds, the debt is paid by waiting
+    available: f64,
+}
+
+impl Bucket {
+    fn new(per_second: u32) -> Self {
+        Self {
+            rate: per_second as f64,
+            available: per_second as f64,
+        }
+    }
+    
+    fn refill(&mut self, elapsed: Duration) {
+        self.available = (self.available + elapsed.as_secs_f64() * self.rate).min(self.rate);
+    }
+    
+    /// Time until `cost` can be taken leaving `reserve` of the bucket, zero if it can now
+    ///
+    /// A cost larger than the bucket can be taken once it is full.
+    fn shortfall(&self, cost: f64, reserve: f64) -> Duration {
+        if self.rate == 0.0 {
+            return Duration::ZERO;
+        }
+        
+        let needed = (cost + reserve * self.rate).min(self.rate);
+        Duration::from_secs_f64((needed - self.available).max(0.0) / self.rate)
+    }
+    
+    fn take(&mut self, cost: f64) {
+        if self.rate > 0.0 {
+            self.available -= cost;
+        }
+    }
+}
+
+struct LimiterState {
+    requests: Bucket,
+    compute_units: Bucket,
+    refilled_at: Instant,
+    /// Requests waiting for budget, by priority
+    waiting: [usize; 3],
+}
+
+impl LimiterState {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now - self.refilled_at;
+        self.requests.refill(elapsed);
+        self.compute_units.refill(elapsed);
+        self.refilled_at = now;
+    }
+    
+    /// Whether requests of a higher priority are waiting
+    fn outranked(&self, priority: RpcPriority) -> bool {
+        self.waiting[..priority as usize].iter().any(|waiting| *waiting > 0)
+    }
+}
+
+/// Request and compute unit budget of one RPC provider
+///
+/// Requests wait until both budgets cover them. A waiting request lets every higher-priority
+/// request go first, and bulk requests leave `bulk_reserve` of each budget untouched, so a long
+/// backfill can't drain the budget under the block monitor. Compute units are counted per
+/// provider and priority whether or not the provider has a budget.
+pub struct RateLimiter {
+    provider: &'static str,
+    bulk_reserve: f64,
+    unlimited: bool,
+    state: Mutex<LimiterState>,
+    /// Woken whenever a request stops waiting, so those it outranked look again
+    released: Notify,
+}
+
+impl RateLimiter {
+    /// Create a limiter for a provider's budget, an empty budget limits nothing
+    pub fn new(provider: &'static str, budget: &ProviderBudget, bulk_reserve: f64) -> Self {
+        Self {
+            provider,
+            bulk_reserve,
+            unlimited: budget.requests_per_second == 0 && budget.compute_units_per_second == 0,
+            state: Mutex::new(LimiterState {
+                requests: Bucket::new(budget.requests_per_second),
+                compute_units: Bucket::new(budget.compute_units_per_second),
+                refilled_at: Instant::now(),
+                waiting: [0; 3],
+            }),
+            released: Notify::new(),
+        }
+    }
+    
+    /// Wait until the budget covers `requests` requests costing `compute_units` in total
+    pub async fn acquire(&self, priority: RpcPriority, requests: u32, compute_units: u32) {
+        metrics::counter!(
+            "rpc_compute_units_total",
+            compute_units as u64,
+            "provider" => self.provider,
+            "priority" => priority.as_str()
+        );
+        if self.unlimited {
+            return;
+        }
+        
+        let queued_at = Instant::now();
+        let _waiting = Waiting::new(self, priority);
+        let reserve = if priority == RpcPriority::Bulk { self.bulk_reserve } else { 0.0 };
+        loop {
+            // Registered before looking, so a release in between isn't missed
+            let released = self.released.notified();
+            let wait = {
+                let mut state = self.state.lock();
+                state.refill();
+                if state.outranked(priority) {
+                    None
+                } else {
+                    let wait = state
+                        .requests
+                        .shortfall(requests as f64, reserve)
+                        .max(state.compute_units.shortfall(compute_units as f64, reserve));
+                    if wait.is_zero() {
+                        state.requests.take(requests as f64);
+                        state.compute_units.take(compute_units as f64);
+                        metrics::gauge!(
+                            "rpc_compute_units_available",
+                            state.compute_units.available.max(0.0),
+                            "provider" => self.provider
+                        );
+                        break;
+                    }
+                    Some(wait)
+                }
+            };
+            
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => released.await,
+            }
+        }
+        
+        metrics::histogram!(
+            "rpc_rate_limit_wait_seconds",
+            queued_at.elapsed().as_secs_f64(),
+            "provider" => self.provider,
+            "priority" => priority.as_str()
+        );
+    }
+}
+
+/// Counts a request as waiting until it is dropped, also when its caller gives up on it
+struct Waiting<'a> {
+    limiter: &'a RateLimiter,
+    priority: RpcPriority,
+}
+
+impl<'a> Waiting<'a> {
+    fn new(limiter: &'a RateLimiter, priority: RpcPriority) -> Self {
+        limiter.state.lock().waiting[priority as usize] += 1;
+        Self { limiter, priority }
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.limiter.state.lock().waiting[self.priority as usize] -= 1;
+        self.limiter.released.notify_waiters();
+    }
+}
@@ -0,0 +1,220 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use ethers::{
+    signers::{AwsSigner, LocalWallet, Signer},
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, Signature},
+};
+use rusoto_core::Region;
+use rusoto_kms::KmsClient;
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+use tracing::info;
+
+use crate::config::SignerConfig;
+
+/// A backend that signs transactions for one account
+#[async_trait]
+pub trait TxSigner: Send + Sync {
+    /// Backend name as used in configuration
+    fn backend(&self) -> &'static str;
+    
+    /// Address of the account
+    fn address(&self) -> Address;
+    
+    /// Sign a transaction, which must already name its chain
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature>;
+}
+
+/// Key held in process memory
+pub struct LocalSigner {
+    wallet: LocalWallet,
+}
+
+#[async_trait]
+impl TxSigner for LocalSigner {
+    fn backend(&self) -> &'static str {
+        "local"
+    }
+    
+    fn address(&self) -> Address {
+        self.wallet.address()
+    }
+    
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature> {
+        Ok(self.wallet.sign_transaction(tx).await?)
+    }
+}
+
+/// Key held in AWS KMS; every signature is a KMS `Sign` call and the key never leaves KMS
+pub struct KmsSigner {
+    signer: AwsSigner,
+}
+
+impl KmsSigner {
+    /// Connect to a KMS key, fetching its public key to derive the address
+    pub async fn connect(key_id: &str, region: Option<&str>, chain_id: u64) -> Result<Self> {
+        let region = match region {
+            Some(region) => Region::from_str(region).with_context(|| format!("Invalid AWS region {}", region))?,
+            None => Region::default(),
+        };
+        let signer = AwsSigner::new(KmsClient::new(region), key_id, chain_id)
+            .await
+            .with_context(|| format!("Failed to load KMS key {}", key_id))?;
+        
+        Ok(Self { signer })
+    }
+}
+
+#[async_trait]
+impl TxSigner for KmsSigner {
+    fn backend(&self) -> &'static str {
+        "kms"
+    }
+    
+    fn address(&self) -> Address {
+        self.signer.address()
+    }
+    
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature> {
+        self.signer
+            .sign_transaction(tx)
+            .await
+            .map_err(|e| anyhow!("KMS signing failed: {}", e))
+    }
+}
+
+/// Named accounts we sign transactions with
+///
+/// In-memory keys are loaded once at startup from encrypted keystores, environment variables
+/// or the config itself and never leave the registry; KMS-backed accounts hold no key at all.
+/// Callers sign by account name.
+#[derive(Clone, Default)]
+pub struct SignerRegistry {
+    /// Signers by account name
+    by_name: Arc<HashMap<String, Arc<dyn TxSigner>>>,
+    /// Account names by address
+    by_address: Arc<HashMap<Address, String>>,
+    /// Chain transactions are signed for
+    chain_id: u64,
+}
+
+impl SignerRegistry {
+    /// Load every configured signer for the given chain
+    pub async fn load(configs: &[SignerConfig], chain_id: u64) -> Result<Self> {
+        let mut by_name = HashMap::new();
+        let mut by_address = HashMap::new();
+        
+        for config in configs {
+            let signer = load_signer(config, chain_id)
+                .await
+                .with_context(|| format!("Failed to load signer {}", config.name))?;
+            
+            info!("Loaded {} signer {} for {:?}", signer.backend(), config.name, signer.address());
+            by_address.insert(signer.address(), config.name.clone());
+            by_name.insert(config.name.clone(), signer);
+        }
+        
+        Ok(Self {
+            by_name: Arc::new(by_name),
+            by_address: Arc::new(by_address),
+            chain_id,
+        })
+    }
+    
+    /// Address of a named account
+    pub fn address(&self, name: &str) -> Result<Address> {
+        Ok(self.signer(name)?.address())
+    }
+    
+    /// Name of the account holding an address, if it is one of ours
+    pub fn name_of(&self, address: Address) -> Option<&str> {
+        self.by_address.get(&address).map(String::as_str)
+    }
+    
+    /// Names of all loaded accounts
+    pub fn names(&self) -> Vec<String> {
+        self.by_name.keys().cloned().collect()
+    }
+    
+    /// Sign a transaction under a named account, returning the raw signed transaction
+    ///
+    /// The transaction's sender is set to the account.
+    pub async fn sign(&self, name: &str, tx: &mut TypedTransaction) -> Result<Bytes> {
+        let signer = self.signer(name)?;
+        tx.set_from(signer.address());
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.chain_id);
+        }
+        
+        let signature = signer
+            .sign_transaction(tx)
+            .await
+            .with_context(|| format!("Failed to sign transaction as {}", name))?;
+        
+        Ok(tx.rlp_signed(&signature))
+    }
+    
+    fn signer(&self, name: &str) -> Result<&Arc<dyn TxSigner>> {
+        self.by_name.get(name).ok_or_else(|| anyhow!("Unknown signer {}", name))
+    }
+}
+
+async fn load_signer(config: &SignerConfig, chain_id: u64) -> Result<Arc<dyn TxSigner>> {
+    if config.kind == "kms" {
+        let key_id = config
+            .kms_key_id
+            .as_ref()
+            .ok_or_else(|| anyhow!("KMS signer requires kms_key_id"))?;
+        let signer = KmsSigner::connect(key_id, config.kms_region.as_deref(), chain_id).await?;
+        return Ok(Arc::new(signer));
+    }
+    
+    let wallet = load_wallet(config)?.with_chain_id(chain_id);
+    Ok(Arc::new(LocalSigner { wallet }))
+}
+
+fn load_wallet(config: &SignerConfig) -> Result<LocalWallet> {
+    match config.kind.as_str() {
+        "keystore" => {
+            let path = config
+                .keystore_path
+                .as_ref()
+                .ok_or_else(|| anyhow!("Keystore signer requires keystore_path"))?;
+            let password_env = config
+                .password_env
+                .as_ref()
+                .ok_or_else(|| anyhow!("Keystore signer requires password_env"))?;
+            let password = std::env::var(password_env)
+                .with_context(|| format!("Keystore password variable {} is not set", password_env))?;
+            
+            LocalWallet::decrypt_keystore(path, password)
+                .with_context(|| format!("Failed to decrypt keystore {}", path))
+        }
+        "env" => {
+            let key_env = config
+                .key_env
+                .as_ref()
+                .ok_or_else(|| anyhow!("Env signer requires key_env"))?;
+            let key = std::env::var(key_env)
+                .with_context(|| format!("Private key variable {} is not set", key_env))?;
+            
+            parse_key(&key)
+        }
+        "inline" => {
+            let key = config
+                .private_key
+                .as_ref()
+                .ok_or_else(|| anyhow!("Inline signer requires private_key"))?;
+            
+            parse_key(key)
+        }
+        other => Err(anyhow!("Unknown signer kind {}", other)),
+    }
+}
+
+/// Parse a hex private key, without echoing it in the error
+fn parse_key(key: &str) -> Result<LocalWallet> {
+    key.trim()
+        .trim_start_matches("0x")
+        .parse::<LocalWallet>()
+        .map_err(|_| anyhow!("Invalid private key"))
+}
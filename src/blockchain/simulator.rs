@@ -0,0 +1,350 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::{
+    providers::{Http, Provider},
+    types::{Address, BlockId, Bytes, Transaction, H256, U256},
+};
+use revm::{
+    db::{CacheDB, EthersDB},
+    primitives::{
+        Address as EvmAddress, Bytes as EvmBytes, ExecutionResult, ResultAndState, TransactTo, U256 as EvmU256,
+    },
+    Database, DatabaseCommit, EVM,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+
+use crate::api::models;
+
+/// ERC-20 `Transfer(address,address,uint256)` event topic
+pub const TRANSFER_TOPIC: [u8; 32] = [
+    0xdd, 0xf2, 0x52, 0xad, 0x1b, 0xe2, 0xc8, 0x9b, 0x69, 0xc2, 0xb0, 0x68, 0xfc, 0x37, 0x8d, 0xaa,
+    0x95, 0x2b, 0xa7, 0xf1, 0x63, 0xc4, 0xa1, 0x16, 0x28, 0xf5, 0x5a, 0x4d, 0xf5, 0x23, 0xb3, 0xef,
+];
+
+/// EVM over lazily fetched forked state
+type ForkEvm = EVM<CacheDB<EthersDB<Provider<Http>>>>;
+
+/// Block context a transaction is executed in
+#[derive(Debug, Clone, Copy)]
+pub struct ForkBlock {
+    /// Block whose post-state is forked
+    pub number: u64,
+    pub timestamp: U256,
+    pub base_fee: U256,
+    pub gas_limit: U256,
+    /// Fee recipient, whose balance change is the block builder's take
+    pub coinbase: Address,
+}
+
+/// A log emitted during simulation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedLog {
+    #[serde(with = "models::checksum")]
+    pub address: Address,
+    pub topics: Vec<H256>,
+    pub data: Bytes,
+}
+
+/// A storage slot changed by the transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageDiff {
+    pub slot: U256,
+    pub before: U256,
+    pub after: U256,
+}
+
+/// Changes to one account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountDiff {
+    #[serde(with = "models::checksum")]
+    pub address: Address,
+    #[serde(with = "models::wei")]
+    pub balance_before: U256,
+    #[serde(with = "models::wei")]
+    pub balance_after: U256,
+    pub nonce_before: u64,
+    pub nonce_after: u64,
+    pub storage: Vec<StorageDiff>,
+}
+
+/// Net ERC-20 movement for the searcher, a signed decimal string
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenDelta {
+    #[serde(with = "models::checksum")]
+    pub token: Address,
+    pub delta: String,
+}
+
+/// Full result of executing a transaction against forked state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionTrace {
+    pub fork_block: u64,
+    pub success: bool,
+    pub gas_used: u64,
+    /// Revert data or halt reason when the transaction failed
+    pub failure: Option<String>,
+    pub logs: Vec<SimulatedLog>,
+    pub state_diff: Vec<AccountDiff>,
+    /// Accounts that ran `SELFDESTRUCT`
+    #[serde(default)]
+    pub selfdestructed: Vec<Address>,
+    /// Account balance deltas are reported for, the transaction sender
+    #[serde(with = "models::checksum")]
+    pub searcher: Address,
+    /// Searcher ETH balance change in wei, a signed decimal string
+    pub searcher_eth_delta: String,
+    pub searcher_token_deltas: Vec<TokenDelta>,
+    /// Fee recipient balance increase in wei, our take as builder
+    #[serde(with = "models::wei")]
+    pub coinbase_profit: U256,
+}
+
+/// Executes transactions with revm against state forked from our node
+#[derive(Clone)]
+pub struct ForkSimulator {
+    provider: Arc<Provider<Http>>,
+    chain_id: u64,
+}
+
+impl ForkSimulator {
+    /// Create a simulator reading state through the given provider
+    pub fn new(provider: Provider<Http>, chain_id: u64) -> Self {
+        Self {
+            provider: Arc::new(provider),
+            chain_id,
+        }
+    }
+    
+    /// Execute a transaction on top of the fork block's post-state
+    ///
+    /// State is fetched lazily over RPC, so execution runs on the blocking pool.
+    pub async fn execute(&self, tx: &Transaction, block: ForkBlock) -> Result<ExecutionTrace> {
+        let simulator = self.clone();
+        let tx = tx.clone();
+        
+        tokio::task::spawn_blocking(move || simulator.execute_blocking(&tx, block))
+            .await
+            .context("Simulation task panicked")?
+    }
+    
+    /// Execute transactions in order, each on top of the state left by the ones before
+    ///
+    /// Reverted transactions still commit their gas payment and nonce bump, as they would on chain.
+    pub async fn execute_bundle(&self, txs: &[Transaction], block: ForkBlock) -> Result<Vec<ExecutionTrace>> {
+        let simulator = self.clone();
+        let txs = txs.to_vec();
+        
+        tokio::task::spawn_blocking(move || {
+            let mut evm = simulator.fork_evm(block)?;
+            txs.iter()
+                .map(|tx| Self::transact(&mut evm, tx, block, true))
+                .collect()
+        })
+        .await
+        .context("Simulation task panicked")?
+    }
+    
+    fn execute_blocking(&self, tx: &Transaction, block: ForkBlock) -> Result<ExecutionTrace> {
+        let mut evm = self.fork_evm(block)?;
+        Self::transact(&mut evm, tx, block, false)
+    }
+    
+    /// An EVM over the fork block's post-state in the context of the block after it
+    fn fork_evm(&self, block: ForkBlock) -> Result<ForkEvm> {
+        let ethers_db = EthersDB::new(self.provider.clone(), Some(BlockId::from(block.number)))
+            .ok_or_else(|| anyhow!("Failed to fork state at block {}", block.number))?;
+        
+        let mut evm = EVM::new();
+        evm.database(CacheDB::new(ethers_db));
+        
+        evm.env.cfg.chain_id = self.chain_id;
+        evm.env.block.number = EvmU256::from(block.number + 1);
+        evm.env.block.timestamp = to_evm_u256(block.timestamp) + EvmU256::from(12);
+        evm.env.block.basefee = to_evm_u256(block.base_fee);
+        evm.env.block.gas_limit = to_evm_u256(block.gas_limit);
+        evm.env.block.coinbase = to_evm_address(block.coinbase);
+        
+        Ok(evm)
+    }
+    
+    /// Execute one transaction, committing its state changes to the cache when `commit` is set
+    fn transact(evm: &mut ForkEvm, tx: &Transaction, block: ForkBlock, commit: bool) -> Result<ExecutionTrace> {
+        evm.env.tx.caller = to_evm_address(tx.from);
+        evm.env.tx.transact_to = match tx.to {
+            Some(to) => TransactTo::Call(to_evm_address(to)),
+            None => TransactTo::create(),
+        };
+        evm.env.tx.data = EvmBytes::from(tx.input.to_vec());
+        evm.env.tx.value = to_evm_u256(tx.value);
+        evm.env.tx.gas_limit = tx.gas.as_u64();
+        evm.env.tx.nonce = Some(tx.nonce.as_u64());
+        evm.env.tx.chain_id = tx.chain_id.map(|id| id.as_u64());
+        match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+            (Some(max_fee), priority) => {
+                evm.env.tx.gas_price = to_evm_u256(max_fee);
+                evm.env.tx.gas_priority_fee = priority.map(to_evm_u256);
+            }
+            _ => {
+                evm.env.tx.gas_price = to_evm_u256(tx.gas_price.unwrap_or_default());
+                evm.env.tx.gas_priority_fee = None;
+            }
+        }
+        evm.env.tx.access_list = tx
+            .access_list
+            .as_ref()
+            .map(|list| {
+                list.0
+                    .iter()
+                    .map(|item| {
+                        let keys = item
+                            .storage_keys
+                            .iter()
+                            .map(|key| EvmU256::from_be_bytes(key.0))
+                            .collect();
+                        (to_evm_address(item.address), keys)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        let ResultAndState { result, state } = evm
+            .transact()
+            .map_err(|e| anyhow!("EVM error: {:?}", e))?;
+        
+        // The transaction is not committed yet, so the cache still holds pre-state
+        let db = evm.db.as_mut().ok_or_else(|| anyhow!("EVM database missing"))?;
+        let mut state_diff = Vec::new();
+        let mut selfdestructed = Vec::new();
+        let mut balances = HashMap::new();
+        for (address, account) in &state {
+            if !account.is_touched() {
+                continue;
+            }
+            if account.is_selfdestructed() {
+                selfdestructed.push(Address::from_slice(address.as_slice()));
+            }
+            
+            let before = db
+                .basic(*address)
+                .map_err(|e| anyhow!("Failed to load account {}: {:?}", address, e))?
+                .unwrap_or_default();
+            let address = Address::from_slice(address.as_slice());
+            balances.insert(address, (from_evm_u256(before.balance), from_evm_u256(account.info.balance)));
+            
+            state_diff.push(AccountDiff {
+                address,
+                balance_before: from_evm_u256(before.balance),
+                balance_after: from_evm_u256(account.info.balance),
+                nonce_before: before.nonce,
+                nonce_after: account.info.nonce,
+                storage: account
+                    .storage
+                    .iter()
+                    .filter(|(_, slot)| slot.is_changed())
+                    .map(|(key, slot)| StorageDiff {
+                        slot: from_evm_u256(*key),
+                        before: from_evm_u256(slot.original_value()),
+                        after: from_evm_u256(slot.present_value()),
+                    })
+                    .collect(),
+            });
+        }
+        state_diff.sort_by_key(|diff| diff.address);
+        selfdestructed.sort();
+        if commit {
+            db.commit(state);
+        }
+        
+        let (success, gas_used, failure, logs) = match result {
+            ExecutionResult::Success { gas_used, logs, .. } => (true, gas_used, None, logs),
+            ExecutionResult::Revert { gas_used, output } => {
+                (false, gas_used, Some(format!("revert: 0x{}", hex::encode(output))), Vec::new())
+            }
+            ExecutionResult::Halt { reason, gas_used } => (false, gas_used, Some(format!("halt: {:?}", reason)), Vec::new()),
+        };
+        
+        let logs: Vec<SimulatedLog> = logs
+            .into_iter()
+            .map(|log| SimulatedLog {
+                address: Address::from_slice(log.address.as_slice()),
+                topics: log.topics.iter().map(|topic| H256::from_slice(topic.as_slice())).collect(),
+                data: Bytes::from(log.data.to_vec()),
+            })
+            .collect();
+        
+        let (searcher_before, searcher_after) = balances.get(&tx.from).copied().unwrap_or_default();
+        let coinbase_profit = balances
+            .get(&block.coinbase)
+            .map(|(before, after)| after.saturating_sub(*before))
+            .unwrap_or_default();
+        
+        Ok(ExecutionTrace {
+            fork_block: block.number,
+            success,
+            gas_used,
+            failure,
+            searcher_token_deltas: token_deltas(&logs, tx.from),
+            logs,
+            state_diff,
+            selfdestructed,
+            searcher: tx.from,
+            searcher_eth_delta: signed_delta(searcher_before, searcher_after),
+            coinbase_profit,
+        })
+    }
+}
+
+/// Net ERC-20 transfers into and out of an account, from Transfer logs
+fn token_deltas(logs: &[SimulatedLog], account: Address) -> Vec<TokenDelta> {
+    let transfer_topic = H256::from(TRANSFER_TOPIC);
+    let mut flows: HashMap<Address, (U256, U256)> = HashMap::new();
+    
+    for log in logs {
+        if log.topics.len() != 3 || log.topics[0] != transfer_topic || log.data.len() < 32 {
+            continue;
+        }
+        
+        let from = Address::from(log.topics[1]);
+        let to = Address::from(log.topics[2]);
+        let amount = U256::from_big_endian(&log.data[..32]);
+        let flow = flows.entry(log.address).or_default();
+        if to == account {
+            flow.0 = flow.0.saturating_add(amount);
+        }
+        if from == account {
+            flow.1 = flow.1.saturating_add(amount);
+        }
+    }
+    
+    let mut deltas: Vec<TokenDelta> = flows
+        .into_iter()
+        .filter(|(_, (incoming, outgoing))| incoming != outgoing)
+        .map(|(token, (incoming, outgoing))| TokenDelta {
+            token,
+            delta: signed_delta(outgoing, incoming),
+        })
+        .collect();
+    deltas.sort_by_key(|delta| delta.token);
+    deltas
+}
+
+/// `after - before` as a signed decimal string
+fn signed_delta(before: U256, after: U256) -> String {
+    if after >= before {
+        (after - before).to_string()
+    } else {
+        format!("-{}", before - after)
+    }
+}
+
+fn to_evm_address(address: Address) -> EvmAddress {
+    EvmAddress::from(address.0)
+}
+
+fn to_evm_u256(value: U256) -> EvmU256 {
+    EvmU256::from_limbs(value.0)
+}
+
+fn from_evm_u256(value: EvmU256) -> U256 {
+    U256(value.into_limbs())
+}
@@ -0,0 +1,330 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use ethers::types::{Address, BlockNumber, H256};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::{blockchain::BlockchainClient, database::RedisPool};
+
+/// A transaction we sent that has not been mined yet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InFlightTx {
+    pub nonce: u64,
+    pub tx_hash: H256,
+    pub sent_at: DateTime<Utc>,
+}
+
+/// A nonce handed out for one outbound transaction
+///
+/// Must be handed back through `mark_sent` or `release`; a reservation that is neither
+/// leaves a gap until the next resync finds it.
+#[derive(Debug)]
+pub struct NonceReservation {
+    pub address: Address,
+    pub nonce: u64,
+}
+
+/// Nonce state of one account
+#[derive(Debug, Default)]
+struct AccountNonces {
+    /// Lowest nonce never handed out
+    next: u64,
+    /// Handed out, send not yet completed
+    reserved: BTreeSet<u64>,
+    /// Handed back after a failed send, reused before `next`
+    released: BTreeSet<u64>,
+    /// Sent and not yet mined
+    in_flight: BTreeMap<u64, InFlightTx>,
+}
+
+impl AccountNonces {
+    /// Return a nonce to the pool, shrinking `next` when the top of the range frees up
+    fn release(&mut self, nonce: u64) {
+        self.released.insert(nonce);
+        while self.next > 0 && self.released.remove(&(self.next - 1)) {
+            self.next -= 1;
+        }
+    }
+}
+
+/// Hands out nonces for our outbound transactions
+///
+/// Each account is allocated locally under its own lock, so services sending concurrently
+/// from one account never collide. In-flight transactions are persisted to Redis to survive
+/// restarts; `resync` reconciles against the chain, turning unused nonces below `next` into
+/// gaps that the next reservation fills, and reports transactions pending for too long.
+#[derive(Clone)]
+pub struct NonceManager {
+    /// Blockchain client, source of the on-chain nonce
+    blockchain_client: Arc<BlockchainClient>,
+    /// Redis connection, persists in-flight transactions
+    redis: RedisPool,
+    /// Nonce state per account, loaded on first use
+    accounts: Arc<DashMap<Address, Arc<Mutex<AccountNonces>>>>,
+    /// In-flight transactions older than this are reported as stuck
+    stuck_after: Duration,
+}
+
+impl NonceManager {
+    /// Create a new nonce manager
+    pub fn new(blockchain_client: Arc<BlockchainClient>, redis: RedisPool, stuck_after: Duration) -> Self {
+        Self {
+            blockchain_client,
+            redis,
+            accounts: Arc::new(DashMap::new()),
+            stuck_after,
+        }
+    }
+    
+    /// Reserve the next nonce for an account
+    pub async fn reserve(&self, address: Address) -> Result<NonceReservation> {
+        let account = self.account(address).await?;
+        let mut account = account.lock().await;
+        
+        let nonce = match account.released.pop_first() {
+            Some(nonce) => nonce,
+            None => {
+                account.next += 1;
+                account.next - 1
+            }
+        };
+        account.reserved.insert(nonce);
+        
+        debug!("Reserved nonce {} for {:?}", nonce, address);
+        Ok(NonceReservation { address, nonce })
+    }
+    
+    /// Record that the transaction using a reservation was accepted by the node
+    pub async fn mark_sent(&self, reservation: NonceReservation, tx_hash: H256) -> Result<()> {
+        let account = self.account(reservation.address).await?;
+        let mut account = account.lock().await;
+        
+        account.reserved.remove(&reservation.nonce);
+        let tx = InFlightTx {
+            nonce: reservation.nonce,
+            tx_hash,
+            sent_at: Utc::now(),
+        };
+        self.persist(reservation.address, &tx).await?;
+        account.in_flight.insert(reservation.nonce, tx);
+        
+        Ok(())
+    }
+    
+    /// Hand back a reservation whose transaction was never sent
+    pub async fn release(&self, reservation: NonceReservation) -> Result<()> {
+        let account = self.account(reservation.address).await?;
+        let mut account = account.lock().await;
+        
+        account.reserved.remove(&reservation.nonce);
+        account.release(reservation.nonce);
+        
+        Ok(())
+    }
+    
+    /// Record a replacement sent for an in-flight transaction at the same nonce
+    pub async fn mark_replaced(&self, address: Address, nonce: u64, tx_hash: H256) -> Result<()> {
+        let account = self.account(address).await?;
+        let mut account = account.lock().await;
+        
+        let tx = InFlightTx {
+            nonce,
+            tx_hash,
+            sent_at: Utc::now(),
+        };
+        self.persist(address, &tx).await?;
+        account.in_flight.insert(nonce, tx);
+        
+        Ok(())
+    }
+    
+    /// The transaction now in flight at an account's nonce, a replacement if one was sent
+    pub async fn in_flight(&self, address: Address, nonce: u64) -> Result<Option<InFlightTx>> {
+        let account = self.account(address).await?;
+        let account = account.lock().await;
+        
+        Ok(account.in_flight.get(&nonce).cloned())
+    }
+    
+    /// Give up on an in-flight transaction the node no longer knows, freeing its nonce
+    pub async fn mark_dropped(&self, address: Address, nonce: u64) -> Result<()> {
+        let account = self.account(address).await?;
+        let mut account = account.lock().await;
+        
+        if account.in_flight.remove(&nonce).is_some() {
+            self.forget(address, &[nonce]).await?;
+            account.release(nonce);
+            warn!("Dropped transaction at nonce {} of {:?}, nonce will be reused", nonce, address);
+        }
+        
+        Ok(())
+    }
+    
+    /// Reconcile an account with the chain, returning its stuck transactions in nonce order
+    pub async fn resync(&self, address: Address) -> Result<Vec<InFlightTx>> {
+        let confirmed = self
+            .blockchain_client
+            .get_transaction_count(address, BlockNumber::Latest)
+            .await?;
+        let pending = self
+            .blockchain_client
+            .get_transaction_count(address, BlockNumber::Pending)
+            .await?;
+        
+        let account = self.account(address).await?;
+        let mut account = account.lock().await;
+        
+        // Everything below the on-chain nonce is mined, by us or by someone sharing the key
+        let mined: Vec<u64> = account.in_flight.range(..confirmed).map(|(nonce, _)| *nonce).collect();
+        for nonce in &mined {
+            account.in_flight.remove(nonce);
+        }
+        self.forget(address, &mined).await?;
+        account.released.retain(|nonce| *nonce >= confirmed);
+        account.next = account.next.max(confirmed);
+        
+        // Nonces the node doesn't hold and we have no record of were lost, e.g. with a crash mid-send
+        let gaps: Vec<u64> = (pending.max(confirmed)..account.next)
+            .filter(|nonce| {
+                !account.in_flight.contains_key(nonce)
+                    && !account.reserved.contains(nonce)
+                    && !account.released.contains(nonce)
+            })
+            .collect();
+        if !gaps.is_empty() {
+            warn!("Found nonce gaps {:?} for {:?}", gaps, address);
+            metrics::counter!("nonce_gaps_total", gaps.len() as u64);
+            for nonce in gaps {
+                account.release(nonce);
+            }
+        }
+        
+        let cutoff = Utc::now() - chrono::Duration::from_std(self.stuck_after).unwrap_or_else(|_| chrono::Duration::zero());
+        let stuck: Vec<InFlightTx> = account
+            .in_flight
+            .values()
+            .filter(|tx| tx.sent_at < cutoff)
+            .cloned()
+            .collect();
+        
+        Ok(stuck)
+    }
+    
+    /// Resync every account in use, returning all stuck transactions
+    pub async fn resync_all(&self) -> Result<Vec<(Address, InFlightTx)>> {
+        let addresses: Vec<Address> = self.accounts.iter().map(|entry| *entry.key()).collect();
+        
+        let mut stuck = Vec::new();
+        for address in addresses {
+            match self.resync(address).await {
+                Ok(txs) => stuck.extend(txs.into_iter().map(|tx| (address, tx))),
+                Err(e) => warn!("Failed to resync nonces of {:?}: {}", address, e),
+            }
+        }
+        
+        metrics::gauge!("nonce_stuck_transactions", stuck.len() as f64);
+        Ok(stuck)
+    }
+    
+    /// Nonce state of an account, loading it from Redis and the chain on first use
+    async fn account(&self, address: Address) -> Result<Arc<Mutex<AccountNonces>>> {
+        if let Some(account) = self.accounts.get(&address) {
+            return Ok(account.clone());
+        }
+        
+        let loaded = self.load(address).await?;
+        Ok(self
+            .accounts
+            .entry(address)
+            .or_insert_with(|| Arc::new(Mutex::new(loaded)))
+            .clone())
+    }
+    
+    async fn load(&self, address: Address) -> Result<AccountNonces> {
+        let confirmed = self
+            .blockchain_client
+            .get_transaction_count(address, BlockNumber::Latest)
+            .await?;
+        let pending = self
+            .blockchain_client
+            .get_transaction_count(address, BlockNumber::Pending)
+            .await?;
+        
+        let mut redis = self.redis.clone();
+        let stored: HashMap<String, String> = redis
+            .hgetall(redis_key(address))
+            .await
+            .context("Failed to load in-flight transactions")?;
+        
+        let mut account = AccountNonces {
+            next: pending.max(confirmed),
+            ..Default::default()
+        };
+        let mut mined = Vec::new();
+        for value in stored.values() {
+            let tx: InFlightTx = match serde_json::from_str(value) {
+                Ok(tx) => tx,
+                Err(e) => {
+                    warn!("Skipping unreadable in-flight transaction of {:?}: {}", address, e);
+                    continue;
+                }
+            };
+            if tx.nonce < confirmed {
+                mined.push(tx.nonce);
+                continue;
+            }
+            account.next = account.next.max(tx.nonce + 1);
+            account.in_flight.insert(tx.nonce, tx);
+        }
+        self.forget(address, &mined).await?;
+        
+        // Nonces we handed out before the restart that never reached the node
+        for nonce in pending.max(confirmed)..account.next {
+            if !account.in_flight.contains_key(&nonce) {
+                account.released.insert(nonce);
+            }
+        }
+        
+        info!(
+            "Loaded nonces for {:?}: next {}, {} in flight, {} gaps",
+            address,
+            account.next,
+            account.in_flight.len(),
+            account.released.len()
+        );
+        
+        Ok(account)
+    }
+    
+    async fn persist(&self, address: Address, tx: &InFlightTx) -> Result<()> {
+        let mut redis = self.redis.clone();
+        redis
+            .hset::<_, _, _, ()>(redis_key(address), tx.nonce, serde_json::to_string(tx)?)
+            .await
+            .context("Failed to persist in-flight transaction")
+    }
+    
+    async fn forget(&self, address: Address, nonces: &[u64]) -> Result<()> {
+        if nonces.is_empty() {
+            return Ok(());
+        }
+        
+        let mut redis = self.redis.clone();
+        redis
+            .hdel::<_, _, ()>(redis_key(address), nonces)
+            .await
+            .context("Failed to remove mined transactions")
+    }
+}
+
+fn redis_key(address: Address) -> String {
+    format!("nonces:{:?}", address)
+}
@@ -0,0 +1,378 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::types::{Address, Bytes, Transaction, TransactionReceipt, H256};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+
+use crate::{
+    api::{
+        auth::IssuedToken,
+        handlers::{
+            analytics::{EfficiencyQuery, GasSavingsQuery, MarketBidsQuery, ReconciliationQuery},
+            apr::AprQuery,
+            auth::TokenRequest,
+            bundles::{SealedBundleResponse, SimulateBundleRequest},
+            events::EventsQuery,
+            export::ExportQuery,
+            health::{HealthResponse, ReadinessResponse},
+            mempool::{FeeQuery, PendingQuery, StoredQuery},
+            permit::PermitStakeResponse,
+            profits::ProfitSummaryQuery,
+            quote::QuoteRequest,
+            transactions::{SubmitTransactionRequest, SubmitTransactionResponse, TransactionStatusRequest},
+        },
+    },
+    blockchain::fees::{FeeSuggestion, GasPriceReport},
+    database::repositories::{events::EventPage, mempool::MempoolPage},
+    models::token::TokenMetadata,
+    services::{
+        analytics_export::ExportManifest,
+        build_decisions::BuildDecision,
+        gas_golf::GasSavingsReport,
+        kpi::HourlyKpis,
+        labels::AddressLabel,
+        mempool::{MempoolStats, PendingTxPage},
+        permit_deposits::PermitDeposit,
+        prices::TokenPrice,
+        profits::ProfitSummary,
+        relay_scraper::SlotMarketComparison,
+        sealed_bundles::{SealedBundle, SealingKeyInfo},
+        settlement::SlotReconciliation,
+        shadow_build::EfficiencyReport,
+        simulation::{BundleSimulationResult, CalibrationReport, SimulationResult},
+        staking_ledger::{AprSummary, StakeQuote, StakingPortfolio},
+        transaction::{InclusionCandidate, TxPrivacy, TxStatusSummary},
+    },
+};
+
+pub mod stream;
+pub mod webhooks;
+
+pub use stream::{ChannelSocket, TopicEvent, TopicSocket};
+pub use webhooks::{WebhookRejection, WebhookVerifier};
+
+/// Header an API key is sent in
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// Deadline of a request, streams are exempt
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How the client authenticates
+#[derive(Debug, Clone)]
+enum Credentials {
+    ApiKey(String),
+    Bearer(String),
+}
+
+/// Typed client of the public API
+///
+/// Every function maps to one endpoint and returns the type the server serializes, so client
+/// and server can't drift apart. Responses with an error status become errors carrying the
+/// status and the body the server sent, such as a validation error naming the field.
+///
+/// The block routes, `/api/blocks/latest`, `/api/blocks/:block_number` and `/api/blocks/simulate`,
+/// and the validator staking routes, `/api/staking/validators`, `/api/staking/rewards`,
+/// `/api/staking/stake` and `/api/staking/unstake`, have no functions yet: their handlers don't
+/// share their request and response types, so there is nothing to type them with.
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    /// API root without a trailing slash, e.g. `https://mev.example.com`
+    base_url: String,
+    credentials: Option<Credentials>,
+}
+
+impl ApiClient {
+    /// Create an unauthenticated client, enough for health checks and issuing tokens
+    pub fn new(base_url: impl Into<String>) -> Result<Self> {
+        let http = reqwest::Client::builder().connect_timeout(Duration::from_secs(10)).build()?;
+        
+        Ok(Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            credentials: None,
+        })
+    }
+    
+    /// Authenticate every request with an API key
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.credentials = Some(Credentials::ApiKey(api_key.into()));
+        self
+    }
+    
+    /// Authenticate every request with a session token from `issue_token`
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.credentials = Some(Credentials::Bearer(token.into()));
+        self
+    }
+    
+    // Health
+    
+    pub async fn health(&self) -> Result<HealthResponse> {
+        self.get("/api/health").await
+    }
+    
+    /// Readiness report, returned whether or not the server is ready
+    pub async fn ready(&self) -> Result<ReadinessResponse> {
+        let response = self
+            .request(Method::GET, "/api/ready")
+            .send()
+            .await
+            .context("Request to /api/ready failed")?;
+        if response.status() == StatusCode::SERVICE_UNAVAILABLE {
+            return response.json().await.context("Invalid response from /api/ready");
+        }
+        
+        decode("/api/ready", response).await
+    }
+    
+    /// Exchange an API key for a short-lived session token
+    pub async fn issue_token(&self, api_key: &str) -> Result<IssuedToken> {
+        let request = TokenRequest {
+            api_key: api_key.to_string(),
+        };
+        self.post("/api/auth/token", &request).await
+    }
+    
+    // Bundles
+    
+    /// Keys sealed bundles are encrypted to
+    pub async fn sealing_keys(&self) -> Result<Vec<SealingKeyInfo>> {
+        self.get("/api/bundles/sealing-keys").await
+    }
+    
+    /// Simulate signed raw transactions in order against the latest state
+    pub async fn simulate_bundle(&self, txs: Vec<Bytes>) -> Result<BundleSimulationResult> {
+        self.post("/api/bundles/simulate", &SimulateBundleRequest { txs }).await
+    }
+    
+    /// Submit a bundle sealed to one of the `sealing_keys`
+    pub async fn submit_sealed_bundle(&self, bundle: &SealedBundle) -> Result<SealedBundleResponse> {
+        self.post("/api/bundles/sealed", bundle).await
+    }
+    
+    // Simulation
+    
+    pub async fn calibration(&self) -> Result<CalibrationReport> {
+        self.get("/api/simulation/calibration").await
+    }
+    
+    /// Simulate a transaction known to the node
+    pub async fn simulate_transaction(&self, tx_hash: H256) -> Result<SimulationResult> {
+        self.get(&format!("/api/simulation/tx/{:?}", tx_hash)).await
+    }
+    
+    // Transactions
+    
+    /// Submit a signed raw transaction, through builders only when private
+    pub async fn submit_transaction(
+        &self,
+        raw_tx: Bytes,
+        privacy: TxPrivacy,
+    ) -> Result<SubmitTransactionResponse> {
+        self.post("/api/transactions", &SubmitTransactionRequest { raw_tx, privacy }).await
+    }
+    
+    pub async fn transaction_statuses(&self, tx_hashes: Vec<H256>) -> Result<Vec<TxStatusSummary>> {
+        self.post("/api/transactions/status", &TransactionStatusRequest { tx_hashes }).await
+    }
+    
+    pub async fn transaction(&self, tx_hash: H256) -> Result<Transaction> {
+        self.get(&format!("/api/transactions/{:?}", tx_hash)).await
+    }
+    
+    pub async fn transaction_receipt(&self, tx_hash: H256) -> Result<TransactionReceipt> {
+        self.get(&format!("/api/transactions/{:?}/receipt", tx_hash)).await
+    }
+    
+    // Opportunities and mempool
+    
+    /// Pending inclusion candidates, only the caller's own unless it is an operator
+    pub async fn opportunities(&self) -> Result<Vec<InclusionCandidate>> {
+        self.get("/api/opportunities").await
+    }
+    
+    /// Pending transactions we hold in memory, filtered and paged
+    pub async fn pending_transactions(&self, query: &PendingQuery) -> Result<PendingTxPage> {
+        self.get_with("/api/mempool/pending", query).await
+    }
+    
+    /// Stored mempool history, filtered and paged
+    pub async fn stored_transactions(&self, query: &StoredQuery) -> Result<MempoolPage> {
+        self.get_with("/api/mempool", query).await
+    }
+    
+    /// Indexed contract events, filtered and paged
+    pub async fn indexed_events(&self, query: &EventsQuery) -> Result<EventPage> {
+        self.get_with("/api/events", query).await
+    }
+    
+    pub async fn mempool_stats(&self) -> Result<MempoolStats> {
+        self.get("/api/mempool/stats").await
+    }
+    
+    pub async fn suggest_fees(&self, query: &FeeQuery) -> Result<FeeSuggestion> {
+        self.get_with("/api/mempool/fees", query).await
+    }
+    
+    /// Gas price report as of the latest head
+    pub async fn latest_gas_price(&self) -> Result<GasPriceReport> {
+        self.get("/api/gas/latest").await
+    }
+    
+    // Prices, tokens and labels
+    
+    /// Price of a token by address or symbol
+    pub async fn price(&self, token: &str) -> Result<TokenPrice> {
+        self.get(&format!("/api/prices/{}", token)).await
+    }
+    
+    pub async fn token(&self, address: Address) -> Result<TokenMetadata> {
+        self.get(&format!("/api/tokens/{:?}", address)).await
+    }
+    
+    pub async fn label(&self, address: Address) -> Result<AddressLabel> {
+        self.get(&format!("/api/labels/{:?}", address)).await
+    }
+    
+    // Analytics
+    
+    /// Rows of a named dashboard query, shaped by the query
+    pub async fn dashboard_query(&self, name: &str) -> Result<serde_json::Value> {
+        self.get(&format!("/api/analytics/query/{}", name)).await
+    }
+    
+    pub async fn kpis(&self) -> Result<HourlyKpis> {
+        self.get("/api/analytics/kpis").await
+    }
+    
+    pub async fn market_bids(&self, query: &MarketBidsQuery) -> Result<Vec<SlotMarketComparison>> {
+        self.get_with("/api/analytics/market-bids", query).await
+    }
+    
+    pub async fn reconciliation(&self, query: &ReconciliationQuery) -> Result<Vec<SlotReconciliation>> {
+        self.get_with("/api/analytics/reconciliation", query).await
+    }
+    
+    pub async fn builder_efficiency(&self, query: &EfficiencyQuery) -> Result<EfficiencyReport> {
+        self.get_with("/api/analytics/builder-efficiency", query).await
+    }
+    
+    pub async fn gas_savings(&self, query: &GasSavingsQuery) -> Result<GasSavingsReport> {
+        self.get_with("/api/analytics/gas-savings", query).await
+    }
+    
+    pub async fn profit_summary(&self, query: &ProfitSummaryQuery) -> Result<ProfitSummary> {
+        self.get_with("/api/profits/summary", query).await
+    }
+    
+    /// Record of what the builder decided for a slot
+    pub async fn build_decision(&self, slot: u64) -> Result<BuildDecision> {
+        self.get(&format!("/api/blocks/{}/decision", slot)).await
+    }
+    
+    // Export
+    
+    /// Built blocks in a range, encoded in the requested format
+    pub async fn export_blocks(&self, query: &ExportQuery) -> Result<Vec<u8>> {
+        let path = "/api/export/blocks";
+        let response = self
+            .request(Method::GET, path)
+            .query(query)
+            .send()
+            .await
+            .context(format!("Request to {} failed", path))?;
+        let response = check_status(path, response).await?;
+        
+        Ok(response.bytes().await.context(format!("Failed to read {}", path))?.to_vec())
+    }
+    
+    pub async fn export_manifest(&self) -> Result<ExportManifest> {
+        self.get("/api/export/manifest").await
+    }
+    
+    // Liquid staking
+    
+    pub async fn staking_apr(&self, query: &AprQuery) -> Result<AprSummary> {
+        self.get_with("/api/staking/apr", query).await
+    }
+    
+    pub async fn portfolio(&self, address: Address) -> Result<StakingPortfolio> {
+        self.get(&format!("/api/staking/portfolio/{:?}", address)).await
+    }
+    
+    /// Quote for staking an amount in ether, e.g. `"1.5"`
+    pub async fn quote_stake(&self, amount: &str) -> Result<StakeQuote> {
+        let request = QuoteRequest {
+            amount: amount.to_string(),
+        };
+        self.post("/api/staking/quote", &request).await
+    }
+    
+    /// Stake with a signed EIP-2612 permit, the server pays the gas
+    pub async fn stake_with_permit(&self, deposit: &PermitDeposit) -> Result<PermitStakeResponse> {
+        self.post("/api/staking/stake-with-permit", deposit).await
+    }
+    
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.authorize(self.http.request(method, format!("{}{}", self.base_url, path)))
+            .timeout(REQUEST_TIMEOUT)
+    }
+    
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.credentials {
+            Some(Credentials::ApiKey(key)) => request.header(API_KEY_HEADER, key),
+            Some(Credentials::Bearer(token)) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+    
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response = self
+            .request(Method::GET, path)
+            .send()
+            .await
+            .context(format!("Request to {} failed", path))?;
+        decode(path, response).await
+    }
+    
+    async fn get_with<Q: Serialize, T: DeserializeOwned>(&self, path: &str, query: &Q) -> Result<T> {
+        let response = self
+            .request(Method::GET, path)
+            .query(query)
+            .send()
+            .await
+            .context(format!("Request to {} failed", path))?;
+        decode(path, response).await
+    }
+    
+    async fn post<B: Serialize + ?Sized, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {
+        let response = self
+            .request(Method::POST, path)
+            .json(body)
+            .send()
+            .await
+            .context(format!("Request to {} failed", path))?;
+        decode(path, response).await
+    }
+}
+
+/// Fail on an error status, with the body the server explained it in
+async fn check_status(path: &str, response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    
+    let body = response.text().await.unwrap_or_default();
+    if body.is_empty() {
+        Err(anyhow!("{} returned {}", path, status))
+    } else {
+        Err(anyhow!("{} returned {}: {}", path, status, body))
+    }
+}
+
+async fn decode<T: DeserializeOwned>(path: &str, response: Response) -> Result<T> {
+    let response = check_status(path, response).await?;
+    response.json().await.context(format!("Invalid response from {}", path))
+}
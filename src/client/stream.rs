@@ -0,0 +1,227 @@
+use anyhow::{anyhow, Context, Result};
+use futures::{
+    stream::{self, BoxStream},
+    SinkExt, StreamExt,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    tungstenite::{client::IntoClientRequest, http::HeaderValue, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+
+use super::{check_status, ApiClient, Credentials, API_KEY_HEADER};
+use crate::{
+    api::{
+        encryption::{EventKeyPair, EventPsk},
+        handlers::stream as topics,
+        websocket as channels,
+    },
+    services::events::Topic,
+};
+
+/// An event received on a topic stream
+#[derive(Debug, Clone)]
+pub struct TopicEvent {
+    pub topic: Topic,
+    pub payload: serde_json::Value,
+}
+
+/// A WebSocket connection speaking one of the API's subscription protocols
+///
+/// The server answers every command with a message, and delivers events between them in
+/// whatever order they happen, so replies are read with `next` like any other message.
+pub struct Socket<C, S> {
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    /// Key of the credential the connection authenticated with, binding encrypted subscriptions
+    event_psk: Option<EventPsk>,
+    _protocol: PhantomData<(C, S)>,
+}
+
+/// Topic subscriptions over `/ws/subscribe`, events carry the raw payload
+pub type TopicSocket = Socket<topics::ClientMessage, topics::ServerMessage>;
+
+/// Filtered channel subscriptions over `/ws`
+pub type ChannelSocket = Socket<channels::ClientMessage, channels::ServerMessage>;
+
+impl<C: Serialize, S: DeserializeOwned> Socket<C, S> {
+    pub async fn send(&mut self, command: &C) -> Result<()> {
+        let text = serde_json::to_string(command)?;
+        self.socket
+            .send(Message::Text(text))
+            .await
+            .context("Failed to send WebSocket command")
+    }
+    
+    /// Next message from the server, `None` once it closed the connection
+    pub async fn next(&mut self) -> Result<Option<S>> {
+        while let Some(message) = self.socket.next().await {
+            match message.context("WebSocket connection failed")? {
+                Message::Text(text) => {
+                    return serde_json::from_str(&text)
+                        .map(Some)
+                        .context(format!("Invalid WebSocket message: {}", text));
+                }
+                Message::Close(_) => break,
+                _ => continue,
+            }
+        }
+        
+        Ok(None)
+    }
+    
+    pub async fn close(mut self) -> Result<()> {
+        self.socket.close(None).await.context("Failed to close WebSocket")
+    }
+}
+
+impl TopicSocket {
+    pub async fn subscribe(&mut self, topic: Topic) -> Result<()> {
+        self.send(&topics::ClientMessage::Subscribe {
+            topic: topic.as_str().to_string(),
+        })
+        .await
+    }
+    
+    pub async fn unsubscribe(&mut self, topic: Topic) -> Result<()> {
+        self.send(&topics::ClientMessage::Unsubscribe {
+            topic: topic.as_str().to_string(),
+        })
+        .await
+    }
+}
+
+impl ChannelSocket {
+    /// Subscribe to a channel with its filters, replacing the filters of an earlier subscription
+    pub async fn subscribe(&mut self, request: channels::SubscribeRequest) -> Result<()> {
+        self.send(&channels::ClientMessage::Subscribe(request)).await
+    }
+    
+    /// Subscribe with the channel's event payloads encrypted, returning the key for the exchange
+    ///
+    /// Complete the key with the `public_key` of the `Subscribed` reply, then open each
+    /// `EncryptedEvent` of the channel with the resulting cipher.
+    pub async fn subscribe_encrypted(&mut self, mut request: channels::SubscribeRequest) -> Result<EventKeyPair> {
+        let psk = self
+            .event_psk
+            .clone()
+            .ok_or_else(|| anyhow!("Encrypted subscriptions need an API key or session token"))?;
+        let key_pair = EventKeyPair::generate(psk);
+        request.public_key = Some(key_pair.public_key());
+        self.subscribe(request).await?;
+        Ok(key_pair)
+    }
+    
+    pub async fn unsubscribe(&mut self, topic: Topic) -> Result<()> {
+        self.send(&channels::ClientMessage::Unsubscribe {
+            topic: channels::channel_name(topic).to_string(),
+        })
+        .await
+    }
+}
+
+impl ApiClient {
+    /// Stream one topic as server-sent events
+    pub async fn events(&self, topic: Topic) -> Result<BoxStream<'static, Result<TopicEvent>>> {
+        let path = format!("/api/stream/{}", topic.as_str());
+        let response = self
+            .authorize(self.http.get(format!("{}{}", self.base_url, path)))
+            .send()
+            .await
+            .context(format!("Request to {} failed", path))?;
+        let response = check_status(&path, response).await?;
+        
+        let events = stream::unfold(
+            (response.bytes_stream(), Vec::new()),
+            |(mut bytes, mut buffer)| async move {
+                loop {
+                    // Events end with a blank line
+                    if let Some(end) = buffer.windows(2).position(|pair| pair == b"\n\n") {
+                        let frame: Vec<u8> = buffer.drain(..end + 2).collect();
+                        match parse_event(&frame) {
+                            Some(event) => return Some((event, (bytes, buffer))),
+                            None => continue,
+                        }
+                    }
+                    
+                    match bytes.next().await? {
+                        Ok(chunk) => buffer.extend_from_slice(&chunk),
+                        Err(e) => return Some((Err(anyhow!("Event stream failed: {}", e)), (bytes, buffer))),
+                    }
+                }
+            },
+        );
+        
+        Ok(events.boxed())
+    }
+    
+    /// Open a WebSocket for topic subscriptions
+    pub async fn topic_socket(&self) -> Result<TopicSocket> {
+        self.connect("/ws/subscribe").await
+    }
+    
+    /// Open a WebSocket for filtered channel subscriptions
+    pub async fn channel_socket(&self) -> Result<ChannelSocket> {
+        self.connect("/ws").await
+    }
+    
+    async fn connect<C, S>(&self, path: &str) -> Result<Socket<C, S>> {
+        let url = match self.base_url.split_once("://") {
+            Some(("https", rest)) => format!("wss://{}{}", rest, path),
+            Some(("http", rest)) => format!("ws://{}{}", rest, path),
+            _ => return Err(anyhow!("Unsupported API URL {}", self.base_url)),
+        };
+        
+        let mut request = url.as_str().into_client_request().context(format!("Invalid WebSocket URL {}", url))?;
+        let (credentials, event_psk) = match &self.credentials {
+            Some(Credentials::ApiKey(key)) => {
+                (Some((API_KEY_HEADER, key.clone())), Some(EventPsk::from_credential(key)))
+            }
+            Some(Credentials::Bearer(token)) => (
+                Some(("authorization", format!("Bearer {}", token))),
+                Some(EventPsk::from_credential(token)),
+            ),
+            None => (None, None),
+        };
+        if let Some((header, value)) = credentials {
+            let value = HeaderValue::from_str(&value).context("Invalid credentials")?;
+            request.headers_mut().insert(header, value);
+        }
+        
+        let (socket, _) = tokio_tungstenite::connect_async(request)
+            .await
+            .context(format!("Failed to connect to {}", url))?;
+        
+        Ok(Socket {
+            socket,
+            event_psk,
+            _protocol: PhantomData,
+        })
+    }
+}
+
+/// Topic and payload of one server-sent event, `None` for comments such as keep-alives
+fn parse_event(frame: &[u8]) -> Option<Result<TopicEvent>> {
+    let frame = String::from_utf8_lossy(frame);
+    let mut name = None;
+    let mut data = Vec::new();
+    for line in frame.lines() {
+        if let Some(value) = line.strip_prefix("event:") {
+            name = Some(value.trim_start());
+        } else if let Some(value) = line.strip_prefix("data:") {
+            data.push(value.strip_prefix(' ').unwrap_or(value));
+        }
+    }
+    if data.is_empty() {
+        return None;
+    }
+    
+    let event = name.ok_or_else(|| anyhow!("Event without a topic")).and_then(|name| {
+        Ok(TopicEvent {
+            topic: name.parse()?,
+            payload: serde_json::from_str(&data.join("\n")).context(format!("Invalid {} event", name))?,
+        })
+    });
+    Some(event)
+}
@@ -0,0 +1,111 @@
+use chrono::Utc;
+use parking_lot::Mutex;
+use reqwest::header::HeaderMap;
+use std::{collections::HashMap, fmt};
+
+use crate::services::webhooks::{signature, DELIVERY_ID_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+/// Why a webhook delivery was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookRejection {
+    MissingHeaders,
+    /// Sent longer ago, or further ahead, than the tolerance
+    Expired,
+    InvalidSignature,
+    /// A delivery with this id was already accepted
+    Replayed,
+}
+
+impl WebhookRejection {
+    /// Status to answer the delivery with
+    ///
+    /// 409 Conflict for a replay tells the sender the delivery already arrived, so it stops
+    /// retrying; the other refusals are retried until the sender gives up.
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::MissingHeaders => 400,
+            Self::Expired | Self::InvalidSignature => 401,
+            Self::Replayed => 409,
+        }
+    }
+}
+
+impl fmt::Display for WebhookRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHeaders => write!(f, "Delivery is missing its id, timestamp or signature"),
+            Self::Expired => write!(f, "Delivery timestamp is outside the tolerance"),
+            Self::InvalidSignature => write!(f, "Delivery signature does not match"),
+            Self::Replayed => write!(f, "Delivery was already received"),
+        }
+    }
+}
+
+impl std::error::Error for WebhookRejection {}
+
+/// Whether a signature header matches a delivery, compared in constant time
+pub fn verify_signature(
+    secret: &str,
+    delivery_id: &str,
+    timestamp: i64,
+    signature_header: &str,
+    body: &[u8],
+) -> bool {
+    let expected = signature(secret, delivery_id, timestamp, body);
+    expected.len() == signature_header.len()
+        && expected
+            .bytes()
+            .zip(signature_header.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Verifies signed alert webhook deliveries for a receiver, refusing replays
+///
+/// A delivery is accepted once: its id is remembered for as long as its timestamp is within the
+/// tolerance, and after that the timestamp alone refuses it. Verify the raw body as received,
+/// before parsing it.
+pub struct WebhookVerifier {
+    secret: String,
+    tolerance_seconds: i64,
+    /// Accepted delivery ids and their timestamps
+    seen: Mutex<HashMap<String, i64>>,
+}
+
+impl WebhookVerifier {
+    /// Create a verifier for the webhook's secret, accepting deliveries up to `tolerance_seconds` old
+    pub fn new(secret: impl Into<String>, tolerance_seconds: u64) -> Self {
+        Self {
+            secret: secret.into(),
+            tolerance_seconds: tolerance_seconds as i64,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+    
+    /// Verify a delivery from its request headers and raw body
+    pub fn verify(&self, headers: &HeaderMap, body: &[u8]) -> Result<(), WebhookRejection> {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        let (delivery_id, timestamp, signature_header) =
+            match (header(DELIVERY_ID_HEADER), header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER)) {
+                (Some(id), Some(timestamp), Some(signature)) => (id, timestamp, signature),
+                _ => return Err(WebhookRejection::MissingHeaders),
+            };
+        let timestamp: i64 = timestamp.parse().map_err(|_| WebhookRejection::MissingHeaders)?;
+        
+        let now = Utc::now().timestamp();
+        if (now - timestamp).abs() > self.tolerance_seconds {
+            return Err(WebhookRejection::Expired);
+        }
+        if !verify_signature(&self.secret, delivery_id, timestamp, signature_header, body) {
+            return Err(WebhookRejection::InvalidSignature);
+        }
+        
+        let mut seen = self.seen.lock();
+        seen.retain(|_, seen_at| (now - *seen_at).abs() <= self.tolerance_seconds);
+        if seen.insert(delivery_id.to_string(), timestamp).is_some() {
+            return Err(WebhookRejection::Replayed);
+        }
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::stream::TryStreamExt;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    config::Config,
+    database::{self, DbHealth},
+    services::export::{ExportFormat, ExportRange, ExportService},
+};
+
+/// Export built blocks in the given time range to a file
+pub async fn run(
+    config: &Config,
+    format: &str,
+    from: &str,
+    to: Option<&str>,
+    output: &str,
+) -> Result<()> {
+    let format: ExportFormat = format.parse()?;
+    let range = ExportRange {
+        from: parse_time(from)?,
+        to: to.map(parse_time).transpose()?.unwrap_or_else(Utc::now),
+    };
+    
+    let db_pool = database::connect(&config.database).await?;
+    let db_health = DbHealth::new(&config.database);
+    let export_service = ExportService::new(db_pool, db_health)?;
+    
+    let mut file = tokio::fs::File::create(output)
+        .await
+        .context(format!("Failed to create {}", output))?;
+    
+    match format {
+        ExportFormat::Json => {
+            let mut stream = Box::pin(export_service.stream_json(range, None));
+            while let Some(chunk) = stream.try_next().await? {
+                file.write_all(&chunk).await?;
+            }
+        }
+        ExportFormat::Parquet => {
+            let mut stream = Box::pin(export_service.stream_parquet(range, None)?);
+            while let Some(chunk) = stream.try_next().await? {
+                file.write_all(&chunk).await?;
+            }
+        }
+    }
+    
+    file.flush().await?;
+    println!("Exported built blocks to {}", output);
+    
+    Ok(())
+}
+
+fn parse_time(value: &str) -> Result<DateTime<Utc>> {
+    Ok(DateTime::parse_from_rfc3339(value)
+        .context(format!("Invalid RFC 3339 timestamp: {}", value))?
+        .with_timezone(&Utc))
+}
@@ -0,0 +1,268 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::types::{Transaction, TransactionReceipt, H256, U256};
+
+use crate::{
+    blockchain::{
+        self,
+        simulator::{ForkBlock, ForkSimulator},
+        BlockchainClient,
+    },
+    config::Config,
+    database::{
+        self,
+        repositories::{MempoolRepository, TokenRepository},
+        DbHealth,
+    },
+    services::{
+        export::ExportService,
+        mempool::{classify, decode_method},
+        simulation,
+    },
+    utils::units::EthAmount,
+};
+
+/// Print everything we know about a transaction, from our database and the node
+pub async fn run(config: &Config, hash: &str) -> Result<()> {
+    let tx_hash: H256 = hash.parse().context(format!("Invalid transaction hash: {}", hash))?;
+    
+    let db_pool = database::connect(&config.database).await?;
+    let blockchain_client =
+        blockchain::create_client(&config.blockchain, &config.caches, Some(db_pool.clone())).await?;
+    let redis = database::connect_redis(&config.redis).await?;
+    let db_health = DbHealth::new(&config.database);
+    let repository = MempoolRepository::new(db_pool.clone(), db_health.clone());
+    let tokens = TokenRepository::new(
+        db_pool.clone(),
+        db_health.clone(),
+        redis,
+        blockchain_client.clone(),
+        &config.caches.tokens,
+    );
+    let export_service = ExportService::new(db_pool.clone(), db_health)?;
+    
+    let tx = blockchain_client.get_transaction(tx_hash).await?;
+    let receipt = blockchain_client.get_transaction_receipt(tx_hash).await?;
+    let observed = repository.get(tx_hash).await?;
+    let disagreements = simulation::recorded_disagreements(&db_pool, tx_hash).await?;
+    let blocks = export_service.blocks_containing(tx_hash).await?;
+    
+    if tx.is_none() && observed.is_none() {
+        return Err(anyhow!("Transaction {:?} is unknown to both the node and our database", tx_hash));
+    }
+    
+    println!("Transaction {:?}", tx_hash);
+    
+    section("Observed");
+    match &observed {
+        Some(observed) => {
+            field("First seen", observed.first_seen_at.to_rfc3339());
+            field("Last seen", observed.last_seen_at.to_rfc3339());
+            field("Source", &observed.source);
+            field("Status", &observed.status);
+            if let Some(removed_at) = observed.removed_at {
+                field("Removed", removed_at.to_rfc3339());
+            }
+        }
+        None => println!("  Never seen in our mempool"),
+    }
+    
+    section("Transaction");
+    match &tx {
+        Some(tx) => print_transaction(&blockchain_client, tx).await,
+        None => println!("  Not known to the node, dropped or replaced before inclusion"),
+    }
+    
+    section("Inclusion");
+    match &receipt {
+        Some(receipt) => print_receipt(receipt),
+        None => println!("  Not included"),
+    }
+    
+    section("Bundles");
+    if blocks.is_empty() {
+        println!("  Not in any block we built");
+    }
+    for block in &blocks {
+        field("Block", format!("{} ({:?})", block.block_number, block.block_hash));
+        field("Built at", block.built_at.to_rfc3339());
+        for bundle in &block.bundles {
+            let position = bundle.tx_hashes.iter().position(|hash| *hash == tx_hash).unwrap_or_default();
+            field(
+                "Bundle",
+                format!(
+                    "position {} of {}, value {} ETH, source {}",
+                    position + 1,
+                    bundle.tx_hashes.len(),
+                    EthAmount::from_wei(bundle.value),
+                    bundle.source
+                ),
+            );
+        }
+    }
+    
+    section("Simulation");
+    match &tx {
+        Some(tx) => simulate(&blockchain_client, &tokens, tx, receipt.as_ref()).await,
+        None => println!("  Transaction body unavailable, not simulated"),
+    }
+    for disagreement in &disagreements {
+        field(
+            "Disagreement",
+            format!(
+                "{} {} ({} ETH) vs {} {} ({} ETH) at {}",
+                disagreement.primary_engine,
+                outcome(disagreement.primary_success),
+                EthAmount::from_wei(disagreement.primary_profit),
+                disagreement.shadow_engine,
+                outcome(disagreement.shadow_success),
+                EthAmount::from_wei(disagreement.shadow_profit),
+                disagreement.observed_at.to_rfc3339()
+            ),
+        );
+    }
+    
+    Ok(())
+}
+
+async fn print_transaction(blockchain_client: &BlockchainClient, tx: &Transaction) {
+    let method = decode_method(&tx.input);
+    
+    field("From", format!("{:?}", tx.from));
+    field("To", tx.to.map_or_else(|| "contract creation".to_string(), |to| format!("{:?}", to)));
+    field("Nonce", tx.nonce);
+    field("Value", format!("{} ETH", EthAmount::from_wei(tx.value)));
+    field("Gas limit", tx.gas);
+    if let Some(max_fee) = tx.max_fee_per_gas {
+        field("Max fee", format!("{} gwei", gwei(max_fee)));
+        field("Priority fee", format!("{} gwei", gwei(tx.max_priority_fee_per_gas.unwrap_or_default())));
+    } else {
+        field("Gas price", format!("{} gwei", gwei(tx.gas_price.unwrap_or_default())));
+    }
+    field("Type", tx.transaction_type.map_or(0, |t| t.as_u64()));
+    field("Method", &*method);
+    field("Class", classify(tx));
+    
+    // Calls to verified contracts decode against their ABI, beyond the selectors we know
+    let to = match tx.to {
+        Some(to) => to,
+        None => return,
+    };
+    match blockchain_client.decode_calldata(to, &tx.input).await {
+        Ok(Some((function, args))) => {
+            field("Call", function.signature());
+            for (index, (param, arg)) in function.inputs.iter().zip(&args).enumerate() {
+                let label = if param.name.is_empty() { format!("arg {}", index) } else { param.name.clone() };
+                field(&label, arg);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => field("Call", format!("not decoded: {:#}", e)),
+    }
+}
+
+fn print_receipt(receipt: &TransactionReceipt) {
+    field(
+        "Block",
+        format!(
+            "{} ({:?}), index {}",
+            receipt.block_number.unwrap_or_default(),
+            receipt.block_hash.unwrap_or_default(),
+            receipt.transaction_index
+        ),
+    );
+    field("Outcome", outcome(receipt.status.map_or(false, |status| status.as_u64() == 1)));
+    field("Gas used", receipt.gas_used.unwrap_or_default());
+    if let Some(price) = receipt.effective_gas_price {
+        field("Effective price", format!("{} gwei", gwei(price)));
+    }
+    field("Logs", receipt.logs.len());
+}
+
+/// Re-run the transaction against the state before its block, or the latest when pending
+///
+/// Other transactions ahead of it in its block are not replayed, so deltas can differ from
+/// what it did on chain when it depended on them.
+async fn simulate(
+    blockchain_client: &BlockchainClient,
+    tokens: &TokenRepository,
+    tx: &Transaction,
+    receipt: Option<&TransactionReceipt>,
+) {
+    let result = async {
+        let (number, header_number) = match receipt.and_then(|receipt| receipt.block_number) {
+            Some(included) => (included.as_u64().saturating_sub(1), included.as_u64()),
+            None => {
+                let latest = blockchain_client.get_block_number().await?;
+                (latest, latest)
+            }
+        };
+        let header = blockchain_client
+            .get_block_with_hashes(header_number)
+            .await?
+            .ok_or_else(|| anyhow!("Block {} not found", header_number))?;
+        let block = ForkBlock {
+            number,
+            timestamp: header.timestamp,
+            base_fee: header.base_fee_per_gas.unwrap_or_default(),
+            gas_limit: header.gas_limit,
+            coinbase: header.author.unwrap_or_default(),
+        };
+        
+        ForkSimulator::new(blockchain_client.http_provider().clone(), blockchain_client.chain_id())
+            .execute(tx, block)
+            .await
+    }
+    .await;
+    
+    let trace = match result {
+        Ok(trace) => trace,
+        Err(e) => {
+            println!("  Simulation failed: {:#}", e);
+            return;
+        }
+    };
+    
+    field("Forked from", format!("post-state of block {}", trace.fork_block));
+    field("Outcome", outcome(trace.success));
+    if let Some(failure) = &trace.failure {
+        field("Failure", failure);
+    }
+    field("Gas used", trace.gas_used);
+    field("Sender ETH", format!("{} wei", trace.searcher_eth_delta));
+    for delta in &trace.searcher_token_deltas {
+        let amount = tokens
+            .get(delta.token)
+            .await
+            .ok()
+            .and_then(|metadata| metadata.amount(&delta.delta))
+            .map_or_else(
+                || format!("{} raw units", delta.delta),
+                |amount| format!("{} {}", amount.amount, amount.symbol),
+            );
+        field("Sender token", format!("{} ({:?})", amount, delta.token));
+    }
+    field("Coinbase profit", format!("{} ETH", EthAmount::from_wei(trace.coinbase_profit)));
+    field("Logs", trace.logs.len());
+    field("Touched accounts", trace.state_diff.len());
+}
+
+fn outcome(success: bool) -> &'static str {
+    if success {
+        "succeeded"
+    } else {
+        "reverted"
+    }
+}
+
+fn gwei(wei: U256) -> String {
+    ethers::utils::format_units(wei, "gwei").unwrap_or_else(|_| wei.to_string())
+}
+
+fn section(title: &str) {
+    println!();
+    println!("{}", title);
+}
+
+fn field(label: &str, value: impl std::fmt::Display) {
+    println!("  {:<18}{}", label, value);
+}
@@ -0,0 +1,66 @@
+use anyhow::{Context, Result};
+use tracing::info;
+
+use crate::{
+    config::{self, cli::{Args, Command}},
+    database,
+    utils,
+};
+
+mod export;
+mod inspect;
+mod replay;
+mod simulate;
+
+/// Run a CLI subcommand instead of the server
+pub async fn run(command: Command, args: &Args) -> Result<()> {
+    match command {
+        Command::Migrate => {
+            let config = config::load_from_args(args)?;
+            utils::logging::init(&config.logging)?;
+            
+            let db_pool = database::connect(&config.database).await?;
+            database::run_migrations(&db_pool).await
+        }
+        Command::GenerateConfig { output } => {
+            let yaml = serde_yaml::to_string(&config::defaults::default_config())?;
+            std::fs::write(&output, yaml)
+                .context(format!("Failed to write configuration to {}", output))?;
+            
+            println!("Wrote default configuration to {}", output);
+            Ok(())
+        }
+        Command::ValidateConfig { config: path } => {
+            let config = config::load_from_file(&path)?;
+            config::validate_config(&config)?;
+            
+            println!("Configuration at {} is valid", path);
+            Ok(())
+        }
+        Command::Export { format, from, to, output } => {
+            let config = config::load_from_args(args)?;
+            utils::logging::init(&config.logging)?;
+            
+            info!("Exporting built blocks to {}", output);
+            export::run(&config, &format, &from, to.as_deref(), &output).await
+        }
+        Command::InspectTx { hash } => {
+            let config = config::load_from_args(args)?;
+            utils::logging::init(&config.logging)?;
+            
+            inspect::run(&config, &hash).await
+        }
+        Command::SimulateBundle { file, block } => {
+            let config = config::load_from_args(args)?;
+            utils::logging::init(&config.logging)?;
+            
+            simulate::run(&config, &file, block).await
+        }
+        Command::Replay { path, speed } => {
+            let config = config::load_from_args(args)?;
+            utils::logging::init(&config.logging)?;
+            
+            replay::run(config, &path, speed).await
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::Arc,
+    time::Instant,
+};
+use tracing::{debug, info};
+
+use crate::{
+    blockchain::{self, monitor},
+    config::{Config, ExecutionMode},
+    database,
+    services::{
+        mempool_recorder::{recording_files, RecordingReader},
+        ServiceContext,
+    },
+};
+
+/// Feed recorded pending transactions through the monitor and print what the strategies found
+///
+/// The services run as a dry run with the recording and mempool persistence off, so a replay
+/// sends nothing and leaves no trace of the replayed flow. Transactions are simulated and
+/// evaluated against the node's current state, not the state they originally arrived on.
+pub async fn run(mut config: Config, path: &str, speed: f64) -> Result<()> {
+    if !(speed >= 0.0 && speed.is_finite()) {
+        return Err(anyhow!("Replay speed must be a non-negative number, got {}", speed));
+    }
+    
+    let files = recording_files(Path::new(path))?;
+    if files.is_empty() {
+        return Err(anyhow!("No mempool recordings in {}", path));
+    }
+    
+    config.execution_mode = ExecutionMode::DryRun;
+    config.services.mempool_recording.enabled = false;
+    config.services.mempool_persistence.enabled = false;
+    
+    let db_pool = database::connect(&config.database).await?;
+    let redis = database::connect_redis(&config.redis).await?;
+    let blockchain_client =
+        blockchain::create_client(&config.blockchain, &config.caches, Some(db_pool.clone())).await?;
+    let services = Arc::new(ServiceContext::new(db_pool, redis, blockchain_client, &config).await?);
+    
+    info!("Replaying {} recordings from {} at {}x", files.len(), path, speed);
+    
+    let started = Instant::now();
+    let mut first_received: Option<DateTime<Utc>> = None;
+    let (mut replayed, mut failed) = (0usize, 0usize);
+    let mut opportunities: BTreeMap<&'static str, usize> = BTreeMap::new();
+    for file in &files {
+        let mut reader = RecordingReader::open(file)?;
+        while let Some(recorded) = reader.read_next()? {
+            // Keep the original spacing between arrivals, scaled by the speed
+            let first = *first_received.get_or_insert(recorded.received_at);
+            if speed > 0.0 {
+                let offset = (recorded.received_at - first).to_std().unwrap_or_default().div_f64(speed);
+                if let Some(wait) = offset.checked_sub(started.elapsed()) {
+                    tokio::time::sleep(wait).await;
+                }
+            }
+            
+            let tx_hash = recorded.tx.hash;
+            match monitor::ingest_pending_transaction(&services, recorded.tx, recorded.source).await {
+                Ok(found) => {
+                    for opportunity in &found {
+                        *opportunities.entry(opportunity.kind()).or_default() += 1;
+                    }
+                }
+                Err(e) => {
+                    debug!("Replayed transaction {:?} failed: {}", tx_hash, e);
+                    failed += 1;
+                }
+            }
+            replayed += 1;
+        }
+    }
+    
+    services.shutdown().await?;
+    
+    let first_arrival = first_received.map(|first| first.to_rfc3339()).unwrap_or_else(|| "-".to_string());
+    println!("Replayed {} transactions from {} recordings", replayed, files.len());
+    println!("  {:<20}{}", "First arrival", first_arrival);
+    println!("  {:<20}{}", "Failed", failed);
+    println!("  {:<20}{:?}", "Replayed in", started.elapsed());
+    if opportunities.is_empty() {
+        println!("  No opportunities found");
+    }
+    for (kind, count) in &opportunities {
+        println!("  {:<20}{}", format!("{} opportunities", kind), count);
+    }
+    
+    Ok(())
+}
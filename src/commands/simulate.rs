@@ -0,0 +1,74 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::{
+    types::{Bytes, Transaction, U64},
+    utils::rlp,
+};
+use serde::Deserialize;
+
+use crate::{blockchain, config::Config, services::simulation::RevmEngine, utils::units::EthAmount};
+
+/// A bundle as `eth_sendBundle` takes it, which is also what `export` writes per line
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleFile {
+    /// Signed raw transactions, hex-encoded, in execution order
+    txs: Vec<Bytes>,
+    /// Block the bundle targets
+    #[serde(default)]
+    block_number: Option<U64>,
+}
+
+/// Simulate a bundle from a file against the configured node and print the outcome
+pub async fn run(config: &Config, file: &str, block: Option<u64>) -> Result<()> {
+    let raw = std::fs::read_to_string(file).context(format!("Failed to read {}", file))?;
+    let bundle: BundleFile = serde_json::from_str(&raw).context(format!("Invalid bundle in {}", file))?;
+    if bundle.txs.is_empty() {
+        return Err(anyhow!("Bundle in {} has no transactions", file));
+    }
+    
+    let txs = bundle
+        .txs
+        .iter()
+        .enumerate()
+        .map(|(index, raw)| {
+            rlp::decode::<Transaction>(raw).context(format!("Transaction {} is not a signed transaction", index))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    
+    let blockchain_client = blockchain::create_client(&config.blockchain, &config.caches, None).await?;
+    let engine = RevmEngine::new(blockchain_client.clone(), config.services.block_building.fee_recipient());
+    
+    let target = match block.or(bundle.block_number.map(|number| number.as_u64())) {
+        Some(target) => target,
+        None => blockchain_client.get_block_number().await? + 1,
+    };
+    let result = engine.simulate_bundle_at(&txs, target.saturating_sub(1)).await?;
+    
+    println!(
+        "Bundle of {} transactions targeting block {}, on the state after block {}",
+        txs.len(),
+        target,
+        result.fork_block
+    );
+    
+    for (index, tx) in result.txs.iter().enumerate() {
+        println!();
+        println!("  #{} {:?}", index, tx.tx_hash);
+        println!("    {:<18}{:?}", "From", tx.from);
+        println!("    {:<18}{}", "Outcome", if tx.success { "succeeded" } else { "reverted" });
+        if let Some(failure) = &tx.failure {
+            println!("    {:<18}{}", "Failure", failure);
+        }
+        println!("    {:<18}{}", "Gas used", tx.gas_used);
+        println!("    {:<18}{} ETH", "Coinbase profit", EthAmount::from_wei(tx.coinbase_profit));
+        println!("    {:<18}{} wei", "Sender ETH", tx.sender_eth_delta);
+    }
+    
+    println!();
+    println!("  {:<20}{}", "All succeeded", result.success);
+    println!("  {:<20}{}", "Gas used", result.gas_used);
+    println!("  {:<20}{} ETH", "Total value", EthAmount::from_wei(result.coinbase_profit));
+    println!("  {:<20}{:?}", "Simulated in", result.duration);
+    
+    Ok(())
+}
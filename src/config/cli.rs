@@ -6,11 +6,11 @@ pub struct Args {
     /// Path to configuration file
     #[arg(short, long, env = "CONFIG_FILE")]
     pub config: Option<String>,
-
+    
     /// Log level (debug, info, warn, error)
     #[arg(short, long, env = "LOG_LEVEL")]
     pub log_level: Option<String>,
-
+    
     /// Subcommands
     #[command(subcommand)]
     pub command: Option<Command>,
@@ -34,9 +34,57 @@ pub enum Command {
         #[arg(short, long)]
         config: String,
     },
+    
+    /// Export landed blocks we built and their bundles
+    Export {
+        /// Output format (json, parquet)
+        #[arg(short, long, default_value = "json")]
+        format: String,
+        
+        /// Start of the time range (RFC 3339)
+        #[arg(long)]
+        from: String,
+        
+        /// End of the time range (RFC 3339), defaults to now
+        #[arg(long)]
+        to: Option<String>,
+        
+        /// Output file path
+        #[arg(short, long)]
+        output: String,
+    },
+    
+    /// Print everything known about a transaction, from the database and RPC
+    InspectTx {
+        /// Transaction hash
+        hash: String,
+    },
+    
+    /// Simulate a bundle from a JSON file against the configured node, without the server
+    SimulateBundle {
+        /// Bundle file: `{"txs": [...]}` with signed raw transactions, optionally `blockNumber`
+        #[arg(short, long)]
+        file: String,
+        
+        /// Block the bundle targets, run on the state after the block before it; defaults to
+        /// the file's `blockNumber`, else the block after the latest
+        #[arg(long)]
+        block: Option<u64>,
+    },
+    
+    /// Feed recorded pending transactions back through the monitor as a dry run
+    Replay {
+        /// Recording file, or a directory whose recordings are replayed oldest first
+        #[arg(short, long)]
+        path: String,
+        
+        /// Speed relative to the original arrival times, 0 replays as fast as possible
+        #[arg(long, default_value_t = 1.0)]
+        speed: f64,
+    },
 }
 
 /// Parse command line arguments
 pub fn parse_args() -> Args {
     Args::parse()
-} 
\ No newline at end of file
+}
\ No newline at end of file
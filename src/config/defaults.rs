@@ -1,4 +1,15 @@
-use crate::config::*;
+use ethers::types::U256;
+use std::collections::HashMap;
+
+use crate::{
+    config::*,
+    services::ordering::OrderingStrategyKind,
+    utils::{cache::EvictionPolicy, units::EthAmount},
+};
+
+/// Mainnet token addresses used by the price defaults
+const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
 
 /// Generate default configuration
 pub fn default_config() -> Config {
@@ -9,6 +20,51 @@ pub fn default_config() -> Config {
         blockchain: default_blockchain_config(),
         logging: default_logging_config(),
         services: default_services_config(),
+        export: default_export_config(),
+        analytics: default_analytics_config(),
+        alerting: default_alerting_config(),
+        heartbeat: default_heartbeat_config(),
+        relays: Vec::new(),
+        caches: CacheConfig {
+            abi: CacheSettings {
+                capacity: 10_000,
+                policy: EvictionPolicy::Lfu,
+            },
+            confirmed_txs: CacheSettings {
+                capacity: 65_536,
+                policy: EvictionPolicy::Lru,
+            },
+            contract_age: CacheSettings {
+                capacity: 16_384,
+                policy: EvictionPolicy::Lru,
+            },
+        },
+        relay_backoff: RelayBackoffConfig {
+            failure_threshold: 10,
+            base_backoff_seconds: 12,
+            max_backoff_seconds: 384,
+        },
+        subsidy: SubsidyConfig {
+            enabled: false,
+            budget_wei: "0".to_string(),
+            budget_period_hours: 24,
+            rules: Vec::new(),
+        },
+        relay_scraper: RelayScraperConfig {
+            enabled: false,
+            interval_seconds: 60,
+            page_limit: 100,
+        },
+        signers: Vec::new(),
+        execution_mode: ExecutionMode::Live,
+        risk: RiskConfig {
+            enabled: false,
+            window_seconds: 3600,
+            max_loss_wei: "1000000000000000000".to_string(),
+            max_failed_bundle_rate: 0.5,
+            max_unexpected_revert_rate: 0.2,
+            min_samples: 20,
+        },
     }
 }
 
@@ -18,6 +74,44 @@ fn default_api_config() -> ApiConfig {
         cors_allowed_origins: vec!["*".to_string()],
         request_timeout_seconds: 30,
         max_json_payload_size: 10 * 1024 * 1024, // 10 MB
+        api_keys: Vec::new(),
+        jwt_secret: None,
+        jwt_ttl_seconds: 3600,
+        rate_limit: RateLimitConfig {
+            enabled: true,
+            default: RateLimitBucket {
+                rate_per_second: 20.0,
+                burst: 50,
+            },
+            // Simulation and submission are the expensive paths
+            routes: [
+                ("/api/blocks/simulate", None),
+                ("/api/simulation", None),
+                ("/api/transactions", Some("POST")),
+                ("/api/bundles", Some("POST")),
+            ]
+            .iter()
+            .map(|(prefix, method)| RouteRateLimit {
+                path_prefix: prefix.to_string(),
+                method: method.map(str::to_string),
+                bucket: RateLimitBucket {
+                    rate_per_second: 2.0,
+                    burst: 10,
+                },
+            })
+            // Bulk status is a read, meant to replace per-hash polling rather than compete with submission
+            .chain(std::iter::once(RouteRateLimit {
+                path_prefix: "/api/transactions/status".to_string(),
+                method: Some("POST".to_string()),
+                bucket: RateLimitBucket {
+                    rate_per_second: 5.0,
+                    burst: 20,
+                },
+            }))
+            .collect(),
+            trust_forwarded_for: false,
+        },
+        max_status_hashes: 100,
     }
 }
 
@@ -27,6 +121,9 @@ fn default_database_config() -> DatabaseConfig {
         max_connections: 20,
         idle_timeout_seconds: 300,
         connect_timeout_seconds: 10,
+        max_retries: 3,
+        retry_backoff_ms: 200,
+        write_buffer_capacity: 10_000,
     }
 }
 
@@ -34,6 +131,10 @@ fn default_redis_config() -> RedisConfig {
     RedisConfig {
         url: "redis://localhost:6379".to_string(),
         pool_size: 10,
+        command_timeout_ms: 250,
+        max_retries: 2,
+        retry_backoff_ms: 20,
+        degraded_cooldown_seconds: 5,
     }
 }
 
@@ -45,6 +146,70 @@ fn default_blockchain_config() -> BlockchainConfig {
         max_block_history: 100,
         confirmation_blocks: 12,
         gas_price_refresh_seconds: 10,
+        fallback_rpc_url: None,
+        slot_duration_seconds: 12,
+        stale_head_slots: 2,
+        fee_history_blocks: 20,
+        stuck_tx_seconds: 120,
+        genesis_timestamp: 1_606_824_023, // Mainnet beacon chain
+        rpc_policies: RpcPolicyConfig {
+            fast_reads: RpcClassPolicy {
+                timeout_ms: 2_000,
+                retries: 2,
+            },
+            heavy: RpcClassPolicy {
+                timeout_ms: 30_000,
+                retries: 1,
+            },
+            // A retried send after a timeout may already be in the mempool, so sends fail fast instead
+            sends: RpcClassPolicy {
+                timeout_ms: 5_000,
+                retries: 0,
+            },
+        },
+        // Unlimited until budgets matching the provider's plan are configured
+        rate_limits: RpcRateLimitConfig {
+            enabled: false,
+            primary: ProviderBudget {
+                requests_per_second: 50,
+                compute_units_per_second: 330,
+            },
+            fallback: ProviderBudget {
+                requests_per_second: 25,
+                compute_units_per_second: 330,
+            },
+            default_compute_units: 20,
+            // Alchemy's pricing of the methods we call
+            method_compute_units: [
+                ("eth_blockNumber", 10),
+                ("eth_gasPrice", 20),
+                ("eth_feeHistory", 10),
+                ("eth_getBlockByNumber", 16),
+                ("eth_getTransactionByHash", 17),
+                ("eth_getTransactionReceipt", 15),
+                ("eth_getBlockReceipts", 500),
+                ("eth_getTransactionCount", 26),
+                ("eth_getCode", 26),
+                ("eth_call", 26),
+                ("eth_estimateGas", 87),
+                ("eth_createAccessList", 10),
+                ("eth_getLogs", 75),
+                ("eth_sendRawTransaction", 250),
+            ]
+            .into_iter()
+            .map(|(method, units)| (method.to_string(), units))
+            .collect(),
+            bulk_reserve: 0.2,
+        },
+        // Multicall3, deployed at the same address on every major chain
+        multicall_address: "0xcA11bde05977b3631167028862bE2a173976CA11".to_string(),
+        abi_sources: AbiSourceConfig {
+            sourcify_url: Some("https://sourcify.dev/server".to_string()),
+            etherscan_url: "https://api.etherscan.io/v2/api".to_string(),
+            etherscan_api_key: None,
+            request_timeout_ms: 10_000,
+            unverified_retry_seconds: 86_400,
+        },
     }
 }
 
@@ -53,6 +218,12 @@ fn default_logging_config() -> LoggingConfig {
         level: "info".to_string(),
         json_format: false,
         file_path: None,
+        audit_file_path: None,
+        trace_sampling: TraceSamplingConfig {
+            head_rate: 0.01,
+            always_sample_errors: true,
+            always_sample_landed: true,
+        },
     }
 }
 
@@ -61,14 +232,226 @@ fn default_services_config() -> ServicesConfig {
         tx_ordering: default_tx_ordering_config(),
         block_building: default_block_building_config(),
         liquid_staking: default_liquid_staking_config(),
+        sealed_bundles: default_sealed_bundles_config(),
+        searcher_reputation: SearcherReputationConfig {
+            enabled: true,
+            min_observations: 20,
+            low_priority_below: 0.5,
+            throttle_below: 0.2,
+            high_load_queue_depth: 256,
+            flush_interval_seconds: 10,
+        },
+        bundles: BundleConfig {
+            enabled: false,
+            relay_url: "https://relay.flashbots.net".to_string(),
+            signing_key: None,
+            max_bundle_txs: 10,
+            min_profit_wei: "0".to_string(),
+        },
+        // Uniswap's mainnet deployment
+        sandwich: SandwichConfig {
+            enabled: false,
+            v2_router: "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D".to_string(),
+            v2_factory: "0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f".to_string(),
+            v2_pair_init_code_hash: "0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f".to_string(),
+            v3_router: "0xE592427A0AEce92De3Edee1F18E0157C05861564".to_string(),
+            v3_factory: "0x1F98431c8aD98523631AE4a59f267346ea31F984".to_string(),
+            v3_pool_init_code_hash: "0xe34f199b19b2b4f47f68442619d555527d244f78a3297ea89325f843f87b8b54".to_string(),
+            weth: "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".to_string(),
+            max_frontrun_wei: "100000000000000000000".to_string(),
+            min_profit_wei: "10000000000000000".to_string(),
+            gas_per_swap: 150_000,
+            searcher: None,
+        },
+        arbitrage: ArbitrageConfig {
+            enabled: false,
+            base_tokens: vec!["0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".to_string()],
+            max_hops: 4,
+            max_input: "100000000000000000000".to_string(),
+            min_profit: "10000000000000000".to_string(),
+            gas_per_hop: 120_000,
+            balancer_vault: "0xBA12222222228d8Ba445958a75a0704d566BF2C8".to_string(),
+            uniswap_v3_router: "0xE592427A0AEce92De3Edee1F18E0157C05861564".to_string(),
+            pools: Vec::new(),
+            trader: None,
+        },
+        // Mainnet Aave v3 and the USDC and WETH Comet markets
+        liquidation: LiquidationConfig {
+            enabled: false,
+            aave_pool: Some("0x87870Bca3F3fD6335C3F4ce8392D69350B4fA4E2".to_string()),
+            aave_data_provider: "0x7B4EB56E7CD4b454BA8ff71E4518426369a138a3".to_string(),
+            aave_oracle: "0x54586bE62E3c3580375aE3723C145253060Ca0C2".to_string(),
+            comets: vec![
+                "0xc3d688B66703497DAA19211EEdff47f25384cdc3".to_string(),
+                "0xA17581A9E3356d9A858b789D68B4d866e593aE94".to_string(),
+            ],
+            eth_usd_feed: "0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419".to_string(),
+            price_aggregators: Vec::new(),
+            watch_accounts: Vec::new(),
+            min_debt_usd: 100_000.0,
+            max_positions: 5_000,
+            refresh_interval_blocks: 10,
+            backfill_blocks: 500_000,
+            gas_per_liquidation: 600_000,
+            min_profit_wei: "10000000000000000".to_string(),
+            liquidator: None,
+        },
+        strategies: StrategiesConfig {
+            hook_timeout_ms: 2_000,
+            // Arbitrage refreshes every tracked pool and liquidations re-read positions per block
+            hook_timeouts_ms: HashMap::from([
+                ("arbitrage".to_string(), 8_000),
+                ("liquidation".to_string(), 10_000),
+            ]),
+            ignore_labels: HashMap::new(),
+            schedules: HashMap::new(),
+        },
+        shadow_build: ShadowBuildConfig {
+            enabled: false,
+            max_slots_per_run: 4,
+        },
+        labels: LabelsConfig { lists: Vec::new() },
+        exploit_detection: ExploitDetectionConfig {
+            enabled: false,
+            min_approval_units: "1000000000000000000000000000".to_string(),
+            fresh_contract_blocks: 100,
+            min_outflow_usd: 250_000.0,
+            etherscan_api_url: None,
+            etherscan_api_key: None,
+            max_in_flight: 16,
+        },
+        gas_golf: GasGolfConfig {
+            enabled: false,
+            multicall_targets: Vec::new(),
+            tune_access_lists: true,
+            min_access_list_gas_saved: 100,
+        },
+        // Mainnet WETH and USDC, each from Chainlink, WETH also from the 5 bps USDC pool
+        prices: PriceConfig {
+            enabled: false,
+            native_token: WETH.to_string(),
+            tokens: vec![
+                PriceTokenConfig {
+                    symbol: "WETH".to_string(),
+                    address: WETH.to_string(),
+                    decimals: 18,
+                    chainlink_feed: Some("0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419".to_string()),
+                    uniswap_v3_pool: Some("0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640".to_string()),
+                    twap_quote: Some(USDC.to_string()),
+                },
+                PriceTokenConfig {
+                    symbol: "USDC".to_string(),
+                    address: USDC.to_string(),
+                    decimals: 6,
+                    chainlink_feed: Some("0x8fFfFfd4AfB6115b954Bd326cbe7B4BA576818f6".to_string()),
+                    uniswap_v3_pool: None,
+                    twap_quote: None,
+                },
+            ],
+            offchain_sources: Vec::new(),
+            cache_ttl_seconds: 12,
+            max_feed_age_seconds: 3_600,
+            max_deviation_bps: 200,
+            twap_seconds: 600,
+            offchain_timeout_ms: 2_000,
+        },
+        privacy: PrivacyConfig {
+            sensitive_sources: Vec::new(),
+        },
+        private_submission: PrivateSubmissionConfig {
+            protect_url: "https://rpc.flashbots.net".to_string(),
+            mev_share_url: "https://relay.flashbots.net".to_string(),
+            mev_share_hints: vec!["hash".to_string(), "logs".to_string()],
+        },
+        bloxroute_stream: BloxrouteStreamConfig {
+            enabled: false,
+            url: "wss://api.blxrbdn.com/ws".to_string(),
+            auth_header: String::new(),
+        },
+        mempool_persistence: MempoolPersistenceConfig {
+            enabled: true,
+            stale_after_seconds: 1800,
+            retention_hours: 24,
+            prune_interval_seconds: 60,
+        },
+        state_history: StateHistoryConfig {
+            retention_hours: 72,
+            prune_interval_seconds: 3600,
+        },
+        mempool_recording: MempoolRecordingConfig {
+            enabled: false,
+            directory: "recordings".to_string(),
+            batch_size: 1_000,
+            max_buffered: 100_000,
+            flush_interval_ms: 1_000,
+        },
+        block_storage: BlockStorageConfig {
+            enabled: false,
+            compression_level: 3,
+            // Past the size of a hash, a reference is smaller than the calldata it replaces
+            inline_calldata_bytes: 64,
+            // About 30 days of mainnet blocks
+            retention_blocks: 216_000,
+            prune_interval_seconds: 600,
+        },
+        event_indexer: EventIndexerConfig {
+            enabled: false,
+            contracts: Vec::new(),
+            // Within the range most providers serve in one request
+            catch_up_range_blocks: 2_000,
+        },
+        canary: CanaryConfig {
+            enabled: false,
+            signer: "canary".to_string(),
+            fork: false,
+            interval_seconds: 300,
+            confirmations: 2,
+            timeout_seconds: 180,
+            // Inclusion in the next block or two plus the confirmations
+            max_latency_ms: 60_000,
+            min_success_rate: 0.9,
+            window: 12,
+        },
+        maintenance: MaintenanceConfig {
+            enabled: false,
+            beacon_url: None,
+            own_validator_pubkeys: Vec::new(),
+            max_auction_value: EthAmount::from_wei(U256::exp10(16)), // 0.01 ETH
+            window_slots: 2,
+            max_deferral_seconds: 120,
+            vacuum_tables: Vec::new(),
+            vacuum_interval_seconds: 86_400,
+        },
+        snapshots: SnapshotConfig {
+            enabled: false,
+            path: "snapshots".to_string(),
+            max_age_seconds: 600,
+            max_age_blocks: 50,
+        },
+        task_lanes: TaskLaneConfig {
+            enabled: true,
+            critical_worker_threads: 2,
+            background_worker_threads: 2,
+        },
+        drain_timeout_seconds: 30,
     }
 }
 
 fn default_tx_ordering_config() -> TxOrderingConfig {
     TxOrderingConfig {
         worker_threads: num_cpus::get(),
+        dedicated_simulation_runtime: true,
+        simulation_threads: (num_cpus::get() / 2).max(1),
+        simulation_cpu_affinity: Vec::new(),
         max_simulation_time_ms: 100,
         simulation_mode: "optimistic".to_string(),
+        simulation_engine: "heuristic".to_string(),
+        shadow_engine: None,
+        shadow_sample_rate: 0.01,
+        profit_tolerance_bps: 500,
+        mempool_stats_interval_ms: 1000,
+        confirmed_tx_concurrency: 16,
+        confirmed_tx_queue_blocks: 64,
     }
 }
 
@@ -77,6 +460,11 @@ fn default_block_building_config() -> BlockBuildingConfig {
         target_block_fullness: 0.95,
         max_gas_limit: 30_000_000,
         priority_accounts: Vec::new(),
+        ordering_strategy: OrderingStrategyKind::GreedyGasPrice,
+        extra_data: "mev-capture".to_string(),
+        builder_pubkey: None,
+        graffiti: "default".to_string(),
+        fee_recipient: "0x0000000000000000000000000000000000000000".to_string(),
     }
 }
 
@@ -84,6 +472,98 @@ fn default_liquid_staking_config() -> LiquidStakingConfig {
     LiquidStakingConfig {
         validator_commission_bps: 500, // 5%
         withdrawal_delay_epochs: 2,
-        min_stake_amount: "0.1".to_string(), // 0.1 ETH
+        pool_contract: String::new(),
+        min_stake_amount: EthAmount::from_wei(U256::exp10(17)), // 0.1 ETH
+        max_stake_amount: None,
+        commission_payout: CommissionPayoutConfig {
+            enabled: false,
+            treasury_address: String::new(),
+            signer: String::new(),
+            interval_seconds: 24 * 60 * 60,
+            min_payout_amount: EthAmount::from_wei(U256::exp10(17)), // 0.1 ETH
+        },
+        deposit_reconciliation: DepositReconciliationConfig {
+            enabled: false,
+            deposit_contract: "0x00000000219ab540356cBB839Cbe05303d7705Fa".to_string(),
+            withdrawal_credentials: Vec::new(),
+            start_block: 11_052_984, // Deposit contract deployment
+            blocks_per_request: 2_000,
+            interval_seconds: 60,
+            missing_after_seconds: 3600,
+        },
+        token_deposits: TokenDepositConfig {
+            enabled: false,
+            token: "0x7f39C581F595B53c5cb19bD0b3f8dA6c935E2Ca0".to_string(), // wstETH, implements EIP-2612
+            min_amount: EthAmount::from_wei(U256::exp10(17)), // 0.1 tokens
+            staking_contract: String::new(),
+            signer: String::new(),
+        },
+    }
+}
+
+fn default_export_config() -> ExportConfig {
+    ExportConfig {
+        enabled: false,
+        format: "parquet".to_string(),
+        destination: "exports".to_string(),
+        tables: ["transactions", "mev_events", "opportunities"]
+            .iter()
+            .map(|name| ExportTableConfig {
+                name: name.to_string(),
+                timestamp_column: "created_at".to_string(),
+            })
+            .collect(),
+        lookback_days: 7,
+        interval_seconds: 3600,
+    }
+}
+
+fn default_analytics_config() -> AnalyticsConfig {
+    AnalyticsConfig {
+        clickhouse_enabled: false,
+        clickhouse_url: "http://localhost:8123".to_string(),
+        clickhouse_database: "mev_capture".to_string(),
+        clickhouse_user: None,
+        clickhouse_password: None,
+        batch_size: 10_000,
+        max_buffered_rows: 200_000,
+        flush_interval_ms: 1_000,
+        kpi_interval_seconds: 60,
+    }
+}
+
+fn default_alerting_config() -> AlertingConfig {
+    AlertingConfig {
+        webhook_urls: Vec::new(),
+        webhooks: Vec::new(),
+        max_delivery_attempts: 3,
+        cooldown_seconds: 300,
+        evaluation_interval_seconds: 15,
+        rules: vec![AlertRuleConfig {
+            name: "blockchain_error_rate".to_string(),
+            metric: "blockchain_errors_total".to_string(),
+            kind: "rate_of_change".to_string(),
+            operator: "gt".to_string(),
+            value: 1.0,
+            window_seconds: 60,
+            severity: "warning".to_string(),
+        }],
+    }
+}
+
+fn default_heartbeat_config() -> HeartbeatConfig {
+    HeartbeatConfig {
+        ping_urls: Default::default(),
+        stale_after_seconds: 120,
+        check_interval_seconds: 30,
     }
-} 
\ No newline at end of file
+}
+
+fn default_sealed_bundles_config() -> SealedBundlesConfig {
+    SealedBundlesConfig {
+        enabled: false,
+        key_rotation_seconds: 24 * 60 * 60,
+        retained_keys: 2,
+        max_pending: 10_000,
+    }
+}
@@ -1,10 +1,12 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::{collections::HashMap, path::Path};
 use tracing::info;
 
-mod cli;
-mod defaults;
+use crate::utils::units::EthAmount;
+
+pub mod cli;
+pub mod defaults;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -14,6 +16,148 @@ pub struct Config {
     pub blockchain: BlockchainConfig,
     pub logging: LoggingConfig,
     pub services: ServicesConfig,
+    pub export: ExportConfig,
+    pub analytics: AnalyticsConfig,
+    pub alerting: AlertingConfig,
+    pub heartbeat: HeartbeatConfig,
+    pub relays: Vec<RelayConfig>,
+    pub caches: CacheConfig,
+    pub relay_backoff: RelayBackoffConfig,
+    pub relay_scraper: RelayScraperConfig,
+    pub subsidy: SubsidyConfig,
+    pub signers: Vec<SignerConfig>,
+    /// Live, or a dry run that goes through the whole pipeline without sending anything
+    #[serde(default)]
+    pub execution_mode: ExecutionMode,
+    pub risk: RiskConfig,
+}
+
+/// Circuit breaker halting live submission, flipping to a dry run, when a threshold is crossed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskConfig {
+    pub enabled: bool,
+    /// Sliding window the thresholds apply over
+    pub window_seconds: u64,
+    /// Most realized loss tolerated in the window, in wei
+    pub max_loss_wei: String,
+    /// Highest share of bundles the relay may reject, between 0 and 1
+    pub max_failed_bundle_rate: f64,
+    /// Highest share of landed candidates that may revert despite simulating successfully
+    pub max_unexpected_revert_rate: f64,
+    /// Bundles or landed candidates in the window before their rate counts
+    pub min_samples: usize,
+}
+
+/// Whether transactions, bundles and bids actually leave the process
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionMode {
+    #[default]
+    Live,
+    /// Simulate, assemble bundles, build and bid as usual, but stop short of broadcasting
+    /// transactions, submitting bundles or submitting bids to relays
+    DryRun,
+}
+
+impl ExecutionMode {
+    pub fn is_dry_run(self) -> bool {
+        self == Self::DryRun
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayConfig {
+    pub name: String,
+    /// Adapter to use: flashbots, ultrasound, agnostic or bloxroute
+    pub kind: String,
+    pub url: String,
+    /// Authorization header value, required by relays that gate submissions
+    pub auth_header: Option<String>,
+    /// Override of the adapter's default submission rate limit
+    pub max_submissions_per_second: Option<u32>,
+}
+
+/// A named account we sign transactions with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignerConfig {
+    pub name: String,
+    /// Signing backend: keystore, env or inline keys held in memory, or kms
+    pub kind: String,
+    /// Encrypted JSON keystore file, for `keystore`
+    pub keystore_path: Option<String>,
+    /// Environment variable holding the keystore password, for `keystore`
+    pub password_env: Option<String>,
+    /// Environment variable holding the hex private key, for `env`
+    pub key_env: Option<String>,
+    /// Hex private key, for `inline`; keep to development configs
+    pub private_key: Option<String>,
+    /// AWS KMS key id or ARN of a secp256k1 signing key, for `kms`
+    pub kms_key_id: Option<String>,
+    /// AWS region of the key, for `kms`; defaults to the environment's region
+    pub kms_region: Option<String>,
+}
+
+/// Capacity and eviction policy of each bounded in-memory cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheConfig {
+    /// Contract ABIs by address
+    pub abi: CacheSettings,
+    /// Recently confirmed transaction hashes, used to skip refetching late announcements
+    pub confirmed_txs: CacheSettings,
+    /// Whether contracts were freshly deployed, for exploit detection
+    pub contract_age: CacheSettings,
+    /// ERC-20 metadata by token address, and contracts that aren't tokens
+    pub tokens: CacheSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheSettings {
+    /// Maximum entries before one is evicted
+    pub capacity: usize,
+    pub policy: crate::utils::cache::EvictionPolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayBackoffConfig {
+    /// Consecutive failed submissions before a relay is backed off
+    pub failure_threshold: u32,
+    pub base_backoff_seconds: u64,
+    pub max_backoff_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsidyConfig {
+    /// Allow bidding above extracted value on strategic slots
+    pub enabled: bool,
+    /// Total subsidy that may be paid per budget period, in wei
+    pub budget_wei: String,
+    pub budget_period_hours: u32,
+    /// Rules checked in order; the first that applies sets the maximum subsidy
+    pub rules: Vec<SubsidyRuleConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsidyRuleConfig {
+    pub name: String,
+    /// own_validator or reputation
+    pub condition: String,
+    /// Maximum subsidy per slot, in wei
+    pub max_subsidy_wei: String,
+    /// Proposers counted as our own validators, for own_validator
+    #[serde(default)]
+    pub proposer_pubkeys: Vec<String>,
+    /// Slots without a win before subsidizing, for reputation
+    #[serde(default)]
+    pub slots_since_last_win: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayScraperConfig {
+    /// Pull delivered payloads and bid traces from the configured relays' data APIs
+    pub enabled: bool,
+    pub interval_seconds: u64,
+    /// Delivered payloads requested per relay per run
+    pub page_limit: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +166,61 @@ pub struct ApiConfig {
     pub cors_allowed_origins: Vec<String>,
     pub request_timeout_seconds: u64,
     pub max_json_payload_size: usize,
+    pub api_keys: Vec<ApiKeyConfig>,
+    /// HMAC secret for session tokens issued from `/api/auth/token`; tokens are disabled without it
+    pub jwt_secret: Option<String>,
+    /// Lifetime of issued session tokens
+    pub jwt_ttl_seconds: u64,
+    pub rate_limit: RateLimitConfig,
+    /// Most hashes accepted by one `POST /api/transactions/status` call
+    pub max_status_hashes: usize,
+}
+
+/// Per-client token buckets, keyed by API key or, for anonymous callers, IP
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// Bucket applied to routes without an override
+    pub default: RateLimitBucket,
+    /// Tighter buckets for costly routes; the longest matching prefix wins
+    #[serde(default)]
+    pub routes: Vec<RouteRateLimit>,
+    /// Take the client IP from `X-Forwarded-For`, only safe behind a trusted proxy
+    #[serde(default)]
+    pub trust_forwarded_for: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitBucket {
+    /// Sustained requests per second
+    pub rate_per_second: f64,
+    /// Requests allowed in a burst
+    pub burst: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteRateLimit {
+    pub path_prefix: String,
+    /// HTTP method the override is limited to, any by default
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(flatten)]
+    pub bucket: RateLimitBucket,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyConfig {
+    /// Identifies the key holder; searcher submissions are owned by this name
+    pub name: String,
+    pub key: String,
+    /// admin, operator, searcher or readonly
+    pub role: String,
+    /// Stream topics the key may subscribe to; admins may subscribe to all
+    #[serde(default)]
+    pub topics: Vec<String>,
+    /// Refuse WebSocket subscriptions of this key that don't encrypt their event payloads
+    #[serde(default)]
+    pub encrypt_events: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,12 +229,24 @@ pub struct DatabaseConfig {
     pub max_connections: u32,
     pub idle_timeout_seconds: u64,
     pub connect_timeout_seconds: u64,
+    /// Retries of a query that lost its connection, with doubling backoff
+    pub max_retries: u32,
+    pub retry_backoff_ms: u64,
+    /// Non-critical writes held in memory while the database is unreachable
+    pub write_buffer_capacity: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RedisConfig {
     pub url: String,
     pub pool_size: u32,
+    /// Per-command deadline before it counts as failed
+    pub command_timeout_ms: u64,
+    /// Retries of a read that timed out or lost its connection, with doubling backoff
+    pub max_retries: u32,
+    pub retry_backoff_ms: u64,
+    /// How long non-critical caching is skipped after Redis stops answering
+    pub degraded_cooldown_seconds: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,6 +257,82 @@ pub struct BlockchainConfig {
     pub max_block_history: u64,
     pub confirmation_blocks: u64,
     pub gas_price_refresh_seconds: u64,
+    /// HTTP endpoint of a second node whose head is used when ours goes stale
+    pub fallback_rpc_url: Option<String>,
+    pub slot_duration_seconds: u64,
+    /// Slots the head may go without advancing before it is considered stale
+    pub stale_head_slots: u64,
+    /// Recent blocks the fee estimator draws tips and base fee trend from
+    pub fee_history_blocks: usize,
+    /// Seconds an outbound transaction may stay pending before it is re-sent with higher fees
+    pub stuck_tx_seconds: u64,
+    /// Unix time of beacon chain genesis, anchors epoch timestamps
+    pub genesis_timestamp: u64,
+    /// Deadlines and retries of HTTP RPC requests, by method class
+    pub rpc_policies: RpcPolicyConfig,
+    /// Request and compute unit budgets of the RPC providers
+    pub rate_limits: RpcRateLimitConfig,
+    /// Multicall3 contract batched contract reads go through
+    pub multicall_address: String,
+    /// Where the ABIs of verified contracts are looked up
+    pub abi_sources: AbiSourceConfig,
+}
+
+/// Verified contract registries ABIs are fetched from, Sourcify first and then Etherscan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AbiSourceConfig {
+    /// Sourcify server, unset to skip Sourcify
+    pub sourcify_url: Option<String>,
+    /// Etherscan multichain API
+    pub etherscan_url: String,
+    /// Etherscan is only asked with a key
+    pub etherscan_api_key: Option<String>,
+    pub request_timeout_ms: u64,
+    /// Seconds before a contract neither registry had verified is looked up again
+    pub unverified_retry_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcPolicyConfig {
+    /// Cheap lookups such as `eth_blockNumber` and `eth_getTransactionReceipt`
+    pub fast_reads: RpcClassPolicy,
+    /// Calls executing or scanning state, such as `eth_call`, `eth_getLogs` and `debug_trace*`
+    pub heavy: RpcClassPolicy,
+    /// Transaction submission
+    pub sends: RpcClassPolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcClassPolicy {
+    pub timeout_ms: u64,
+    /// Retries after a timeout or transport failure; error responses from the node are never retried
+    pub retries: u32,
+}
+
+/// Budgets HTTP RPC requests are held to, so the node provider doesn't throttle us
+///
+/// Providers such as Alchemy meter requests in compute units priced per method. When a
+/// budget runs low, requests queue by priority: head processing and sends go first, and bulk
+/// work such as backfill may not spend the share of a budget reserved for the others.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRateLimitConfig {
+    pub enabled: bool,
+    pub primary: ProviderBudget,
+    pub fallback: ProviderBudget,
+    /// Compute units of a method missing from `method_compute_units`
+    pub default_compute_units: u32,
+    /// Compute units the provider charges per method, a batch is charged for each request in it
+    #[serde(default)]
+    pub method_compute_units: HashMap<String, u32>,
+    /// Share of each budget, between 0 and 1, that bulk requests leave to the others
+    pub bulk_reserve: f64,
+}
+
+/// Sustained rates of one provider, each also the most that can be spent in a burst; 0 for no limit
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProviderBudget {
+    pub requests_per_second: u32,
+    pub compute_units_per_second: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +340,21 @@ pub struct LoggingConfig {
     pub level: String,
     pub json_format: bool,
     pub file_path: Option<String>,
+    /// JSON file every bundle, bid and private transaction submission is also written to
+    #[serde(default)]
+    pub audit_file_path: Option<String>,
+    pub trace_sampling: TraceSamplingConfig,
+}
+
+/// Sampling of per-transaction spans, which at mempool rate would swamp a tracing backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceSamplingConfig {
+    /// Share of pending transactions traced, between 0 and 1
+    pub head_rate: f64,
+    /// Trace failures of transactions outside the head sample too
+    pub always_sample_errors: bool,
+    /// Trace every inclusion candidate that lands on chain
+    pub always_sample_landed: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,13 +362,494 @@ pub struct ServicesConfig {
     pub tx_ordering: TxOrderingConfig,
     pub block_building: BlockBuildingConfig,
     pub liquid_staking: LiquidStakingConfig,
+    pub sealed_bundles: SealedBundlesConfig,
+    pub searcher_reputation: SearcherReputationConfig,
+    pub bundles: BundleConfig,
+    pub sandwich: SandwichConfig,
+    pub arbitrage: ArbitrageConfig,
+    pub liquidation: LiquidationConfig,
+    pub strategies: StrategiesConfig,
+    pub shadow_build: ShadowBuildConfig,
+    pub labels: LabelsConfig,
+    pub exploit_detection: ExploitDetectionConfig,
+    pub gas_golf: GasGolfConfig,
+    pub prices: PriceConfig,
+    pub privacy: PrivacyConfig,
+    pub private_submission: PrivateSubmissionConfig,
+    pub bloxroute_stream: BloxrouteStreamConfig,
+    pub mempool_persistence: MempoolPersistenceConfig,
+    pub state_history: StateHistoryConfig,
+    pub mempool_recording: MempoolRecordingConfig,
+    pub block_storage: BlockStorageConfig,
+    pub event_indexer: EventIndexerConfig,
+    pub canary: CanaryConfig,
+    pub maintenance: MaintenanceConfig,
+    pub snapshots: SnapshotConfig,
+    pub task_lanes: TaskLaneConfig,
+    pub drain_timeout_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolPersistenceConfig {
+    /// Store non-sensitive pending transactions in `mempool_transactions` for `/api/mempool`
+    pub enabled: bool,
+    /// Pending transactions not seen again for this long are marked dropped
+    pub stale_after_seconds: u64,
+    /// How long included, replaced and dropped transactions are kept
+    pub retention_hours: u64,
+    pub prune_interval_seconds: u64,
+}
+
+/// Per-head archive of in-flight builder state in `inflight_state_history`, read by slot replay
+/// and the hourly KPIs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateHistoryConfig {
+    /// How long archived heads are kept, at least the hour the KPIs cover
+    pub retention_hours: u64,
+    pub prune_interval_seconds: u64,
+}
+
+/// Storage of confirmed block bodies and their transactions, compressed and deduplicated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockStorageConfig {
+    pub enabled: bool,
+    /// zstd level, 1 to 22
+    pub compression_level: i32,
+    /// Calldata longer than this is stored once per distinct content and referenced by hash
+    pub inline_calldata_bytes: usize,
+    /// Blocks behind the head kept
+    pub retention_blocks: u64,
+    pub prune_interval_seconds: u64,
+}
+
+/// Self-transfers sent through the whole pipeline to check it end to end, on a testnet or fork
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanaryConfig {
+    pub enabled: bool,
+    /// Named signer canaries are sent from and back to
+    pub signer: String,
+    /// Whether the node is a local fork, which may keep mainnet's chain id; required on any chain
+    /// besides the known testnets
+    pub fork: bool,
+    pub interval_seconds: u64,
+    /// Blocks on top of the including block, counting it, before a canary is confirmed
+    pub confirmations: u64,
+    /// Seconds a canary may take to confirm before it counts as failed
+    pub timeout_seconds: u64,
+    /// Median submission-to-confirmation latency over the window above which we alert
+    pub max_latency_ms: u64,
+    /// Share of canaries in the window that must confirm, 0 to 1
+    pub min_success_rate: f64,
+    /// Recent canaries latency and success rate are taken over
+    pub window: usize,
+}
+
+/// Heavy background jobs held until a run of upcoming slots we have little reason to compete for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceConfig {
+    /// When off, maintenance jobs run on their own schedule
+    pub enabled: bool,
+    /// Beacon node API for the proposer lookahead, covering proposers no relay knows
+    pub beacon_url: Option<String>,
+    /// Public keys of our own validators, whose slots are never quiet
+    #[serde(default)]
+    pub own_validator_pubkeys: Vec<String>,
+    /// Pending value above which a slot with a relay-registered proposer is worth competing for
+    pub max_auction_value: EthAmount,
+    /// Consecutive upcoming slots that must be quiet for a window to open
+    pub window_slots: u64,
+    /// How long a due job waits for a window before that run is skipped, capped by its period
+    pub max_deferral_seconds: u64,
+    /// Tables vacuumed and analyzed in a window
+    #[serde(default)]
+    pub vacuum_tables: Vec<String>,
+    pub vacuum_interval_seconds: u64,
+}
+
+/// Indexing of the events of chosen contracts into `events`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventIndexerConfig {
+    pub enabled: bool,
+    #[serde(default)]
+    pub contracts: Vec<IndexedContractConfig>,
+    /// Most blocks asked for in one `eth_getLogs` when catching up after a gap
+    pub catch_up_range_blocks: u64,
+}
+
+/// A contract whose events are indexed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedContractConfig {
+    /// Shown with its events and accepted as a filter
+    pub name: String,
+    pub address: String,
+    /// Human-readable signatures with parameter names and `indexed` markers, e.g.
+    /// `Transfer(address indexed from, address indexed to, uint256 value)`
+    pub events: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolRecordingConfig {
+    /// Append every non-sensitive pending transaction and its receive time to hourly files for `replay`
+    pub enabled: bool,
+    pub directory: String,
+    /// Buffered transactions that trigger a write
+    pub batch_size: usize,
+    /// Buffered transactions beyond which new ones are left out of the recording
+    pub max_buffered: usize,
+    pub flush_interval_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotConfig {
+    /// Save pool states, the fee window and tracked positions on shutdown and restore them on startup
+    pub enabled: bool,
+    /// Directory of the embedded store
+    pub path: String,
+    /// Snapshots older than this at startup are discarded
+    pub max_age_seconds: u64,
+    /// Snapshots taken more blocks than this behind the head at startup, or ahead of it, are discarded
+    pub max_age_blocks: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskLaneConfig {
+    /// Run slot-critical and low-priority work on runtimes of their own, all on the main one if not
+    pub enabled: bool,
+    /// Workers of the runtime bids and relay header requests run on
+    pub critical_worker_threads: usize,
+    /// Workers of the runtime analytics and backfill jobs run on
+    pub background_worker_threads: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacyConfig {
+    /// Sources whose flow is kept in memory only, by kind (`private_api`, also covering the
+    /// searchers submitting through it) or exact source (`searcher:<key>`); only aggregate
+    /// counters are recorded for them
+    pub sensitive_sources: Vec<String>,
+}
+
+/// bloXroute's `newTxs` stream, ingested alongside the node's mempool as `bloxroute` flow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BloxrouteStreamConfig {
+    pub enabled: bool,
+    /// Cloud API or gateway WebSocket endpoint
+    pub url: String,
+    /// Account authorization header bloXroute issues
+    pub auth_header: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivateSubmissionConfig {
+    /// Flashbots Protect RPC, accepting `eth_sendRawTransaction`
+    pub protect_url: String,
+    /// MEV-Share endpoint accepting `eth_sendPrivateTransaction`, signed with the bundle signing key
+    pub mev_share_url: String,
+    /// What MEV-Share reveals to searchers backrunning the transaction, e.g. `hash`, `calldata`, `logs`
+    pub mev_share_hints: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedBundlesConfig {
+    pub enabled: bool,
+    /// How often a new sealing key is generated
+    pub key_rotation_seconds: u64,
+    /// Keys accepted for decryption, including the current one
+    pub retained_keys: usize,
+    /// Maximum sealed bundles held awaiting their target block
+    pub max_pending: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearcherReputationConfig {
+    /// Score searchers by their track record; when disabled every searcher scores 1.0
+    pub enabled: bool,
+    /// Simulated transactions before a score counts, newer searchers score 1.0
+    pub min_observations: u64,
+    /// Searchers scoring below this are simulated in the low priority lane
+    pub low_priority_below: f64,
+    /// Searchers scoring below this are refused new bundles while the builder is under load
+    pub throttle_below: f64,
+    /// Simulations waiting for a worker at which the builder counts as under load
+    pub high_load_queue_depth: usize,
+    /// How often recorded counts are written to `searcher_reputation`
+    pub flush_interval_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleConfig {
+    pub enabled: bool,
+    /// Endpoint accepting `eth_sendBundle`
+    pub relay_url: String,
+    /// Private key signing the `X-Flashbots-Signature` header, usually set via `FLASHBOTS_SIGNING_KEY`
+    pub signing_key: Option<String>,
+    /// Maximum transactions assembled into one bundle
+    pub max_bundle_txs: usize,
+    /// Minimum simulated profit in wei for a transaction to be bundled
+    pub min_profit_wei: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandwichConfig {
+    pub enabled: bool,
+    /// Uniswap V2 router whose pending swaps are inspected
+    pub v2_router: String,
+    pub v2_factory: String,
+    pub v2_pair_init_code_hash: String,
+    /// Uniswap V3 swap router whose pending `exactInputSingle` swaps are inspected
+    pub v3_router: String,
+    pub v3_factory: String,
+    pub v3_pool_init_code_hash: String,
+    /// Wrapped native token; only swaps selling it are sandwiched
+    pub weth: String,
+    /// Largest front-run we would fund, in wei
+    pub max_frontrun_wei: String,
+    /// Minimum expected profit after gas, in wei
+    pub min_profit_wei: String,
+    /// Gas charged for each of the front-run and back-run swaps
+    pub gas_per_swap: u64,
+    /// Signer front-runs and back-runs are sent from; without one sandwiches are only reported
+    ///
+    /// It must hold WETH approved to both routers and should send nothing outside bundles.
+    #[serde(default)]
+    pub searcher: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArbitrageConfig {
+    pub enabled: bool,
+    /// Tokens a cycle may start from; opportunities are sized and priced in them
+    pub base_tokens: Vec<String>,
+    /// Longest cycle reported, in swaps
+    pub max_hops: usize,
+    /// Largest cycle input we would fund, in the base token's smallest unit
+    pub max_input: String,
+    /// Minimum profit after gas, in the base token's smallest unit
+    pub min_profit: String,
+    /// Gas charged per swap when estimating a cycle's cost
+    pub gas_per_hop: u64,
+    /// Balancer vault holding pool balances
+    pub balancer_vault: String,
+    /// Uniswap V3 swap router hops through V3 pools are sent through
+    pub uniswap_v3_router: String,
+    /// Pools whose reserves are tracked
+    pub pools: Vec<ArbitragePoolConfig>,
+    /// Signer cycles are sent from; without one they are only reported
+    ///
+    /// It must hold the base tokens cycles start from.
+    #[serde(default)]
+    pub trader: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArbitragePoolConfig {
+    /// `uniswap_v2`, `uniswap_v3`, `curve` or `balancer`
+    pub kind: String,
+    pub address: String,
+    /// Pool tokens in the pool's own coin order
+    pub tokens: Vec<String>,
+    /// Uniswap V2 fee in millionths; other pools report their fee on-chain
+    #[serde(default)]
+    pub fee: Option<u32>,
+    /// Curve token decimals, used to scale balances to a common precision
+    #[serde(default)]
+    pub decimals: Vec<u8>,
+    /// Balancer pool id in the vault
+    #[serde(default)]
+    pub pool_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidationConfig {
+    pub enabled: bool,
+    /// Aave v3 pool; Aave positions are not tracked when unset
+    pub aave_pool: Option<String>,
+    pub aave_data_provider: String,
+    pub aave_oracle: String,
+    /// Compound v3 Comet markets
+    pub comets: Vec<String>,
+    /// Chainlink ETH/USD feed, pricing expected profit in wei when the price service is disabled
+    pub eth_usd_feed: String,
+    /// Chainlink aggregators, not proxies, whose `AnswerUpdated` re-checks every position
+    pub price_aggregators: Vec<String>,
+    /// Accounts tracked whatever their size
+    #[serde(default)]
+    pub watch_accounts: Vec<String>,
+    /// Borrowers with less debt are not tracked
+    pub min_debt_usd: f64,
+    pub max_positions: usize,
+    /// Every tracked position is re-checked at least this often, in blocks
+    pub refresh_interval_blocks: u64,
+    /// Blocks before startup whose borrowers are picked up from their borrow logs, 0 for none
+    pub backfill_blocks: u64,
+    /// Gas charged per liquidation when estimating profit
+    pub gas_per_liquidation: u64,
+    /// Minimum expected profit after gas, in wei
+    pub min_profit_wei: String,
+    /// Signer liquidations are sent from; without one they are only reported
+    #[serde(default)]
+    pub liquidator: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategiesConfig {
+    /// Time a strategy gets to handle one pending transaction or block before it is abandoned
+    pub hook_timeout_ms: u64,
+    /// Timeouts overriding `hook_timeout_ms`, by strategy, for strategies whose block hook re-reads a
+    /// whole market
+    #[serde(default)]
+    pub hook_timeouts_ms: HashMap<String, u64>,
+    /// Label categories, e.g. `exploiter`, whose pending transactions a strategy is not shown, by strategy
+    #[serde(default)]
+    pub ignore_labels: HashMap<String, Vec<String>>,
+    /// When each strategy runs, by strategy; strategies without a schedule always run while enabled
+    #[serde(default)]
+    pub schedules: HashMap<String, StrategyScheduleConfig>,
+}
+
+/// Time windows and network conditions a strategy runs under, all of which must hold
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StrategyScheduleConfig {
+    /// UTC time-of-day windows, e.g. `13:30-20:00`, a window ending before it starts wraps midnight
+    #[serde(default)]
+    pub windows: Vec<String>,
+    /// Base fee of the latest block, in gwei
+    pub min_base_fee_gwei: Option<f64>,
+    pub max_base_fee_gwei: Option<f64>,
+    /// Lending markets, at least one of which must be this utilized
+    #[serde(default)]
+    pub min_utilization: Vec<UtilizationThreshold>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UtilizationThreshold {
+    /// Compound v3 Comet market, or the asset of an Aave v3 reserve
+    pub market: String,
+    /// Share of supplied funds borrowed, between 0 and 1
+    pub threshold: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LabelsConfig {
+    /// Label lists imported on startup, each replacing what was imported under its name before
+    #[serde(default)]
+    pub lists: Vec<LabelListConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExploitDetectionConfig {
+    /// Screen the public mempool for live exploits and raise security alerts
+    pub enabled: bool,
+    /// Approvals of at least this many raw token units count as huge; unlimited ones are `2^256 - 1`
+    pub min_approval_units: String,
+    /// Contracts deployed within this many blocks count as fresh; older lookups need an archive node
+    pub fresh_contract_blocks: u64,
+    /// Simulated outflow from a `protocol` or `bridge` labeled address worth raising, in USD
+    pub min_outflow_usd: f64,
+    /// Etherscan-compatible API used to tell whether a selfdestructed contract was verified
+    pub etherscan_api_url: Option<String>,
+    pub etherscan_api_key: Option<String>,
+    /// Suspicious transactions simulated at once, the rest are skipped
+    pub max_in_flight: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasGolfConfig {
+    /// Optimize the transactions we originate before they are signed, recording projected savings
+    pub enabled: bool,
+    /// Contracts taking `multicall(bytes[])` that keep `msg.sender`, consecutive calls to which are packed
+    pub multicall_targets: Vec<String>,
+    /// Attach the access list the node suggests when simulating with it uses less gas than without
+    pub tune_access_lists: bool,
+    /// Gas the list has to save in simulation to be attached
+    pub min_access_list_gas_saved: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelListConfig {
+    pub name: String,
+    /// JSON array of `{address, label, category}` entries
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowBuildConfig {
+    /// Rebuild the blocks that won slots we lost from their own transactions, scoring our ordering
+    pub enabled: bool,
+    /// Lost slots shadow-built per run, each replaying its block twice
+    pub max_slots_per_run: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceConfig {
+    pub enabled: bool,
+    /// Wrapped native token, whose USD price converts token values to wei
+    pub native_token: String,
+    pub tokens: Vec<PriceTokenConfig>,
+    /// HTTP price sources queried for every token
+    #[serde(default)]
+    pub offchain_sources: Vec<OffchainPriceSourceConfig>,
+    /// How long an aggregated price is served from cache
+    pub cache_ttl_seconds: u64,
+    /// Chainlink answers older than this are ignored
+    pub max_feed_age_seconds: u64,
+    /// Sources further than this from the median are left out of the price
+    pub max_deviation_bps: u64,
+    /// Window of Uniswap V3 time-weighted average prices
+    pub twap_seconds: u32,
+    pub offchain_timeout_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceTokenConfig {
+    pub symbol: String,
+    pub address: String,
+    pub decimals: u8,
+    /// Chainlink USD feed proxy
+    #[serde(default)]
+    pub chainlink_feed: Option<String>,
+    /// Uniswap V3 pool pairing the token with `twap_quote`
+    #[serde(default)]
+    pub uniswap_v3_pool: Option<String>,
+    /// Configured token the TWAP is quoted in, priced without its own TWAP
+    #[serde(default)]
+    pub twap_quote: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OffchainPriceSourceConfig {
+    pub name: String,
+    /// URL returning a JSON USD price; `{symbol}` and `{address}` are substituted
+    pub url: String,
+    /// JSON pointer to the price, with the same substitutions, e.g. `/{symbol}/usd`
+    pub json_pointer: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TxOrderingConfig {
     pub worker_threads: usize,
+    /// Run simulations on their own runtime, apart from the API and monitor reactors
+    pub dedicated_simulation_runtime: bool,
+    /// Worker threads of the dedicated simulation runtime
+    pub simulation_threads: usize,
+    /// CPU cores simulation threads are pinned to, round-robin; empty leaves them unpinned
+    pub simulation_cpu_affinity: Vec<usize>,
     pub max_simulation_time_ms: u64,
     pub simulation_mode: String,
+    /// Engine whose results drive decisions (heuristic or revm)
+    pub simulation_engine: String,
+    /// Engine run alongside the primary on a sample of transactions for calibration
+    pub shadow_engine: Option<String>,
+    /// Share of transactions also run through the shadow engine, from 0.0 to 1.0
+    pub shadow_sample_rate: f64,
+    /// Relative profit difference, in basis points, tolerated before engines are considered in disagreement
+    pub profit_tolerance_bps: u64,
+    /// How often rolling mempool statistics are recomputed
+    pub mempool_stats_interval_ms: u64,
+    /// Confirmed transactions processed concurrently off the block loop
+    pub confirmed_tx_concurrency: usize,
+    /// Blocks of confirmed transactions queued before the block loop waits for the workers
+    pub confirmed_tx_queue_blocks: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,23 +857,188 @@ pub struct BlockBuildingConfig {
     pub target_block_fullness: f64,
     pub max_gas_limit: u64,
     pub priority_accounts: Vec<String>,
+    /// How inclusion candidates are picked and ordered into block templates
+    #[serde(default)]
+    pub ordering_strategy: crate::services::ordering::OrderingStrategyKind,
+    /// Extra-data stamped into the header of blocks we build, at most 32 bytes
+    pub extra_data: String,
+    /// BLS public key our bids are signed with, as hex; relays attribute our blocks to it, and
+    /// with relays configured it is required so our wins can be told apart
+    pub builder_pubkey: Option<String>,
+    /// Label of this deployment, recorded with its bids
+    pub graffiti: String,
+    /// Coinbase of blocks we build, which simulations measure profit at
+    pub fee_recipient: String,
+}
+
+impl BlockBuildingConfig {
+    /// Gas block templates may fill
+    pub fn gas_budget(&self) -> u64 {
+        (self.max_gas_limit as f64 * self.target_block_fullness) as u64
+    }
+    
+    /// Coinbase of blocks we build, checked by `validate_config`
+    pub fn fee_recipient(&self) -> ethers::types::Address {
+        self.fee_recipient.parse().unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LiquidStakingConfig {
     pub validator_commission_bps: u32,
     pub withdrawal_delay_epochs: u32,
-    pub min_stake_amount: String,
+    /// Staking pool contract, emitting stake and unstake events and receiving our validators'
+    /// execution rewards; empty leaves the ledger unbooked
+    pub pool_contract: String,
+    /// Smallest amount accepted by stake and unstake requests, in ether
+    pub min_stake_amount: EthAmount,
+    /// Largest amount accepted by a single stake request, in ether
+    pub max_stake_amount: Option<EthAmount>,
+    pub commission_payout: CommissionPayoutConfig,
+    pub deposit_reconciliation: DepositReconciliationConfig,
+    pub token_deposits: TokenDepositConfig,
+}
+
+/// Stakes paid in an EIP-2612 token, approved by permit and relayed by one of our signers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenDepositConfig {
+    pub enabled: bool,
+    /// EIP-2612 token accepted as stake; WETH9 has no `permit` and can't be used
+    pub token: String,
+    /// Smallest stake accepted, in whole tokens of 18 decimals
+    pub min_amount: EthAmount,
+    /// Staking contract taking the permit, the spender it must name
+    pub staking_contract: String,
+    /// Named signer relaying the combined permit and stake call
+    pub signer: String,
+}
+
+/// Independent indexing of beacon deposit contract events, checked against booked stake
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepositReconciliationConfig {
+    pub enabled: bool,
+    pub deposit_contract: String,
+    /// Withdrawal credentials identifying deposits to our validators, as 0x-prefixed hex
+    pub withdrawal_credentials: Vec<String>,
+    /// Block to start indexing from on first run, usually our first deposit
+    pub start_block: u64,
+    /// Blocks scanned per `eth_getLogs` request
+    pub blocks_per_request: u64,
+    pub interval_seconds: u64,
+    /// Seconds a position assigned to a validator may go without an on-chain deposit
+    pub missing_after_seconds: u64,
+}
+
+/// Periodic transfer of accrued operator commission to the treasury
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommissionPayoutConfig {
+    pub enabled: bool,
+    /// Address receiving payouts
+    pub treasury_address: String,
+    /// Named signer the payout is sent from
+    pub signer: String,
+    pub interval_seconds: u64,
+    /// Balances below this are left to accrue, in ether
+    pub min_payout_amount: EthAmount,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportConfig {
+    pub enabled: bool,
+    /// Output format for analytics partitions (parquet, csv)
+    pub format: String,
+    /// Local directory or `s3://bucket/prefix` destination
+    pub destination: String,
+    pub tables: Vec<ExportTableConfig>,
+    /// Number of past days to backfill if partitions are missing
+    pub lookback_days: u32,
+    pub interval_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportTableConfig {
+    pub name: String,
+    /// Column used to assign rows to daily partitions
+    pub timestamp_column: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsConfig {
+    pub clickhouse_enabled: bool,
+    pub clickhouse_url: String,
+    pub clickhouse_database: String,
+    pub clickhouse_user: Option<String>,
+    pub clickhouse_password: Option<String>,
+    /// Rows buffered per table before an insert is triggered
+    pub batch_size: usize,
+    /// Rows kept per table while ClickHouse is failing, the oldest dropped beyond it
+    pub max_buffered_rows: usize,
+    pub flush_interval_ms: u64,
+    /// How often business KPIs are recomputed
+    pub kpi_interval_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertingConfig {
+    /// Webhook endpoints (Slack-compatible) notified on every alert
+    pub webhook_urls: Vec<String>,
+    /// Named webhooks notified on every alert, with signed deliveries logged per webhook
+    pub webhooks: Vec<WebhookConfig>,
+    /// Attempts per signed delivery before it is given up
+    pub max_delivery_attempts: u32,
+    /// Minimum time between repeated notifications for the same alert
+    pub cooldown_seconds: u64,
+    pub evaluation_interval_seconds: u64,
+    pub rules: Vec<AlertRuleConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRuleConfig {
+    pub name: String,
+    /// Metric series as rendered, e.g. `blockchain_errors_total` or `name{label="x"}`
+    pub metric: String,
+    /// Rule kind (threshold, rate_of_change)
+    pub kind: String,
+    /// Comparison operator (gt, lt)
+    pub operator: String,
+    pub value: f64,
+    /// Window for rate-of-change rules; the rate is expressed per second
+    pub window_seconds: u64,
+    /// Severity (info, warning, critical)
+    pub severity: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// Name the webhook's delivery attempts are queried by
+    pub name: String,
+    pub url: String,
+    /// Shared secret deliveries are signed with, unsigned when unset
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeartbeatConfig {
+    /// External ping URL per subsystem, e.g. healthchecks.io checks
+    pub ping_urls: HashMap<String, String>,
+    /// Minimum silence before a subsystem is considered stale
+    pub stale_after_seconds: u64,
+    pub check_interval_seconds: u64,
 }
 
 /// Loads configuration from file and environment variables
 pub fn load() -> Result<Config> {
-    // Initialize dotenv
-    dotenv::dotenv().ok();
-    
     // Parse command line arguments
     let args = cli::parse_args();
     
+    load_from_args(&args)
+}
+
+/// Loads configuration using already-parsed command line arguments
+pub fn load_from_args(args: &cli::Args) -> Result<Config> {
+    // Initialize dotenv
+    dotenv::dotenv().ok();
+    
     // Load config from file
     let config_path = args.config.as_deref().unwrap_or("config/default.yaml");
     let mut config = load_from_file(config_path)?;
@@ -105,7 +1053,7 @@ pub fn load() -> Result<Config> {
     Ok(config)
 }
 
-fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Config> {
+pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Config> {
     let config_file = std::fs::File::open(path)
         .context("Failed to open configuration file")?;
     
@@ -129,26 +1077,511 @@ fn apply_env_overrides(config: &mut Config) -> Result<()> {
         config.blockchain.rpc_url = rpc_url;
     }
     
+    if let Ok(signing_key) = std::env::var("FLASHBOTS_SIGNING_KEY") {
+        config.services.bundles.signing_key = Some(signing_key);
+    }
+    
+    if let Ok(api_key) = std::env::var("ETHERSCAN_API_KEY") {
+        config.blockchain.abi_sources.etherscan_api_key = Some(api_key);
+    }
+    
+    if let Ok(mode) = std::env::var("EXECUTION_MODE") {
+        config.execution_mode = serde_yaml::from_str(&mode)
+            .context(format!("Invalid EXECUTION_MODE {}, expected live or dry_run", mode))?;
+    }
+    
     Ok(())
 }
 
-fn validate_config(config: &Config) -> Result<()> {
+/// Chains canaries may run on without `fork`: Goerli, Holesky, Sepolia, Hoodi and local dev nodes
+const CANARY_TESTNET_CHAIN_IDS: [u64; 5] = [5, 17_000, 11_155_111, 560_048, 31_337];
+
+pub fn validate_config(config: &Config) -> Result<()> {
     // Validate API configuration
     if config.api.bind_address.is_empty() {
         anyhow::bail!("API bind address cannot be empty");
     }
     
+    for api_key in &config.api.api_keys {
+        api_key
+            .role
+            .parse::<crate::api::auth::Role>()
+            .context(format!("API key {} has invalid role", api_key.name))?;
+        for topic in &api_key.topics {
+            topic
+                .parse::<crate::services::events::Topic>()
+                .context(format!("API key {} has invalid topic", api_key.name))?;
+        }
+    }
+    
+    if config.api.jwt_secret.as_ref().map_or(false, |secret| secret.len() < 32) {
+        anyhow::bail!("API JWT secret must be at least 32 bytes");
+    }
+    if config.api.jwt_ttl_seconds == 0 {
+        anyhow::bail!("API JWT lifetime must be greater than 0");
+    }
+    if config.api.max_status_hashes == 0 {
+        anyhow::bail!("API max_status_hashes must be greater than 0");
+    }
+    
+    let rate_limit = &config.api.rate_limit;
+    let buckets = std::iter::once(("default", &rate_limit.default))
+        .chain(rate_limit.routes.iter().map(|route| (route.path_prefix.as_str(), &route.bucket)));
+    for (name, bucket) in buckets {
+        if bucket.rate_per_second <= 0.0 || bucket.burst == 0 {
+            anyhow::bail!("Rate limit for {} must have a positive rate and burst", name);
+        }
+    }
+    
+    let head_rate = config.logging.trace_sampling.head_rate;
+    if !(0.0..=1.0).contains(&head_rate) {
+        anyhow::bail!("Trace sampling head_rate must be between 0 and 1");
+    }
+    
     // Validate database configuration
     if config.database.url.is_empty() {
         anyhow::bail!("Database URL cannot be empty");
     }
+    if config.redis.command_timeout_ms == 0 {
+        anyhow::bail!("Redis command_timeout_ms must be greater than 0");
+    }
     
     // Validate blockchain configuration
     if config.blockchain.rpc_url.is_empty() || config.blockchain.ws_url.is_empty() {
         anyhow::bail!("Blockchain RPC and WebSocket URLs must be provided");
     }
     
+    let policies = &config.blockchain.rpc_policies;
+    let classes = [("fast_reads", &policies.fast_reads), ("heavy", &policies.heavy), ("sends", &policies.sends)];
+    for (class, policy) in classes {
+        if policy.timeout_ms == 0 {
+            anyhow::bail!("RPC {} timeout must be greater than 0", class);
+        }
+        if policy.retries > crate::blockchain::client::MAX_RPC_RETRIES {
+            anyhow::bail!("RPC {} retries must be at most {}", class, crate::blockchain::client::MAX_RPC_RETRIES);
+        }
+    }
+    let abi_sources = &config.blockchain.abi_sources;
+    if abi_sources.request_timeout_ms == 0 {
+        anyhow::bail!("ABI source request_timeout_ms must be greater than 0");
+    }
+    if abi_sources.etherscan_api_key.as_deref().map_or(false, str::is_empty) {
+        anyhow::bail!("Etherscan API key must not be empty, leave it unset instead");
+    }
+    let rate_limits = &config.blockchain.rate_limits;
+    if !(0.0..1.0).contains(&rate_limits.bulk_reserve) {
+        anyhow::bail!("RPC bulk_reserve must be at least 0 and below 1");
+    }
+    let multicall_address = &config.blockchain.multicall_address;
+    multicall_address
+        .parse::<ethers::types::Address>()
+        .map_err(|_| anyhow::anyhow!("Multicall address {} is not an address", multicall_address))?;
+    
+    if config.services.bundles.enabled && config.services.bundles.signing_key.is_none() {
+        anyhow::bail!("Bundle submission requires a signing key");
+    }
+    
+    let mut signer_names = std::collections::HashSet::new();
+    for signer in &config.signers {
+        if !signer_names.insert(signer.name.as_str()) {
+            anyhow::bail!("Signer {} is defined more than once", signer.name);
+        }
+        let source = match signer.kind.as_str() {
+            "keystore" => signer.keystore_path.as_ref().and(signer.password_env.as_ref()),
+            "env" => signer.key_env.as_ref(),
+            "inline" => signer.private_key.as_ref(),
+            "kms" => signer.kms_key_id.as_ref(),
+            other => anyhow::bail!("Signer {} has invalid kind: {}", signer.name, other),
+        };
+        if source.is_none() {
+            anyhow::bail!("Signer {} is missing the key source for kind {}", signer.name, signer.kind);
+        }
+    }
+    
+    let staking = &config.services.liquid_staking;
+    if staking.max_stake_amount.map_or(false, |max| max < staking.min_stake_amount) {
+        anyhow::bail!("Liquid staking max_stake_amount is below min_stake_amount");
+    }
+    
+    if !staking.pool_contract.is_empty() {
+        staking
+            .pool_contract
+            .parse::<ethers::types::Address>()
+            .context("Liquid staking pool_contract is not a valid address")?;
+    }
+    if staking.validator_commission_bps > 10_000 {
+        anyhow::bail!("Liquid staking validator_commission_bps must be at most 10000");
+    }
+    
+    let deposits = &staking.deposit_reconciliation;
+    if deposits.enabled {
+        deposits
+            .deposit_contract
+            .parse::<ethers::types::Address>()
+            .context("Deposit reconciliation deposit_contract is not a valid address")?;
+        if deposits.withdrawal_credentials.is_empty() {
+            anyhow::bail!("Deposit reconciliation requires at least one withdrawal credential");
+        }
+        for credentials in &deposits.withdrawal_credentials {
+            let hex = credentials.strip_prefix("0x").unwrap_or(credentials);
+            if hex.len() != 64 || hex::decode(hex).is_err() {
+                anyhow::bail!("Withdrawal credentials {} must be 32 bytes of hex", credentials);
+            }
+        }
+        if deposits.blocks_per_request == 0 || deposits.interval_seconds == 0 {
+            anyhow::bail!("Deposit reconciliation blocks_per_request and interval_seconds must be positive");
+        }
+    }
+    
+    let token_deposits = &staking.token_deposits;
+    if token_deposits.enabled {
+        token_deposits
+            .token
+            .parse::<ethers::types::Address>()
+            .context("Token deposits token is not a valid address")?;
+        if token_deposits.min_amount.wei().is_zero() {
+            anyhow::bail!("Token deposits min_amount must be positive");
+        }
+        token_deposits
+            .staking_contract
+            .parse::<ethers::types::Address>()
+            .context("Token deposits staking_contract is not a valid address")?;
+        if !signer_names.contains(token_deposits.signer.as_str()) {
+            anyhow::bail!("Token deposits signer {} is not a configured signer", token_deposits.signer);
+        }
+    }
+    
+    let payout = &staking.commission_payout;
+    if payout.enabled {
+        payout
+            .treasury_address
+            .parse::<ethers::types::Address>()
+            .context("Commission payout treasury_address is not a valid address")?;
+        if !signer_names.contains(payout.signer.as_str()) {
+            anyhow::bail!("Commission payout signer {} is not a configured signer", payout.signer);
+        }
+        if payout.interval_seconds == 0 {
+            anyhow::bail!("Commission payout interval must be greater than 0");
+        }
+    }
+    
+    if let Some(searcher) = &config.services.sandwich.searcher {
+        if !signer_names.contains(searcher.as_str()) {
+            anyhow::bail!("Sandwich searcher {} is not a configured signer", searcher);
+        }
+    }
+    
+    if let Some(trader) = &config.services.arbitrage.trader {
+        if !signer_names.contains(trader.as_str()) {
+            anyhow::bail!("Arbitrage trader {} is not a configured signer", trader);
+        }
+    }
+    
+    if let Some(liquidator) = &config.services.liquidation.liquidator {
+        if !signer_names.contains(liquidator.as_str()) {
+            anyhow::bail!("Liquidator {} is not a configured signer", liquidator);
+        }
+    }
+    
+    let liquidation = &config.services.liquidation;
+    if liquidation.enabled {
+        if liquidation.aave_pool.is_none() && liquidation.comets.is_empty() {
+            anyhow::bail!("Liquidation monitor needs an Aave pool or at least one Comet market");
+        }
+        if liquidation.max_positions == 0 {
+            anyhow::bail!("Liquidation max_positions must be greater than 0");
+        }
+    }
+    
+    let shadow_build = &config.services.shadow_build;
+    if shadow_build.enabled {
+        if !config.relay_scraper.enabled {
+            anyhow::bail!("Shadow builds need the relay scraper to learn which blocks won our lost slots");
+        }
+        if shadow_build.max_slots_per_run == 0 {
+            anyhow::bail!("Shadow build max_slots_per_run must be greater than 0");
+        }
+    }
+    
+    let risk = &config.risk;
+    if risk.enabled {
+        if risk.window_seconds == 0 {
+            anyhow::bail!("Risk window_seconds must be greater than 0");
+        }
+        ethers::types::U256::from_dec_str(&risk.max_loss_wei)
+            .map_err(|e| anyhow::anyhow!("Risk max_loss_wei is not a wei amount: {}", e))?;
+        for (name, rate) in [
+            ("max_failed_bundle_rate", risk.max_failed_bundle_rate),
+            ("max_unexpected_revert_rate", risk.max_unexpected_revert_rate),
+        ] {
+            if !(0.0..=1.0).contains(&rate) {
+                anyhow::bail!("Risk {} must be between 0 and 1", name);
+            }
+        }
+    }
+    
+    if config.services.strategies.hook_timeout_ms == 0 {
+        anyhow::bail!("Strategy hook_timeout_ms must be greater than 0");
+    }
+    for (strategy, timeout_ms) in &config.services.strategies.hook_timeouts_ms {
+        if *timeout_ms == 0 {
+            anyhow::bail!("Strategy {} hook timeout must be greater than 0", strategy);
+        }
+    }
+    for (strategy, schedule) in &config.services.strategies.schedules {
+        crate::core::schedule::StrategySchedule::parse(schedule)
+            .map_err(|e| anyhow::anyhow!("Invalid schedule for strategy {}: {}", strategy, e))?;
+    }
+    
+    let exploit_detection = &config.services.exploit_detection;
+    if exploit_detection.enabled {
+        ethers::types::U256::from_dec_str(&exploit_detection.min_approval_units)
+            .map_err(|e| anyhow::anyhow!("Exploit detection min_approval_units is not an amount: {}", e))?;
+        if exploit_detection.fresh_contract_blocks == 0 {
+            anyhow::bail!("Exploit detection fresh_contract_blocks must be greater than 0");
+        }
+        if exploit_detection.max_in_flight == 0 {
+            anyhow::bail!("Exploit detection max_in_flight must be greater than 0");
+        }
+    }
+    
+    let lanes = &config.services.task_lanes;
+    if lanes.enabled && (lanes.critical_worker_threads == 0 || lanes.background_worker_threads == 0) {
+        anyhow::bail!("Task lanes need at least one critical and one background worker thread");
+    }
+    
+    let analytics = &config.analytics;
+    let batch_fits = analytics.batch_size > 0 && analytics.batch_size <= analytics.max_buffered_rows;
+    if analytics.clickhouse_enabled && !batch_fits {
+        anyhow::bail!("Analytics batch_size must be greater than 0 and at most max_buffered_rows");
+    }
+    
+    let recording = &config.services.mempool_recording;
+    if recording.enabled {
+        if recording.directory.is_empty() {
+            anyhow::bail!("Mempool recording directory must be set");
+        }
+        if recording.batch_size == 0 || recording.max_buffered < recording.batch_size {
+            anyhow::bail!("Mempool recording batch_size must be greater than 0 and at most max_buffered");
+        }
+        if recording.flush_interval_ms == 0 {
+            anyhow::bail!("Mempool recording flush_interval_ms must be greater than 0");
+        }
+    }
+    
+    let snapshots = &config.services.snapshots;
+    if snapshots.enabled {
+        if snapshots.path.is_empty() {
+            anyhow::bail!("Snapshot path must be set");
+        }
+        if snapshots.max_age_seconds == 0 {
+            anyhow::bail!("Snapshot max_age_seconds must be greater than 0");
+        }
+        if snapshots.max_age_blocks == 0 {
+            anyhow::bail!("Snapshot max_age_blocks must be greater than 0");
+        }
+    }
+    
+    let gas_golf = &config.services.gas_golf;
+    if gas_golf.enabled {
+        for target in &gas_golf.multicall_targets {
+            target
+                .parse::<ethers::types::Address>()
+                .map_err(|_| anyhow::anyhow!("Gas golf multicall target {} is not an address", target))?;
+        }
+    }
+    
+    let mut label_lists = std::collections::HashSet::new();
+    for list in &config.services.labels.lists {
+        if list.name.is_empty() || list.name == "manual" {
+            anyhow::bail!("Label list names must be non-empty and not `manual`");
+        }
+        if !label_lists.insert(list.name.as_str()) {
+            anyhow::bail!("Label list {} is configured twice", list.name);
+        }
+    }
+    
+    let prices = &config.services.prices;
+    if prices.enabled {
+        if prices.cache_ttl_seconds == 0 {
+            anyhow::bail!("Price cache_ttl_seconds must be greater than 0");
+        }
+        if prices.twap_seconds == 0 {
+            anyhow::bail!("Price twap_seconds must be greater than 0");
+        }
+        for token in &prices.tokens {
+            let onchain = token.chainlink_feed.is_some() || token.uniswap_v3_pool.is_some();
+            if !onchain && prices.offchain_sources.is_empty() {
+                anyhow::bail!("Price token {} has no price source", token.symbol);
+            }
+            if token.uniswap_v3_pool.is_some() != token.twap_quote.is_some() {
+                anyhow::bail!("Price token {} needs both uniswap_v3_pool and twap_quote", token.symbol);
+            }
+        }
+        if !prices.tokens.iter().any(|token| token.address.eq_ignore_ascii_case(&prices.native_token)) {
+            anyhow::bail!("Price native_token must be one of the configured tokens");
+        }
+    }
+    
+    let reputation = &config.services.searcher_reputation;
+    if reputation.enabled {
+        for (name, threshold) in [
+            ("low_priority_below", reputation.low_priority_below),
+            ("throttle_below", reputation.throttle_below),
+        ] {
+            if !(0.0..=1.0).contains(&threshold) {
+                anyhow::bail!("Searcher reputation {} must be between 0 and 1", name);
+            }
+        }
+        if reputation.flush_interval_seconds == 0 {
+            anyhow::bail!("Searcher reputation flush interval must be greater than 0");
+        }
+    }
+    
+    let mempool = &config.services.mempool_persistence;
+    if mempool.enabled && (mempool.stale_after_seconds == 0 || mempool.prune_interval_seconds == 0) {
+        anyhow::bail!("Mempool persistence stale_after_seconds and prune_interval_seconds must be positive");
+    }
+    
+    let bloxroute = &config.services.bloxroute_stream;
+    if bloxroute.enabled && (bloxroute.url.is_empty() || bloxroute.auth_header.is_empty()) {
+        anyhow::bail!("bloXroute stream needs a url and an auth_header");
+    }
+    
+    let state_history = &config.services.state_history;
+    if state_history.retention_hours == 0 || state_history.prune_interval_seconds == 0 {
+        anyhow::bail!("State history retention_hours and prune_interval_seconds must be positive");
+    }
+    
+    let block_storage = &config.services.block_storage;
+    if block_storage.enabled {
+        if !(1..=22).contains(&block_storage.compression_level) {
+            anyhow::bail!("Block storage compression_level must be between 1 and 22");
+        }
+        if block_storage.retention_blocks == 0 || block_storage.prune_interval_seconds == 0 {
+            anyhow::bail!("Block storage retention_blocks and prune_interval_seconds must be positive");
+        }
+    }
+    
+    let event_indexer = &config.services.event_indexer;
+    if event_indexer.enabled {
+        if event_indexer.contracts.is_empty() {
+            anyhow::bail!("Event indexer needs at least one contract");
+        }
+        if event_indexer.catch_up_range_blocks == 0 {
+            anyhow::bail!("Event indexer catch_up_range_blocks must be greater than 0");
+        }
+        let mut names = std::collections::HashSet::new();
+        for contract in &event_indexer.contracts {
+            if !names.insert(contract.name.as_str()) {
+                anyhow::bail!("Event indexer contract {} is listed twice", contract.name);
+            }
+            contract
+                .address
+                .parse::<ethers::types::Address>()
+                .context(format!("Event indexer contract {} has an invalid address", contract.name))?;
+            if contract.events.is_empty() {
+                anyhow::bail!("Event indexer contract {} lists no events", contract.name);
+            }
+            for signature in &contract.events {
+                crate::services::log_indexer::parse_event(signature)
+                    .context(format!("Event indexer contract {} has an invalid event", contract.name))?;
+            }
+        }
+    }
+    
+    let canary = &config.services.canary;
+    if canary.enabled {
+        // A self-transfer is harmless but still pays gas, which is only free on a testnet or fork
+        if !canary.fork && !CANARY_TESTNET_CHAIN_IDS.contains(&config.blockchain.chain_id) {
+            anyhow::bail!(
+                "Canary transactions only run on a testnet or fork, chain {} is neither",
+                config.blockchain.chain_id
+            );
+        }
+        if !signer_names.contains(canary.signer.as_str()) {
+            anyhow::bail!("Canary signer {} is not a configured signer", canary.signer);
+        }
+        if canary.interval_seconds == 0 || canary.timeout_seconds == 0 || canary.window == 0 {
+            anyhow::bail!("Canary interval_seconds, timeout_seconds and window must be positive");
+        }
+        // A running canary delays the job's next tick and heartbeat
+        if canary.timeout_seconds >= canary.interval_seconds {
+            anyhow::bail!("Canary timeout_seconds must be shorter than interval_seconds");
+        }
+        if canary.confirmations == 0 {
+            anyhow::bail!("Canary confirmations must be at least 1");
+        }
+        if !(0.0..=1.0).contains(&canary.min_success_rate) {
+            anyhow::bail!("Canary min_success_rate must be between 0 and 1");
+        }
+    }
+    
+    let maintenance = &config.services.maintenance;
+    if maintenance.enabled {
+        if maintenance.window_slots == 0 || maintenance.max_deferral_seconds == 0 {
+            anyhow::bail!("Maintenance window_slots and max_deferral_seconds must be positive");
+        }
+        if maintenance.beacon_url.is_none() && config.relays.is_empty() {
+            anyhow::bail!("Maintenance windows need a beacon_url or a relay for the proposer lookahead");
+        }
+    }
+    for table in &maintenance.vacuum_tables {
+        // Interpolated into VACUUM, which takes no bind parameters
+        let valid = table.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+            && table.chars().next().map_or(false, |c| !c.is_ascii_digit());
+        if !valid {
+            anyhow::bail!("Maintenance vacuum table {} is not a plain table name", table);
+        }
+    }
+    if !maintenance.vacuum_tables.is_empty() && maintenance.vacuum_interval_seconds == 0 {
+        anyhow::bail!("Maintenance vacuum_interval_seconds must be greater than 0");
+    }
+    
+    let block_building = &config.services.block_building;
+    // The execution layer caps header extra-data at 32 bytes
+    if block_building.extra_data.len() > 32 {
+        anyhow::bail!("Block building extra_data must be at most 32 bytes");
+    }
+    if block_building.graffiti.is_empty() || block_building.graffiti.len() > 32 {
+        anyhow::bail!("Block building graffiti must be between 1 and 32 bytes");
+    }
+    if block_building.fee_recipient.parse::<ethers::types::Address>().is_err() {
+        anyhow::bail!("Block building fee_recipient {} is not an address", block_building.fee_recipient);
+    }
+    if !config.relays.is_empty() && block_building.builder_pubkey.is_none() {
+        anyhow::bail!("Block building builder_pubkey is required to bid to relays");
+    }
+    if let Some(pubkey) = &block_building.builder_pubkey {
+        let hex = pubkey.strip_prefix("0x").unwrap_or(pubkey);
+        if hex.len() != 96 || hex::decode(hex).is_err() {
+            anyhow::bail!("Block building builder_pubkey {} must be 48 bytes of hex", pubkey);
+        }
+    }
+    
+    let mut webhooks = std::collections::HashSet::new();
+    for webhook in &config.alerting.webhooks {
+        if webhook.name.is_empty() || webhook.url.is_empty() {
+            anyhow::bail!("Webhooks need a name and a URL");
+        }
+        if !webhooks.insert(webhook.name.as_str()) {
+            anyhow::bail!("Webhook {} is configured twice", webhook.name);
+        }
+        if webhook.secret.as_deref() == Some("") {
+            anyhow::bail!("Webhook {} secret must not be empty", webhook.name);
+        }
+    }
+    let mut rules = std::collections::HashSet::new();
+    for rule in &config.alerting.rules {
+        if !rules.insert(rule.name.as_str()) {
+            anyhow::bail!("Alert rule {} is configured twice", rule.name);
+        }
+    }
+    if config.alerting.max_delivery_attempts == 0 {
+        anyhow::bail!("Alerting max_delivery_attempts must be greater than 0");
+    }
+    
     // Additional validation for specific services could be added here
     
     Ok(())
-} 
\ No newline at end of file
+}
\ No newline at end of file
@@ -0,0 +1,150 @@
+use ethers::types::{U256, U512};
+
+/// Fees are expressed in millionths, matching Uniswap V3 fee tiers
+pub const FEE_DENOMINATOR: u32 = 1_000_000;
+
+/// Newton iterations before StableSwap invariants are considered divergent
+const STABLESWAP_ITERATIONS: usize = 255;
+
+/// Constant-product output of a swap, or `None` if the arithmetic overflows
+pub fn constant_product_out(amount_in: U256, reserve_in: U256, reserve_out: U256, fee: u32) -> Option<U256> {
+    let in_with_fee = amount_in.full_mul(U256::from(FEE_DENOMINATOR.checked_sub(fee)?));
+    let numerator = in_with_fee.checked_mul(U512::from(reserve_out))?;
+    let denominator = reserve_in.full_mul(U256::from(FEE_DENOMINATOR)).checked_add(in_with_fee)?;
+    if denominator.is_zero() {
+        return None;
+    }
+    U256::try_from(numerator / denominator).ok()
+}
+
+/// Virtual token0 and token1 reserves of a Uniswap V3 pool at its current price
+///
+/// Swaps against these behave like the pool only while they stay inside the current tick range.
+pub fn v3_virtual_reserves(sqrt_price_x96: U256, liquidity: U256) -> (U256, U256) {
+    if sqrt_price_x96.is_zero() {
+        return (U256::zero(), U256::zero());
+    }
+    
+    // x = L / sqrt(P), y = L * sqrt(P)
+    let q96 = U256::one() << 96;
+    let reserve0 = U256::try_from(liquidity.full_mul(q96) / U512::from(sqrt_price_x96)).unwrap_or(U256::MAX);
+    let reserve1 = U256::try_from(liquidity.full_mul(sqrt_price_x96) / U512::from(q96)).unwrap_or(U256::MAX);
+    (reserve0, reserve1)
+}
+
+/// Output of a Curve StableSwap exchange from coin `i` to coin `j`
+///
+/// Balances and the input must already be scaled to a common precision.
+pub fn stableswap_out(balances: &[U256], amplification: U256, fee: u32, i: usize, j: usize, amount_in: U256) -> Option<U256> {
+    if i == j || i >= balances.len() || j >= balances.len() {
+        return None;
+    }
+    
+    let d = stableswap_invariant(balances, amplification)?;
+    let x = balances[i].checked_add(amount_in)?;
+    let y = stableswap_balance(balances, amplification, i, j, x, d)?;
+    
+    // Curve rounds one wei against the trader
+    let dy = balances[j].checked_sub(y)?.checked_sub(U256::one())?;
+    let fee = dy * U256::from(fee) / U256::from(FEE_DENOMINATOR);
+    Some(dy - fee)
+}
+
+fn stableswap_invariant(balances: &[U256], amplification: U256) -> Option<U256> {
+    let n = U256::from(balances.len());
+    let sum = balances.iter().try_fold(U256::zero(), |acc, x| acc.checked_add(*x))?;
+    if sum.is_zero() {
+        return Some(U256::zero());
+    }
+    
+    let ann = amplification.checked_mul(n)?;
+    let mut d = sum;
+    for _ in 0..STABLESWAP_ITERATIONS {
+        let mut d_p = d;
+        for x in balances {
+            if x.is_zero() {
+                return None;
+            }
+            d_p = d_p.checked_mul(d)? / x.checked_mul(n)?;
+        }
+        let previous = d;
+        let numerator = ann.checked_mul(sum)?.checked_add(d_p.checked_mul(n)?)?.checked_mul(d)?;
+        let denominator = ann.checked_sub(U256::one())?.checked_mul(d)?.checked_add((n + 1).checked_mul(d_p)?)?;
+        d = numerator / denominator;
+        
+        if d.max(previous) - d.min(previous) <= U256::one() {
+            return Some(d);
+        }
+    }
+    
+    None
+}
+
+/// Balance of coin `j` keeping the invariant once coin `i` holds `x`
+fn stableswap_balance(balances: &[U256], amplification: U256, i: usize, j: usize, x: U256, d: U256) -> Option<U256> {
+    let n = U256::from(balances.len());
+    let ann = amplification.checked_mul(n)?;
+    
+    let mut c = d;
+    let mut sum = U256::zero();
+    for (k, balance) in balances.iter().enumerate() {
+        let balance = match k {
+            k if k == i => x,
+            k if k == j => continue,
+            _ => *balance,
+        };
+        sum = sum.checked_add(balance)?;
+        c = c.checked_mul(d)? / balance.checked_mul(n)?;
+    }
+    c = c.checked_mul(d)? / ann.checked_mul(n)?;
+    let b = sum.checked_add(d / ann)?;
+    
+    let mut y = d;
+    for _ in 0..STABLESWAP_ITERATIONS {
+        let previous = y;
+        let denominator = (y * 2).checked_add(b)?.checked_sub(d)?;
+        y = y.checked_mul(y)?.checked_add(c)? / denominator;
+        
+        if y.max(previous) - y.min(previous) <= U256::one() {
+            return Some(y);
+        }
+    }
+    
+    None
+}
+
+/// Output of a Balancer weighted pool swap
+///
+/// `out = balance_out * (1 - (balance_in / (balance_in + in)) ^ (weight_in / weight_out))`,
+/// evaluated in floating point as precision here only needs to rank paths.
+pub fn weighted_out(
+    balance_in: U256,
+    weight_in: f64,
+    balance_out: U256,
+    weight_out: f64,
+    fee: u32,
+    amount_in: U256,
+) -> Option<U256> {
+    if balance_in.is_zero() || weight_out <= 0.0 {
+        return None;
+    }
+    
+    let fee = fee as f64 / FEE_DENOMINATOR as f64;
+    let balance_in = to_f64(balance_in);
+    let amount_in = to_f64(amount_in) * (1.0 - fee);
+    let ratio = (balance_in / (balance_in + amount_in)).powf(weight_in / weight_out);
+    let out = to_f64(balance_out) * (1.0 - ratio);
+    
+    if !out.is_finite() || out < 0.0 {
+        return None;
+    }
+    Some(from_f64(out))
+}
+
+pub fn to_f64(value: U256) -> f64 {
+    value.to_string().parse().unwrap_or(f64::MAX)
+}
+
+fn from_f64(value: f64) -> U256 {
+    U256::from_dec_str(&format!("{:.0}", value.floor())).unwrap_or(U256::MAX)
+}
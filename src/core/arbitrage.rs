@@ -0,0 +1,1115 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use ethers::{
+    abi::{self, ParamType, Token},
+    types::{Address, Bytes, Eip1559TransactionRequest, Filter, Log, ValueOrArray, H256, U256, U512},
+    utils::keccak256,
+};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+};
+use tracing::{debug, warn};
+
+use crate::{
+    blockchain::{
+        client::{calldata, first_word},
+        BlockchainClient,
+    },
+    config::{ArbitrageConfig, ArbitragePoolConfig, CacheSettings},
+    services::{gas_golf::Route, prices::PriceService},
+    core::amm::{constant_product_out, stableswap_out, to_f64, v3_virtual_reserves, weighted_out, FEE_DENOMINATOR},
+    utils::cache::{BoundedCache, EvictionPolicy},
+};
+
+/// Relaxations smaller than this are treated as rounding noise rather than a cycle
+const NEGATIVE_CYCLE_EPSILON: f64 = 1e-9;
+
+/// Spot rates are probed with this fraction of the input reserve
+const SPOT_PROBE_DIVISOR: u64 = 10_000;
+
+/// Curve reports fees with 10 decimals
+const CURVE_FEE_SCALE: u64 = 10_000;
+
+/// Balancer reports fees and weights with 18 decimals
+const BALANCER_FEE_SCALE: u64 = 1_000_000_000_000;
+
+/// A cycle sent this many blocks ago that is found again is sent again
+const RESEND_AFTER_BLOCKS: u64 = 3;
+
+/// Gas allowed for approving or transferring a hop's input
+const TOKEN_GAS: u64 = 65_000;
+
+const APPROVE: &str = "approve(address,uint256)";
+const TRANSFER: &str = "transfer(address,uint256)";
+/// Balancer vault single swap: `(poolId, kind, assetIn, assetOut, amount, userData)`, funds, limit, deadline
+const BALANCER_SWAP: &str =
+    "swap((bytes32,uint8,address,address,uint256,bytes),(address,bool,address,bool),uint256,uint256)";
+
+/// Uniswap V2 pairs emit this with their reserves after every change
+const V2_SYNC: &str = "Sync(uint112,uint112)";
+/// Uniswap V3 swaps carry the pool's price and in-range liquidity after the swap
+const V3_SWAP: &str = "Swap(address,address,int256,int256,uint160,uint128,int24)";
+/// Uniswap V3 position changes, which move in-range liquidity without a swap
+const V3_MINT: &str = "Mint(address,address,int24,int24,uint128,uint256,uint256)";
+const V3_BURN: &str = "Burn(address,int24,int24,uint128,uint256,uint256)";
+
+/// AMM design of a pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Protocol {
+    UniswapV2,
+    UniswapV3,
+    Curve,
+    Balancer,
+}
+
+impl FromStr for Protocol {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "uniswap_v2" => Ok(Protocol::UniswapV2),
+            "uniswap_v3" => Ok(Protocol::UniswapV3),
+            "curve" => Ok(Protocol::Curve),
+            "balancer" => Ok(Protocol::Balancer),
+            other => Err(anyhow!("Unknown pool kind {}", other)),
+        }
+    }
+}
+
+/// Reserves and pricing parameters of one pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolState {
+    UniswapV2 {
+        reserves: [U256; 2],
+        fee: u32,
+    },
+    UniswapV3 {
+        sqrt_price_x96: U256,
+        liquidity: U256,
+        fee: u32,
+    },
+    Curve {
+        /// Balances scaled to 18 decimals
+        balances: Vec<U256>,
+        amplification: U256,
+        fee: u32,
+    },
+    Balancer {
+        balances: Vec<U256>,
+        /// Normalized weights summing to one
+        weights: Vec<f64>,
+        fee: u32,
+    },
+}
+
+/// A tracked pool and its latest state
+#[derive(Debug, Clone)]
+struct Pool {
+    address: Address,
+    protocol: Protocol,
+    tokens: Vec<Address>,
+    /// Multipliers scaling Curve balances to 18 decimals
+    precisions: Vec<U256>,
+    /// Configured Uniswap V2 fee
+    fee: u32,
+    /// Balancer pool id
+    pool_id: Option<H256>,
+    state: Option<PoolState>,
+}
+
+/// What one log of a tracked pool says about its state
+enum PoolLog {
+    /// The pool's state after the log
+    State(PoolState),
+    /// The log leaves the pool's pricing as it was
+    Unchanged,
+    /// The pool moved in a way only re-reading it captures
+    Moved,
+}
+
+impl Pool {
+    fn from_config(config: &ArbitragePoolConfig) -> Result<Self> {
+        let protocol: Protocol = config.kind.parse()?;
+        let address = config.address.parse().with_context(|| format!("Invalid pool address {}", config.address))?;
+        let tokens = config
+            .tokens
+            .iter()
+            .map(|token| token.parse().with_context(|| format!("Invalid token address {}", token)))
+            .collect::<Result<Vec<Address>>>()?;
+        if tokens.len() < 2 {
+            return Err(anyhow!("Pool {} needs at least two tokens", config.address));
+        }
+        if matches!(protocol, Protocol::UniswapV2 | Protocol::UniswapV3) && tokens.len() != 2 {
+            return Err(anyhow!("Uniswap pool {} must list exactly token0 and token1", config.address));
+        }
+        
+        let precisions = match protocol {
+            Protocol::Curve if config.decimals.len() != tokens.len() => {
+                return Err(anyhow!("Curve pool {} needs decimals for every token", config.address));
+            }
+            Protocol::Curve => config
+                .decimals
+                .iter()
+                .map(|decimals| U256::exp10(18usize.saturating_sub(*decimals as usize)))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let pool_id = match (protocol, &config.pool_id) {
+            (Protocol::Balancer, Some(pool_id)) => Some(pool_id.parse().context("Invalid Balancer pool id")?),
+            (Protocol::Balancer, None) => return Err(anyhow!("Balancer pool {} needs a pool id", config.address)),
+            _ => None,
+        };
+        
+        Ok(Self {
+            address,
+            protocol,
+            tokens,
+            precisions,
+            fee: config.fee.unwrap_or(3000),
+            pool_id,
+            state: None,
+        })
+    }
+    
+    /// Read a log the pool emitted
+    ///
+    /// Uniswap V2 pairs sync their reserves after every change and V3 swaps report the new
+    /// price and liquidity, so those are applied as they are. Curve and Balancer logs don't
+    /// carry full balances.
+    fn read_log(&self, log: &Log) -> PoolLog {
+        let topic = match log.topics.first() {
+            Some(topic) => *topic,
+            None => return PoolLog::Unchanged,
+        };
+        match self.protocol {
+            Protocol::UniswapV2 if topic == H256(keccak256(V2_SYNC)) => {
+                match decode(&[ParamType::Uint(112), ParamType::Uint(112)], &log.data) {
+                    Ok(tokens) => PoolLog::State(PoolState::UniswapV2 {
+                        reserves: [uint(&tokens[0]), uint(&tokens[1])],
+                        fee: self.fee,
+                    }),
+                    Err(_) => PoolLog::Moved,
+                }
+            }
+            Protocol::UniswapV2 => PoolLog::Unchanged,
+            Protocol::UniswapV3 if topic == H256(keccak256(V3_SWAP)) => {
+                let fee = match &self.state {
+                    Some(PoolState::UniswapV3 { fee, .. }) => *fee,
+                    _ => return PoolLog::Moved,
+                };
+                let types = [
+                    ParamType::Int(256),
+                    ParamType::Int(256),
+                    ParamType::Uint(160),
+                    ParamType::Uint(128),
+                    ParamType::Int(24),
+                ];
+                match decode(&types, &log.data) {
+                    Ok(tokens) => PoolLog::State(PoolState::UniswapV3 {
+                        sqrt_price_x96: uint(&tokens[2]),
+                        liquidity: uint(&tokens[3]),
+                        fee,
+                    }),
+                    Err(_) => PoolLog::Moved,
+                }
+            }
+            Protocol::UniswapV3 if topic == H256(keccak256(V3_MINT)) || topic == H256(keccak256(V3_BURN)) => {
+                PoolLog::Moved
+            }
+            Protocol::UniswapV3 => PoolLog::Unchanged,
+            Protocol::Curve | Protocol::Balancer => PoolLog::Moved,
+        }
+    }
+    
+    /// Whether a state fits this pool's design and token count
+    fn accepts(&self, state: &PoolState) -> bool {
+        match (self.protocol, state) {
+            (Protocol::UniswapV2, PoolState::UniswapV2 { .. }) => true,
+            (Protocol::UniswapV3, PoolState::UniswapV3 { .. }) => true,
+            (Protocol::Curve, PoolState::Curve { balances, .. }) => balances.len() == self.tokens.len(),
+            (Protocol::Balancer, PoolState::Balancer { balances, weights, .. }) => {
+                balances.len() == self.tokens.len() && weights.len() == self.tokens.len()
+            }
+            _ => false,
+        }
+    }
+    
+    /// Output of swapping `amount_in` of token `i` for token `j`
+    fn amount_out(&self, i: usize, j: usize, amount_in: U256) -> Option<U256> {
+        match self.state.as_ref()? {
+            PoolState::UniswapV2 { reserves, fee } => constant_product_out(amount_in, reserves[i], reserves[j], *fee),
+            PoolState::UniswapV3 {
+                sqrt_price_x96,
+                liquidity,
+                fee,
+            } => {
+                let (reserve0, reserve1) = v3_virtual_reserves(*sqrt_price_x96, *liquidity);
+                let reserves = [reserve0, reserve1];
+                constant_product_out(amount_in, reserves[i], reserves[j], *fee)
+            }
+            PoolState::Curve {
+                balances,
+                amplification,
+                fee,
+            } => {
+                let scaled = amount_in.checked_mul(self.precisions[i])?;
+                stableswap_out(balances, *amplification, *fee, i, j, scaled).map(|out| out / self.precisions[j])
+            }
+            PoolState::Balancer { balances, weights, fee } => {
+                weighted_out(balances[i], weights[i], balances[j], weights[j], *fee, amount_in)
+            }
+        }
+    }
+    
+    /// Reserve of token `i` in its own units, sizing the spot-rate probe
+    fn reserve(&self, i: usize) -> U256 {
+        match self.state.as_ref() {
+            Some(PoolState::UniswapV2 { reserves, .. }) => reserves[i],
+            Some(PoolState::UniswapV3 {
+                sqrt_price_x96,
+                liquidity,
+                ..
+            }) => {
+                let (reserve0, reserve1) = v3_virtual_reserves(*sqrt_price_x96, *liquidity);
+                if i == 0 {
+                    reserve0
+                } else {
+                    reserve1
+                }
+            }
+            Some(PoolState::Curve { balances, .. }) => balances[i] / self.precisions[i],
+            Some(PoolState::Balancer { balances, .. }) => balances[i],
+            None => U256::zero(),
+        }
+    }
+    
+    /// Marginal exchange rate from token `i` to token `j`, net of fees
+    fn spot_rate(&self, i: usize, j: usize) -> Option<f64> {
+        let probe = (self.reserve(i) / SPOT_PROBE_DIVISOR).max(U256::one());
+        let out = self.amount_out(i, j, probe)?;
+        if out.is_zero() {
+            return None;
+        }
+        Some(to_f64(out) / to_f64(probe))
+    }
+}
+
+/// One swap of an arbitrage cycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArbitrageHop {
+    pub pool: Address,
+    pub protocol: Protocol,
+    pub token_in: Address,
+    pub token_out: Address,
+    pub amount_in: U256,
+    pub amount_out: U256,
+}
+
+/// A profitable cycle through tracked pools, sized for maximum profit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArbitrageOpportunity {
+    /// Base token the cycle starts and ends in
+    pub input_token: Address,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    /// Output minus input, in the input token, before gas
+    pub profit: U256,
+    /// Profit less the cycle's gas cost, in the input token
+    pub net_profit: U256,
+    pub hops: Vec<ArbitrageHop>,
+    /// Gas the cycle is expected to use
+    pub gas_estimate: u64,
+    pub detected_at: DateTime<Utc>,
+}
+
+/// Directed swap between two tokens through one pool
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    from: usize,
+    to: usize,
+    pool: usize,
+    token_in: usize,
+    token_out: usize,
+    /// Negative log of the spot rate, so profitable cycles have negative total weight
+    weight: f64,
+}
+
+/// Pools and the tokens they connect
+#[derive(Default)]
+struct PoolGraph {
+    pools: Vec<Pool>,
+    by_address: HashMap<Address, usize>,
+    tokens: Vec<Address>,
+    token_index: HashMap<Address, usize>,
+}
+
+impl PoolGraph {
+    fn add_pool(&mut self, pool: Pool) {
+        for token in &pool.tokens {
+            if !self.token_index.contains_key(token) {
+                self.token_index.insert(*token, self.tokens.len());
+                self.tokens.push(*token);
+            }
+        }
+        self.by_address.insert(pool.address, self.pools.len());
+        self.pools.push(pool);
+    }
+    
+    fn edges(&self) -> Vec<Edge> {
+        let mut edges = Vec::new();
+        for (index, pool) in self.pools.iter().enumerate() {
+            for i in 0..pool.tokens.len() {
+                for j in 0..pool.tokens.len() {
+                    if i == j {
+                        continue;
+                    }
+                    if let Some(rate) = pool.spot_rate(i, j).filter(|rate| rate.is_finite() && *rate > 0.0) {
+                        edges.push(Edge {
+                            from: self.token_index[&pool.tokens[i]],
+                            to: self.token_index[&pool.tokens[j]],
+                            pool: index,
+                            token_in: i,
+                            token_out: j,
+                            weight: -rate.ln(),
+                        });
+                    }
+                }
+            }
+        }
+        edges
+    }
+    
+    /// Run an amount through a cycle, returning every hop's amounts
+    fn simulate(&self, cycle: &[Edge], amount_in: U256) -> Option<Vec<(U256, U256)>> {
+        let mut amount = amount_in;
+        let mut amounts = Vec::with_capacity(cycle.len());
+        for edge in cycle {
+            let out = self.pools[edge.pool].amount_out(edge.token_in, edge.token_out, amount)?;
+            amounts.push((amount, out));
+            amount = out;
+        }
+        Some(amounts)
+    }
+}
+
+/// Negative cycles in the rate graph, as edge indices in swap order
+///
+/// Bellman-Ford runs from a virtual source connected to every token, so cycles anywhere in
+/// the graph are found. Each reported cycle is distinct by its set of edges.
+fn negative_cycles(token_count: usize, edges: &[Edge], max_hops: usize) -> Vec<Vec<usize>> {
+    let mut distance = vec![0.0f64; token_count];
+    let mut predecessor: Vec<Option<usize>> = vec![None; token_count];
+    
+    for _ in 0..token_count.saturating_sub(1) {
+        let mut relaxed = false;
+        for (index, edge) in edges.iter().enumerate() {
+            if distance[edge.from] + edge.weight < distance[edge.to] - NEGATIVE_CYCLE_EPSILON {
+                distance[edge.to] = distance[edge.from] + edge.weight;
+                predecessor[edge.to] = Some(index);
+                relaxed = true;
+            }
+        }
+        if !relaxed {
+            return Vec::new();
+        }
+    }
+    
+    let mut cycles = Vec::new();
+    let mut seen = HashSet::new();
+    for edge in edges {
+        if distance[edge.from] + edge.weight >= distance[edge.to] - NEGATIVE_CYCLE_EPSILON {
+            continue;
+        }
+        
+        // Walking back once per token is guaranteed to land on the cycle
+        let mut node = edge.to;
+        for _ in 0..token_count {
+            match predecessor[node] {
+                Some(index) => node = edges[index].from,
+                None => break,
+            }
+        }
+        
+        let start = node;
+        let mut cycle = Vec::new();
+        loop {
+            let index = match predecessor[node] {
+                Some(index) => index,
+                None => break,
+            };
+            cycle.push(index);
+            node = edges[index].from;
+            if node == start || cycle.len() > token_count {
+                break;
+            }
+        }
+        if node != start || cycle.is_empty() || cycle.len() > max_hops {
+            continue;
+        }
+        cycle.reverse();
+        
+        let mut key = cycle.clone();
+        key.sort_unstable();
+        if seen.insert(key) {
+            cycles.push(cycle);
+        }
+    }
+    
+    cycles
+}
+
+/// Cross-DEX arbitrage engine over an in-memory graph of pool reserves
+///
+/// Every price update re-runs negative-cycle detection over the spot-rate graph; each cycle
+/// that can start from a base token is then sized against the pools' actual curves.
+#[derive(Clone)]
+pub struct ArbitrageEngine {
+    /// Whether pools are refreshed and cycles searched
+    enabled: bool,
+    /// Blockchain client, reads pool state
+    blockchain_client: Arc<BlockchainClient>,
+    /// Token prices, converting gas cost into a cycle's input token
+    prices: PriceService,
+    /// Balancer vault holding pool balances
+    balancer_vault: Address,
+    /// Uniswap V3 swap router V3 hops are sent through
+    uniswap_v3_router: Address,
+    /// Signer cycles are sent from, if they are sent at all
+    trader: Option<String>,
+    /// Tokens a cycle may start from
+    base_tokens: HashSet<Address>,
+    /// Longest cycle reported
+    max_hops: usize,
+    /// Largest input sized for
+    max_input: U256,
+    /// Minimum profit after gas reported
+    min_profit: U256,
+    /// Gas charged per swap
+    gas_per_hop: u64,
+    /// Tracked pools
+    graph: Arc<RwLock<PoolGraph>>,
+    /// Opportunities from the latest detection, most profitable first
+    opportunities: Arc<RwLock<Vec<ArbitrageOpportunity>>>,
+    /// Block each recently sent cycle was sent at, keyed by its pools in swap order
+    sent: Arc<BoundedCache<Vec<Address>, u64>>,
+}
+
+impl ArbitrageEngine {
+    /// Create a new arbitrage engine tracking the configured pools
+    pub fn new(
+        config: &ArbitrageConfig,
+        blockchain_client: Arc<BlockchainClient>,
+        prices: PriceService,
+    ) -> Result<Self> {
+        let mut graph = PoolGraph::default();
+        for pool in &config.pools {
+            graph.add_pool(Pool::from_config(pool)?);
+        }
+        let base_tokens = config
+            .base_tokens
+            .iter()
+            .map(|token| token.parse().with_context(|| format!("Invalid base token {}", token)))
+            .collect::<Result<HashSet<Address>>>()?;
+        
+        Ok(Self {
+            enabled: config.enabled,
+            blockchain_client,
+            prices,
+            balancer_vault: config.balancer_vault.parse().context("Invalid Balancer vault address")?,
+            uniswap_v3_router: config.uniswap_v3_router.parse().context("Invalid Uniswap V3 router address")?,
+            trader: config.trader.clone(),
+            base_tokens,
+            max_hops: config.max_hops,
+            max_input: U256::from_dec_str(&config.max_input).context("Invalid arbitrage max_input")?,
+            min_profit: U256::from_dec_str(&config.min_profit).context("Invalid arbitrage min_profit")?,
+            gas_per_hop: config.gas_per_hop,
+            graph: Arc::new(RwLock::new(graph)),
+            opportunities: Arc::new(RwLock::new(Vec::new())),
+            sent: Arc::new(BoundedCache::new(
+                "arbitrage_sends",
+                &CacheSettings {
+                    capacity: config.pools.len().max(1),
+                    policy: EvictionPolicy::Lru,
+                },
+            )),
+        })
+    }
+    
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+    
+    /// Signer cycles are sent from, if they are sent at all
+    pub fn trader(&self) -> Option<&str> {
+        self.trader.as_deref()
+    }
+    
+    /// Claim a found cycle for sending at a block
+    ///
+    /// False while one sent through the same pools in the last `RESEND_AFTER_BLOCKS` may still
+    /// land, as a cycle is found again on every block until its pools move.
+    pub fn claim_send(&self, opportunity: &ArbitrageOpportunity, block_number: u64) -> bool {
+        let key: Vec<Address> = opportunity.hops.iter().map(|hop| hop.pool).collect();
+        if let Some(sent) = self.sent.get(&key) {
+            if block_number < sent + RESEND_AFTER_BLOCKS {
+                return false;
+            }
+        }
+        self.sent.insert(key, block_number);
+        true
+    }
+    
+    /// Route executing a cycle from `trader`, one swap per hop
+    ///
+    /// Uniswap V2 hops transfer their input to the pair and swap against it directly; V3,
+    /// Curve and Balancer hops approve their input and swap through the V3 router, the pool or
+    /// the vault. Each hop's sized output is its minimum, so a cycle whose pools moved before
+    /// it landed reverts at the hop that moved rather than trading at a loss. Sent through gas
+    /// golf, approvals the allowance already covers are dropped.
+    pub fn route(&self, opportunity: &ArbitrageOpportunity, trader: Address) -> Result<Route> {
+        let graph = self.graph.read();
+        let mut txs = Vec::with_capacity(opportunity.hops.len() * 2);
+        for hop in &opportunity.hops {
+            let pool = graph
+                .by_address
+                .get(&hop.pool)
+                .map(|index| &graph.pools[*index])
+                .ok_or_else(|| anyhow!("Pool {:?} is not tracked", hop.pool))?;
+            let position = |token: Address| {
+                pool.tokens
+                    .iter()
+                    .position(|pool_token| *pool_token == token)
+                    .ok_or_else(|| anyhow!("Pool {:?} doesn't hold {:?}", pool.address, token))
+            };
+            let (i, j) = (position(hop.token_in)?, position(hop.token_out)?);
+            let input = |spender: Address, signature: &str| {
+                Eip1559TransactionRequest::new()
+                    .from(trader)
+                    .to(hop.token_in)
+                    .data(calldata(signature, &[Token::Address(spender), Token::Uint(hop.amount_in)]))
+                    .gas(TOKEN_GAS)
+            };
+            let swap = |to: Address, data: Bytes| {
+                Eip1559TransactionRequest::new()
+                    .from(trader)
+                    .to(to)
+                    .data(data)
+                    .gas(self.gas_per_hop)
+            };
+            
+            match pool.protocol {
+                Protocol::UniswapV2 => {
+                    let (amount0_out, amount1_out) = if j == 0 {
+                        (hop.amount_out, U256::zero())
+                    } else {
+                        (U256::zero(), hop.amount_out)
+                    };
+                    txs.push(input(pool.address, TRANSFER));
+                    txs.push(swap(
+                        pool.address,
+                        calldata(
+                            "swap(uint256,uint256,address,bytes)",
+                            &[
+                                Token::Uint(amount0_out),
+                                Token::Uint(amount1_out),
+                                Token::Address(trader),
+                                Token::Bytes(Vec::new()),
+                            ],
+                        ),
+                    ));
+                }
+                Protocol::UniswapV3 => {
+                    let fee = match &pool.state {
+                        Some(PoolState::UniswapV3 { fee, .. }) => *fee,
+                        _ => return Err(anyhow!("Pool {:?} has not been read", pool.address)),
+                    };
+                    txs.push(input(self.uniswap_v3_router, APPROVE));
+                    txs.push(swap(
+                        self.uniswap_v3_router,
+                        calldata(
+                            "exactInputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))",
+                            &[Token::Tuple(vec![
+                                Token::Address(hop.token_in),
+                                Token::Address(hop.token_out),
+                                Token::Uint(fee.into()),
+                                Token::Address(trader),
+                                Token::Uint(U256::MAX),
+                                Token::Uint(hop.amount_in),
+                                Token::Uint(hop.amount_out),
+                                Token::Uint(U256::zero()),
+                            ])],
+                        ),
+                    ));
+                }
+                Protocol::Curve => {
+                    txs.push(input(pool.address, APPROVE));
+                    txs.push(swap(
+                        pool.address,
+                        calldata(
+                            "exchange(int128,int128,uint256,uint256)",
+                            &[
+                                Token::Int(U256::from(i)),
+                                Token::Int(U256::from(j)),
+                                Token::Uint(hop.amount_in),
+                                Token::Uint(hop.amount_out),
+                            ],
+                        ),
+                    ));
+                }
+                Protocol::Balancer => {
+                    let pool_id = pool.pool_id.ok_or_else(|| anyhow!("Balancer pool without a pool id"))?;
+                    txs.push(input(self.balancer_vault, APPROVE));
+                    // A given-in single swap, paid from and to the trader's own balance
+                    txs.push(swap(
+                        self.balancer_vault,
+                        calldata(
+                            BALANCER_SWAP,
+                            &[
+                                Token::Tuple(vec![
+                                    Token::FixedBytes(pool_id.as_bytes().to_vec()),
+                                    Token::Uint(U256::zero()),
+                                    Token::Address(hop.token_in),
+                                    Token::Address(hop.token_out),
+                                    Token::Uint(hop.amount_in),
+                                    Token::Bytes(Vec::new()),
+                                ]),
+                                Token::Tuple(vec![
+                                    Token::Address(trader),
+                                    Token::Bool(false),
+                                    Token::Address(trader),
+                                    Token::Bool(false),
+                                ]),
+                                Token::Uint(hop.amount_out),
+                                Token::Uint(U256::MAX),
+                            ],
+                        ),
+                    ));
+                }
+            }
+        }
+        
+        Ok(Route {
+            name: "arbitrage".to_string(),
+            txs,
+        })
+    }
+    
+    /// Opportunities from the latest detection, most profitable first
+    pub fn opportunities(&self) -> Vec<ArbitrageOpportunity> {
+        self.opportunities.read().clone()
+    }
+    
+    /// Latest state of every pool read so far
+    pub fn pool_states(&self) -> Vec<(Address, PoolState)> {
+        self.graph
+            .read()
+            .pools
+            .iter()
+            .filter_map(|pool| pool.state.clone().map(|state| (pool.address, state)))
+            .collect()
+    }
+    
+    /// Seed pools not yet read with saved states, returning how many were restored
+    ///
+    /// States of pools no longer tracked, or tracked as a different design, are skipped. The
+    /// next refresh replaces every restored state with one read from chain.
+    pub fn restore_pool_states(&self, states: Vec<(Address, PoolState)>) -> usize {
+        let mut graph = self.graph.write();
+        let mut restored = 0;
+        for (address, state) in states {
+            let index = match graph.by_address.get(&address) {
+                Some(index) => *index,
+                None => continue,
+            };
+            let pool = &mut graph.pools[index];
+            if pool.state.is_none() && pool.accepts(&state) {
+                pool.state = Some(state);
+                restored += 1;
+            }
+        }
+        restored
+    }
+    
+    /// Apply price updates for tracked pools, in order, and search for new cycles
+    pub async fn update_pool(&self, updates: Vec<(Address, PoolState)>) -> Result<Vec<ArbitrageOpportunity>> {
+        {
+            let mut graph = self.graph.write();
+            for (address, state) in updates {
+                let index = *graph
+                    .by_address
+                    .get(&address)
+                    .ok_or_else(|| anyhow!("Pool {:?} is not tracked", address))?;
+                graph.pools[index].state = Some(state);
+            }
+        }
+        
+        self.publish(self.detect()).await
+    }
+    
+    /// Follow the pools through a block's logs and search for cycles
+    ///
+    /// Syncs and swaps of Uniswap pools are applied through `update_pool`. Any other move of a
+    /// tracked pool, such as a Curve exchange, a Balancer vault swap or a V3 mint, re-reads
+    /// every pool, as do logs that can't be fetched and a first block before all were read.
+    pub async fn on_block(&self, block_hash: Option<H256>) -> Result<Vec<ArbitrageOpportunity>> {
+        if !self.enabled {
+            return Ok(Vec::new());
+        }
+        let tracked = {
+            let graph = self.graph.read();
+            graph.pools.iter().all(|pool| pool.state.is_some()).then(|| {
+                let addresses: Vec<Address> = graph.pools.iter().map(|pool| pool.address).collect();
+                let pool_ids: HashSet<H256> = graph.pools.iter().filter_map(|pool| pool.pool_id).collect();
+                (addresses, pool_ids)
+            })
+        };
+        let (Some(block_hash), Some((mut addresses, pool_ids))) = (block_hash, tracked) else {
+            return self.refresh().await;
+        };
+        if !pool_ids.is_empty() {
+            addresses.push(self.balancer_vault);
+        }
+        
+        let filter = Filter::new().at_block_hash(block_hash).address(ValueOrArray::Array(addresses));
+        let logs = match self.blockchain_client.get_logs(&filter).await {
+            Ok(logs) => logs,
+            Err(e) => {
+                debug!("Re-reading pools, logs of block {:?} unavailable: {}", block_hash, e);
+                return self.refresh().await;
+            }
+        };
+        
+        let mut updates = Vec::new();
+        let mut moved = false;
+        {
+            let graph = self.graph.read();
+            for log in &logs {
+                let read = match graph.by_address.get(&log.address) {
+                    Some(index) => graph.pools[*index].read_log(log),
+                    // Vault logs name the pool they moved as their first indexed argument
+                    None if log.topics.get(1).map_or(false, |pool_id| pool_ids.contains(pool_id)) => PoolLog::Moved,
+                    None => PoolLog::Unchanged,
+                };
+                match read {
+                    PoolLog::State(state) => updates.push((log.address, state)),
+                    PoolLog::Unchanged => {}
+                    PoolLog::Moved => {
+                        moved = true;
+                        break;
+                    }
+                }
+            }
+        }
+        
+        if moved {
+            return self.refresh().await;
+        }
+        if updates.is_empty() {
+            return Ok(self.opportunities());
+        }
+        self.update_pool(updates).await
+    }
+    
+    /// Re-read every tracked pool from chain and search for cycles
+    pub async fn refresh(&self) -> Result<Vec<ArbitrageOpportunity>> {
+        if !self.enabled {
+            return Ok(Vec::new());
+        }
+        
+        let pools: Vec<Pool> = self.graph.read().pools.clone();
+        let reads: Vec<Result<Vec<(Address, Bytes)>>> = pools.iter().map(|pool| self.state_reads(pool)).collect();
+        let calls: Vec<(Address, Bytes)> = reads.iter().flatten().flatten().cloned().collect();
+        let mut outputs = self.blockchain_client.multicall(&calls).await?.into_iter();
+        
+        // Outputs come back flattened in the order of the reads, one slice per pool
+        let states: Vec<Result<PoolState>> = pools
+            .iter()
+            .zip(reads)
+            .map(|(pool, reads)| {
+                let reads = reads?;
+                let pool_outputs: Vec<Option<Bytes>> = outputs.by_ref().take(reads.len()).collect();
+                let pool_outputs = pool_outputs
+                    .into_iter()
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| anyhow!("A state read reverted"))?;
+                self.decode_state(pool, &pool_outputs)
+            })
+            .collect();
+        
+        {
+            let mut graph = self.graph.write();
+            for (pool, state) in pools.iter().zip(states) {
+                match state {
+                    Ok(state) => {
+                        let index = graph.by_address[&pool.address];
+                        graph.pools[index].state = Some(state);
+                    }
+                    Err(e) => warn!("Failed to refresh pool {:?}: {}", pool.address, e),
+                }
+            }
+        }
+        
+        self.publish(self.detect()).await
+    }
+    
+    /// Keep the cycles still worth at least `min_profit` once gas is paid, most profitable first
+    async fn publish(&self, sized: Vec<ArbitrageOpportunity>) -> Result<Vec<ArbitrageOpportunity>> {
+        let gas_price = self.blockchain_client.get_cached_gas_price().await?;
+        
+        let mut opportunities = Vec::with_capacity(sized.len());
+        for mut opportunity in sized {
+            let gas_cost = match self.gas_cost_in(&opportunity, gas_price).await {
+                Ok(gas_cost) => gas_cost,
+                Err(e) => {
+                    debug!("Dropping arbitrage cycle from {:?}, gas unpriced: {}", opportunity.input_token, e);
+                    continue;
+                }
+            };
+            opportunity.net_profit = opportunity.profit.saturating_sub(gas_cost);
+            if !opportunity.net_profit.is_zero() && opportunity.net_profit >= self.min_profit {
+                opportunities.push(opportunity);
+            }
+        }
+        opportunities.sort_by(|a, b| b.net_profit.cmp(&a.net_profit));
+        
+        metrics::counter!("arbitrage_opportunities_total", opportunities.len() as u64);
+        if !opportunities.is_empty() {
+            debug!("Found {} arbitrage cycles profitable after gas", opportunities.len());
+        }
+        
+        *self.opportunities.write() = opportunities.clone();
+        Ok(opportunities)
+    }
+    
+    /// Gas cost of a cycle in its input token, converted at the input's value in wei
+    async fn gas_cost_in(&self, opportunity: &ArbitrageOpportunity, gas_price: U256) -> Result<U256> {
+        let gas_cost_wei = gas_price.saturating_mul(U256::from(opportunity.gas_estimate));
+        let input_wei = self.prices.value_wei(opportunity.input_token, opportunity.amount_in).await?;
+        if input_wei.is_zero() {
+            return Err(anyhow!("Input token {:?} is priced at zero", opportunity.input_token));
+        }
+        
+        let gas_cost = gas_cost_wei.full_mul(opportunity.amount_in) / U512::from(input_wei);
+        Ok(U256::try_from(gas_cost).unwrap_or(U256::MAX))
+    }
+    
+    /// Search the current graph for cycles and size each one, before gas
+    fn detect(&self) -> Vec<ArbitrageOpportunity> {
+        let graph = self.graph.read();
+        let edges = graph.edges();
+        let cycles = negative_cycles(graph.tokens.len(), &edges, self.max_hops);
+        
+        let mut opportunities: Vec<ArbitrageOpportunity> = cycles
+            .into_iter()
+            .filter_map(|cycle| {
+                let mut cycle: Vec<Edge> = cycle.into_iter().map(|index| edges[index]).collect();
+                
+                // Start from a base token so the input can be funded
+                let start = cycle
+                    .iter()
+                    .position(|edge| self.base_tokens.contains(&graph.tokens[edge.from]))?;
+                cycle.rotate_left(start);
+                
+                self.size(&graph, &cycle)
+            })
+            .collect();
+        opportunities.sort_by(|a, b| b.profit.cmp(&a.profit));
+        
+        metrics::gauge!("arbitrage_pools_tracked", graph.pools.len() as f64);
+        opportunities
+    }
+    
+    /// Find the input maximising output minus input
+    ///
+    /// Every hop's output is concave in its input, so profit along the cycle is unimodal and a
+    /// ternary search converges on the optimum.
+    fn size(&self, graph: &PoolGraph, cycle: &[Edge]) -> Option<ArbitrageOpportunity> {
+        let output = |amount: U256| graph.simulate(cycle, amount).and_then(|hops| hops.last().map(|hop| hop.1));
+        // a.out - a.in > b.out - b.in, without going negative
+        let better = |a: (U256, U256), b: (U256, U256)| a.1 + b.0 > b.1 + a.0;
+        
+        let mut lower = U256::zero();
+        let mut upper = self.max_input;
+        while upper - lower > U256::from(2) {
+            let third = (upper - lower) / 3;
+            let left = lower + third;
+            let right = upper - third;
+            match (output(left), output(right)) {
+                (Some(left_out), Some(right_out)) => {
+                    if better((left, left_out), (right, right_out)) {
+                        upper = right;
+                    } else {
+                        lower = left;
+                    }
+                }
+                // Sizes the pools cannot absorb bound the search from above
+                (Some(_), None) => upper = right,
+                (None, _) => upper = left,
+            }
+        }
+        
+        let amount_in = [lower, lower + 1, upper]
+            .into_iter()
+            .filter_map(|amount| output(amount).map(|out| (amount, out)))
+            .reduce(|best, candidate| if better(candidate, best) { candidate } else { best })
+            .filter(|(amount, out)| !amount.is_zero() && out > amount)?
+            .0;
+        
+        let amounts = graph.simulate(cycle, amount_in)?;
+        let amount_out = amounts.last()?.1;
+        let hops = cycle
+            .iter()
+            .zip(&amounts)
+            .map(|(edge, (hop_in, hop_out))| {
+                let pool = &graph.pools[edge.pool];
+                ArbitrageHop {
+                    pool: pool.address,
+                    protocol: pool.protocol,
+                    token_in: graph.tokens[edge.from],
+                    token_out: graph.tokens[edge.to],
+                    amount_in: *hop_in,
+                    amount_out: *hop_out,
+                }
+            })
+            .collect();
+        
+        Some(ArbitrageOpportunity {
+            input_token: graph.tokens[cycle[0].from],
+            amount_in,
+            amount_out,
+            profit: amount_out - amount_in,
+            net_profit: U256::zero(),
+            hops,
+            gas_estimate: self.gas_per_hop * cycle.len() as u64,
+            detected_at: Utc::now(),
+        })
+    }
+    
+    /// Calls reading a pool's reserves and fee, decoded in the same order by `decode_state`
+    fn state_reads(&self, pool: &Pool) -> Result<Vec<(Address, Bytes)>> {
+        let read = |signature: &str, args: &[Token]| (pool.address, calldata(signature, args));
+        let reads = match pool.protocol {
+            Protocol::UniswapV2 => vec![read("getReserves()", &[])],
+            Protocol::UniswapV3 => vec![read("slot0()", &[]), read("liquidity()", &[]), read("fee()", &[])],
+            Protocol::Curve => {
+                let mut reads: Vec<_> = (0..pool.precisions.len())
+                    .map(|i| read("balances(uint256)", &[Token::Uint(U256::from(i))]))
+                    .collect();
+                reads.push(read("A()", &[]));
+                reads.push(read("fee()", &[]));
+                reads
+            }
+            Protocol::Balancer => {
+                let pool_id = pool.pool_id.ok_or_else(|| anyhow!("Balancer pool without a pool id"))?;
+                let pool_id = Token::FixedBytes(pool_id.as_bytes().to_vec());
+                let pool_tokens = calldata("getPoolTokens(bytes32)", &[pool_id]);
+                vec![
+                    (self.balancer_vault, pool_tokens),
+                    read("getNormalizedWeights()", &[]),
+                    read("getSwapFeePercentage()", &[]),
+                ]
+            }
+        };
+        
+        Ok(reads)
+    }
+    
+    /// A pool's state from the outputs of its `state_reads`
+    fn decode_state(&self, pool: &Pool, outputs: &[Bytes]) -> Result<PoolState> {
+        match pool.protocol {
+            Protocol::UniswapV2 => {
+                let [output] = outputs else {
+                    return Err(anyhow!("Expected 1 reserves read, got {}", outputs.len()));
+                };
+                let tokens = decode(&[ParamType::Uint(112), ParamType::Uint(112), ParamType::Uint(32)], output)?;
+                Ok(PoolState::UniswapV2 {
+                    reserves: [uint(&tokens[0]), uint(&tokens[1])],
+                    fee: pool.fee,
+                })
+            }
+            Protocol::UniswapV3 => {
+                let [slot0, liquidity, fee] = outputs else {
+                    return Err(anyhow!("Expected 3 Uniswap v3 reads, got {}", outputs.len()));
+                };
+                Ok(PoolState::UniswapV3 {
+                    sqrt_price_x96: first_word("slot0()", slot0)?,
+                    liquidity: first_word("liquidity()", liquidity)?,
+                    fee: first_word("fee()", fee)?.low_u32(),
+                })
+            }
+            Protocol::Curve => {
+                let [balances @ .., amplification, fee] = outputs else {
+                    return Err(anyhow!("Expected Curve balance, A and fee reads, got {}", outputs.len()));
+                };
+                let balances = balances
+                    .iter()
+                    .zip(&pool.precisions)
+                    .map(|(balance, precision)| {
+                        Ok(first_word("balances(uint256)", balance)?.saturating_mul(*precision))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(PoolState::Curve {
+                    balances,
+                    amplification: first_word("A()", amplification)?,
+                    fee: (first_word("fee()", fee)? / CURVE_FEE_SCALE).low_u32(),
+                })
+            }
+            Protocol::Balancer => {
+                let [pool_tokens, weights, fee] = outputs else {
+                    return Err(anyhow!("Expected 3 Balancer reads, got {}", outputs.len()));
+                };
+                let tokens = decode(
+                    &[
+                        ParamType::Array(Box::new(ParamType::Address)),
+                        ParamType::Array(Box::new(ParamType::Uint(256))),
+                        ParamType::Uint(256),
+                    ],
+                    pool_tokens,
+                )?;
+                let vault_tokens: Vec<Address> = array(&tokens[0]).iter().filter_map(|t| t.clone().into_address()).collect();
+                let vault_balances: Vec<U256> = array(&tokens[1]).iter().map(uint).collect();
+                
+                let weights = decode(&[ParamType::Array(Box::new(ParamType::Uint(256)))], weights)?;
+                let vault_weights: Vec<f64> = array(&weights[0]).iter().map(|w| to_f64(uint(w)) / 1e18).collect();
+                
+                let fee = first_word("getSwapFeePercentage()", fee)?;
+                
+                // Order balances and weights like the configured tokens
+                let mut balances = Vec::with_capacity(pool.tokens.len());
+                let mut weights = Vec::with_capacity(pool.tokens.len());
+                for token in &pool.tokens {
+                    let position = vault_tokens
+                        .iter()
+                        .position(|vault_token| vault_token == token)
+                        .ok_or_else(|| anyhow!("Token {:?} is not in Balancer pool {:?}", token, pool.address))?;
+                    balances.push(*vault_balances.get(position).context("Missing Balancer balance")?);
+                    weights.push(*vault_weights.get(position).context("Missing Balancer weight")?);
+                }
+                
+                Ok(PoolState::Balancer {
+                    balances,
+                    weights,
+                    fee: (fee / BALANCER_FEE_SCALE).min(U256::from(FEE_DENOMINATOR)).low_u32(),
+                })
+            }
+        }
+    }
+}
+
+fn decode(types: &[ParamType], data: &[u8]) -> Result<Vec<Token>> {
+    abi::decode(types, data).map_err(|e| anyhow!("Failed to decode pool state: {}", e))
+}
+
+fn uint(token: &Token) -> U256 {
+    token.clone().into_uint().unwrap_or_default()
+}
+
+fn array(token: &Token) -> Vec<Token> {
+    token.clone().into_array().unwrap_or_default()
+}
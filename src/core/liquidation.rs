@@ -0,0 +1,1133 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use ethers::{
+    abi::{self, ParamType, Token},
+    types::{Address, Bytes, Eip1559TransactionRequest, Filter, Log, H256, U256},
+    utils::keccak256,
+};
+use futures::future::join_all;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use tracing::{debug, info};
+
+use crate::{
+    blockchain::{
+        client::{calldata, first_word},
+        BlockchainClient,
+    },
+    config::{CacheSettings, LiquidationConfig},
+    core::amm::to_f64,
+    services::{gas_golf::Route, prices::PriceService},
+    utils::cache::{BoundedCache, EvictionPolicy},
+};
+
+/// `Borrow(address indexed reserve, address user, address indexed onBehalfOf, ...)` on the Aave v3 pool
+const AAVE_BORROW_EVENT: &str = "Borrow(address,address,address,uint256,uint8,uint256,uint16)";
+
+/// `Withdraw(address indexed src, address indexed to, uint256 amount)`, emitted by Comet on borrows
+const COMET_WITHDRAW_EVENT: &str = "Withdraw(address,address,uint256)";
+
+/// `AnswerUpdated(int256 indexed current, uint256 indexed roundId, uint256 updatedAt)` on Chainlink aggregators
+const ANSWER_UPDATED_EVENT: &str = "AnswerUpdated(int256,uint256,uint256)";
+
+/// Blocks of lending logs fetched per backfill run
+const BACKFILL_RANGE_BLOCKS: u64 = 2_000;
+
+/// Health factors are reported with 18 decimals
+const HEALTH_FACTOR_SCALE: f64 = 1e18;
+
+/// Aave liquidates half the debt above this health factor and all of it below
+const CLOSE_FACTOR_HF_THRESHOLD: f64 = 0.95;
+
+/// Aave bonuses and protocol fees are in basis points
+const BPS: u64 = 10_000;
+
+/// Comet collateral factors have 18 decimals
+const COMET_FACTOR_SCALE: f64 = 1e18;
+
+/// Comet and Chainlink USD prices have 8 decimals
+const USD_PRICE_SCALE: u64 = 100_000_000;
+
+/// Collateral bought back from Comet may come in this much below the quote
+const BUY_COLLATERAL_SLIPPAGE_BPS: u64 = 100;
+
+/// Gas limit of an ERC-20 approval, writing a fresh allowance slot
+const APPROVE_GAS: u64 = 60_000;
+
+/// Blocks a sent liquidation gets to land before the position is liquidated again
+const RESEND_AFTER_BLOCKS: u64 = 25;
+
+/// Lending protocol a position is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LendingProtocol {
+    AaveV3,
+    CompoundV3,
+}
+
+/// A tracked borrower and its latest health
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackedPosition {
+    pub protocol: LendingProtocol,
+    /// Aave pool or Comet market
+    pub market: Address,
+    pub account: Address,
+    pub debt_usd: f64,
+    /// Liquidatable below 1.0
+    pub health_factor: f64,
+    /// Block the health was last read at
+    pub checked_block: u64,
+}
+
+/// An underwater position and the liquidation we would send for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidationOpportunity {
+    pub protocol: LendingProtocol,
+    pub market: Address,
+    pub account: Address,
+    pub health_factor: f64,
+    /// Asset repaid, the base token for Comet
+    pub debt_asset: Address,
+    /// Debt repaid on Aave, base paid for the collateral on Comet
+    pub debt_to_cover: U256,
+    pub collateral_asset: Address,
+    /// Collateral we expect to receive
+    pub collateral_amount: U256,
+    /// Value of the collateral received minus what we repay and gas, in wei
+    pub expected_profit: U256,
+    pub gas_estimate: u64,
+    pub detected_at: DateTime<Utc>,
+}
+
+impl LiquidationOpportunity {
+    /// Transactions executing this liquidation from `liquidator`, in order
+    ///
+    /// The liquidator must hold and have approved the debt asset. Comet absorbs the account
+    /// first and then sells us its collateral at the store front discount.
+    pub fn transactions(&self, liquidator: Address) -> Vec<Eip1559TransactionRequest> {
+        let call = |signature: &str, args: &[Token]| {
+            Eip1559TransactionRequest::new()
+                .from(liquidator)
+                .to(self.market)
+                .data(calldata(signature, args))
+                .gas(self.gas_estimate)
+        };
+        
+        match self.protocol {
+            LendingProtocol::AaveV3 => vec![call(
+                "liquidationCall(address,address,address,uint256,bool)",
+                &[
+                    Token::Address(self.collateral_asset),
+                    Token::Address(self.debt_asset),
+                    Token::Address(self.account),
+                    Token::Uint(self.debt_to_cover),
+                    Token::Bool(false),
+                ],
+            )],
+            LendingProtocol::CompoundV3 => {
+                let min_amount = self.collateral_amount * (BPS - BUY_COLLATERAL_SLIPPAGE_BPS) / BPS;
+                vec![
+                    call(
+                        "absorb(address,address[])",
+                        &[Token::Address(liquidator), Token::Array(vec![Token::Address(self.account)])],
+                    ),
+                    call(
+                        "buyCollateral(address,uint256,uint256,address)",
+                        &[
+                            Token::Address(self.collateral_asset),
+                            Token::Uint(min_amount),
+                            Token::Uint(self.debt_to_cover),
+                            Token::Address(liquidator),
+                        ],
+                    ),
+                ]
+            }
+        }
+    }
+    
+    /// Route executing this liquidation, approving the debt asset to the market first
+    ///
+    /// Sent through gas golf, the approval is dropped when the allowance is already in place.
+    pub fn route(&self, liquidator: Address) -> Route {
+        let approve = Eip1559TransactionRequest::new()
+            .from(liquidator)
+            .to(self.debt_asset)
+            .data(calldata(
+                "approve(address,uint256)",
+                &[Token::Address(self.market), Token::Uint(self.debt_to_cover)],
+            ))
+            .gas(APPROVE_GAS);
+        
+        let mut txs = vec![approve];
+        txs.extend(self.transactions(liquidator));
+        Route {
+            name: match self.protocol {
+                LendingProtocol::AaveV3 => "aave_v3",
+                LendingProtocol::CompoundV3 => "compound_v3",
+            }
+            .to_string(),
+            txs,
+        }
+    }
+}
+
+/// Parameters of one Aave reserve
+#[derive(Debug, Clone)]
+struct AaveReserve {
+    asset: Address,
+    decimals: usize,
+    /// Collateral received per unit of debt, in basis points above 10000
+    liquidation_bonus: U256,
+    /// Share of the bonus kept by the protocol, in basis points
+    protocol_fee: U256,
+}
+
+/// One collateral asset of a Comet market
+#[derive(Debug, Clone)]
+struct CometAsset {
+    asset: Address,
+    price_feed: Address,
+    scale: U256,
+    liquidate_collateral_factor: f64,
+}
+
+/// Parameters of one Comet market
+#[derive(Debug, Clone)]
+struct CometMarket {
+    address: Address,
+    base_token: Address,
+    base_scale: U256,
+    base_price_feed: Address,
+    assets: Vec<CometAsset>,
+}
+
+/// Market parameters, read from chain once
+#[derive(Debug, Default)]
+struct Markets {
+    aave_reserves: Vec<AaveReserve>,
+    /// Aave base currency unit, 1e8 for USD
+    aave_base_unit: U256,
+    comets: Vec<CometMarket>,
+}
+
+/// Prices read for one refresh
+struct PriceSnapshot {
+    /// Aave oracle prices in the base currency
+    aave: HashMap<Address, U256>,
+    /// Comet feed prices in USD with 8 decimals
+    comet: HashMap<Address, U256>,
+    /// ETH/USD with 8 decimals
+    eth_usd: U256,
+    gas_price: U256,
+}
+
+/// Watches large Aave v3 and Compound v3 borrowers for liquidation
+///
+/// Borrowers are picked up from borrow events as blocks arrive, and from those of a window
+/// before startup by a backfill, and dropped once their debt is below the tracking threshold.
+/// Every tracked position is re-checked when one of the watched Chainlink aggregators reports a
+/// new answer and every refresh interval otherwise; positions whose health factor fell below one
+/// are sized into liquidations.
+#[derive(Clone)]
+pub struct LiquidationMonitor {
+    /// Whether positions are tracked at all
+    enabled: bool,
+    /// Blockchain client, reads positions and prices
+    blockchain_client: Arc<BlockchainClient>,
+    /// Aave v3 pool, None to skip Aave
+    aave_pool: Option<Address>,
+    aave_data_provider: Address,
+    aave_oracle: Address,
+    /// Compound v3 markets
+    comets: Vec<Address>,
+    /// Prices profit in wei when enabled
+    prices: PriceService,
+    /// Chainlink ETH/USD feed pricing profit in wei otherwise
+    eth_usd_feed: Address,
+    /// Aggregators whose answers move collateral prices
+    price_aggregators: HashSet<Address>,
+    /// Accounts tracked whatever their size
+    watch_accounts: HashSet<Address>,
+    /// Positions with less debt are not tracked
+    min_debt_usd: f64,
+    max_positions: usize,
+    refresh_interval_blocks: u64,
+    gas_per_liquidation: u64,
+    min_profit: U256,
+    /// Market parameters, loaded on first use
+    markets: Arc<tokio::sync::OnceCell<Markets>>,
+    /// Tracked positions by market and account
+    positions: Arc<RwLock<HashMap<(Address, Address), TrackedPosition>>>,
+    /// Open liquidations by market and account, most profitable first when listed
+    opportunities: Arc<RwLock<HashMap<(Address, Address), LiquidationOpportunity>>>,
+    /// Set while a block's positions are being checked
+    running: Arc<AtomicBool>,
+    /// Blocks before startup whose borrowers are backfilled
+    backfill_blocks: u64,
+    /// Next block range end to backfill and the block backfilling stops at, from the first run
+    backfill_cursor: Arc<Mutex<Option<(u64, u64)>>>,
+    /// Signer liquidations are sent from, None to only report them
+    liquidator: Option<String>,
+    /// Block each position was last sent for liquidation at, by market and account
+    sent: Arc<BoundedCache<(Address, Address), u64>>,
+}
+
+/// Marks a block check as running until dropped, also when its caller gives up on it
+struct Running<'a>(&'a AtomicBool);
+
+impl<'a> Running<'a> {
+    fn try_start(running: &'a AtomicBool) -> Option<Self> {
+        running
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+            .map(|_| Self(running))
+    }
+}
+
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+impl LiquidationMonitor {
+    /// Create a new liquidation monitor
+    pub fn new(
+        config: &LiquidationConfig,
+        blockchain_client: Arc<BlockchainClient>,
+        prices: PriceService,
+    ) -> Result<Self> {
+        let address = |value: &str, name: &str| -> Result<Address> {
+            value.parse().with_context(|| format!("Invalid liquidation {} {}", name, value))
+        };
+        let addresses = |values: &[String], name: &str| -> Result<Vec<Address>> {
+            values.iter().map(|value| address(value, name)).collect()
+        };
+        
+        let watch_accounts = config
+            .watch_accounts
+            .iter()
+            .map(|account| address(account, "watch account"))
+            .collect::<Result<HashSet<_>>>()?;
+        
+        Ok(Self {
+            enabled: config.enabled,
+            blockchain_client,
+            aave_pool: config.aave_pool.as_deref().map(|pool| address(pool, "aave_pool")).transpose()?,
+            aave_data_provider: address(&config.aave_data_provider, "aave_data_provider")?,
+            aave_oracle: address(&config.aave_oracle, "aave_oracle")?,
+            comets: addresses(&config.comets, "comet")?,
+            prices,
+            eth_usd_feed: address(&config.eth_usd_feed, "eth_usd_feed")?,
+            price_aggregators: addresses(&config.price_aggregators, "price aggregator")?.into_iter().collect(),
+            watch_accounts,
+            min_debt_usd: config.min_debt_usd,
+            max_positions: config.max_positions,
+            refresh_interval_blocks: config.refresh_interval_blocks.max(1),
+            gas_per_liquidation: config.gas_per_liquidation,
+            min_profit: U256::from_dec_str(&config.min_profit_wei).context("Invalid liquidation min_profit_wei")?,
+            markets: Arc::new(tokio::sync::OnceCell::new()),
+            positions: Arc::new(RwLock::new(HashMap::new())),
+            opportunities: Arc::new(RwLock::new(HashMap::new())),
+            running: Arc::new(AtomicBool::new(false)),
+            backfill_blocks: config.backfill_blocks,
+            backfill_cursor: Arc::new(Mutex::new(None)),
+            liquidator: config.liquidator.clone(),
+            sent: Arc::new(BoundedCache::new(
+                "liquidation_sends",
+                &CacheSettings {
+                    capacity: config.max_positions.max(1),
+                    policy: EvictionPolicy::Lru,
+                },
+            )),
+        })
+    }
+    
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+    
+    /// Signer liquidations are sent from, if they are sent at all
+    pub fn liquidator(&self) -> Option<&str> {
+        self.liquidator.as_deref()
+    }
+    
+    /// Claim a found liquidation for sending at a block
+    ///
+    /// False while one sent for the same position in the last `RESEND_AFTER_BLOCKS` may still
+    /// land, as positions are found again on every re-check until they are liquidated.
+    pub fn claim_send(&self, opportunity: &LiquidationOpportunity, block_number: u64) -> bool {
+        let key = (opportunity.market, opportunity.account);
+        if let Some(sent) = self.sent.get(&key) {
+            if block_number < sent + RESEND_AFTER_BLOCKS {
+                return false;
+            }
+        }
+        self.sent.insert(key, block_number);
+        true
+    }
+    
+    /// Tracked positions, least healthy first
+    pub fn positions(&self) -> Vec<TrackedPosition> {
+        let mut positions: Vec<_> = self.positions.read().values().cloned().collect();
+        positions.sort_by(|a, b| {
+            a.health_factor
+                .partial_cmp(&b.health_factor)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        positions
+    }
+    
+    /// Track positions saved by a previous process, returning how many were restored
+    ///
+    /// Positions in markets no longer configured are skipped, as are any beyond the tracking
+    /// limit; health is re-read on the next refresh.
+    pub fn restore_positions(&self, saved: Vec<TrackedPosition>) -> usize {
+        if !self.enabled {
+            return 0;
+        }
+        
+        let mut positions = self.positions.write();
+        let mut restored = 0;
+        for position in saved {
+            let configured = match position.protocol {
+                LendingProtocol::AaveV3 => self.aave_pool == Some(position.market),
+                LendingProtocol::CompoundV3 => self.comets.contains(&position.market),
+            };
+            if !configured || positions.len() >= self.max_positions {
+                continue;
+            }
+            
+            let key = (position.market, position.account);
+            if !positions.contains_key(&key) {
+                positions.insert(key, position);
+                restored += 1;
+            }
+        }
+        restored
+    }
+    
+    /// Open liquidations, most profitable first
+    pub fn opportunities(&self) -> Vec<LiquidationOpportunity> {
+        let mut opportunities: Vec<_> = self.opportunities.read().values().cloned().collect();
+        opportunities.sort_by(|a, b| b.expected_profit.cmp(&a.expected_profit));
+        opportunities
+    }
+    
+    /// Share of a lending market's supply that is borrowed, for a Comet market or an Aave reserve asset
+    pub async fn utilization(&self, market: Address) -> Result<f64> {
+        if self.comets.contains(&market) {
+            let utilization = self.blockchain_client.read_uint(market, "getUtilization()", &[]).await?;
+            return Ok(to_f64(utilization) / COMET_FACTOR_SCALE);
+        }
+        
+        // unbacked, accruedToTreasuryScaled, totalAToken, totalStableDebt, totalVariableDebt, ...
+        let output = self
+            .blockchain_client
+            .call_function(self.aave_data_provider, "getReserveData(address)", &[Token::Address(market)])
+            .await?;
+        let reserve = decode(&vec![ParamType::Uint(256); 5], &output)?;
+        let supplied = to_f64(uint(&reserve[2]));
+        if supplied == 0.0 {
+            return Ok(0.0);
+        }
+        
+        Ok(to_f64(uint(&reserve[3]).saturating_add(uint(&reserve[4]))) / supplied)
+    }
+    
+    /// Pick up new borrowers from a block and re-check positions when prices moved
+    ///
+    /// A block arriving while the previous one is still being checked is skipped, the next
+    /// refresh catches up on its positions.
+    pub async fn on_block(&self, block_number: u64) -> Result<Vec<LiquidationOpportunity>> {
+        if !self.enabled {
+            return Ok(Vec::new());
+        }
+        let _running = match Running::try_start(&self.running) {
+            Some(running) => running,
+            None => {
+                debug!("Still checking positions, skipping block {}", block_number);
+                metrics::counter!("liquidation_blocks_skipped_total", 1);
+                return Ok(Vec::new());
+            }
+        };
+        
+        let markets = self.markets().await?;
+        let logs = self.block_logs(block_number).await?;
+        
+        let answer_updated = H256::from(keccak256(ANSWER_UPDATED_EVENT));
+        
+        let mut oracle_updated = false;
+        let mut borrowers = HashSet::new();
+        for log in &logs {
+            let topic = log.topics.first().copied().unwrap_or_default();
+            if topic == answer_updated && self.price_aggregators.contains(&log.address) {
+                oracle_updated = true;
+            } else if let Some(borrower) = self.borrower(log) {
+                borrowers.insert(borrower);
+            }
+        }
+        
+        let mut watched: Vec<(LendingProtocol, Address, Address)> = Vec::new();
+        for account in &self.watch_accounts {
+            watched.extend(self.aave_pool.map(|pool| (LendingProtocol::AaveV3, pool, *account)));
+            watched.extend(self.comets.iter().map(|comet| (LendingProtocol::CompoundV3, *comet, *account)));
+        }
+        
+        let due = oracle_updated || block_number % self.refresh_interval_blocks == 0;
+        let mut checks: Vec<(LendingProtocol, Address, Address)> = if due {
+            self.positions
+                .read()
+                .values()
+                .map(|position| (position.protocol, position.market, position.account))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        {
+            let positions = self.positions.read();
+            checks.extend(
+                borrowers
+                    .into_iter()
+                    .chain(watched)
+                    .filter(|(_, market, account)| !positions.contains_key(&(*market, *account))),
+            );
+        }
+        
+        if oracle_updated && !checks.is_empty() {
+            debug!("Oracle update in block {}, re-checking {} positions", block_number, checks.len());
+        }
+        
+        self.check_positions(markets, block_number, checks).await
+    }
+    
+    /// Track the borrowers of the `backfill_blocks` before startup, one log range per call
+    ///
+    /// Borrowers otherwise only show up with their next borrow. Walks back from the head seen
+    /// on the first call and does nothing once the window is covered.
+    pub async fn backfill(&self) -> Result<()> {
+        if !self.enabled || self.backfill_blocks == 0 {
+            return Ok(());
+        }
+        
+        let cursor = *self.backfill_cursor.lock();
+        if matches!(cursor, Some((end, stop)) if end < stop || end == 0) {
+            return Ok(());
+        }
+        let head = self.blockchain_client.get_block_number().await?;
+        let (end, stop) = cursor.unwrap_or((head, head.saturating_sub(self.backfill_blocks)));
+        let start = stop.max(end.saturating_sub(BACKFILL_RANGE_BLOCKS - 1));
+        
+        let markets = self.markets().await?;
+        let addresses: Vec<Address> = self.aave_pool.into_iter().chain(self.comets.iter().copied()).collect();
+        let topics: Vec<H256> = [AAVE_BORROW_EVENT, COMET_WITHDRAW_EVENT]
+            .iter()
+            .map(|event| H256::from(keccak256(event)))
+            .collect();
+        let filter = Filter::new().address(addresses).topic0(topics).from_block(start).to_block(end);
+        let logs = self
+            .blockchain_client
+            .get_logs(&filter)
+            .await
+            .with_context(|| format!("Failed to fetch lending logs of blocks #{}-#{}", start, end))?;
+        
+        let checks: Vec<(LendingProtocol, Address, Address)> = {
+            let positions = self.positions.read();
+            logs.iter()
+                .filter_map(|log| self.borrower(log))
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .filter(|(_, market, account)| !positions.contains_key(&(*market, *account)))
+                .collect()
+        };
+        let borrowers = checks.len();
+        self.check_positions(markets, head, checks).await?;
+        
+        // Only moved on once checked, so a failed range is fetched again
+        *self.backfill_cursor.lock() = Some((start.saturating_sub(1), stop));
+        debug!("Backfilled {} lending borrowers of blocks #{}-#{}", borrowers, start, end);
+        
+        Ok(())
+    }
+    
+    /// Borrower whose debt a borrow or Comet withdraw log changed
+    fn borrower(&self, log: &Log) -> Option<(LendingProtocol, Address, Address)> {
+        let topic = log.topics.first().copied().unwrap_or_default();
+        if topic == H256::from(keccak256(AAVE_BORROW_EVENT)) && Some(log.address) == self.aave_pool {
+            // Debt is booked to onBehalfOf
+            indexed_address(log, 2).map(|account| (LendingProtocol::AaveV3, log.address, account))
+        } else if topic == H256::from(keccak256(COMET_WITHDRAW_EVENT)) && self.comets.contains(&log.address) {
+            indexed_address(log, 1).map(|account| (LendingProtocol::CompoundV3, log.address, account))
+        } else {
+            None
+        }
+    }
+    
+    /// Re-read the health of positions, tracking the large ones and sizing the underwater ones
+    async fn check_positions(
+        &self,
+        markets: &Markets,
+        block_number: u64,
+        checks: Vec<(LendingProtocol, Address, Address)>,
+    ) -> Result<Vec<LiquidationOpportunity>> {
+        if checks.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let prices = self.prices(markets).await?;
+        let reads: Vec<Vec<(Address, Bytes)>> = checks
+            .iter()
+            .map(|(protocol, market, account)| health_reads(markets, *protocol, *market, *account))
+            .collect();
+        let calls: Vec<(Address, Bytes)> = reads.iter().flatten().cloned().collect();
+        
+        // Outputs come back flattened in the order of the reads, one slice per position
+        let mut outputs = self.blockchain_client.multicall(&calls).await?.into_iter();
+        let outputs: Vec<Vec<Option<Bytes>>> = reads
+            .iter()
+            .map(|reads| outputs.by_ref().take(reads.len()).collect())
+            .collect();
+        let results = join_all(checks.iter().zip(outputs).map(|((protocol, market, account), outputs)| {
+            self.check(markets, &prices, *protocol, *market, *account, outputs)
+        }))
+        .await;
+        
+        let mut found = Vec::new();
+        {
+            let mut positions = self.positions.write();
+            let mut opportunities = self.opportunities.write();
+            for ((protocol, market, account), result) in checks.into_iter().zip(results) {
+                let key = (market, account);
+                let (position, opportunity) = match result {
+                    Ok(checked) => checked,
+                    Err(e) => {
+                        debug!("Failed to check {:?} position of {:?}: {}", protocol, account, e);
+                        continue;
+                    }
+                };
+                
+                opportunities.remove(&key);
+                let opportunity = opportunity.filter(|opportunity| opportunity.expected_profit >= self.min_profit);
+                if let Some(opportunity) = opportunity {
+                    info!(
+                        "Liquidatable {:?} position of {:?}: health {:.4}, expected profit {} wei",
+                        protocol, account, opportunity.health_factor, opportunity.expected_profit
+                    );
+                    opportunities.insert(key, opportunity.clone());
+                    found.push(opportunity);
+                }
+                
+                let tracked = position.debt_usd >= self.min_debt_usd || self.watch_accounts.contains(&account);
+                if !tracked {
+                    positions.remove(&key);
+                } else if positions.contains_key(&key) || positions.len() < self.max_positions {
+                    positions.insert(key, TrackedPosition {
+                        checked_block: block_number,
+                        ..position
+                    });
+                } else {
+                    metrics::counter!("liquidation_positions_skipped_total", 1);
+                }
+            }
+            
+            metrics::gauge!("liquidation_positions_tracked", positions.len() as f64);
+        }
+        
+        metrics::counter!("liquidation_opportunities_total", found.len() as u64);
+        Ok(found)
+    }
+    
+    async fn markets(&self) -> Result<&Markets> {
+        self.markets.get_or_try_init(|| self.load_markets()).await
+    }
+    
+    /// Read reserve and market parameters
+    async fn load_markets(&self) -> Result<Markets> {
+        let mut markets = Markets::default();
+        
+        if let Some(pool) = self.aave_pool {
+            markets.aave_base_unit = self
+                .blockchain_client
+                .read_uint(self.aave_oracle, "BASE_CURRENCY_UNIT()", &[])
+                .await?;
+            let output = self.blockchain_client.call_function(pool, "getReservesList()", &[]).await?;
+            let list = decode(&[ParamType::Array(Box::new(ParamType::Address))], &output)?;
+            let assets: Vec<Address> = array(&list[0])
+                .into_iter()
+                .filter_map(|token| token.into_address())
+                .collect();
+            
+            for asset in assets {
+                let args = [Token::Address(asset)];
+                let output = self
+                    .blockchain_client
+                    .call_function(self.aave_data_provider, "getReserveConfigurationData(address)", &args)
+                    .await?;
+                let mut types = vec![ParamType::Uint(256); 5];
+                types.extend(vec![ParamType::Bool; 5]);
+                let config = decode(&types, &output)?;
+                let protocol_fee = self
+                    .blockchain_client
+                    .read_uint(self.aave_data_provider, "getLiquidationProtocolFee(address)", &args)
+                    .await?;
+                markets.aave_reserves.push(AaveReserve {
+                    asset,
+                    decimals: uint(&config[0]).as_usize(),
+                    liquidation_bonus: uint(&config[3]),
+                    protocol_fee,
+                });
+            }
+        }
+        
+        for comet in &self.comets {
+            let base_token = self.blockchain_client.read_address(*comet, "baseToken()", &[]).await?;
+            let base_scale = self.blockchain_client.read_uint(*comet, "baseScale()", &[]).await?;
+            let base_price_feed = self.blockchain_client.read_address(*comet, "baseTokenPriceFeed()", &[]).await?;
+            let count = self.blockchain_client.read_uint(*comet, "numAssets()", &[]).await?.as_usize();
+            
+            let mut assets = Vec::with_capacity(count);
+            for i in 0..count {
+                let output = self
+                    .blockchain_client
+                    .call_function(*comet, "getAssetInfo(uint8)", &[Token::Uint(U256::from(i))])
+                    .await?;
+                let info = decode(
+                    &[
+                        ParamType::Uint(8),
+                        ParamType::Address,
+                        ParamType::Address,
+                        ParamType::Uint(64),
+                        ParamType::Uint(64),
+                        ParamType::Uint(64),
+                        ParamType::Uint(64),
+                        ParamType::Uint(128),
+                    ],
+                    &output,
+                )?;
+                assets.push(CometAsset {
+                    asset: info[1].clone().into_address().context("Missing Comet asset")?,
+                    price_feed: info[2].clone().into_address().context("Missing Comet price feed")?,
+                    scale: uint(&info[3]),
+                    liquidate_collateral_factor: to_f64(uint(&info[5])) / COMET_FACTOR_SCALE,
+                });
+            }
+            
+            markets.comets.push(CometMarket {
+                address: *comet,
+                base_token,
+                base_scale,
+                base_price_feed,
+                assets,
+            });
+        }
+        
+        info!(
+            "Liquidation monitor loaded {} Aave reserves and {} Comet markets",
+            markets.aave_reserves.len(),
+            markets.comets.len()
+        );
+        Ok(markets)
+    }
+    
+    /// Borrow and oracle events of one block
+    async fn block_logs(&self, block_number: u64) -> Result<Vec<Log>> {
+        let addresses: Vec<Address> = self
+            .aave_pool
+            .into_iter()
+            .chain(self.comets.iter().copied())
+            .chain(self.price_aggregators.iter().copied())
+            .collect();
+        let topics: Vec<H256> = [AAVE_BORROW_EVENT, COMET_WITHDRAW_EVENT, ANSWER_UPDATED_EVENT]
+            .iter()
+            .map(|event| H256::from(keccak256(event)))
+            .collect();
+        
+        let filter = Filter::new()
+            .address(addresses)
+            .topic0(topics)
+            .from_block(block_number)
+            .to_block(block_number);
+        self.blockchain_client
+            .get_logs(&filter)
+            .await
+            .with_context(|| format!("Failed to fetch lending logs for block {}", block_number))
+    }
+    
+    /// Oracle prices of every reserve and collateral, plus ETH and gas
+    async fn prices(&self, markets: &Markets) -> Result<PriceSnapshot> {
+        // Each Comet feed is read once, through the first market using it
+        let mut comet_feeds: Vec<(Address, Address)> = Vec::new();
+        for market in &markets.comets {
+            let feeds = std::iter::once(market.base_price_feed)
+                .chain(market.assets.iter().map(|asset| asset.price_feed));
+            for feed in feeds {
+                if !comet_feeds.iter().any(|(_, read)| *read == feed) {
+                    comet_feeds.push((market.address, feed));
+                }
+            }
+        }
+        
+        let reads: Vec<(Address, Bytes)> = markets
+            .aave_reserves
+            .iter()
+            .map(|reserve| {
+                let args = [Token::Address(reserve.asset)];
+                (self.aave_oracle, calldata("getAssetPrice(address)", &args))
+            })
+            .chain(
+                comet_feeds
+                    .iter()
+                    .map(|(comet, feed)| (*comet, calldata("getPrice(address)", &[Token::Address(*feed)]))),
+            )
+            .collect();
+        let mut outputs = self.blockchain_client.multicall(&reads).await?.into_iter();
+        let mut price = |key: Address| -> Result<(Address, U256)> {
+            let output = outputs.next().flatten().ok_or_else(|| anyhow!("Price read of {:?} reverted", key))?;
+            Ok((key, first_word("Price read", &output)?))
+        };
+        
+        let aave = markets
+            .aave_reserves
+            .iter()
+            .map(|reserve| price(reserve.asset))
+            .collect::<Result<HashMap<_, _>>>()?;
+        let comet = comet_feeds
+            .iter()
+            .map(|(_, feed)| price(*feed))
+            .collect::<Result<HashMap<_, _>>>()?;
+        
+        Ok(PriceSnapshot {
+            aave,
+            comet,
+            eth_usd: self.eth_usd().await?,
+            gas_price: self.blockchain_client.get_cached_gas_price().await?,
+        })
+    }
+    
+    /// ETH/USD with 8 decimals, from the price service or else straight from the feed
+    async fn eth_usd(&self) -> Result<U256> {
+        if self.prices.enabled() {
+            match self.prices.eth_usd().await {
+                Ok(price) => return Ok(U256::from((price * USD_PRICE_SCALE as f64) as u128)),
+                Err(e) => debug!("Falling back to the ETH/USD feed: {}", e),
+            }
+        }
+        
+        let round = self.blockchain_client.call_function(self.eth_usd_feed, "latestRoundData()", &[]).await?;
+        let round = decode(
+            &[
+                ParamType::Uint(80),
+                ParamType::Int(256),
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Uint(80),
+            ],
+            &round,
+        )?;
+        let eth_usd = round[1].clone().into_int().unwrap_or_default();
+        if eth_usd.is_zero() || eth_usd.bit(255) {
+            return Err(anyhow!("ETH/USD feed returned a non-positive answer"));
+        }
+        
+        Ok(eth_usd)
+    }
+    
+    /// Decode a position's health from the outputs of its `health_reads`, sizing a liquidation if
+    /// it is underwater
+    async fn check(
+        &self,
+        markets: &Markets,
+        prices: &PriceSnapshot,
+        protocol: LendingProtocol,
+        market: Address,
+        account: Address,
+        outputs: Vec<Option<Bytes>>,
+    ) -> Result<(TrackedPosition, Option<LiquidationOpportunity>)> {
+        let outputs = outputs
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| anyhow!("A health read reverted"))?;
+        match protocol {
+            LendingProtocol::AaveV3 => self.check_aave(markets, prices, market, account, &outputs).await,
+            LendingProtocol::CompoundV3 => {
+                let comet = markets
+                    .comets
+                    .iter()
+                    .find(|comet| comet.address == market)
+                    .ok_or_else(|| anyhow!("Comet {:?} is not tracked", market))?;
+                self.check_comet(comet, prices, account, &outputs).await
+            }
+        }
+    }
+    
+    async fn check_aave(
+        &self,
+        markets: &Markets,
+        prices: &PriceSnapshot,
+        pool: Address,
+        account: Address,
+        outputs: &[Bytes],
+    ) -> Result<(TrackedPosition, Option<LiquidationOpportunity>)> {
+        let [output] = outputs else {
+            return Err(anyhow!("Expected 1 Aave account read, got {}", outputs.len()));
+        };
+        let data = decode(&vec![ParamType::Uint(256); 6], output)?;
+        let debt_base = uint(&data[1]);
+        let health_factor = if debt_base.is_zero() {
+            f64::INFINITY
+        } else {
+            to_f64(uint(&data[5])) / HEALTH_FACTOR_SCALE
+        };
+        
+        let position = TrackedPosition {
+            protocol: LendingProtocol::AaveV3,
+            market: pool,
+            account,
+            debt_usd: to_f64(debt_base) / to_f64(markets.aave_base_unit.max(U256::one())),
+            health_factor,
+            checked_block: 0,
+        };
+        if health_factor >= 1.0 {
+            return Ok((position, None));
+        }
+        
+        // The largest debt is repaid against the largest collateral
+        let reads: Vec<(Address, Bytes)> = markets
+            .aave_reserves
+            .iter()
+            .map(|reserve| {
+                let args = [Token::Address(reserve.asset), Token::Address(account)];
+                (self.aave_data_provider, calldata("getUserReserveData(address,address)", &args))
+            })
+            .collect();
+        let outputs = self.blockchain_client.multicall(&reads).await?;
+        
+        let mut debt: Option<(&AaveReserve, U256, U256)> = None;
+        let mut collateral: Option<(&AaveReserve, U256, U256)> = None;
+        for (reserve, output) in markets.aave_reserves.iter().zip(outputs) {
+            let price = prices.aave.get(&reserve.asset).copied().unwrap_or_default();
+            let output = output.ok_or_else(|| anyhow!("Aave reserve read of {:?} reverted", reserve.asset))?;
+            let data = decode(
+                &[
+                    ParamType::Uint(256),
+                    ParamType::Uint(256),
+                    ParamType::Uint(256),
+                    ParamType::Uint(256),
+                    ParamType::Uint(256),
+                    ParamType::Uint(256),
+                    ParamType::Uint(256),
+                    ParamType::Uint(40),
+                    ParamType::Bool,
+                ],
+                &output,
+            )?;
+            
+            let unit = U256::exp10(reserve.decimals);
+            let borrowed = uint(&data[1]).saturating_add(uint(&data[2]));
+            let borrowed_value = borrowed.saturating_mul(price) / unit;
+            if !borrowed.is_zero() && debt.map_or(true, |(_, _, value)| borrowed_value > value) {
+                debt = Some((reserve, borrowed, borrowed_value));
+            }
+            
+            let supplied = uint(&data[0]);
+            let supplied_value = supplied.saturating_mul(price) / unit;
+            let enabled = data[8].clone().into_bool().unwrap_or(false);
+            if enabled && !supplied.is_zero() && collateral.map_or(true, |(_, _, value)| supplied_value > value) {
+                collateral = Some((reserve, supplied, supplied_value));
+            }
+        }
+        
+        let ((debt_reserve, borrowed, _), (collateral_reserve, supplied, _)) = match (debt, collateral) {
+            (Some(debt), Some(collateral)) => (debt, collateral),
+            _ => return Ok((position, None)),
+        };
+        let debt_price = prices.aave.get(&debt_reserve.asset).copied().unwrap_or_default();
+        let collateral_price = prices.aave.get(&collateral_reserve.asset).copied().unwrap_or_default();
+        if debt_price.is_zero() || collateral_price.is_zero() || collateral_reserve.liquidation_bonus.is_zero() {
+            return Ok((position, None));
+        }
+        
+        let debt_unit = U256::exp10(debt_reserve.decimals);
+        let collateral_unit = U256::exp10(collateral_reserve.decimals);
+        let bonus = collateral_reserve.liquidation_bonus;
+        
+        let mut debt_to_cover = if health_factor > CLOSE_FACTOR_HF_THRESHOLD { borrowed / 2 } else { borrowed };
+        let mut debt_value = debt_to_cover.saturating_mul(debt_price) / debt_unit;
+        let mut collateral_amount = debt_value.saturating_mul(bonus) / BPS * collateral_unit / collateral_price;
+        
+        // Not enough collateral for the full close, repay only what it covers
+        if collateral_amount > supplied {
+            collateral_amount = supplied;
+            debt_value = supplied.saturating_mul(collateral_price) / collateral_unit * BPS / bonus;
+            debt_to_cover = debt_value.saturating_mul(debt_unit) / debt_price;
+        }
+        
+        let bonus_amount = collateral_amount - collateral_amount * BPS / bonus;
+        let received = collateral_amount - bonus_amount * collateral_reserve.protocol_fee / BPS;
+        let received_value = received.saturating_mul(collateral_price) / collateral_unit;
+        
+        let profit_base = received_value.saturating_sub(debt_value);
+        let gross = to_wei(profit_base, markets.aave_base_unit, prices.eth_usd);
+        
+        let opportunity = LiquidationOpportunity {
+            protocol: LendingProtocol::AaveV3,
+            market: pool,
+            account,
+            health_factor,
+            debt_asset: debt_reserve.asset,
+            debt_to_cover,
+            collateral_asset: collateral_reserve.asset,
+            collateral_amount: received,
+            expected_profit: gross.saturating_sub(self.gas_cost(prices)),
+            gas_estimate: self.gas_per_liquidation,
+            detected_at: Utc::now(),
+        };
+        Ok((position, Some(opportunity)))
+    }
+    
+    async fn check_comet(
+        &self,
+        comet: &CometMarket,
+        prices: &PriceSnapshot,
+        account: Address,
+        outputs: &[Bytes],
+    ) -> Result<(TrackedPosition, Option<LiquidationOpportunity>)> {
+        let [borrowed, collateral @ ..] = outputs else {
+            return Err(anyhow!("Missing Comet borrow balance read"));
+        };
+        if collateral.len() != comet.assets.len() {
+            let expected = comet.assets.len();
+            return Err(anyhow!("Expected {} Comet collateral reads, got {}", expected, collateral.len()));
+        }
+        let borrowed = first_word("borrowBalanceOf(address)", borrowed)?;
+        let base_price = prices.comet.get(&comet.base_price_feed).copied().unwrap_or_default();
+        let debt_value = borrowed.saturating_mul(base_price) / comet.base_scale.max(U256::one());
+        
+        let mut liquidation_value = 0.0;
+        let mut largest: Option<(&CometAsset, U256, U256)> = None;
+        for (asset, output) in comet.assets.iter().zip(collateral) {
+            let balance = uint(&decode(&[ParamType::Uint(128), ParamType::Uint(128)], output)?[0]);
+            if balance.is_zero() {
+                continue;
+            }
+            
+            let price = prices.comet.get(&asset.price_feed).copied().unwrap_or_default();
+            let value = balance.saturating_mul(price) / asset.scale.max(U256::one());
+            liquidation_value += to_f64(value) * asset.liquidate_collateral_factor;
+            if largest.map_or(true, |(_, _, largest)| value > largest) {
+                largest = Some((asset, balance, value));
+            }
+        }
+        
+        let health_factor = if debt_value.is_zero() {
+            f64::INFINITY
+        } else {
+            liquidation_value / to_f64(debt_value)
+        };
+        let position = TrackedPosition {
+            protocol: LendingProtocol::CompoundV3,
+            market: comet.address,
+            account,
+            debt_usd: to_f64(debt_value) / USD_PRICE_SCALE as f64,
+            health_factor,
+            checked_block: 0,
+        };
+        
+        let (asset, balance, value) = match largest {
+            Some(largest) if health_factor < 1.0 && !base_price.is_zero() => largest,
+            _ => return Ok((position, None)),
+        };
+        
+        // After absorbing, Comet sells the collateral below its oracle price
+        let per_base = self
+            .blockchain_client
+            .read_uint(
+                comet.address,
+                "quoteCollateral(address,uint256)",
+                &[Token::Address(asset.asset), Token::Uint(comet.base_scale)],
+            )
+            .await?;
+        if per_base.is_zero() {
+            return Ok((position, None));
+        }
+        let base_amount = balance.saturating_mul(comet.base_scale) / per_base;
+        let cost = base_amount.saturating_mul(base_price) / comet.base_scale;
+        
+        let gross = to_wei(value.saturating_sub(cost), U256::from(USD_PRICE_SCALE), prices.eth_usd);
+        let opportunity = LiquidationOpportunity {
+            protocol: LendingProtocol::CompoundV3,
+            market: comet.address,
+            account,
+            health_factor,
+            debt_asset: comet.base_token,
+            debt_to_cover: base_amount,
+            collateral_asset: asset.asset,
+            collateral_amount: balance,
+            expected_profit: gross.saturating_sub(self.gas_cost(prices)),
+            gas_estimate: self.gas_per_liquidation,
+            detected_at: Utc::now(),
+        };
+        Ok((position, Some(opportunity)))
+    }
+    
+    fn gas_cost(&self, prices: &PriceSnapshot) -> U256 {
+        prices.gas_price.saturating_mul(U256::from(self.gas_per_liquidation))
+    }
+}
+
+/// Calls reading a position's health, decoded in the same order by `check`
+///
+/// Aave reports health in one account read. Comet takes the borrow balance and the collateral
+/// balance of every asset; an untracked Comet gets no reads and fails in `check`.
+fn health_reads(
+    markets: &Markets,
+    protocol: LendingProtocol,
+    market: Address,
+    account: Address,
+) -> Vec<(Address, Bytes)> {
+    let account_arg = [Token::Address(account)];
+    match protocol {
+        LendingProtocol::AaveV3 => vec![(market, calldata("getUserAccountData(address)", &account_arg))],
+        LendingProtocol::CompoundV3 => {
+            let comet = match markets.comets.iter().find(|comet| comet.address == market) {
+                Some(comet) => comet,
+                None => return Vec::new(),
+            };
+            let mut reads = vec![(market, calldata("borrowBalanceOf(address)", &account_arg))];
+            reads.extend(comet.assets.iter().map(|asset| {
+                let args = [Token::Address(account), Token::Address(asset.asset)];
+                (market, calldata("userCollateral(address,address)", &args))
+            }));
+            reads
+        }
+    }
+}
+
+/// A value in a price unit converted to wei at the ETH/USD price
+fn to_wei(value: U256, unit: U256, eth_usd: U256) -> U256 {
+    let scaled = value.saturating_mul(U256::exp10(18)).saturating_mul(U256::from(USD_PRICE_SCALE));
+    scaled / unit.max(U256::one()).saturating_mul(eth_usd)
+}
+
+fn indexed_address(log: &Log, index: usize) -> Option<Address> {
+    log.topics.get(index).map(|topic| Address::from_slice(&topic.as_bytes()[12..]))
+}
+
+fn decode(types: &[ParamType], data: &[u8]) -> Result<Vec<Token>> {
+    abi::decode(types, data).map_err(|e| anyhow!("Failed to decode lending state: {}", e))
+}
+
+fn uint(token: &Token) -> U256 {
+    token.clone().into_uint().unwrap_or_default()
+}
+
+fn array(token: &Token) -> Vec<Token> {
+    token.clone().into_array().unwrap_or_default()
+}
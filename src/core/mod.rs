@@ -0,0 +1,6 @@
+pub mod amm;
+pub mod arbitrage;
+pub mod liquidation;
+pub mod opportunities;
+pub mod schedule;
+pub mod strategy;
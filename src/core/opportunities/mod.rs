@@ -0,0 +1,40 @@
+use ethers::types::{Address, H256, U256};
+use serde::{Deserialize, Serialize};
+
+pub mod sandwich;
+pub mod swap;
+
+pub use sandwich::SandwichDetector;
+
+/// Uniswap protocol version a pool belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DexVersion {
+    V2,
+    V3,
+}
+
+/// A pending swap that can be sandwiched, with the sizing that maximises our profit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandwichOpportunity {
+    /// The victim swap
+    pub victim: H256,
+    pub dex: DexVersion,
+    pub pool: Address,
+    /// Fee tier of the pool in hundredths of a basis point, 3000 for V2
+    pub fee: u32,
+    /// Token the victim sells, always WETH
+    pub token_in: Address,
+    /// Token the victim buys
+    pub token_out: Address,
+    /// WETH we swap in ahead of the victim
+    pub frontrun_amount_in: U256,
+    /// Tokens the front-run buys, sold back after the victim
+    pub frontrun_amount_out: U256,
+    /// WETH the back-run returns
+    pub backrun_amount_out: U256,
+    /// Back-run proceeds minus front-run input and gas, in wei
+    pub expected_profit: U256,
+    /// Target block, the one after the block the opportunity was found at
+    pub target_block: u64,
+}
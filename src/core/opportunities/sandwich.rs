@@ -0,0 +1,408 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::{
+    abi::{self, ParamType, Token},
+    types::{Address, Bytes, Eip1559TransactionRequest, Transaction, H256, U256},
+    utils::{get_create2_address_from_hash, keccak256},
+};
+use parking_lot::RwLock;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use tracing::debug;
+
+use super::{
+    swap::{decode_swap, DecodedSwap, DexDeployment},
+    DexVersion, SandwichOpportunity,
+};
+use crate::{
+    blockchain::{
+        client::{calldata, first_word},
+        BlockchainClient,
+    },
+    config::SandwichConfig,
+    core::amm::{constant_product_out, v3_virtual_reserves},
+    services::head_tracker::HeadTracker,
+};
+
+const GET_RESERVES: [u8; 4] = [0x09, 0x02, 0xf1, 0xac];
+const SLOT0: [u8; 4] = [0x38, 0x50, 0xc7, 0xbd];
+const LIQUIDITY: [u8; 4] = [0x1a, 0x68, 0x65, 0x02];
+const APPROVE: &str = "approve(address,uint256)";
+const ALLOWANCE: &str = "allowance(address,address)";
+
+/// Gas limit of the token approval ahead of a back-run
+const APPROVE_GAS: u64 = 60_000;
+
+/// Reserves of one pool, oriented from WETH to the other token
+#[derive(Debug, Clone, Copy)]
+struct Reserves {
+    weth: U256,
+    token: U256,
+    fee: u32,
+}
+
+/// Amounts of a sized sandwich
+#[derive(Debug, Clone, Copy)]
+struct Sandwich {
+    frontrun_in: U256,
+    frontrun_out: U256,
+    backrun_out: U256,
+}
+
+/// Detects pending Uniswap swaps selling WETH that can be sandwiched profitably
+///
+/// V3 pools are modelled as constant product over their virtual reserves, which holds while
+/// the three swaps stay inside the current tick range. Opportunities are held until their
+/// target block, when each is bundled around its victim and submitted.
+#[derive(Clone)]
+pub struct SandwichDetector {
+    /// Whether pending swaps are inspected at all
+    enabled: bool,
+    /// Routers and factories swaps are decoded against
+    dex: DexDeployment,
+    /// Blockchain client, reads pool state
+    blockchain_client: Arc<BlockchainClient>,
+    /// Head tracker, the opportunity targets the block after the head
+    head_tracker: HeadTracker,
+    /// Largest front-run we would fund
+    max_frontrun: U256,
+    /// Minimum expected profit after gas
+    min_profit: U256,
+    /// Gas charged per sandwich leg
+    gas_per_swap: u64,
+    /// Signer front-runs and back-runs are sent from
+    searcher: Option<String>,
+    /// Open opportunities by victim transaction, with the victim as it was seen pending
+    opportunities: Arc<RwLock<HashMap<H256, (SandwichOpportunity, Arc<Transaction>)>>>,
+}
+
+impl SandwichDetector {
+    /// Create a new sandwich detector
+    pub fn new(
+        config: &SandwichConfig,
+        blockchain_client: Arc<BlockchainClient>,
+        head_tracker: HeadTracker,
+    ) -> Result<Self> {
+        let max_frontrun = U256::from_dec_str(&config.max_frontrun_wei).context("Invalid max_frontrun_wei")?;
+        let min_profit = U256::from_dec_str(&config.min_profit_wei).context("Invalid sandwich min_profit_wei")?;
+        
+        Ok(Self {
+            enabled: config.enabled,
+            dex: DexDeployment::from_config(config)?,
+            blockchain_client,
+            head_tracker,
+            max_frontrun,
+            min_profit,
+            gas_per_swap: config.gas_per_swap,
+            searcher: config.searcher.clone(),
+            opportunities: Arc::new(RwLock::new(HashMap::new())),
+        })
+    }
+    
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+    
+    /// Inspect a pending transaction, recording a sandwich if one clears the profit threshold
+    pub async fn inspect(&self, tx: &Transaction) -> Result<Option<SandwichOpportunity>> {
+        if !self.enabled {
+            return Ok(None);
+        }
+        let swap = match decode_swap(tx, &self.dex) {
+            Some(swap) if swap.token_in == self.dex.weth && swap.token_out != self.dex.weth => swap,
+            _ => return Ok(None),
+        };
+        let target_block = match self.head_tracker.head_number() {
+            Some(head) => head + 1,
+            None => return Ok(None),
+        };
+        
+        let (pool, reserves) = match swap.dex {
+            DexVersion::V2 => self.v2_reserves(&swap).await?,
+            DexVersion::V3 => self.v3_reserves(&swap).await?,
+        };
+        if reserves.weth.is_zero() || reserves.token.is_zero() {
+            return Ok(None);
+        }
+        
+        let sandwich = match optimal_sandwich(&swap, reserves, self.max_frontrun) {
+            Some(sandwich) => sandwich,
+            None => return Ok(None),
+        };
+        
+        let gas_price = self.blockchain_client.get_cached_gas_price().await?;
+        let gas_cost = gas_price.saturating_mul(U256::from(self.gas_per_swap * 2));
+        let expected_profit = match sandwich
+            .backrun_out
+            .checked_sub(sandwich.frontrun_in)
+            .and_then(|gross| gross.checked_sub(gas_cost))
+        {
+            Some(profit) if profit >= self.min_profit => profit,
+            _ => return Ok(None),
+        };
+        
+        let opportunity = SandwichOpportunity {
+            victim: tx.hash,
+            dex: swap.dex,
+            pool,
+            fee: swap.fee,
+            token_in: swap.token_in,
+            token_out: swap.token_out,
+            frontrun_amount_in: sandwich.frontrun_in,
+            frontrun_amount_out: sandwich.frontrun_out,
+            backrun_amount_out: sandwich.backrun_out,
+            expected_profit,
+            target_block,
+        };
+        
+        debug!(
+            "Sandwich on {:?} around {:?}: front-run {} wei for {} wei profit",
+            pool, tx.hash, sandwich.frontrun_in, expected_profit
+        );
+        let dex = match swap.dex {
+            DexVersion::V2 => "v2",
+            DexVersion::V3 => "v3",
+        };
+        metrics::counter!("sandwich_opportunities_total", 1, "dex" => dex);
+        
+        self.opportunities.write().insert(tx.hash, (opportunity.clone(), Arc::new(tx.clone())));
+        
+        Ok(Some(opportunity))
+    }
+    
+    /// Signer front-runs and back-runs are sent from; without one sandwiches are only reported
+    pub fn searcher(&self) -> Option<&str> {
+        self.searcher.as_deref()
+    }
+    
+    /// Open opportunities targeting a block with their victims, most profitable first
+    ///
+    /// Only the most profitable sandwich on each pool is kept, as the others were sized
+    /// against reserves it moves.
+    pub fn opportunities_for_block(&self, block_number: u64) -> Vec<(SandwichOpportunity, Arc<Transaction>)> {
+        let mut opportunities: Vec<(SandwichOpportunity, Arc<Transaction>)> = self
+            .opportunities
+            .read()
+            .values()
+            .filter(|(opportunity, _)| opportunity.target_block == block_number)
+            .cloned()
+            .collect();
+        opportunities.sort_by(|(a, _), (b, _)| b.expected_profit.cmp(&a.expected_profit));
+        let mut pools = HashSet::new();
+        opportunities.retain(|(opportunity, _)| pools.insert(opportunity.pool));
+        opportunities
+    }
+    
+    /// Front-run and back-run of a sandwich from `searcher`, to go either side of the victim
+    ///
+    /// Both swap through the router the victim used. The searcher must hold the WETH and have
+    /// approved it to the routers; the bought token is approved in the back-run when the
+    /// allowance falls short. The back-run's minimum output is the expected proceeds, so a
+    /// bundle whose victim moved the pool differently reverts instead of losing.
+    pub async fn legs(
+        &self,
+        opportunity: &SandwichOpportunity,
+        searcher: Address,
+    ) -> Result<(Vec<Eip1559TransactionRequest>, Vec<Eip1559TransactionRequest>)> {
+        let router = match opportunity.dex {
+            DexVersion::V2 => self.dex.v2_router,
+            DexVersion::V3 => self.dex.v3_router,
+        };
+        let swap = |token_in: Address, token_out: Address, amount_in: U256, min_out: U256| {
+            let data = match opportunity.dex {
+                DexVersion::V2 => calldata(
+                    "swapExactTokensForTokens(uint256,uint256,address[],address,uint256)",
+                    &[
+                        Token::Uint(amount_in),
+                        Token::Uint(min_out),
+                        Token::Array(vec![Token::Address(token_in), Token::Address(token_out)]),
+                        Token::Address(searcher),
+                        Token::Uint(U256::MAX),
+                    ],
+                ),
+                DexVersion::V3 => calldata(
+                    "exactInputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))",
+                    &[Token::Tuple(vec![
+                        Token::Address(token_in),
+                        Token::Address(token_out),
+                        Token::Uint(opportunity.fee.into()),
+                        Token::Address(searcher),
+                        Token::Uint(U256::MAX),
+                        Token::Uint(amount_in),
+                        Token::Uint(min_out),
+                        Token::Uint(U256::zero()),
+                    ])],
+                ),
+            };
+            Eip1559TransactionRequest::new()
+                .from(searcher)
+                .to(router)
+                .data(data)
+                .gas(self.gas_per_swap)
+        };
+        
+        let front = vec![swap(
+            opportunity.token_in,
+            opportunity.token_out,
+            opportunity.frontrun_amount_in,
+            opportunity.frontrun_amount_out,
+        )];
+        
+        let mut back = Vec::with_capacity(2);
+        let output = self
+            .blockchain_client
+            .call(
+                opportunity.token_out,
+                calldata(ALLOWANCE, &[Token::Address(searcher), Token::Address(router)]),
+            )
+            .await?;
+        if first_word("allowance", &output)? < opportunity.frontrun_amount_out {
+            back.push(
+                Eip1559TransactionRequest::new()
+                    .from(searcher)
+                    .to(opportunity.token_out)
+                    .data(calldata(APPROVE, &[Token::Address(router), Token::Uint(U256::MAX)]))
+                    .gas(APPROVE_GAS),
+            );
+        }
+        back.push(swap(
+            opportunity.token_out,
+            opportunity.token_in,
+            opportunity.frontrun_amount_out,
+            opportunity.backrun_amount_out,
+        ));
+        
+        Ok((front, back))
+    }
+    
+    /// Drop opportunities whose victim landed or whose target block has passed
+    pub fn prune(&self, block_number: u64, included: &[H256]) {
+        let mut opportunities = self.opportunities.write();
+        for hash in included {
+            opportunities.remove(hash);
+        }
+        opportunities.retain(|_, (opportunity, _)| opportunity.target_block > block_number);
+        metrics::gauge!("sandwich_opportunities_open", opportunities.len() as f64);
+    }
+    
+    async fn v2_reserves(&self, swap: &DecodedSwap) -> Result<(Address, Reserves)> {
+        let (token0, token1) = sort_tokens(swap.token_in, swap.token_out);
+        let salt = keccak256([token0.as_bytes(), token1.as_bytes()].concat());
+        let pair = get_create2_address_from_hash(self.dex.v2_factory, salt, self.dex.v2_pair_init_code_hash);
+        
+        let output = self.blockchain_client.call(pair, Bytes::from(GET_RESERVES.to_vec())).await?;
+        let tokens = abi::decode(&[ParamType::Uint(112), ParamType::Uint(112), ParamType::Uint(32)], &output)
+            .map_err(|e| anyhow!("Failed to decode reserves of {:?}: {}", pair, e))?;
+        let reserve0 = tokens[0].clone().into_uint().unwrap_or_default();
+        let reserve1 = tokens[1].clone().into_uint().unwrap_or_default();
+        
+        Ok((pair, orient(swap.token_in == token0, reserve0, reserve1, swap.fee)))
+    }
+    
+    async fn v3_reserves(&self, swap: &DecodedSwap) -> Result<(Address, Reserves)> {
+        let (token0, token1) = sort_tokens(swap.token_in, swap.token_out);
+        let salt = keccak256(abi::encode(&[
+            Token::Address(token0),
+            Token::Address(token1),
+            Token::Uint(U256::from(swap.fee)),
+        ]));
+        let pool = get_create2_address_from_hash(self.dex.v3_factory, salt, self.dex.v3_pool_init_code_hash);
+        
+        let slot0 = self.blockchain_client.call(pool, Bytes::from(SLOT0.to_vec())).await?;
+        let liquidity = self.blockchain_client.call(pool, Bytes::from(LIQUIDITY.to_vec())).await?;
+        if slot0.len() < 32 || liquidity.len() < 32 {
+            return Err(anyhow!("Unexpected state returned by pool {:?}", pool));
+        }
+        let sqrt_price = U256::from_big_endian(&slot0[..32]);
+        let liquidity = U256::from_big_endian(&liquidity[..32]);
+        let (reserve0, reserve1) = v3_virtual_reserves(sqrt_price, liquidity);
+        
+        Ok((pool, orient(swap.token_in == token0, reserve0, reserve1, swap.fee)))
+    }
+}
+
+fn sort_tokens(a: Address, b: Address) -> (Address, Address) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+fn orient(weth_is_token0: bool, reserve0: U256, reserve1: U256, fee: u32) -> Reserves {
+    let (weth, token) = if weth_is_token0 { (reserve0, reserve1) } else { (reserve1, reserve0) };
+    Reserves { weth, token, fee }
+}
+
+/// Run front-run, victim and back-run against the pool; `None` if the victim would revert
+fn simulate(frontrun_in: U256, swap: &DecodedSwap, reserves: Reserves) -> Option<Sandwich> {
+    let frontrun_out = constant_product_out(frontrun_in, reserves.weth, reserves.token, reserves.fee)?;
+    let weth = reserves.weth.checked_add(frontrun_in)?;
+    let token = reserves.token.checked_sub(frontrun_out)?;
+    
+    let victim_out = constant_product_out(swap.amount_in, weth, token, reserves.fee)?;
+    if victim_out < swap.amount_out_min {
+        return None;
+    }
+    let weth = weth.checked_add(swap.amount_in)?;
+    let token = token.checked_sub(victim_out)?;
+    
+    let backrun_out = constant_product_out(frontrun_out, token, weth, reserves.fee)?;
+    
+    Some(Sandwich {
+        frontrun_in,
+        frontrun_out,
+        backrun_out,
+    })
+}
+
+/// Size the front-run that maximises back-run proceeds minus input
+///
+/// The victim's minimum output caps the front-run, so the largest size keeping the victim
+/// swap valid is found first, then profit is maximised below it.
+fn optimal_sandwich(swap: &DecodedSwap, reserves: Reserves, max_frontrun: U256) -> Option<Sandwich> {
+    simulate(U256::zero(), swap, reserves)?;
+    
+    let mut upper = max_frontrun;
+    if simulate(upper, swap, reserves).is_none() {
+        let mut lower = U256::zero();
+        while upper - lower > U256::one() {
+            let mid = lower + (upper - lower) / 2;
+            if simulate(mid, swap, reserves).is_some() {
+                lower = mid;
+            } else {
+                upper = mid;
+            }
+        }
+        upper = lower;
+    }
+    
+    // Profit is unimodal in the front-run size; compare a.back - a.in against b.back - b.in
+    // without going negative
+    let better = |a: &Sandwich, b: &Sandwich| a.backrun_out + b.frontrun_in > b.backrun_out + a.frontrun_in;
+    let mut lower = U256::zero();
+    while upper - lower > U256::from(2) {
+        let third = (upper - lower) / 3;
+        let left = simulate(lower + third, swap, reserves)?;
+        let right = simulate(upper - third, swap, reserves)?;
+        if better(&left, &right) {
+            upper = upper - third;
+        } else {
+            lower = lower + third;
+        }
+    }
+    
+    let mut best: Option<Sandwich> = None;
+    let mut size = lower;
+    while size <= upper {
+        if let Some(candidate) = simulate(size, swap, reserves) {
+            if best.as_ref().map_or(true, |current| better(&candidate, current)) {
+                best = Some(candidate);
+            }
+        }
+        size += U256::one();
+    }
+    
+    best.filter(|sandwich| !sandwich.frontrun_in.is_zero() && sandwich.backrun_out > sandwich.frontrun_in)
+}
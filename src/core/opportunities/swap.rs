@@ -0,0 +1,100 @@
+use anyhow::{Context, Result};
+use ethers::types::{Address, Transaction, H256, U256};
+
+use super::DexVersion;
+use crate::{
+    blockchain::decoder::{DecodedCall, SwapKind, UniswapSwap},
+    config::SandwichConfig,
+};
+
+/// Router, factory and wrapped-native addresses of the Uniswap deployment we watch
+#[derive(Debug, Clone)]
+pub struct DexDeployment {
+    pub v2_router: Address,
+    pub v2_factory: Address,
+    pub v2_pair_init_code_hash: H256,
+    pub v3_router: Address,
+    pub v3_factory: Address,
+    pub v3_pool_init_code_hash: H256,
+    /// Wrapped native token, the only input we sandwich so profit is in wei
+    pub weth: Address,
+}
+
+impl DexDeployment {
+    pub fn from_config(config: &SandwichConfig) -> Result<Self> {
+        let address = |value: &str| value.parse::<Address>().with_context(|| format!("Invalid address {}", value));
+        let hash = |value: &str| value.parse::<H256>().with_context(|| format!("Invalid init code hash {}", value));
+        
+        Ok(Self {
+            v2_router: address(&config.v2_router)?,
+            v2_factory: address(&config.v2_factory)?,
+            v2_pair_init_code_hash: hash(&config.v2_pair_init_code_hash)?,
+            v3_router: address(&config.v3_router)?,
+            v3_factory: address(&config.v3_factory)?,
+            v3_pool_init_code_hash: hash(&config.v3_pool_init_code_hash)?,
+            weth: address(&config.weth)?,
+        })
+    }
+}
+
+/// An exact-input swap decoded from router calldata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedSwap {
+    pub dex: DexVersion,
+    pub token_in: Address,
+    pub token_out: Address,
+    pub amount_in: U256,
+    /// The victim's slippage bound, which caps how far we can move the price
+    pub amount_out_min: U256,
+    /// V3 fee tier in hundredths of a basis point; 3000 (0.3%) for V2
+    pub fee: u32,
+}
+
+/// Decode a single-hop exact-input swap through a Uniswap router
+///
+/// Multi-hop paths are skipped, as only the first pool can be sandwiched in one bundle
+/// without modelling the others, and so are batches of several swaps. Fee-on-transfer swaps
+/// and swaps with a price limit are skipped too, as they don't pay what the pool quotes.
+pub fn decode_swap(tx: &Transaction, dex: &DexDeployment) -> Option<DecodedSwap> {
+    let to = tx.to?;
+    if to != dex.v2_router && to != dex.v3_router {
+        return None;
+    }
+    let call = DecodedCall::decode(&tx.input, tx.value)?;
+    let swaps = call.uniswap_swaps();
+    let swap = match swaps.as_slice() {
+        [swap] => *swap,
+        _ => return None,
+    };
+    
+    match swap {
+        UniswapSwap::V2(swap) if to == dex.v2_router => {
+            if swap.kind != SwapKind::ExactInput || swap.fee_on_transfer || swap.path.len() != 2 {
+                return None;
+            }
+            Some(DecodedSwap {
+                dex: DexVersion::V2,
+                token_in: swap.path[0],
+                token_out: swap.path[1],
+                amount_in: swap.amount_in,
+                amount_out_min: swap.amount_out,
+                fee: 3000,
+            })
+        }
+        UniswapSwap::V3(swap) if to == dex.v3_router => {
+            // A price limit means the swap stops early rather than paying our moved price
+            if swap.kind != SwapKind::ExactInput || swap.path.len() != 2 || !swap.sqrt_price_limit_x96.is_zero() {
+                return None;
+            }
+            Some(DecodedSwap {
+                dex: DexVersion::V3,
+                token_in: swap.path[0],
+                token_out: swap.path[1],
+                amount_in: swap.amount_in,
+                amount_out_min: swap.amount_out,
+                fee: swap.fees[0],
+            })
+        }
+        _ => None,
+    }
+}
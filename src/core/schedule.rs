@@ -0,0 +1,143 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Timelike, Utc};
+use ethers::types::{Address, U256};
+use std::collections::HashMap;
+
+use crate::{config::StrategyScheduleConfig, core::amm::to_f64};
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Network state schedules are evaluated against, refreshed with every new head
+#[derive(Debug, Clone, Default)]
+pub struct NetworkConditions {
+    /// Base fee of the latest block
+    pub base_fee: Option<U256>,
+    /// Utilization of the scheduled lending markets, as last read
+    pub utilization: HashMap<Address, f64>,
+}
+
+/// When a strategy may run, parsed from its schedule config
+///
+/// Every configured condition must hold. A condition that can't be evaluated yet, a base fee
+/// before the first head or a market whose utilization hasn't been read, does not hold, so a
+/// scheduled strategy stays off until the conditions are known.
+#[derive(Debug, Clone)]
+pub struct StrategySchedule {
+    /// Minutes after UTC midnight each window starts and ends at, the end exclusive
+    windows: Vec<(u32, u32)>,
+    /// Base fee bounds in gwei
+    min_base_fee_gwei: Option<f64>,
+    max_base_fee_gwei: Option<f64>,
+    /// Markets and the utilization one of them must reach
+    min_utilization: Vec<(Address, f64)>,
+}
+
+impl StrategySchedule {
+    pub fn parse(config: &StrategyScheduleConfig) -> Result<Self> {
+        let windows = config
+            .windows
+            .iter()
+            .map(|window| parse_window(window))
+            .collect::<Result<Vec<_>>>()?;
+        
+        for (name, bound) in [
+            ("min_base_fee_gwei", config.min_base_fee_gwei),
+            ("max_base_fee_gwei", config.max_base_fee_gwei),
+        ] {
+            if bound.map_or(false, |bound| !bound.is_finite() || bound < 0.0) {
+                return Err(anyhow!("{} must not be negative", name));
+            }
+        }
+        if let (Some(min), Some(max)) = (config.min_base_fee_gwei, config.max_base_fee_gwei) {
+            if min > max {
+                return Err(anyhow!("min_base_fee_gwei is above max_base_fee_gwei"));
+            }
+        }
+        
+        let min_utilization = config
+            .min_utilization
+            .iter()
+            .map(|condition| {
+                let market: Address = condition
+                    .market
+                    .parse()
+                    .context(format!("Invalid market {}", condition.market))?;
+                if !(0.0..=1.0).contains(&condition.threshold) {
+                    return Err(anyhow!("Utilization threshold of {} must be between 0 and 1", condition.market));
+                }
+                Ok((market, condition.threshold))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        
+        Ok(Self {
+            windows,
+            min_base_fee_gwei: config.min_base_fee_gwei,
+            max_base_fee_gwei: config.max_base_fee_gwei,
+            min_utilization,
+        })
+    }
+    
+    /// Lending markets whose utilization the schedule depends on
+    pub fn markets(&self) -> impl Iterator<Item = Address> + '_ {
+        self.min_utilization.iter().map(|(market, _)| *market)
+    }
+    
+    /// Whether the strategy may run at this time under these conditions
+    pub fn allows(&self, now: DateTime<Utc>, conditions: &NetworkConditions) -> bool {
+        let minute = now.hour() * 60 + now.minute();
+        let in_window = self.windows.is_empty()
+            || self.windows.iter().any(|&(start, end)| {
+                if start <= end {
+                    (start..end).contains(&minute)
+                } else {
+                    minute >= start || minute < end
+                }
+            });
+        if !in_window {
+            return false;
+        }
+        
+        if self.min_base_fee_gwei.is_some() || self.max_base_fee_gwei.is_some() {
+            let base_fee_gwei = match conditions.base_fee {
+                Some(base_fee) => to_f64(base_fee) / 1e9,
+                None => return false,
+            };
+            if self.min_base_fee_gwei.map_or(false, |min| base_fee_gwei < min)
+                || self.max_base_fee_gwei.map_or(false, |max| base_fee_gwei > max)
+            {
+                return false;
+            }
+        }
+        
+        self.min_utilization.is_empty()
+            || self.min_utilization.iter().any(|(market, threshold)| {
+                conditions.utilization.get(market).map_or(false, |utilization| utilization >= threshold)
+            })
+    }
+}
+
+/// `HH:MM-HH:MM` as minutes after midnight
+fn parse_window(window: &str) -> Result<(u32, u32)> {
+    let (start, end) = window
+        .split_once('-')
+        .ok_or_else(|| anyhow!("Window {} is not HH:MM-HH:MM", window))?;
+    let start = parse_time(start.trim()).context(format!("Invalid window {}", window))?;
+    let end = parse_time(end.trim()).context(format!("Invalid window {}", window))?;
+    if start == end {
+        return Err(anyhow!("Window {} is empty", window));
+    }
+    
+    Ok((start, end))
+}
+
+/// `HH:MM` as minutes after midnight, `24:00` for the end of the day
+fn parse_time(time: &str) -> Result<u32> {
+    let (hours, minutes) = time.split_once(':').ok_or_else(|| anyhow!("{} is not HH:MM", time))?;
+    let hours: u32 = hours.parse().context(format!("Invalid hour in {}", time))?;
+    let minutes: u32 = minutes.parse().context(format!("Invalid minutes in {}", time))?;
+    if hours > 24 || minutes >= 60 || hours * 60 + minutes > MINUTES_PER_DAY {
+        return Err(anyhow!("{} is not a time of day", time));
+    }
+    
+    Ok(hours * 60 + minutes)
+}
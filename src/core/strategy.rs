@@ -0,0 +1,442 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use dashmap::DashMap;
+use ethers::types::{Address, Block, Transaction};
+use futures::{future::join_all, FutureExt};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    config::{StrategiesConfig, StrategyScheduleConfig},
+    core::{
+        arbitrage::{ArbitrageEngine, ArbitrageOpportunity},
+        liquidation::{LiquidationMonitor, LiquidationOpportunity},
+        opportunities::{SandwichDetector, SandwichOpportunity},
+        schedule::{NetworkConditions, StrategySchedule},
+    },
+    services::{
+        controls::SubsystemControls,
+        labels::{LabelCategory, LabelRegistry},
+    },
+};
+
+/// An opportunity found by a strategy, offered to every other running strategy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Opportunity {
+    Sandwich(SandwichOpportunity),
+    Arbitrage(ArbitrageOpportunity),
+    Liquidation(LiquidationOpportunity),
+}
+
+impl Opportunity {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Sandwich(_) => "sandwich",
+            Self::Arbitrage(_) => "arbitrage",
+            Self::Liquidation(_) => "liquidation",
+        }
+    }
+}
+
+/// A searching strategy driven by the chain monitor
+///
+/// Hooks default to doing nothing, so a strategy only implements the events it reacts to.
+/// Pending-transaction and block hooks return what they found, which is then handed to the
+/// other strategies' `on_opportunity`.
+#[async_trait]
+pub trait Strategy: Send + Sync {
+    /// Name the strategy is listed and controlled under
+    fn name(&self) -> &'static str;
+    
+    /// A transaction entered the public mempool
+    async fn on_pending_tx(&self, _tx: &Transaction) -> Result<Vec<Opportunity>> {
+        Ok(Vec::new())
+    }
+    
+    /// A new head arrived
+    async fn on_new_block(&self, _block: &Block<Transaction>) -> Result<Vec<Opportunity>> {
+        Ok(Vec::new())
+    }
+    
+    /// Another strategy found an opportunity
+    async fn on_opportunity(&self, _opportunity: &Opportunity) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Runtime state of one loaded strategy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyState {
+    pub name: String,
+    pub enabled: bool,
+    /// Label categories whose pending transactions the strategy is not shown
+    pub ignored_labels: Vec<LabelCategory>,
+    /// Time windows and network conditions the strategy runs under
+    pub schedule: Option<StrategyScheduleConfig>,
+    /// Enabled and within its schedule right now
+    pub active: bool,
+}
+
+
+/// Runs the loaded strategies side by side, each isolated from the others
+///
+/// Every hook runs concurrently across enabled strategies under a timeout, configurable per
+/// strategy since block hooks that re-read a market take longer, and a strategy that
+/// errors, panics or overruns is logged and counted without affecting the rest. Strategies can
+/// be disabled and enabled again at runtime, as pausable subsystems whose state persists across
+/// restarts. Pending transactions from or to an address whose label a strategy ignores are kept
+/// from that strategy, and a strategy with a schedule only sees hooks while its time windows and
+/// network conditions hold, evaluated against the latest head.
+#[derive(Clone)]
+pub struct StrategyManager {
+    /// Loaded strategies by name
+    strategies: Arc<DashMap<&'static str, Arc<dyn Strategy>>>,
+    /// Persisted pause state, a strategy is disabled while paused under its name
+    controls: SubsystemControls,
+    /// Time a single hook gets before it is abandoned, and overrides of it by strategy
+    hook_timeout: Duration,
+    hook_timeouts: Arc<HashMap<String, Duration>>,
+    /// Labels pending transactions are tagged with
+    labels: LabelRegistry,
+    /// Label categories each strategy ignores on pending transactions
+    ignore_labels: Arc<HashMap<String, Vec<LabelCategory>>>,
+    /// Schedules by strategy, as configured and as parsed
+    schedule_configs: Arc<HashMap<String, StrategyScheduleConfig>>,
+    schedules: Arc<HashMap<String, StrategySchedule>>,
+    /// Base fee and market utilization schedules were last evaluated against
+    conditions: Arc<RwLock<NetworkConditions>>,
+    /// Reads lending market utilization, without it utilization conditions never hold
+    markets: Option<LiquidationMonitor>,
+    /// Whether each scheduled strategy was within its schedule at the last head, to log changes
+    scheduled: Arc<DashMap<String, bool>>,
+}
+
+impl StrategyManager {
+    pub fn new(config: &StrategiesConfig, labels: LabelRegistry, controls: SubsystemControls) -> Result<Self> {
+        let ignore_labels = config
+            .ignore_labels
+            .iter()
+            .map(|(strategy, categories)| {
+                let categories = categories
+                    .iter()
+                    .map(|category| category.parse())
+                    .collect::<Result<Vec<LabelCategory>>>()
+                    .context(format!("Invalid ignored labels for strategy {}", strategy))?;
+                Ok((strategy.clone(), categories))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+        let schedules = config
+            .schedules
+            .iter()
+            .map(|(strategy, schedule)| {
+                let schedule = StrategySchedule::parse(schedule)
+                    .context(format!("Invalid schedule for strategy {}", strategy))?;
+                Ok((strategy.clone(), schedule))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+        
+        Ok(Self {
+            strategies: Arc::new(DashMap::new()),
+            controls,
+            hook_timeout: Duration::from_millis(config.hook_timeout_ms),
+            hook_timeouts: Arc::new(
+                config
+                    .hook_timeouts_ms
+                    .iter()
+                    .map(|(strategy, timeout_ms)| (strategy.clone(), Duration::from_millis(*timeout_ms)))
+                    .collect(),
+            ),
+            labels,
+            ignore_labels: Arc::new(ignore_labels),
+            schedule_configs: Arc::new(config.schedules.clone()),
+            schedules: Arc::new(schedules),
+            conditions: Arc::new(RwLock::new(NetworkConditions::default())),
+            markets: None,
+            scheduled: Arc::new(DashMap::new()),
+        })
+    }
+    
+    /// Load the built-in strategies whose engines are enabled in config
+    pub fn with_builtin(
+        config: &StrategiesConfig,
+        labels: LabelRegistry,
+        controls: SubsystemControls,
+        sandwich_detector: &SandwichDetector,
+        arbitrage_engine: &ArbitrageEngine,
+        liquidation_monitor: &LiquidationMonitor,
+    ) -> Result<Self> {
+        let mut manager = Self::new(config, labels, controls)?;
+        manager.markets = Some(liquidation_monitor.clone());
+        if sandwich_detector.enabled() {
+            manager.load(Arc::new(sandwich_detector.clone()));
+        }
+        if arbitrage_engine.enabled() {
+            manager.load(Arc::new(arbitrage_engine.clone()));
+        }
+        if liquidation_monitor.enabled() {
+            manager.load(Arc::new(liquidation_monitor.clone()));
+        }
+        Ok(manager)
+    }
+    
+    /// Load a strategy, replacing any loaded under the same name
+    ///
+    /// It starts enabled unless it was disabled before a restart.
+    pub fn load(&self, strategy: Arc<dyn Strategy>) {
+        let name = strategy.name();
+        self.controls.register(name);
+        let enabled = self.is_enabled(name);
+        metrics::gauge!("strategy_enabled", if enabled { 1.0 } else { 0.0 }, "strategy" => name);
+        info!("Loaded strategy {}{}", name, if enabled { "" } else { ", disabled by persisted state" });
+        self.strategies.insert(name, strategy);
+    }
+    
+    /// Enable or disable a loaded strategy, persisting the choice
+    pub async fn set_enabled(&self, name: &str, enabled: bool) -> Result<()> {
+        let name = self
+            .strategies
+            .get(name)
+            .map(|loaded| loaded.name())
+            .ok_or_else(|| anyhow!("Unknown strategy: {}", name))?;
+        self.controls.set_paused(name, !enabled).await?;
+        metrics::gauge!("strategy_enabled", if enabled { 1.0 } else { 0.0 }, "strategy" => name);
+        info!("Strategy {} {}", name, if enabled { "enabled" } else { "disabled" });
+        
+        Ok(())
+    }
+    
+    /// Whether a strategy is enabled, also when paused as a subsystem
+    fn is_enabled(&self, name: &str) -> bool {
+        !self.controls.is_paused(name)
+    }
+    
+    /// Whether a name refers to a loaded strategy
+    pub fn is_loaded(&self, name: &str) -> bool {
+        self.strategies.contains_key(name)
+    }
+    
+    /// Whether any strategy would see a hook, letting callers skip preparing its input
+    pub fn any_enabled(&self) -> bool {
+        self.strategies.iter().any(|loaded| self.is_enabled(loaded.name()))
+    }
+    
+    /// State of every loaded strategy
+    pub fn list(&self) -> Vec<StrategyState> {
+        let mut states: Vec<StrategyState> = self
+            .strategies
+            .iter()
+            .map(|loaded| StrategyState {
+                name: loaded.name().to_string(),
+                enabled: self.is_enabled(loaded.name()),
+                ignored_labels: self.ignore_labels.get(loaded.name()).cloned().unwrap_or_default(),
+                schedule: self.schedule_configs.get(loaded.name()).cloned(),
+                active: self.is_enabled(loaded.name()) && self.within_schedule(loaded.name()),
+            })
+            .collect();
+        states.sort_by(|a, b| a.name.cmp(&b.name));
+        states
+    }
+    
+    /// Hand a pending transaction to every active strategy not ignoring its labels
+    pub async fn on_pending_tx(&self, tx: &Transaction) -> Vec<Opportunity> {
+        let labels = self.labels.tag(tx);
+        let strategies = self.active().into_iter().filter(|strategy| {
+            self.ignore_labels
+                .get(strategy.name())
+                .map_or(true, |ignored| !ignored.iter().any(|category| labels.has(*category)))
+        });
+        
+        let found = join_all(strategies.map(|strategy| async move {
+            let name = strategy.name();
+            let found = self.run(name, "pending_tx", strategy.on_pending_tx(tx)).await.unwrap_or_default();
+            (name, found)
+        }))
+        .await;
+        
+        self.dispatch(found).await
+    }
+    
+    /// Hand a new head to every strategy active under the conditions it brings
+    pub async fn on_new_block(&self, block: &Block<Transaction>) -> Vec<Opportunity> {
+        self.refresh_conditions(block).await;
+        
+        let found = join_all(self.active().into_iter().map(|strategy| async move {
+            let name = strategy.name();
+            let found = self.run(name, "new_block", strategy.on_new_block(block)).await.unwrap_or_default();
+            (name, found)
+        }))
+        .await;
+        
+        self.dispatch(found).await
+    }
+    
+    /// Offer each strategy the opportunities the others found
+    async fn dispatch(&self, found: Vec<(&'static str, Vec<Opportunity>)>) -> Vec<Opportunity> {
+        let strategies = self.active();
+        let mut calls = Vec::new();
+        for (source, opportunities) in &found {
+            if !opportunities.is_empty() {
+                metrics::counter!("strategy_opportunities_total", opportunities.len() as u64, "strategy" => *source);
+            }
+            for opportunity in opportunities {
+                for strategy in strategies.iter().filter(|strategy| strategy.name() != *source) {
+                    calls.push(self.run(strategy.name(), "opportunity", strategy.on_opportunity(opportunity)));
+                }
+            }
+        }
+        join_all(calls).await;
+        
+        found.into_iter().flat_map(|(_, opportunities)| opportunities).collect()
+    }
+    
+    /// Enabled strategies within their schedules
+    fn active(&self) -> Vec<Arc<dyn Strategy>> {
+        self.strategies
+            .iter()
+            .filter(|loaded| self.is_enabled(loaded.name()) && self.within_schedule(loaded.name()))
+            .map(|loaded| loaded.value().clone())
+            .collect()
+    }
+    
+    fn hook_timeout(&self, name: &str) -> Duration {
+        self.hook_timeouts.get(name).copied().unwrap_or(self.hook_timeout)
+    }
+    
+    fn within_schedule(&self, name: &str) -> bool {
+        self.schedules
+            .get(name)
+            .map_or(true, |schedule| schedule.allows(Utc::now(), &self.conditions.read()))
+    }
+    
+    /// Take the base fee from a new head and re-read the scheduled markets' utilization
+    async fn refresh_conditions(&self, block: &Block<Transaction>) {
+        if self.schedules.is_empty() {
+            return;
+        }
+        
+        let mut markets: Vec<Address> = self.schedules.values().flat_map(|schedule| schedule.markets()).collect();
+        markets.sort();
+        markets.dedup();
+        
+        let mut utilization = HashMap::with_capacity(markets.len());
+        if let Some(reader) = &self.markets {
+            for market in markets {
+                match reader.utilization(market).await {
+                    Ok(value) => {
+                        utilization.insert(market, value);
+                    }
+                    Err(e) => warn!("Failed to read utilization of {:?} for strategy schedules: {}", market, e),
+                }
+            }
+        }
+        
+        *self.conditions.write() = NetworkConditions {
+            base_fee: block.base_fee_per_gas,
+            utilization,
+        };
+        
+        for name in self.schedules.keys() {
+            let within = self.within_schedule(name);
+            if self.scheduled.insert(name.clone(), within) != Some(within) {
+                metrics::gauge!("strategy_scheduled", if within { 1.0 } else { 0.0 }, "strategy" => name.clone());
+                info!("Strategy {} {} its schedule", name, if within { "entered" } else { "left" });
+            }
+        }
+    }
+    
+    /// Run one hook, containing its errors, panics and overruns
+    async fn run<T>(
+        &self,
+        name: &'static str,
+        hook: &'static str,
+        call: impl Future<Output = Result<T>>,
+    ) -> Option<T> {
+        let started = Instant::now();
+        let timeout = self.hook_timeout(name);
+        let outcome = tokio::time::timeout(timeout, AssertUnwindSafe(call).catch_unwind()).await;
+        metrics::histogram!("strategy_hook_seconds", started.elapsed().as_secs_f64(), "strategy" => name, "hook" => hook);
+        
+        match outcome {
+            Ok(Ok(Ok(value))) => {
+                metrics::counter!("strategy_hooks_total", 1, "strategy" => name, "hook" => hook, "outcome" => "ok");
+                Some(value)
+            }
+            Ok(Ok(Err(e))) => {
+                metrics::counter!("strategy_hooks_total", 1, "strategy" => name, "hook" => hook, "outcome" => "error");
+                // Pending transactions arrive at mempool rate, their failures would flood the log
+                if hook == "pending_tx" {
+                    debug!("Strategy {} failed on {}: {}", name, hook, e);
+                } else {
+                    warn!("Strategy {} failed on {}: {}", name, hook, e);
+                }
+                None
+            }
+            Ok(Err(panic)) => {
+                metrics::counter!("strategy_hooks_total", 1, "strategy" => name, "hook" => hook, "outcome" => "panic");
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                error!("Strategy {} panicked on {}: {}", name, hook, message);
+                None
+            }
+            Err(_) => {
+                metrics::counter!("strategy_hooks_total", 1, "strategy" => name, "hook" => hook, "outcome" => "timeout");
+                warn!("Strategy {} timed out on {} after {:?}", name, hook, timeout);
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Strategy for SandwichDetector {
+    fn name(&self) -> &'static str {
+        "sandwich"
+    }
+    
+    async fn on_pending_tx(&self, tx: &Transaction) -> Result<Vec<Opportunity>> {
+        Ok(self.inspect(tx).await?.into_iter().map(Opportunity::Sandwich).collect())
+    }
+}
+
+#[async_trait]
+impl Strategy for ArbitrageEngine {
+    fn name(&self) -> &'static str {
+        "arbitrage"
+    }
+    
+    /// Pool reserves moved with the block, look for new cycles
+    async fn on_new_block(&self, block: &Block<Transaction>) -> Result<Vec<Opportunity>> {
+        Ok(self.on_block(block.hash).await?.into_iter().map(Opportunity::Arbitrage).collect())
+    }
+}
+
+#[async_trait]
+impl Strategy for LiquidationMonitor {
+    fn name(&self) -> &'static str {
+        "liquidation"
+    }
+    
+    /// Borrowers and oracle answers in the block may have pushed positions underwater
+    async fn on_new_block(&self, block: &Block<Transaction>) -> Result<Vec<Opportunity>> {
+        let block_number = block.number.unwrap_or_default().as_u64();
+        Ok(self
+            .on_block(block_number)
+            .await?
+            .into_iter()
+            .map(Opportunity::Liquidation)
+            .collect())
+    }
+}
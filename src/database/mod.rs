@@ -7,10 +7,14 @@ use crate::config::{DatabaseConfig, RedisConfig};
 
 pub mod migrations;
 pub mod models;
+mod redis_pool;
 pub mod repositories;
+pub mod resilience;
+
+pub use redis_pool::RedisPool;
+pub use resilience::DbHealth;
 
 pub type DbPool = Pool<Postgres>;
-pub type RedisPool = RedisConnectionManager;
 
 /// Connect to the PostgreSQL database
 pub async fn connect(config: &DatabaseConfig) -> Result<DbPool> {
@@ -20,6 +24,8 @@ pub async fn connect(config: &DatabaseConfig) -> Result<DbPool> {
         .max_connections(config.max_connections)
         .idle_timeout(std::time::Duration::from_secs(config.idle_timeout_seconds))
         .connect_timeout(std::time::Duration::from_secs(config.connect_timeout_seconds))
+        // Fail fast while the database is unreachable instead of queueing for the 30s default
+        .acquire_timeout(std::time::Duration::from_secs(config.connect_timeout_seconds))
         .connect(&config.url)
         .await
         .context("Failed to connect to database")?;
@@ -42,7 +48,7 @@ pub async fn connect_redis(config: &RedisConfig) -> Result<RedisPool> {
     
     info!("Successfully connected to Redis");
     
-    Ok(manager)
+    Ok(RedisPool::new(manager, config))
 }
 
 /// Run database migrations
@@ -57,4 +63,4 @@ pub async fn run_migrations(pool: &DbPool) -> Result<()> {
     info!("Database migrations completed successfully");
     
     Ok(())
-} 
\ No newline at end of file
+}
\ No newline at end of file
@@ -0,0 +1,218 @@
+use parking_lot::Mutex;
+use redis::{
+    aio::{ConnectionLike, ConnectionManager},
+    Arg, Cmd, FromRedisValue, Pipeline, RedisError, RedisFuture, RedisResult, Value,
+};
+use std::{
+    io,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::{debug, info, warn};
+
+use crate::config::RedisConfig;
+
+/// Read-only commands, the only ones safe to send again when a reply went missing
+const IDEMPOTENT_READS: &[&str] = &[
+    "EXISTS", "GET", "HEXISTS", "HGET", "HGETALL", "HLEN", "HMGET", "LLEN", "LRANGE", "MGET", "PING", "PTTL",
+    "SCAN", "SCARD", "SISMEMBER", "SMEMBERS", "STRLEN", "TTL", "TYPE", "ZCARD", "ZCOUNT", "ZRANGE",
+    "ZRANGEBYSCORE", "ZRANK", "ZREVRANGE", "ZREVRANGEBYSCORE", "ZREVRANK", "ZSCORE",
+];
+
+/// Timeouts and retries applied to every command
+#[derive(Debug, Clone, Copy)]
+struct CommandPolicy {
+    timeout: Duration,
+    max_retries: u32,
+    backoff: Duration,
+    cooldown: Duration,
+}
+
+/// Redis connection with command timeouts, retries and a degraded mode
+///
+/// Implements `ConnectionLike`, so commands, pipelines and scripts run against it as against
+/// the bare `ConnectionManager`. Reads that time out or lose their connection are retried
+/// with doubling backoff; writes and scripts are sent once, as a lost reply doesn't mean they
+/// didn't run. Once a command fails for good Redis counts as degraded for a cooldown: retries
+/// are skipped so callers fail fast, and `cached`/`cached_pipeline` skip non-critical work
+/// entirely.
+#[derive(Clone)]
+pub struct RedisPool {
+    manager: ConnectionManager,
+    policy: CommandPolicy,
+    /// Until when Redis counts as degraded
+    degraded_until: Arc<Mutex<Option<Instant>>>,
+}
+
+impl RedisPool {
+    pub fn new(manager: ConnectionManager, config: &RedisConfig) -> Self {
+        Self {
+            manager,
+            policy: CommandPolicy {
+                timeout: Duration::from_millis(config.command_timeout_ms),
+                max_retries: config.max_retries,
+                backoff: Duration::from_millis(config.retry_backoff_ms),
+                cooldown: Duration::from_secs(config.degraded_cooldown_seconds),
+            },
+            degraded_until: Arc::new(Mutex::new(None)),
+        }
+    }
+    
+    /// Whether Redis recently stopped answering
+    pub fn is_degraded(&self) -> bool {
+        self.degraded_until.lock().map_or(false, |until| Instant::now() < until)
+    }
+    
+    /// Run a batch of commands in one round trip
+    pub async fn pipeline<T: FromRedisValue>(&self, pipe: &Pipeline) -> RedisResult<T> {
+        pipe.query_async(&mut self.clone()).await
+    }
+    
+    /// Run a non-critical command, skipping it while degraded and swallowing failures
+    pub async fn cached<T: FromRedisValue>(&self, cmd: &Cmd) -> Option<T> {
+        if self.skip_cache() {
+            return None;
+        }
+        cmd.query_async(&mut self.clone())
+            .await
+            .map_err(|e| debug!("Skipping Redis cache command: {}", e))
+            .ok()
+    }
+    
+    /// Run a non-critical batch, skipping it while degraded and swallowing failures
+    pub async fn cached_pipeline<T: FromRedisValue>(&self, pipe: &Pipeline) -> Option<T> {
+        if self.skip_cache() {
+            return None;
+        }
+        self.pipeline(pipe)
+            .await
+            .map_err(|e| debug!("Skipping Redis cache pipeline: {}", e))
+            .ok()
+    }
+    
+    fn skip_cache(&self) -> bool {
+        let degraded = self.is_degraded();
+        if degraded {
+            metrics::counter!("redis_cache_skipped_total", 1);
+        }
+        degraded
+    }
+    
+    fn attempts(&self, idempotent: bool) -> u32 {
+        if !idempotent || self.is_degraded() {
+            1
+        } else {
+            self.policy.max_retries + 1
+        }
+    }
+    
+    fn record_success(&self) {
+        let mut degraded_until = self.degraded_until.lock();
+        if degraded_until.take().is_some() {
+            info!("Redis is answering again, leaving degraded mode");
+            metrics::gauge!("redis_degraded", 0.0);
+        }
+    }
+    
+    fn record_failure(&self, error: &RedisError) {
+        metrics::counter!("redis_command_failures_total", 1);
+        if !is_transient(error) {
+            return;
+        }
+        
+        let mut degraded_until = self.degraded_until.lock();
+        if degraded_until.is_none() {
+            warn!("Redis unavailable ({}), skipping non-critical caching", error);
+            metrics::gauge!("redis_degraded", 1.0);
+        }
+        *degraded_until = Some(Instant::now() + self.policy.cooldown);
+    }
+}
+
+impl ConnectionLike for RedisPool {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        Box::pin(async move {
+            let attempts = self.attempts(is_idempotent_read(cmd));
+            let mut backoff = self.policy.backoff;
+            for attempt in 1..=attempts {
+                let request = self.manager.req_packed_command(cmd);
+                let result = match tokio::time::timeout(self.policy.timeout, request).await {
+                    Ok(result) => result,
+                    Err(_) => Err(timed_out()),
+                };
+                match result {
+                    Err(e) if is_transient(&e) && attempt < attempts => {
+                        metrics::counter!("redis_command_retries_total", 1);
+                        tokio::time::sleep(backoff).await;
+                        backoff *= 2;
+                    }
+                    Err(e) => {
+                        self.record_failure(&e);
+                        return Err(e);
+                    }
+                    Ok(value) => {
+                        self.record_success();
+                        return Ok(value);
+                    }
+                }
+            }
+            unreachable!("at least one attempt is made")
+        })
+    }
+    
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        Box::pin(async move {
+            let attempts = self.attempts(cmd.cmd_iter().all(is_idempotent_read));
+            let mut backoff = self.policy.backoff;
+            for attempt in 1..=attempts {
+                let request = self.manager.req_packed_commands(cmd, offset, count);
+                let result = match tokio::time::timeout(self.policy.timeout, request).await {
+                    Ok(result) => result,
+                    Err(_) => Err(timed_out()),
+                };
+                match result {
+                    Err(e) if is_transient(&e) && attempt < attempts => {
+                        metrics::counter!("redis_command_retries_total", 1);
+                        tokio::time::sleep(backoff).await;
+                        backoff *= 2;
+                    }
+                    Err(e) => {
+                        self.record_failure(&e);
+                        return Err(e);
+                    }
+                    Ok(values) => {
+                        self.record_success();
+                        return Ok(values);
+                    }
+                }
+            }
+            unreachable!("at least one attempt is made")
+        })
+    }
+    
+    fn get_db(&self) -> i64 {
+        self.manager.get_db()
+    }
+}
+
+fn timed_out() -> RedisError {
+    RedisError::from(io::Error::new(io::ErrorKind::TimedOut, "Redis command timed out"))
+}
+
+/// Whether a command only reads, so sending it twice can't apply anything twice
+fn is_idempotent_read(cmd: &Cmd) -> bool {
+    match cmd.args_iter().next() {
+        Some(Arg::Simple(name)) => IDEMPOTENT_READS.iter().any(|read| read.as_bytes().eq_ignore_ascii_case(name)),
+        _ => false,
+    }
+}
+
+/// Failures worth retrying: the command may never have reached a healthy server
+fn is_transient(error: &RedisError) -> bool {
+    error.is_timeout() || error.is_io_error() || error.is_connection_dropped() || error.is_connection_refusal()
+}
@@ -0,0 +1,342 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::{
+    types::{Block, Transaction, H256},
+    utils::keccak256,
+};
+use serde_json::Value;
+use sqlx::Row;
+use std::collections::{HashMap, HashSet};
+use tracing::debug;
+
+use crate::{
+    config::BlockStorageConfig,
+    database::{DbHealth, DbPool},
+};
+
+/// Field a transaction's calldata is referenced by once moved to `calldata_blobs`
+const INPUT_HASH_FIELD: &str = "inputHash";
+
+/// A block encoded for storage
+struct EncodedBlock {
+    number: u64,
+    hash: String,
+    /// Compressed JSON, long calldata replaced by its hash
+    body: Vec<u8>,
+    /// Size of the block's JSON with its calldata
+    raw_size: usize,
+    /// Distinct calldata moved out of the body: hash, compressed data and uncompressed size
+    blobs: Vec<(Vec<u8>, Vec<u8>, i32)>,
+    /// Calldata moved out that another transaction of the block already sent
+    repeated: usize,
+    tx_hashes: Vec<String>,
+}
+
+/// Storage of confirmed blocks with their full transactions in `block_bodies`
+///
+/// Bodies are stored as zstd-compressed JSON. Calldata longer than `inline_calldata_bytes` is
+/// moved to `calldata_blobs` under its hash, so calldata that many transactions send, such as
+/// bot calls, approvals and mints, is stored once however often it repeats. Reads put the
+/// calldata back, returning blocks and transactions as the node served them. Storage is
+/// retried on connection loss but not deferred, a block body is too large to buffer.
+#[derive(Clone)]
+pub struct BlockBodyRepository {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Configuration
+    config: BlockStorageConfig,
+}
+
+impl BlockBodyRepository {
+    /// Create a new block body repository
+    pub fn new(db_pool: DbPool, health: DbHealth, config: BlockStorageConfig) -> Self {
+        Self {
+            db_pool,
+            health,
+            config,
+        }
+    }
+    
+    /// Whether confirmed blocks are stored
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Store a confirmed block, replacing one stored at its height before a reorg
+    pub async fn store(&self, block: &Block<Transaction>) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        
+        let (block, config) = (block.clone(), self.config.clone());
+        let encoded = tokio::task::spawn_blocking(move || encode(&block, &config))
+            .await
+            .context("Block encoding panicked")??;
+        let deduplicated = self.health.retry(|| self.store_once(&encoded)).await? + encoded.repeated;
+        
+        let stored = encoded.body.len() + encoded.blobs.iter().map(|(_, data, _)| data.len()).sum::<usize>();
+        metrics::counter!("block_storage_raw_bytes_total", encoded.raw_size as u64);
+        metrics::counter!("block_storage_written_bytes_total", stored as u64);
+        metrics::counter!("calldata_blobs_deduplicated_total", deduplicated as u64);
+        debug!(
+            "Stored block {} in {} of {} bytes, {} calldata deduplicated",
+            encoded.number, stored, encoded.raw_size, deduplicated
+        );
+        
+        Ok(())
+    }
+    
+    /// Stored block with its full transactions, `None` when it isn't stored
+    pub async fn block(&self, block_number: u64) -> Result<Option<Block<Transaction>>> {
+        self.health.retry(|| self.block_once(block_number)).await
+    }
+    
+    /// Stored transaction by hash, with the block fields of where it was included
+    pub async fn transaction(&self, tx_hash: H256) -> Result<Option<Transaction>> {
+        self.health.retry(|| self.transaction_once(tx_hash)).await
+    }
+    
+    /// Delete blocks more than `retention_blocks` behind `head` and calldata no newer block
+    /// sent; returns the blocks and calldata deleted
+    pub async fn prune(&self, head: u64) -> Result<(u64, u64)> {
+        let before = head.saturating_sub(self.config.retention_blocks) as i64;
+        self.health.retry(|| self.prune_once(before)).await
+    }
+    
+    /// Write an encoded block, returning how many of its calldata blobs were already stored
+    async fn store_once(&self, block: &EncodedBlock) -> Result<usize> {
+        let number = block.number as i64;
+        let hashes: Vec<Vec<u8>> = block.blobs.iter().map(|(hash, _, _)| hash.clone()).collect();
+        let mut db_tx = self.db_pool.begin().await.context("Failed to start block storage transaction")?;
+        
+        // Calldata already stored only has its newest block moved up
+        let existing: HashSet<Vec<u8>> = sqlx::query_scalar(
+            "UPDATE calldata_blobs SET last_block = GREATEST(last_block, $2)
+             WHERE hash = ANY($1)
+             RETURNING hash",
+        )
+        .bind(&hashes)
+        .bind(number)
+        .fetch_all(&mut *db_tx)
+        .await
+        .context("Failed to reference stored calldata")?
+        .into_iter()
+        .collect();
+        
+        let new: Vec<_> = block.blobs.iter().filter(|(hash, _, _)| !existing.contains(hash)).collect();
+        if !new.is_empty() {
+            sqlx::query(
+                "INSERT INTO calldata_blobs (hash, data, size, last_block)
+                 SELECT hash, data, size, $4
+                 FROM UNNEST($1::BYTEA[], $2::BYTEA[], $3::INTEGER[]) AS blob(hash, data, size)
+                 ON CONFLICT (hash) DO UPDATE
+                 SET last_block = GREATEST(calldata_blobs.last_block, EXCLUDED.last_block)",
+            )
+            .bind(new.iter().map(|(hash, _, _)| hash.clone()).collect::<Vec<_>>())
+            .bind(new.iter().map(|(_, data, _)| data.clone()).collect::<Vec<_>>())
+            .bind(new.iter().map(|(_, _, size)| *size).collect::<Vec<_>>())
+            .bind(number)
+            .execute(&mut *db_tx)
+            .await
+            .context("Failed to store calldata")?;
+        }
+        
+        sqlx::query(
+            "INSERT INTO block_bodies (block_number, block_hash, body, raw_size, stored_size)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (block_number) DO UPDATE
+             SET block_hash = EXCLUDED.block_hash, body = EXCLUDED.body, raw_size = EXCLUDED.raw_size,
+                 stored_size = EXCLUDED.stored_size, stored_at = NOW()",
+        )
+        .bind(number)
+        .bind(&block.hash)
+        .bind(&block.body)
+        .bind(block.raw_size as i32)
+        .bind(block.body.len() as i32)
+        .execute(&mut *db_tx)
+        .await
+        .context("Failed to store block body")?;
+        
+        // Transactions of a block reorged out of this height no longer point at it
+        sqlx::query("DELETE FROM block_transactions WHERE block_number = $1")
+            .bind(number)
+            .execute(&mut *db_tx)
+            .await
+            .context("Failed to remove reorged block transactions")?;
+        sqlx::query(
+            "INSERT INTO block_transactions (tx_hash, block_number, tx_index)
+             SELECT tx_hash, $2, tx_index - 1 FROM UNNEST($1::TEXT[]) WITH ORDINALITY AS tx(tx_hash, tx_index)
+             ON CONFLICT (tx_hash) DO UPDATE
+             SET block_number = EXCLUDED.block_number, tx_index = EXCLUDED.tx_index",
+        )
+        .bind(&block.tx_hashes)
+        .bind(number)
+        .execute(&mut *db_tx)
+        .await
+        .context("Failed to store block transactions")?;
+        
+        db_tx.commit().await.context("Failed to commit block storage")?;
+        
+        Ok(existing.len())
+    }
+    
+    async fn block_once(&self, block_number: u64) -> Result<Option<Block<Transaction>>> {
+        let row = sqlx::query("SELECT body FROM block_bodies WHERE block_number = $1")
+            .bind(block_number as i64)
+            .fetch_optional(&self.db_pool)
+            .await
+            .context("Failed to fetch block body")?;
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        
+        let mut body = decompress(&row.try_get::<Vec<u8>, _>("body")?)?;
+        if let Some(transactions) = body["transactions"].as_array_mut() {
+            self.restore_calldata(transactions).await?;
+        }
+        
+        serde_json::from_value(body)
+            .map(Some)
+            .context(format!("Invalid stored block {}", block_number))
+    }
+    
+    async fn transaction_once(&self, tx_hash: H256) -> Result<Option<Transaction>> {
+        let row = sqlx::query(
+            "SELECT body.body, tx.tx_index
+             FROM block_transactions tx
+             JOIN block_bodies body ON body.block_number = tx.block_number
+             WHERE tx.tx_hash = $1",
+        )
+        .bind(format!("{:?}", tx_hash))
+        .fetch_optional(&self.db_pool)
+        .await
+        .context("Failed to fetch stored transaction")?;
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        
+        let index = row.try_get::<i32, _>("tx_index")? as usize;
+        let mut body = decompress(&row.try_get::<Vec<u8>, _>("body")?)?;
+        let mut transaction = match body["transactions"].get_mut(index) {
+            Some(transaction) => transaction.take(),
+            None => return Err(anyhow!("Stored block of {:?} has no transaction {}", tx_hash, index)),
+        };
+        self.restore_calldata(std::slice::from_mut(&mut transaction)).await?;
+        
+        serde_json::from_value(transaction)
+            .map(Some)
+            .context(format!("Invalid stored transaction {:?}", tx_hash))
+    }
+    
+    /// Put calldata moved to `calldata_blobs` back into stored transactions
+    async fn restore_calldata(&self, transactions: &mut [Value]) -> Result<()> {
+        let mut references = Vec::new();
+        for (index, transaction) in transactions.iter_mut().enumerate() {
+            let reference = transaction.as_object_mut().and_then(|fields| fields.remove(INPUT_HASH_FIELD));
+            if let Some(reference) = reference {
+                let hash = reference
+                    .as_str()
+                    .and_then(|hash| hex::decode(hash.trim_start_matches("0x")).ok())
+                    .ok_or_else(|| anyhow!("Invalid calldata reference {}", reference))?;
+                references.push((index, hash));
+            }
+        }
+        if references.is_empty() {
+            return Ok(());
+        }
+        
+        let hashes: Vec<Vec<u8>> = references.iter().map(|(_, hash)| hash.clone()).collect();
+        let rows = sqlx::query("SELECT hash, data FROM calldata_blobs WHERE hash = ANY($1)")
+            .bind(&hashes)
+            .fetch_all(&self.db_pool)
+            .await
+            .context("Failed to fetch stored calldata")?;
+        let mut blobs = HashMap::with_capacity(rows.len());
+        for row in rows {
+            let data = zstd::decode_all(row.try_get::<Vec<u8>, _>("data")?.as_slice())
+                .context("Failed to decompress stored calldata")?;
+            blobs.insert(row.try_get::<Vec<u8>, _>("hash")?, data);
+        }
+        
+        for (index, hash) in references {
+            let data = blobs
+                .get(&hash)
+                .ok_or_else(|| anyhow!("Stored calldata 0x{} is missing", hex::encode(&hash)))?;
+            transactions[index]["input"] = Value::String(format!("0x{}", hex::encode(data)));
+        }
+        
+        Ok(())
+    }
+    
+    async fn prune_once(&self, before: i64) -> Result<(u64, u64)> {
+        sqlx::query("DELETE FROM block_transactions WHERE block_number < $1")
+            .bind(before)
+            .execute(&self.db_pool)
+            .await
+            .context("Failed to delete old block transactions")?;
+        let blocks = sqlx::query("DELETE FROM block_bodies WHERE block_number < $1")
+            .bind(before)
+            .execute(&self.db_pool)
+            .await
+            .context("Failed to delete old block bodies")?;
+        let blobs = sqlx::query("DELETE FROM calldata_blobs WHERE last_block < $1")
+            .bind(before)
+            .execute(&self.db_pool)
+            .await
+            .context("Failed to delete old calldata")?;
+        
+        Ok((blocks.rows_affected(), blobs.rows_affected()))
+    }
+}
+
+/// Encode a block for storage, moving long calldata out of its body
+fn encode(block: &Block<Transaction>, config: &BlockStorageConfig) -> Result<EncodedBlock> {
+    let number = block.number.ok_or_else(|| anyhow!("Block has no number"))?.as_u64();
+    let mut body = serde_json::to_value(block).context("Failed to encode block")?;
+    let raw_size = serde_json::to_vec(&body)?.len();
+    
+    let mut blobs = Vec::new();
+    let mut seen = HashSet::new();
+    let mut repeated = 0;
+    if let Some(transactions) = body["transactions"].as_array_mut() {
+        for (tx, stored) in block.transactions.iter().zip(transactions) {
+            if tx.input.len() <= config.inline_calldata_bytes {
+                continue;
+            }
+            
+            let hash = keccak256(&tx.input).to_vec();
+            stored["input"] = Value::String("0x".to_string());
+            stored[INPUT_HASH_FIELD] = Value::String(format!("0x{}", hex::encode(&hash)));
+            if !seen.insert(hash.clone()) {
+                repeated += 1;
+                continue;
+            }
+            
+            let data = zstd::encode_all(tx.input.as_ref(), config.compression_level)
+                .context("Failed to compress calldata")?;
+            blobs.push((hash, data, tx.input.len() as i32));
+        }
+    }
+    
+    let json = serde_json::to_vec(&body).context("Failed to encode block")?;
+    let body = zstd::encode_all(json.as_slice(), config.compression_level).context("Failed to compress block")?;
+    
+    Ok(EncodedBlock {
+        number,
+        hash: format!("{:?}", block.hash.unwrap_or_default()),
+        body,
+        raw_size,
+        blobs,
+        repeated,
+        tx_hashes: block.transactions.iter().map(|tx| format!("{:?}", tx.hash)).collect(),
+    })
+}
+
+/// Decompress a stored body, its long calldata still referenced by hash
+fn decompress(body: &[u8]) -> Result<Value> {
+    let json = zstd::decode_all(body).context("Failed to decompress block body")?;
+    serde_json::from_slice(&json).context("Invalid stored block body")
+}
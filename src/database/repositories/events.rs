@@ -0,0 +1,212 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use ethers::types::{Address, H256};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{postgres::PgRow, Row};
+
+use crate::{
+    api::models,
+    database::{DbHealth, DbPool},
+};
+
+/// A decoded event of an indexed contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedEvent {
+    pub block_number: u64,
+    pub block_hash: H256,
+    pub tx_hash: H256,
+    pub log_index: u64,
+    /// Configured name of the emitting contract
+    pub contract: String,
+    #[serde(with = "models::checksum")]
+    pub address: Address,
+    pub event_name: String,
+    /// Canonical signature, e.g. `Transfer(address,address,uint256)`
+    pub signature: String,
+    /// Decoded parameters by name, integers as decimal strings
+    pub params: Value,
+    pub indexed_at: DateTime<Utc>,
+}
+
+/// Filters and pagination over indexed events, newest first
+#[derive(Debug, Clone, Default)]
+pub struct EventQuery {
+    /// Configured contract name
+    pub contract: Option<String>,
+    pub address: Option<Address>,
+    pub event_name: Option<String>,
+    pub tx_hash: Option<H256>,
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+    pub offset: i64,
+    pub limit: i64,
+}
+
+/// A page of indexed events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventPage {
+    /// Events matching the filter
+    pub total: i64,
+    pub events: Vec<IndexedEvent>,
+}
+
+/// Persistence of indexed contract events in `events`
+///
+/// Writes are retried on connection loss but not deferred, so the indexer knows which blocks
+/// it has to fetch again.
+#[derive(Clone)]
+pub struct EventRepository {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+}
+
+impl EventRepository {
+    /// Create a new event repository
+    pub fn new(db_pool: DbPool, health: DbHealth) -> Self {
+        Self { db_pool, health }
+    }
+    
+    /// Store events, keeping those already stored as they are
+    pub async fn record(&self, events: &[IndexedEvent]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.health.retry(|| self.record_once(events)).await
+    }
+    
+    /// Remove the event of a log its block was reorged out with
+    pub async fn remove(&self, block_hash: H256, log_index: u64) -> Result<()> {
+        self.health.retry(|| self.remove_once(block_hash, log_index)).await
+    }
+    
+    /// Highest block with a stored event, where catching up resumes
+    pub async fn latest_block(&self) -> Result<Option<u64>> {
+        self.health.retry(|| self.latest_block_once()).await
+    }
+    
+    /// Page through stored events matching a query
+    pub async fn query(&self, query: &EventQuery) -> Result<EventPage> {
+        self.health.retry(|| self.query_once(query)).await
+    }
+    
+    async fn record_once(&self, events: &[IndexedEvent]) -> Result<()> {
+        let mut db_tx = self.db_pool.begin().await.context("Failed to begin event transaction")?;
+        for event in events {
+            sqlx::query(
+                "INSERT INTO events
+                     (block_hash, log_index, block_number, tx_hash, contract, address, event_name, signature,
+                      params)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                 ON CONFLICT (block_hash, log_index) DO NOTHING",
+            )
+            .bind(format!("{:?}", event.block_hash))
+            .bind(event.log_index as i32)
+            .bind(event.block_number as i64)
+            .bind(format!("{:?}", event.tx_hash))
+            .bind(&event.contract)
+            .bind(format!("{:?}", event.address))
+            .bind(&event.event_name)
+            .bind(&event.signature)
+            .bind(&event.params)
+            .execute(&mut *db_tx)
+            .await
+            .context("Failed to record event")?;
+        }
+        db_tx.commit().await.context("Failed to commit events")?;
+        
+        Ok(())
+    }
+    
+    async fn remove_once(&self, block_hash: H256, log_index: u64) -> Result<()> {
+        sqlx::query("DELETE FROM events WHERE block_hash = $1 AND log_index = $2")
+            .bind(format!("{:?}", block_hash))
+            .bind(log_index as i32)
+            .execute(&self.db_pool)
+            .await
+            .context("Failed to remove reorged event")?;
+        
+        Ok(())
+    }
+    
+    async fn latest_block_once(&self) -> Result<Option<u64>> {
+        let latest: Option<i64> = sqlx::query_scalar("SELECT MAX(block_number) FROM events")
+            .fetch_one(&self.db_pool)
+            .await
+            .context("Failed to fetch latest indexed block")?;
+        
+        Ok(latest.map(|block| block as u64))
+    }
+    
+    async fn query_once(&self, query: &EventQuery) -> Result<EventPage> {
+        const FILTER: &str = "WHERE ($1::TEXT IS NULL OR contract = $1)
+               AND ($2::TEXT IS NULL OR address = $2)
+               AND ($3::TEXT IS NULL OR event_name = $3)
+               AND ($4::TEXT IS NULL OR tx_hash = $4)
+               AND ($5::BIGINT IS NULL OR block_number >= $5)
+               AND ($6::BIGINT IS NULL OR block_number <= $6)";
+        
+        let address = query.address.map(|address| format!("{:?}", address));
+        let tx_hash = query.tx_hash.map(|tx_hash| format!("{:?}", tx_hash));
+        let from_block = query.from_block.map(|block| block as i64);
+        let to_block = query.to_block.map(|block| block as i64);
+        
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM events {}", FILTER))
+            .bind(&query.contract)
+            .bind(&address)
+            .bind(&query.event_name)
+            .bind(&tx_hash)
+            .bind(from_block)
+            .bind(to_block)
+            .fetch_one(&self.db_pool)
+            .await
+            .context("Failed to count events")?;
+        
+        let rows = sqlx::query(&format!(
+            "SELECT block_hash, log_index, block_number, tx_hash, contract, address, event_name, signature,
+                    params, indexed_at
+             FROM events
+             {}
+             ORDER BY block_number DESC, log_index DESC
+             OFFSET $7 LIMIT $8",
+            FILTER
+        ))
+        .bind(&query.contract)
+        .bind(&address)
+        .bind(&query.event_name)
+        .bind(&tx_hash)
+        .bind(from_block)
+        .bind(to_block)
+        .bind(query.offset)
+        .bind(query.limit)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to query events")?;
+        
+        let events = rows.iter().map(event_from_row).collect::<Result<Vec<_>>>()?;
+        Ok(EventPage { total, events })
+    }
+}
+
+fn event_from_row(row: &PgRow) -> Result<IndexedEvent> {
+    let hash = |column: &str| -> Result<H256> {
+        let text: String = row.try_get(column)?;
+        text.parse().map_err(|e| anyhow!("Invalid {} {}: {}", column, text, e))
+    };
+    let address: String = row.try_get("address")?;
+    
+    Ok(IndexedEvent {
+        block_number: row.try_get::<i64, _>("block_number")? as u64,
+        block_hash: hash("block_hash")?,
+        tx_hash: hash("tx_hash")?,
+        log_index: row.try_get::<i32, _>("log_index")? as u64,
+        contract: row.try_get("contract")?,
+        address: address.parse().map_err(|e| anyhow!("Invalid address {}: {}", address, e))?,
+        event_name: row.try_get("event_name")?,
+        signature: row.try_get("signature")?,
+        params: row.try_get("params")?,
+        indexed_at: row.try_get("indexed_at")?,
+    })
+}
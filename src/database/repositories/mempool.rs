@@ -0,0 +1,309 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use ethers::types::{Address, Transaction, H256, U256};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, Row};
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    api::models,
+    database::{resilience::DeferredWrite, DbHealth, DbPool},
+};
+
+/// An observed pending transaction as stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolTransaction {
+    pub hash: String,
+    #[serde(with = "models::checksum")]
+    pub from: Address,
+    #[serde(with = "models::checksum_opt")]
+    pub to: Option<Address>,
+    #[serde(with = "models::wei")]
+    pub nonce: U256,
+    #[serde(with = "models::wei")]
+    pub value: U256,
+    pub gas: u64,
+    /// Legacy gas price or EIP-1559 max fee per gas
+    #[serde(with = "models::wei")]
+    pub gas_price: U256,
+    #[serde(with = "models::wei_opt")]
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub tx_type: u64,
+    /// First four bytes of calldata, absent for plain transfers
+    pub method_selector: Option<String>,
+    pub input_size: usize,
+    /// Source as displayed, e.g. `public_mempool` or `searcher:<key>`
+    pub source: String,
+    /// `pending`, `included`, `replaced` or `dropped`
+    pub status: String,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub removed_at: Option<DateTime<Utc>>,
+}
+
+/// Filters and pagination over stored transactions, newest first
+#[derive(Debug, Clone)]
+pub struct MempoolQuery {
+    pub from: Option<Address>,
+    /// Target contract
+    pub to: Option<Address>,
+    pub min_gas_price: Option<U256>,
+    /// Selector as `0x` followed by eight hex digits
+    pub method_selector: Option<String>,
+    /// Defaults to `pending`, the live snapshot
+    pub status: String,
+    pub offset: i64,
+    pub limit: i64,
+}
+
+/// A page of stored transactions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolPage {
+    /// Transactions matching the filter
+    pub total: i64,
+    pub transactions: Vec<MempoolTransaction>,
+}
+
+/// Selector of a call as stored, `None` when the calldata is shorter than one
+pub fn method_selector(input: &[u8]) -> Option<String> {
+    (input.len() >= 4).then(|| format!("0x{}", hex::encode(&input[..4])))
+}
+
+/// Persistence of observed pending transactions in `mempool_transactions`
+///
+/// Recording and status updates are non-critical: while the database is unreachable they are
+/// buffered for replay rather than failing transaction processing.
+#[derive(Clone)]
+pub struct MempoolRepository {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries and the buffer of deferred writes
+    health: DbHealth,
+}
+
+impl MempoolRepository {
+    /// Create a new mempool repository
+    pub fn new(db_pool: DbPool, health: DbHealth) -> Self {
+        Self { db_pool, health }
+    }
+    
+    /// Record a pending transaction, refreshing it if already seen
+    ///
+    /// Any other pending transaction from the same sender and nonce is marked replaced.
+    pub async fn record(&self, tx: &Transaction, source: &str) -> Result<()> {
+        let tx = Arc::new(tx.clone());
+        let source: Arc<str> = source.into();
+        let write: DeferredWrite = Arc::new(move |pool: DbPool| -> BoxFuture<'static, Result<()>> {
+            let (tx, source) = (tx.clone(), source.clone());
+            Box::pin(async move { record_transaction(&pool, &tx, &source).await })
+        });
+        
+        self.health.write_or_defer(&self.db_pool, write).await
+    }
+    
+    /// Mark pending transactions as included in a block
+    pub async fn mark_included(&self, tx_hashes: &[H256]) -> Result<()> {
+        let hashes: Vec<String> = tx_hashes.iter().map(|hash| format!("{:?}", hash)).collect();
+        let hashes = Arc::new(hashes);
+        let write: DeferredWrite = Arc::new(move |pool: DbPool| -> BoxFuture<'static, Result<()>> {
+            let hashes = hashes.clone();
+            Box::pin(async move {
+                sqlx::query(
+                    "UPDATE mempool_transactions SET status = 'included', removed_at = NOW()
+                     WHERE tx_hash = ANY($1) AND status = 'pending'",
+                )
+                .bind(hashes.as_slice())
+                .execute(&pool)
+                .await
+                .context("Failed to mark mempool transactions included")?;
+                Ok(())
+            })
+        });
+        
+        self.health.write_or_defer(&self.db_pool, write).await
+    }
+    
+    /// Mark transactions pending but unseen for `stale_after` as dropped, then delete
+    /// removed transactions older than `retention`; returns the rows dropped and deleted
+    pub async fn prune(&self, stale_after: Duration, retention: Duration) -> Result<(u64, u64)> {
+        self.health.retry(|| self.prune_once(stale_after, retention)).await
+    }
+    
+    /// Page through stored transactions matching a query
+    ///
+    /// When `viewer` is set, private flow is hidden except the viewer's own searcher submissions.
+    pub async fn query(&self, query: &MempoolQuery, viewer: Option<&str>) -> Result<MempoolPage> {
+        self.health.retry(|| self.query_once(query, viewer)).await
+    }
+    
+    /// Look up a stored transaction by hash, whatever its status
+    pub async fn get(&self, tx_hash: H256) -> Result<Option<MempoolTransaction>> {
+        self.health.retry(|| self.get_once(tx_hash)).await
+    }
+    
+    async fn get_once(&self, tx_hash: H256) -> Result<Option<MempoolTransaction>> {
+        let row = sqlx::query(
+            "SELECT tx_hash, from_address, to_address, nonce::TEXT AS nonce, value_wei::TEXT AS value_wei,
+                    gas, gas_price_wei::TEXT AS gas_price_wei,
+                    max_priority_fee_wei::TEXT AS max_priority_fee_wei, tx_type, method_selector,
+                    input_size, source, status, first_seen_at, last_seen_at, removed_at
+             FROM mempool_transactions
+             WHERE tx_hash = $1",
+        )
+        .bind(format!("{:?}", tx_hash))
+        .fetch_optional(&self.db_pool)
+        .await
+        .context("Failed to fetch mempool transaction")?;
+        
+        row.as_ref().map(transaction_from_row).transpose()
+    }
+    
+    async fn prune_once(&self, stale_after: Duration, retention: Duration) -> Result<(u64, u64)> {
+        let dropped = sqlx::query(
+            "UPDATE mempool_transactions SET status = 'dropped', removed_at = NOW()
+             WHERE status = 'pending' AND last_seen_at < NOW() - make_interval(secs => $1)",
+        )
+        .bind(stale_after.as_secs_f64())
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to drop stale mempool transactions")?;
+        
+        let deleted = sqlx::query(
+            "DELETE FROM mempool_transactions
+             WHERE status <> 'pending' AND removed_at < NOW() - make_interval(secs => $1)",
+        )
+        .bind(retention.as_secs_f64())
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to delete old mempool transactions")?;
+        
+        Ok((dropped.rows_affected(), deleted.rows_affected()))
+    }
+    
+    async fn query_once(&self, query: &MempoolQuery, viewer: Option<&str>) -> Result<MempoolPage> {
+        const FILTER: &str = "WHERE status = $1
+               AND ($2::TEXT IS NULL OR from_address = $2)
+               AND ($3::TEXT IS NULL OR to_address = $3)
+               AND ($4::NUMERIC IS NULL OR gas_price_wei >= $4::NUMERIC)
+               AND ($5::TEXT IS NULL OR method_selector = $5)
+               AND ($6::TEXT IS NULL OR (source <> 'private_api'
+                    AND (source NOT LIKE 'searcher:%' OR source = 'searcher:' || $6)))";
+        
+        let from = query.from.map(|from| format!("{:?}", from));
+        let to = query.to.map(|to| format!("{:?}", to));
+        let min_gas_price = query.min_gas_price.map(|price| price.to_string());
+        let selector = query.method_selector.as_deref().map(str::to_ascii_lowercase);
+        
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM mempool_transactions {}", FILTER))
+            .bind(&query.status)
+            .bind(&from)
+            .bind(&to)
+            .bind(&min_gas_price)
+            .bind(&selector)
+            .bind(viewer)
+            .fetch_one(&self.db_pool)
+            .await
+            .context("Failed to count mempool transactions")?;
+        
+        let rows = sqlx::query(&format!(
+            "SELECT tx_hash, from_address, to_address, nonce::TEXT AS nonce, value_wei::TEXT AS value_wei,
+                    gas, gas_price_wei::TEXT AS gas_price_wei,
+                    max_priority_fee_wei::TEXT AS max_priority_fee_wei, tx_type, method_selector,
+                    input_size, source, status, first_seen_at, last_seen_at, removed_at
+             FROM mempool_transactions
+             {}
+             ORDER BY first_seen_at DESC, tx_hash
+             OFFSET $7 LIMIT $8",
+            FILTER
+        ))
+        .bind(&query.status)
+        .bind(&from)
+        .bind(&to)
+        .bind(&min_gas_price)
+        .bind(&selector)
+        .bind(viewer)
+        .bind(query.offset)
+        .bind(query.limit)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to query mempool transactions")?;
+        
+        let transactions = rows.iter().map(transaction_from_row).collect::<Result<Vec<_>>>()?;
+        Ok(MempoolPage { total, transactions })
+    }
+}
+
+/// Insert or refresh a pending transaction and mark the ones it replaced
+async fn record_transaction(db_pool: &DbPool, tx: &Transaction, source: &str) -> Result<()> {
+    let tx_hash = format!("{:?}", tx.hash);
+    let from = format!("{:?}", tx.from);
+    let gas_price = tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default();
+    
+    sqlx::query(
+        "INSERT INTO mempool_transactions
+             (tx_hash, from_address, to_address, nonce, value_wei, gas, gas_price_wei,
+              max_priority_fee_wei, tx_type, method_selector, input_size, source)
+         VALUES ($1, $2, $3, $4::NUMERIC, $5::NUMERIC, $6, $7::NUMERIC, $8::NUMERIC, $9, $10, $11, $12)
+         ON CONFLICT (tx_hash) DO UPDATE SET last_seen_at = NOW()",
+    )
+    .bind(&tx_hash)
+    .bind(&from)
+    .bind(tx.to.map(|to| format!("{:?}", to)))
+    .bind(tx.nonce.to_string())
+    .bind(tx.value.to_string())
+    .bind(tx.gas.min(U256::from(i64::MAX)).as_u64() as i64)
+    .bind(gas_price.to_string())
+    .bind(tx.max_priority_fee_per_gas.map(|fee| fee.to_string()))
+    .bind(tx.transaction_type.map_or(0, |t| t.as_u64()) as i16)
+    .bind(method_selector(&tx.input))
+    .bind(tx.input.len() as i32)
+    .bind(source)
+    .execute(db_pool)
+    .await
+    .context("Failed to record mempool transaction")?;
+    
+    sqlx::query(
+        "UPDATE mempool_transactions SET status = 'replaced', removed_at = NOW()
+         WHERE from_address = $1 AND nonce = $2::NUMERIC AND tx_hash <> $3 AND status = 'pending'",
+    )
+    .bind(&from)
+    .bind(tx.nonce.to_string())
+    .bind(&tx_hash)
+    .execute(db_pool)
+    .await
+    .context("Failed to mark replaced mempool transactions")?;
+    
+    Ok(())
+}
+
+fn transaction_from_row(row: &PgRow) -> Result<MempoolTransaction> {
+    let max_priority_fee: Option<String> = row.try_get("max_priority_fee_wei")?;
+    let address = |text: String| text.parse::<Address>().map_err(|e| anyhow!("Invalid address {}: {}", text, e));
+    
+    Ok(MempoolTransaction {
+        hash: row.try_get("tx_hash")?,
+        from: address(row.try_get("from_address")?)?,
+        to: row.try_get::<Option<String>, _>("to_address")?.map(address).transpose()?,
+        nonce: wei(row, "nonce")?,
+        value: wei(row, "value_wei")?,
+        gas: row.try_get::<i64, _>("gas")? as u64,
+        gas_price: wei(row, "gas_price_wei")?,
+        max_priority_fee_per_gas: max_priority_fee
+            .map(|fee| U256::from_dec_str(&fee).map_err(|e| anyhow!("Invalid priority fee: {}", e)))
+            .transpose()?,
+        tx_type: row.try_get::<i16, _>("tx_type")? as u64,
+        method_selector: row.try_get("method_selector")?,
+        input_size: row.try_get::<i32, _>("input_size")? as usize,
+        source: row.try_get("source")?,
+        status: row.try_get("status")?,
+        first_seen_at: row.try_get("first_seen_at")?,
+        last_seen_at: row.try_get("last_seen_at")?,
+        removed_at: row.try_get("removed_at")?,
+    })
+}
+
+fn wei(row: &PgRow, column: &str) -> Result<U256> {
+    let text: String = row.try_get(column)?;
+    U256::from_dec_str(&text).map_err(|e| anyhow!("Invalid amount in {}: {}", column, e))
+}
@@ -0,0 +1,9 @@
+pub mod blocks;
+pub mod events;
+pub mod mempool;
+pub mod tokens;
+
+pub use blocks::BlockBodyRepository;
+pub use events::EventRepository;
+pub use mempool::MempoolRepository;
+pub use tokens::TokenRepository;
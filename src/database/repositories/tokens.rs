@@ -0,0 +1,379 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use ethers::{
+    abi::{self, ParamType},
+    types::{Address, Bytes, U256},
+};
+use futures::future::BoxFuture;
+use sqlx::Row;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::{debug, warn};
+
+use crate::{
+    blockchain::{
+        client::{calldata, first_word},
+        BlockchainClient,
+    },
+    config::CacheSettings,
+    database::{DbHealth, DbPool, RedisPool},
+    models::TokenMetadata,
+    utils::cache::BoundedCache,
+};
+
+/// Redis key prefix token metadata is cached under, shared across builder instances
+const CACHE_KEY_PREFIX: &str = "tokens:";
+
+/// How long a total supply reading is served before it is read from chain again
+const TOTAL_SUPPLY_MAX_AGE: Duration = Duration::from_secs(3600);
+
+/// How long a contract that couldn't be read as an ERC-20 is remembered as one
+const MISS_RETRY_AFTER: Duration = Duration::from_secs(600);
+
+/// Views read from each token, in the order they go into the multicall
+const METADATA_CALLS: [&str; 3] = ["decimals()", "totalSupply()", "symbol()"];
+
+/// A token lookup held in process
+#[derive(Clone)]
+enum CachedToken {
+    Known(TokenMetadata),
+    /// A contract that answered but couldn't be read as an ERC-20, and when it was asked
+    NotToken(Instant),
+}
+
+impl CachedToken {
+    fn is_fresh(&self) -> bool {
+        match self {
+            CachedToken::Known(metadata) => is_fresh(metadata),
+            CachedToken::NotToken(checked_at) => checked_at.elapsed() < MISS_RETRY_AFTER,
+        }
+    }
+}
+
+/// Token registry: ERC-20 metadata read from chain on first use
+///
+/// Lookups go through the bounded process cache, Redis and Postgres before reading the
+/// contracts, all in one multicall, and a token read from chain is stored in all three.
+/// Symbol and decimals never change, so only the total supply goes stale; a token is read again
+/// once its reading is older than an hour. Contracts that aren't ERC-20s are remembered as
+/// misses in process and Redis for ten minutes, so they aren't read again on every lookup.
+#[derive(Clone)]
+pub struct TokenRepository {
+    db_pool: DbPool,
+    health: DbHealth,
+    redis: RedisPool,
+    blockchain_client: Arc<BlockchainClient>,
+    cache: Arc<BoundedCache<Address, CachedToken>>,
+}
+
+impl TokenRepository {
+    pub fn new(
+        db_pool: DbPool,
+        health: DbHealth,
+        redis: RedisPool,
+        blockchain_client: Arc<BlockchainClient>,
+        cache: &CacheSettings,
+    ) -> Self {
+        Self {
+            db_pool,
+            health,
+            redis,
+            blockchain_client,
+            cache: Arc::new(BoundedCache::new("tokens", cache)),
+        }
+    }
+    
+    /// Metadata of a token, read from chain when not known or stale
+    pub async fn get(&self, token: Address) -> Result<TokenMetadata> {
+        self.lookup(&[token], true)
+            .await
+            .remove(&token)
+            .ok_or_else(|| anyhow!("{:?} could not be read as an ERC-20 token", token))
+    }
+    
+    /// Metadata of a token as `get` finds it, without storing a chain read in Postgres
+    ///
+    /// For lookups of arbitrary addresses from callers that shouldn't write rows.
+    pub async fn peek(&self, token: Address) -> Result<TokenMetadata> {
+        self.lookup(&[token], false)
+            .await
+            .remove(&token)
+            .ok_or_else(|| anyhow!("{:?} could not be read as an ERC-20 token", token))
+    }
+    
+    /// Metadata of tokens that could be read, leaving out the ones that couldn't
+    pub async fn get_many(&self, tokens: &[Address]) -> HashMap<Address, TokenMetadata> {
+        self.lookup(tokens, true).await
+    }
+    
+    /// Look tokens up in each tier in turn, reading the ones no tier holds from chain
+    ///
+    /// A chain read that failed outright isn't remembered, so those tokens are asked again on
+    /// the next lookup.
+    async fn lookup(&self, tokens: &[Address], persist: bool) -> HashMap<Address, TokenMetadata> {
+        let mut found = HashMap::with_capacity(tokens.len());
+        let mut missing = Vec::new();
+        for &token in tokens {
+            if found.contains_key(&token) || missing.contains(&token) {
+                continue;
+            }
+            match self.cache.get(&token).filter(CachedToken::is_fresh) {
+                Some(CachedToken::Known(metadata)) => {
+                    found.insert(token, metadata);
+                }
+                Some(CachedToken::NotToken(_)) => {}
+                None => missing.push(token),
+            }
+        }
+        if missing.is_empty() {
+            return found;
+        }
+        
+        let missing = self.take_from_redis(missing, &mut found).await;
+        let missing = self.take_from_database(missing, &mut found).await;
+        if missing.is_empty() {
+            return found;
+        }
+        
+        let fetched = match self.fetch(&missing).await {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                debug!("Failed to read {} tokens from chain: {}", missing.len(), e);
+                return found;
+            }
+        };
+        for (token, metadata) in missing.into_iter().zip(fetched) {
+            match metadata {
+                Some(metadata) => {
+                    if persist {
+                        if let Err(e) = self.persist(&metadata).await {
+                            warn!("Failed to store token {:?}: {}", token, e);
+                        }
+                    }
+                    self.store(&metadata).await;
+                    found.insert(token, metadata);
+                }
+                None => self.store_miss(token).await,
+            }
+        }
+        found
+    }
+    
+    /// Move the tokens Redis holds into `found`, returning the rest
+    async fn take_from_redis(
+        &self,
+        tokens: Vec<Address>,
+        found: &mut HashMap<Address, TokenMetadata>,
+    ) -> Vec<Address> {
+        let mut cmd = redis::cmd("MGET");
+        for token in &tokens {
+            cmd.arg(cache_key(*token));
+        }
+        let cached = match self.redis.cached::<Vec<Option<String>>>(&cmd).await {
+            Some(cached) if cached.len() == tokens.len() => cached,
+            _ => return tokens,
+        };
+        
+        let mut missing = Vec::new();
+        for (token, raw) in tokens.into_iter().zip(cached) {
+            match raw.and_then(|raw| serde_json::from_str::<Option<TokenMetadata>>(&raw).ok()) {
+                Some(Some(metadata)) if is_fresh(&metadata) => {
+                    self.cache.insert(token, CachedToken::Known(metadata.clone()));
+                    found.insert(token, metadata);
+                }
+                Some(None) => self.cache.insert(token, CachedToken::NotToken(Instant::now())),
+                _ => missing.push(token),
+            }
+        }
+        missing
+    }
+    
+    /// Move the fresh tokens Postgres holds into `found`, returning the rest
+    async fn take_from_database(
+        &self,
+        tokens: Vec<Address>,
+        found: &mut HashMap<Address, TokenMetadata>,
+    ) -> Vec<Address> {
+        if tokens.is_empty() {
+            return tokens;
+        }
+        
+        // A database outage shouldn't stop lookups the chain can still answer
+        let mut stored = match self.health.retry(|| self.load(&tokens)).await {
+            Ok(stored) => stored,
+            Err(e) => {
+                debug!("Failed to load {} tokens: {}", tokens.len(), e);
+                return tokens;
+            }
+        };
+        
+        let mut missing = Vec::new();
+        for token in tokens {
+            match stored.remove(&token).filter(is_fresh) {
+                Some(metadata) => {
+                    self.store(&metadata).await;
+                    found.insert(token, metadata);
+                }
+                None => missing.push(token),
+            }
+        }
+        missing
+    }
+    
+    async fn load(&self, tokens: &[Address]) -> Result<HashMap<Address, TokenMetadata>> {
+        let addresses: Vec<String> = tokens.iter().map(|token| format!("{:?}", token)).collect();
+        let rows = sqlx::query(
+            "SELECT address, symbol, decimals, total_supply::TEXT AS total_supply, fetched_at
+             FROM tokens WHERE address = ANY($1)",
+        )
+        .bind(addresses)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to load token metadata")?;
+        
+        rows.into_iter()
+            .map(|row| {
+                let address: String = row.try_get("address")?;
+                let address: Address = address
+                    .parse()
+                    .map_err(|e| anyhow!("Invalid token address {}: {}", address, e))?;
+                let total_supply: String = row.try_get("total_supply")?;
+                let metadata = TokenMetadata {
+                    address,
+                    symbol: row.try_get("symbol")?,
+                    decimals: row.try_get::<i16, _>("decimals")? as u8,
+                    total_supply: U256::from_dec_str(&total_supply)
+                        .map_err(|e| anyhow!("Invalid total supply {}: {}", total_supply, e))?,
+                    fetched_at: row.try_get("fetched_at")?,
+                };
+                Ok((address, metadata))
+            })
+            .collect()
+    }
+    
+    /// Store metadata read from chain, buffered while the database is down
+    async fn persist(&self, metadata: &TokenMetadata) -> Result<()> {
+        let metadata = metadata.clone();
+        let write = Arc::new(move |pool: DbPool| -> BoxFuture<'static, Result<()>> {
+            let metadata = metadata.clone();
+            Box::pin(async move { upsert(&pool, &metadata).await })
+        });
+        self.health.write_or_defer(&self.db_pool, write).await
+    }
+    
+    /// Cache metadata in process and, unless Redis is degraded, in Redis until it goes stale
+    async fn store(&self, metadata: &TokenMetadata) {
+        self.cache.insert(metadata.address, CachedToken::Known(metadata.clone()));
+        
+        let age = Utc::now().signed_duration_since(metadata.fetched_at).to_std().unwrap_or_default();
+        let ttl = TOTAL_SUPPLY_MAX_AGE.saturating_sub(age).as_secs().max(1);
+        if let Ok(raw) = serde_json::to_string(metadata) {
+            let mut cmd = redis::cmd("SET");
+            cmd.arg(cache_key(metadata.address)).arg(raw).arg("EX").arg(ttl);
+            self.redis.cached::<()>(&cmd).await;
+        }
+    }
+    
+    /// Remember a contract that isn't an ERC-20, in process and in Redis as `null`
+    async fn store_miss(&self, token: Address) {
+        self.cache.insert(token, CachedToken::NotToken(Instant::now()));
+        
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(cache_key(token)).arg("null").arg("EX").arg(MISS_RETRY_AFTER.as_secs());
+        self.redis.cached::<()>(&cmd).await;
+    }
+    
+    /// Read tokens' metadata from their contracts in one multicall, `None` for a contract that
+    /// isn't an ERC-20
+    async fn fetch(&self, tokens: &[Address]) -> Result<Vec<Option<TokenMetadata>>> {
+        let calls: Vec<(Address, Bytes)> = tokens
+            .iter()
+            .flat_map(|&token| METADATA_CALLS.iter().map(move |signature| (token, calldata(signature, &[]))))
+            .collect();
+        let outputs = self.blockchain_client.multicall(&calls).await?;
+        let fetched_at = Utc::now();
+        
+        Ok(tokens
+            .iter()
+            .zip(outputs.chunks(METADATA_CALLS.len()))
+            .map(|(&token, outputs)| match decode_metadata(token, outputs, fetched_at) {
+                Ok(metadata) => {
+                    metrics::counter!("token_metadata_fetches_total", 1);
+                    debug!("Read token {:?} ({}) from chain", token, metadata.symbol);
+                    Some(metadata)
+                }
+                Err(e) => {
+                    debug!("{:?} is not an ERC-20 token: {}", token, e);
+                    None
+                }
+            })
+            .collect())
+    }
+}
+
+/// Metadata from the outputs of a token's `METADATA_CALLS`
+fn decode_metadata(token: Address, outputs: &[Option<Bytes>], fetched_at: DateTime<Utc>) -> Result<TokenMetadata> {
+    let output = |call: usize| {
+        outputs
+            .get(call)
+            .and_then(Option::as_ref)
+            .ok_or_else(|| anyhow!("{} reverted", METADATA_CALLS[call]))
+    };
+    
+    let decimals = first_word(METADATA_CALLS[0], output(0)?)?;
+    if decimals > U256::from(u8::MAX) {
+        return Err(anyhow!("Token reports {} decimals", decimals));
+    }
+    
+    Ok(TokenMetadata {
+        address: token,
+        symbol: decode_symbol(output(2)?),
+        decimals: decimals.as_u32() as u8,
+        total_supply: first_word(METADATA_CALLS[1], output(1)?)?,
+        fetched_at,
+    })
+}
+
+async fn upsert(db_pool: &DbPool, metadata: &TokenMetadata) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO tokens (address, symbol, decimals, total_supply, fetched_at)
+         VALUES ($1, $2, $3, $4::NUMERIC, $5)
+         ON CONFLICT (address) DO UPDATE
+         SET symbol = EXCLUDED.symbol, decimals = EXCLUDED.decimals,
+             total_supply = EXCLUDED.total_supply, fetched_at = EXCLUDED.fetched_at",
+    )
+    .bind(format!("{:?}", metadata.address))
+    .bind(&metadata.symbol)
+    .bind(metadata.decimals as i16)
+    .bind(metadata.total_supply.to_string())
+    .bind(metadata.fetched_at)
+    .execute(db_pool)
+    .await
+    .context("Failed to store token metadata")?;
+    
+    Ok(())
+}
+
+/// Symbol as a string, or as the `bytes32` some early tokens such as MKR return
+fn decode_symbol(output: &[u8]) -> String {
+    if let Ok(tokens) = abi::decode(&[ParamType::String], output) {
+        if let Some(symbol) = tokens.into_iter().next().and_then(|token| token.into_string()) {
+            return symbol;
+        }
+    }
+    
+    let word = &output[..output.len().min(32)];
+    String::from_utf8_lossy(word).trim_end_matches('\0').to_string()
+}
+
+fn is_fresh(metadata: &TokenMetadata) -> bool {
+    let age = Utc::now().signed_duration_since(metadata.fetched_at);
+    age.to_std().map_or(true, |age| age < TOTAL_SUPPLY_MAX_AGE)
+}
+
+fn cache_key(token: Address) -> String {
+    format!("{}{:?}", CACHE_KEY_PREFIX, token)
+}
@@ -0,0 +1,234 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, future::Future, sync::Arc, time::Duration};
+use tracing::{info, warn};
+
+use crate::{config::DatabaseConfig, database::DbPool};
+
+/// SQLSTATEs a failover or restart surfaces as: admin and crash shutdown, startup, and
+/// writes against a promoted-away primary that is now read-only
+const FAILOVER_SQLSTATES: &[&str] = &["57P01", "57P02", "57P03", "25006"];
+
+/// A write replayed once the database is back, as often as it keeps losing the connection
+pub type DeferredWrite = Arc<dyn Fn(DbPool) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// Whether an error means the connection to Postgres was lost rather than the query failing
+pub fn is_connection_loss(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<sqlx::Error>())
+        .any(|error| match error {
+            sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed => true,
+            sqlx::Error::Database(error) => error
+                .code()
+                .map_or(false, |code| code.starts_with("08") || FAILOVER_SQLSTATES.contains(&code.as_ref())),
+            _ => false,
+        })
+}
+
+/// Database health as reported in readiness
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbHealthReport {
+    pub healthy: bool,
+    /// When the connection was lost, while it is
+    pub down_since: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// Non-critical writes waiting for the database
+    pub buffered_writes: usize,
+    /// Writes dropped because the buffer was full
+    pub dropped_writes: u64,
+}
+
+struct HealthState {
+    down_since: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+    buffered: VecDeque<DeferredWrite>,
+    dropped: u64,
+}
+
+/// Connection-loss handling shared by the repositories
+///
+/// Queries that lose their connection are retried with doubling backoff, which rides out a
+/// failover onto a promoted replica. Non-critical writes made while the database is down are
+/// kept in a bounded buffer, the oldest dropped when it is full, and replayed in order once it
+/// is back; everything else still fails, now recognizably as an outage.
+#[derive(Clone)]
+pub struct DbHealth {
+    state: Arc<Mutex<HealthState>>,
+    max_retries: u32,
+    backoff: Duration,
+    buffer_capacity: usize,
+}
+
+impl DbHealth {
+    pub fn new(config: &DatabaseConfig) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(HealthState {
+                down_since: None,
+                last_error: None,
+                buffered: VecDeque::new(),
+                dropped: 0,
+            })),
+            max_retries: config.max_retries,
+            backoff: Duration::from_millis(config.retry_backoff_ms),
+            buffer_capacity: config.write_buffer_capacity,
+        }
+    }
+    
+    /// Whether the last operation reached the database
+    pub fn is_healthy(&self) -> bool {
+        self.state.lock().down_since.is_none()
+    }
+    
+    pub fn report(&self) -> DbHealthReport {
+        let state = self.state.lock();
+        DbHealthReport {
+            healthy: state.down_since.is_none(),
+            down_since: state.down_since,
+            last_error: state.last_error.clone(),
+            buffered_writes: state.buffered.len(),
+            dropped_writes: state.dropped,
+        }
+    }
+    
+    /// Run an operation, retrying it while it fails on connection loss
+    ///
+    /// While the database is already known to be down only one attempt is made, so callers
+    /// don't stack up retries against an outage.
+    pub async fn retry<T, F, Fut>(&self, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let attempts = if self.is_healthy() { self.max_retries + 1 } else { 1 };
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Ok(value) => {
+                    self.record_success();
+                    return Ok(value);
+                }
+                Err(e) if is_connection_loss(&e) => {
+                    if attempt >= attempts {
+                        self.record_loss(&e);
+                        return Err(e);
+                    }
+                    metrics::counter!("db_retries_total", 1);
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    
+    /// Run a non-critical write, buffering it for replay if the database is unreachable
+    ///
+    /// While the database is down, or older writes still wait for replay, writes are buffered
+    /// without being tried so they stay in order.
+    pub async fn write_or_defer(&self, pool: &DbPool, write: DeferredWrite) -> Result<()> {
+        let waiting = {
+            let state = self.state.lock();
+            state.down_since.is_some() || !state.buffered.is_empty()
+        };
+        if waiting {
+            self.defer(write);
+            return Ok(());
+        }
+        
+        match self.retry(|| write(pool.clone())).await {
+            Err(e) if is_connection_loss(&e) => {
+                self.defer(write);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+    
+    /// Replay buffered writes in order, stopping if the connection is lost again
+    pub async fn flush(&self, pool: &DbPool) -> Result<usize> {
+        let mut replayed = 0;
+        loop {
+            let Some(write) = self.state.lock().buffered.pop_front() else {
+                break;
+            };
+            match write(pool.clone()).await {
+                Ok(()) => {
+                    self.record_success();
+                    replayed += 1;
+                }
+                Err(e) if is_connection_loss(&e) => {
+                    self.state.lock().buffered.push_front(write);
+                    self.record_loss(&e);
+                    break;
+                }
+                // Replaying can't fix a write the database rejects
+                Err(e) => warn!("Dropping buffered database write: {}", e),
+            }
+        }
+        metrics::gauge!("db_buffered_writes", self.state.lock().buffered.len() as f64);
+        
+        Ok(replayed)
+    }
+    
+    /// Check the connection in a single attempt, recovering from an outage when it answers
+    pub async fn probe(&self, pool: &DbPool) -> bool {
+        match sqlx::query("SELECT 1").execute(pool).await.map_err(anyhow::Error::from) {
+            Ok(_) => {
+                self.record_success();
+                true
+            }
+            Err(e) => {
+                if is_connection_loss(&e) {
+                    self.record_loss(&e);
+                }
+                false
+            }
+        }
+    }
+    
+    fn defer(&self, write: DeferredWrite) {
+        let mut state = self.state.lock();
+        if state.buffered.len() >= self.buffer_capacity {
+            state.buffered.pop_front();
+            state.dropped += 1;
+            metrics::counter!("db_buffered_writes_dropped_total", 1);
+        }
+        if self.buffer_capacity > 0 {
+            state.buffered.push_back(write);
+        }
+        metrics::gauge!("db_buffered_writes", state.buffered.len() as f64);
+    }
+    
+    fn record_success(&self) {
+        let mut state = self.state.lock();
+        if let Some(down_since) = state.down_since.take() {
+            let outage = Utc::now().signed_duration_since(down_since);
+            info!(
+                "Database connection restored after {}s, {} writes buffered",
+                outage.num_seconds(),
+                state.buffered.len()
+            );
+            metrics::gauge!("db_healthy", 1.0);
+        }
+    }
+    
+    fn record_loss(&self, error: &anyhow::Error) {
+        let mut state = self.state.lock();
+        if state.down_since.is_none() {
+            warn!("Lost the database connection: {:#}", error);
+            state.down_since = Some(Utc::now());
+            metrics::gauge!("db_healthy", 0.0);
+        }
+        state.last_error = Some(format!("{:#}", error));
+    }
+}
@@ -0,0 +1,18 @@
+//! MEV Capture: block building, MEV extraction and liquid staking services for Monad
+//!
+//! The `mev-capture` binary runs the services; `client` is a typed client of their public
+//! API for searchers and integrators.
+
+pub mod api;
+pub mod blockchain;
+pub mod client;
+pub mod commands;
+pub mod config;
+pub mod core;
+pub mod database;
+pub mod models;
+pub mod relay;
+pub mod services;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod utils;
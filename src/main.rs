@@ -4,22 +4,22 @@ use anyhow::Result;
 use tokio::signal;
 use tracing::{error, info};
 
-mod api;
-mod blockchain;
-mod config;
-mod core;
-mod database;
-mod models;
-mod services;
-mod utils;
+use mev_capture::{api, blockchain, commands, config, database, services, utils};
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Run a CLI subcommand if one was given
+    let mut args = config::cli::parse_args();
+    if let Some(command) = args.command.take() {
+        return commands::run(command, &args).await;
+    }
+    
     // Initialize configuration
-    let config = config::load()?;
+    let config = config::load_from_args(&args)?;
     
     // Setup logging
     utils::logging::init(&config.logging)?;
+    utils::metrics::install_recorder()?;
     
     info!("Starting MEV Capture v{}", env!("CARGO_PKG_VERSION"));
     
@@ -28,7 +28,8 @@ async fn main() -> Result<()> {
     let redis = database::connect_redis(&config.redis).await?;
     
     // Initialize blockchain client
-    let blockchain_client = blockchain::create_client(&config.blockchain).await?;
+    let blockchain_client =
+        blockchain::create_client(&config.blockchain, &config.caches, Some(db_pool.clone())).await?;
     
     // Initialize core services
     let services = services::ServiceContext::new(
@@ -40,6 +41,14 @@ async fn main() -> Result<()> {
     
     let services = Arc::new(services);
     
+    // Resume in-flight work left behind by a previous crash
+    if let Err(e) = services.recovery_service.restore().await {
+        error!("Failed to recover in-flight state: {}", e);
+    }
+    
+    // Start periodic background jobs
+    services.start_background_tasks();
+    
     // Initialize API server
     let api_server = api::start_server(
         config.api.bind_address.clone(),
@@ -54,12 +63,19 @@ async fn main() -> Result<()> {
         services.clone(),
     ).await?;
     
-    // Wait for shutdown signal
-    match signal::ctrl_c().await {
-        Ok(()) => info!("Shutdown signal received, starting graceful shutdown"),
-        Err(err) => error!("Failed to listen for shutdown signal: {}", err),
+    // Wait for a shutdown signal or an admin drain request
+    tokio::select! {
+        result = signal::ctrl_c() => match result {
+            Ok(()) => info!("Shutdown signal received, starting graceful shutdown"),
+            Err(err) => error!("Failed to listen for shutdown signal: {}", err),
+        },
+        _ = terminate_signal() => info!("SIGTERM received, starting graceful shutdown"),
+        _ = services.drain_controller.requested() => info!("Drain requested, starting graceful shutdown"),
     }
     
+    // Drain in-flight work before tearing anything down
+    services.drain().await?;
+    
     // Graceful shutdown
     api_server.shutdown().await?;
     monitor_handle.shutdown().await?;
@@ -68,3 +84,23 @@ async fn main() -> Result<()> {
     info!("Shutdown complete");
     Ok(())
 }
+
+
+/// Wait for SIGTERM, as sent by orchestrators during rolling deploys
+#[cfg(unix)]
+async fn terminate_signal() {
+    match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+        Ok(mut sigterm) => {
+            sigterm.recv().await;
+        }
+        Err(err) => {
+            error!("Failed to listen for SIGTERM: {}", err);
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn terminate_signal() {
+    std::future::pending::<()>().await
+}
@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use ethers::{
+    types::{Address, Sign, I256, U256},
+    utils::format_units,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{api::models, core::amm::to_f64};
+
+/// ERC-20 metadata as read from the token contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenMetadata {
+    #[serde(with = "models::checksum")]
+    pub address: Address,
+    pub symbol: String,
+    pub decimals: u8,
+    /// Raw units as of `fetched_at`
+    #[serde(with = "models::wei")]
+    pub total_supply: U256,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// A signed token amount in whole units
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenAmount {
+    #[serde(with = "models::checksum")]
+    pub token: Address,
+    pub symbol: String,
+    /// Exact decimal amount, e.g. `-1250.5`
+    pub amount: String,
+}
+
+impl TokenMetadata {
+    /// A raw amount in whole units, as a float for display and pricing only
+    pub fn units(&self, raw: U256) -> f64 {
+        to_f64(raw) / 10f64.powi(self.decimals as i32)
+    }
+    
+    /// A raw amount as an exact decimal string in whole units
+    pub fn format(&self, raw: U256) -> String {
+        let formatted = format_units(raw, self.decimals as u32).unwrap_or_else(|_| raw.to_string());
+        match formatted.split_once('.') {
+            Some((whole, fraction)) => match fraction.trim_end_matches('0') {
+                "" => whole.to_string(),
+                fraction => format!("{}.{}", whole, fraction),
+            },
+            None => formatted,
+        }
+    }
+    
+    /// A signed raw delta, as simulations report them, in whole units
+    pub fn amount(&self, delta: &str) -> Option<TokenAmount> {
+        let (sign, raw) = I256::from_dec_str(delta).ok()?.into_sign_and_abs();
+        let sign = if sign == Sign::Negative && !raw.is_zero() { "-" } else { "" };
+        
+        Some(TokenAmount {
+            token: self.address,
+            symbol: self.symbol.clone(),
+            amount: format!("{}{}", sign, self.format(raw)),
+        })
+    }
+}
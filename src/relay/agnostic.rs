@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+
+use super::{
+    classify_error, BidSubmission, BuilderApi, ProposerDuty, RelayAdapter, RelayCapabilities, RelayError,
+    SubmissionReceipt,
+};
+use crate::config::RelayConfig;
+
+/// Agnostic relay
+pub struct AgnosticRelay {
+    api: BuilderApi,
+}
+
+impl AgnosticRelay {
+    pub fn new(config: &RelayConfig, http: reqwest::Client) -> Self {
+        Self {
+            api: BuilderApi::new(config, http, HeaderMap::new(), 10),
+        }
+    }
+}
+
+#[async_trait]
+impl RelayAdapter for AgnosticRelay {
+    fn name(&self) -> &str {
+        &self.api.name
+    }
+    
+    fn capabilities(&self) -> RelayCapabilities {
+        RelayCapabilities {
+            cancellations: false,
+            max_submissions_per_second: self.api.limiter_rate(),
+        }
+    }
+    
+    async fn submit_bid(&self, bid: &BidSubmission, _cancellable: bool) -> Result<SubmissionReceipt, RelayError> {
+        // Without cancellations every submission is final for the relay
+        self.api.submit_block(bid, &[], classify_error).await
+    }
+    
+    async fn proposer_duties(&self) -> Result<Vec<ProposerDuty>, RelayError> {
+        self.api.get_validators().await
+    }
+}
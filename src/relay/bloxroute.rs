@@ -0,0 +1,55 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+
+use super::{
+    classify_error, BidSubmission, BuilderApi, ProposerDuty, RelayAdapter, RelayCapabilities, RelayError,
+    SubmissionReceipt,
+};
+use crate::config::RelayConfig;
+
+/// bloXroute relay, which requires an authorization header on every submission
+pub struct BloxrouteRelay {
+    api: BuilderApi,
+}
+
+impl BloxrouteRelay {
+    pub fn new(config: &RelayConfig, http: reqwest::Client) -> Result<Self> {
+        let auth = config
+            .auth_header
+            .as_ref()
+            .ok_or_else(|| anyhow!("Relay {} requires auth_header", config.name))?;
+        
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(auth).context("Invalid bloXroute auth header")?,
+        );
+        
+        Ok(Self {
+            api: BuilderApi::new(config, http, headers, 10),
+        })
+    }
+}
+
+#[async_trait]
+impl RelayAdapter for BloxrouteRelay {
+    fn name(&self) -> &str {
+        &self.api.name
+    }
+    
+    fn capabilities(&self) -> RelayCapabilities {
+        RelayCapabilities {
+            cancellations: false,
+            max_submissions_per_second: self.api.limiter_rate(),
+        }
+    }
+    
+    async fn submit_bid(&self, bid: &BidSubmission, _cancellable: bool) -> Result<SubmissionReceipt, RelayError> {
+        self.api.submit_block(bid, &[], classify_error).await
+    }
+    
+    async fn proposer_duties(&self) -> Result<Vec<ProposerDuty>, RelayError> {
+        self.api.get_validators().await
+    }
+}
@@ -0,0 +1,447 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::{
+    types::{Address, Bytes, H256, U256},
+    utils::keccak256,
+};
+use reqwest::{header::HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    config::{BlockBuildingConfig, RelayConfig},
+    services::{subsidy::Subsidy, transaction::InclusionCandidate},
+};
+
+pub mod agnostic;
+pub mod bloxroute;
+pub mod flashbots;
+pub mod ultrasound;
+
+/// Builder API path for block submissions
+const SUBMIT_BLOCK_PATH: &str = "/relay/v1/builder/blocks";
+
+/// Builder API path for proposers registered for the current and next epoch
+const VALIDATORS_PATH: &str = "/relay/v1/builder/validators";
+
+/// A signed block bid ready to send to relays
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BidSubmission {
+    pub slot: u64,
+    pub parent_hash: H256,
+    pub block_hash: H256,
+    /// Value paid to the proposer in wei, including any subsidy
+    pub value: U256,
+    /// Amount bid above extracted value, if this is a subsidized bid
+    #[serde(default)]
+    pub subsidy: Option<Subsidy>,
+    /// Builder API `SignedBidSubmission` body, already signed
+    pub signed_submission: serde_json::Value,
+}
+
+impl BidSubmission {
+    /// Hashes of the block's transactions, read from the signed execution payload
+    pub fn tx_hashes(&self) -> Vec<H256> {
+        let transactions = self.signed_submission["execution_payload"]["transactions"].clone();
+        serde_json::from_value::<Vec<Bytes>>(transactions)
+            .unwrap_or_default()
+            .iter()
+            .map(|raw| H256::from(keccak256(raw)))
+            .collect()
+    }
+    
+    /// Header extra-data, read from the signed execution payload
+    pub fn extra_data(&self) -> Option<Bytes> {
+        serde_json::from_value(self.signed_submission["execution_payload"]["extra_data"].clone()).ok()
+    }
+    
+    /// Public key the bid is signed with, read from the signed bid trace
+    pub fn builder_pubkey(&self) -> Option<&str> {
+        self.signed_submission["message"]["builder_pubkey"].as_str()
+    }
+}
+
+/// What the block builder assembles and signs a slot's bid from
+#[derive(Debug, Clone)]
+pub struct BidRequest<'a> {
+    pub slot: u64,
+    /// Head the block is built on
+    pub parent_hash: H256,
+    /// Proposer registration, giving the fee recipient and gas limit
+    pub duty: &'a ProposerDuty,
+    /// Block template, in block order
+    pub transactions: &'a [InclusionCandidate],
+    /// Value paid to the proposer in wei, including any subsidy
+    pub value: U256,
+    /// Amount bid above extracted value, carried into the submission
+    pub subsidy: Option<Subsidy>,
+    /// Extra-data stamped into the payload header and public key the bid is signed with
+    pub identity: &'a BuilderIdentity,
+}
+
+/// Who built a block, as stamped into our payloads and bids
+///
+/// Tells apart deployments run by one operator: the extra-data is visible on chain in every
+/// block we build, relays attribute our blocks to the builder public key, and the graffiti
+/// labels the deployment in our own bid records.
+#[derive(Debug, Clone, Serialize)]
+pub struct BuilderIdentity {
+    pub extra_data: Bytes,
+    pub builder_pubkey: Option<String>,
+    pub graffiti: String,
+}
+
+impl BuilderIdentity {
+    pub fn new(config: &BlockBuildingConfig) -> Self {
+        Self {
+            extra_data: Bytes::from(config.extra_data.as_bytes().to_vec()),
+            builder_pubkey: config.builder_pubkey.clone(),
+            graffiti: config.graffiti.clone(),
+        }
+    }
+    
+    /// Check a signed bid carries this identity, so a misconfigured deployment can't bid as another
+    ///
+    /// A bid without the extra-data, or without the public key when one is configured, is
+    /// refused too: its block wouldn't be attributed to us.
+    pub fn verify(&self, bid: &BidSubmission) -> Result<()> {
+        match bid.extra_data() {
+            Some(extra_data) if extra_data == self.extra_data => {}
+            Some(extra_data) => {
+                return Err(anyhow!(
+                    "Bid for slot {} carries extra data {}, expected {}",
+                    bid.slot,
+                    extra_data,
+                    self.extra_data
+                ));
+            }
+            None => return Err(anyhow!("Bid for slot {} carries no extra data", bid.slot)),
+        }
+        if let Some(expected) = &self.builder_pubkey {
+            match bid.builder_pubkey() {
+                Some(pubkey) if expected.eq_ignore_ascii_case(pubkey) => {}
+                Some(pubkey) => {
+                    return Err(anyhow!("Bid for slot {} is signed by {}, expected {}", bid.slot, pubkey, expected));
+                }
+                None => return Err(anyhow!("Bid for slot {} carries no builder public key", bid.slot)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a relay supports, used to pick per-relay behaviour
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct RelayCapabilities {
+    /// Whether a later, lower bid may replace an earlier one for the same slot
+    pub cancellations: bool,
+    /// Submissions per second accepted before the relay starts rejecting
+    pub max_submissions_per_second: u32,
+}
+
+/// Acknowledgement of an accepted submission
+#[derive(Debug, Clone, Serialize)]
+pub struct SubmissionReceipt {
+    pub relay: String,
+    pub slot: u64,
+    pub block_hash: H256,
+    pub latency_ms: u64,
+}
+
+/// A validator registered with a relay to propose an upcoming slot
+#[derive(Debug, Clone, Serialize)]
+pub struct ProposerDuty {
+    pub slot: u64,
+    pub validator_index: u64,
+    pub pubkey: String,
+    /// Address the proposer payment must go to
+    pub fee_recipient: Address,
+    /// Gas limit the validator asked for
+    pub gas_limit: u64,
+}
+
+/// Proposer schedule entry as returned by the builder API; numbers are decimal strings
+#[derive(Deserialize)]
+struct ValidatorEntry {
+    slot: String,
+    validator_index: String,
+    entry: SignedRegistration,
+}
+
+#[derive(Deserialize)]
+struct SignedRegistration {
+    message: Registration,
+}
+
+#[derive(Deserialize)]
+struct Registration {
+    fee_recipient: Address,
+    gas_limit: String,
+    pubkey: String,
+}
+
+/// Why a relay refused a submission
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayErrorKind {
+    /// The relay's simulation of our block failed
+    SimulationFailed,
+    RateLimited,
+    /// The relay warned about conflicting submissions for the same slot
+    Equivocation,
+    Timeout,
+    /// The relay could not be reached
+    Unavailable,
+    /// Any other rejection
+    Rejected,
+}
+
+impl RelayErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SimulationFailed => "simulation_failed",
+            Self::RateLimited => "rate_limited",
+            Self::Equivocation => "equivocation",
+            Self::Timeout => "timeout",
+            Self::Unavailable => "unavailable",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// A failed submission, classified from the relay's response
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("relay {relay} {}: {message}", kind.as_str())]
+pub struct RelayError {
+    pub relay: String,
+    pub kind: RelayErrorKind,
+    /// HTTP status, when the relay responded
+    pub status: Option<u16>,
+    pub message: String,
+}
+
+/// Error body returned by the builder API
+#[derive(Deserialize)]
+struct ErrorBody {
+    message: String,
+}
+
+/// Classify a builder API error response; shared by adapters whose relays follow the spec wording
+pub fn classify_error(status: StatusCode, message: &str) -> RelayErrorKind {
+    let message = message.to_ascii_lowercase();
+    
+    if status == StatusCode::TOO_MANY_REQUESTS || message.contains("rate limit") {
+        RelayErrorKind::RateLimited
+    } else if message.contains("equivocat") {
+        RelayErrorKind::Equivocation
+    } else if message.contains("simulation") || message.contains("sim failed") {
+        RelayErrorKind::SimulationFailed
+    } else if status.is_server_error() {
+        RelayErrorKind::Unavailable
+    } else {
+        RelayErrorKind::Rejected
+    }
+}
+
+/// A relay's submission interface; relay-specific handling lives in its implementation
+#[async_trait]
+pub trait RelayAdapter: Send + Sync {
+    /// Configured relay name
+    fn name(&self) -> &str;
+    
+    fn capabilities(&self) -> RelayCapabilities;
+    
+    /// Submit a bid; `cancellable` asks the relay to let a later bid replace this one
+    async fn submit_bid(&self, bid: &BidSubmission, cancellable: bool) -> Result<SubmissionReceipt, RelayError>;
+    
+    /// Validators registered with the relay for the current and next epoch
+    async fn proposer_duties(&self) -> Result<Vec<ProposerDuty>, RelayError>;
+}
+
+/// Build the adapter for a configured relay
+pub fn create_adapter(config: &RelayConfig, http: reqwest::Client) -> Result<Arc<dyn RelayAdapter>> {
+    match config.kind.as_str() {
+        "flashbots" => Ok(Arc::new(flashbots::FlashbotsRelay::new(config, http))),
+        "ultrasound" => Ok(Arc::new(ultrasound::UltraSoundRelay::new(config, http))),
+        "agnostic" => Ok(Arc::new(agnostic::AgnosticRelay::new(config, http))),
+        "bloxroute" => Ok(Arc::new(bloxroute::BloxrouteRelay::new(config, http)?)),
+        other => Err(anyhow!("Unknown relay kind: {}", other)),
+    }
+}
+
+/// Builder API plumbing shared by the adapters
+struct BuilderApi {
+    name: String,
+    url: String,
+    http: reqwest::Client,
+    headers: HeaderMap,
+    rate: u32,
+    limiter: RateLimiter,
+}
+
+impl BuilderApi {
+    fn new(config: &RelayConfig, http: reqwest::Client, headers: HeaderMap, default_rate: u32) -> Self {
+        let rate = config.max_submissions_per_second.unwrap_or(default_rate);
+        
+        Self {
+            name: config.name.clone(),
+            url: config.url.trim_end_matches('/').to_string(),
+            http,
+            headers,
+            rate,
+            limiter: RateLimiter::new(rate),
+        }
+    }
+    
+    /// Effective submissions per second, after any configured override
+    fn limiter_rate(&self) -> u32 {
+        self.rate
+    }
+    
+    /// POST a signed submission to the builder API
+    async fn submit_block(
+        &self,
+        bid: &BidSubmission,
+        query: &[(&str, &str)],
+        classify: fn(StatusCode, &str) -> RelayErrorKind,
+    ) -> Result<SubmissionReceipt, RelayError> {
+        self.limiter.acquire().await;
+        
+        let start = Instant::now();
+        let response = self
+            .http
+            .post(format!("{}{}", self.url, SUBMIT_BLOCK_PATH))
+            .headers(self.headers.clone())
+            .query(query)
+            .json(&bid.signed_submission)
+            .send()
+            .await
+            .map_err(|e| RelayError {
+                relay: self.name.clone(),
+                kind: if e.is_timeout() { RelayErrorKind::Timeout } else { RelayErrorKind::Unavailable },
+                status: None,
+                message: e.to_string(),
+            })?;
+        
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let message = serde_json::from_str::<ErrorBody>(&body)
+                .map(|error| error.message)
+                .unwrap_or(body);
+            
+            return Err(RelayError {
+                relay: self.name.clone(),
+                kind: classify(status, &message),
+                status: Some(status.as_u16()),
+                message,
+            });
+        }
+        
+        let latency_ms = start.elapsed().as_millis() as u64;
+        debug!("Relay {} accepted bid for slot {} in {}ms", self.name, bid.slot, latency_ms);
+        
+        Ok(SubmissionReceipt {
+            relay: self.name.clone(),
+            slot: bid.slot,
+            block_hash: bid.block_hash,
+            latency_ms,
+        })
+    }
+    
+    /// GET the proposer schedule
+    async fn get_validators(&self) -> Result<Vec<ProposerDuty>, RelayError> {
+        let response = self
+            .http
+            .get(format!("{}{}", self.url, VALIDATORS_PATH))
+            .headers(self.headers.clone())
+            .send()
+            .await
+            .map_err(|e| self.request_error(e))?;
+        let response = self.check_status(response).await?;
+        
+        let entries: Vec<ValidatorEntry> = response.json().await.map_err(|e| self.request_error(e))?;
+        entries
+            .into_iter()
+            .map(|entry| {
+                let parse = |value: &str, field: &str| {
+                    value.parse::<u64>().map_err(|_| self.invalid_response(format!("invalid {} {}", field, value)))
+                };
+                Ok(ProposerDuty {
+                    slot: parse(&entry.slot, "slot")?,
+                    validator_index: parse(&entry.validator_index, "validator index")?,
+                    gas_limit: parse(&entry.entry.message.gas_limit, "gas limit")?,
+                    fee_recipient: entry.entry.message.fee_recipient,
+                    pubkey: entry.entry.message.pubkey,
+                })
+            })
+            .collect()
+    }
+    
+    /// Turn an error response into a classified relay error
+    async fn check_status(&self, response: reqwest::Response) -> Result<reqwest::Response, RelayError> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<ErrorBody>(&body)
+            .map(|error| error.message)
+            .unwrap_or(body);
+        
+        Err(RelayError {
+            relay: self.name.clone(),
+            kind: classify_error(status, &message),
+            status: Some(status.as_u16()),
+            message,
+        })
+    }
+    
+    fn request_error(&self, e: reqwest::Error) -> RelayError {
+        RelayError {
+            relay: self.name.clone(),
+            kind: if e.is_timeout() { RelayErrorKind::Timeout } else { RelayErrorKind::Unavailable },
+            status: None,
+            message: e.to_string(),
+        }
+    }
+    
+    fn invalid_response(&self, message: String) -> RelayError {
+        RelayError {
+            relay: self.name.clone(),
+            kind: RelayErrorKind::Rejected,
+            status: None,
+            message,
+        }
+    }
+}
+
+/// Spaces requests evenly to stay under a relay's rate limit
+struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(per_second: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / per_second.max(1),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+    
+    async fn acquire(&self) {
+        let mut next = self.next.lock().await;
+        let now = Instant::now();
+        if *next > now {
+            tokio::time::sleep_until((*next).into()).await;
+        }
+        *next = (*next).max(now) + self.interval;
+    }
+}
@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+
+use super::{
+    classify_error, BidSubmission, BuilderApi, ProposerDuty, RelayAdapter, RelayCapabilities, RelayError,
+    SubmissionReceipt,
+};
+use crate::config::RelayConfig;
+
+/// Ultra Sound relay
+pub struct UltraSoundRelay {
+    api: BuilderApi,
+}
+
+impl UltraSoundRelay {
+    pub fn new(config: &RelayConfig, http: reqwest::Client) -> Self {
+        Self {
+            api: BuilderApi::new(config, http, HeaderMap::new(), 20),
+        }
+    }
+}
+
+#[async_trait]
+impl RelayAdapter for UltraSoundRelay {
+    fn name(&self) -> &str {
+        &self.api.name
+    }
+    
+    fn capabilities(&self) -> RelayCapabilities {
+        RelayCapabilities {
+            cancellations: true,
+            max_submissions_per_second: self.api.limiter_rate(),
+        }
+    }
+    
+    async fn submit_bid(&self, bid: &BidSubmission, cancellable: bool) -> Result<SubmissionReceipt, RelayError> {
+        let query: &[(&str, &str)] = if cancellable { &[("cancellations", "1")] } else { &[] };
+        self.api.submit_block(bid, query, classify_error).await
+    }
+    
+    async fn proposer_duties(&self) -> Result<Vec<ProposerDuty>, RelayError> {
+        self.api.get_validators().await
+    }
+}
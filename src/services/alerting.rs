@@ -0,0 +1,312 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::{HashMap, VecDeque},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    config::{AlertRuleConfig, AlertingConfig},
+    services::{
+        drain::DrainController,
+        webhooks::{SignedWebhookSink, WebhookDeliveryLog},
+    },
+    utils::metrics::snapshot,
+};
+
+/// Alert severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl FromStr for Severity {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "info" => Ok(Self::Info),
+            "warning" => Ok(Self::Warning),
+            "critical" => Ok(Self::Critical),
+            other => Err(anyhow!("Unknown alert severity: {}", other)),
+        }
+    }
+}
+
+/// A notification raised by any subsystem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    /// Stable key used to suppress repeated notifications
+    pub key: String,
+    pub severity: Severity,
+    /// Subsystem raising the alert
+    pub source: String,
+    pub message: String,
+    pub raised_at: DateTime<Utc>,
+}
+
+impl Alert {
+    pub fn new(key: impl Into<String>, severity: Severity, source: &str, message: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            severity,
+            source: source.to_string(),
+            message: message.into(),
+            raised_at: Utc::now(),
+        }
+    }
+}
+
+/// Destination for alert notifications
+#[async_trait]
+pub trait AlertSink: Send + Sync {
+    async fn notify(&self, alert: &Alert) -> Result<()>;
+}
+
+/// Writes alerts to the application log
+pub struct LogSink;
+
+#[async_trait]
+impl AlertSink for LogSink {
+    async fn notify(&self, alert: &Alert) -> Result<()> {
+        match alert.severity {
+            Severity::Critical => error!(target: "alerts", key = %alert.key, source = %alert.source, "{}", alert.message),
+            Severity::Warning => warn!(target: "alerts", key = %alert.key, source = %alert.source, "{}", alert.message),
+            Severity::Info => info!(target: "alerts", key = %alert.key, source = %alert.source, "{}", alert.message),
+        }
+        Ok(())
+    }
+}
+
+/// Posts alerts to a Slack-compatible webhook
+pub struct WebhookSink {
+    http: reqwest::Client,
+    url: String,
+}
+
+impl WebhookSink {
+    pub fn new(http: reqwest::Client, url: String) -> Self {
+        Self { http, url }
+    }
+}
+
+#[async_trait]
+impl AlertSink for WebhookSink {
+    async fn notify(&self, alert: &Alert) -> Result<()> {
+        let body = json!({
+            "text": format!("[{:?}] {}: {}", alert.severity, alert.source, alert.message),
+            "alert": alert,
+        });
+        
+        self.http
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+        
+        Ok(())
+    }
+}
+
+/// Fans alerts out to every sink, suppressing repeats within the cooldown
+#[derive(Clone)]
+pub struct AlertManager {
+    sinks: Arc<Vec<Arc<dyn AlertSink>>>,
+    cooldown: Duration,
+    last_fired: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl AlertManager {
+    /// Create a new alert manager with the log sink plus any configured webhooks
+    pub fn new(config: &AlertingConfig, deliveries: WebhookDeliveryLog, drain: DrainController) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        
+        let mut sinks: Vec<Arc<dyn AlertSink>> = vec![Arc::new(LogSink)];
+        for url in &config.webhook_urls {
+            sinks.push(Arc::new(WebhookSink::new(http.clone(), url.clone())));
+        }
+        for webhook in &config.webhooks {
+            sinks.push(Arc::new(SignedWebhookSink::new(
+                http.clone(),
+                webhook.clone(),
+                config.max_delivery_attempts,
+                deliveries.clone(),
+                drain.clone(),
+            )));
+        }
+        
+        Ok(Self {
+            sinks: Arc::new(sinks),
+            cooldown: Duration::from_secs(config.cooldown_seconds),
+            last_fired: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+    
+    /// Send an alert to every sink unless the same key fired within the cooldown
+    pub async fn fire(&self, alert: Alert) {
+        {
+            let mut last_fired = self.last_fired.lock();
+            if let Some(at) = last_fired.get(&alert.key) {
+                if at.elapsed() < self.cooldown {
+                    return;
+                }
+            }
+            last_fired.insert(alert.key.clone(), Instant::now());
+        }
+        
+        metrics::counter!("alerts_fired_total", 1, "severity" => format!("{:?}", alert.severity));
+        
+        for sink in self.sinks.iter() {
+            if let Err(e) = sink.notify(&alert).await {
+                warn!("Failed to deliver alert {}: {}", alert.key, e);
+            }
+        }
+    }
+}
+
+/// How a rule interprets its metric
+#[derive(Debug, Clone, Copy)]
+enum RuleKind {
+    Threshold,
+    RateOfChange,
+}
+
+/// A validated alert rule
+#[derive(Debug, Clone)]
+struct AlertRule {
+    name: String,
+    metric: String,
+    kind: RuleKind,
+    greater_than: bool,
+    value: f64,
+    window: Duration,
+    severity: Severity,
+}
+
+impl AlertRule {
+    fn from_config(config: &AlertRuleConfig) -> Result<Self> {
+        let kind = match config.kind.as_str() {
+            "threshold" => RuleKind::Threshold,
+            "rate_of_change" => RuleKind::RateOfChange,
+            other => return Err(anyhow!("Unknown alert rule kind for {}: {}", config.name, other)),
+        };
+        let greater_than = match config.operator.as_str() {
+            "gt" => true,
+            "lt" => false,
+            other => return Err(anyhow!("Unknown alert rule operator for {}: {}", config.name, other)),
+        };
+        
+        Ok(Self {
+            name: config.name.clone(),
+            metric: config.metric.clone(),
+            kind,
+            greater_than,
+            value: config.value,
+            window: Duration::from_secs(config.window_seconds.max(1)),
+            severity: config.severity.parse()?,
+        })
+    }
+    
+    fn breached(&self, observed: f64) -> bool {
+        if self.greater_than {
+            observed > self.value
+        } else {
+            observed < self.value
+        }
+    }
+}
+
+/// Evaluates threshold and rate-of-change rules over internal metrics
+pub struct AlertRuleEngine {
+    rules: Vec<AlertRule>,
+    alert_manager: AlertManager,
+    /// Recent samples per rule, for rate-of-change rules; each rule keeps its own window
+    history: Mutex<HashMap<String, VecDeque<(Instant, f64)>>>,
+}
+
+impl AlertRuleEngine {
+    /// Create a new rule engine from config
+    pub fn new(config: &AlertingConfig, alert_manager: AlertManager) -> Result<Self> {
+        let rules = config
+            .rules
+            .iter()
+            .map(AlertRule::from_config)
+            .collect::<Result<Vec<_>>>()?;
+        
+        Ok(Self {
+            rules,
+            alert_manager,
+            history: Mutex::new(HashMap::new()),
+        })
+    }
+    
+    /// Evaluate every rule against the current metric values
+    pub async fn evaluate(&self) -> Result<()> {
+        let snapshot = snapshot();
+        let now = Instant::now();
+        let mut fired = Vec::new();
+        
+        {
+            let mut history = self.history.lock();
+            for rule in &self.rules {
+                let current = match snapshot.get(&rule.metric) {
+                    Some(value) => *value,
+                    None => continue,
+                };
+                
+                let observed = match rule.kind {
+                    RuleKind::Threshold => current,
+                    RuleKind::RateOfChange => {
+                        let samples = history.entry(rule.name.clone()).or_default();
+                        samples.push_back((now, current));
+                        while samples.len() > 1 && now.duration_since(samples[0].0) > rule.window {
+                            samples.pop_front();
+                        }
+                        
+                        let (first_at, first) = samples[0];
+                        let elapsed = now.duration_since(first_at).as_secs_f64();
+                        if elapsed <= 0.0 {
+                            continue;
+                        }
+                        (current - first) / elapsed
+                    }
+                };
+                
+                if rule.breached(observed) {
+                    fired.push(Alert::new(
+                        format!("rule:{}", rule.name),
+                        rule.severity,
+                        "alert_rules",
+                        format!(
+                            "Rule {} breached: {} = {:.4} ({} {})",
+                            rule.name,
+                            rule.metric,
+                            observed,
+                            if rule.greater_than { ">" } else { "<" },
+                            rule.value
+                        ),
+                    ));
+                }
+            }
+        }
+        
+        for alert in fired {
+            self.alert_manager.fire(alert).await;
+        }
+        
+        Ok(())
+    }
+}
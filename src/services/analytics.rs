@@ -0,0 +1,302 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use ethers::types::{Transaction, H256, U256};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{sync::Arc, time::Duration};
+use tracing::{debug, info, warn};
+
+use crate::{config::AnalyticsConfig, services::labels::TxLabels, utils::intern::address_str};
+
+/// Table holding every pending transaction observation
+const PENDING_TX_TABLE: &str = "pending_tx_observations";
+
+/// Table holding every simulation result
+const SIMULATION_TABLE: &str = "simulation_results";
+
+/// A single sighting of a pending transaction
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingTxObservation {
+    pub tx_hash: String,
+    pub from: Arc<str>,
+    pub to: Option<Arc<str>>,
+    pub gas_price: String,
+    pub max_priority_fee: Option<String>,
+    pub value: String,
+    pub gas: u64,
+    /// Label categories of the sender and recipient, when known
+    pub from_label: Option<&'static str>,
+    pub to_label: Option<&'static str>,
+    pub observed_at_ms: i64,
+}
+
+impl PendingTxObservation {
+    pub fn from_transaction(tx: &Transaction, labels: &TxLabels) -> Self {
+        Self {
+            tx_hash: format!("{:?}", tx.hash),
+            from: address_str(tx.from),
+            to: tx.to.map(address_str),
+            gas_price: tx.gas_price.unwrap_or_default().to_string(),
+            max_priority_fee: tx.max_priority_fee_per_gas.map(|fee| fee.to_string()),
+            value: tx.value.to_string(),
+            gas: tx.gas.low_u64(),
+            from_label: labels.from.as_ref().map(|label| label.category.as_str()),
+            to_label: labels.to.as_ref().map(|label| label.category.as_str()),
+            observed_at_ms: Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+/// The outcome of simulating one transaction
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationObservation {
+    pub tx_hash: String,
+    pub profit: String,
+    pub success: bool,
+    pub duration_us: u64,
+    pub simulated_at_ms: i64,
+}
+
+impl SimulationObservation {
+    pub fn new(tx_hash: H256, profit: Option<U256>, duration: Duration) -> Self {
+        Self {
+            tx_hash: format!("{:?}", tx_hash),
+            profit: profit.unwrap_or_default().to_string(),
+            success: profit.is_some(),
+            duration_us: duration.as_micros() as u64,
+            simulated_at_ms: Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+/// Named dashboard queries that may be proxied to ClickHouse
+const DASHBOARD_QUERIES: &[(&str, &str)] = &[
+    (
+        "pending_tx_rate",
+        "SELECT toStartOfMinute(fromUnixTimestamp64Milli(observed_at_ms)) AS minute, count() AS observations \
+         FROM pending_tx_observations WHERE observed_at_ms > toUnixTimestamp64Milli(now64() - INTERVAL 1 HOUR) \
+         GROUP BY minute ORDER BY minute",
+    ),
+    (
+        "simulation_outcomes",
+        "SELECT toStartOfMinute(fromUnixTimestamp64Milli(simulated_at_ms)) AS minute, countIf(success) AS succeeded, \
+         countIf(NOT success) AS failed, avg(duration_us) AS avg_duration_us \
+         FROM simulation_results WHERE simulated_at_ms > toUnixTimestamp64Milli(now64() - INTERVAL 1 HOUR) \
+         GROUP BY minute ORDER BY minute",
+    ),
+    (
+        "labeled_flow",
+        "SELECT ifNull(from_label, 'unlabeled') AS sender, ifNull(to_label, 'unlabeled') AS recipient, \
+         count() AS observations, sum(value) AS value \
+         FROM pending_tx_observations WHERE observed_at_ms > toUnixTimestamp64Milli(now64() - INTERVAL 1 HOUR) \
+         AND (from_label IS NOT NULL OR to_label IS NOT NULL) \
+         GROUP BY sender, recipient ORDER BY observations DESC",
+    ),
+];
+
+/// Optional ClickHouse writer for firehose-grade event data
+#[derive(Clone)]
+pub struct AnalyticsSink {
+    /// HTTP client for the ClickHouse interface
+    http: reqwest::Client,
+    /// Configuration
+    config: AnalyticsConfig,
+    /// Buffered pending transaction observations
+    pending_txs: Arc<Mutex<Vec<PendingTxObservation>>>,
+    /// Buffered simulation results
+    simulations: Arc<Mutex<Vec<SimulationObservation>>>,
+}
+
+impl AnalyticsSink {
+    /// Create a new analytics sink
+    pub fn new(config: AnalyticsConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .context("Failed to create ClickHouse HTTP client")?;
+        
+        Ok(Self {
+            http,
+            config,
+            pending_txs: Arc::new(Mutex::new(Vec::new())),
+            simulations: Arc::new(Mutex::new(Vec::new())),
+        })
+    }
+    
+    /// Whether the ClickHouse sink is enabled
+    pub fn enabled(&self) -> bool {
+        self.config.clickhouse_enabled
+    }
+    
+    /// Create the event tables if they don't exist
+    pub async fn ensure_schema(&self) -> Result<()> {
+        if !self.enabled() {
+            return Ok(());
+        }
+        
+        self.execute(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                tx_hash String, from String, to Nullable(String), gas_price UInt256,
+                max_priority_fee Nullable(UInt256), value UInt256, gas UInt64,
+                from_label Nullable(String), to_label Nullable(String), observed_at_ms Int64
+            ) ENGINE = MergeTree ORDER BY observed_at_ms",
+            PENDING_TX_TABLE
+        ))
+        .await?;
+        
+        // Tables created before transactions were labeled
+        self.execute(&format!(
+            "ALTER TABLE {} ADD COLUMN IF NOT EXISTS from_label Nullable(String) AFTER gas,
+                ADD COLUMN IF NOT EXISTS to_label Nullable(String) AFTER from_label",
+            PENDING_TX_TABLE
+        ))
+        .await?;
+        
+        self.execute(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                tx_hash String, profit UInt256, success Bool, duration_us UInt64, simulated_at_ms Int64
+            ) ENGINE = MergeTree ORDER BY simulated_at_ms",
+            SIMULATION_TABLE
+        ))
+        .await?;
+        
+        info!("ClickHouse analytics schema ready");
+        Ok(())
+    }
+    
+    /// Buffer a pending transaction observation
+    pub fn record_pending_tx(&self, observation: PendingTxObservation) {
+        if !self.enabled() {
+            return;
+        }
+        
+        let mut buffer = self.pending_txs.lock();
+        buffer.push(observation);
+        if buffer.len() >= self.config.batch_size {
+            self.spawn_flush();
+        }
+    }
+    
+    /// Buffer a simulation result
+    pub fn record_simulation(&self, observation: SimulationObservation) {
+        if !self.enabled() {
+            return;
+        }
+        
+        let mut buffer = self.simulations.lock();
+        buffer.push(observation);
+        if buffer.len() >= self.config.batch_size {
+            self.spawn_flush();
+        }
+    }
+    
+    /// Rows buffered and not yet inserted
+    pub fn backlog(&self) -> usize {
+        self.pending_txs.lock().len() + self.simulations.lock().len()
+    }
+    
+    /// Insert everything buffered so far
+    pub async fn flush(&self) -> Result<()> {
+        if !self.enabled() {
+            return Ok(());
+        }
+        
+        let pending_txs = std::mem::take(&mut *self.pending_txs.lock());
+        let simulations = std::mem::take(&mut *self.simulations.lock());
+        
+        // A failed insert puts its rows back, so one table failing doesn't hold up the other
+        let pending_result = self.insert(PENDING_TX_TABLE, &pending_txs).await;
+        if pending_result.is_err() {
+            self.rebuffer(&self.pending_txs, PENDING_TX_TABLE, pending_txs);
+        }
+        let simulation_result = self.insert(SIMULATION_TABLE, &simulations).await;
+        if simulation_result.is_err() {
+            self.rebuffer(&self.simulations, SIMULATION_TABLE, simulations);
+        }
+        
+        pending_result.and(simulation_result)
+    }
+    
+    /// Put rows that failed to insert back ahead of those buffered since, dropping the oldest
+    /// beyond `max_buffered_rows`
+    fn rebuffer<T>(&self, buffer: &Mutex<Vec<T>>, table: &'static str, mut rows: Vec<T>) {
+        let mut buffer = buffer.lock();
+        rows.append(&mut buffer);
+        let excess = rows.len().saturating_sub(self.config.max_buffered_rows);
+        if excess > 0 {
+            rows.drain(..excess);
+            metrics::counter!("analytics_rows_dropped_total", excess as u64, "table" => table);
+        }
+        *buffer = rows;
+    }
+    
+    /// Run one of the named dashboard queries, returning ClickHouse's JSON result
+    pub async fn dashboard_query(&self, name: &str) -> Result<Option<serde_json::Value>> {
+        if !self.enabled() {
+            return Err(anyhow!("ClickHouse analytics sink is disabled"));
+        }
+        
+        let query = match DASHBOARD_QUERIES.iter().find(|(n, _)| *n == name) {
+            Some((_, query)) => query,
+            None => return Ok(None),
+        };
+        
+        let response = self
+            .request(&format!("{} FORMAT JSON", query))
+            .send()
+            .await?
+            .error_for_status()?;
+        
+        Ok(Some(response.json().await?))
+    }
+    
+    fn spawn_flush(&self) {
+        let sink = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = sink.flush().await {
+                warn!("Failed to flush analytics sink: {}", e);
+            }
+        });
+    }
+    
+    async fn insert<T: Serialize>(&self, table: &str, rows: &[T]) -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        
+        let mut body = Vec::new();
+        for row in rows {
+            serde_json::to_writer(&mut body, row)?;
+            body.push(b'\n');
+        }
+        
+        self.request(&format!("INSERT INTO {} FORMAT JSONEachRow", table))
+            .query(&[("async_insert", "1"), ("wait_for_async_insert", "0")])
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()
+            .context(format!("Failed to insert into ClickHouse table {}", table))?;
+        
+        debug!("Inserted {} rows into ClickHouse table {}", rows.len(), table);
+        Ok(())
+    }
+    
+    async fn execute(&self, statement: &str) -> Result<()> {
+        self.request(statement).send().await?.error_for_status()?;
+        Ok(())
+    }
+    
+    fn request(&self, query: &str) -> reqwest::RequestBuilder {
+        let mut request = self
+            .http
+            .post(&self.config.clickhouse_url)
+            .query(&[("database", self.config.clickhouse_database.as_str()), ("query", query)]);
+        
+        if let Some(user) = &self.config.clickhouse_user {
+            request = request.basic_auth(user, self.config.clickhouse_password.as_ref());
+        }
+        
+        request
+    }
+}
@@ -0,0 +1,320 @@
+use anyhow::{anyhow, Context, Result};
+use arrow::{
+    array::{ArrayRef, StringArray},
+    datatypes::{DataType, Field, Schema},
+    record_batch::RecordBatch,
+};
+use bytes::Bytes;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use futures::stream::TryStreamExt;
+use object_store::{aws::AmazonS3Builder, local::LocalFileSystem, path::Path, ObjectStore};
+use parquet::arrow::ArrowWriter;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::Row;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+use crate::{
+    config::{ExportConfig, ExportTableConfig},
+    database::{DbHealth, DbPool},
+};
+
+/// Rows buffered per record batch while exporting a partition
+const EXPORT_BATCH_SIZE: usize = 10_000;
+
+/// Location of the manifest within the destination
+const MANIFEST_PATH: &str = "manifest.json";
+
+/// An exported daily partition of one table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartitionEntry {
+    pub table: String,
+    pub date: NaiveDate,
+    pub path: String,
+    pub format: String,
+    pub rows: u64,
+    pub schema_version: u32,
+    pub columns: Vec<String>,
+    pub exported_at: DateTime<Utc>,
+}
+
+/// Index of every exported partition
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub partitions: Vec<PartitionEntry>,
+}
+
+impl ExportManifest {
+    /// Latest schema entry recorded for a table
+    fn latest_schema(&self, table: &str) -> Option<(u32, &[String])> {
+        self.partitions
+            .iter()
+            .filter(|p| p.table == table)
+            .max_by_key(|p| p.schema_version)
+            .map(|p| (p.schema_version, p.columns.as_slice()))
+    }
+    
+    fn contains(&self, table: &str, date: NaiveDate) -> bool {
+        self.partitions.iter().any(|p| p.table == table && p.date == date)
+    }
+}
+
+/// Service that writes daily analytics partitions to local disk or S3
+#[derive(Clone)]
+pub struct AnalyticsExportService {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Configuration
+    config: ExportConfig,
+    /// Destination object store
+    store: Arc<dyn ObjectStore>,
+    /// Key prefix within the object store
+    prefix: Path,
+    /// Serializes manifest updates between export runs
+    manifest_lock: Arc<Mutex<()>>,
+}
+
+impl AnalyticsExportService {
+    /// Create a new analytics export service
+    pub fn new(db_pool: DbPool, health: DbHealth, config: ExportConfig) -> Result<Self> {
+        let (store, prefix): (Arc<dyn ObjectStore>, Path) =
+            match config.destination.strip_prefix("s3://") {
+                Some(location) => {
+                    let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+                    let store = AmazonS3Builder::from_env()
+                        .with_bucket_name(bucket)
+                        .build()
+                        .context("Failed to configure S3 export destination")?;
+                    (Arc::new(store), Path::from(prefix))
+                }
+                None => {
+                    std::fs::create_dir_all(&config.destination)
+                        .context("Failed to create export directory")?;
+                    let store = LocalFileSystem::new_with_prefix(&config.destination)
+                        .context("Failed to configure local export destination")?;
+                    (Arc::new(store), Path::default())
+                }
+            };
+        
+        Ok(Self {
+            db_pool,
+            health,
+            config,
+            store,
+            prefix,
+            manifest_lock: Arc::new(Mutex::new(())),
+        })
+    }
+    
+    /// Whether the scheduled exporter is enabled
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Export every complete day within the lookback window that is missing from the manifest
+    pub async fn export_due_partitions(&self) -> Result<usize> {
+        let _guard = self.manifest_lock.lock().await;
+        let mut manifest = self.load_manifest().await?;
+        let today = Utc::now().date_naive();
+        let mut exported = 0;
+        
+        for days_ago in (1..=self.config.lookback_days as i64).rev() {
+            let date = today - Duration::days(days_ago);
+            
+            for table in &self.config.tables {
+                if manifest.contains(&table.name, date) {
+                    continue;
+                }
+                
+                let entry = self.health.retry(|| self.export_partition(table, date, &manifest)).await?;
+                info!("Exported {} rows from {} for {}", entry.rows, table.name, date);
+                manifest.partitions.push(entry);
+                exported += 1;
+                
+                // Persist progress after every partition so a failure doesn't redo work
+                self.save_manifest(&manifest).await?;
+            }
+        }
+        
+        Ok(exported)
+    }
+    
+    /// Load the manifest of exported partitions
+    pub async fn load_manifest(&self) -> Result<ExportManifest> {
+        match self.store.get(&self.path(MANIFEST_PATH)).await {
+            Ok(result) => Ok(serde_json::from_slice(&result.bytes().await?)?),
+            Err(object_store::Error::NotFound { .. }) => Ok(ExportManifest::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+    
+    async fn save_manifest(&self, manifest: &ExportManifest) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(manifest)?;
+        self.store.put(&self.path(MANIFEST_PATH), Bytes::from(bytes)).await?;
+        Ok(())
+    }
+    
+    /// Export one day of one table, bumping the schema version if its columns changed
+    async fn export_partition(
+        &self,
+        table: &ExportTableConfig,
+        date: NaiveDate,
+        manifest: &ExportManifest,
+    ) -> Result<PartitionEntry> {
+        validate_identifier(&table.name)?;
+        validate_identifier(&table.timestamp_column)?;
+        
+        let start = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let end = start + Duration::days(1);
+        
+        let query = format!(
+            "SELECT row_to_json(t)::text AS row FROM {} t WHERE t.{} >= $1 AND t.{} < $2",
+            table.name, table.timestamp_column, table.timestamp_column
+        );
+        let mut rows = sqlx::query(&query)
+            .bind(start)
+            .bind(end)
+            .fetch(&self.db_pool);
+        
+        let mut columns: Option<Vec<String>> = None;
+        let mut batch: Vec<Map<String, Value>> = Vec::new();
+        let mut encoder: Option<PartitionEncoder> = None;
+        let mut row_count = 0u64;
+        
+        while let Some(row) = rows.try_next().await? {
+            let record: Map<String, Value> = serde_json::from_str(row.try_get::<&str, _>("row")?)?;
+            if columns.is_none() {
+                let mut keys: Vec<String> = record.keys().cloned().collect();
+                keys.sort();
+                columns = Some(keys);
+            }
+            batch.push(record);
+            row_count += 1;
+            
+            if batch.len() >= EXPORT_BATCH_SIZE {
+                if encoder.is_none() {
+                    encoder = Some(PartitionEncoder::new(
+                        &self.config.format,
+                        columns.as_ref().unwrap(),
+                    )?);
+                }
+                encoder.as_mut().unwrap().write(&std::mem::take(&mut batch))?;
+            }
+        }
+        
+        let columns = columns.unwrap_or_default();
+        let mut encoder = match encoder {
+            Some(encoder) => encoder,
+            None => PartitionEncoder::new(&self.config.format, &columns)?,
+        };
+        if !batch.is_empty() {
+            encoder.write(&batch)?;
+        }
+        let bytes = encoder.finish()?;
+        
+        let schema_version = match manifest.latest_schema(&table.name) {
+            Some((version, previous)) if previous == columns.as_slice() || columns.is_empty() => version,
+            Some((version, _)) => version + 1,
+            None => 1,
+        };
+        
+        let relative = format!(
+            "{}/v{}/date={}/part-0.{}",
+            table.name, schema_version, date, self.config.format
+        );
+        self.store.put(&self.path(&relative), Bytes::from(bytes)).await?;
+        debug!("Wrote analytics partition {}", relative);
+        
+        Ok(PartitionEntry {
+            table: table.name.clone(),
+            date,
+            path: relative,
+            format: self.config.format.clone(),
+            rows: row_count,
+            schema_version,
+            columns,
+            exported_at: Utc::now(),
+        })
+    }
+    
+    fn path(&self, relative: &str) -> Path {
+        relative
+            .split('/')
+            .fold(self.prefix.clone(), |path, part| path.child(part))
+    }
+}
+
+/// Writes batches of JSON rows as Parquet or CSV with string-typed columns
+enum PartitionEncoder {
+    Parquet(Arc<Schema>, ArrowWriter<Vec<u8>>),
+    Csv(Arc<Schema>, arrow::csv::Writer<Vec<u8>>),
+}
+
+impl PartitionEncoder {
+    fn new(format: &str, columns: &[String]) -> Result<Self> {
+        let schema = Arc::new(Schema::new(
+            columns
+                .iter()
+                .map(|c| Field::new(c, DataType::Utf8, true))
+                .collect::<Vec<_>>(),
+        ));
+        
+        match format {
+            "parquet" => Ok(Self::Parquet(
+                schema.clone(),
+                ArrowWriter::try_new(Vec::new(), schema, None)?,
+            )),
+            "csv" => Ok(Self::Csv(schema, arrow::csv::Writer::new(Vec::new()))),
+            other => Err(anyhow!("Unsupported analytics export format: {}", other)),
+        }
+    }
+    
+    fn write(&mut self, rows: &[Map<String, Value>]) -> Result<()> {
+        let schema = match self {
+            Self::Parquet(schema, _) | Self::Csv(schema, _) => schema.clone(),
+        };
+        
+        let arrays: Vec<ArrayRef> = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let values: StringArray = rows
+                    .iter()
+                    .map(|row| match row.get(field.name()) {
+                        None | Some(Value::Null) => None,
+                        Some(Value::String(s)) => Some(s.clone()),
+                        Some(other) => Some(other.to_string()),
+                    })
+                    .collect();
+                Arc::new(values) as ArrayRef
+            })
+            .collect();
+        let batch = RecordBatch::try_new(schema, arrays)?;
+        
+        match self {
+            Self::Parquet(_, writer) => writer.write(&batch)?,
+            Self::Csv(_, writer) => writer.write(&batch)?,
+        }
+        
+        Ok(())
+    }
+    
+    fn finish(self) -> Result<Vec<u8>> {
+        match self {
+            Self::Parquet(_, writer) => Ok(writer.into_inner()?),
+            Self::Csv(_, writer) => Ok(writer.into_inner()),
+        }
+    }
+}
+
+/// Reject table and column names that aren't plain SQL identifiers
+fn validate_identifier(name: &str) -> Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(anyhow!("Invalid export identifier: {}", name));
+    }
+    Ok(())
+}
@@ -0,0 +1,229 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use ethers::{
+    types::{H256, U256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use tracing::debug;
+
+use crate::{
+    api::models,
+    database::{DbHealth, DbPool},
+    services::transaction::InclusionCandidate,
+};
+
+/// What went into a block template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateSummary {
+    /// Candidates the ordering strategy was offered
+    pub candidates: usize,
+    /// Candidates in the template
+    pub included: usize,
+    /// Candidates left out because they touch state an included candidate touches
+    pub conflicts_resolved: usize,
+    /// keccak256 of the included transaction hashes in block order
+    pub ordering_hash: H256,
+    /// Simulated profit of the template in wei
+    #[serde(with = "models::wei")]
+    pub value: U256,
+}
+
+impl TemplateSummary {
+    pub fn new(candidates: usize, conflicts_resolved: usize, template: &[InclusionCandidate]) -> Self {
+        let hashes: Vec<u8> = template.iter().flat_map(|c| c.tx.hash.as_bytes().to_vec()).collect();
+        
+        Self {
+            candidates,
+            included: template.len(),
+            conflicts_resolved,
+            ordering_hash: H256::from(keccak256(hashes)),
+            value: template.iter().fold(U256::zero(), |value, c| value.saturating_add(c.profit)),
+        }
+    }
+}
+
+/// Outcome of a bid at one relay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelaySubmission {
+    pub relay: String,
+    pub accepted: bool,
+    /// Round trip of an accepted submission
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Audit record of what the builder decided for one slot
+///
+/// Template fields describe the last template built for the slot and bid fields the last bid
+/// submitted, either may be missing when the slot was abandoned before that step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildDecision {
+    pub slot: u64,
+    /// Head block the last template was built on
+    pub head_block: Option<u64>,
+    pub candidates: Option<u32>,
+    pub included: Option<u32>,
+    pub conflicts_resolved: Option<u32>,
+    pub ordering_hash: Option<String>,
+    pub value_wei: Option<String>,
+    pub template_built_at: Option<DateTime<Utc>>,
+    pub block_hash: Option<String>,
+    pub bid_wei: Option<String>,
+    pub subsidy_wei: Option<String>,
+    /// Bids submitted for the slot, each replacing the last
+    pub bids: u32,
+    pub submissions: Vec<RelaySubmission>,
+    pub first_submitted_at: Option<DateTime<Utc>>,
+    pub last_submitted_at: Option<DateTime<Utc>>,
+}
+
+/// Per-slot log of builder decisions
+///
+/// The block builder records each template it bids from and the relay service each bid it
+/// submits, so one row per slot holds the final ordering, value and bid with its timing.
+#[derive(Clone)]
+pub struct BuildDecisionLog {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+}
+
+impl BuildDecisionLog {
+    /// Create a new decision log
+    pub fn new(db_pool: DbPool, health: DbHealth) -> Self {
+        Self { db_pool, health }
+    }
+    
+    /// Record the template built for a slot on a head block, replacing any built before it
+    pub async fn record_template(&self, slot: u64, head_block: u64, summary: &TemplateSummary) -> Result<()> {
+        self.health.retry(|| self.record_template_once(slot, head_block, summary)).await
+    }
+    
+    /// Record a bid submitted for a slot with its outcome at each relay
+    pub async fn record_bid(
+        &self,
+        slot: u64,
+        block_hash: H256,
+        value: U256,
+        subsidy: Option<U256>,
+        submissions: &[RelaySubmission],
+        submitted_at: DateTime<Utc>,
+    ) -> Result<()> {
+        self.health
+            .retry(|| self.record_bid_once(slot, block_hash, value, subsidy, submissions, submitted_at))
+            .await
+    }
+    
+    /// Decision record of a slot, if we attempted it
+    pub async fn get(&self, slot: u64) -> Result<Option<BuildDecision>> {
+        self.health.retry(|| self.get_once(slot)).await
+    }
+    
+    async fn record_template_once(&self, slot: u64, head_block: u64, summary: &TemplateSummary) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO build_decisions
+             (slot, candidates, included, conflicts_resolved, ordering_hash, value_wei, template_built_at,
+              head_block)
+             VALUES ($1, $2, $3, $4, $5, $6::NUMERIC, NOW(), $7)
+             ON CONFLICT (slot) DO UPDATE
+             SET candidates = EXCLUDED.candidates, included = EXCLUDED.included,
+                 conflicts_resolved = EXCLUDED.conflicts_resolved, ordering_hash = EXCLUDED.ordering_hash,
+                 value_wei = EXCLUDED.value_wei, template_built_at = EXCLUDED.template_built_at,
+                 head_block = EXCLUDED.head_block",
+        )
+        .bind(slot as i64)
+        .bind(summary.candidates as i32)
+        .bind(summary.included as i32)
+        .bind(summary.conflicts_resolved as i32)
+        .bind(format!("{:?}", summary.ordering_hash))
+        .bind(summary.value.to_string())
+        .bind(head_block as i64)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to record block template decision")?;
+        
+        debug!(
+            "Recorded template for slot {}: {} of {} candidates, {} conflicts resolved",
+            slot, summary.included, summary.candidates, summary.conflicts_resolved
+        );
+        
+        Ok(())
+    }
+    
+    async fn record_bid_once(
+        &self,
+        slot: u64,
+        block_hash: H256,
+        value: U256,
+        subsidy: Option<U256>,
+        submissions: &[RelaySubmission],
+        submitted_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO build_decisions
+             (slot, block_hash, bid_wei, subsidy_wei, bids, submissions, first_submitted_at, last_submitted_at)
+             VALUES ($1, $2, $3::NUMERIC, $4::NUMERIC, 1, $5, $6, $6)
+             ON CONFLICT (slot) DO UPDATE
+             SET block_hash = EXCLUDED.block_hash, bid_wei = EXCLUDED.bid_wei,
+                 subsidy_wei = EXCLUDED.subsidy_wei, bids = build_decisions.bids + 1,
+                 submissions = EXCLUDED.submissions,
+                 first_submitted_at = COALESCE(build_decisions.first_submitted_at, EXCLUDED.first_submitted_at),
+                 last_submitted_at = EXCLUDED.last_submitted_at",
+        )
+        .bind(slot as i64)
+        .bind(format!("{:?}", block_hash))
+        .bind(value.to_string())
+        .bind(subsidy.map(|subsidy| subsidy.to_string()))
+        .bind(sqlx::types::Json(submissions))
+        .bind(submitted_at)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to record bid decision")?;
+        
+        Ok(())
+    }
+    
+    async fn get_once(&self, slot: u64) -> Result<Option<BuildDecision>> {
+        let row = sqlx::query(
+            "SELECT slot, head_block, candidates, included, conflicts_resolved, ordering_hash,
+                    value_wei::TEXT AS value_wei, template_built_at, block_hash,
+                    bid_wei::TEXT AS bid_wei, subsidy_wei::TEXT AS subsidy_wei,
+                    bids, submissions, first_submitted_at, last_submitted_at
+             FROM build_decisions
+             WHERE slot = $1",
+        )
+        .bind(slot as i64)
+        .fetch_optional(&self.db_pool)
+        .await
+        .context("Failed to fetch build decision")?;
+        
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        let count = |column: &str| -> Result<Option<u32>> {
+            Ok(row.try_get::<Option<i32>, _>(column)?.map(|n| n as u32))
+        };
+        
+        Ok(Some(BuildDecision {
+            slot: row.try_get::<i64, _>("slot")? as u64,
+            head_block: row.try_get::<Option<i64>, _>("head_block")?.map(|n| n as u64),
+            candidates: count("candidates")?,
+            included: count("included")?,
+            conflicts_resolved: count("conflicts_resolved")?,
+            ordering_hash: row.try_get("ordering_hash")?,
+            value_wei: row.try_get("value_wei")?,
+            template_built_at: row.try_get("template_built_at")?,
+            block_hash: row.try_get("block_hash")?,
+            bid_wei: row.try_get("bid_wei")?,
+            subsidy_wei: row.try_get("subsidy_wei")?,
+            bids: row.try_get::<i32, _>("bids")? as u32,
+            submissions: row.try_get::<sqlx::types::Json<Vec<RelaySubmission>>, _>("submissions")?.0,
+            first_submitted_at: row.try_get("first_submitted_at")?,
+            last_submitted_at: row.try_get("last_submitted_at")?,
+        }))
+    }
+}
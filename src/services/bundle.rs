@@ -0,0 +1,246 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::{Bytes, H256, U256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{sync::Arc, time::Duration};
+use tracing::{debug, info, warn};
+
+use crate::{
+    api::models,
+    config::BundleConfig,
+    services::{drain::DrainController, risk::RiskManager, transaction::TransactionService},
+    utils::audit,
+};
+
+/// A Flashbots-style bundle targeting one block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bundle {
+    /// Signed raw transactions in bundle order
+    pub txs: Vec<Bytes>,
+    pub tx_hashes: Vec<H256>,
+    pub target_block: u64,
+    /// Combined simulated profit in wei
+    #[serde(with = "models::wei")]
+    pub profit: U256,
+}
+
+/// Relay acknowledgement of a submitted bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleReceipt {
+    pub bundle_hash: H256,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<BundleReceipt>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+/// Assembles profitable inclusion candidates into bundles and submits them via `eth_sendBundle`
+#[derive(Clone)]
+pub struct BundleService {
+    /// Transaction service, source of inclusion candidates
+    transaction_service: TransactionService,
+    /// Drain controller, used to stop submitting while draining
+    drain: DrainController,
+    /// HTTP client for the relay
+    http: reqwest::Client,
+    /// Key signing the relay authentication header
+    signer: Option<Arc<LocalWallet>>,
+    /// Minimum profit for a candidate to be bundled
+    min_profit: U256,
+    /// Configuration
+    config: BundleConfig,
+    /// Dry run switch, when on bundles are assembled and signed but not sent; told how the
+    /// relay took the ones that were
+    risk_manager: RiskManager,
+}
+
+impl BundleService {
+    /// Create a new bundle service
+    pub fn new(
+        config: BundleConfig,
+        transaction_service: TransactionService,
+        drain: DrainController,
+        risk_manager: RiskManager,
+    ) -> Result<Self> {
+        let signer = config
+            .signing_key
+            .as_deref()
+            .map(|key| key.parse::<LocalWallet>())
+            .transpose()
+            .context("Invalid bundle signing key")?
+            .map(Arc::new);
+        
+        let min_profit = U256::from_dec_str(&config.min_profit_wei).context("Invalid bundle min_profit_wei")?;
+        
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(2))
+            .build()
+            .context("Failed to create bundle relay HTTP client")?;
+        
+        Ok(Self {
+            transaction_service,
+            drain,
+            http,
+            signer,
+            min_profit,
+            config,
+            risk_manager,
+        })
+    }
+    
+    /// Whether bundle submission is enabled
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Assemble the most profitable candidates into a bundle for the target block
+    pub async fn build_bundle(&self, target_block: u64) -> Option<Bundle> {
+        let candidates: Vec<_> = self
+            .transaction_service
+            .block_template()
+            .await
+            .into_iter()
+            .filter(|candidate| candidate.profit >= self.min_profit)
+            .take(self.config.max_bundle_txs)
+            .collect();
+        
+        if candidates.is_empty() {
+            return None;
+        }
+        
+        Some(Bundle {
+            txs: candidates.iter().map(|c| c.tx.rlp()).collect(),
+            tx_hashes: candidates.iter().map(|c| c.tx.hash).collect(),
+            target_block,
+            profit: candidates
+                .iter()
+                .fold(U256::zero(), |acc, c| acc.saturating_add(c.profit)),
+        })
+    }
+    
+    /// Build and submit a bundle for the target block, if there is anything worth bundling
+    pub async fn submit_for_block(&self, target_block: u64) -> Result<Option<BundleReceipt>> {
+        if !self.enabled() {
+            return Ok(None);
+        }
+        
+        let bundle = match self.build_bundle(target_block).await {
+            Some(bundle) => bundle,
+            None => return Ok(None),
+        };
+        
+        self.submit(&bundle).await.map(Some)
+    }
+    
+    /// Submit a bundle to the configured relay
+    pub async fn submit(&self, bundle: &Bundle) -> Result<BundleReceipt> {
+        if self.drain.is_draining() {
+            return Err(anyhow!("Draining, not submitting bundles"));
+        }
+        
+        let signer = self
+            .signer
+            .as_ref()
+            .ok_or_else(|| anyhow!("No bundle signing key configured"))?;
+        
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_sendBundle",
+            "params": [{
+                "txs": bundle.txs,
+                "blockNumber": format!("0x{:x}", bundle.target_block),
+            }],
+        })
+        .to_string();
+        
+        // Flashbots authenticates requests by a signature over the body hash
+        let body_hash = format!("0x{}", hex::encode(keccak256(body.as_bytes())));
+        let signature = signer.sign_message(body_hash).await?;
+        let auth = format!("{:?}:0x{}", signer.address(), signature);
+        
+        if self.risk_manager.is_dry_run() {
+            return Ok(self.dry_run(bundle));
+        }
+        
+        let result = self.send(body, auth).await;
+        audit::bundle_submitted(
+            result.as_ref().ok().map(|receipt| receipt.bundle_hash),
+            &bundle.tx_hashes,
+            bundle.target_block,
+            bundle.profit,
+            &self.config.relay_url,
+            result.as_ref().err(),
+        );
+        let outcome = if result.is_ok() { "accepted" } else { "rejected" };
+        metrics::counter!("bundles_submitted_total", 1, "outcome" => outcome);
+        self.risk_manager.record_bundle(result.is_ok()).await;
+        
+        match &result {
+            Ok(receipt) => info!(
+                "Submitted bundle {:?} of {} txs for block {}",
+                receipt.bundle_hash,
+                bundle.txs.len(),
+                bundle.target_block
+            ),
+            Err(e) => warn!("Bundle for block {} was rejected: {}", bundle.target_block, e),
+        }
+        
+        result
+    }
+    
+    /// Receipt for a bundle that was not sent, hashed the way Flashbots hashes bundles
+    fn dry_run(&self, bundle: &Bundle) -> BundleReceipt {
+        let hashes: Vec<u8> = bundle.tx_hashes.iter().flat_map(|hash| hash.to_fixed_bytes()).collect();
+        let bundle_hash = H256::from(keccak256(hashes));
+        
+        metrics::counter!("bundles_submitted_total", 1, "outcome" => "dry_run");
+        metrics::counter!("dry_run_suppressed_total", 1, "kind" => "bundle");
+        info!(
+            "Dry run, not submitting bundle {:?} of {} txs for block {} worth {} wei",
+            bundle_hash,
+            bundle.txs.len(),
+            bundle.target_block,
+            bundle.profit
+        );
+        
+        BundleReceipt { bundle_hash }
+    }
+    
+    async fn send(&self, body: String, auth: String) -> Result<BundleReceipt> {
+        let response: RpcResponse = self
+            .http
+            .post(&self.config.relay_url)
+            .header("Content-Type", "application/json")
+            .header("X-Flashbots-Signature", auth)
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        
+        if let Some(error) = response.error {
+            return Err(anyhow!("Relay error: {}", error.message));
+        }
+        
+        let receipt = response
+            .result
+            .ok_or_else(|| anyhow!("Relay returned no bundle hash"))?;
+        debug!("Relay accepted bundle {:?}", receipt.bundle_hash);
+        
+        Ok(receipt)
+    }
+}
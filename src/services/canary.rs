@@ -0,0 +1,290 @@
+use anyhow::Result;
+use ethers::types::{Eip1559TransactionRequest, H256, U256};
+use parking_lot::Mutex;
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::time::sleep;
+use tracing::{debug, info, warn};
+
+use crate::{
+    blockchain::{fees::Urgency, signer::SignerRegistry},
+    config::CanaryConfig,
+    services::{
+        alerting::{Alert, AlertManager, Severity},
+        drain::DrainController,
+        risk::RiskManager,
+        transaction::{TransactionService, TxLifecycle},
+    },
+};
+
+/// How often the in-memory pipeline state is checked for a canary
+const PIPELINE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often the node is asked for a canary's receipt
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How far a canary got through the pipeline, in the order it passes the stages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    /// Accepted by the node
+    Submitted,
+    /// Seen by the transaction monitor and tracked in the mempool view
+    Detected,
+    /// Simulated, with its profit recorded in the mempool view
+    Simulated,
+    /// Mined in a block
+    Included,
+    /// Buried under the configured confirmations
+    Confirmed,
+}
+
+impl Stage {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Submitted => "submitted",
+            Self::Detected => "detected",
+            Self::Simulated => "simulated",
+            Self::Included => "included",
+            Self::Confirmed => "confirmed",
+        }
+    }
+    
+    /// The stage a canary that only reached this one failed at
+    fn next(&self) -> Self {
+        match self {
+            Self::Submitted => Self::Detected,
+            Self::Detected => Self::Simulated,
+            Self::Simulated => Self::Included,
+            Self::Included | Self::Confirmed => Self::Confirmed,
+        }
+    }
+}
+
+/// Result of one canary
+#[derive(Debug, Clone, Copy)]
+struct CanaryOutcome {
+    /// Submission to confirmation, when every stage was passed
+    latency: Option<Duration>,
+}
+
+/// Sends a harmless self-transfer through the whole pipeline on a schedule and follows it
+///
+/// Each canary has to be picked up by the transaction monitor, simulated, mined and confirmed
+/// within the timeout. A canary mined before the pipeline saw it counts as failed, since real
+/// flow would have been missed the same way. Success rate and median latency are taken over
+/// the last `window` canaries and alerted on once the window is full.
+#[derive(Clone)]
+pub struct CanaryService {
+    /// Canary configuration
+    config: CanaryConfig,
+    /// Sends canaries and exposes the mempool view they are detected in
+    transaction_service: TransactionService,
+    /// Signer of the canary account
+    signers: SignerRegistry,
+    /// Nothing is sent in a dry run, so canaries are skipped
+    risk_manager: RiskManager,
+    /// Canaries are skipped while draining
+    drain: DrainController,
+    /// Alert manager, notified of degraded latency or success rate
+    alert_manager: AlertManager,
+    /// Outcomes of the most recent canaries, oldest first
+    outcomes: Arc<Mutex<VecDeque<CanaryOutcome>>>,
+}
+
+impl CanaryService {
+    /// Create a new canary service
+    pub fn new(
+        config: CanaryConfig,
+        transaction_service: TransactionService,
+        signers: SignerRegistry,
+        risk_manager: RiskManager,
+        drain: DrainController,
+        alert_manager: AlertManager,
+    ) -> Self {
+        Self {
+            outcomes: Arc::new(Mutex::new(VecDeque::with_capacity(config.window))),
+            config,
+            transaction_service,
+            signers,
+            risk_manager,
+            drain,
+            alert_manager,
+        }
+    }
+    
+    /// Whether canaries are sent
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Send one canary, follow it through the pipeline and alert if the window degraded
+    pub async fn run(&self) -> Result<()> {
+        if self.risk_manager.is_dry_run() || self.drain.is_draining() {
+            debug!("Skipping canary while nothing is sent");
+            return Ok(());
+        }
+        
+        let outcome = self.send_canary().await?;
+        metrics::counter!("canary_runs_total", 1);
+        
+        {
+            let mut outcomes = self.outcomes.lock();
+            outcomes.push_back(outcome);
+            while outcomes.len() > self.config.window {
+                outcomes.pop_front();
+            }
+        }
+        
+        self.evaluate().await;
+        Ok(())
+    }
+    
+    /// Send a zero-value transfer from the canary account to itself and follow it
+    async fn send_canary(&self) -> Result<CanaryOutcome> {
+        let account = self.signers.address(&self.config.signer)?;
+        let tx = Eip1559TransactionRequest::new().to(account).value(U256::zero());
+        
+        let started = Instant::now();
+        let tx_hash = match self
+            .transaction_service
+            .send_transaction_as(&self.config.signer, tx, Urgency::Normal)
+            .await
+        {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                warn!("Failed to send canary: {}", e);
+                return Ok(self.failed(Stage::Submitted));
+            }
+        };
+        self.reached(Stage::Submitted, started);
+        
+        let reached = self.follow(tx_hash, started).await;
+        if reached < Stage::Confirmed {
+            return Ok(self.failed(reached.next()));
+        }
+        
+        let latency = started.elapsed();
+        info!("Canary {} confirmed in {:?}", tx_hash, latency);
+        Ok(CanaryOutcome { latency: Some(latency) })
+    }
+    
+    /// Follow a sent canary, returning the last stage it reached before it stalled or confirmed
+    async fn follow(&self, tx_hash: H256, started: Instant) -> Stage {
+        let deadline = started + Duration::from_secs(self.config.timeout_seconds);
+        let mut reached = Stage::Submitted;
+        let mut next_receipt_poll = started;
+        
+        while Instant::now() < deadline {
+            if reached < Stage::Simulated {
+                if let Some(view) = self.transaction_service.pending_transaction(&tx_hash) {
+                    if reached < Stage::Detected {
+                        reached = self.reached(Stage::Detected, started);
+                    }
+                    if view.profit.is_some() {
+                        reached = self.reached(Stage::Simulated, started);
+                    }
+                }
+            }
+            
+            if Instant::now() >= next_receipt_poll {
+                next_receipt_poll = Instant::now() + RECEIPT_POLL_INTERVAL;
+                let statuses = self.transaction_service.transaction_statuses(&[tx_hash]).await;
+                if let Some(status) = statuses.into_iter().next() {
+                    match status.status {
+                        TxLifecycle::Included if reached < Stage::Simulated => {
+                            // Evicted from the mempool view on inclusion, so it can't be seen any more
+                            let missed = reached.next().as_str();
+                            warn!("Canary {} was mined before the pipeline {} it", tx_hash, missed);
+                            return reached;
+                        }
+                        TxLifecycle::Included => {
+                            if reached < Stage::Included {
+                                reached = self.reached(Stage::Included, started);
+                            }
+                            if status.confirmations.unwrap_or(0) >= self.config.confirmations {
+                                return self.reached(Stage::Confirmed, started);
+                            }
+                        }
+                        TxLifecycle::Reverted => {
+                            warn!("Canary {} reverted", tx_hash);
+                            return reached;
+                        }
+                        TxLifecycle::Error => {
+                            debug!("Canary {} status lookup failed: {:?}", tx_hash, status.error);
+                        }
+                        TxLifecycle::Pending | TxLifecycle::Unknown => {}
+                    }
+                }
+            }
+            
+            sleep(PIPELINE_POLL_INTERVAL).await;
+        }
+        
+        warn!(
+            "Canary {} timed out after {}s without being {}",
+            tx_hash,
+            self.config.timeout_seconds,
+            reached.next().as_str()
+        );
+        reached
+    }
+    
+    fn reached(&self, stage: Stage, started: Instant) -> Stage {
+        metrics::histogram!("canary_stage_seconds", started.elapsed().as_secs_f64(), "stage" => stage.as_str());
+        stage
+    }
+    
+    fn failed(&self, stage: Stage) -> CanaryOutcome {
+        metrics::counter!("canary_failures_total", 1, "stage" => stage.as_str());
+        CanaryOutcome { latency: None }
+    }
+    
+    /// Alert when the full window's success rate or median latency is out of bounds
+    async fn evaluate(&self) {
+        let (success_rate, median_latency) = {
+            let outcomes = self.outcomes.lock();
+            if outcomes.len() < self.config.window {
+                return;
+            }
+            let mut latencies: Vec<Duration> = outcomes.iter().filter_map(|outcome| outcome.latency).collect();
+            latencies.sort();
+            let success_rate = latencies.len() as f64 / outcomes.len() as f64;
+            (success_rate, latencies.get(latencies.len() / 2).copied())
+        };
+        
+        metrics::gauge!("canary_success_rate", success_rate);
+        if let Some(median_latency) = median_latency {
+            metrics::gauge!("canary_latency_seconds", median_latency.as_secs_f64());
+        }
+        
+        if success_rate < self.config.min_success_rate {
+            let message = format!(
+                "{:.0}% of the last {} canaries made it through the pipeline, below {:.0}%",
+                success_rate * 100.0,
+                self.config.window,
+                self.config.min_success_rate * 100.0
+            );
+            warn!("{}", message);
+            self.alert_manager
+                .fire(Alert::new("canary:success_rate", Severity::Critical, "canary", message))
+                .await;
+        }
+        
+        if let Some(median_latency) = median_latency {
+            let max_latency = Duration::from_millis(self.config.max_latency_ms);
+            if median_latency > max_latency {
+                let message = format!(
+                    "Median canary latency over the last {} canaries is {:?}, above {:?}",
+                    self.config.window, median_latency, max_latency
+                );
+                warn!("{}", message);
+                self.alert_manager
+                    .fire(Alert::new("canary:latency", Severity::Warning, "canary", message))
+                    .await;
+            }
+        }
+    }
+}
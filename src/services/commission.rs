@@ -0,0 +1,460 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use ethers::types::{Address, BlockNumber, Eip1559TransactionRequest, H256, U256};
+use serde::Serialize;
+use sqlx::{postgres::PgRow, Row};
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    blockchain::{client::is_node_rejection, fees::Urgency, BlockchainClient},
+    config::CommissionPayoutConfig,
+    database::{DbHealth, DbPool},
+    services::{
+        staking_ledger::StakingLedger,
+        transaction::{SendUnconfirmed, TransactionService},
+    },
+    utils::units::{wei_to_eth, EthAmount},
+};
+
+/// Epochs of accruals recomputed on each run, picking up rewards booked late; about a day
+const ACCRUAL_RECOMPUTE_EPOCHS: u64 = 225;
+
+/// One commission accrual or payout
+#[derive(Debug, Clone, Serialize)]
+pub struct CommissionEntry {
+    pub id: i64,
+    /// `accrual` or `payout`
+    pub kind: String,
+    pub epoch: Option<u64>,
+    pub amount: EthAmount,
+    pub treasury: Option<String>,
+    pub tx_hash: Option<String>,
+    pub status: String,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Outstanding balance and recent ledger entries
+#[derive(Debug, Clone, Serialize)]
+pub struct CommissionHistory {
+    /// Accrued and not yet paid out
+    pub balance: EthAmount,
+    pub entries: Vec<CommissionEntry>,
+}
+
+/// Operator commission ledger and payouts to the treasury
+///
+/// Commission is accrued per epoch from booked staking rewards at the validator commission
+/// rate. Payouts debit the balance as soon as they are recorded, before the transfer is sent,
+/// so a crash mid-payout can never pay the same commission twice; a payout left `pending` is
+/// flagged for an operator and blocks further payouts until resolved, as is one whose send
+/// failed without the node refusing it. Sent payouts, and those the node may have taken before
+/// the request failed, settle by nonce, following replacements of a stuck transfer.
+#[derive(Clone)]
+pub struct CommissionService {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Blockchain client, checks payout receipts
+    blockchain_client: Arc<BlockchainClient>,
+    /// Transaction service, sends payouts
+    transaction_service: TransactionService,
+    /// Staking ledger, source of epoch timing and the commission rate
+    staking_ledger: StakingLedger,
+    /// Payout configuration
+    config: CommissionPayoutConfig,
+    /// Parsed treasury address, set when payouts are enabled
+    treasury: Option<Address>,
+}
+
+impl CommissionService {
+    /// Create a new commission service
+    pub fn new(
+        db_pool: DbPool,
+        health: DbHealth,
+        blockchain_client: Arc<BlockchainClient>,
+        transaction_service: TransactionService,
+        staking_ledger: StakingLedger,
+        config: CommissionPayoutConfig,
+    ) -> Result<Self> {
+        let treasury = if config.enabled {
+            Some(
+                config
+                    .treasury_address
+                    .parse()
+                    .context("Invalid commission treasury address")?,
+            )
+        } else {
+            None
+        };
+        
+        Ok(Self {
+            db_pool,
+            health,
+            blockchain_client,
+            transaction_service,
+            staking_ledger,
+            config,
+            treasury,
+        })
+    }
+    
+    /// Whether payouts are enabled
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Book commission of completed epochs from staking rewards, returning the epochs written
+    pub async fn accrue(&self) -> Result<u64> {
+        self.health.retry(|| self.accrue_once()).await
+    }
+    
+    /// Commission accrued and not yet paid out; payouts count unless they failed
+    pub async fn balance(&self) -> Result<U256> {
+        self.health.retry(|| self.balance_once()).await
+    }
+    
+    /// Settle sent payouts, accrue new commission and pay out the balance if it is large enough
+    pub async fn run_payouts(&self) -> Result<()> {
+        let treasury = self
+            .treasury
+            .ok_or_else(|| anyhow!("Commission payouts are disabled"))?;
+        
+        self.settle_sent().await?;
+        self.accrue().await?;
+        
+        let balance = self.balance().await?;
+        metrics::gauge!("commission_balance_eth", wei_to_eth(balance));
+        
+        if self.health.retry(|| self.unresolved_payouts()).await? > 0 {
+            warn!("A commission payout is unresolved; resolve it before further payouts");
+            return Ok(());
+        }
+        
+        if balance.is_zero() || balance < self.config.min_payout_amount.wei() {
+            debug!("Commission balance {} is below the payout minimum", EthAmount::from_wei(balance));
+            return Ok(());
+        }
+        
+        // A payout recorded in a dry run would debit commission that never left
+        if self.transaction_service.is_dry_run() {
+            info!("Dry run, not paying out {} ETH of commission", EthAmount::from_wei(balance));
+            return Ok(());
+        }
+        
+        self.pay(treasury, balance).await
+    }
+    
+    /// Payouts recorded and not known to be either sent or refused
+    async fn unresolved_payouts(&self) -> Result<i64> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) AS pending FROM commission_ledger WHERE kind = 'payout' AND status = 'pending'",
+        )
+        .fetch_one(&self.db_pool)
+        .await
+        .context("Failed to check for unresolved payouts")?;
+        
+        Ok(row.try_get("pending")?)
+    }
+    
+    /// Record, then send, a payout of `amount` to the treasury
+    async fn pay(&self, treasury: Address, amount: U256) -> Result<()> {
+        // Not retried: an insert whose commit was lost with the connection would debit twice
+        let row = sqlx::query(
+            "INSERT INTO commission_ledger (kind, amount_wei, treasury, status)
+             VALUES ('payout', $1::NUMERIC, $2, 'pending')
+             RETURNING id",
+        )
+        .bind(amount.to_string())
+        .bind(format!("{:?}", treasury))
+        .fetch_one(&self.db_pool)
+        .await
+        .context("Failed to record commission payout")?;
+        let id: i64 = row.try_get("id")?;
+        
+        let tx = Eip1559TransactionRequest::new().to(treasury).value(amount);
+        match self
+            .transaction_service
+            .send_transaction_as(&self.config.signer, tx, Urgency::Normal)
+            .await
+        {
+            Ok(tx_hash) => {
+                self.update_payout(id, "sent", Some(tx_hash), None).await?;
+                if let Err(e) = self.record_nonce(id, tx_hash).await {
+                    warn!("Failed to record the nonce of commission payout {}: {}", id, e);
+                }
+                info!(
+                    "Sent commission payout {} of {} ETH to {:?} in {:?}",
+                    id,
+                    EthAmount::from_wei(amount),
+                    treasury,
+                    tx_hash
+                );
+            }
+            Err(e) => match e.downcast_ref::<SendUnconfirmed>() {
+                // The node may hold it, so it settles by receipt and nonce like any sent payout
+                Some(unconfirmed) => {
+                    warn!("Commission payout {} may have been sent: {}", id, e);
+                    self.update_payout(id, "sent", Some(unconfirmed.tx_hash), Some(e.to_string())).await?;
+                    let (sender, nonce) = (unconfirmed.from, unconfirmed.nonce);
+                    if let Err(e) = self.health.retry(|| self.store_nonce(id, sender, nonce)).await {
+                        warn!("Failed to record the nonce of commission payout {}: {}", id, e);
+                    }
+                }
+                // The node refused it, so nothing left our account
+                None if is_node_rejection(&e) => {
+                    error!("Commission payout {} failed: {}", id, e);
+                    self.update_payout(id, "failed", None, Some(e.to_string())).await?;
+                    metrics::counter!("commission_payouts_total", 1, "status" => "failed");
+                }
+                // Whether anything left is unknown, so it stays pending for an operator
+                None => {
+                    error!("Commission payout {} is unresolved: {}", id, e);
+                    self.update_payout(id, "pending", None, Some(e.to_string())).await?;
+                }
+            },
+        }
+        
+        Ok(())
+    }
+    
+    /// Confirm or fail sent payouts that have been mined
+    async fn settle_sent(&self) -> Result<()> {
+        let rows = self.health.retry(|| self.sent_payouts()).await?;
+        
+        for row in rows {
+            let id: i64 = row.try_get("id")?;
+            if let Err(e) = self.settle_payout(&row).await {
+                warn!("Failed to settle commission payout {}: {}", id, e);
+            }
+        }
+        
+        Ok(())
+    }
+    
+    async fn sent_payouts(&self) -> Result<Vec<PgRow>> {
+        sqlx::query(
+            "SELECT id, tx_hash, sender, nonce FROM commission_ledger WHERE kind = 'payout' AND status = 'sent'",
+        )
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to load sent payouts")
+    }
+    
+    /// Settle one sent payout by its transaction, or whichever replaced it at its nonce
+    async fn settle_payout(&self, row: &PgRow) -> Result<()> {
+        let id: i64 = row.try_get("id")?;
+        let mut tx_hash: H256 = row
+            .try_get::<Option<String>, _>("tx_hash")?
+            .ok_or_else(|| anyhow!("Sent payout has no transaction hash"))?
+            .parse()
+            .context("Invalid transaction hash")?;
+        let nonce = match (row.try_get::<Option<String>, _>("sender")?, row.try_get::<Option<i64>, _>("nonce")?) {
+            (Some(sender), Some(nonce)) => {
+                let sender: Address = sender.parse().context("Invalid payout sender")?;
+                Some((sender, nonce as u64))
+            }
+            _ => None,
+        };
+        
+        // A stuck payout may have been re-sent with higher fees at the same nonce
+        if let Some((sender, nonce)) = nonce {
+            if let Some(current) = self.transaction_service.in_flight_hash(sender, nonce).await? {
+                if current != tx_hash {
+                    info!("Commission payout {} was replaced by {:?}", id, current);
+                    self.update_payout(id, "sent", Some(current), None).await?;
+                    tx_hash = current;
+                }
+            }
+        }
+        
+        let receipt = match self.blockchain_client.get_transaction_receipt(tx_hash).await? {
+            Some(receipt) => receipt,
+            None => {
+                if let Some((sender, nonce)) = nonce {
+                    let mined = self
+                        .blockchain_client
+                        .get_transaction_count(sender, BlockNumber::Latest)
+                        .await?;
+                    if mined > nonce {
+                        // Paid or not, only an operator can tell; hold further payouts until then
+                        let error = format!("nonce {} was mined by an untracked transaction", nonce);
+                        error!("Commission payout {} is unresolved: {}", id, error);
+                        self.update_payout(id, "pending", None, Some(error)).await?;
+                    }
+                }
+                return Ok(());
+            }
+        };
+        
+        if receipt.status.map_or(false, |status| status.as_u64() == 1) {
+            self.update_payout(id, "confirmed", None, None).await?;
+            metrics::counter!("commission_payouts_total", 1, "status" => "confirmed");
+            info!("Commission payout {} confirmed in block {:?}", id, receipt.block_number);
+        } else {
+            self.update_payout(id, "failed", None, Some("transaction reverted".to_string())).await?;
+            metrics::counter!("commission_payouts_total", 1, "status" => "failed");
+            error!("Commission payout {} reverted in {:?}", id, tx_hash);
+        }
+        
+        Ok(())
+    }
+    
+    /// Store the sending account and nonce of a sent payout
+    async fn record_nonce(&self, id: i64, tx_hash: H256) -> Result<()> {
+        let tx = self
+            .blockchain_client
+            .get_transaction(tx_hash)
+            .await?
+            .ok_or_else(|| anyhow!("Node does not know payout transaction {:?}", tx_hash))?;
+        
+        self.health.retry(|| self.store_nonce(id, tx.from, tx.nonce.as_u64())).await
+    }
+    
+    async fn store_nonce(&self, id: i64, sender: Address, nonce: u64) -> Result<()> {
+        sqlx::query("UPDATE commission_ledger SET sender = $2, nonce = $3, updated_at = NOW() WHERE id = $1")
+            .bind(id)
+            .bind(format!("{:?}", sender))
+            .bind(nonce as i64)
+            .execute(&self.db_pool)
+            .await
+            .context("Failed to record commission payout nonce")?;
+        
+        Ok(())
+    }
+    
+    async fn update_payout(
+        &self,
+        id: i64,
+        status: &str,
+        tx_hash: Option<H256>,
+        error: Option<String>,
+    ) -> Result<()> {
+        self.health
+            .retry(|| self.update_payout_once(id, status, tx_hash, error.as_deref()))
+            .await
+    }
+    
+    async fn update_payout_once(
+        &self,
+        id: i64,
+        status: &str,
+        tx_hash: Option<H256>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE commission_ledger
+             SET status = $2, tx_hash = COALESCE($3, tx_hash), error = $4, updated_at = NOW()
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(status)
+        .bind(tx_hash.map(|hash| format!("{:?}", hash)))
+        .bind(error)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to update commission payout")?;
+        
+        Ok(())
+    }
+    
+    /// Current balance and the most recent ledger entries, newest first
+    pub async fn history(&self, kind: Option<&str>, limit: i64) -> Result<CommissionHistory> {
+        let entries = self.health.retry(|| self.entries(kind, limit)).await?;
+        
+        Ok(CommissionHistory {
+            balance: EthAmount::from_wei(self.balance().await?),
+            entries,
+        })
+    }
+    
+    async fn entries(&self, kind: Option<&str>, limit: i64) -> Result<Vec<CommissionEntry>> {
+        let rows = sqlx::query(
+            "SELECT id, kind, epoch, amount_wei::TEXT AS amount, treasury, tx_hash, status, error,
+                    created_at, updated_at
+             FROM commission_ledger
+             WHERE $1::TEXT IS NULL OR kind = $1
+             ORDER BY created_at DESC, id DESC
+             LIMIT $2",
+        )
+        .bind(kind)
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to load commission history")?;
+        
+        let mut entries = Vec::with_capacity(rows.len());
+        for row in rows {
+            let amount: String = row.try_get("amount")?;
+            entries.push(CommissionEntry {
+                id: row.try_get("id")?,
+                kind: row.try_get("kind")?,
+                epoch: row.try_get::<Option<i64>, _>("epoch")?.map(|epoch| epoch as u64),
+                amount: EthAmount::from_wei(
+                    U256::from_dec_str(&amount).map_err(|e| anyhow!("Invalid commission amount: {}", e))?,
+                ),
+                treasury: row.try_get("treasury")?,
+                tx_hash: row.try_get("tx_hash")?,
+                status: row.try_get("status")?,
+                error: row.try_get("error")?,
+                created_at: row.try_get("created_at")?,
+                updated_at: row.try_get("updated_at")?,
+            });
+        }
+        
+        Ok(entries)
+    }
+    
+    async fn accrue_once(&self) -> Result<u64> {
+        let row = sqlx::query("SELECT MAX(epoch) AS epoch FROM commission_ledger WHERE kind = 'accrual'")
+            .fetch_one(&self.db_pool)
+            .await
+            .context("Failed to load last commission epoch")?;
+        let from = row
+            .try_get::<Option<i64>, _>("epoch")?
+            .map_or(0, |epoch| (epoch as u64).saturating_sub(ACCRUAL_RECOMPUTE_EPOCHS));
+        
+        let result = sqlx::query(
+            "INSERT INTO commission_ledger (kind, epoch, amount_wei, status)
+             SELECT 'accrual', epoch, TRUNC(SUM(consensus_wei + mev_wei) * $3 / 10000), 'accrued'
+             FROM staking_rewards
+             WHERE epoch >= $1 AND epoch < $2
+             GROUP BY epoch
+             HAVING TRUNC(SUM(consensus_wei + mev_wei) * $3 / 10000) > 0
+             ON CONFLICT (epoch) WHERE kind = 'accrual' DO UPDATE SET
+                 amount_wei = EXCLUDED.amount_wei,
+                 updated_at = NOW()
+             WHERE commission_ledger.amount_wei <> EXCLUDED.amount_wei",
+        )
+        .bind(from as i64)
+        .bind(self.staking_ledger.current_epoch() as i64)
+        .bind(self.staking_ledger.commission_bps() as i64)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to accrue commission")?;
+        
+        debug!("Accrued commission for {} epochs from {}", result.rows_affected(), from);
+        Ok(result.rows_affected())
+    }
+    
+    async fn balance_once(&self) -> Result<U256> {
+        let row = sqlx::query(
+            "SELECT COALESCE(SUM(CASE WHEN kind = 'accrual' THEN amount_wei ELSE -amount_wei END), 0)::TEXT
+                        AS balance
+             FROM commission_ledger
+             WHERE kind = 'accrual' OR status <> 'failed'",
+        )
+        .fetch_one(&self.db_pool)
+        .await
+        .context("Failed to load commission balance")?;
+        
+        let balance: String = row.try_get("balance")?;
+        if balance.starts_with('-') {
+            return Err(anyhow!("Commission ledger is overdrawn by {} wei", &balance[1..]));
+        }
+        U256::from_dec_str(&balance).map_err(|e| anyhow!("Invalid commission balance {}: {}", balance, e))
+    }
+}
@@ -0,0 +1,118 @@
+use anyhow::{anyhow, Context, Result};
+use dashmap::{DashMap, DashSet};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::database::{DbHealth, DbPool};
+
+/// Runtime state of one controllable subsystem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsystemState {
+    pub name: String,
+    pub paused: bool,
+}
+
+/// Runtime pause/resume controls for individual subsystems, persisted across restarts
+#[derive(Clone)]
+pub struct SubsystemControls {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Desired paused state by subsystem name
+    paused: Arc<DashMap<String, bool>>,
+    /// Subsystems that consult these controls
+    known: Arc<DashSet<String>>,
+}
+
+impl SubsystemControls {
+    /// Create the controls, loading the persisted desired state
+    pub async fn load(db_pool: DbPool, health: DbHealth) -> Result<Self> {
+        let paused = health.retry(|| load_state(&db_pool)).await?;
+        
+        Ok(Self {
+            db_pool,
+            health,
+            paused: Arc::new(paused),
+            known: Arc::new(DashSet::new()),
+        })
+    }
+    
+    /// Declare a subsystem as controllable
+    pub fn register(&self, name: &str) {
+        self.known.insert(name.to_string());
+    }
+    
+    /// Whether a subsystem is currently paused
+    pub fn is_paused(&self, name: &str) -> bool {
+        self.paused.get(name).map(|paused| *paused).unwrap_or(false)
+    }
+    
+    /// Pause or resume a subsystem and persist the desired state
+    pub async fn set_paused(&self, name: &str, paused: bool) -> Result<()> {
+        if !self.known.contains(name) {
+            return Err(anyhow!("Unknown subsystem: {}", name));
+        }
+        
+        self.health.retry(|| self.persist(name, paused)).await?;
+        
+        self.paused.insert(name.to_string(), paused);
+        info!("Subsystem {} {}", name, if paused { "paused" } else { "resumed" });
+        
+        Ok(())
+    }
+    
+    /// Whether a name refers to a controllable subsystem
+    pub fn is_known(&self, name: &str) -> bool {
+        self.known.contains(name)
+    }
+    
+    /// State of every controllable subsystem
+    pub fn list(&self) -> Vec<SubsystemState> {
+        let mut states: Vec<SubsystemState> = self
+            .known
+            .iter()
+            .map(|name| SubsystemState {
+                paused: self.is_paused(&name),
+                name: name.clone(),
+            })
+            .collect();
+        states.sort_by(|a, b| a.name.cmp(&b.name));
+        states
+    }
+    
+    async fn persist(&self, name: &str, paused: bool) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO subsystem_state (name, paused, updated_at) VALUES ($1, $2, NOW())
+             ON CONFLICT (name) DO UPDATE SET paused = EXCLUDED.paused, updated_at = NOW()",
+        )
+        .bind(name)
+        .bind(paused)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to persist subsystem state")?;
+        
+        Ok(())
+    }
+}
+
+/// Persisted desired state by subsystem name
+async fn load_state(db_pool: &DbPool) -> Result<DashMap<String, bool>> {
+    let rows = sqlx::query("SELECT name, paused FROM subsystem_state")
+        .fetch_all(db_pool)
+        .await
+        .context("Failed to load subsystem state")?;
+    
+    let paused = DashMap::new();
+    for row in rows {
+        let name: String = row.try_get("name")?;
+        let is_paused: bool = row.try_get("paused")?;
+        if is_paused {
+            info!("Subsystem {} is paused by persisted state", name);
+        }
+        paused.insert(name, is_paused);
+    }
+    Ok(paused)
+}
@@ -0,0 +1,413 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::{
+    abi::{self, ParamType, Token},
+    types::{Address, Filter, Log, H256, U256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, Row};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    blockchain::BlockchainClient,
+    config::DepositReconciliationConfig,
+    database::{DbHealth, DbPool},
+    services::alerting::{Alert, AlertManager, Severity},
+};
+
+/// `DepositEvent(bytes pubkey, bytes withdrawal_credentials, bytes amount, bytes signature, bytes index)`
+pub const DEPOSIT_EVENT_SIGNATURE: &str = "DepositEvent(bytes,bytes,bytes,bytes,bytes)";
+
+/// Wei per gwei, the unit deposit amounts are logged in
+const WEI_PER_GWEI: u64 = 1_000_000_000;
+
+/// A mismatch between the deposit contract and our stake positions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DepositDiscrepancy {
+    /// A position assigned to a validator has no deposit on chain
+    MissingDeposit,
+    /// A deposit to our withdrawal credentials was never booked as a position
+    UnknownDepositor,
+    /// The deposited and booked amounts of a deposit differ
+    AmountMismatch,
+}
+
+impl DepositDiscrepancy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MissingDeposit => "missing_deposit",
+            Self::UnknownDepositor => "unknown_depositor",
+            Self::AmountMismatch => "amount_mismatch",
+        }
+    }
+    
+    fn severity(&self) -> Severity {
+        match self {
+            Self::MissingDeposit => Severity::Warning,
+            Self::UnknownDepositor | Self::AmountMismatch => Severity::Critical,
+        }
+    }
+}
+
+/// One decoded deposit
+#[derive(Debug, Clone)]
+struct DepositEvent {
+    tx_hash: H256,
+    log_index: u64,
+    block_number: u64,
+    /// Sender of the depositing transaction
+    depositor: Option<Address>,
+    pubkey: String,
+    withdrawal_credentials: String,
+    amount: U256,
+    deposit_index: u64,
+}
+
+/// Indexes beacon deposit contract events for our validators and reconciles them against
+/// booked stake positions
+///
+/// The indexer reads logs straight from the chain, independently of the staking flow that
+/// books positions, so a bug crediting shares without a deposit, or a deposit without shares,
+/// shows up as a discrepancy. Deposits match positions by transaction and log, so a
+/// transaction making several deposits is checked deposit by deposit. Each discrepancy is
+/// alerted once and cleared once it resolves.
+#[derive(Clone)]
+pub struct DepositReconciler {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Blockchain client, source of deposit logs
+    blockchain_client: Arc<BlockchainClient>,
+    /// Alert manager, notified of discrepancies
+    alert_manager: AlertManager,
+    /// Reconciliation configuration
+    config: DepositReconciliationConfig,
+    /// Parsed deposit contract address
+    deposit_contract: Address,
+    /// Our withdrawal credentials, lowercase 0x-prefixed hex
+    withdrawal_credentials: HashSet<String>,
+    /// Blocks behind the head treated as final
+    confirmation_blocks: u64,
+}
+
+impl DepositReconciler {
+    /// Create a new deposit reconciler
+    pub fn new(
+        db_pool: DbPool,
+        health: DbHealth,
+        blockchain_client: Arc<BlockchainClient>,
+        alert_manager: AlertManager,
+        config: DepositReconciliationConfig,
+        confirmation_blocks: u64,
+    ) -> Result<Self> {
+        let deposit_contract = config
+            .deposit_contract
+            .parse()
+            .context("Invalid deposit contract address")?;
+        let withdrawal_credentials = config
+            .withdrawal_credentials
+            .iter()
+            .map(|credentials| format!("0x{}", credentials.trim_start_matches("0x").to_lowercase()))
+            .collect();
+        
+        Ok(Self {
+            db_pool,
+            health,
+            blockchain_client,
+            alert_manager,
+            config,
+            deposit_contract,
+            withdrawal_credentials,
+            confirmation_blocks,
+        })
+    }
+    
+    /// Whether reconciliation is enabled
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Index new deposits, then reconcile
+    pub async fn run(&self) -> Result<()> {
+        let caught_up = self.index().await?;
+        self.reconcile(caught_up).await
+    }
+    
+    /// Index deposit events up to the confirmed head, returning whether it was reached
+    async fn index(&self) -> Result<bool> {
+        let head = self.blockchain_client.get_block_number().await?;
+        let safe_head = head.saturating_sub(self.confirmation_blocks);
+        
+        let mut from = self
+            .health
+            .retry(|| self.last_indexed_block())
+            .await?
+            .map_or(self.config.start_block, |last| last + 1);
+        let topic = H256::from(keccak256(DEPOSIT_EVENT_SIGNATURE));
+        
+        while from <= safe_head {
+            let to = (from + self.config.blocks_per_request - 1).min(safe_head);
+            let filter = Filter::new()
+                .address(self.deposit_contract)
+                .topic0(topic)
+                .from_block(from)
+                .to_block(to);
+            
+            let logs = self
+                .blockchain_client
+                .get_logs(&filter)
+                .await
+                .with_context(|| format!("Failed to fetch deposit logs for blocks {}..={}", from, to))?;
+            
+            let mut stored = 0;
+            let mut depositors: HashMap<H256, Option<Address>> = HashMap::new();
+            for log in &logs {
+                let mut event = decode_deposit(log)?;
+                if !self.withdrawal_credentials.contains(&event.withdrawal_credentials) {
+                    continue;
+                }
+                event.depositor = match depositors.get(&event.tx_hash) {
+                    Some(depositor) => *depositor,
+                    None => {
+                        let tx = self.blockchain_client.get_transaction(event.tx_hash).await?;
+                        let depositor = tx.map(|tx| tx.from);
+                        depositors.insert(event.tx_hash, depositor);
+                        depositor
+                    }
+                };
+                self.health.retry(|| self.store(&event)).await?;
+                stored += 1;
+            }
+            
+            self.health.retry(|| self.set_last_indexed_block(to)).await?;
+            if stored > 0 {
+                info!("Indexed {} deposits to our validators in blocks {}..={}", stored, from, to);
+            }
+            from = to + 1;
+        }
+        
+        Ok(from > safe_head)
+    }
+    
+    /// Flag new discrepancies and clear those that have resolved
+    async fn reconcile(&self, caught_up: bool) -> Result<()> {
+        // A late booking or a late deposit settles the discrepancy
+        let cleared = self.health.retry(|| self.clear_resolved()).await?;
+        if cleared > 0 {
+            info!("Cleared {} resolved deposit discrepancies", cleared);
+        }
+        
+        let unknown = self.health.retry(|| self.flag_unbooked()).await?;
+        self.raise_all(DepositDiscrepancy::UnknownDepositor, unknown).await?;
+        
+        let mismatched = self.health.retry(|| self.flag_mismatched()).await?;
+        self.raise_all(DepositDiscrepancy::AmountMismatch, mismatched).await?;
+        
+        // Absence only means something once the indexer has seen every block so far
+        if !caught_up {
+            debug!("Deposit indexer is behind the head, skipping missing deposit check");
+            return Ok(());
+        }
+        
+        let missing = self.health.retry(|| self.flag_missing()).await?;
+        self.raise_all(DepositDiscrepancy::MissingDeposit, missing).await
+    }
+    
+    async fn clear_resolved(&self) -> Result<u64> {
+        let cleared = sqlx::query(
+            "DELETE FROM deposit_discrepancies d
+             WHERE (d.kind = 'missing_deposit'
+                    AND EXISTS (SELECT 1 FROM deposit_events e
+                                WHERE e.tx_hash = d.tx_hash AND e.log_index = d.log_index))
+                OR (d.kind = 'unknown_depositor'
+                    AND EXISTS (SELECT 1 FROM stake_positions p
+                                WHERE p.tx_hash = d.tx_hash AND p.deposit_log_index = d.log_index))",
+        )
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to clear resolved deposit discrepancies")?;
+        
+        Ok(cleared.rows_affected())
+    }
+    
+    async fn flag_unbooked(&self) -> Result<Vec<PgRow>> {
+        sqlx::query(
+            "INSERT INTO deposit_discrepancies (tx_hash, log_index, kind, onchain_wei, depositor)
+             SELECT e.tx_hash, e.log_index, 'unknown_depositor', e.amount_wei, e.depositor
+             FROM deposit_events e
+             WHERE NOT EXISTS (SELECT 1 FROM stake_positions p
+                               WHERE p.tx_hash = e.tx_hash AND p.deposit_log_index = e.log_index)
+             ON CONFLICT DO NOTHING
+             RETURNING tx_hash, log_index, depositor,
+                       onchain_wei::TEXT AS onchain_wei, booked_wei::TEXT AS booked_wei",
+        )
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to check for unbooked deposits")
+    }
+    
+    async fn flag_mismatched(&self) -> Result<Vec<PgRow>> {
+        sqlx::query(
+            "INSERT INTO deposit_discrepancies (tx_hash, log_index, kind, onchain_wei, booked_wei, depositor)
+             SELECT p.tx_hash, e.log_index, 'amount_mismatch', e.amount_wei, p.amount_wei, e.depositor
+             FROM stake_positions p
+             JOIN deposit_events e ON e.tx_hash = p.tx_hash AND e.log_index = p.deposit_log_index
+             WHERE e.amount_wei <> p.amount_wei
+             ON CONFLICT DO NOTHING
+             RETURNING tx_hash, log_index, depositor,
+                       onchain_wei::TEXT AS onchain_wei, booked_wei::TEXT AS booked_wei",
+        )
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to check deposit amounts")
+    }
+    
+    async fn flag_missing(&self) -> Result<Vec<PgRow>> {
+        sqlx::query(
+            "INSERT INTO deposit_discrepancies (tx_hash, log_index, kind, booked_wei, depositor)
+             SELECT p.tx_hash, COALESCE(p.deposit_log_index, -1), 'missing_deposit', p.amount_wei, p.account
+             FROM stake_positions p
+             WHERE p.validator_index IS NOT NULL
+               AND p.staked_at < NOW() - make_interval(secs => $1)
+               AND NOT EXISTS (SELECT 1 FROM deposit_events e
+                               WHERE e.tx_hash = p.tx_hash AND e.log_index = p.deposit_log_index)
+             ON CONFLICT DO NOTHING
+             RETURNING tx_hash, log_index, depositor,
+                       onchain_wei::TEXT AS onchain_wei, booked_wei::TEXT AS booked_wei",
+        )
+        .bind(self.config.missing_after_seconds as f64)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to check for missing deposits")
+    }
+    
+    async fn raise_all(&self, discrepancy: DepositDiscrepancy, rows: Vec<PgRow>) -> Result<()> {
+        for row in rows {
+            let tx_hash: String = row.try_get("tx_hash")?;
+            let log_index: i64 = row.try_get("log_index")?;
+            let depositor: Option<String> = row.try_get("depositor")?;
+            let depositor = depositor.as_deref().unwrap_or("unknown sender");
+            let onchain: Option<String> = row.try_get("onchain_wei")?;
+            let booked: Option<String> = row.try_get("booked_wei")?;
+            
+            let message = match discrepancy {
+                DepositDiscrepancy::MissingDeposit => format!(
+                    "Stake position {} of {} wei by {} has no deposit on chain",
+                    tx_hash,
+                    booked.as_deref().unwrap_or("0"),
+                    depositor
+                ),
+                DepositDiscrepancy::UnknownDepositor => format!(
+                    "Deposit {} (log {}) of {} wei from {} to our withdrawal credentials was never booked",
+                    tx_hash,
+                    log_index,
+                    onchain.as_deref().unwrap_or("0"),
+                    depositor
+                ),
+                DepositDiscrepancy::AmountMismatch => format!(
+                    "Deposit {} (log {}) from {} put {} wei on chain but booked {} wei",
+                    tx_hash,
+                    log_index,
+                    depositor,
+                    onchain.as_deref().unwrap_or("0"),
+                    booked.as_deref().unwrap_or("0")
+                ),
+            };
+            
+            warn!("Deposit discrepancy: {}", message);
+            metrics::counter!("deposit_discrepancies_total", 1, "kind" => discrepancy.as_str());
+            
+            self.alert_manager
+                .fire(Alert::new(
+                    format!("deposits:{}:{}:{}", discrepancy.as_str(), tx_hash, log_index),
+                    discrepancy.severity(),
+                    "deposits",
+                    message,
+                ))
+                .await;
+        }
+        
+        Ok(())
+    }
+    
+    async fn store(&self, event: &DepositEvent) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO deposit_events
+                 (tx_hash, log_index, block_number, pubkey, withdrawal_credentials, amount_wei, deposit_index,
+                  depositor)
+             VALUES ($1, $2, $3, $4, $5, $6::NUMERIC, $7, $8)
+             ON CONFLICT (tx_hash, log_index) DO NOTHING",
+        )
+        .bind(format!("{:?}", event.tx_hash))
+        .bind(event.log_index as i64)
+        .bind(event.block_number as i64)
+        .bind(&event.pubkey)
+        .bind(&event.withdrawal_credentials)
+        .bind(event.amount.to_string())
+        .bind(event.deposit_index as i64)
+        .bind(event.depositor.map(|depositor| format!("{:?}", depositor)))
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to store deposit event")?;
+        
+        Ok(())
+    }
+    
+    async fn last_indexed_block(&self) -> Result<Option<u64>> {
+        let last: Option<i64> = sqlx::query_scalar("SELECT last_block FROM deposit_indexer_state")
+            .fetch_optional(&self.db_pool)
+            .await
+            .context("Failed to load deposit indexer state")?;
+        
+        Ok(last.map(|block| block as u64))
+    }
+    
+    async fn set_last_indexed_block(&self, block: u64) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO deposit_indexer_state (id, last_block, updated_at) VALUES (TRUE, $1, NOW())
+             ON CONFLICT (id) DO UPDATE SET last_block = EXCLUDED.last_block, updated_at = NOW()",
+        )
+        .bind(block as i64)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to save deposit indexer state")?;
+        
+        Ok(())
+    }
+}
+
+/// Decode a deposit contract log; amounts and indices are little-endian u64s
+fn decode_deposit(log: &Log) -> Result<DepositEvent> {
+    let tokens = abi::decode(&[ParamType::Bytes; 5], &log.data).context("Malformed DepositEvent")?;
+    let bytes: Vec<Vec<u8>> = tokens
+        .into_iter()
+        .map(|token| match token {
+            Token::Bytes(bytes) => Ok(bytes),
+            other => Err(anyhow!("Unexpected DepositEvent field {:?}", other)),
+        })
+        .collect::<Result<_>>()?;
+    
+    let amount_gwei = le_u64(&bytes[2]).ok_or_else(|| anyhow!("Invalid DepositEvent amount"))?;
+    let deposit_index = le_u64(&bytes[4]).ok_or_else(|| anyhow!("Invalid DepositEvent index"))?;
+    
+    Ok(DepositEvent {
+        tx_hash: log.transaction_hash.ok_or_else(|| anyhow!("Deposit log without transaction hash"))?,
+        log_index: log.log_index.map_or(0, |index| index.as_u64()),
+        block_number: log.block_number.map_or(0, |number| number.as_u64()),
+        depositor: None,
+        pubkey: format!("0x{}", hex::encode(&bytes[0])),
+        withdrawal_credentials: format!("0x{}", hex::encode(&bytes[1])),
+        amount: U256::from(amount_gwei) * U256::from(WEI_PER_GWEI),
+        deposit_index,
+    })
+}
+
+fn le_u64(bytes: &[u8]) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
@@ -0,0 +1,110 @@
+use parking_lot::Mutex;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::{watch, Notify};
+use tracing::info;
+
+/// Coordinates the drain sequence used for zero-downtime deploys
+#[derive(Clone)]
+pub struct DrainController {
+    /// Set once a drain has been requested
+    draining: Arc<AtomicBool>,
+    /// Notifies waiters that a drain has been requested
+    sender: Arc<watch::Sender<bool>>,
+    /// Work a drain waits for, counted by kind
+    in_flight: Arc<Mutex<BTreeMap<&'static str, usize>>>,
+    /// Notified whenever the last piece of in-flight work finishes
+    idle: Arc<Notify>,
+}
+
+/// One piece of in-flight work, done when dropped
+pub struct InFlight {
+    kind: &'static str,
+    controller: DrainController,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let mut in_flight = self.controller.in_flight.lock();
+        if let Some(count) = in_flight.get_mut(self.kind) {
+            *count -= 1;
+            if *count == 0 {
+                in_flight.remove(self.kind);
+            }
+        }
+        if in_flight.is_empty() {
+            self.controller.idle.notify_waiters();
+        }
+    }
+}
+
+impl DrainController {
+    /// Create a new drain controller
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        
+        Self {
+            draining: Arc::new(AtomicBool::new(false)),
+            sender: Arc::new(sender),
+            in_flight: Arc::new(Mutex::new(BTreeMap::new())),
+            idle: Arc::new(Notify::new()),
+        }
+    }
+    
+    /// Whether the process is draining and should refuse new work
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+    
+    /// Request a drain, returning false if one was already in progress
+    pub fn request(&self) -> bool {
+        if self.draining.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        
+        info!("Drain requested, no longer accepting new work");
+        let _ = self.sender.send(true);
+        true
+    }
+    
+    /// Wait until a drain has been requested
+    pub async fn requested(&self) {
+        let mut receiver = self.sender.subscribe();
+        while !*receiver.borrow() {
+            if receiver.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+    
+    /// Count work of a kind as in flight until the returned guard drops
+    pub fn track(&self, kind: &'static str) -> InFlight {
+        *self.in_flight.lock().entry(kind).or_insert(0) += 1;
+        InFlight {
+            kind,
+            controller: self.clone(),
+        }
+    }
+    
+    /// In-flight work by kind
+    pub fn in_flight(&self) -> Vec<(&'static str, usize)> {
+        self.in_flight.lock().iter().map(|(kind, count)| (*kind, *count)).collect()
+    }
+    
+    /// Wait until no tracked work is in flight
+    pub async fn wait_idle(&self) {
+        loop {
+            // Created before checking, so a finish in between still wakes us
+            let idle = self.idle.notified();
+            if self.in_flight.lock().is_empty() {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+use tokio::sync::broadcast;
+use tracing::debug;
+
+/// Capacity of the event broadcast channel; slow subscribers past this lag and skip events
+const EVENT_CHANNEL_CAPACITY: usize = 4096;
+
+/// A channel clients may subscribe to over WebSocket or SSE
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    /// Profitable transactions marked for inclusion, the global opportunity feed
+    Opportunities,
+    /// New blocks seen by the monitor
+    Blocks,
+    /// Status updates for submitted bundles, delivered only to their owner
+    Bundles,
+    /// Gas price report refreshed on every new head
+    GasPrice,
+}
+
+impl Topic {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Opportunities => "opportunities",
+            Self::Blocks => "blocks",
+            Self::Bundles => "bundles",
+            Self::GasPrice => "gas_price",
+        }
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Topic {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "opportunities" => Ok(Self::Opportunities),
+            "blocks" => Ok(Self::Blocks),
+            "bundles" => Ok(Self::Bundles),
+            "gas_price" => Ok(Self::GasPrice),
+            other => Err(anyhow!("Unknown topic: {}", other)),
+        }
+    }
+}
+
+/// An event published to a topic
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamEvent {
+    pub topic: Topic,
+    /// API key name of the searcher the event belongs to, if any
+    pub owner: Option<String>,
+    pub payload: serde_json::Value,
+}
+
+/// In-process fan-out of stream events to API subscribers
+#[derive(Clone)]
+pub struct EventBus {
+    /// Broadcast sender shared by all publishers
+    sender: broadcast::Sender<StreamEvent>,
+}
+
+impl EventBus {
+    /// Create a new event bus
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self { sender }
+    }
+    
+    /// Publish an event; dropped silently when nobody is subscribed
+    pub fn publish<T: Serialize>(&self, topic: Topic, owner: Option<String>, payload: &T) {
+        let payload = match serde_json::to_value(payload) {
+            Ok(payload) => payload,
+            Err(e) => {
+                debug!("Failed to serialize {} event: {}", topic, e);
+                return;
+            }
+        };
+        
+        let _ = self.sender.send(StreamEvent { topic, owner, payload });
+    }
+    
+    /// Events the slowest subscriber has yet to receive, and the number of subscribers
+    pub fn backlog(&self) -> (usize, usize) {
+        (self.sender.len(), self.sender.receiver_count())
+    }
+    
+    /// Subscribe to every event; callers filter by topic and owner
+    pub fn subscribe(&self) -> broadcast::Receiver<StreamEvent> {
+        self.sender.subscribe()
+    }
+}
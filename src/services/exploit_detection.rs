@@ -0,0 +1,347 @@
+use anyhow::{anyhow, Result};
+use dashmap::DashMap;
+use ethers::types::{Address, BlockNumber, Transaction, H256, U256};
+use serde::Deserialize;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::sync::Semaphore;
+use tracing::{debug, warn};
+
+use crate::{
+    blockchain::{
+        decoder::DecodedCall,
+        simulator::{ForkBlock, ForkSimulator, SimulatedLog, TokenDelta, TRANSFER_TOPIC},
+        BlockchainClient,
+    },
+    config::{CacheSettings, ExploitDetectionConfig},
+    services::{
+        alerting::{Alert, AlertManager, Severity},
+        head_tracker::HeadTracker,
+        labels::{LabelCategory, LabelRegistry},
+        prices::PriceService,
+    },
+    utils::cache::BoundedCache,
+};
+
+/// Labels whose outflows are watched, contracts holding other people's funds
+const GUARDED_LABELS: &[LabelCategory] = &[LabelCategory::Protocol, LabelCategory::Bridge];
+
+#[derive(Deserialize)]
+struct SourceCodeResponse {
+    result: Vec<SourceCodeEntry>,
+}
+
+#[derive(Deserialize)]
+struct SourceCodeEntry {
+    #[serde(rename = "SourceCode")]
+    source_code: String,
+}
+
+/// Security heuristics over the public mempool
+///
+/// Operators often see a live exploit in their own mempool before anyone reports it. Every
+/// public pending transaction is screened from its calldata: huge approvals to contracts
+/// deployed within `fresh_contract_blocks`, the signature of a drainer, are raised directly.
+/// Deployments, calls into fresh contracts and anything sent by a labeled exploiter are then
+/// simulated on the latest state, raising priced token outflows from `protocol` and `bridge`
+/// labeled addresses above `min_outflow_usd`, and selfdestructs of labeled or verified
+/// contracts. Alerts go through the alert manager, whose cooldown folds repeats per address.
+#[derive(Clone)]
+pub struct ExploitDetector {
+    /// Blockchain client, source of contract code and fork headers
+    blockchain_client: Arc<BlockchainClient>,
+    /// Executes suspicious transactions on the latest state
+    simulator: ForkSimulator,
+    /// Latest head, the reference for contract age
+    head_tracker: HeadTracker,
+    /// Known entities, guarding protocol funds and flagging exploiters
+    labels: LabelRegistry,
+    /// Token prices, valuing outflows
+    prices: PriceService,
+    /// Alert manager security alerts are raised through
+    alert_manager: AlertManager,
+    /// HTTP client for the source verification API
+    http: reqwest::Client,
+    /// Whether a contract is fresh, with the head it was checked at
+    contract_age: Arc<BoundedCache<Address, (bool, u64)>>,
+    /// Whether selfdestructed contracts had verified source
+    verified: Arc<DashMap<Address, bool>>,
+    /// Bounds concurrent simulations of suspicious transactions
+    in_flight: Arc<Semaphore>,
+    min_approval: U256,
+    /// Configuration
+    config: ExploitDetectionConfig,
+}
+
+impl ExploitDetector {
+    /// Create a new exploit detector
+    pub fn new(
+        config: ExploitDetectionConfig,
+        cache: &CacheSettings,
+        blockchain_client: Arc<BlockchainClient>,
+        head_tracker: HeadTracker,
+        labels: LabelRegistry,
+        prices: PriceService,
+        alert_manager: AlertManager,
+    ) -> Result<Self> {
+        let min_approval = U256::from_dec_str(&config.min_approval_units)
+            .map_err(|e| anyhow!("Invalid exploit detection min_approval_units: {}", e))?;
+        let http = reqwest::Client::builder().timeout(Duration::from_secs(10)).build()?;
+        
+        Ok(Self {
+            simulator: ForkSimulator::new(blockchain_client.http_provider().clone(), blockchain_client.chain_id()),
+            blockchain_client,
+            head_tracker,
+            labels,
+            prices,
+            alert_manager,
+            http,
+            contract_age: Arc::new(BoundedCache::new("contract_age", cache)),
+            verified: Arc::new(DashMap::new()),
+            in_flight: Arc::new(Semaphore::new(config.max_in_flight)),
+            min_approval,
+            config,
+        })
+    }
+    
+    /// Whether pending transactions are screened
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Screen one public pending transaction, raising an alert for anything suspicious
+    pub async fn inspect(&self, tx: &Transaction) {
+        if let Err(e) = self.screen(tx).await {
+            debug!("Failed to screen {:?} for exploits: {}", tx.hash, e);
+        }
+    }
+    
+    async fn screen(&self, tx: &Transaction) -> Result<()> {
+        let head = match self.head_tracker.head_number() {
+            Some(head) => head,
+            None => return Ok(()),
+        };
+        let labels = self.labels.tag(tx);
+        let from_exploiter = labels
+            .from
+            .as_ref()
+            .filter(|label| label.category == LabelCategory::Exploiter);
+        
+        if let Some(label) = from_exploiter {
+            self.raise(
+                "exploiter",
+                format!("exploit:exploiter:{:?}", tx.from),
+                Severity::Warning,
+                format!("Known exploiter {} ({:?}) sent pending transaction {:?}", label.label, tx.from, tx.hash),
+            )
+            .await;
+        }
+        
+        if let Some((spender, amount)) = approval(tx) {
+            if amount >= self.min_approval && self.is_fresh(spender, head).await {
+                self.raise(
+                    "fresh_approval",
+                    format!("exploit:approval:{:?}", spender),
+                    Severity::Warning,
+                    format!(
+                        "{:?} approved {} units of {:?} to {:?}, deployed within the last {} blocks ({:?})",
+                        tx.from,
+                        amount,
+                        tx.to.unwrap_or_default(),
+                        spender,
+                        self.config.fresh_contract_blocks,
+                        tx.hash
+                    ),
+                )
+                .await;
+            }
+            return Ok(());
+        }
+        
+        let suspicious = match tx.to {
+            None => true,
+            Some(to) => from_exploiter.is_some() || (!tx.input.is_empty() && self.is_fresh(to, head).await),
+        };
+        if suspicious {
+            self.simulate(tx, head).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Run a suspicious transaction and raise what it would do to guarded funds
+    async fn simulate(&self, tx: &Transaction, head: u64) -> Result<()> {
+        let _permit = match self.in_flight.try_acquire() {
+            Ok(permit) => permit,
+            Err(_) => {
+                metrics::counter!("exploit_screen_skipped_total", 1);
+                return Ok(());
+            }
+        };
+        
+        let header = self
+            .blockchain_client
+            .get_block_with_hashes(head)
+            .await?
+            .ok_or_else(|| anyhow!("Block {} not found", head))?;
+        let block = ForkBlock {
+            number: head,
+            timestamp: header.timestamp,
+            base_fee: header.base_fee_per_gas.unwrap_or_default(),
+            gas_limit: header.gas_limit,
+            coinbase: header.author.unwrap_or_default(),
+        };
+        let trace = self.simulator.execute(tx, block).await?;
+        metrics::counter!("exploit_simulations_total", 1);
+        if !trace.success {
+            return Ok(());
+        }
+        
+        for ((holder, token), amount) in self.guarded_outflows(&trace.logs) {
+            // Only configured tokens are priced, others can't be measured against the threshold
+            let delta = TokenDelta {
+                token,
+                delta: amount.to_string(),
+            };
+            let value_usd = match self.prices.value_deltas_usd(&[delta]).await {
+                Some(value_usd) if value_usd >= self.config.min_outflow_usd => value_usd,
+                _ => continue,
+            };
+            let label = self.labels.lookup(&holder).map(|label| label.label).unwrap_or_default();
+            self.raise(
+                "protocol_outflow",
+                format!("exploit:outflow:{:?}", holder),
+                Severity::Critical,
+                format!(
+                    "Pending transaction {:?} from {:?} moves ${:.0} of {:?} out of {} ({:?})",
+                    tx.hash, tx.from, value_usd, token, label, holder
+                ),
+            )
+            .await;
+        }
+        
+        for address in &trace.selfdestructed {
+            if self.is_verified(*address).await {
+                self.raise(
+                    "selfdestruct",
+                    format!("exploit:selfdestruct:{:?}", address),
+                    Severity::Critical,
+                    format!(
+                        "Pending transaction {:?} from {:?} selfdestructs verified contract {:?}",
+                        tx.hash, tx.from, address
+                    ),
+                )
+                .await;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Token amounts transferred out of `protocol` and `bridge` labeled addresses, by holder and token
+    fn guarded_outflows(&self, logs: &[SimulatedLog]) -> HashMap<(Address, Address), U256> {
+        let transfer_topic = H256::from(TRANSFER_TOPIC);
+        let mut outflows: HashMap<(Address, Address), U256> = HashMap::new();
+        
+        for log in logs {
+            if log.topics.len() != 3 || log.topics[0] != transfer_topic || log.data.len() < 32 {
+                continue;
+            }
+            let from = Address::from(log.topics[1]);
+            let guarded = self
+                .labels
+                .lookup(&from)
+                .map_or(false, |label| GUARDED_LABELS.contains(&label.category));
+            if guarded {
+                let amount = U256::from_big_endian(&log.data[..32]);
+                let outflow = outflows.entry((from, log.address)).or_default();
+                *outflow = outflow.saturating_add(amount);
+            }
+        }
+        
+        outflows
+    }
+    
+    /// Whether an address holds code that was not there `fresh_contract_blocks` ago
+    async fn is_fresh(&self, address: Address, head: u64) -> bool {
+        if let Some((fresh, checked_at)) = self.contract_age.get(&address) {
+            if !fresh || head <= checked_at + self.config.fresh_contract_blocks {
+                return fresh;
+            }
+        }
+        
+        let fresh = async {
+            if self.blockchain_client.get_code(address, BlockNumber::Latest).await?.is_empty() {
+                return Ok::<_, anyhow::Error>(false);
+            }
+            let before = head.saturating_sub(self.config.fresh_contract_blocks);
+            Ok(self.blockchain_client.get_code(address, BlockNumber::Number(before.into())).await?.is_empty())
+        }
+        .await
+        .unwrap_or_else(|e| {
+            debug!("Failed to read the age of {:?}: {}", address, e);
+            false
+        });
+        
+        self.contract_age.insert(address, (fresh, head));
+        fresh
+    }
+    
+    /// Whether a contract is a known entity or has verified source
+    async fn is_verified(&self, address: Address) -> bool {
+        if let Some(label) = self.labels.lookup(&address) {
+            return label.category != LabelCategory::Exploiter;
+        }
+        if let Some(verified) = self.verified.get(&address) {
+            return *verified;
+        }
+        let url = match &self.config.etherscan_api_url {
+            Some(url) => url,
+            None => return false,
+        };
+        
+        let response = self
+            .http
+            .get(url)
+            .query(&[
+                ("module", "contract"),
+                ("action", "getsourcecode"),
+                ("address", &format!("{:?}", address)),
+                ("apikey", self.config.etherscan_api_key.as_deref().unwrap_or_default()),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        let verified = match response {
+            Ok(response) => match response.json::<SourceCodeResponse>().await {
+                Ok(body) => body.result.iter().any(|entry| !entry.source_code.is_empty()),
+                Err(e) => {
+                    warn!("Unexpected source verification response for {:?}: {}", address, e);
+                    return false;
+                }
+            },
+            Err(e) => {
+                warn!("Failed to look up source verification of {:?}: {}", address, e);
+                return false;
+            }
+        };
+        
+        self.verified.insert(address, verified);
+        verified
+    }
+    
+    async fn raise(&self, heuristic: &'static str, key: String, severity: Severity, message: String) {
+        metrics::counter!("exploit_alerts_total", 1, "heuristic" => heuristic);
+        self.alert_manager
+            .fire(Alert::new(key, severity, "exploit_detection", message))
+            .await;
+    }
+}
+
+/// Spender and amount of an allowance grant, unlimited for operator approvals
+fn approval(tx: &Transaction) -> Option<(Address, U256)> {
+    match DecodedCall::decode(&tx.input, tx.value)? {
+        DecodedCall::Approve { spender, amount, .. } => Some((spender, amount)),
+        DecodedCall::ApprovalForAll { operator, approved: true } => Some((operator, U256::MAX)),
+        _ => None,
+    }
+}
@@ -0,0 +1,399 @@
+use anyhow::{anyhow, Context, Result};
+use arrow::{
+    array::{ArrayRef, Int64Array, StringArray, UInt32Array, UInt64Array},
+    datatypes::{DataType, Field, Schema},
+    record_batch::RecordBatch,
+};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use ethers::types::{Bytes as TxBytes, H256, U256};
+use futures::stream::{self, Stream};
+use parquet::arrow::ArrowWriter;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{postgres::PgRow, Row};
+use parking_lot::Mutex;
+use std::{
+    io::{self, Write},
+    str::FromStr,
+    sync::Arc,
+};
+use tracing::debug;
+
+use crate::{api::models, database::{DbHealth, DbPool}, services::transaction::TxSource};
+
+/// Number of blocks fetched per page while exporting
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// Longest range the API exports in one request
+pub const MAX_EXPORT_RANGE_DAYS: i64 = 31;
+
+/// Supported export formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Newline-delimited Flashbots `eth_sendBundle`-style JSON
+    Json,
+    /// Apache Parquet, one row per bundle
+    Parquet,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "parquet" => Ok(Self::Parquet),
+            other => Err(anyhow!("Unknown export format: {}", other)),
+        }
+    }
+}
+
+/// A bundle we contributed to a landed block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportBundle {
+    /// Transaction hashes in bundle order
+    pub tx_hashes: Vec<H256>,
+    /// Signed raw transactions in bundle order
+    pub raw_txs: Vec<TxBytes>,
+    /// Value the bundle paid the fee recipient in wei, in priority fees and transfers
+    #[serde(with = "models::wei")]
+    pub value: U256,
+    /// Where the bundle's order flow came from
+    #[serde(default)]
+    pub source: TxSource,
+    /// Strategy that sent the bundle, `None` for order flow we included
+    #[serde(default)]
+    pub strategy: Option<String>,
+}
+
+/// A landed block containing our bundles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuiltBlockRecord {
+    pub block_number: u64,
+    pub block_hash: H256,
+    #[serde(with = "models::wei")]
+    pub value: U256,
+    pub bundles: Vec<ExportBundle>,
+    pub built_at: DateTime<Utc>,
+}
+
+/// Time range selection for exports
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ExportRange {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+/// Service for recording built blocks and exporting them
+#[derive(Clone)]
+pub struct ExportService {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+}
+
+impl ExportService {
+    /// Create a new export service
+    pub fn new(db_pool: DbPool, health: DbHealth) -> Result<Self> {
+        Ok(Self { db_pool, health })
+    }
+    
+    /// Record a landed block and the bundles we contributed to it
+    pub async fn record_built_block(
+        &self,
+        block_number: u64,
+        block_hash: H256,
+        bundles: Vec<ExportBundle>,
+    ) -> Result<()> {
+        self.health.retry(|| self.record_built_block_once(block_number, block_hash, &bundles)).await
+    }
+    
+    /// Fetch a page of built blocks in the range, after the given block number
+    ///
+    /// When `owner` is set only blocks containing that searcher's bundles are returned,
+    /// and only their bundles are included.
+    pub async fn fetch_page(
+        &self,
+        range: ExportRange,
+        after_block: Option<u64>,
+        owner: Option<&str>,
+    ) -> Result<Vec<BuiltBlockRecord>> {
+        self.health.retry(|| self.fetch_page_once(range, after_block, owner)).await
+    }
+    
+    /// Landed blocks we built with a bundle containing the transaction, keeping only those bundles
+    pub async fn blocks_containing(&self, tx_hash: H256) -> Result<Vec<BuiltBlockRecord>> {
+        self.health.retry(|| self.blocks_containing_once(tx_hash)).await
+    }
+    
+    /// Stream built blocks in the range as newline-delimited Flashbots-style bundle JSON
+    pub fn stream_json(
+        &self,
+        range: ExportRange,
+        owner: Option<String>,
+    ) -> impl Stream<Item = Result<Bytes>> + Send + 'static {
+        let service = self.clone();
+        
+        stream::try_unfold(Some(None), move |cursor: Option<Option<u64>>| {
+            let service = service.clone();
+            let owner = owner.clone();
+            async move {
+                let after_block = match cursor {
+                    Some(after_block) => after_block,
+                    None => return Ok(None),
+                };
+                
+                let page = service.fetch_page(range, after_block, owner.as_deref()).await?;
+                let next = if (page.len() as i64) < EXPORT_PAGE_SIZE {
+                    None
+                } else {
+                    Some(page.last().map(|b| b.block_number))
+                };
+                
+                let mut chunk = Vec::new();
+                for block in &page {
+                    for bundle in &block.bundles {
+                        let line = json!({
+                            "blockNumber": format!("0x{:x}", block.block_number),
+                            "blockHash": block.block_hash,
+                            "txs": bundle.raw_txs,
+                            "value": bundle.value.to_string(),
+                            "source": bundle.source.to_string(),
+                        });
+                        serde_json::to_writer(&mut chunk, &line)?;
+                        chunk.push(b'\n');
+                    }
+                }
+                
+                Ok(Some((Bytes::from(chunk), next)))
+            }
+        })
+    }
+    
+    /// Stream built blocks in the range as a Parquet file, one row per bundle
+    ///
+    /// Each page of blocks is written as its own row group and sent as soon as it is encoded,
+    /// so only one page is held in memory, whatever the range.
+    pub fn stream_parquet(
+        &self,
+        range: ExportRange,
+        owner: Option<String>,
+    ) -> Result<impl Stream<Item = Result<Bytes>> + Send + 'static> {
+        let schema = parquet_schema();
+        let buffer = SharedBuffer::default();
+        let writer = ArrowWriter::try_new(buffer.clone(), schema.clone(), None)?;
+        let service = self.clone();
+        
+        Ok(stream::try_unfold(Some((writer, None)), move |state: Option<(ArrowWriter<SharedBuffer>, Option<u64>)>| {
+            let service = service.clone();
+            let owner = owner.clone();
+            let schema = schema.clone();
+            let buffer = buffer.clone();
+            async move {
+                let (mut writer, after_block) = match state {
+                    Some(state) => state,
+                    None => return Ok(None),
+                };
+                
+                let page = service.fetch_page(range, after_block, owner.as_deref()).await?;
+                if !page.is_empty() {
+                    writer.write(&parquet_batch(&schema, &page)?)?;
+                    writer.flush()?;
+                }
+                
+                if (page.len() as i64) < EXPORT_PAGE_SIZE {
+                    writer.close()?;
+                    return Ok(Some((buffer.take(), None)));
+                }
+                let next = page.last().map(|b| b.block_number);
+                Ok(Some((buffer.take(), Some((writer, next)))))
+            }
+        }))
+    }
+    
+    async fn record_built_block_once(
+        &self,
+        block_number: u64,
+        block_hash: H256,
+        bundles: &[ExportBundle],
+    ) -> Result<()> {
+        let value = total_value(bundles);
+        
+        let mut owners: Vec<String> = bundles.iter().filter_map(|b| b.source.owner()).collect();
+        owners.sort();
+        owners.dedup();
+        
+        sqlx::query(
+            "INSERT INTO built_blocks (block_number, block_hash, value_wei, bundles, owners, built_at)
+             VALUES ($1, $2, $3, $4, $5, NOW())
+             ON CONFLICT (block_number) DO UPDATE
+             SET block_hash = EXCLUDED.block_hash, value_wei = EXCLUDED.value_wei,
+                 bundles = EXCLUDED.bundles, owners = EXCLUDED.owners",
+        )
+        .bind(block_number as i64)
+        .bind(format!("{:?}", block_hash))
+        .bind(value.to_string())
+        .bind(sqlx::types::Json(bundles))
+        .bind(owners)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to record built block")?;
+        
+        debug!("Recorded built block {} with {} bundles", block_number, bundles.len());
+        
+        Ok(())
+    }
+    
+    async fn fetch_page_once(
+        &self,
+        range: ExportRange,
+        after_block: Option<u64>,
+        owner: Option<&str>,
+    ) -> Result<Vec<BuiltBlockRecord>> {
+        let rows = sqlx::query(
+            "SELECT block_number, block_hash, value_wei, bundles, built_at
+             FROM built_blocks
+             WHERE built_at >= $1 AND built_at < $2 AND block_number > $3
+               AND ($5::TEXT IS NULL OR $5 = ANY(owners))
+             ORDER BY block_number
+             LIMIT $4",
+        )
+        .bind(range.from)
+        .bind(range.to)
+        .bind(after_block.map(|b| b as i64).unwrap_or(-1))
+        .bind(EXPORT_PAGE_SIZE)
+        .bind(owner)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to fetch built blocks")?;
+        
+        rows.iter()
+            .map(|row| {
+                let mut record = record_from_row(row)?;
+                if let Some(owner) = owner {
+                    record.bundles.retain(|b| b.source.owner().as_deref() == Some(owner));
+                    record.value = total_value(&record.bundles);
+                }
+                
+                Ok(record)
+            })
+            .collect()
+    }
+    
+    async fn blocks_containing_once(&self, tx_hash: H256) -> Result<Vec<BuiltBlockRecord>> {
+        let rows = sqlx::query(
+            "SELECT block_number, block_hash, value_wei, bundles, built_at
+             FROM built_blocks
+             WHERE bundles @> $1
+             ORDER BY block_number",
+        )
+        .bind(sqlx::types::Json(json!([{ "tx_hashes": [tx_hash] }])))
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to look up built blocks by transaction")?;
+        
+        rows.iter()
+            .map(|row| {
+                let mut record = record_from_row(row)?;
+                record.bundles.retain(|b| b.tx_hashes.contains(&tx_hash));
+                Ok(record)
+            })
+            .collect()
+    }
+}
+
+fn record_from_row(row: &PgRow) -> Result<BuiltBlockRecord> {
+    Ok(BuiltBlockRecord {
+        block_number: row.try_get::<i64, _>("block_number")? as u64,
+        block_hash: H256::from_str(row.try_get("block_hash")?)?,
+        value: U256::from_dec_str(row.try_get("value_wei")?)?,
+        bundles: row.try_get::<sqlx::types::Json<Vec<ExportBundle>>, _>("bundles")?.0,
+        built_at: row.try_get("built_at")?,
+    })
+}
+
+/// Columns of the Parquet export
+fn parquet_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("block_number", DataType::UInt64, false),
+        Field::new("block_hash", DataType::Utf8, false),
+        Field::new("built_at_ms", DataType::Int64, false),
+        Field::new("bundle_index", DataType::UInt32, false),
+        Field::new("tx_hashes", DataType::Utf8, false),
+        Field::new("value_wei", DataType::Utf8, false),
+        Field::new("source", DataType::Utf8, false),
+    ]))
+}
+
+/// One page of built blocks as a Parquet row group, one row per bundle
+fn parquet_batch(schema: &Arc<Schema>, page: &[BuiltBlockRecord]) -> Result<RecordBatch> {
+    let mut block_numbers = Vec::new();
+    let mut block_hashes = Vec::new();
+    let mut built_at = Vec::new();
+    let mut bundle_indices = Vec::new();
+    let mut tx_hashes = Vec::new();
+    let mut values = Vec::new();
+    let mut sources = Vec::new();
+    
+    for block in page {
+        for (index, bundle) in block.bundles.iter().enumerate() {
+            block_numbers.push(block.block_number);
+            block_hashes.push(format!("{:?}", block.block_hash));
+            built_at.push(block.built_at.timestamp_millis());
+            bundle_indices.push(index as u32);
+            tx_hashes.push(
+                bundle
+                    .tx_hashes
+                    .iter()
+                    .map(|h| format!("{:?}", h))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+            values.push(bundle.value.to_string());
+            sources.push(bundle.source.to_string());
+        }
+    }
+    
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from(block_numbers)),
+        Arc::new(StringArray::from(block_hashes)),
+        Arc::new(Int64Array::from(built_at)),
+        Arc::new(UInt32Array::from(bundle_indices)),
+        Arc::new(StringArray::from(tx_hashes)),
+        Arc::new(StringArray::from(values)),
+        Arc::new(StringArray::from(sources)),
+    ];
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+/// Parquet output taken out as each row group is written
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    /// Bytes written since the last take
+    fn take(&self) -> Bytes {
+        Bytes::from(std::mem::take(&mut *self.0.lock()))
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Total value extracted by a set of bundles
+fn total_value(bundles: &[ExportBundle]) -> U256 {
+    bundles
+        .iter()
+        .fold(U256::zero(), |acc, b| acc.saturating_add(b.value))
+}
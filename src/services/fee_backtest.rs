@@ -0,0 +1,205 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::types::{BlockNumber, U256};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::blockchain::BlockchainClient;
+
+/// Most blocks nodes return from a single fee history call
+pub const MAX_BACKTEST_BLOCKS: u64 = 1024;
+
+/// Reward percentile a tip must reach to count as included in a block
+///
+/// Fee history gives no per-block minimum tip, so a tip at or above the block's
+/// 10th-percentile reward is taken to have outbid the marginal transaction.
+const INCLUSION_PERCENTILE: f64 = 10.0;
+
+/// A hypothetical fee policy, e.g. "always the p60 priority fee of the last block"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeePolicy {
+    /// Priority fee percentile of recent blocks to pay, 0-100
+    pub priority_fee_percentile: f64,
+    /// Recent blocks the percentile is averaged over
+    #[serde(default = "default_lookback_blocks")]
+    pub lookback_blocks: usize,
+    /// Max fee as a multiple of the next block's base fee, on top of the tip
+    #[serde(default = "default_base_fee_multiplier")]
+    pub base_fee_multiplier: f64,
+}
+
+fn default_lookback_blocks() -> usize {
+    1
+}
+
+fn default_base_fee_multiplier() -> f64 {
+    2.0
+}
+
+/// Backtest parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeBacktestRequest {
+    pub policy: FeePolicy,
+    /// Recent blocks to replay, at most `MAX_BACKTEST_BLOCKS`
+    #[serde(default = "default_backtest_blocks")]
+    pub blocks: u64,
+    /// Blocks a transaction may wait before it counts as not included
+    #[serde(default = "default_max_wait_blocks")]
+    pub max_wait_blocks: usize,
+    /// Gas used per transaction when reporting cost
+    #[serde(default = "default_gas_used")]
+    pub gas_used: u64,
+}
+
+fn default_backtest_blocks() -> u64 {
+    256
+}
+
+fn default_max_wait_blocks() -> usize {
+    3
+}
+
+fn default_gas_used() -> u64 {
+    21_000
+}
+
+/// What a fee policy would have achieved over the replayed blocks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeBacktestReport {
+    pub from_block: u64,
+    pub to_block: u64,
+    /// Simulated submissions, one per replayed block
+    pub submissions: usize,
+    pub included: usize,
+    pub inclusion_rate: f64,
+    /// Mean blocks from submission to inclusion, counting the first block as 1
+    pub mean_blocks_to_inclusion: Option<f64>,
+    /// Mean effective gas price paid by included transactions
+    pub mean_effective_gas_price_wei: Option<String>,
+    /// Mean priority fee paid by included transactions
+    pub mean_priority_fee_wei: Option<String>,
+    /// Mean cost of an included transaction using `gas_used`
+    pub mean_cost_wei: Option<String>,
+}
+
+/// Replays recent fee history against hypothetical fee policies
+#[derive(Clone)]
+pub struct FeeBacktestService {
+    /// Blockchain client, source of fee history
+    blockchain_client: Arc<BlockchainClient>,
+}
+
+impl FeeBacktestService {
+    /// Create a new fee backtest service
+    pub fn new(blockchain_client: Arc<BlockchainClient>) -> Result<Self> {
+        Ok(Self { blockchain_client })
+    }
+    
+    /// Check a request before spending RPC calls on it
+    pub fn validate(request: &FeeBacktestRequest) -> Result<()> {
+        let policy = &request.policy;
+        if !(0.0..=100.0).contains(&policy.priority_fee_percentile) {
+            return Err(anyhow!("priority_fee_percentile must be between 0 and 100"));
+        }
+        if policy.lookback_blocks == 0 {
+            return Err(anyhow!("lookback_blocks must be at least 1"));
+        }
+        if policy.base_fee_multiplier < 1.0 {
+            return Err(anyhow!("base_fee_multiplier must be at least 1"));
+        }
+        if request.blocks == 0 || request.blocks > MAX_BACKTEST_BLOCKS {
+            return Err(anyhow!("blocks must be between 1 and {}", MAX_BACKTEST_BLOCKS));
+        }
+        if request.max_wait_blocks == 0 {
+            return Err(anyhow!("max_wait_blocks must be at least 1"));
+        }
+        if policy.lookback_blocks as u64 >= request.blocks {
+            return Err(anyhow!("lookback_blocks must be smaller than blocks"));
+        }
+        
+        Ok(())
+    }
+    
+    /// Replay the most recent blocks, submitting one transaction per block under the policy
+    pub async fn backtest(&self, request: &FeeBacktestRequest) -> Result<FeeBacktestReport> {
+        Self::validate(request)?;
+        let policy = &request.policy;
+        
+        // Percentiles must be requested in ascending order
+        let mut percentiles = vec![policy.priority_fee_percentile, INCLUSION_PERCENTILE];
+        percentiles.sort_by(|a, b| a.total_cmp(b));
+        percentiles.dedup();
+        let policy_index = percentiles
+            .iter()
+            .position(|p| *p == policy.priority_fee_percentile)
+            .unwrap_or_default();
+        let inclusion_index = percentiles
+            .iter()
+            .position(|p| *p == INCLUSION_PERCENTILE)
+            .unwrap_or_default();
+        
+        let history = self
+            .blockchain_client
+            .get_fee_history(request.blocks, BlockNumber::Latest, &percentiles)
+            .await
+            .context("Failed to fetch fee history")?;
+        
+        let blocks = history.reward.len();
+        if blocks <= policy.lookback_blocks || history.base_fee_per_gas.len() < blocks {
+            return Err(anyhow!("Not enough fee history to backtest"));
+        }
+        let reward = |block: usize, index: usize| history.reward[block].get(index).copied().unwrap_or_default();
+        
+        let mut included = 0usize;
+        let mut total_wait = 0usize;
+        let mut total_price = U256::zero();
+        let mut total_tip = U256::zero();
+        let submissions = blocks - policy.lookback_blocks;
+        
+        for submit in policy.lookback_blocks..blocks {
+            // The tip is set from blocks already seen; the submit block's base fee is known from its parent
+            let window = submit - policy.lookback_blocks..submit;
+            let tip = window
+                .clone()
+                .fold(U256::zero(), |acc, block| acc + reward(block, policy_index))
+                / U256::from(window.len());
+            let max_fee = scale(history.base_fee_per_gas[submit], policy.base_fee_multiplier) + tip;
+            
+            let last = submit.saturating_add(request.max_wait_blocks).min(blocks);
+            for block in submit..last {
+                let base_fee = history.base_fee_per_gas[block];
+                if max_fee < base_fee {
+                    continue;
+                }
+                
+                let effective_tip = tip.min(max_fee - base_fee);
+                if effective_tip >= reward(block, inclusion_index) {
+                    included += 1;
+                    total_wait += block - submit + 1;
+                    total_price += base_fee + effective_tip;
+                    total_tip += effective_tip;
+                    break;
+                }
+            }
+        }
+        
+        let oldest = history.oldest_block.as_u64();
+        let mean = |total: U256| (included > 0).then(|| total / U256::from(included));
+        
+        Ok(FeeBacktestReport {
+            from_block: oldest,
+            to_block: oldest + blocks as u64 - 1,
+            submissions,
+            included,
+            inclusion_rate: included as f64 / submissions as f64,
+            mean_blocks_to_inclusion: (included > 0).then(|| total_wait as f64 / included as f64),
+            mean_effective_gas_price_wei: mean(total_price).map(|p| p.to_string()),
+            mean_priority_fee_wei: mean(total_tip).map(|t| t.to_string()),
+            mean_cost_wei: mean(total_price).map(|p| (p * U256::from(request.gas_used)).to_string()),
+        })
+    }
+}
+
+/// Multiply a wei amount by a factor, in basis points to stay in integers
+fn scale(value: U256, factor: f64) -> U256 {
+    value * U256::from((factor * 10_000.0).round() as u64) / U256::from(10_000u64)
+}
@@ -0,0 +1,575 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use ethers::{
+    abi::{self, ParamType, Token},
+    types::{
+        transaction::eip2718::TypedTransaction, AccessList, Address, BlockNumber, Bytes, Eip1559TransactionRequest,
+        NameOrAddress, Transaction, H256, U256,
+    },
+    utils::id,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::{collections::HashSet, sync::Arc};
+use tracing::{debug, info};
+
+use crate::{
+    api::models,
+    blockchain::BlockchainClient,
+    config::GasGolfConfig,
+    database::{DbHealth, DbPool},
+    services::simulation::SimulationService,
+};
+
+const APPROVE: &str = "approve(address,uint256)";
+const ALLOWANCE: &str = "allowance(address,address)";
+const MULTICALL: &str = "multicall(bytes[])";
+
+/// Intrinsic gas of a transaction, saved for every call packed into another
+const TX_BASE_GAS: u64 = 21_000;
+
+/// Calldata gas under EIP-2028
+pub fn calldata_gas(data: &[u8]) -> u64 {
+    data.iter().map(|byte| if *byte == 0 { 4 } else { 16 }).sum()
+}
+
+fn data_of(tx: &Eip1559TransactionRequest) -> &[u8] {
+    tx.data.as_ref().map_or(&[], |data| data.as_ref())
+}
+
+fn target_of(tx: &Eip1559TransactionRequest) -> Option<Address> {
+    match tx.to {
+        Some(NameOrAddress::Address(to)) => Some(to),
+        _ => None,
+    }
+}
+
+/// One way of carrying out an action, as the transactions to send in order
+#[derive(Debug, Clone)]
+pub struct Route {
+    /// Name savings are recorded under, e.g. `aave_v3`
+    pub name: String,
+    pub txs: Vec<Eip1559TransactionRequest>,
+}
+
+/// What the optimizer did to one transaction and the gas it expects that to save
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasSavings {
+    pub route: String,
+    /// Equivalent routes priced against each other
+    pub routes_considered: usize,
+    /// Gas of the transaction as built, with the calls packed into it and approvals dropped before it
+    pub baseline_gas: u64,
+    pub optimized_gas: u64,
+    /// Gas the chosen route saves over the first route offered, booked on its first transaction
+    pub route_gas_saved: u64,
+    pub baseline_calldata_gas: u64,
+    pub optimized_calldata_gas: u64,
+    /// Calls packed into this transaction, 1 if none
+    pub packed_calls: usize,
+    /// Approvals dropped because the existing allowance already covered them
+    pub approvals_skipped: usize,
+    /// Whether the node's access list was attached, its simulated saving taken off `optimized_gas`
+    pub access_list: bool,
+}
+
+impl GasSavings {
+    fn unchanged(route: &str, gas: u64, calldata_gas: u64) -> Self {
+        Self {
+            route: route.to_string(),
+            routes_considered: 1,
+            baseline_gas: gas,
+            optimized_gas: gas,
+            route_gas_saved: 0,
+            baseline_calldata_gas: calldata_gas,
+            optimized_calldata_gas: calldata_gas,
+            packed_calls: 1,
+            approvals_skipped: 0,
+            access_list: false,
+        }
+    }
+    
+    pub fn saved_gas(&self) -> u64 {
+        self.baseline_gas.saturating_sub(self.optimized_gas) + self.route_gas_saved
+    }
+}
+
+/// A transaction after optimization, ready to price and sign
+#[derive(Debug, Clone)]
+pub struct GolfedTx {
+    pub tx: Eip1559TransactionRequest,
+    pub savings: GasSavings,
+}
+
+/// Savings recorded for one sent transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasSavingsRecord {
+    pub tx_hash: String,
+    #[serde(flatten)]
+    pub savings: GasSavings,
+    /// Gas saved at the fees the transaction was priced with
+    #[serde(with = "models::wei")]
+    pub projected_savings: U256,
+    pub dry_run: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Projected savings over every optimized transaction and the most recent ones
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasSavingsReport {
+    pub transactions: i64,
+    pub saved_gas: i64,
+    #[serde(with = "models::wei")]
+    pub projected_savings: U256,
+    pub recent: Vec<GasSavingsRecord>,
+}
+
+/// Gas optimizer for the transactions we originate
+///
+/// Each equivalent route is optimized: approvals the existing allowance already covers are
+/// dropped, consecutive calls to a configured multicall contract are packed into one
+/// transaction, sharing its intrinsic gas, and the node's suggested access list is attached
+/// where simulation shows it saves gas. The cheapest route is sent. Savings are measured
+/// against the transactions as built and recorded per sent transaction, priced at its fees.
+#[derive(Clone)]
+pub struct GasGolfer {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Blockchain client, estimates gas and reads allowances
+    blockchain_client: Arc<BlockchainClient>,
+    /// Simulates transactions with and without their suggested access list
+    simulation_service: SimulationService,
+    /// Contracts whose consecutive calls are packed into `multicall(bytes[])`
+    multicall_targets: Arc<HashSet<Address>>,
+    /// Configuration
+    config: GasGolfConfig,
+}
+
+impl GasGolfer {
+    /// Create a new gas optimizer
+    pub fn new(
+        db_pool: DbPool,
+        health: DbHealth,
+        blockchain_client: Arc<BlockchainClient>,
+        simulation_service: SimulationService,
+        config: GasGolfConfig,
+    ) -> Result<Self> {
+        let multicall_targets = config
+            .multicall_targets
+            .iter()
+            .map(|target| target.parse().map_err(|_| anyhow!("Invalid gas golf multicall target: {}", target)))
+            .collect::<Result<HashSet<Address>>>()?;
+        
+        Ok(Self {
+            db_pool,
+            health,
+            blockchain_client,
+            simulation_service,
+            multicall_targets: Arc::new(multicall_targets),
+            config,
+        })
+    }
+    
+    /// Whether our transactions are optimized before they are sent
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Optimize a lone transaction, which leaves only its access list to tune
+    ///
+    /// Approvals and packing need the route around a transaction, see [`Self::golf`].
+    pub async fn golf_tx(&self, route: &str, tx: Eip1559TransactionRequest) -> GolfedTx {
+        let gas = self.cost(&tx).await.unwrap_or_default();
+        let savings = GasSavings::unchanged(route, gas, calldata_gas(data_of(&tx)));
+        let mut golfed = GolfedTx { tx, savings };
+        if gas > 0 {
+            self.tune_access_list(&mut golfed).await;
+        }
+        golfed
+    }
+    
+    /// Optimize each route and pick the cheapest, returning its transactions in order
+    ///
+    /// Routes are offered in order of preference and savings are measured against the first.
+    /// A route that cannot be priced is left out; one made only of approvals already in place
+    /// comes back empty.
+    pub async fn golf(&self, routes: Vec<Route>) -> Result<Vec<GolfedTx>> {
+        let considered = routes.len();
+        let mut priced = Vec::with_capacity(considered);
+        let mut first_total = None;
+        for (index, route) in routes.into_iter().enumerate() {
+            let name = route.name.clone();
+            match self.optimize(route).await {
+                Ok(txs) => {
+                    let total: u64 = txs.iter().map(|golfed| golfed.savings.optimized_gas).sum();
+                    if index == 0 {
+                        first_total = Some(total);
+                    }
+                    priced.push((total, txs));
+                }
+                Err(e) => debug!("Failed to price route {}: {}", name, e),
+            }
+        }
+        
+        let (total, mut txs) = priced
+            .into_iter()
+            .min_by_key(|(total, _)| *total)
+            .ok_or_else(|| anyhow!("None of {} routes could be priced", considered))?;
+        for golfed in &mut txs {
+            golfed.savings.routes_considered = considered;
+        }
+        if let (Some(first), Some(first_total)) = (txs.first_mut(), first_total) {
+            first.savings.route_gas_saved = first_total.saturating_sub(total);
+        }
+        
+        Ok(txs)
+    }
+    
+    async fn optimize(&self, route: Route) -> Result<Vec<GolfedTx>> {
+        // Approvals already in place are dropped, their gas booked on the route's first transaction
+        let mut kept = Vec::with_capacity(route.txs.len());
+        let (mut skipped, mut skipped_gas, mut skipped_calldata_gas) = (0, 0, 0);
+        for tx in route.txs {
+            let gas = self.cost(&tx).await?;
+            if self.allowance_covers(&tx).await {
+                skipped += 1;
+                skipped_gas += gas;
+                skipped_calldata_gas += calldata_gas(data_of(&tx));
+                continue;
+            }
+            kept.push((tx, gas));
+        }
+        
+        let mut txs = Vec::with_capacity(kept.len());
+        let mut start = 0;
+        while start < kept.len() {
+            let mut end = start + 1;
+            while end < kept.len() && self.packable(&kept[start].0, &kept[end].0) {
+                end += 1;
+            }
+            
+            let group = &kept[start..end];
+            match self.pack(&route.name, group).await {
+                Some(packed) => txs.push(packed),
+                None => txs.extend(group.iter().map(|(tx, gas)| GolfedTx {
+                    tx: tx.clone(),
+                    savings: GasSavings::unchanged(&route.name, *gas, calldata_gas(data_of(tx))),
+                })),
+            }
+            start = end;
+        }
+        
+        for golfed in &mut txs {
+            self.tune_access_list(golfed).await;
+        }
+        if let Some(first) = txs.first_mut() {
+            first.savings.approvals_skipped = skipped;
+            first.savings.baseline_gas += skipped_gas;
+            first.savings.baseline_calldata_gas += skipped_calldata_gas;
+        }
+        
+        Ok(txs)
+    }
+    
+    /// Target and sender of a call that can go into a multicall
+    fn multicall_key(&self, tx: &Eip1559TransactionRequest) -> Option<(Address, Option<Address>)> {
+        let to = target_of(tx).filter(|to| self.multicall_targets.contains(to))?;
+        tx.value.unwrap_or_default().is_zero().then_some((to, tx.from))
+    }
+    
+    fn packable(&self, first: &Eip1559TransactionRequest, next: &Eip1559TransactionRequest) -> bool {
+        let key = self.multicall_key(first);
+        key.is_some() && key == self.multicall_key(next)
+    }
+    
+    /// Pack consecutive calls to one multicall contract, if that estimates cheaper than sending each
+    async fn pack(&self, route: &str, group: &[(Eip1559TransactionRequest, u64)]) -> Option<GolfedTx> {
+        if group.len() < 2 {
+            return None;
+        }
+        
+        let (to, from) = self.multicall_key(&group[0].0)?;
+        let calls = group.iter().map(|(tx, _)| Token::Bytes(data_of(tx).to_vec())).collect();
+        let mut data = id(MULTICALL).to_vec();
+        data.extend(abi::encode(&[Token::Array(calls)]));
+        let packed_calldata_gas = calldata_gas(&data);
+        
+        let mut tx = Eip1559TransactionRequest::new().to(to).data(Bytes::from(data));
+        if let Some(from) = from {
+            tx = tx.from(from);
+        }
+        let shared_gas = TX_BASE_GAS * (group.len() as u64 - 1);
+        let declared = group.iter().try_fold(U256::zero(), |sum, (tx, _)| tx.gas.map(|gas| sum + gas));
+        if let Some(declared) = declared {
+            tx = tx.gas(declared.saturating_sub(U256::from(shared_gas)));
+        }
+        
+        let baseline_gas: u64 = group.iter().map(|(_, gas)| *gas).sum();
+        let baseline_calldata_gas: u64 = group.iter().map(|(tx, _)| calldata_gas(data_of(tx))).sum();
+        // The packed call may revert where the separate ones don't, so it is only sent once it estimates
+        let optimized_gas = self.estimate(&tx).await?;
+        if optimized_gas >= baseline_gas {
+            return None;
+        }
+        
+        let mut savings = GasSavings::unchanged(route, baseline_gas, baseline_calldata_gas);
+        savings.optimized_gas = optimized_gas;
+        savings.optimized_calldata_gas = packed_calldata_gas;
+        savings.packed_calls = group.len();
+        Some(GolfedTx { tx, savings })
+    }
+    
+    /// Attach the node's suggested access list when simulating with it uses less gas than without
+    ///
+    /// Both runs are on the latest state, so they take the account's next mined nonce and, as
+    /// fees are set only when the transaction is signed, the current gas price. Any failure
+    /// leaves the transaction as it was.
+    async fn tune_access_list(&self, golfed: &mut GolfedTx) {
+        if !self.config.tune_access_lists || !golfed.tx.access_list.0.is_empty() {
+            return;
+        }
+        let from = match golfed.tx.from {
+            Some(from) => from,
+            None => return,
+        };
+        let to = match &golfed.tx.to {
+            Some(NameOrAddress::Address(to)) => Some(*to),
+            Some(NameOrAddress::Name(_)) => return,
+            None => None,
+        };
+        
+        let typed = TypedTransaction::Eip1559(golfed.tx.clone());
+        let suggestion = match self.blockchain_client.create_access_list(&typed).await {
+            Ok(Some(suggestion)) if !suggestion.access_list.0.is_empty() => suggestion,
+            Ok(_) => return,
+            Err(e) => {
+                debug!("Failed to create an access list: {}", e);
+                return;
+            }
+        };
+        let state = tokio::try_join!(
+            self.blockchain_client.get_transaction_count(from, BlockNumber::Latest),
+            self.blockchain_client.get_cached_gas_price(),
+        );
+        let (nonce, gas_price) = match state {
+            Ok(state) => state,
+            Err(e) => {
+                debug!("Failed to read the state of {:?} for access list simulation: {}", from, e);
+                return;
+            }
+        };
+        
+        // Without the list the first touch of each entry is cold, so leave room over the node's figure
+        let tx = &golfed.tx;
+        let gas = tx.gas.unwrap_or(suggestion.gas_used + suggestion.gas_used / 2);
+        let simulated = |access_list: AccessList| Transaction {
+            from,
+            to,
+            nonce: nonce.into(),
+            value: tx.value.unwrap_or_default(),
+            gas,
+            input: tx.data.clone().unwrap_or_default(),
+            max_fee_per_gas: Some(tx.max_fee_per_gas.unwrap_or(gas_price)),
+            max_priority_fee_per_gas: Some(tx.max_priority_fee_per_gas.unwrap_or_default()),
+            transaction_type: Some(2u64.into()),
+            access_list: Some(access_list),
+            chain_id: Some(self.blockchain_client.chain_id().into()),
+            ..Default::default()
+        };
+        
+        let runs = tokio::try_join!(
+            self.simulation_service.simulate_bundle(vec![simulated(AccessList::default())]),
+            self.simulation_service.simulate_bundle(vec![simulated(suggestion.access_list.clone())]),
+        );
+        let (without, with) = match runs {
+            Ok((without, with)) if without.success && with.success => (without, with),
+            Ok(_) => {
+                debug!("Access list simulation of a transaction from {:?} reverted, sending without", from);
+                return;
+            }
+            Err(e) => {
+                debug!("Failed to simulate an access list for a transaction from {:?}: {}", from, e);
+                return;
+            }
+        };
+        
+        let saved = without.gas_used.saturating_sub(with.gas_used);
+        if saved == 0 || saved < self.config.min_access_list_gas_saved {
+            return;
+        }
+        debug!(
+            "Attaching an access list of {} addresses to a transaction from {:?}, saving {} gas in simulation",
+            suggestion.access_list.0.len(),
+            from,
+            saved
+        );
+        metrics::counter!("access_lists_attached_total", 1);
+        metrics::counter!("access_list_saved_gas_total", saved);
+        golfed.tx = golfed.tx.clone().access_list(suggestion.access_list);
+        golfed.savings.optimized_gas = golfed.savings.optimized_gas.saturating_sub(saved);
+        golfed.savings.access_list = true;
+    }
+    
+    /// Whether a transaction is an approval the existing allowance already covers
+    async fn allowance_covers(&self, tx: &Eip1559TransactionRequest) -> bool {
+        let data = data_of(tx);
+        if data.len() != 68 || data[..4] != id(APPROVE)[..] || !tx.value.unwrap_or_default().is_zero() {
+            return false;
+        }
+        let (token, owner) = match (target_of(tx), tx.from) {
+            (Some(token), Some(owner)) => (token, owner),
+            _ => return false,
+        };
+        let (spender, amount) = match abi::decode(&[ParamType::Address, ParamType::Uint(256)], &data[4..]) {
+            Ok(args) => (
+                args[0].clone().into_address().unwrap_or_default(),
+                args[1].clone().into_uint().unwrap_or_default(),
+            ),
+            Err(_) => return false,
+        };
+        // Resetting an allowance to zero is never redundant
+        if amount.is_zero() {
+            return false;
+        }
+        
+        let mut call = id(ALLOWANCE).to_vec();
+        call.extend(abi::encode(&[Token::Address(owner), Token::Address(spender)]));
+        match self.blockchain_client.call(token, Bytes::from(call)).await {
+            Ok(output) if output.len() >= 32 => U256::from_big_endian(&output[..32]) >= amount,
+            Ok(_) => false,
+            Err(e) => {
+                debug!("Failed to read allowance of {:?} on {:?}: {}", spender, token, e);
+                false
+            }
+        }
+    }
+    
+    async fn estimate(&self, tx: &Eip1559TransactionRequest) -> Option<u64> {
+        match self.blockchain_client.estimate_gas(&TypedTransaction::Eip1559(tx.clone())).await {
+            Ok(gas) => Some(gas.min(U256::from(u64::MAX)).as_u64()),
+            Err(e) => {
+                debug!("Failed to estimate gas while optimizing: {}", e);
+                None
+            }
+        }
+    }
+    
+    /// Estimated gas, or the declared limit of a transaction that can't be estimated yet
+    async fn cost(&self, tx: &Eip1559TransactionRequest) -> Result<u64> {
+        match self.estimate(tx).await {
+            Some(gas) => Ok(gas),
+            None => tx
+                .gas
+                .map(|gas| gas.min(U256::from(u64::MAX)).as_u64())
+                .ok_or_else(|| anyhow!("Transaction can neither be estimated nor declares its gas")),
+        }
+    }
+    
+    /// Record the savings of a sent transaction, priced at the gas price it was sent with
+    pub async fn record(&self, tx_hash: H256, savings: &GasSavings, gas_price: U256, dry_run: bool) -> Result<()> {
+        self.health.retry(|| self.record_once(tx_hash, savings, gas_price, dry_run)).await
+    }
+    
+    /// Totals over every recorded transaction and the most recent ones
+    pub async fn report(&self, limit: i64) -> Result<GasSavingsReport> {
+        self.health.retry(|| self.report_once(limit)).await
+    }
+    
+    async fn record_once(&self, tx_hash: H256, savings: &GasSavings, gas_price: U256, dry_run: bool) -> Result<()> {
+        let saved_gas = savings.saved_gas();
+        let projected_savings = U256::from(saved_gas) * gas_price;
+        sqlx::query(
+            "INSERT INTO gas_savings
+             (tx_hash, route, routes_considered, baseline_gas, optimized_gas, route_gas_saved,
+              baseline_calldata_gas, optimized_calldata_gas, packed_calls, approvals_skipped, access_list,
+              projected_savings_wei, dry_run)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12::NUMERIC, $13)
+             ON CONFLICT (tx_hash) DO NOTHING",
+        )
+        .bind(format!("{:?}", tx_hash))
+        .bind(&savings.route)
+        .bind(savings.routes_considered as i32)
+        .bind(savings.baseline_gas as i64)
+        .bind(savings.optimized_gas as i64)
+        .bind(savings.route_gas_saved as i64)
+        .bind(savings.baseline_calldata_gas as i64)
+        .bind(savings.optimized_calldata_gas as i64)
+        .bind(savings.packed_calls as i32)
+        .bind(savings.approvals_skipped as i32)
+        .bind(savings.access_list)
+        .bind(projected_savings.to_string())
+        .bind(dry_run)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to record gas savings")?;
+        
+        metrics::counter!("gas_golf_transactions_total", 1, "route" => savings.route.clone());
+        metrics::counter!("gas_golf_saved_gas_total", saved_gas, "route" => savings.route.clone());
+        if saved_gas > 0 {
+            info!("Gas golf saves {} gas on {:?} ({})", saved_gas, tx_hash, savings.route);
+        }
+        
+        Ok(())
+    }
+    
+    async fn report_once(&self, limit: i64) -> Result<GasSavingsReport> {
+        let totals = sqlx::query(
+            "SELECT COUNT(*) AS transactions,
+                    COALESCE(SUM(GREATEST(baseline_gas - optimized_gas, 0) + route_gas_saved), 0)::BIGINT
+                        AS saved_gas,
+                    COALESCE(SUM(projected_savings_wei), 0)::TEXT AS projected_savings
+             FROM gas_savings",
+        )
+        .fetch_one(&self.db_pool)
+        .await
+        .context("Failed to summarize gas savings")?;
+        
+        let wei = |raw: String| U256::from_dec_str(&raw).map_err(|e| anyhow!("Invalid savings {}: {}", raw, e));
+        
+        let rows = sqlx::query(
+            "SELECT tx_hash, route, routes_considered, baseline_gas, optimized_gas, route_gas_saved,
+                    baseline_calldata_gas, optimized_calldata_gas, packed_calls, approvals_skipped, access_list,
+                    projected_savings_wei::TEXT AS projected_savings_wei, dry_run, created_at
+             FROM gas_savings
+             ORDER BY created_at DESC
+             LIMIT $1",
+        )
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to fetch gas savings")?;
+        
+        let recent = rows
+            .iter()
+            .map(|row| {
+                Ok(GasSavingsRecord {
+                    tx_hash: row.try_get("tx_hash")?,
+                    savings: GasSavings {
+                        route: row.try_get("route")?,
+                        routes_considered: row.try_get::<i32, _>("routes_considered")? as usize,
+                        baseline_gas: row.try_get::<i64, _>("baseline_gas")? as u64,
+                        optimized_gas: row.try_get::<i64, _>("optimized_gas")? as u64,
+                        route_gas_saved: row.try_get::<i64, _>("route_gas_saved")? as u64,
+                        baseline_calldata_gas: row.try_get::<i64, _>("baseline_calldata_gas")? as u64,
+                        optimized_calldata_gas: row.try_get::<i64, _>("optimized_calldata_gas")? as u64,
+                        packed_calls: row.try_get::<i32, _>("packed_calls")? as usize,
+                        approvals_skipped: row.try_get::<i32, _>("approvals_skipped")? as usize,
+                        access_list: row.try_get("access_list")?,
+                    },
+                    projected_savings: wei(row.try_get("projected_savings_wei")?)?,
+                    dry_run: row.try_get("dry_run")?,
+                    created_at: row.try_get("created_at")?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        
+        Ok(GasSavingsReport {
+            transactions: totals.try_get("transactions")?,
+            saved_gas: totals.try_get("saved_gas")?,
+            projected_savings: wei(totals.try_get("projected_savings")?)?,
+            recent,
+        })
+    }
+}
@@ -0,0 +1,144 @@
+use ethers::types::H256;
+use parking_lot::Mutex;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+use crate::{
+    config::BlockchainConfig,
+    services::alerting::{Alert, AlertManager, Severity},
+};
+
+/// The head the builder is currently building on
+#[derive(Debug, Clone, Copy)]
+struct Head {
+    number: u64,
+    hash: H256,
+    observed_at: Instant,
+}
+
+/// Detects when the head we build on stops advancing
+#[derive(Clone)]
+pub struct HeadTracker {
+    /// Latest head seen on any provider
+    head: Arc<Mutex<Option<Head>>>,
+    /// When the primary subscription last delivered a new head
+    primary_observed_at: Arc<Mutex<Option<Instant>>>,
+    /// Set while the head is stale; bidding must stop
+    stale: Arc<AtomicBool>,
+    /// How long the head may go without advancing
+    stale_after: Duration,
+    /// Construction time, the staleness clock of a head or subscription never seen
+    started_at: Instant,
+    /// Alert manager
+    alert_manager: AlertManager,
+}
+
+impl HeadTracker {
+    /// Create a new head tracker
+    pub fn new(config: &BlockchainConfig, alert_manager: AlertManager) -> Self {
+        Self {
+            head: Arc::new(Mutex::new(None)),
+            primary_observed_at: Arc::new(Mutex::new(None)),
+            stale: Arc::new(AtomicBool::new(false)),
+            stale_after: Duration::from_secs(config.slot_duration_seconds * config.stale_head_slots),
+            started_at: Instant::now(),
+            alert_manager,
+        }
+    }
+    
+    /// Record a head, returning false if it is not newer than the one we already have
+    pub fn record_head(&self, number: u64, hash: H256, from_fallback: bool) -> bool {
+        if !from_fallback {
+            *self.primary_observed_at.lock() = Some(Instant::now());
+        }
+        
+        {
+            let mut head = self.head.lock();
+            if let Some(current) = *head {
+                // Same-height heads with a different hash are reorgs and still count
+                if number < current.number || (number == current.number && hash == current.hash) {
+                    return false;
+                }
+            }
+            *head = Some(Head {
+                number,
+                hash,
+                observed_at: Instant::now(),
+            });
+        }
+        
+        if self.stale.swap(false, Ordering::SeqCst) {
+            info!("Head advanced to #{}, resuming bidding", number);
+            metrics::gauge!("builder_head_stale", 0.0);
+        }
+        
+        true
+    }
+    
+    /// Latest head number, if any head has been seen
+    pub fn head_number(&self) -> Option<u64> {
+        self.head.lock().map(|head| head.number)
+    }
+    
+    /// Number and hash of the latest head, if any head has been seen
+    pub fn head(&self) -> Option<(u64, H256)> {
+        self.head.lock().map(|head| (head.number, head.hash))
+    }
+    
+    /// Whether bids may be submitted on the current head
+    pub fn can_bid(&self) -> bool {
+        !self.stale.load(Ordering::SeqCst)
+    }
+    
+    /// Re-evaluate staleness, alerting when the head first goes stale
+    ///
+    /// Returns whether the primary subscription is stale, in which case heads should be
+    /// taken from the fallback provider until it recovers. A subscription that never delivered
+    /// a head goes stale as if it had delivered one at startup.
+    pub async fn check(&self) -> bool {
+        let primary_observed_at = self.primary_observed_at.lock().unwrap_or(self.started_at);
+        let primary_stale = primary_observed_at.elapsed() > self.stale_after;
+        
+        let head = *self.head.lock();
+        let (head_number, observed_at) = match head {
+            Some(head) => (Some(head.number), head.observed_at),
+            None => (None, self.started_at),
+        };
+        
+        let age = observed_at.elapsed();
+        if age <= self.stale_after {
+            return primary_stale;
+        }
+        
+        if !self.stale.swap(true, Ordering::SeqCst) {
+            let head = head_number.map_or_else(
+                || "No head has been seen".to_string(),
+                |number| format!("Head #{} has not advanced", number),
+            );
+            warn!("{} for {:?}, stopping bids", head, age);
+            metrics::gauge!("builder_head_stale", 1.0);
+            metrics::counter!("builder_stale_head_total", 1);
+            
+            self.alert_manager
+                .fire(Alert::new(
+                    "builder:stale_head",
+                    Severity::Critical,
+                    "head_tracker",
+                    format!(
+                        "{} for {}s; bidding stopped, switching to fallback provider",
+                        head,
+                        age.as_secs()
+                    ),
+                ))
+                .await;
+        }
+        
+        primary_stale
+    }
+}
@@ -0,0 +1,239 @@
+use anyhow::{Context, Result};
+use ethers::types::{H256, U256};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::collections::{HashMap, HashSet};
+use tracing::debug;
+
+use crate::{
+    database::{DbHealth, DbPool},
+    services::{export::ExportBundle, staking_ledger::StakingLedger, transaction::InclusionCandidate},
+    utils::units::wei_to_eth,
+};
+
+/// Trailing window of the staking APR KPI
+const APR_WINDOW_DAYS: u32 = 7;
+
+/// Bidding KPIs for one relay over the trailing hour
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RelayKpis {
+    /// Slots we had a bid accepted for at this relay
+    pub slots_bid: usize,
+    /// Of those, slots the relay delivered one of our blocks for
+    pub slots_won: usize,
+    pub win_rate: f64,
+}
+
+/// Flow KPIs for one transaction source over the trailing hour
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SourceKpis {
+    /// Distinct candidates from this source
+    pub candidates: usize,
+    /// Distinct candidates from this source that landed
+    pub landed: usize,
+    /// Profit of landed candidates from this source, in ETH
+    pub realized_profit_eth: f64,
+}
+
+/// Business KPIs over the trailing hour
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HourlyKpis {
+    /// Simulated profit of every distinct candidate we considered, in ETH
+    pub gross_profit_eth: f64,
+    /// Profit of candidates that actually landed, in ETH
+    pub realized_profit_eth: f64,
+    /// Distinct candidates considered
+    pub candidates: usize,
+    /// Distinct candidates that landed
+    pub landed: usize,
+    /// Share of candidates that landed
+    pub landed_rate: f64,
+    /// Subsidy paid above extracted value on slots we won, in ETH; a cost line in P&L
+    pub subsidy_cost_eth: f64,
+    /// Breakdown by transaction source
+    pub by_source: HashMap<String, SourceKpis>,
+    /// Breakdown by relay
+    pub by_relay: HashMap<String, RelayKpis>,
+    /// Ether pooled by stakers, in ETH
+    pub tvl_eth: f64,
+    /// Realized staking APR over the trailing week, before commission
+    pub gross_apr: f64,
+    /// Realized staking APR over the trailing week, net of commission
+    pub net_apr: f64,
+}
+
+/// Periodic aggregator that turns persisted records into business KPI gauges
+#[derive(Clone)]
+pub struct KpiAggregator {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Pooled ether and realized APR
+    staking_ledger: StakingLedger,
+}
+
+impl KpiAggregator {
+    /// Create a new KPI aggregator
+    pub fn new(db_pool: DbPool, health: DbHealth, staking_ledger: StakingLedger) -> Result<Self> {
+        Ok(Self {
+            db_pool,
+            health,
+            staking_ledger,
+        })
+    }
+    
+    /// Recompute the KPIs and publish them as gauges
+    pub async fn aggregate(&self) -> Result<HourlyKpis> {
+        let kpis = self.hourly_kpis().await?;
+        
+        metrics::gauge!("kpi_gross_profit_eth_per_hour", kpis.gross_profit_eth);
+        metrics::gauge!("kpi_realized_profit_eth_per_hour", kpis.realized_profit_eth);
+        metrics::gauge!("kpi_landed_bundle_rate", kpis.landed_rate);
+        metrics::gauge!("kpi_subsidy_cost_eth_per_hour", kpis.subsidy_cost_eth);
+        metrics::gauge!("kpi_tvl_eth", kpis.tvl_eth);
+        metrics::gauge!("kpi_staking_apr", kpis.gross_apr, "kind" => "gross");
+        metrics::gauge!("kpi_staking_apr", kpis.net_apr, "kind" => "net");
+        for (relay, relay_kpis) in &kpis.by_relay {
+            metrics::gauge!("kpi_relay_win_rate", relay_kpis.win_rate, "relay" => relay.clone());
+        }
+        
+        let mut realized_by_kind: HashMap<&str, f64> = HashMap::new();
+        for (source, source_kpis) in &kpis.by_source {
+            let kind = source.split(':').next().unwrap_or(source);
+            *realized_by_kind.entry(kind).or_default() += source_kpis.realized_profit_eth;
+        }
+        for (kind, realized) in realized_by_kind {
+            metrics::gauge!("kpi_realized_profit_eth_per_hour_by_source", realized, "source" => kind.to_string());
+        }
+        
+        debug!(
+            "KPIs: gross={} ETH realized={} ETH landed={}/{}",
+            kpis.gross_profit_eth, kpis.realized_profit_eth, kpis.landed, kpis.candidates
+        );
+        
+        Ok(kpis)
+    }
+    
+    /// Compute the KPIs over the trailing hour
+    pub async fn hourly_kpis(&self) -> Result<HourlyKpis> {
+        let mut kpis = self.health.retry(|| self.hourly_kpis_once()).await?;
+        
+        let totals = self.staking_ledger.pool_totals().await?;
+        let apr = self.staking_ledger.apr_summary(APR_WINDOW_DAYS).await?;
+        kpis.tvl_eth = wei_to_eth(totals.pooled);
+        kpis.gross_apr = apr.gross_apr;
+        kpis.net_apr = apr.net_apr;
+        
+        Ok(kpis)
+    }
+    
+    /// KPIs of our own records, the staking ones left for the ledger to fill in
+    async fn hourly_kpis_once(&self) -> Result<HourlyKpis> {
+        // A candidate stays in the template across heads until it lands, so dedupe by hash
+        let rows = sqlx::query(
+            "SELECT state->'candidates' AS candidates FROM inflight_state_history
+             WHERE recorded_at > NOW() - INTERVAL '1 hour'",
+        )
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to load template history")?;
+        
+        let mut candidates: HashMap<H256, U256> = HashMap::new();
+        let mut by_source: HashMap<String, SourceKpis> = HashMap::new();
+        for row in rows {
+            let snapshot = row.try_get::<sqlx::types::Json<Vec<InclusionCandidate>>, _>("candidates")?.0;
+            for candidate in snapshot {
+                let profit = candidates.entry(candidate.tx.hash).or_insert_with(|| {
+                    by_source.entry(candidate.source.to_string()).or_default().candidates += 1;
+                    U256::zero()
+                });
+                *profit = (*profit).max(candidate.profit);
+            }
+        }
+        
+        let rows = sqlx::query(
+            "SELECT bundles FROM built_blocks WHERE built_at > NOW() - INTERVAL '1 hour'",
+        )
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to load built blocks")?;
+        
+        let mut landed: HashSet<H256> = HashSet::new();
+        let mut realized = U256::zero();
+        for row in rows {
+            let bundles = row.try_get::<sqlx::types::Json<Vec<ExportBundle>>, _>("bundles")?.0;
+            for bundle in bundles {
+                realized = realized.saturating_add(bundle.value);
+                
+                let source = by_source.entry(bundle.source.to_string()).or_default();
+                source.realized_profit_eth += wei_to_eth(bundle.value);
+                source.landed += bundle.tx_hashes.len();
+                
+                landed.extend(bundle.tx_hashes);
+            }
+        }
+        
+        let row = sqlx::query(
+            "SELECT COALESCE(SUM(amount_wei), 0)::TEXT AS subsidy FROM subsidy_ledger
+             WHERE paid_at > NOW() - INTERVAL '1 hour'",
+        )
+        .fetch_one(&self.db_pool)
+        .await
+        .context("Failed to load subsidy spend")?;
+        let subsidy = U256::from_dec_str(row.try_get("subsidy")?)?;
+        
+        // Won where the relay delivered a block we bid to it
+        let rows = sqlx::query(
+            "SELECT b.relay,
+                    COUNT(DISTINCT b.slot) AS slots_bid,
+                    COUNT(DISTINCT b.slot) FILTER (WHERE EXISTS (
+                        SELECT 1 FROM relay_delivered_payloads d
+                        WHERE d.relay = b.relay AND d.slot = b.slot AND LOWER(d.block_hash) = LOWER(b.block_hash)
+                    )) AS slots_won
+             FROM builder_bids b
+             WHERE b.accepted AND b.submitted_at > NOW() - INTERVAL '1 hour'
+             GROUP BY b.relay",
+        )
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to load relay win rates")?;
+        
+        let mut by_relay = HashMap::new();
+        for row in rows {
+            let slots_bid = row.try_get::<i64, _>("slots_bid")? as usize;
+            let slots_won = row.try_get::<i64, _>("slots_won")? as usize;
+            by_relay.insert(
+                row.try_get::<String, _>("relay")?,
+                RelayKpis {
+                    slots_bid,
+                    slots_won,
+                    win_rate: if slots_bid == 0 { 0.0 } else { slots_won as f64 / slots_bid as f64 },
+                },
+            );
+        }
+        
+        let gross = candidates
+            .values()
+            .fold(U256::zero(), |acc, profit| acc.saturating_add(*profit));
+        let landed_count = landed.iter().filter(|hash| candidates.contains_key(hash)).count();
+        
+        Ok(HourlyKpis {
+            gross_profit_eth: wei_to_eth(gross),
+            realized_profit_eth: wei_to_eth(realized),
+            candidates: candidates.len(),
+            landed: landed_count,
+            landed_rate: if candidates.is_empty() {
+                0.0
+            } else {
+                landed_count as f64 / candidates.len() as f64
+            },
+            subsidy_cost_eth: wei_to_eth(subsidy),
+            by_source,
+            by_relay,
+            tvl_eth: 0.0,
+            gross_apr: 0.0,
+            net_apr: 0.0,
+        })
+    }
+}
@@ -0,0 +1,309 @@
+use anyhow::{anyhow, Context, Result};
+use dashmap::DashMap;
+use ethers::types::{Address, Transaction};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::{fmt, str::FromStr, sync::Arc};
+use tracing::info;
+
+use crate::{api::models, config::LabelListConfig, database::{DbHealth, DbPool}};
+
+/// Source of labels entered by an operator, which take precedence over imported lists
+pub const MANUAL_SOURCE: &str = "manual";
+
+const UPSERT_LABEL: &str = "INSERT INTO address_labels (address, source, label, category, updated_at)
+     VALUES ($1, $2, $3, $4, NOW())
+     ON CONFLICT (address, source) DO UPDATE
+     SET label = EXCLUDED.label, category = EXCLUDED.category, updated_at = NOW()";
+
+/// Kind of entity behind a labeled address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelCategory {
+    /// Lending pools, DEXes and other protocol contracts holding user funds
+    Protocol,
+    MarketMaker,
+    Exploiter,
+    Bridge,
+    Exchange,
+    Fund,
+    Searcher,
+    Other,
+}
+
+impl LabelCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Protocol => "protocol",
+            Self::MarketMaker => "market_maker",
+            Self::Exploiter => "exploiter",
+            Self::Bridge => "bridge",
+            Self::Exchange => "exchange",
+            Self::Fund => "fund",
+            Self::Searcher => "searcher",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for LabelCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for LabelCategory {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "protocol" => Ok(Self::Protocol),
+            "market_maker" => Ok(Self::MarketMaker),
+            "exploiter" => Ok(Self::Exploiter),
+            "bridge" => Ok(Self::Bridge),
+            "exchange" => Ok(Self::Exchange),
+            "fund" => Ok(Self::Fund),
+            "searcher" => Ok(Self::Searcher),
+            "other" => Ok(Self::Other),
+            other => Err(anyhow!("Unknown label category: {}", other)),
+        }
+    }
+}
+
+/// A label entry as imported or entered by an operator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelEntry {
+    #[serde(with = "models::checksum")]
+    pub address: Address,
+    /// Entity name, e.g. `Wintermute`
+    pub label: String,
+    pub category: LabelCategory,
+}
+
+/// A known entity behind an address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressLabel {
+    #[serde(with = "models::checksum")]
+    pub address: Address,
+    pub label: String,
+    pub category: LabelCategory,
+    /// `manual`, or the name of the list the label was imported from
+    pub source: String,
+}
+
+/// Known entities sending or receiving a transaction
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TxLabels {
+    pub from: Option<AddressLabel>,
+    pub to: Option<AddressLabel>,
+}
+
+impl TxLabels {
+    pub fn is_empty(&self) -> bool {
+        self.from.is_none() && self.to.is_none()
+    }
+    
+    /// Whether the sender or recipient is an entity of this kind
+    pub fn has(&self, category: LabelCategory) -> bool {
+        self.from.iter().chain(&self.to).any(|label| label.category == category)
+    }
+}
+
+/// Address label registry
+///
+/// Labels come from imported lists, each replaced wholesale when imported again, and from
+/// operator entries which win over any list. The registry is held in memory for lookups on
+/// the mempool path and persisted so both survive restarts.
+#[derive(Clone)]
+pub struct LabelRegistry {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Labels by address, one per source
+    labels: Arc<DashMap<Address, Vec<AddressLabel>>>,
+}
+
+impl LabelRegistry {
+    /// Create the registry, loading the persisted labels
+    pub async fn load(db_pool: DbPool, health: DbHealth) -> Result<Self> {
+        let labels = health.retry(|| load_labels(&db_pool)).await?;
+        
+        Ok(Self {
+            db_pool,
+            health,
+            labels: Arc::new(labels),
+        })
+    }
+    
+    /// Import the configured label lists from disk
+    pub async fn import_lists(&self, lists: &[LabelListConfig]) -> Result<()> {
+        for list in lists {
+            let raw = std::fs::read_to_string(&list.path)
+                .context(format!("Failed to read label list {} from {}", list.name, list.path))?;
+            let entries: Vec<LabelEntry> =
+                serde_json::from_str(&raw).context(format!("Invalid label list {}", list.name))?;
+            self.import(&list.name, entries).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Label of an address, preferring an operator entry over imported lists
+    pub fn lookup(&self, address: &Address) -> Option<AddressLabel> {
+        let labels = self.labels.get(address)?;
+        labels
+            .iter()
+            .find(|label| label.source == MANUAL_SOURCE)
+            .or_else(|| labels.first())
+            .cloned()
+    }
+    
+    /// Known entities sending or receiving a transaction
+    pub fn tag(&self, tx: &Transaction) -> TxLabels {
+        if self.labels.is_empty() {
+            return TxLabels::default();
+        }
+        
+        TxLabels {
+            from: self.lookup(&tx.from),
+            to: tx.to.and_then(|to| self.lookup(&to)),
+        }
+    }
+    
+    /// Every label of every source, by address
+    pub fn list(&self) -> Vec<AddressLabel> {
+        let mut labels: Vec<AddressLabel> = self.labels.iter().flat_map(|entry| entry.value().clone()).collect();
+        labels.sort_by(|a, b| a.address.cmp(&b.address).then_with(|| a.source.cmp(&b.source)));
+        labels
+    }
+    
+    /// Replace every label imported from a list, returning how many it holds
+    pub async fn import(&self, source: &str, entries: Vec<LabelEntry>) -> Result<usize> {
+        if source == MANUAL_SOURCE {
+            return Err(anyhow!("Label lists cannot be imported as {}", MANUAL_SOURCE));
+        }
+        
+        self.health.retry(|| self.store_import(source, &entries)).await?;
+        
+        self.labels.alter_all(|_, mut labels| {
+            labels.retain(|label| label.source != source);
+            labels
+        });
+        for entry in entries.iter() {
+            self.insert(entry, source);
+        }
+        self.labels.retain(|_, labels| !labels.is_empty());
+        metrics::gauge!("address_labels", self.labels.len() as f64);
+        info!("Imported {} address labels from {}", entries.len(), source);
+        
+        Ok(entries.len())
+    }
+    
+    /// Label an address by hand, overriding any imported label
+    pub async fn set_manual(&self, entry: LabelEntry) -> Result<AddressLabel> {
+        self.health.retry(|| self.store_manual(&entry)).await?;
+        
+        let label = self.insert(&entry, MANUAL_SOURCE);
+        metrics::gauge!("address_labels", self.labels.len() as f64);
+        info!("Labeled {:?} as {} ({})", entry.address, entry.label, entry.category);
+        
+        Ok(label)
+    }
+    
+    /// Remove an operator label, returning whether there was one
+    pub async fn remove_manual(&self, address: Address) -> Result<bool> {
+        let removed = self.health.retry(|| self.delete_manual(address)).await?;
+        
+        if let Some(mut labels) = self.labels.get_mut(&address) {
+            labels.retain(|label| label.source != MANUAL_SOURCE);
+        }
+        self.labels.retain(|_, labels| !labels.is_empty());
+        metrics::gauge!("address_labels", self.labels.len() as f64);
+        
+        Ok(removed)
+    }
+    
+    fn insert(&self, entry: &LabelEntry, source: &str) -> AddressLabel {
+        let label = AddressLabel {
+            address: entry.address,
+            label: entry.label.clone(),
+            category: entry.category,
+            source: source.to_string(),
+        };
+        let mut labels = self.labels.entry(entry.address).or_default();
+        labels.retain(|existing| existing.source != source);
+        labels.push(label.clone());
+        label
+    }
+    
+    async fn store_import(&self, source: &str, entries: &[LabelEntry]) -> Result<()> {
+        let mut db_tx = self.db_pool.begin().await.context("Failed to start label import")?;
+        sqlx::query("DELETE FROM address_labels WHERE source = $1")
+            .bind(source)
+            .execute(&mut *db_tx)
+            .await
+            .context("Failed to remove previous labels")?;
+        for entry in entries {
+            sqlx::query(UPSERT_LABEL)
+                .bind(format!("{:?}", entry.address))
+                .bind(source)
+                .bind(&entry.label)
+                .bind(entry.category.as_str())
+                .execute(&mut *db_tx)
+                .await
+                .context("Failed to store imported label")?;
+        }
+        db_tx.commit().await.context("Failed to commit label import")?;
+        
+        Ok(())
+    }
+    
+    async fn store_manual(&self, entry: &LabelEntry) -> Result<()> {
+        sqlx::query(UPSERT_LABEL)
+            .bind(format!("{:?}", entry.address))
+            .bind(MANUAL_SOURCE)
+            .bind(&entry.label)
+            .bind(entry.category.as_str())
+            .execute(&self.db_pool)
+            .await
+            .context("Failed to store address label")?;
+        
+        Ok(())
+    }
+    
+    async fn delete_manual(&self, address: Address) -> Result<bool> {
+        let removed = sqlx::query("DELETE FROM address_labels WHERE address = $1 AND source = $2")
+            .bind(format!("{:?}", address))
+            .bind(MANUAL_SOURCE)
+            .execute(&self.db_pool)
+            .await
+            .context("Failed to remove address label")?
+            .rows_affected();
+        
+        Ok(removed > 0)
+    }
+}
+
+/// Persisted labels by address
+async fn load_labels(db_pool: &DbPool) -> Result<DashMap<Address, Vec<AddressLabel>>> {
+    let rows = sqlx::query("SELECT address, label, category, source FROM address_labels")
+        .fetch_all(db_pool)
+        .await
+        .context("Failed to load address labels")?;
+    
+    let labels: DashMap<Address, Vec<AddressLabel>> = DashMap::new();
+    for row in &rows {
+        let address: String = row.try_get("address")?;
+        let category: String = row.try_get("category")?;
+        let label = AddressLabel {
+            address: address.parse().map_err(|_| anyhow!("Invalid labeled address: {}", address))?,
+            label: row.try_get("label")?,
+            category: category.parse()?,
+            source: row.try_get("source")?,
+        };
+        labels.entry(label.address).or_default().push(label);
+    }
+    info!("Loaded {} address labels", rows.len());
+    Ok(labels)
+}
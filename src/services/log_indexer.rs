@@ -0,0 +1,214 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use ethers::{
+    abi::{parse_abi, Event, EventExt, RawLog, Token},
+    types::{Address, Filter, Log, ValueOrArray, H256, I256},
+    utils::to_checksum,
+};
+use serde_json::{Map, Value};
+use std::{collections::HashMap, sync::Arc};
+use tracing::debug;
+
+use crate::{
+    blockchain::{
+        rate_limit::{with_priority, RpcPriority},
+        BlockchainClient,
+    },
+    config::EventIndexerConfig,
+    database::repositories::events::{EventPage, EventQuery, EventRepository, IndexedEvent},
+};
+
+/// Parse a human-readable event signature, with or without the `event` keyword
+pub fn parse_event(signature: &str) -> Result<Event> {
+    let signature = signature.trim();
+    let declaration = match signature.strip_prefix("event ") {
+        Some(_) => signature.to_string(),
+        None => format!("event {}", signature),
+    };
+    let abi = parse_abi(&[declaration.as_str()]).context(format!("Invalid event signature {}", signature))?;
+    let event = abi.events().next().cloned().ok_or_else(|| anyhow!("No event in {}", signature))?;
+    if event.anonymous {
+        return Err(anyhow!("Anonymous event {} can't be matched by topic", signature));
+    }
+    
+    Ok(event)
+}
+
+/// An event type we index, by the contract emitting it
+struct EventType {
+    contract: String,
+    event: Event,
+    /// Canonical signature, e.g. `Transfer(address,address,uint256)`
+    signature: String,
+}
+
+/// Decodes and stores the events of the configured contracts
+///
+/// The block monitor feeds it logs from a WebSocket subscription and, after a gap, from
+/// `eth_getLogs`. Logs the node reports as removed by a reorg take their event out again.
+#[derive(Clone)]
+pub struct LogIndexer {
+    /// Configuration
+    config: EventIndexerConfig,
+    /// Indexed event types by emitting contract and topic
+    event_types: Arc<HashMap<(Address, H256), EventType>>,
+    /// Storage of indexed events
+    repository: EventRepository,
+}
+
+impl LogIndexer {
+    /// Create a new indexer of the configured contracts' events
+    pub fn new(config: EventIndexerConfig, repository: EventRepository) -> Result<Self> {
+        let mut event_types = HashMap::new();
+        for contract in &config.contracts {
+            let address: Address = contract
+                .address
+                .parse()
+                .context(format!("Invalid address of indexed contract {}", contract.name))?;
+            for signature in &contract.events {
+                let event = parse_event(signature)?;
+                let event_type = EventType {
+                    contract: contract.name.clone(),
+                    signature: event.abi_signature(),
+                    event,
+                };
+                event_types.insert((address, event_type.event.signature()), event_type);
+            }
+        }
+        
+        Ok(Self {
+            config,
+            event_types: Arc::new(event_types),
+            repository,
+        })
+    }
+    
+    /// Whether events are indexed
+    pub fn enabled(&self) -> bool {
+        self.config.enabled && !self.event_types.is_empty()
+    }
+    
+    /// Filter matching the logs of every indexed event type
+    ///
+    /// It may also match an event of one contract emitted by another; `decode` skips those.
+    pub fn filter(&self) -> Filter {
+        let mut addresses: Vec<Address> = self.event_types.keys().map(|(address, _)| *address).collect();
+        let mut topics: Vec<H256> = self.event_types.keys().map(|(_, topic)| *topic).collect();
+        addresses.sort();
+        addresses.dedup();
+        topics.sort();
+        topics.dedup();
+        
+        Filter::new()
+            .address(ValueOrArray::Array(addresses))
+            .topic0(ValueOrArray::Array(topics.into_iter().map(Some).collect()))
+    }
+    
+    /// Decode a mined log of an indexed event type
+    pub fn decode(&self, log: &Log) -> Option<IndexedEvent> {
+        let topic = *log.topics.first()?;
+        let event_type = self.event_types.get(&(log.address, topic))?;
+        let decoded = match event_type.event.parse_log(RawLog {
+            topics: log.topics.clone(),
+            data: log.data.to_vec(),
+        }) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                debug!("Failed to decode {} log of {}: {}", event_type.signature, event_type.contract, e);
+                return None;
+            }
+        };
+        let params: Map<String, Value> = decoded
+            .params
+            .into_iter()
+            .enumerate()
+            .map(|(position, param)| {
+                // Unnamed parameters are keyed by position
+                let name = if param.name.is_empty() { position.to_string() } else { param.name };
+                (name, token_json(&param.value))
+            })
+            .collect();
+        
+        Some(IndexedEvent {
+            block_number: log.block_number?.as_u64(),
+            block_hash: log.block_hash?,
+            tx_hash: log.transaction_hash?,
+            log_index: log.log_index?.as_u64(),
+            contract: event_type.contract.clone(),
+            address: log.address,
+            event_name: event_type.event.name.clone(),
+            signature: event_type.signature.clone(),
+            params: Value::Object(params),
+            indexed_at: Utc::now(),
+        })
+    }
+    
+    /// Store the events of a batch of logs and remove those of logs reorged out, returning
+    /// the events stored
+    pub async fn ingest(&self, logs: &[Log]) -> Result<usize> {
+        let mut events = Vec::new();
+        for log in logs {
+            if log.removed == Some(true) {
+                if let (Some(block_hash), Some(log_index)) = (log.block_hash, log.log_index) {
+                    self.repository.remove(block_hash, log_index.as_u64()).await?;
+                    metrics::counter!("indexed_events_removed_total", 1);
+                }
+                continue;
+            }
+            if let Some(event) = self.decode(log) {
+                metrics::counter!("indexed_events_total", 1, "contract" => event.contract.clone());
+                events.push(event);
+            }
+        }
+        
+        self.repository.record(&events).await?;
+        Ok(events.len())
+    }
+    
+    /// Index the events of a block range from `eth_getLogs`, in ranges providers will serve
+    pub async fn catch_up(&self, blockchain_client: &BlockchainClient, from: u64, to: u64) -> Result<usize> {
+        let filter = self.filter();
+        let mut indexed = 0;
+        let mut start = from;
+        while start <= to {
+            let end = to.min(start + self.config.catch_up_range_blocks - 1);
+            let range = filter.clone().from_block(start).to_block(end);
+            let logs = with_priority(RpcPriority::Bulk, blockchain_client.get_logs(&range))
+                .await
+                .context(format!("Failed to fetch logs of blocks #{}-#{}", start, end))?;
+            indexed += self.ingest(&logs).await?;
+            start = end + 1;
+        }
+        
+        if indexed > 0 {
+            debug!("Indexed {} events of blocks #{}-#{}", indexed, from, to);
+        }
+        Ok(indexed)
+    }
+    
+    /// Highest block with an indexed event
+    pub async fn latest_block(&self) -> Result<Option<u64>> {
+        self.repository.latest_block().await
+    }
+    
+    /// Page through indexed events
+    pub async fn query(&self, query: &EventQuery) -> Result<EventPage> {
+        self.repository.query(query).await
+    }
+}
+
+/// JSON of a decoded parameter: integers as decimal strings, bytes and addresses as hex
+fn token_json(token: &Token) -> Value {
+    match token {
+        Token::Address(address) => Value::String(to_checksum(address, None)),
+        Token::Uint(value) => Value::String(value.to_string()),
+        // Two's complement, negative when the top bit is set
+        Token::Int(value) => Value::String(I256::from_raw(*value).to_string()),
+        Token::Bool(value) => Value::Bool(*value),
+        Token::String(value) => Value::String(value.clone()),
+        Token::Bytes(bytes) | Token::FixedBytes(bytes) => Value::String(format!("0x{}", hex::encode(bytes))),
+        Token::Array(items) | Token::FixedArray(items) | Token::Tuple(items) => {
+            Value::Array(items.iter().map(token_json).collect())
+        }
+    }
+}
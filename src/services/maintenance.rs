@@ -0,0 +1,268 @@
+use anyhow::{Context, Result};
+use ethers::types::U256;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    config::{BlockchainConfig, MaintenanceConfig},
+    database::{DbHealth, DbPool},
+    services::{
+        drain::DrainController, relay::RelayService, staking_ledger::SLOTS_PER_EPOCH,
+        transaction::TransactionService,
+    },
+};
+
+/// Beacon API path for the proposers of an epoch's slots
+const PROPOSER_DUTIES_PATH: &str = "/eth/v1/validator/duties/proposer";
+
+/// Proposer duties as the beacon API returns them; numbers are decimal strings
+#[derive(Debug, Deserialize)]
+struct ProposerDutiesResponse {
+    data: Vec<BeaconProposerDuty>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BeaconProposerDuty {
+    pubkey: String,
+    slot: String,
+}
+
+/// Proposers of upcoming slots according to the beacon node
+#[derive(Debug, Default)]
+struct Lookahead {
+    /// Lowercase proposer public key by slot
+    proposers: BTreeMap<u64, String>,
+    /// Epochs whose duties were fetched
+    epochs: BTreeSet<u64>,
+}
+
+/// Holds heavy background jobs until the upcoming slots are ones we have little reason to win
+///
+/// A slot is quiet when none of our validators proposes it and either no relay knows its
+/// proposer, so there is no auction to bid in, or the pending value we could extract is below
+/// `max_auction_value`. A slot past the relays' schedule, as before it was first fetched, has no
+/// known proposer yet and counts as an auction. The auction for a slot runs during the slot before, so a window opens
+/// once the next `window_slots` slots are all quiet. Proposers come from the relays' validator
+/// registrations and, with a `beacon_url`, from the beacon node's lookahead of the current and
+/// next epoch.
+#[derive(Clone)]
+pub struct MaintenanceScheduler {
+    /// Maintenance configuration
+    config: MaintenanceConfig,
+    /// Database pool, vacuumed in windows
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Proposers registered at the relays we bid at
+    relay_service: RelayService,
+    /// Pending value an auction would be fought over
+    transaction_service: TransactionService,
+    /// Deferred jobs give up once draining starts
+    drain: DrainController,
+    /// HTTP client for the beacon API
+    http: reqwest::Client,
+    /// Unix time of beacon chain genesis
+    genesis_timestamp: u64,
+    slot_duration: Duration,
+    /// Lowercase public keys of our own validators
+    own_validators: Arc<HashSet<String>>,
+    /// Beacon proposer lookahead
+    lookahead: Arc<Mutex<Lookahead>>,
+}
+
+impl MaintenanceScheduler {
+    /// Create a new maintenance scheduler
+    pub fn new(
+        config: MaintenanceConfig,
+        blockchain: &BlockchainConfig,
+        db_pool: DbPool,
+        health: DbHealth,
+        relay_service: RelayService,
+        transaction_service: TransactionService,
+        drain: DrainController,
+    ) -> Result<Self> {
+        let http = reqwest::Client::builder().timeout(Duration::from_secs(10)).build()?;
+        let own_validators = config
+            .own_validator_pubkeys
+            .iter()
+            .map(|pubkey| pubkey.to_lowercase())
+            .collect();
+        
+        Ok(Self {
+            config,
+            db_pool,
+            health,
+            relay_service,
+            transaction_service,
+            drain,
+            http,
+            genesis_timestamp: blockchain.genesis_timestamp,
+            slot_duration: Duration::from_secs(blockchain.slot_duration_seconds.max(1)),
+            own_validators: Arc::new(own_validators),
+            lookahead: Arc::new(Mutex::new(Lookahead::default())),
+        })
+    }
+    
+    /// Whether maintenance jobs wait for quiet windows
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Whether the beacon lookahead is fetched
+    pub fn uses_beacon(&self) -> bool {
+        self.config.beacon_url.is_some()
+    }
+    
+    /// Whether tables are vacuumed in windows
+    pub fn vacuums(&self) -> bool {
+        !self.config.vacuum_tables.is_empty()
+    }
+    
+    /// Fetch the beacon proposer duties of the current and next epoch, if not held yet
+    pub async fn refresh_lookahead(&self) -> Result<()> {
+        let beacon_url = match &self.config.beacon_url {
+            Some(beacon_url) => beacon_url.trim_end_matches('/'),
+            None => return Ok(()),
+        };
+        
+        let slot = self.current_slot();
+        let epoch = slot / SLOTS_PER_EPOCH;
+        {
+            let mut lookahead = self.lookahead.lock();
+            lookahead.proposers.retain(|duty_slot, _| *duty_slot >= slot);
+            lookahead.epochs.retain(|fetched| *fetched >= epoch);
+        }
+        
+        for epoch in [epoch, epoch + 1] {
+            if self.lookahead.lock().epochs.contains(&epoch) {
+                continue;
+            }
+            
+            let url = format!("{}{}/{}", beacon_url, PROPOSER_DUTIES_PATH, epoch);
+            let response: ProposerDutiesResponse = self
+                .http
+                .get(&url)
+                .send()
+                .await
+                .context("Beacon proposer duties request failed")?
+                .error_for_status()
+                .context("Beacon node rejected the proposer duties request")?
+                .json()
+                .await
+                .context("Invalid beacon proposer duties")?;
+            
+            let mut lookahead = self.lookahead.lock();
+            for duty in response.data {
+                let slot: u64 = duty.slot.parse().context("Invalid slot in proposer duties")?;
+                lookahead.proposers.insert(slot, duty.pubkey.to_lowercase());
+            }
+            lookahead.epochs.insert(epoch);
+            debug!("Fetched beacon proposer duties of epoch {}", epoch);
+        }
+        
+        Ok(())
+    }
+    
+    /// Wait for a quiet window, returning whether one opened within the wait
+    ///
+    /// Gives up early once draining starts, so a deferred job never holds up shutdown.
+    pub async fn wait_for_window(&self, job: &'static str, max_wait: Duration) -> bool {
+        let started = Instant::now();
+        let max_wait = max_wait.min(Duration::from_secs(self.config.max_deferral_seconds));
+        
+        loop {
+            if self.drain.is_draining() {
+                return false;
+            }
+            if self.window_open().await {
+                metrics::histogram!("maintenance_deferral_seconds", started.elapsed().as_secs_f64(), "job" => job);
+                return true;
+            }
+            
+            let next_slot = self.until_next_slot();
+            if started.elapsed() + next_slot >= max_wait {
+                warn!("No quiet window for maintenance job {} within {:?}, skipping this run", job, max_wait);
+                metrics::counter!("maintenance_runs_skipped_total", 1, "job" => job);
+                return false;
+            }
+            tokio::time::sleep(next_slot).await;
+        }
+    }
+    
+    /// Vacuum and analyze the configured tables
+    pub async fn vacuum(&self) -> Result<()> {
+        for table in &self.config.vacuum_tables {
+            let started = Instant::now();
+            self.health.retry(|| self.vacuum_table(table)).await?;
+            info!("Vacuumed {} in {:?}", table, started.elapsed());
+        }
+        
+        Ok(())
+    }
+    
+    /// Vacuum and analyze one table
+    async fn vacuum_table(&self, table: &str) -> Result<()> {
+        // VACUUM refuses to run in a transaction block, so it goes over the simple query protocol
+        // and can't bind the table; config only admits plain names, which are quoted regardless
+        let quoted = format!("\"{}\"", table.replace('"', "\"\""));
+        sqlx::Executor::execute(&self.db_pool, format!("VACUUM (ANALYZE) {}", quoted).as_str())
+            .await
+            .context(format!("Failed to vacuum {}", table))?;
+        Ok(())
+    }
+    
+    /// Whether the next `window_slots` slots are all quiet
+    async fn window_open(&self) -> bool {
+        let pending_value = self
+            .transaction_service
+            .inclusion_candidates()
+            .await
+            .iter()
+            .fold(U256::zero(), |total, candidate| total.saturating_add(candidate.profit));
+        let contested = pending_value > self.config.max_auction_value.wei();
+        
+        let slot = self.current_slot();
+        let open = (slot + 1..=slot + self.config.window_slots).all(|slot| self.is_quiet(slot, contested));
+        metrics::gauge!("maintenance_window_open", if open { 1.0 } else { 0.0 });
+        open
+    }
+    
+    fn is_quiet(&self, slot: u64, contested: bool) -> bool {
+        let relay_duty = self.relay_service.proposer_duty(slot);
+        let proposer = relay_duty
+            .as_ref()
+            .map(|duty| duty.pubkey.to_lowercase())
+            .or_else(|| self.lookahead.lock().proposers.get(&slot).cloned());
+        
+        if proposer.map_or(false, |pubkey| self.own_validators.contains(&pubkey)) {
+            return false;
+        }
+        // Without a registered proposer there is no auction for us to lose, once the schedule
+        // reaches the slot; until then its proposer may still turn out to be registered
+        let scheduled = self.relay_service.duties_through().map_or(false, |through| slot <= through);
+        if relay_duty.is_none() && scheduled {
+            return true;
+        }
+        !contested
+    }
+    
+    fn unix_now(&self) -> Duration {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+    }
+    
+    fn current_slot(&self) -> u64 {
+        let elapsed = self.unix_now().as_secs().saturating_sub(self.genesis_timestamp);
+        elapsed / self.slot_duration.as_secs()
+    }
+    
+    fn until_next_slot(&self) -> Duration {
+        let next_start = self.genesis_timestamp + (self.current_slot() + 1) * self.slot_duration.as_secs();
+        Duration::from_secs(next_start).saturating_sub(self.unix_now())
+    }
+}
@@ -0,0 +1,570 @@
+use chrono::{DateTime, Utc};
+use ethers::{
+    types::{Address, Transaction, H256, U256},
+    utils::id,
+};
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    api::models,
+    database::RedisPool,
+    services::{
+        labels::{LabelCategory, TxLabels},
+        transaction::TxSource,
+    },
+};
+
+/// Pending transactions retained in the view before the oldest are evicted
+const MAX_TRACKED_TXS: usize = 50_000;
+
+/// Arrivals counted in the rolling window
+const ARRIVAL_WINDOW_SECONDS: i64 = 60;
+
+/// Recent blocks the base fee trajectory is measured over
+const BASE_FEE_WINDOW: usize = 10;
+
+/// Largest base fee change per block allowed by EIP-1559
+const MAX_BASE_FEE_CHANGE: f64 = 0.125;
+
+/// Redis sorted set of pending transaction hashes scored by simulated profit
+const SCORES_KEY: &str = "mempool:scores";
+
+/// Lifetime of the score set, refreshed on every publish, so it empties if we stop
+const SCORES_TTL_SECONDS: usize = 300;
+
+/// Percentiles reported in the fee distribution
+const FEE_PERCENTILES: &[usize] = &[10, 25, 50, 75, 90];
+
+/// Lending liquidations, Aave's and Compound v3's absorb and collateral purchase
+const LIQUIDATION_SIGNATURES: &[&str] = &[
+    "liquidationCall(address,address,address,uint256,bool)",
+    "absorb(address,address[])",
+    "buyCollateral(address,uint256,uint256,address)",
+];
+
+/// Well-known function selectors shown by name
+const KNOWN_METHODS: &[([u8; 4], &str)] = &[
+    ([0xa9, 0x05, 0x9c, 0xbb], "transfer"),
+    ([0x23, 0xb8, 0x72, 0xdd], "transferFrom"),
+    ([0x09, 0x5e, 0xa7, 0xb3], "approve"),
+    ([0xd0, 0xe3, 0x0d, 0xb0], "deposit"),
+    ([0x2e, 0x1a, 0x7d, 0x4d], "withdraw"),
+    ([0x38, 0xed, 0x17, 0x39], "swapExactTokensForTokens"),
+    ([0x88, 0x03, 0xdb, 0xee], "swapTokensForExactTokens"),
+    ([0x7f, 0xf3, 0x6a, 0xb5], "swapExactETHForTokens"),
+    ([0x18, 0xcb, 0xaf, 0xe5], "swapExactTokensForETH"),
+    ([0x41, 0x4b, 0xf3, 0x89], "exactInputSingle"),
+    ([0xc0, 0x4b, 0x8d, 0x59], "exactInput"),
+    ([0xdb, 0x3e, 0x21, 0x98], "exactOutputSingle"),
+    ([0x5a, 0xe4, 0x01, 0xdc], "multicall"),
+    ([0xac, 0x96, 0x50, 0xd8], "multicall"),
+    ([0x35, 0x93, 0x56, 0x4c], "execute"),
+];
+
+/// Name of the called method, the raw selector when unknown
+///
+/// Names are shared per selector, so repeated calls to the same method don't allocate.
+pub fn decode_method(input: &[u8]) -> Arc<str> {
+    static METHODS: OnceLock<DashMap<[u8; 4], Arc<str>>> = OnceLock::new();
+    static TRANSFER_ETH: OnceLock<Arc<str>> = OnceLock::new();
+    static UNKNOWN: OnceLock<Arc<str>> = OnceLock::new();
+    
+    if input.is_empty() {
+        return TRANSFER_ETH.get_or_init(|| Arc::from("transfer_eth")).clone();
+    }
+    if input.len() < 4 {
+        return UNKNOWN.get_or_init(|| Arc::from("unknown")).clone();
+    }
+    
+    let selector = [input[0], input[1], input[2], input[3]];
+    let methods = METHODS.get_or_init(DashMap::new);
+    if let Some(name) = methods.get(&selector) {
+        return name.clone();
+    }
+    
+    // Unknown selectors are unbounded, so only well-known names are kept
+    match KNOWN_METHODS.iter().find(|(known, _)| *known == selector) {
+        Some((_, name)) => methods.entry(selector).or_insert_with(|| Arc::from(*name)).clone(),
+        None => Arc::from(format!("0x{}", hex::encode(selector))),
+    }
+}
+
+/// Low-cardinality kind of a transaction from its shape and called method, e.g. `swap`
+pub fn classify(tx: &Transaction) -> &'static str {
+    static LIQUIDATIONS: OnceLock<Vec<[u8; 4]>> = OnceLock::new();
+    
+    if tx.to.is_none() {
+        return "contract_deployment";
+    }
+    let liquidations = LIQUIDATIONS.get_or_init(|| LIQUIDATION_SIGNATURES.iter().map(id).collect());
+    if tx.input.len() >= 4 && liquidations.iter().any(|selector| tx.input[..4] == selector[..]) {
+        return "liquidation";
+    }
+    
+    match &*decode_method(&tx.input) {
+        "transfer_eth" => "eth_transfer",
+        "transfer" | "transferFrom" => "token_transfer",
+        "approve" => "token_approval",
+        "deposit" | "withdraw" => "wrap",
+        "multicall" | "execute" => "router",
+        name if name.starts_with("swap") || name.starts_with("exact") => "swap",
+        _ => "contract_call",
+    }
+}
+
+/// Whether a transaction replaced, or was replaced by, another with the same sender and nonce
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", content = "tx_hash", rename_all = "snake_case")]
+pub enum ReplacementStatus {
+    Original,
+    /// Replaced an earlier transaction
+    Replacement(H256),
+    /// Superseded by a later transaction
+    Replaced(H256),
+}
+
+/// A pending transaction as the builder sees it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingTxView {
+    pub hash: H256,
+    #[serde(with = "models::checksum")]
+    pub from: Address,
+    #[serde(with = "models::checksum_opt")]
+    pub to: Option<Address>,
+    #[serde(with = "models::wei")]
+    pub nonce: U256,
+    #[serde(with = "models::wei")]
+    pub value: U256,
+    #[serde(with = "models::wei")]
+    pub gas: U256,
+    #[serde(with = "models::wei_opt")]
+    pub max_fee_per_gas: Option<U256>,
+    #[serde(with = "models::wei_opt")]
+    pub max_priority_fee_per_gas: Option<U256>,
+    #[serde(with = "models::wei_opt")]
+    pub gas_price: Option<U256>,
+    /// EIP-2718 transaction type
+    pub tx_type: u64,
+    /// Encoded size in bytes
+    pub size: usize,
+    /// Decoded method name or raw selector
+    pub method: Arc<str>,
+    /// Simulated profit in wei, once simulated
+    #[serde(with = "models::wei_opt")]
+    pub profit: Option<U256>,
+    pub first_seen: DateTime<Utc>,
+    pub source: TxSource,
+    pub replacement: ReplacementStatus,
+    /// Known entities sending or receiving the transaction
+    pub labels: TxLabels,
+}
+
+/// Filters and pagination over the pending view, newest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PendingTxFilter {
+    /// Source kind, e.g. `public_mempool`
+    pub source: Option<String>,
+    pub method: Option<String>,
+    /// Only transactions simulated at or above this profit
+    pub min_profit: Option<U256>,
+    /// Replacement status: `original`, `replacement` or `replaced`
+    pub replacement: Option<String>,
+    /// Only transactions from or to an entity of this kind
+    pub label: Option<LabelCategory>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl PendingTxFilter {
+    fn matches(&self, tx: &PendingTxView) -> bool {
+        let replacement = match tx.replacement {
+            ReplacementStatus::Original => "original",
+            ReplacementStatus::Replacement(_) => "replacement",
+            ReplacementStatus::Replaced(_) => "replaced",
+        };
+        
+        self.source.as_deref().map_or(true, |s| s == tx.source.kind())
+            && self.method.as_deref().map_or(true, |m| m == &*tx.method)
+            && self
+                .min_profit
+                .map_or(true, |min| tx.profit.map_or(false, |profit| profit >= min))
+            && self.replacement.as_deref().map_or(true, |r| r == replacement)
+            && self.label.map_or(true, |category| tx.labels.has(category))
+    }
+}
+
+/// A page of the pending view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingTxPage {
+    /// Transactions matching the filter
+    pub total: usize,
+    pub transactions: Vec<PendingTxView>,
+}
+
+/// Fee and fullness of a recent block
+#[derive(Debug, Clone, Copy)]
+struct BlockFees {
+    base_fee: U256,
+    gas_used: U256,
+    gas_limit: U256,
+}
+
+/// Rolling mempool statistics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MempoolStats {
+    /// Pending transactions by type (`legacy`, `access_list`, `eip1559`, `blob`)
+    pub pending_by_type: HashMap<String, usize>,
+    /// Transactions first seen in the last minute, by type
+    pub arrivals_last_minute: HashMap<String, usize>,
+    pub pending_bytes: usize,
+    #[serde(with = "models::wei")]
+    pub pending_gas: U256,
+    /// Tip each pending transaction would pay at the latest base fee, by percentile
+    #[serde(with = "models::wei_map")]
+    pub effective_tip_percentiles_wei: HashMap<String, U256>,
+    #[serde(with = "models::wei_opt")]
+    pub latest_base_fee_wei: Option<U256>,
+    /// Mean per-block base fee change over recent blocks, from -0.125 to 0.125
+    pub base_fee_trend: f64,
+    /// Pending gas relative to a block's gas target
+    pub pending_blocks: f64,
+    /// 0 (idle) to 1 (congested), blending pending gas pressure and the base fee trend
+    pub congestion_score: f64,
+    pub refreshed_at: Option<DateTime<Utc>>,
+}
+
+/// Name of an EIP-2718 transaction type
+fn type_name(tx_type: u64) -> String {
+    match tx_type {
+        0 => "legacy".to_string(),
+        1 => "access_list".to_string(),
+        2 => "eip1559".to_string(),
+        3 => "blob".to_string(),
+        other => format!("type_{}", other),
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    txs: HashMap<H256, PendingTxView>,
+    /// Latest transaction per sender and nonce, for replacement detection
+    by_nonce: HashMap<(Address, U256), H256>,
+    /// Insertion order, for eviction; may hold hashes already removed
+    order: VecDeque<H256>,
+    /// First-seen time and type of recent arrivals
+    arrivals: VecDeque<(DateTime<Utc>, u64)>,
+    /// Most recent blocks, newest last
+    blocks: VecDeque<BlockFees>,
+    /// Latest statistics snapshot
+    stats: MempoolStats,
+    /// Profit scores changed since the last publish, `None` once the transaction is gone
+    score_changes: HashMap<H256, Option<U256>>,
+}
+
+/// Live enriched view of pending transactions, excluding sensitive flow
+#[derive(Clone, Default)]
+pub struct MempoolView {
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl MempoolView {
+    /// Create an empty view
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Track a newly seen pending transaction
+    pub fn insert(&self, tx: &Transaction, source: TxSource, labels: TxLabels) {
+        let mut inner = self.inner.write();
+        if inner.txs.contains_key(&tx.hash) {
+            return;
+        }
+        
+        let mut replacement = ReplacementStatus::Original;
+        if let Some(previous) = inner.by_nonce.insert((tx.from, tx.nonce), tx.hash) {
+            if let Some(previous_tx) = inner.txs.get_mut(&previous) {
+                previous_tx.replacement = ReplacementStatus::Replaced(tx.hash);
+            }
+            replacement = ReplacementStatus::Replacement(previous);
+        }
+        
+        inner.txs.insert(
+            tx.hash,
+            PendingTxView {
+                hash: tx.hash,
+                from: tx.from,
+                to: tx.to,
+                nonce: tx.nonce,
+                value: tx.value,
+                gas: tx.gas,
+                max_fee_per_gas: tx.max_fee_per_gas,
+                max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+                gas_price: tx.gas_price,
+                tx_type: tx.transaction_type.map(|t| t.as_u64()).unwrap_or_default(),
+                size: tx.rlp().len(),
+                method: decode_method(&tx.input),
+                profit: None,
+                first_seen: Utc::now(),
+                source,
+                replacement,
+                labels,
+            },
+        );
+        inner.order.push_back(tx.hash);
+        inner.arrivals.push_back((Utc::now(), tx.transaction_type.map(|t| t.as_u64()).unwrap_or_default()));
+        
+        while inner.txs.len() > MAX_TRACKED_TXS {
+            match inner.order.pop_front() {
+                Some(oldest) => inner.remove(&oldest),
+                None => break,
+            }
+        }
+        
+        // Drop hashes of confirmed transactions once they dominate the queue
+        if inner.order.len() > 2 * MAX_TRACKED_TXS {
+            let Inner { txs, order, .. } = &mut *inner;
+            order.retain(|hash| txs.contains_key(hash));
+        }
+    }
+    
+    /// Record the simulated profit of a tracked transaction
+    pub fn set_profit(&self, tx_hash: H256, profit: U256) {
+        let mut inner = self.inner.write();
+        if let Some(tx) = inner.txs.get_mut(&tx_hash) {
+            tx.profit = Some(profit);
+            inner.score_changes.insert(tx_hash, Some(profit));
+        }
+    }
+    
+    /// Mirror changed profit scores into Redis in one pipelined round trip
+    ///
+    /// Scores are a non-critical cache: while Redis is degraded the batch is dropped and the
+    /// set catches up with later changes.
+    pub async fn publish_scores(&self, redis: &RedisPool) {
+        let changes = std::mem::take(&mut self.inner.write().score_changes);
+        if changes.is_empty() {
+            return;
+        }
+        
+        let mut pipe = redis::pipe();
+        for (tx_hash, profit) in changes {
+            let member = format!("{:?}", tx_hash);
+            match profit {
+                Some(profit) => pipe.zadd(SCORES_KEY, member, to_f64(profit)).ignore(),
+                None => pipe.zrem(SCORES_KEY, member).ignore(),
+            };
+        }
+        pipe.expire(SCORES_KEY, SCORES_TTL_SECONDS).ignore();
+        redis.cached_pipeline::<()>(&pipe).await;
+    }
+    
+    /// Stop tracking a transaction once it is confirmed
+    pub fn remove(&self, tx_hash: &H256) {
+        self.inner.write().remove(tx_hash);
+    }
+    
+    /// Record a new block's base fee and fullness for the congestion score
+    pub fn record_block(&self, base_fee: U256, gas_used: U256, gas_limit: U256) {
+        let mut inner = self.inner.write();
+        inner.blocks.push_back(BlockFees {
+            base_fee,
+            gas_used,
+            gas_limit,
+        });
+        while inner.blocks.len() > BASE_FEE_WINDOW {
+            inner.blocks.pop_front();
+        }
+    }
+    
+    /// Latest statistics snapshot
+    pub fn stats(&self) -> MempoolStats {
+        self.inner.read().stats.clone()
+    }
+    
+    /// Recompute the statistics snapshot and emit it as metrics
+    pub fn refresh_stats(&self) -> MempoolStats {
+        let mut inner = self.inner.write();
+        
+        let cutoff = Utc::now() - chrono::Duration::seconds(ARRIVAL_WINDOW_SECONDS);
+        while inner.arrivals.front().map_or(false, |(seen, _)| *seen < cutoff) {
+            inner.arrivals.pop_front();
+        }
+        
+        let mut stats = MempoolStats {
+            latest_base_fee_wei: inner.blocks.back().map(|b| b.base_fee),
+            refreshed_at: Some(Utc::now()),
+            ..Default::default()
+        };
+        
+        for (_, tx_type) in &inner.arrivals {
+            *stats.arrivals_last_minute.entry(type_name(*tx_type)).or_default() += 1;
+        }
+        
+        let base_fee = stats.latest_base_fee_wei.unwrap_or_default();
+        let mut tips = Vec::with_capacity(inner.txs.len());
+        for tx in inner.txs.values() {
+            // Replaced transactions can no longer be included
+            if matches!(tx.replacement, ReplacementStatus::Replaced(_)) {
+                continue;
+            }
+            
+            *stats.pending_by_type.entry(type_name(tx.tx_type)).or_default() += 1;
+            stats.pending_bytes += tx.size;
+            stats.pending_gas = stats.pending_gas.saturating_add(tx.gas);
+            
+            let tip = match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+                (Some(max_fee), Some(priority)) => priority.min(max_fee.saturating_sub(base_fee)),
+                _ => tx.gas_price.unwrap_or_default().saturating_sub(base_fee),
+            };
+            tips.push(tip);
+        }
+        
+        tips.sort();
+        if !tips.is_empty() {
+            for percentile in FEE_PERCENTILES {
+                let index = (tips.len() - 1) * percentile / 100;
+                stats
+                    .effective_tip_percentiles_wei
+                    .insert(format!("p{}", percentile), tips[index]);
+            }
+        }
+        
+        // Base fee moves at most 12.5% per block, so the trend is normalised against that
+        let changes: Vec<f64> = inner
+            .blocks
+            .iter()
+            .zip(inner.blocks.iter().skip(1))
+            .filter(|(previous, _)| !previous.base_fee.is_zero())
+            .map(|(previous, next)| wei_ratio(next.base_fee, previous.base_fee) - 1.0)
+            .collect();
+        if !changes.is_empty() {
+            stats.base_fee_trend = changes.iter().sum::<f64>() / changes.len() as f64;
+        }
+        
+        let gas_target = inner
+            .blocks
+            .back()
+            .map(|b| b.gas_limit / 2)
+            .filter(|target| !target.is_zero());
+        if let Some(target) = gas_target {
+            stats.pending_blocks = wei_ratio(stats.pending_gas, target);
+        }
+        
+        // Pending pressure saturates at four blocks' worth of target gas
+        let pressure = (stats.pending_blocks / 4.0).min(1.0);
+        let trend = ((stats.base_fee_trend / MAX_BASE_FEE_CHANGE + 1.0) / 2.0).clamp(0.0, 1.0);
+        let fullness = inner
+            .blocks
+            .back()
+            .filter(|b| !b.gas_limit.is_zero())
+            .map(|b| wei_ratio(b.gas_used, b.gas_limit))
+            .unwrap_or_default();
+        stats.congestion_score = if inner.blocks.is_empty() {
+            pressure
+        } else {
+            0.4 * pressure + 0.4 * trend + 0.2 * fullness
+        };
+        
+        metrics::gauge!("mempool_pending_transactions", inner.txs.len() as f64);
+        metrics::gauge!("mempool_pending_bytes", stats.pending_bytes as f64);
+        metrics::gauge!("mempool_pending_gas", to_f64(stats.pending_gas));
+        metrics::gauge!("mempool_arrivals_last_minute", inner.arrivals.len() as f64);
+        metrics::gauge!("mempool_base_fee_trend", stats.base_fee_trend);
+        metrics::gauge!("mempool_congestion_score", stats.congestion_score);
+        for (tx_type, count) in &stats.pending_by_type {
+            metrics::gauge!("mempool_pending_by_type", *count as f64, "type" => tx_type.clone());
+        }
+        if let Some(median) = stats.effective_tip_percentiles_wei.get("p50") {
+            metrics::gauge!("mempool_median_effective_tip_gwei", to_f64(*median) / 1e9);
+        }
+        
+        inner.stats = stats.clone();
+        stats
+    }
+    
+    /// A tracked transaction
+    pub fn get(&self, tx_hash: &H256) -> Option<PendingTxView> {
+        self.inner.read().txs.get(tx_hash).cloned()
+    }
+    
+    /// Whether a transaction is tracked
+    pub fn contains(&self, tx_hash: &H256) -> bool {
+        self.inner.read().txs.contains_key(tx_hash)
+    }
+    
+    /// Number of tracked transactions
+    pub fn len(&self) -> usize {
+        self.inner.read().txs.len()
+    }
+    
+    /// Whether no transactions are tracked
+    pub fn is_empty(&self) -> bool {
+        self.inner.read().txs.is_empty()
+    }
+    
+    /// A page of tracked transactions matching the filter, newest first
+    ///
+    /// When `viewer` is set, private flow is hidden except the viewer's own searcher submissions.
+    pub fn query(&self, filter: &PendingTxFilter, viewer: Option<&str>) -> PendingTxPage {
+        let inner = self.inner.read();
+        let mut matching: Vec<&PendingTxView> = inner
+            .txs
+            .values()
+            .filter(|tx| visible_to(tx, viewer) && filter.matches(tx))
+            .collect();
+        matching.sort_by(|a, b| b.first_seen.cmp(&a.first_seen).then_with(|| a.hash.cmp(&b.hash)));
+        
+        PendingTxPage {
+            total: matching.len(),
+            transactions: matching
+                .into_iter()
+                .skip(filter.offset)
+                .take(filter.limit)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+fn visible_to(tx: &PendingTxView, viewer: Option<&str>) -> bool {
+    let viewer = match viewer {
+        Some(viewer) => viewer,
+        None => return true,
+    };
+    
+    match &tx.source {
+        TxSource::PrivateApi => false,
+        TxSource::Searcher(key) => key == viewer,
+        _ => true,
+    }
+}
+
+impl Inner {
+    fn remove(&mut self, tx_hash: &H256) {
+        if let Some(tx) = self.txs.remove(tx_hash) {
+            if tx.profit.is_some() {
+                self.score_changes.insert(*tx_hash, None);
+            }
+            let key = (tx.from, tx.nonce);
+            if self.by_nonce.get(&key) == Some(tx_hash) {
+                self.by_nonce.remove(&key);
+            }
+        }
+    }
+}
+
+/// Ratio of two amounts as a float
+fn wei_ratio(numerator: U256, denominator: U256) -> f64 {
+    to_f64(numerator) / to_f64(denominator)
+}
+
+/// Lossy float conversion that saturates instead of panicking on huge values
+fn to_f64(value: U256) -> f64 {
+    value.min(U256::from(u128::MAX)).as_u128() as f64
+}
@@ -0,0 +1,261 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use ethers::{types::Transaction, utils::rlp};
+use parking_lot::Mutex;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tracing::{info, warn};
+
+use crate::{config::MempoolRecordingConfig, services::transaction::TxSource};
+
+/// Opens every recording file, the last byte is the format version
+const MAGIC: &[u8; 8] = b"MEVREC\x00\x01";
+
+/// Recording files are named by the hour their transactions were received in
+const FILE_PREFIX: &str = "mempool-";
+const FILE_EXTENSION: &str = "rec";
+
+/// A pending transaction as recorded
+#[derive(Debug, Clone)]
+pub struct RecordedTx {
+    pub received_at: DateTime<Utc>,
+    pub source: TxSource,
+    pub tx: Transaction,
+}
+
+/// One encoded frame: receive time in microseconds (i64), source (u16 length, UTF-8) and the
+/// signed transaction (u32 length, typed envelope RLP), little-endian
+fn encode_frame(received_at: DateTime<Utc>, source: &str, raw_tx: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(14 + source.len() + raw_tx.len());
+    frame.extend_from_slice(&received_at.timestamp_micros().to_le_bytes());
+    frame.extend_from_slice(&(source.len() as u16).to_le_bytes());
+    frame.extend_from_slice(source.as_bytes());
+    frame.extend_from_slice(&(raw_tx.len() as u32).to_le_bytes());
+    frame.extend_from_slice(raw_tx);
+    frame
+}
+
+/// Append-only recorder of the pending transactions we observe
+///
+/// Every non-sensitive pending transaction is framed with the time we received it and
+/// appended to an hourly file, the input `replay` feeds back through the monitor. Frames are
+/// buffered in memory and written in batches off the ingestion path; when writes fall behind
+/// the buffer limit, new transactions are dropped from the recording rather than held.
+#[derive(Clone)]
+pub struct MempoolRecorder {
+    /// Frames not yet written, with the hour they belong to
+    buffer: Arc<Mutex<Vec<(String, Vec<u8>)>>>,
+    /// File being appended to and the hour it covers
+    writer: Arc<Mutex<Option<(String, BufWriter<File>)>>>,
+    /// Configuration
+    config: MempoolRecordingConfig,
+}
+
+impl MempoolRecorder {
+    /// Create a new recorder, creating the recording directory
+    pub fn new(config: MempoolRecordingConfig) -> Result<Self> {
+        if config.enabled {
+            fs::create_dir_all(&config.directory)
+                .context(format!("Failed to create recording directory {}", config.directory))?;
+            info!("Recording pending transactions to {}", config.directory);
+        }
+        
+        Ok(Self {
+            buffer: Arc::new(Mutex::new(Vec::new())),
+            writer: Arc::new(Mutex::new(None)),
+            config,
+        })
+    }
+    
+    /// Whether pending transactions are recorded
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Buffer a pending transaction received just now
+    pub fn record(&self, tx: &Transaction, source: &TxSource) {
+        if !self.enabled() {
+            return;
+        }
+        
+        let received_at = Utc::now();
+        let frame = encode_frame(received_at, &source.to_string(), &tx.rlp());
+        let hour = received_at.format("%Y%m%d%H").to_string();
+        
+        let mut buffer = self.buffer.lock();
+        if buffer.len() >= self.config.max_buffered {
+            metrics::counter!("mempool_recording_dropped_total", 1);
+            return;
+        }
+        buffer.push((hour, frame));
+        if buffer.len() >= self.config.batch_size {
+            self.spawn_flush();
+        }
+    }
+    
+    /// Write everything buffered so far
+    pub async fn flush(&self) -> Result<()> {
+        if !self.enabled() {
+            return Ok(());
+        }
+        
+        let recorder = self.clone();
+        tokio::task::spawn_blocking(move || recorder.write())
+            .await
+            .context("Recording writer panicked")?
+    }
+    
+    fn spawn_flush(&self) {
+        let recorder = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = recorder.flush().await {
+                warn!("Failed to flush mempool recording: {}", e);
+            }
+        });
+    }
+    
+    /// Append the buffered frames, taken under the file lock so concurrent flushes keep their order
+    fn write(&self) -> Result<()> {
+        let mut writer = self.writer.lock();
+        let frames = std::mem::take(&mut *self.buffer.lock());
+        if frames.is_empty() {
+            return Ok(());
+        }
+        
+        let count = frames.len();
+        let mut bytes = 0;
+        for (hour, frame) in frames {
+            if writer.as_ref().map_or(true, |(open, _)| *open != hour) {
+                if let Some((_, mut previous)) = writer.take() {
+                    previous.flush().context("Failed to finish recording file")?;
+                }
+                *writer = Some((hour.clone(), self.open(&hour)?));
+            }
+            
+            let (_, file) = writer.as_mut().expect("recording file was just opened");
+            file.write_all(&frame).context("Failed to append to recording")?;
+            bytes += frame.len();
+        }
+        if let Some((_, file)) = writer.as_mut() {
+            file.flush().context("Failed to write recording")?;
+        }
+        
+        metrics::counter!("mempool_recorded_transactions_total", count as u64);
+        metrics::counter!("mempool_recorded_bytes_total", bytes as u64);
+        Ok(())
+    }
+    
+    /// Open the hour's file for appending, starting it with the magic bytes if it is new
+    fn open(&self, hour: &str) -> Result<BufWriter<File>> {
+        let path = Path::new(&self.config.directory).join(format!("{}{}.{}", FILE_PREFIX, hour, FILE_EXTENSION));
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .context(format!("Failed to open recording {}", path.display()))?;
+        
+        let mut writer = BufWriter::new(file);
+        if writer.get_ref().metadata()?.len() == 0 {
+            writer.write_all(MAGIC)?;
+        }
+        Ok(writer)
+    }
+}
+
+/// Recording files at a path, a single file or every recording in a directory, oldest first
+pub fn recording_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    
+    let mut files: Vec<PathBuf> = fs::read_dir(path)
+        .context(format!("Failed to read recordings in {}", path.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|file| {
+            let name = file.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+            name.starts_with(FILE_PREFIX) && file.extension().map_or(false, |ext| ext == FILE_EXTENSION)
+        })
+        .collect();
+    // Hours in the names sort chronologically
+    files.sort();
+    
+    Ok(files)
+}
+
+/// Reads the frames of one recording file in order
+pub struct RecordingReader {
+    reader: BufReader<File>,
+    path: PathBuf,
+}
+
+impl RecordingReader {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path).context(format!("Failed to open recording {}", path.display()))?;
+        let mut reader = BufReader::new(file);
+        
+        let mut magic = [0u8; 8];
+        reader
+            .read_exact(&mut magic)
+            .context(format!("{} is not a mempool recording", path.display()))?;
+        if &magic != MAGIC {
+            return Err(anyhow!("{} is not a mempool recording of a supported version", path.display()));
+        }
+        
+        Ok(Self {
+            reader,
+            path: path.to_path_buf(),
+        })
+    }
+    
+    /// Next recorded transaction, `None` at the end of the file
+    ///
+    /// A frame cut short, as left by a crash mid-write, ends the file.
+    pub fn read_next(&mut self) -> Result<Option<RecordedTx>> {
+        let mut micros = [0u8; 8];
+        match self.reader.read_exact(&mut micros) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e).context(format!("Failed to read {}", self.path.display())),
+        }
+        
+        let (source, raw_tx) = match self.read_rest() {
+            Ok(frame) => frame,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                warn!("Recording {} ends in a partial frame", self.path.display());
+                return Ok(None);
+            }
+            Err(e) => return Err(e).context(format!("Failed to read {}", self.path.display())),
+        };
+        
+        let micros = i64::from_le_bytes(micros);
+        let received_at = Utc
+            .timestamp_opt(micros.div_euclid(1_000_000), (micros.rem_euclid(1_000_000) * 1_000) as u32)
+            .single()
+            .ok_or_else(|| anyhow!("Invalid receive time in {}", self.path.display()))?;
+        let source = String::from_utf8(source)
+            .map_err(|_| anyhow!("Invalid source in {}", self.path.display()))?
+            .parse()?;
+        let tx = rlp::decode::<Transaction>(&raw_tx)
+            .map_err(|e| anyhow!("Invalid transaction in {}: {}", self.path.display(), e))?;
+        
+        Ok(Some(RecordedTx { received_at, source, tx }))
+    }
+    
+    fn read_rest(&mut self) -> std::io::Result<(Vec<u8>, Vec<u8>)> {
+        let mut length = [0u8; 2];
+        self.reader.read_exact(&mut length)?;
+        let mut source = vec![0u8; u16::from_le_bytes(length) as usize];
+        self.reader.read_exact(&mut source)?;
+        
+        let mut length = [0u8; 4];
+        self.reader.read_exact(&mut length)?;
+        let mut raw_tx = vec![0u8; u32::from_le_bytes(length) as usize];
+        self.reader.read_exact(&mut raw_tx)?;
+        
+        Ok((source, raw_tx))
+    }
+}
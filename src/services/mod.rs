@@ -1,26 +1,142 @@
 use anyhow::Result;
-use std::{sync::Arc, time::Instant};
+use std::{
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
 
 use crate::{
-    blockchain::BlockchainClient,
+    blockchain::{
+        fees::FeeEstimator,
+        monitor::bid_for_next_slot,
+        rate_limit::{with_priority, RpcPriority},
+        signer::SignerRegistry,
+        transaction::NonceManager,
+        BlockchainClient,
+    },
     config::Config,
-    database::{DbPool, RedisPool},
+    core::{
+        arbitrage::ArbitrageEngine, liquidation::LiquidationMonitor, opportunities::SandwichDetector,
+        strategy::StrategyManager,
+    },
+    database::{
+        repositories::{BlockBodyRepository, EventRepository, MempoolRepository, TokenRepository},
+        DbHealth, DbPool, RedisPool,
+    },
+    relay::BuilderIdentity,
+    utils::{
+        heartbeat::HeartbeatRegistry,
+        tasks::{BackgroundTasks, Lane, TaskLanes},
+    },
 };
 
+/// How often internal queue depths are sampled into gauges
+const QUEUE_METRICS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the database is probed during an outage and buffered writes replayed
+const DB_RECOVERY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often the liquidation monitor backfills another range of lending logs
+const LIQUIDATION_BACKFILL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub mod alerting;
+pub mod analytics;
+pub mod analytics_export;
 pub mod block_building;
+pub mod build_decisions;
+pub mod bundle;
+pub mod canary;
+pub mod commission;
+pub mod controls;
+pub mod deposits;
+pub mod drain;
+pub mod events;
+pub mod exploit_detection;
+pub mod export;
+pub mod fee_backtest;
+pub mod gas_golf;
+pub mod head_tracker;
+pub mod kpi;
+pub mod labels;
 pub mod transaction;
+pub mod watchdog;
+pub mod webhooks;
 pub mod liquid_staking;
+pub mod log_indexer;
+pub mod maintenance;
+pub mod mempool;
+pub mod mempool_recorder;
+pub mod ordering;
+pub mod permit_deposits;
+pub mod prices;
+pub mod processed_blocks;
+pub mod profits;
+pub mod recovery;
+pub mod relay;
+pub mod relay_scraper;
+pub mod replay;
+pub mod reputation;
+pub mod risk;
+pub mod sealed_bundles;
+pub mod settlement;
+pub mod shadow_build;
 pub mod simulation;
+pub mod simulation_pool;
+pub mod snapshot;
+pub mod staking_ledger;
+pub mod subsidy;
 
+use alerting::{AlertManager, AlertRuleEngine};
+use analytics::AnalyticsSink;
+use analytics_export::AnalyticsExportService;
 use block_building::BlockBuildingService;
+use build_decisions::BuildDecisionLog;
+use bundle::BundleService;
+use canary::CanaryService;
+use commission::CommissionService;
+use controls::SubsystemControls;
+use deposits::DepositReconciler;
+use drain::DrainController;
+use events::EventBus;
+use exploit_detection::ExploitDetector;
+use export::ExportService;
+use fee_backtest::FeeBacktestService;
+use gas_golf::GasGolfer;
+use head_tracker::HeadTracker;
+use kpi::KpiAggregator;
+use labels::LabelRegistry;
+use mempool_recorder::MempoolRecorder;
 use liquid_staking::LiquidStakingService;
-use transaction::TransactionService;
+use log_indexer::LogIndexer;
+use maintenance::MaintenanceScheduler;
+use permit_deposits::PermitDepositService;
+use prices::PriceService;
+use processed_blocks::ProcessedBlocks;
+use profits::ProfitService;
+use recovery::RecoveryService;
+use relay::RelayService;
+use relay_scraper::RelayScraper;
+use replay::ReplayService;
+use reputation::ReputationService;
+use snapshot::SnapshotStore;
+use risk::RiskManager;
+use sealed_bundles::SealedBundleService;
+use settlement::SettlementReconciler;
+use shadow_build::ShadowBuildService;
+use transaction::{PrivateTxSubmitter, TransactionService};
+use watchdog::Watchdog;
+use webhooks::WebhookDeliveryLog;
 use simulation::SimulationService;
+use staking_ledger::StakingLedger;
+use subsidy::SubsidyService;
 
 /// Service context containing all services
 pub struct ServiceContext {
     /// PostgreSQL database pool
     pub db_pool: DbPool,
+    /// Connection-loss retries and buffered writes, reported in readiness
+    pub db_health: DbHealth,
     /// Redis connection manager
     pub redis: RedisPool,
     /// Blockchain client
@@ -29,6 +145,12 @@ pub struct ServiceContext {
     pub config: Config,
     /// Application start time
     pub start_time: Instant,
+    /// EIP-1559 fee estimation from recent blocks
+    pub fee_estimator: FeeEstimator,
+    /// Nonces of the accounts we send transactions from
+    pub nonce_manager: NonceManager,
+    /// Named accounts we sign transactions with
+    pub signers: SignerRegistry,
     /// Transaction service
     pub transaction_service: TransactionService,
     /// Block building service
@@ -37,6 +159,106 @@ pub struct ServiceContext {
     pub liquid_staking_service: LiquidStakingService,
     /// Simulation service
     pub simulation_service: SimulationService,
+    /// Token prices aggregated from Chainlink, Uniswap V3 TWAPs and off-chain sources
+    pub price_service: PriceService,
+    /// Bundle assembly and `eth_sendBundle` submission
+    pub bundle_service: BundleService,
+    /// Sandwich opportunities around pending swaps, offered to the block builder
+    pub sandwich_detector: SandwichDetector,
+    /// Cross-DEX arbitrage cycles over tracked pools
+    pub arbitrage_engine: ArbitrageEngine,
+    /// Health of large Aave and Compound borrowers, and liquidations when they go underwater
+    pub liquidation_monitor: LiquidationMonitor,
+    /// Strategies driven by pending transactions and new heads, toggled at runtime
+    pub strategy_manager: StrategyManager,
+    /// Bid submission to relays
+    pub relay_service: RelayService,
+    /// Per-slot record of templates built and bids submitted, for audits
+    pub build_decisions: BuildDecisionLog,
+    /// Subsidy decisions and budget tracking for strategic slots
+    pub subsidy_service: SubsidyService,
+    /// Quiet-slot windows heavy background jobs wait for
+    pub maintenance: MaintenanceScheduler,
+    /// Relay data API scraper for market intelligence
+    pub relay_scraper: RelayScraper,
+    /// End-of-slot reconciliation of bids, chain and P&L
+    pub settlement_reconciler: SettlementReconciler,
+    /// Our ordering replayed over the blocks that won slots we lost
+    pub shadow_build_service: ShadowBuildService,
+    /// Crash-recovery service for in-flight state
+    pub recovery_service: RecoveryService,
+    /// Encrypted bundle intake
+    pub sealed_bundle_service: SealedBundleService,
+    /// Searcher track records for simulation priority and load shedding
+    pub reputation_service: ReputationService,
+    /// Slot replay service for debugging
+    pub replay_service: ReplayService,
+    /// Built block export service
+    pub export_service: ExportService,
+    /// Realized profit per landed bundle and strategy, in ETH and USD
+    pub profit_service: ProfitService,
+    /// Per-block processing state, so each confirmed block is booked once
+    pub processed_blocks: ProcessedBlocks,
+    /// Persisted pending transactions behind `/api/mempool`
+    pub mempool_repository: MempoolRepository,
+    /// Compressed bodies of confirmed blocks
+    pub block_bodies: BlockBodyRepository,
+    /// Decoded events of the contracts we follow, behind `/api/events`
+    pub log_indexer: LogIndexer,
+    /// ERC-20 metadata read from chain on first use
+    pub token_repository: TokenRepository,
+    /// Known entities behind addresses, tagging pending transactions and opportunities
+    pub label_registry: LabelRegistry,
+    /// Security alerts on exploits spotted in the public mempool
+    pub exploit_detector: ExploitDetector,
+    /// Optimizes the transactions we originate and records the gas it saves
+    pub gas_golfer: GasGolfer,
+    /// Replayable recording of the pending transactions we observe
+    pub mempool_recorder: MempoolRecorder,
+    /// Hot state saved on shutdown for a warm restart
+    pub snapshot_store: SnapshotStore,
+    /// Stake, reward and withdrawal ledger per account
+    pub staking_ledger: StakingLedger,
+    /// Operator commission ledger and treasury payouts
+    pub commission_service: CommissionService,
+    /// Deposit contract indexer, reconciled against stake positions
+    pub deposit_reconciler: DepositReconciler,
+    /// Token stakes approved by EIP-2612 permit and relayed by our signer
+    pub permit_deposit_service: PermitDepositService,
+    /// Self-transfers checking the pipeline end to end on a testnet or fork
+    pub canary_service: CanaryService,
+    /// Fee policy backtesting over recent history
+    pub fee_backtest_service: FeeBacktestService,
+    /// ClickHouse sink for high-volume event data
+    pub analytics_sink: AnalyticsSink,
+    /// Scheduled analytics partition exporter
+    pub analytics_export_service: AnalyticsExportService,
+    /// Business KPI aggregator
+    pub kpi_aggregator: KpiAggregator,
+    /// Alert fan-out to notification sinks
+    pub alert_manager: AlertManager,
+    /// Delivery attempts of alerts to named webhooks
+    pub webhook_deliveries: WebhookDeliveryLog,
+    /// Rule engine over internal metrics
+    pub alert_rule_engine: Arc<AlertRuleEngine>,
+    /// Per-subsystem liveness heartbeats
+    pub heartbeats: HeartbeatRegistry,
+    /// Stale head detection for the builder
+    pub head_tracker: HeadTracker,
+    /// Watchdog over subsystem heartbeats
+    pub watchdog: Watchdog,
+    /// Runtime pause/resume controls
+    pub controls: SubsystemControls,
+    /// Circuit breaker flipping live submission to a dry run
+    pub risk_manager: RiskManager,
+    /// Drain controller for zero-downtime deploys
+    pub drain_controller: DrainController,
+    /// Fan-out of stream events to API subscribers
+    pub event_bus: EventBus,
+    /// Runtimes separating slot-critical and low-priority work from the rest
+    pub task_lanes: TaskLanes,
+    /// Periodic background jobs
+    pub background_tasks: BackgroundTasks,
 }
 
 impl ServiceContext {
@@ -47,16 +269,140 @@ impl ServiceContext {
         blockchain_client: Arc<BlockchainClient>,
         config: &Config,
     ) -> Result<Self> {
+        if config.execution_mode.is_dry_run() {
+            warn!("Dry run: transactions, bundles and bids are simulated and recorded but never sent");
+        }
+        
+        let drain_controller = DrainController::new();
+        let event_bus = EventBus::new();
+        let task_lanes = TaskLanes::new(&config.services.task_lanes)?;
+        let db_health = DbHealth::new(&config.database);
+        let controls = SubsystemControls::load(db_pool.clone(), db_health.clone()).await?;
+        
+        let webhook_deliveries = WebhookDeliveryLog::new(db_pool.clone(), db_health.clone());
+        let alert_manager =
+            AlertManager::new(&config.alerting, webhook_deliveries.clone(), drain_controller.clone())?;
+        let alert_rule_engine = Arc::new(AlertRuleEngine::new(
+            &config.alerting,
+            alert_manager.clone(),
+        )?);
+        
+        let risk_manager = RiskManager::new(
+            config.risk.clone(),
+            config.execution_mode,
+            controls.clone(),
+            alert_manager.clone(),
+        )?;
+        
+        let heartbeats = HeartbeatRegistry::new();
+        let watchdog = Watchdog::new(
+            heartbeats.clone(),
+            alert_manager.clone(),
+            config.heartbeat.clone(),
+        )?;
+        
+        let head_tracker = HeadTracker::new(&config.blockchain, alert_manager.clone());
+        
+        let analytics_sink = AnalyticsSink::new(config.analytics.clone())?;
+        analytics_sink.ensure_schema().await?;
+        
         // Initialize services
+        let price_service = PriceService::new(&config.services.prices, blockchain_client.clone(), redis.clone())?;
+        
+        let token_repository = TokenRepository::new(
+            db_pool.clone(),
+            db_health.clone(),
+            redis.clone(),
+            blockchain_client.clone(),
+            &config.caches.tokens,
+        );
+        
         let simulation_service = SimulationService::new(
+            db_pool.clone(),
+            db_health.clone(),
             blockchain_client.clone(),
             config.services.tx_ordering.clone(),
+            config.services.block_building.fee_recipient(),
+            price_service.clone(),
+            token_repository.clone(),
         )?;
         
+        let fee_estimator = FeeEstimator::new(blockchain_client.clone(), config.blockchain.fee_history_blocks);
+        let signers = SignerRegistry::load(&config.signers, config.blockchain.chain_id).await?;
+        let nonce_manager = NonceManager::new(
+            blockchain_client.clone(),
+            redis.clone(),
+            Duration::from_secs(config.blockchain.stuck_tx_seconds),
+        );
+        
+        let mempool_repository = MempoolRepository::new(db_pool.clone(), db_health.clone());
+        let block_bodies = BlockBodyRepository::new(
+            db_pool.clone(),
+            db_health.clone(),
+            config.services.block_storage.clone(),
+        );
+        let log_indexer = LogIndexer::new(
+            config.services.event_indexer.clone(),
+            EventRepository::new(db_pool.clone(), db_health.clone()),
+        )?;
+        
+        let label_registry = LabelRegistry::load(db_pool.clone(), db_health.clone()).await?;
+        label_registry.import_lists(&config.services.labels.lists).await?;
+        
+        let exploit_detector = ExploitDetector::new(
+            config.services.exploit_detection.clone(),
+            &config.caches.contract_age,
+            blockchain_client.clone(),
+            head_tracker.clone(),
+            label_registry.clone(),
+            price_service.clone(),
+            alert_manager.clone(),
+        )?;
+        
+        let gas_golfer = GasGolfer::new(
+            db_pool.clone(),
+            db_health.clone(),
+            blockchain_client.clone(),
+            simulation_service.clone(),
+            config.services.gas_golf.clone(),
+        )?;
+        
+        let mempool_recorder = MempoolRecorder::new(config.services.mempool_recording.clone())?;
+        
+        let private_submitter = PrivateTxSubmitter::new(
+            config.services.private_submission.clone(),
+            config.services.bundles.signing_key.as_deref(),
+        )?;
+        
+        let reputation_service = ReputationService::new(
+            db_pool.clone(),
+            db_health.clone(),
+            config.services.searcher_reputation.clone(),
+        )?;
+        reputation_service.load().await?;
+        
         let transaction_service = TransactionService::new(
             db_pool.clone(),
             blockchain_client.clone(),
             simulation_service.clone(),
+            drain_controller.clone(),
+            analytics_sink.clone(),
+            event_bus.clone(),
+            config.services.privacy.clone(),
+            config.caches.confirmed_txs.clone(),
+            fee_estimator.clone(),
+            nonce_manager.clone(),
+            signers.clone(),
+            mempool_repository.clone(),
+            config.services.mempool_persistence.enabled,
+            private_submitter,
+            ordering::strategy(config.services.block_building.ordering_strategy),
+            config.services.block_building.gas_budget(),
+            risk_manager.clone(),
+            label_registry.clone(),
+            gas_golfer.clone(),
+            mempool_recorder.clone(),
+            reputation_service.clone(),
         )?;
         
         let block_building_service = BlockBuildingService::new(
@@ -66,33 +412,723 @@ impl ServiceContext {
             config.services.block_building.clone(),
         )?;
         
+        let sandwich_detector = SandwichDetector::new(
+            &config.services.sandwich,
+            blockchain_client.clone(),
+            head_tracker.clone(),
+        )?;
+        
+        let arbitrage_engine = ArbitrageEngine::new(
+            &config.services.arbitrage,
+            blockchain_client.clone(),
+            price_service.clone(),
+        )?;
+        let liquidation_monitor = LiquidationMonitor::new(
+            &config.services.liquidation,
+            blockchain_client.clone(),
+            price_service.clone(),
+        )?;
+        let snapshot_store = SnapshotStore::new(config.services.snapshots.clone())?;
+        if snapshot_store.enabled() {
+            match blockchain_client.get_block_number().await {
+                Ok(head) => snapshot_store.restore(head, &fee_estimator, &arbitrage_engine, &liquidation_monitor),
+                Err(e) => warn!("Not restoring hot state, failed to read the head: {}", e),
+            }
+        }
+        let strategy_manager = StrategyManager::with_builtin(
+            &config.services.strategies,
+            label_registry.clone(),
+            controls.clone(),
+            &sandwich_detector,
+            &arbitrage_engine,
+            &liquidation_monitor,
+        )?;
+        
         let liquid_staking_service = LiquidStakingService::new(
             db_pool.clone(),
             blockchain_client.clone(),
             config.services.liquid_staking.clone(),
         )?;
         
+        let subsidy_service = SubsidyService::new(db_pool.clone(), db_health.clone(), config.subsidy.clone())?;
+        subsidy_service.refresh_spend().await?;
+        
+        let build_decisions = BuildDecisionLog::new(db_pool.clone(), db_health.clone());
+        let relay_service = RelayService::new(
+            db_pool.clone(),
+            db_health.clone(),
+            &config.blockchain,
+            &config.relays,
+            config.relay_backoff.clone(),
+            head_tracker.clone(),
+            subsidy_service.clone(),
+            BuilderIdentity::new(&config.services.block_building),
+            risk_manager.clone(),
+            build_decisions.clone(),
+            task_lanes.clone(),
+        )?;
+        
+        let maintenance = MaintenanceScheduler::new(
+            config.services.maintenance.clone(),
+            &config.blockchain,
+            db_pool.clone(),
+            db_health.clone(),
+            relay_service.clone(),
+            transaction_service.clone(),
+            drain_controller.clone(),
+        )?;
+        
+        let relay_scraper = RelayScraper::new(
+            db_pool.clone(),
+            db_health.clone(),
+            config.relays.clone(),
+            config.relay_scraper.clone(),
+        )?;
+        
+        let bundle_service = BundleService::new(
+            config.services.bundles.clone(),
+            transaction_service.clone(),
+            drain_controller.clone(),
+            risk_manager.clone(),
+        )?;
+        
+        let settlement_reconciler = SettlementReconciler::new(
+            db_pool.clone(),
+            db_health.clone(),
+            blockchain_client.clone(),
+            alert_manager.clone(),
+            risk_manager.clone(),
+        )?;
+        
+        let shadow_build_service = ShadowBuildService::new(
+            db_pool.clone(),
+            db_health.clone(),
+            blockchain_client.clone(),
+            ordering::strategy(config.services.block_building.ordering_strategy),
+            config.services.shadow_build.clone(),
+            config.services.block_building.fee_recipient(),
+        )?;
+        
+        let sealed_bundle_service = SealedBundleService::new(
+            config.services.sealed_bundles.clone(),
+            transaction_service.clone(),
+            simulation_service.clone(),
+            reputation_service.clone(),
+        )?;
+        
+        let recovery_service = RecoveryService::new(
+            db_pool.clone(),
+            db_health.clone(),
+            blockchain_client.clone(),
+            transaction_service.clone(),
+            config.blockchain.max_block_history,
+        )?;
+        
+        let export_service = ExportService::new(db_pool.clone(), db_health.clone())?;
+        let profit_service = ProfitService::new(db_pool.clone(), db_health.clone(), price_service.clone())?;
+        
+        let processed_blocks = ProcessedBlocks::new(db_pool.clone(), db_health.clone())?;
+        
+        let staking_ledger = StakingLedger::new(
+            db_pool.clone(),
+            db_health.clone(),
+            &config.blockchain,
+            &config.services.liquid_staking,
+        )?;
+        
+        let commission_service = CommissionService::new(
+            db_pool.clone(),
+            db_health.clone(),
+            blockchain_client.clone(),
+            transaction_service.clone(),
+            staking_ledger.clone(),
+            config.services.liquid_staking.commission_payout.clone(),
+        )?;
+        
+        let deposit_reconciler = DepositReconciler::new(
+            db_pool.clone(),
+            db_health.clone(),
+            blockchain_client.clone(),
+            alert_manager.clone(),
+            config.services.liquid_staking.deposit_reconciliation.clone(),
+            config.blockchain.confirmation_blocks,
+        )?;
+        
+        let permit_deposit_service = PermitDepositService::new(
+            blockchain_client.clone(),
+            transaction_service.clone(),
+            signers.clone(),
+            config.services.liquid_staking.token_deposits.clone(),
+        )?;
+        
+        let canary_service = CanaryService::new(
+            config.services.canary.clone(),
+            transaction_service.clone(),
+            signers.clone(),
+            risk_manager.clone(),
+            drain_controller.clone(),
+            alert_manager.clone(),
+        );
+        
+        let fee_backtest_service = FeeBacktestService::new(blockchain_client.clone())?;
+        
+        let analytics_export_service = AnalyticsExportService::new(
+            db_pool.clone(),
+            db_health.clone(),
+            config.export.clone(),
+        )?;
+        
+        let kpi_aggregator = KpiAggregator::new(db_pool.clone(), db_health.clone(), staking_ledger.clone())?;
+        
+        let replay_service = ReplayService::new(
+            blockchain_client.clone(),
+            recovery_service.clone(),
+            build_decisions.clone(),
+            transaction_service.clone(),
+        )?;
+        
         Ok(Self {
             db_pool,
+            db_health,
             redis,
             blockchain_client,
             config: config.clone(),
             start_time: Instant::now(),
+            fee_estimator,
+            nonce_manager,
+            signers,
             transaction_service,
             block_building_service,
             liquid_staking_service,
             simulation_service,
+            price_service,
+            bundle_service,
+            sandwich_detector,
+            arbitrage_engine,
+            liquidation_monitor,
+            strategy_manager,
+            relay_service,
+            build_decisions,
+            subsidy_service,
+            maintenance,
+            relay_scraper,
+            settlement_reconciler,
+            shadow_build_service,
+            recovery_service,
+            sealed_bundle_service,
+            reputation_service,
+            replay_service,
+            export_service,
+            profit_service,
+            processed_blocks,
+            mempool_repository,
+            block_bodies,
+            log_indexer,
+            token_repository,
+            label_registry,
+            exploit_detector,
+            gas_golfer,
+            mempool_recorder,
+            snapshot_store,
+            staking_ledger,
+            commission_service,
+            deposit_reconciler,
+            permit_deposit_service,
+            canary_service,
+            fee_backtest_service,
+            analytics_sink,
+            analytics_export_service,
+            kpi_aggregator,
+            alert_manager,
+            webhook_deliveries,
+            alert_rule_engine,
+            heartbeats: heartbeats.clone(),
+            head_tracker,
+            watchdog,
+            controls,
+            risk_manager,
+            drain_controller,
+            event_bus,
+            background_tasks: BackgroundTasks::new(heartbeats, task_lanes.clone()),
+            task_lanes,
+        })
+    }
+    
+    /// Sample the depth of every internal queue
+    async fn record_queue_metrics(&self) {
+        let (queued, running) = self.simulation_service.queue_depth();
+        metrics::gauge!("simulation_queue_depth", queued as f64);
+        metrics::gauge!("simulations_in_flight", running as f64);
+        
+        let (public, sensitive) = self.transaction_service.candidate_counts().await;
+        metrics::gauge!("inclusion_candidates", public as f64, "flow" => "public");
+        metrics::gauge!("inclusion_candidates", sensitive as f64, "flow" => "sensitive");
+        
+        metrics::gauge!("analytics_outbox_rows", self.analytics_sink.backlog() as f64);
+        
+        let (backlog, subscribers) = self.event_bus.backlog();
+        metrics::gauge!("event_bus_backlog", backlog as f64);
+        metrics::gauge!("event_bus_subscribers", subscribers as f64);
+    }
+    
+    /// Start periodic background jobs
+    pub fn start_background_tasks(self: &Arc<Self>) {
+        if self.bundle_service.enabled() {
+            // Bundles are submitted per block by the monitor, but can be paused like a job
+            self.controls.register("bundle_submitter");
+        }
+        if !self.config.relays.is_empty() {
+            // Likewise bids, built and submitted per block
+            self.controls.register("bidder");
+        }
+        
+        self.spawn_job(
+            "watchdog",
+            Duration::from_secs(self.config.heartbeat.check_interval_seconds),
+            |services| async move { services.watchdog.check().await },
+        );
+        
+        self.spawn_job(
+            "alert_rules",
+            Duration::from_secs(self.config.alerting.evaluation_interval_seconds),
+            |services| async move { services.alert_rule_engine.evaluate().await },
+        );
+        
+        self.spawn_maintenance_job(
+            "kpi_aggregator",
+            Duration::from_secs(self.config.analytics.kpi_interval_seconds),
+            |services| async move {
+                services.kpi_aggregator.aggregate().await?;
+                Ok(())
+            },
+        );
+        
+        self.spawn_job(
+            "mempool_stats",
+            Duration::from_millis(self.config.services.tx_ordering.mempool_stats_interval_ms),
+            |services| async move {
+                services.transaction_service.refresh_mempool_stats();
+                services.transaction_service.publish_mempool_scores(&services.redis).await;
+                Ok(())
+            },
+        );
+        
+        self.spawn_job("db_recovery", DB_RECOVERY_INTERVAL, |services| async move {
+            if !services.db_health.is_healthy() && !services.db_health.probe(&services.db_pool).await {
+                return Ok(());
+            }
+            let replayed = services.db_health.flush(&services.db_pool).await?;
+            if replayed > 0 {
+                info!("Replayed {} database writes buffered during an outage", replayed);
+            }
+            Ok(())
+        });
+        
+        // Backpressure shows in these well before it costs a slot
+        self.spawn_job("queue_metrics", QUEUE_METRICS_INTERVAL, |services| async move {
+            services.record_queue_metrics().await;
+            Ok(())
+        });
+        
+        // Reconcile outbound nonces and re-send transactions that stopped moving
+        self.spawn_job(
+            "nonce_resync",
+            Duration::from_secs(self.config.blockchain.slot_duration_seconds.max(1)),
+            |services| async move {
+                services.transaction_service.replace_stuck_transactions().await?;
+                Ok(())
+            },
+        );
+        
+        // Fold booked staking rewards into the APR series and commission ledger once per epoch
+        self.spawn_job(
+            "staking_apr",
+            Duration::from_secs(self.staking_ledger.epoch_seconds()),
+            |services| async move {
+                services.staking_ledger.refresh_apr().await?;
+                services.commission_service.accrue().await?;
+                Ok(())
+            },
+        );
+        
+        if self.price_service.enabled() {
+            // Refresh well within the TTL so lookups on the hot path always hit the cache
+            self.spawn_job(
+                "price_refresh",
+                Duration::from_secs((self.config.services.prices.cache_ttl_seconds / 2).max(1)),
+                |services| async move { services.price_service.refresh().await },
+            );
+        }
+        
+        if self.commission_service.enabled() {
+            self.spawn_job(
+                "commission_payout",
+                Duration::from_secs(self.config.services.liquid_staking.commission_payout.interval_seconds),
+                |services| async move { services.commission_service.run_payouts().await },
+            );
+        }
+        
+        if self.deposit_reconciler.enabled() {
+            self.spawn_job(
+                "deposit_reconciliation",
+                Duration::from_secs(self.config.services.liquid_staking.deposit_reconciliation.interval_seconds),
+                |services| async move { services.deposit_reconciler.run().await },
+            );
+        }
+        
+        let persistence = &self.config.services.mempool_persistence;
+        if persistence.enabled {
+            let stale_after = Duration::from_secs(persistence.stale_after_seconds);
+            let retention = Duration::from_secs(persistence.retention_hours * 3600);
+            self.spawn_maintenance_job(
+                "mempool_prune",
+                Duration::from_secs(persistence.prune_interval_seconds),
+                move |services| async move {
+                    let (dropped, deleted) = services.mempool_repository.prune(stale_after, retention).await?;
+                    if dropped > 0 || deleted > 0 {
+                        info!("Mempool table: {} dropped as stale, {} old rows deleted", dropped, deleted);
+                    }
+                    Ok(())
+                },
+            );
+        }
+        
+        let state_history = &self.config.services.state_history;
+        let retention = Duration::from_secs(state_history.retention_hours * 3600);
+        self.spawn_maintenance_job(
+            "state_history_prune",
+            Duration::from_secs(state_history.prune_interval_seconds),
+            move |services| async move {
+                let deleted = services.recovery_service.prune_history(retention).await?;
+                if deleted > 0 {
+                    info!("In-flight state history: {} old heads deleted", deleted);
+                }
+                Ok(())
+            },
+        );
+        
+        if self.block_bodies.enabled() {
+            self.spawn_maintenance_job(
+                "block_storage_prune",
+                Duration::from_secs(self.config.services.block_storage.prune_interval_seconds),
+                |services| async move {
+                    let head = match services.head_tracker.head_number() {
+                        Some(head) => head,
+                        None => return Ok(()),
+                    };
+                    let (blocks, blobs) = services.block_bodies.prune(head).await?;
+                    if blocks > 0 || blobs > 0 {
+                        info!("Block storage: {} old blocks and {} unreferenced calldata deleted", blocks, blobs);
+                    }
+                    Ok(())
+                },
+            );
+        }
+        
+        if self.maintenance.enabled() && self.maintenance.uses_beacon() {
+            // Duties of the next epoch are known an epoch ahead, so most runs find nothing to fetch
+            self.spawn_job_on(
+                Lane::Background,
+                "maintenance_lookahead",
+                Duration::from_secs(self.config.blockchain.slot_duration_seconds.max(1)),
+                |services| async move { services.maintenance.refresh_lookahead().await },
+            );
+        }
+        
+        if self.maintenance.vacuums() {
+            self.spawn_maintenance_job(
+                "vacuum",
+                Duration::from_secs(self.config.services.maintenance.vacuum_interval_seconds),
+                |services| async move { services.maintenance.vacuum().await },
+            );
+        }
+        
+        if self.mempool_recorder.enabled() {
+            self.spawn_job(
+                "mempool_recording_flush",
+                Duration::from_millis(self.config.services.mempool_recording.flush_interval_ms),
+                |services| async move { services.mempool_recorder.flush().await },
+            );
+        }
+        
+        if self.analytics_sink.enabled() {
+            self.spawn_job(
+                "analytics_flush",
+                Duration::from_millis(self.config.analytics.flush_interval_ms),
+                |services| async move { services.analytics_sink.flush().await },
+            );
+        }
+        
+        if self.subsidy_service.enabled() {
+            // Roll spend out of the budget window as it ages
+            self.spawn_job(
+                "subsidy_budget",
+                Duration::from_secs(60),
+                |services| async move { services.subsidy_service.refresh_spend().await },
+            );
+        }
+        
+        if !self.config.relays.is_empty() {
+            // Bids are only accepted for slots with a registered validator
+            self.spawn_job(
+                "proposer_duties",
+                Duration::from_secs(self.config.blockchain.slot_duration_seconds),
+                |services| async move { services.relay_service.refresh_proposer_duties().await },
+            );
+        }
+        
+        if self.relay_scraper.enabled() {
+            self.spawn_job_on(
+                Lane::Background,
+                "relay_scraper",
+                Duration::from_secs(self.config.relay_scraper.interval_seconds),
+                |services| async move { services.relay_scraper.scrape().await },
+            );
+            
+            // Slots are reconciled once the scraper has seen their delivered payload
+            self.spawn_job_on(
+                Lane::Background,
+                "settlement_reconciliation",
+                Duration::from_secs(self.config.blockchain.slot_duration_seconds),
+                |services| async move {
+                    let _in_flight = services.drain_controller.track("settlement");
+                    services.settlement_reconciler.reconcile_pending().await
+                },
+            );
+            
+            // Lost slots are shadow-built once reconciled
+            if self.shadow_build_service.enabled() {
+                self.spawn_job_on(
+                    Lane::Background,
+                    "shadow_builds",
+                    Duration::from_secs(self.config.blockchain.slot_duration_seconds),
+                    |services| async move { services.shadow_build_service.score_pending().await },
+                );
+            }
+        }
+        
+        if self.sealed_bundle_service.enabled() {
+            self.spawn_job(
+                "sealing_key_rotation",
+                Duration::from_secs(self.config.services.sealed_bundles.key_rotation_seconds),
+                |services| async move {
+                    services.sealed_bundle_service.rotate_if_due();
+                    Ok(())
+                },
+            );
+        }
+        
+        if self.liquidation_monitor.enabled() {
+            // Borrowers from before startup, a log range per run until the window is covered
+            self.spawn_job_on(
+                Lane::Background,
+                "liquidation_backfill",
+                LIQUIDATION_BACKFILL_INTERVAL,
+                |services| async move { services.liquidation_monitor.backfill().await },
+            );
+        }
+        
+        if self.reputation_service.enabled() {
+            self.spawn_job(
+                "searcher_reputation",
+                Duration::from_secs(self.config.services.searcher_reputation.flush_interval_seconds),
+                |services| async move { services.reputation_service.flush().await },
+            );
+        }
+        
+        if self.canary_service.enabled() {
+            self.spawn_job(
+                "canary",
+                Duration::from_secs(self.config.services.canary.interval_seconds),
+                |services| async move { services.canary_service.run().await },
+            );
+        }
+        
+        if self.analytics_export_service.enabled() {
+            self.spawn_maintenance_job(
+                "analytics_export",
+                Duration::from_secs(self.config.export.interval_seconds),
+                |services| async move {
+                    services.analytics_export_service.export_due_partitions().await?;
+                    Ok(())
+                },
+            );
+        }
+    }
+    
+    /// Spawn a periodic job that is skipped while its subsystem is paused
+    fn spawn_job<F, Fut>(self: &Arc<Self>, name: &'static str, period: Duration, job: F)
+    where
+        F: Fn(Arc<ServiceContext>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.spawn_job_on(Lane::Normal, name, period, job)
+    }
+    
+    /// Spawn a heavy periodic job on the background lane, whose runs wait for a quiet window
+    ///
+    /// A run that finds no window within its period is skipped, so the job still beats its
+    /// heartbeat in time.
+    fn spawn_maintenance_job<F, Fut>(self: &Arc<Self>, name: &'static str, period: Duration, job: F)
+    where
+        F: Fn(Arc<ServiceContext>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.spawn_job_on(Lane::Background, name, period, move |services| {
+            let run = job(services.clone());
+            async move {
+                if services.maintenance.enabled() && !services.maintenance.wait_for_window(name, period).await {
+                    return Ok(());
+                }
+                run.await
+            }
         })
     }
     
+    /// Spawn a periodic job on a lane, skipped while its subsystem is paused
+    fn spawn_job_on<F, Fut>(self: &Arc<Self>, lane: Lane, name: &'static str, period: Duration, job: F)
+    where
+        F: Fn(Arc<ServiceContext>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.controls.register(name);
+        
+        let services = self.clone();
+        self.background_tasks.spawn_periodic_on(lane, name, period, move || {
+            let run = (!services.controls.is_paused(name)).then(|| job(services.clone()));
+            // Background jobs only spend the RPC budget the block path leaves
+            let priority = match lane {
+                Lane::Background => RpcPriority::Bulk,
+                _ => RpcPriority::Normal,
+            };
+            async move {
+                match run {
+                    Some(run) => with_priority(priority, run).await,
+                    None => Ok(()),
+                }
+            }
+        });
+    }
+    
+    /// Drain in-flight work so the process can exit without dropping it
+    ///
+    /// New bundles are refused first, then simulations and tracked work are waited on, the
+    /// next slot is bid once more with what they added to the template, and write buffers are
+    /// flushed before in-flight state is checkpointed. Instances don't elect a leader, each
+    /// builds and bids on its own; the only thing handed over is this process's unfinished
+    /// block claims, released so a peer can book those blocks without waiting out the lease.
+    pub async fn drain(&self) -> Result<()> {
+        self.drain_controller.request();
+        info!("Draining in-flight work");
+        
+        let timeout = Duration::from_secs(self.config.services.drain_timeout_seconds);
+        
+        let deadline = tokio::time::Instant::now() + timeout;
+        
+        // Let in-flight simulations finish before tearing anything down
+        if tokio::time::timeout_at(deadline, self.simulation_service.wait_idle()).await.is_err() {
+            warn!("Timed out after {:?} waiting for in-flight simulations", timeout);
+        }
+        // Sends, settlement runs and webhook deliveries already under way
+        if tokio::time::timeout_at(deadline, self.drain_controller.wait_idle()).await.is_err() {
+            warn!(
+                "Timed out after {:?} with work still in flight: {:?}",
+                timeout,
+                self.drain_controller.in_flight()
+            );
+        }
+        
+        // Bid the template as the finished simulations left it, the last bid this process makes
+        if !self.config.relays.is_empty() && !self.controls.is_paused("bidder") {
+            match self.head_tracker.head() {
+                Some((parent_number, parent_hash)) => {
+                    if let Err(e) = bid_for_next_slot(self, parent_number, parent_hash).await {
+                        warn!("Failed to submit the final bid during drain: {}", e);
+                    }
+                }
+                None => warn!("No head block known, skipping the final bid"),
+            }
+        }
+        
+        // Buffered analytics rows and database writes would otherwise wait for shutdown
+        if let Err(e) = self.analytics_sink.flush().await {
+            warn!("Failed to flush analytics sink during drain: {}", e);
+        }
+        if let Err(e) = self.reputation_service.flush().await {
+            warn!("Failed to flush searcher reputation during drain: {}", e);
+        }
+        if let Err(e) = self.mempool_recorder.flush().await {
+            warn!("Failed to flush mempool recording during drain: {}", e);
+        }
+        if self.db_health.is_healthy() || self.db_health.probe(&self.db_pool).await {
+            match self.db_health.flush(&self.db_pool).await {
+                Ok(0) => {}
+                Ok(replayed) => info!("Replayed {} buffered database writes during drain", replayed),
+                Err(e) => warn!("Failed to replay buffered database writes during drain: {}", e),
+            }
+        }
+        
+        match self.processed_blocks.release_all().await {
+            Ok(0) => {}
+            Ok(released) => info!("Released {} unfinished block claims during drain", released),
+            Err(e) => warn!("Failed to release block claims during drain: {}", e),
+        }
+        
+        // Checkpoint what is left so the next process can pick it up
+        let head_block = match self.blockchain_client.get_block_number().await {
+            Ok(head_block) => Some(head_block),
+            Err(e) => {
+                warn!("Failed to fetch the head block during drain, using the last head seen: {}", e);
+                self.head_tracker.head_number()
+            }
+        };
+        match head_block {
+            Some(head_block) => {
+                if let Err(e) = self.recovery_service.checkpoint(head_block).await {
+                    warn!("Failed to checkpoint in-flight state during drain: {}", e);
+                }
+            }
+            None => warn!("No head block known, skipping the in-flight checkpoint"),
+        }
+        
+        info!("Drain complete");
+        Ok(())
+    }
+    
     /// Gracefully shutdown all services
     pub async fn shutdown(&self) -> Result<()> {
+        // Stop background jobs before the services they depend on
+        self.background_tasks.shutdown().await;
+        
+        if let Err(e) = self.analytics_sink.flush().await {
+            warn!("Failed to flush analytics sink on shutdown: {}", e);
+        }
+        if let Err(e) = self.reputation_service.flush().await {
+            warn!("Failed to flush searcher reputation on shutdown: {}", e);
+        }
+        if let Err(e) = self.mempool_recorder.flush().await {
+            warn!("Failed to flush mempool recording on shutdown: {}", e);
+        }
+        if let Err(e) = self
+            .snapshot_store
+            .save_all(
+                self.head_tracker.head_number(),
+                &self.fee_estimator,
+                &self.arbitrage_engine,
+                &self.liquidation_monitor,
+            )
+            .await
+        {
+            warn!("Failed to snapshot hot state on shutdown: {}", e);
+        }
+        
         // Shutdown services in order
         self.transaction_service.shutdown().await?;
         self.block_building_service.shutdown().await?;
         self.liquid_staking_service.shutdown().await?;
         self.simulation_service.shutdown().await?;
+        self.recovery_service.shutdown().await?;
         
         Ok(())
     }
-} 
\ No newline at end of file
+}
\ No newline at end of file
@@ -0,0 +1,333 @@
+use ethers::types::{Address, Transaction, H256, U256};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{services::transaction::InclusionCandidate, utils::units::wei_to_eth};
+
+/// Gas per knapsack capacity bucket; coarse enough to keep the table small for a full block
+const KNAPSACK_GAS_UNIT: u64 = 5_000;
+
+/// Units considered by the knapsack, best profit per gas first; the rest are filled greedily
+const MAX_KNAPSACK_UNITS: usize = 512;
+
+/// No unit of the group taken at this capacity
+const NOT_TAKEN: u16 = u16::MAX;
+
+/// Which ordering strategy builds block templates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderingStrategyKind {
+    /// Highest effective gas price first, as a vanilla node would
+    #[default]
+    GreedyGasPrice,
+    /// Most total profit within the gas budget, skipping transactions touching the same state
+    Knapsack,
+}
+
+/// Block the template is ordered for
+#[derive(Debug, Clone, Copy)]
+pub struct BlockContext {
+    pub base_fee: U256,
+    /// Gas available to the template
+    pub gas_limit: u64,
+}
+
+/// Picks and orders inclusion candidates for one block
+pub trait OrderingStrategy: Send + Sync {
+    fn name(&self) -> &'static str;
+    
+    /// Candidates to include, in execution order, within the context's gas limit
+    fn order(&self, candidates: Vec<InclusionCandidate>, context: &BlockContext) -> Vec<InclusionCandidate>;
+}
+
+/// Create the configured strategy
+pub fn strategy(kind: OrderingStrategyKind) -> Arc<dyn OrderingStrategy> {
+    match kind {
+        OrderingStrategyKind::GreedyGasPrice => Arc::new(GreedyGasPrice),
+        OrderingStrategyKind::Knapsack => Arc::new(ConflictAwareKnapsack),
+    }
+}
+
+/// Candidates left out of a template that touch state one of its transactions touches
+pub fn conflicts_resolved(considered: &[InclusionCandidate], template: &[InclusionCandidate]) -> usize {
+    let included: HashSet<H256> = template.iter().map(|c| c.tx.hash).collect();
+    let included_keys: Vec<StateKeys> = template.iter().map(|c| StateKeys::of(&c.tx)).collect();
+    
+    considered
+        .iter()
+        .filter(|c| !included.contains(&c.tx.hash))
+        .filter(|c| {
+            let keys = StateKeys::of(&c.tx);
+            included_keys.iter().any(|other| keys.conflicts(other))
+        })
+        .count()
+}
+
+/// Fills the block by effective gas price, highest first
+pub struct GreedyGasPrice;
+
+impl OrderingStrategy for GreedyGasPrice {
+    fn name(&self) -> &'static str {
+        "greedy_gas_price"
+    }
+    
+    fn order(&self, candidates: Vec<InclusionCandidate>, context: &BlockContext) -> Vec<InclusionCandidate> {
+        let mut units = sender_units(candidates, context.base_fee);
+        units.sort_by(by_gas_price);
+        
+        let mut gas_left = context.gas_limit;
+        let mut selected = Vec::new();
+        for unit in units {
+            if unit.gas <= gas_left {
+                gas_left -= unit.gas;
+                selected.push(unit);
+            }
+        }
+        
+        flatten(selected)
+    }
+}
+
+/// Maximizes total profit within the gas budget
+///
+/// Candidates that touch the same state can't all keep their simulated profit, since each was
+/// simulated on top of the parent block alone, so at most one of each conflicting group is taken.
+/// The choice across groups is a multiple-choice knapsack over gas.
+pub struct ConflictAwareKnapsack;
+
+impl OrderingStrategy for ConflictAwareKnapsack {
+    fn name(&self) -> &'static str {
+        "knapsack"
+    }
+    
+    fn order(&self, candidates: Vec<InclusionCandidate>, context: &BlockContext) -> Vec<InclusionCandidate> {
+        let mut units = sender_units(candidates, context.base_fee);
+        units.sort_by(|a, b| {
+            profit_density(b)
+                .partial_cmp(&profit_density(a))
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.first_hash().cmp(&b.first_hash()))
+        });
+        let overflow = units.split_off(units.len().min(MAX_KNAPSACK_UNITS));
+        
+        let groups = conflict_groups(&units);
+        let capacity = (context.gas_limit / KNAPSACK_GAS_UNIT) as usize;
+        let weight = |unit: &Unit| ((unit.gas + KNAPSACK_GAS_UNIT - 1) / KNAPSACK_GAS_UNIT) as usize;
+        
+        // best[c] is the most profit within c buckets; choices[g][c] the unit group g took for it
+        let mut best = vec![U256::zero(); capacity + 1];
+        let mut choices: Vec<Vec<u16>> = Vec::with_capacity(groups.len());
+        for group in &groups {
+            let mut next = best.clone();
+            let mut choice = vec![NOT_TAKEN; capacity + 1];
+            for &index in group {
+                let w = weight(&units[index]);
+                for c in w..=capacity {
+                    let profit = best[c - w].saturating_add(units[index].profit);
+                    if profit > next[c] {
+                        next[c] = profit;
+                        choice[c] = index as u16;
+                    }
+                }
+            }
+            best = next;
+            choices.push(choice);
+        }
+        
+        let mut chosen = HashSet::new();
+        let mut c = capacity;
+        for choice in choices.iter().rev() {
+            if choice[c] != NOT_TAKEN {
+                let index = choice[c] as usize;
+                chosen.insert(index);
+                c -= weight(&units[index]);
+            }
+        }
+        
+        let mut gas_left = context.gas_limit;
+        let mut selected = Vec::new();
+        let mut rest = Vec::new();
+        for (index, unit) in units.into_iter().enumerate() {
+            if chosen.contains(&index) {
+                gas_left = gas_left.saturating_sub(unit.gas);
+                selected.push(unit);
+            } else {
+                rest.push(unit);
+            }
+        }
+        
+        // Leftover gas goes to whatever doesn't conflict with what was chosen
+        let mut fillers = Vec::new();
+        for unit in rest.into_iter().chain(overflow) {
+            let conflicts = selected.iter().chain(&fillers).any(|other| unit.keys.conflicts(&other.keys));
+            if unit.gas <= gas_left && !conflicts {
+                gas_left -= unit.gas;
+                fillers.push(unit);
+            }
+        }
+        selected.extend(fillers);
+        
+        // Within the block, higher paying transactions still go first
+        selected.sort_by(by_gas_price);
+        flatten(selected)
+    }
+}
+
+/// State a transaction may write, approximated from its target and access list
+#[derive(Debug, Default)]
+struct StateKeys {
+    /// Contracts called without an access list; any access to them conflicts
+    contracts: HashSet<Address>,
+    /// Storage slots declared in access lists
+    slots: HashSet<(Address, H256)>,
+}
+
+impl StateKeys {
+    fn of(tx: &Transaction) -> Self {
+        let mut keys = Self::default();
+        // Plain transfers only touch balances, which don't invalidate other candidates' profit
+        let to = match tx.to {
+            Some(to) if !tx.input.is_empty() => to,
+            _ => return keys,
+        };
+        
+        let access_list = tx.access_list.as_ref().map(|list| &list.0).filter(|list| !list.is_empty());
+        match access_list {
+            Some(list) => {
+                for item in list {
+                    keys.slots.extend(item.storage_keys.iter().map(|slot| (item.address, *slot)));
+                }
+            }
+            None => {
+                keys.contracts.insert(to);
+            }
+        }
+        keys
+    }
+    
+    fn merge(&mut self, other: Self) {
+        self.contracts.extend(other.contracts);
+        self.slots.extend(other.slots);
+    }
+    
+    fn conflicts(&self, other: &Self) -> bool {
+        let touches = |keys: &Self, contract: &Address| {
+            keys.contracts.contains(contract) || keys.slots.iter().any(|(address, _)| address == contract)
+        };
+        
+        self.contracts.iter().any(|contract| touches(other, contract))
+            || other.contracts.iter().any(|contract| touches(self, contract))
+            || !self.slots.is_disjoint(&other.slots)
+    }
+}
+
+/// One sender's candidates in nonce order, included all together or not at all
+struct Unit {
+    candidates: Vec<InclusionCandidate>,
+    gas: u64,
+    profit: U256,
+    /// Effective gas price of the first transaction, which the rest depend on
+    gas_price: U256,
+    keys: StateKeys,
+}
+
+impl Unit {
+    fn first_hash(&self) -> H256 {
+        self.candidates[0].tx.hash
+    }
+}
+
+/// Effective gas price at the given base fee, `None` if the transaction can't pay it
+fn effective_gas_price(tx: &Transaction, base_fee: U256) -> Option<U256> {
+    let price = match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+        (Some(max_fee), Some(priority)) => max_fee.min(base_fee.saturating_add(priority)),
+        _ => tx.gas_price.unwrap_or_default(),
+    };
+    (price >= base_fee).then_some(price)
+}
+
+/// Group candidates by sender, dropping those below the base fee and any later nonces after them
+fn sender_units(candidates: Vec<InclusionCandidate>, base_fee: U256) -> Vec<Unit> {
+    let mut by_sender: HashMap<Address, Vec<InclusionCandidate>> = HashMap::new();
+    for candidate in candidates {
+        by_sender.entry(candidate.tx.from).or_default().push(candidate);
+    }
+    
+    let mut units = Vec::with_capacity(by_sender.len());
+    for (_, mut candidates) in by_sender {
+        candidates.sort_by_key(|candidate| candidate.tx.nonce);
+        
+        let mut unit: Option<Unit> = None;
+        for candidate in candidates {
+            let gas_price = match effective_gas_price(&candidate.tx, base_fee) {
+                Some(gas_price) => gas_price,
+                None => break,
+            };
+            let gas = candidate.tx.gas.min(U256::from(u64::MAX)).as_u64();
+            let keys = StateKeys::of(&candidate.tx);
+            match &mut unit {
+                Some(unit) => {
+                    unit.gas = unit.gas.saturating_add(gas);
+                    unit.profit = unit.profit.saturating_add(candidate.profit);
+                    unit.keys.merge(keys);
+                    unit.candidates.push(candidate);
+                }
+                None => {
+                    unit = Some(Unit {
+                        gas,
+                        profit: candidate.profit,
+                        gas_price,
+                        keys,
+                        candidates: vec![candidate],
+                    });
+                }
+            }
+        }
+        units.extend(unit);
+    }
+    units
+}
+
+fn by_gas_price(a: &Unit, b: &Unit) -> std::cmp::Ordering {
+    b.gas_price.cmp(&a.gas_price).then_with(|| a.first_hash().cmp(&b.first_hash()))
+}
+
+fn profit_density(unit: &Unit) -> f64 {
+    wei_to_eth(unit.profit) / unit.gas.max(1) as f64
+}
+
+/// Indices of units partitioned into groups connected by state conflicts
+fn conflict_groups(units: &[Unit]) -> Vec<Vec<usize>> {
+    let mut parent: Vec<usize> = (0..units.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    
+    for i in 0..units.len() {
+        for j in i + 1..units.len() {
+            if units[i].keys.conflicts(&units[j].keys) {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[a] = b;
+            }
+        }
+    }
+    
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..units.len() {
+        groups.entry(root(&mut parent, i)).or_default().push(i);
+    }
+    let mut groups: Vec<_> = groups.into_values().collect();
+    groups.sort_by_key(|group| group[0]);
+    groups
+}
+
+fn flatten(units: Vec<Unit>) -> Vec<InclusionCandidate> {
+    units.into_iter().flat_map(|unit| unit.candidates).collect()
+}
@@ -0,0 +1,294 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::{
+    abi::{self, Token},
+    types::{
+        transaction::eip2718::TypedTransaction, Address, Eip1559TransactionRequest, Signature, H256, U256,
+    },
+    utils::keccak256,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+use tracing::{debug, info};
+
+use crate::{
+    blockchain::{
+        client::{calldata, is_execution_revert},
+        fees::Urgency,
+        signer::SignerRegistry,
+        BlockchainClient,
+    },
+    config::TokenDepositConfig,
+    services::transaction::{SendUnconfirmed, TransactionService},
+};
+
+/// EIP-2612 permit struct type
+const PERMIT_TYPE: &str = "Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)";
+
+/// Staking contract entry point that applies the permit and pulls the tokens in one call
+const STAKE_WITH_PERMIT: &str = "stakeWithPermit(address,uint256,uint256,uint8,bytes32,bytes32)";
+
+/// Headroom added to the estimated gas of the relayed call, in percent
+const GAS_HEADROOM_PERCENT: u64 = 20;
+
+/// Most permits relayed and not yet seen mined or dropped; further permits are refused
+const MAX_IN_FLIGHT: usize = 1_000;
+
+/// A token stake approved by an EIP-2612 permit signed by the staker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermitDeposit {
+    pub owner: Address,
+    /// Token amount in base units, the permit's `value`
+    pub amount: U256,
+    /// Unix time the permit expires
+    pub deadline: U256,
+    pub v: u8,
+    pub r: H256,
+    pub s: H256,
+}
+
+/// A permit refused before anything was sent
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PermitRejection {
+    #[error("permit deadline has passed")]
+    Expired,
+    #[error("amount is below the minimum stake of {0} base units")]
+    BelowMinimum(U256),
+    #[error("permit signature is not from the owner")]
+    WrongSigner,
+    #[error("owner balance is below the permitted amount")]
+    InsufficientBalance,
+    #[error("stake call would revert: {0}")]
+    WouldRevert(String),
+    #[error("a stake with this permit is already in flight")]
+    InFlight,
+}
+
+/// A permit, by owner, token and the token nonce it was signed for
+type PermitKey = (Address, Address, U256);
+
+/// Gasless token stakes: the staker signs a permit, one of our signers submits the stake
+///
+/// The permit is checked against the token's own domain separator and nonce before the
+/// combined call is built, so a bad signature never costs us gas.
+#[derive(Clone)]
+pub struct PermitDepositService {
+    /// Blockchain client, reads token state
+    blockchain_client: Arc<BlockchainClient>,
+    /// Transaction service, sends the relayed call
+    transaction_service: TransactionService,
+    /// Named accounts, resolves the relaying signer
+    signers: SignerRegistry,
+    /// Configuration
+    config: TokenDepositConfig,
+    /// Parsed token and staking contract, set when enabled
+    contracts: Option<(Address, Address)>,
+    /// Permits being relayed, with the stake's hash once sent, until it is mined or dropped
+    in_flight: Arc<Mutex<HashMap<PermitKey, Option<H256>>>>,
+}
+
+impl PermitDepositService {
+    /// Create a new permit deposit service
+    pub fn new(
+        blockchain_client: Arc<BlockchainClient>,
+        transaction_service: TransactionService,
+        signers: SignerRegistry,
+        config: TokenDepositConfig,
+    ) -> Result<Self> {
+        let contracts = if config.enabled {
+            let token = config.token.parse().context("Invalid token deposit token address")?;
+            let staking = config
+                .staking_contract
+                .parse()
+                .context("Invalid token deposit staking contract address")?;
+            Some((token, staking))
+        } else {
+            None
+        };
+        
+        Ok(Self {
+            blockchain_client,
+            transaction_service,
+            signers,
+            config,
+            contracts,
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+    
+    /// Whether token deposits are enabled
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Validate a permit and relay the stake, returning the transaction hash
+    ///
+    /// Refusals are returned as a [`PermitRejection`] inside the error. A permit is relayed
+    /// once at a time: until the stake sent for it is mined or dropped, the same permit is
+    /// refused rather than sent again to revert at our expense.
+    pub async fn submit(&self, deposit: &PermitDeposit) -> Result<H256> {
+        let (token, staking) = self.contracts.ok_or_else(|| anyhow!("Token deposits are disabled"))?;
+        
+        let nonce = self.validate(token, staking, deposit).await?;
+        let key = (deposit.owner, token, nonce);
+        self.claim(key).await?;
+        
+        let sent = self.relay(staking, deposit).await;
+        match &sent {
+            Ok(tx_hash) => {
+                self.in_flight.lock().insert(key, Some(*tx_hash));
+            }
+            // The stake may be out, it stays in flight under its hash until that settles
+            Err(e) => match e.downcast_ref::<SendUnconfirmed>() {
+                Some(unconfirmed) => {
+                    self.in_flight.lock().insert(key, Some(unconfirmed.tx_hash));
+                }
+                None => {
+                    self.in_flight.lock().remove(&key);
+                }
+            },
+        }
+        sent
+    }
+    
+    /// Hold a permit for relaying, refusing it while a stake sent for it may still land
+    async fn claim(&self, key: PermitKey) -> Result<()> {
+        let existing = self.in_flight.lock().get(&key).copied();
+        match existing {
+            Some(None) => return Err(PermitRejection::InFlight.into()),
+            Some(Some(tx_hash)) if !self.settled(tx_hash).await? => return Err(PermitRejection::InFlight.into()),
+            _ => {}
+        }
+        if existing.is_none() && self.in_flight.lock().len() >= MAX_IN_FLIGHT {
+            self.prune().await;
+        }
+        
+        let mut in_flight = self.in_flight.lock();
+        // Another request may have claimed the permit while its last stake was looked up
+        if in_flight.get(&key).copied() != existing {
+            return Err(PermitRejection::InFlight.into());
+        }
+        if existing.is_none() && in_flight.len() >= MAX_IN_FLIGHT {
+            return Err(anyhow!("{} permit stakes already in flight", in_flight.len()));
+        }
+        in_flight.insert(key, None);
+        Ok(())
+    }
+    
+    /// Whether a sent stake was mined or dropped, so its permit may be relayed again
+    async fn settled(&self, tx_hash: H256) -> Result<bool> {
+        if self.blockchain_client.get_transaction_receipt(tx_hash).await?.is_some() {
+            return Ok(true);
+        }
+        Ok(self.blockchain_client.get_transaction(tx_hash).await?.is_none())
+    }
+    
+    /// Forget sent stakes that were mined or dropped
+    async fn prune(&self) {
+        let sent: Vec<(PermitKey, H256)> = self
+            .in_flight
+            .lock()
+            .iter()
+            .filter_map(|(key, tx_hash)| tx_hash.map(|tx_hash| (*key, tx_hash)))
+            .collect();
+        for (key, tx_hash) in sent {
+            match self.settled(tx_hash).await {
+                Ok(true) => {
+                    let mut in_flight = self.in_flight.lock();
+                    if in_flight.get(&key) == Some(&Some(tx_hash)) {
+                        in_flight.remove(&key);
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    debug!("Failed to look up permit stake {:?}: {}", tx_hash, e);
+                    return;
+                }
+            }
+        }
+    }
+    
+    /// Build, estimate and send the stake call for a validated permit
+    async fn relay(&self, staking: Address, deposit: &PermitDeposit) -> Result<H256> {
+        let relayer = self.signers.address(&self.config.signer)?;
+        let data = calldata(
+            STAKE_WITH_PERMIT,
+            &[
+                Token::Address(deposit.owner),
+                Token::Uint(deposit.amount),
+                Token::Uint(deposit.deadline),
+                Token::Uint(U256::from(deposit.v)),
+                Token::FixedBytes(deposit.r.as_bytes().to_vec()),
+                Token::FixedBytes(deposit.s.as_bytes().to_vec()),
+            ],
+        );
+        let tx = Eip1559TransactionRequest::new().from(relayer).to(staking).data(data);
+        
+        // Only a revert is the permit's fault; a failed or timed out estimate is ours
+        let gas = match self.blockchain_client.estimate_gas(&TypedTransaction::Eip1559(tx.clone())).await {
+            Ok(gas) => gas,
+            Err(e) if is_execution_revert(&e) => return Err(PermitRejection::WouldRevert(e.to_string()).into()),
+            Err(e) => return Err(e.context("Failed to estimate the stake call")),
+        };
+        let tx = tx.gas(gas * (100 + GAS_HEADROOM_PERCENT) / 100);
+        
+        let tx_hash = self
+            .transaction_service
+            .send_transaction_as(&self.config.signer, tx, Urgency::Normal)
+            .await?;
+        
+        metrics::counter!("permit_deposits_total", 1);
+        info!("Relayed permit stake of {} from {:?} in {:?}", deposit.amount, deposit.owner, tx_hash);
+        Ok(tx_hash)
+    }
+    
+    /// Check the deadline, the signature against the token's EIP-712 domain, and the balance
+    ///
+    /// Returns the token nonce the permit was signed for.
+    async fn validate(&self, token: Address, spender: Address, deposit: &PermitDeposit) -> Result<U256> {
+        let now = U256::from(chrono::Utc::now().timestamp().max(0) as u64);
+        if deposit.deadline <= now {
+            return Err(PermitRejection::Expired.into());
+        }
+        let min_amount = self.config.min_amount.wei();
+        if deposit.amount < min_amount {
+            return Err(PermitRejection::BelowMinimum(min_amount).into());
+        }
+        
+        let domain_separator = self.blockchain_client.read_word(token, "DOMAIN_SEPARATOR()", &[]).await?;
+        let owner = [Token::Address(deposit.owner)];
+        let nonce = self.blockchain_client.read_uint(token, "nonces(address)", &owner).await?;
+        
+        let struct_hash = keccak256(abi::encode(&[
+            Token::FixedBytes(keccak256(PERMIT_TYPE).to_vec()),
+            Token::Address(deposit.owner),
+            Token::Address(spender),
+            Token::Uint(deposit.amount),
+            Token::Uint(nonce),
+            Token::Uint(deposit.deadline),
+        ]));
+        let mut message = Vec::with_capacity(66);
+        message.extend_from_slice(&[0x19, 0x01]);
+        message.extend_from_slice(domain_separator.as_bytes());
+        message.extend_from_slice(&struct_hash);
+        let digest = H256::from(keccak256(message));
+        
+        let signature = Signature {
+            r: U256::from_big_endian(deposit.r.as_bytes()),
+            s: U256::from_big_endian(deposit.s.as_bytes()),
+            v: deposit.v as u64,
+        };
+        match signature.recover(digest) {
+            Ok(signer) if signer == deposit.owner => {}
+            _ => return Err(PermitRejection::WrongSigner.into()),
+        }
+        
+        let owner = [Token::Address(deposit.owner)];
+        let balance = self.blockchain_client.read_uint(token, "balanceOf(address)", &owner).await?;
+        if balance < deposit.amount {
+            return Err(PermitRejection::InsufficientBalance.into());
+        }
+        
+        Ok(nonce)
+    }
+}
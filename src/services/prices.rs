@@ -0,0 +1,461 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use ethers::{
+    abi::{self, ParamType, Token},
+    types::{Address, I256, U256},
+};
+use futures::future::join_all;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tracing::{debug, warn};
+
+use crate::{
+    api::models,
+    blockchain::{client::calldata, simulator::TokenDelta, BlockchainClient},
+    config::{OffchainPriceSourceConfig, PriceConfig},
+    core::amm::to_f64,
+    database::RedisPool,
+};
+
+/// Redis key prefix aggregated prices are cached under, shared across builder instances
+const CACHE_KEY_PREFIX: &str = "prices:";
+
+/// A price reported by one source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceQuote {
+    /// `chainlink`, `uniswap_v3_twap` or the off-chain source's name
+    pub source: String,
+    pub price_usd: f64,
+    /// Whether the quote was close enough to the median to count
+    pub accepted: bool,
+}
+
+/// Aggregated USD price of a token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenPrice {
+    #[serde(with = "models::checksum")]
+    pub token: Address,
+    pub symbol: String,
+    /// Median of the accepted quotes
+    pub price_usd: f64,
+    pub sources: Vec<SourceQuote>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A priced token and where its price comes from
+struct TrackedToken {
+    symbol: String,
+    decimals: u8,
+    chainlink_feed: Option<Address>,
+    /// Uniswap V3 pool and the token its TWAP is quoted in
+    twap: Option<(Address, Address)>,
+}
+
+/// Token prices aggregated from Chainlink, Uniswap V3 TWAPs and off-chain sources
+///
+/// Each source is read independently and failures are left out; quotes further than the
+/// allowed deviation from their median are discarded and the median of the rest is the price.
+/// Aggregated prices are cached in process and in Redis for the cache TTL, so simulation and
+/// liquidation can price tokens on every block without a round of RPC calls each time.
+#[derive(Clone)]
+pub struct PriceService {
+    /// Whether prices are served at all
+    enabled: bool,
+    /// Blockchain client, reads feeds and pools
+    blockchain_client: Arc<BlockchainClient>,
+    /// Redis connection, shared price cache
+    redis: RedisPool,
+    http: reqwest::Client,
+    /// Priced tokens by address
+    tokens: Arc<HashMap<Address, TrackedToken>>,
+    /// Wrapped native token, converting USD to wei
+    native_token: Address,
+    offchain_sources: Vec<OffchainPriceSourceConfig>,
+    cache_ttl: Duration,
+    max_feed_age: Duration,
+    max_deviation_bps: u64,
+    twap_seconds: u32,
+    /// Latest aggregated prices by token
+    cache: Arc<RwLock<HashMap<Address, TokenPrice>>>,
+}
+
+impl PriceService {
+    /// Create a new price service
+    pub fn new(config: &PriceConfig, blockchain_client: Arc<BlockchainClient>, redis: RedisPool) -> Result<Self> {
+        let address = |value: &str, name: &str| -> Result<Address> {
+            value.parse().with_context(|| format!("Invalid price {} {}", name, value))
+        };
+        
+        let mut tokens = HashMap::with_capacity(config.tokens.len());
+        for token in &config.tokens {
+            let twap = match (&token.uniswap_v3_pool, &token.twap_quote) {
+                (Some(pool), Some(quote)) => {
+                    Some((address(pool, "uniswap_v3_pool")?, address(quote, "twap_quote")?))
+                }
+                _ => None,
+            };
+            tokens.insert(
+                address(&token.address, "token")?,
+                TrackedToken {
+                    symbol: token.symbol.clone(),
+                    decimals: token.decimals,
+                    chainlink_feed: token
+                        .chainlink_feed
+                        .as_deref()
+                        .map(|feed| address(feed, "chainlink_feed"))
+                        .transpose()?,
+                    twap,
+                },
+            );
+        }
+        
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.offchain_timeout_ms))
+            .build()?;
+        
+        Ok(Self {
+            enabled: config.enabled,
+            blockchain_client,
+            redis,
+            http,
+            tokens: Arc::new(tokens),
+            native_token: address(&config.native_token, "native_token")?,
+            offchain_sources: config.offchain_sources.clone(),
+            cache_ttl: Duration::from_secs(config.cache_ttl_seconds),
+            max_feed_age: Duration::from_secs(config.max_feed_age_seconds),
+            max_deviation_bps: config.max_deviation_bps,
+            twap_seconds: config.twap_seconds,
+            cache: Arc::new(RwLock::new(HashMap::new())),
+        })
+    }
+    
+    /// Whether prices are served
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+    
+    /// Configured token by address or case-insensitive symbol
+    pub fn resolve(&self, token: &str) -> Option<Address> {
+        if let Ok(address) = token.parse::<Address>() {
+            return self.tokens.contains_key(&address).then_some(address);
+        }
+        self.tokens
+            .iter()
+            .find(|(_, tracked)| tracked.symbol.eq_ignore_ascii_case(token))
+            .map(|(address, _)| *address)
+    }
+    
+    /// Aggregated price of a configured token, from cache while fresh
+    pub async fn price(&self, token: Address) -> Result<TokenPrice> {
+        if !self.enabled {
+            return Err(anyhow!("Price service is disabled"));
+        }
+        
+        if let Some(price) = self.cache.read().get(&token).filter(|price| self.is_fresh(price)) {
+            return Ok(price.clone());
+        }
+        
+        let cached: Option<Option<String>> = self.redis.cached(redis::cmd("GET").arg(cache_key(token))).await;
+        if let Some(price) = cached.flatten().and_then(|raw| serde_json::from_str::<TokenPrice>(&raw).ok()) {
+            if self.is_fresh(&price) {
+                self.cache.write().insert(token, price.clone());
+                return Ok(price);
+            }
+        }
+        
+        let price = self.aggregate(token).await?;
+        self.store(&price).await;
+        Ok(price)
+    }
+    
+    /// USD price of a configured token
+    pub async fn usd_price(&self, token: Address) -> Result<f64> {
+        Ok(self.price(token).await?.price_usd)
+    }
+    
+    /// USD price of ETH, from the wrapped native token
+    pub async fn eth_usd(&self) -> Result<f64> {
+        self.usd_price(self.native_token).await
+    }
+    
+    /// Value of a raw token amount in wei
+    pub async fn value_wei(&self, token: Address, amount: U256) -> Result<U256> {
+        let decimals = self
+            .tokens
+            .get(&token)
+            .map(|tracked| tracked.decimals)
+            .ok_or_else(|| anyhow!("Token {:?} is not priced", token))?;
+        let usd = to_f64(amount) / 10f64.powi(decimals as i32) * self.usd_price(token).await?;
+        let eth = usd / self.eth_usd().await?;
+        
+        Ok(U256::from((eth * 1e18).max(0.0) as u128))
+    }
+    
+    /// Net USD value of signed token deltas, None when any of the tokens can't be priced
+    pub async fn value_deltas_usd(&self, deltas: &[TokenDelta]) -> Option<f64> {
+        let mut total = 0.0;
+        for delta in deltas {
+            let decimals = self.tokens.get(&delta.token)?.decimals;
+            let amount = delta.delta.parse::<f64>().ok()?;
+            let price = self.usd_price(delta.token).await.ok()?;
+            total += amount / 10f64.powi(decimals as i32) * price;
+        }
+        Some(total)
+    }
+    
+    /// Re-aggregate every configured token into the caches
+    pub async fn refresh(&self) -> Result<()> {
+        for token in self.tokens.keys() {
+            match self.aggregate(*token).await {
+                Ok(price) => self.store(&price).await,
+                Err(e) => warn!("Failed to refresh price of {:?}: {}", token, e),
+            }
+        }
+        Ok(())
+    }
+    
+    fn is_fresh(&self, price: &TokenPrice) -> bool {
+        let age = Utc::now().signed_duration_since(price.updated_at);
+        age.to_std().map_or(false, |age| age < self.cache_ttl)
+    }
+    
+    /// Cache a price in process and, unless Redis is degraded, in Redis
+    async fn store(&self, price: &TokenPrice) {
+        self.cache.write().insert(price.token, price.clone());
+        if let Ok(raw) = serde_json::to_string(price) {
+            let mut cmd = redis::cmd("SET");
+            cmd.arg(cache_key(price.token)).arg(raw).arg("EX").arg(self.cache_ttl.as_secs().max(1));
+            self.redis.cached::<()>(&cmd).await;
+        }
+    }
+    
+    async fn aggregate(&self, token: Address) -> Result<TokenPrice> {
+        let tracked = self.tokens.get(&token).ok_or_else(|| anyhow!("Token {:?} is not priced", token))?;
+        
+        let mut quotes = self.direct_quotes(token, tracked).await;
+        if let Some((pool, quote)) = tracked.twap {
+            match self.twap_price(token, tracked, pool, quote).await {
+                Ok(price) => quotes.push(("uniswap_v3_twap".to_string(), price)),
+                Err(e) => self.source_failed("uniswap_v3_twap", &tracked.symbol, &e),
+            }
+        }
+        
+        let sources = self.weigh(quotes);
+        let accepted = sources
+            .iter()
+            .filter(|quote| quote.accepted)
+            .map(|quote| quote.price_usd)
+            .collect();
+        let price_usd =
+            median(accepted).ok_or_else(|| anyhow!("No price source answered for {}", tracked.symbol))?;
+        metrics::gauge!("token_price_usd", price_usd, "token" => tracked.symbol.clone());
+        
+        Ok(TokenPrice {
+            token,
+            symbol: tracked.symbol.clone(),
+            price_usd,
+            sources,
+            updated_at: Utc::now(),
+        })
+    }
+    
+    /// Chainlink and off-chain quotes in USD, failed sources left out
+    async fn direct_quotes(&self, token: Address, tracked: &TrackedToken) -> Vec<(String, f64)> {
+        let mut quotes = Vec::new();
+        if let Some(feed) = tracked.chainlink_feed {
+            match self.chainlink_price(feed).await {
+                Ok(price) => quotes.push(("chainlink".to_string(), price)),
+                Err(e) => self.source_failed("chainlink", &tracked.symbol, &e),
+            }
+        }
+        
+        let offchain = join_all(
+            self.offchain_sources
+                .iter()
+                .map(|source| self.offchain_price(source, token, tracked)),
+        )
+        .await;
+        for (source, result) in self.offchain_sources.iter().zip(offchain) {
+            match result {
+                Ok(price) => quotes.push((source.name.clone(), price)),
+                Err(e) => self.source_failed(&source.name, &tracked.symbol, &e),
+            }
+        }
+        
+        quotes
+    }
+    
+    /// Mark quotes within the allowed deviation of their median as accepted
+    fn weigh(&self, quotes: Vec<(String, f64)>) -> Vec<SourceQuote> {
+        let Some(middle) = median(quotes.iter().map(|(_, price)| *price).collect()) else {
+            return Vec::new();
+        };
+        quotes
+            .into_iter()
+            .map(|(source, price_usd)| {
+                let deviation_bps = (price_usd - middle).abs() / middle * 10_000.0;
+                SourceQuote {
+                    source,
+                    price_usd,
+                    accepted: deviation_bps <= self.max_deviation_bps as f64,
+                }
+            })
+            .collect()
+    }
+    
+    fn source_failed(&self, source: &str, symbol: &str, error: &anyhow::Error) {
+        debug!("Price source {} failed for {}: {}", source, symbol, error);
+        metrics::counter!("price_source_errors_total", 1, "source" => source.to_string());
+    }
+    
+    /// Latest answer of a Chainlink USD feed
+    async fn chainlink_price(&self, feed: Address) -> Result<f64> {
+        let reads = [(feed, calldata("latestRoundData()", &[])), (feed, calldata("decimals()", &[]))];
+        let outputs = self.blockchain_client.multicall(&reads).await?;
+        let (round, decimals) = match outputs.as_slice() {
+            [Some(round), Some(decimals)] => (round, decimals),
+            _ => return Err(anyhow!("Feed {:?} reverted", feed)),
+        };
+        
+        let round = abi::decode(
+            &[
+                ParamType::Uint(80),
+                ParamType::Int(256),
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Uint(80),
+            ],
+            round,
+        )
+        .map_err(|e| anyhow!("Failed to decode Chainlink round: {}", e))?;
+        
+        let answer = round[1].clone().into_int().unwrap_or_default();
+        if answer.is_zero() || answer.bit(255) {
+            return Err(anyhow!("Feed {:?} returned a non-positive answer", feed));
+        }
+        let updated_at = round[3].clone().into_uint().unwrap_or_default().low_u64() as i64;
+        if Utc::now().timestamp() - updated_at > self.max_feed_age.as_secs() as i64 {
+            return Err(anyhow!("Feed {:?} answer is stale", feed));
+        }
+        
+        if decimals.len() < 32 {
+            return Err(anyhow!("Feed {:?} returned {} bytes of decimals", feed, decimals.len()));
+        }
+        let decimals = U256::from_big_endian(&decimals[..32]).low_u32() as i32;
+        Ok(to_f64(answer) / 10f64.powi(decimals))
+    }
+    
+    /// USD price from a Uniswap V3 TWAP against a token priced without its own TWAP
+    async fn twap_price(
+        &self,
+        token: Address,
+        tracked: &TrackedToken,
+        pool: Address,
+        quote: Address,
+    ) -> Result<f64> {
+        let quote_token = self
+            .tokens
+            .get(&quote)
+            .ok_or_else(|| anyhow!("TWAP quote {:?} of {} is not a priced token", quote, tracked.symbol))?;
+        let cached = self
+            .cache
+            .read()
+            .get(&quote)
+            .filter(|price| self.is_fresh(price))
+            .map(|price| price.price_usd);
+        let quote_usd = match cached {
+            Some(price) => Some(price),
+            None => {
+                let quotes = self.direct_quotes(quote, quote_token).await;
+                median(quotes.into_iter().map(|(_, price)| price).collect())
+            }
+        }
+        .ok_or_else(|| anyhow!("No price for TWAP quote {}", quote_token.symbol))?;
+        
+        let seconds_ago = vec![Token::Uint(U256::from(self.twap_seconds)), Token::Uint(U256::zero())];
+        let seconds_ago = Token::Array(seconds_ago);
+        let output = self.blockchain_client.call_function(pool, "observe(uint32[])", &[seconds_ago]).await?;
+        let observed = abi::decode(
+            &[
+                ParamType::Array(Box::new(ParamType::Int(56))),
+                ParamType::Array(Box::new(ParamType::Uint(160))),
+            ],
+            &output,
+        )
+        .map_err(|e| anyhow!("Failed to decode pool observations: {}", e))?;
+        let cumulatives: Vec<i64> = observed[0]
+            .clone()
+            .into_array()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|tick| tick.into_int().map(|raw| I256::from_raw(raw).low_i64()))
+            .collect();
+        if cumulatives.len() != 2 {
+            return Err(anyhow!("Pool {:?} returned {} observations", pool, cumulatives.len()));
+        }
+        let tick = (cumulatives[1] - cumulatives[0]) as f64 / self.twap_seconds as f64;
+        
+        // The pool prices its lower-addressed token in units of the other
+        let (decimals0, decimals1) = if token < quote {
+            (tracked.decimals, quote_token.decimals)
+        } else {
+            (quote_token.decimals, tracked.decimals)
+        };
+        let price0 = 1.0001f64.powf(tick) * 10f64.powi(decimals0 as i32 - decimals1 as i32);
+        let in_quote = if token < quote { price0 } else { 1.0 / price0 };
+        
+        Ok(in_quote * quote_usd)
+    }
+    
+    /// USD price from an off-chain JSON endpoint
+    async fn offchain_price(
+        &self,
+        source: &OffchainPriceSourceConfig,
+        token: Address,
+        tracked: &TrackedToken,
+    ) -> Result<f64> {
+        let substitute = |template: &str| {
+            template
+                .replace("{symbol}", &tracked.symbol)
+                .replace("{address}", &format!("{:?}", token))
+        };
+        
+        let body: serde_json::Value = self
+            .http
+            .get(substitute(&source.url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let value = body
+            .pointer(&substitute(&source.json_pointer))
+            .ok_or_else(|| anyhow!("No price at {}", source.json_pointer))?;
+        let price = match value {
+            serde_json::Value::String(raw) => raw.parse::<f64>().ok(),
+            other => other.as_f64(),
+        };
+        
+        price
+            .filter(|price| price.is_finite() && *price > 0.0)
+            .ok_or_else(|| anyhow!("Invalid price {}", value))
+    }
+}
+
+fn cache_key(token: Address) -> String {
+    format!("{}{:?}", CACHE_KEY_PREFIX, token)
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let middle = values.len() / 2;
+    Some(if values.len() % 2 == 0 {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    })
+}
@@ -0,0 +1,157 @@
+use anyhow::{Context, Result};
+use ethers::types::H256;
+use sqlx::Row;
+use std::{sync::Arc, time::Duration};
+use tracing::debug;
+
+use crate::database::{DbHealth, DbPool};
+
+/// Claimed, bookkeeping in progress
+const STATE_PROCESSING: &str = "processing";
+
+/// Bookkeeping finished
+const STATE_DONE: &str = "done";
+
+/// How long a claim holds a block; a claim not completed by then can be taken over
+const CLAIM_LEASE: Duration = Duration::from_secs(300);
+
+/// Per-block processing state, making confirmed-block bookkeeping exactly-once
+///
+/// Live heads, catch-up and reorg handling all claim a block by hash before booking it. A
+/// claim holds the block for a lease; blocks whose bookkeeping failed, or whose claimant died,
+/// can be claimed again once it lapses, by this process or another.
+#[derive(Clone)]
+pub struct ProcessedBlocks {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Identifies this process's claims
+    owner: Arc<str>,
+}
+
+impl ProcessedBlocks {
+    /// Create a new processed block ledger
+    pub fn new(db_pool: DbPool, health: DbHealth) -> Result<Self> {
+        Ok(Self {
+            db_pool,
+            health,
+            owner: uuid::Uuid::new_v4().to_string().into(),
+        })
+    }
+    
+    /// Claim a block for processing, returning false if it is done or its claim hasn't lapsed
+    pub async fn claim(&self, block_number: u64, block_hash: H256) -> Result<bool> {
+        self.health.retry(|| self.claim_once(block_number, block_hash)).await
+    }
+    
+    /// Mark a claimed block's bookkeeping as finished
+    pub async fn complete(&self, block_hash: H256) -> Result<()> {
+        self.health.retry(|| self.complete_once(block_hash)).await
+    }
+    
+    /// Give up a claimed block whose bookkeeping failed, so the next claim can retry it
+    pub async fn release(&self, block_hash: H256) -> Result<()> {
+        self.health.retry(|| self.release_once(block_hash)).await
+    }
+    
+    /// Give up every claim this process still holds, so another can take the blocks over
+    /// without waiting out the lease
+    pub async fn release_all(&self) -> Result<u64> {
+        self.health.retry(|| self.release_all_once()).await
+    }
+    
+    /// Lowest block number in `from..=to` without a finished block
+    pub async fn first_unprocessed(&self, from: u64, to: u64) -> Result<Option<u64>> {
+        self.health.retry(|| self.first_unprocessed_once(from, to)).await
+    }
+    
+    async fn claim_once(&self, block_number: u64, block_hash: H256) -> Result<bool> {
+        let claimed = sqlx::query(
+            "INSERT INTO processed_blocks (block_hash, block_number, state, owner, updated_at, lease_expires_at)
+             VALUES ($1, $2, $3, $4, NOW(), NOW() + make_interval(secs => $6))
+             ON CONFLICT (block_hash) DO UPDATE
+             SET state = EXCLUDED.state, owner = EXCLUDED.owner, updated_at = NOW(),
+                 lease_expires_at = EXCLUDED.lease_expires_at
+             WHERE processed_blocks.state <> $5 AND processed_blocks.lease_expires_at < NOW()",
+        )
+        .bind(format!("{:?}", block_hash))
+        .bind(block_number as i64)
+        .bind(STATE_PROCESSING)
+        .bind(self.owner.as_ref())
+        .bind(STATE_DONE)
+        .bind(CLAIM_LEASE.as_secs_f64())
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to claim block")?
+        .rows_affected()
+            > 0;
+        
+        if !claimed {
+            debug!("Block {} ({}) already processed or claimed", block_number, block_hash);
+        }
+        Ok(claimed)
+    }
+    
+    async fn complete_once(&self, block_hash: H256) -> Result<()> {
+        sqlx::query("UPDATE processed_blocks SET state = $2, updated_at = NOW() WHERE block_hash = $1")
+            .bind(format!("{:?}", block_hash))
+            .bind(STATE_DONE)
+            .execute(&self.db_pool)
+            .await
+            .context("Failed to mark block processed")?;
+        
+        Ok(())
+    }
+    
+    async fn release_once(&self, block_hash: H256) -> Result<()> {
+        sqlx::query(
+            "UPDATE processed_blocks SET lease_expires_at = NOW(), updated_at = NOW()
+             WHERE block_hash = $1 AND owner = $2 AND state <> $3",
+        )
+        .bind(format!("{:?}", block_hash))
+        .bind(self.owner.as_ref())
+        .bind(STATE_DONE)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to release block")?;
+        
+        Ok(())
+    }
+    
+    async fn release_all_once(&self) -> Result<u64> {
+        let released = sqlx::query(
+            "UPDATE processed_blocks SET lease_expires_at = NOW(), updated_at = NOW()
+             WHERE owner = $1 AND state <> $2 AND lease_expires_at > NOW()",
+        )
+        .bind(self.owner.as_ref())
+        .bind(STATE_DONE)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to release block claims")?
+        .rows_affected();
+        
+        Ok(released)
+    }
+    
+    async fn first_unprocessed_once(&self, from: u64, to: u64) -> Result<Option<u64>> {
+        if from > to {
+            return Ok(None);
+        }
+        
+        let row = sqlx::query(
+            "SELECT MIN(n) AS block_number FROM generate_series($1::BIGINT, $2::BIGINT) AS n
+             WHERE NOT EXISTS (
+                 SELECT 1 FROM processed_blocks WHERE block_number = n AND state = $3
+             )",
+        )
+        .bind(from as i64)
+        .bind(to as i64)
+        .bind(STATE_DONE)
+        .fetch_one(&self.db_pool)
+        .await
+        .context("Failed to find unprocessed blocks")?;
+        
+        Ok(row.try_get::<Option<i64>, _>("block_number")?.map(|n| n as u64))
+    }
+}
@@ -0,0 +1,216 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use ethers::types::{H256, U256};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::{
+    api::models,
+    database::{DbHealth, DbPool},
+    services::{export::ExportBundle, prices::PriceService},
+    utils::units::wei_to_eth,
+};
+
+/// Realized profit of one strategy over a period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyProfit {
+    pub strategy: String,
+    pub bundles: i64,
+    #[serde(with = "models::wei")]
+    pub profit: U256,
+    pub profit_eth: f64,
+    /// USD value of the bundles that could be priced at inclusion
+    pub profit_usd: f64,
+}
+
+/// Realized profit over a trailing period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfitSummary {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub blocks: i64,
+    pub bundles: i64,
+    #[serde(with = "models::wei")]
+    pub profit: U256,
+    pub profit_eth: f64,
+    /// USD value of the bundles that could be priced at inclusion
+    pub profit_usd: f64,
+    /// Bundles left out of `profit_usd` as no ETH price was available when they landed
+    pub unpriced_bundles: i64,
+    /// Breakdown by strategy, most profitable first
+    pub by_strategy: Vec<StrategyProfit>,
+}
+
+/// Strategy booked for order flow we only included
+const ORDER_FLOW: &str = "order_flow";
+
+/// Profit accounting ledger
+///
+/// Books what every bundle we landed paid the fee recipient, per block, bundle and strategy, in
+/// ETH and in USD at the ETH price when the block is processed, so later price moves don't
+/// rewrite history. Bundles are booked under the strategy that sent them, and order flow we
+/// only included under `order_flow`.
+#[derive(Clone)]
+pub struct ProfitService {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Token prices, valuing profit in USD at inclusion
+    prices: PriceService,
+}
+
+impl ProfitService {
+    /// Create a new profit service
+    pub fn new(db_pool: DbPool, health: DbHealth, prices: PriceService) -> Result<Self> {
+        Ok(Self {
+            db_pool,
+            health,
+            prices,
+        })
+    }
+    
+    /// Book the bundles we landed in a block, replacing what was booked for a reorged-out block
+    pub async fn record_block(
+        &self,
+        block_number: u64,
+        block_hash: H256,
+        included_at: DateTime<Utc>,
+        bundles: &[ExportBundle],
+    ) -> Result<()> {
+        let eth_usd = if self.prices.enabled() {
+            self.prices
+                .eth_usd()
+                .await
+                .map_err(|e| warn!("No ETH price to book block {} in USD: {}", block_number, e))
+                .ok()
+        } else {
+            None
+        };
+        
+        self.health
+            .retry(|| self.store_block(block_number, block_hash, included_at, bundles, eth_usd))
+            .await?;
+        debug!("Booked {} bundles for block {}", bundles.len(), block_number);
+        
+        Ok(())
+    }
+    
+    /// Realized profit over the trailing period, with the breakdown by strategy
+    pub async fn summary(&self, period: Duration) -> Result<ProfitSummary> {
+        self.health.retry(|| self.summary_once(period)).await
+    }
+    
+    async fn store_block(
+        &self,
+        block_number: u64,
+        block_hash: H256,
+        included_at: DateTime<Utc>,
+        bundles: &[ExportBundle],
+        eth_usd: Option<f64>,
+    ) -> Result<()> {
+        let mut db_tx = self.db_pool.begin().await.context("Failed to start profit transaction")?;
+        
+        sqlx::query("DELETE FROM profits WHERE block_number = $1 AND block_hash <> $2")
+            .bind(block_number as i64)
+            .bind(format!("{:?}", block_hash))
+            .execute(&mut *db_tx)
+            .await
+            .context("Failed to remove reorged profits")?;
+        
+        for (index, bundle) in bundles.iter().enumerate() {
+            let strategy = bundle.strategy.as_deref().unwrap_or(ORDER_FLOW);
+            let profit_usd = eth_usd.map(|price| wei_to_eth(bundle.value) * price);
+            let tx_hashes: Vec<String> = bundle.tx_hashes.iter().map(|hash| format!("{:?}", hash)).collect();
+            
+            sqlx::query(
+                "INSERT INTO profits
+                     (block_number, block_hash, bundle_index, tx_hashes, strategy, source, profit_wei,
+                      eth_usd, profit_usd, included_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7::NUMERIC, $8, $9, $10)
+                 ON CONFLICT (block_number, bundle_index) DO NOTHING",
+            )
+            .bind(block_number as i64)
+            .bind(format!("{:?}", block_hash))
+            .bind(index as i32)
+            .bind(tx_hashes)
+            .bind(strategy)
+            .bind(bundle.source.to_string())
+            .bind(bundle.value.to_string())
+            .bind(eth_usd)
+            .bind(profit_usd)
+            .bind(included_at)
+            .execute(&mut *db_tx)
+            .await
+            .context("Failed to record profit")?;
+        }
+        
+        db_tx.commit().await.context("Failed to commit profits")?;
+        
+        Ok(())
+    }
+    
+    async fn summary_once(&self, period: Duration) -> Result<ProfitSummary> {
+        let to = Utc::now();
+        let from = to - chrono::Duration::from_std(period).context("Period is too long")?;
+        
+        let rows = sqlx::query(
+            "SELECT strategy, COUNT(*) AS bundles, SUM(profit_wei)::TEXT AS profit,
+                    COALESCE(SUM(profit_usd), 0) AS profit_usd
+             FROM profits
+             WHERE included_at >= $1 AND included_at < $2
+             GROUP BY strategy
+             ORDER BY SUM(profit_wei) DESC",
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to summarize profits by strategy")?;
+        
+        let by_strategy = rows
+            .iter()
+            .map(|row| {
+                let profit: String = row.try_get("profit")?;
+                let profit = U256::from_dec_str(&profit).map_err(|e| anyhow!("Invalid profit {}: {}", profit, e))?;
+                Ok(StrategyProfit {
+                    strategy: row.try_get("strategy")?,
+                    bundles: row.try_get("bundles")?,
+                    profit,
+                    profit_eth: wei_to_eth(profit),
+                    profit_usd: row.try_get("profit_usd")?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        
+        let totals = sqlx::query(
+            "SELECT COUNT(DISTINCT block_number) AS blocks,
+                    COUNT(*) FILTER (WHERE profit_usd IS NULL) AS unpriced
+             FROM profits
+             WHERE included_at >= $1 AND included_at < $2",
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_one(&self.db_pool)
+        .await
+        .context("Failed to count profitable blocks")?;
+        
+        let profit = by_strategy
+            .iter()
+            .fold(U256::zero(), |acc, strategy| acc.saturating_add(strategy.profit));
+        
+        Ok(ProfitSummary {
+            from,
+            to,
+            blocks: totals.try_get("blocks")?,
+            bundles: by_strategy.iter().map(|strategy| strategy.bundles).sum(),
+            profit,
+            profit_eth: wei_to_eth(profit),
+            profit_usd: by_strategy.iter().map(|strategy| strategy.profit_usd).sum(),
+            unpriced_bundles: totals.try_get("unpriced")?,
+            by_strategy,
+        })
+    }
+}
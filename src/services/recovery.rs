@@ -0,0 +1,226 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::{sync::Arc, time::Duration};
+use tracing::{debug, info, warn};
+
+use crate::{
+    blockchain::BlockchainClient,
+    database::{DbHealth, DbPool},
+    services::transaction::{InclusionCandidate, TransactionService},
+};
+
+/// Row key for the builder's in-flight state snapshot
+const BUILDER_STATE_ID: &str = "builder";
+
+/// Snapshot of everything the builder was working on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InFlightState {
+    /// Head block the snapshot was taken at
+    pub head_block: u64,
+    /// Candidate transactions for the next block, in template order
+    pub candidates: Vec<InclusionCandidate>,
+    /// When the snapshot was taken
+    pub saved_at: DateTime<Utc>,
+}
+
+/// Service that checkpoints in-flight state and restores it after a crash
+#[derive(Clone)]
+pub struct RecoveryService {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Blockchain client
+    blockchain_client: Arc<BlockchainClient>,
+    /// Transaction service holding the candidate set
+    transaction_service: TransactionService,
+    /// Snapshots older than this many blocks are discarded
+    max_block_history: u64,
+}
+
+impl RecoveryService {
+    /// Create a new recovery service
+    pub fn new(
+        db_pool: DbPool,
+        health: DbHealth,
+        blockchain_client: Arc<BlockchainClient>,
+        transaction_service: TransactionService,
+        max_block_history: u64,
+    ) -> Result<Self> {
+        Ok(Self {
+            db_pool,
+            health,
+            blockchain_client,
+            transaction_service,
+            max_block_history,
+        })
+    }
+    
+    /// Persist the current in-flight state
+    pub async fn checkpoint(&self, head_block: u64) -> Result<()> {
+        let candidates = self.transaction_service.block_template().await;
+        
+        let state = InFlightState {
+            head_block,
+            candidates,
+            saved_at: Utc::now(),
+        };
+        
+        self.health.retry(|| self.store_checkpoint(&state)).await?;
+        
+        debug!("Checkpointed {} in-flight candidates at block {}", state.candidates.len(), head_block);
+        
+        Ok(())
+    }
+    
+    /// Delete archived heads recorded longer ago than the retention, returning how many
+    pub async fn prune_history(&self, retention: Duration) -> Result<u64> {
+        self.health.retry(|| self.prune_history_once(retention)).await
+    }
+    
+    /// Reload the last snapshot, re-validate it against the current head and resume
+    pub async fn restore(&self) -> Result<usize> {
+        let state = match self.health.retry(|| self.saved_state()).await? {
+            Some(state) => state,
+            None => {
+                info!("No in-flight state to recover");
+                return Ok(0);
+            }
+        };
+        
+        let current_block = self.blockchain_client.get_block_number().await?;
+        if current_block.saturating_sub(state.head_block) > self.max_block_history {
+            warn!(
+                "Discarding in-flight state from block {} (current head {})",
+                state.head_block, current_block
+            );
+            return Ok(0);
+        }
+        
+        let mut restored = 0;
+        for candidate in state.candidates {
+            let tx_hash = candidate.tx.hash;
+            
+            // Only resume transactions that are still pending
+            match self.blockchain_client.get_transaction(tx_hash).await {
+                Ok(Some(tx)) if tx.block_number.is_none() => {
+                    self.transaction_service
+                        .mark_transaction_for_inclusion(InclusionCandidate {
+                            tx: Arc::new(tx),
+                            ..candidate
+                        })
+                        .await?;
+                    restored += 1;
+                }
+                Ok(_) => debug!("Dropping recovered candidate {}, no longer pending", tx_hash),
+                Err(e) => warn!("Failed to re-validate recovered candidate {}: {}", tx_hash, e),
+            }
+        }
+        
+        info!(
+            "Recovered {} in-flight candidates from block {} snapshot",
+            restored, state.head_block
+        );
+        
+        Ok(restored)
+    }
+    
+    /// Head block of the latest checkpoint, the last block processed before a restart
+    pub async fn last_checkpoint_block(&self) -> Result<Option<u64>> {
+        self.health.retry(|| self.last_checkpoint_block_once()).await
+    }
+    
+    /// Load the archived snapshot taken at a given head block
+    pub async fn load_snapshot(&self, head_block: u64) -> Result<Option<InFlightState>> {
+        self.health.retry(|| self.load_snapshot_once(head_block)).await
+    }
+    
+    /// Shutdown the recovery service
+    pub async fn shutdown(&self) -> Result<()> {
+        debug!("Shutting down recovery service");
+        Ok(())
+    }
+    
+    async fn store_checkpoint(&self, state: &InFlightState) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO inflight_state (id, head_block, state, updated_at)
+             VALUES ($1, $2, $3, NOW())
+             ON CONFLICT (id) DO UPDATE
+             SET head_block = EXCLUDED.head_block, state = EXCLUDED.state, updated_at = NOW()",
+        )
+        .bind(BUILDER_STATE_ID)
+        .bind(state.head_block as i64)
+        .bind(sqlx::types::Json(state))
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to persist in-flight state")?;
+        
+        // Keep a per-head archive for replaying past slots
+        sqlx::query(
+            "INSERT INTO inflight_state_history (head_block, state, recorded_at)
+             VALUES ($1, $2, NOW())
+             ON CONFLICT (head_block) DO UPDATE SET state = EXCLUDED.state, recorded_at = NOW()",
+        )
+        .bind(state.head_block as i64)
+        .bind(sqlx::types::Json(state))
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to archive in-flight state")?;
+        
+        Ok(())
+    }
+    
+    async fn saved_state(&self) -> Result<Option<InFlightState>> {
+        let row = sqlx::query("SELECT state FROM inflight_state WHERE id = $1")
+            .bind(BUILDER_STATE_ID)
+            .fetch_optional(&self.db_pool)
+            .await
+            .context("Failed to load in-flight state")?;
+        
+        match row {
+            Some(row) => Ok(Some(row.try_get::<sqlx::types::Json<InFlightState>, _>("state")?.0)),
+            None => Ok(None),
+        }
+    }
+    
+    async fn prune_history_once(&self, retention: Duration) -> Result<u64> {
+        let deleted = sqlx::query(
+            "DELETE FROM inflight_state_history WHERE recorded_at < NOW() - make_interval(secs => $1)",
+        )
+        .bind(retention.as_secs_f64())
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to prune in-flight state history")?
+        .rows_affected();
+        
+        Ok(deleted)
+    }
+    
+    async fn last_checkpoint_block_once(&self) -> Result<Option<u64>> {
+        let row = sqlx::query("SELECT head_block FROM inflight_state WHERE id = $1")
+            .bind(BUILDER_STATE_ID)
+            .fetch_optional(&self.db_pool)
+            .await
+            .context("Failed to load last checkpoint block")?;
+        
+        match row {
+            Some(row) => Ok(Some(row.try_get::<i64, _>("head_block")? as u64)),
+            None => Ok(None),
+        }
+    }
+    
+    async fn load_snapshot_once(&self, head_block: u64) -> Result<Option<InFlightState>> {
+        let row = sqlx::query("SELECT state FROM inflight_state_history WHERE head_block = $1")
+            .bind(head_block as i64)
+            .fetch_optional(&self.db_pool)
+            .await
+            .context("Failed to load archived in-flight state")?;
+        
+        match row {
+            Some(row) => Ok(Some(row.try_get::<sqlx::types::Json<InFlightState>, _>("state")?.0)),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use futures::future::{join_all, BoxFuture};
+use parking_lot::Mutex;
+use serde::Serialize;
+use ethers::types::{H256, U256};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    config::{BlockchainConfig, RelayBackoffConfig, RelayConfig},
+    database::{resilience::DeferredWrite, DbHealth, DbPool},
+    relay::{
+        self, BidSubmission, BuilderIdentity, ProposerDuty, RelayAdapter, RelayCapabilities, RelayError,
+        RelayErrorKind, SubmissionReceipt,
+    },
+    services::{
+        build_decisions::{BuildDecisionLog, RelaySubmission},
+        head_tracker::HeadTracker,
+        risk::RiskManager,
+        subsidy::SubsidyService,
+    },
+    utils::{
+        audit,
+        tasks::{Lane, TaskLanes},
+    },
+};
+
+/// Recent errors kept per relay for the admin breakdown
+const RECENT_RELAY_ERRORS: usize = 50;
+
+/// Outcome of submitting one bid to one relay
+pub struct RelayOutcome {
+    pub relay: String,
+    pub result: Result<SubmissionReceipt, RelayError>,
+}
+
+/// A recent failed submission
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentRelayError {
+    pub at: DateTime<Utc>,
+    pub slot: u64,
+    pub kind: RelayErrorKind,
+    pub status: Option<u16>,
+    pub message: String,
+}
+
+/// Submission history and backoff state for one relay
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RelayStatus {
+    pub name: String,
+    pub cancellations: bool,
+    pub max_submissions_per_second: u32,
+    pub accepted: u64,
+    /// Failed submissions by error kind
+    pub errors: HashMap<RelayErrorKind, u64>,
+    pub consecutive_failures: u32,
+    /// Submissions are skipped until this time
+    pub backed_off_until: Option<DateTime<Utc>>,
+    pub recent_errors: VecDeque<RecentRelayError>,
+}
+
+impl RelayStatus {
+    fn is_backed_off(&self) -> bool {
+        self.backed_off_until.map_or(false, |until| until > Utc::now())
+    }
+    
+    fn record(&mut self, slot: u64, result: &Result<SubmissionReceipt, RelayError>, config: &RelayBackoffConfig) {
+        let error = match result {
+            Ok(_) => {
+                self.accepted += 1;
+                self.consecutive_failures = 0;
+                self.backed_off_until = None;
+                return;
+            }
+            Err(error) => error,
+        };
+        
+        *self.errors.entry(error.kind).or_default() += 1;
+        if self.recent_errors.len() >= RECENT_RELAY_ERRORS {
+            self.recent_errors.pop_front();
+        }
+        self.recent_errors.push_back(RecentRelayError {
+            at: Utc::now(),
+            slot,
+            kind: error.kind,
+            status: error.status,
+            message: error.message.clone(),
+        });
+        
+        // Equivocation warnings are about our submissions, not the relay's health
+        if error.kind == RelayErrorKind::Equivocation {
+            return;
+        }
+        
+        self.consecutive_failures += 1;
+        if self.consecutive_failures >= config.failure_threshold {
+            // Double the backoff for every further threshold's worth of failures
+            let exponent = (self.consecutive_failures / config.failure_threshold.max(1) - 1).min(16);
+            let seconds = config
+                .base_backoff_seconds
+                .saturating_mul(1 << exponent)
+                .min(config.max_backoff_seconds);
+            self.backed_off_until = Some(Utc::now() + chrono::Duration::seconds(seconds as i64));
+            warn!(
+                "Backing off relay {} for {}s after {} consecutive failures",
+                self.name, seconds, self.consecutive_failures
+            );
+        }
+    }
+}
+
+/// Service fanning bids out to every configured relay
+#[derive(Clone)]
+pub struct RelayService {
+    /// Database pool, records our bids for market comparison
+    db_pool: DbPool,
+    /// Bid records are deferred while the database is down
+    health: DbHealth,
+    /// One adapter per configured relay
+    adapters: Vec<Arc<dyn RelayAdapter>>,
+    /// Head tracker, bids are refused while the head is stale
+    head_tracker: HeadTracker,
+    /// Subsidy service, told about subsidized bids so landed ones are booked
+    subsidy_service: SubsidyService,
+    /// Per-relay outcomes and backoff, by relay name
+    statuses: Arc<Mutex<HashMap<String, RelayStatus>>>,
+    /// Backoff policy for relays returning systematic failures
+    backoff: RelayBackoffConfig,
+    /// Registered proposers by slot, merged across relays
+    duties: Arc<Mutex<BTreeMap<u64, ProposerDuty>>>,
+    /// Extra-data, public key and graffiti our bids must carry
+    identity: BuilderIdentity,
+    /// Dry run switch, when on bids are neither sent to relays nor recorded
+    risk_manager: RiskManager,
+    /// Per-slot decision log, told about every bid and its outcome at each relay
+    decisions: BuildDecisionLog,
+    /// Lanes bids and header requests are run on
+    lanes: TaskLanes,
+    /// Unix time of beacon chain genesis, duties are kept from the current slot on
+    genesis_timestamp: u64,
+    slot_duration_seconds: u64,
+}
+
+impl RelayService {
+    /// Create a new relay service
+    pub fn new(
+        db_pool: DbPool,
+        health: DbHealth,
+        blockchain: &BlockchainConfig,
+        configs: &[RelayConfig],
+        backoff: RelayBackoffConfig,
+        head_tracker: HeadTracker,
+        subsidy_service: SubsidyService,
+        identity: BuilderIdentity,
+        risk_manager: RiskManager,
+        decisions: BuildDecisionLog,
+        lanes: TaskLanes,
+    ) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(2))
+            .build()
+            .context("Failed to create relay HTTP client")?;
+        
+        let adapters = configs
+            .iter()
+            .map(|config| relay::create_adapter(config, http.clone()))
+            .collect::<Result<Vec<_>>>()?;
+        
+        info!("Configured {} relays", adapters.len());
+        
+        let statuses = adapters
+            .iter()
+            .map(|adapter| {
+                let capabilities: RelayCapabilities = adapter.capabilities();
+                let status = RelayStatus {
+                    name: adapter.name().to_string(),
+                    cancellations: capabilities.cancellations,
+                    max_submissions_per_second: capabilities.max_submissions_per_second,
+                    ..Default::default()
+                };
+                (adapter.name().to_string(), status)
+            })
+            .collect();
+        
+        Ok(Self {
+            db_pool,
+            health,
+            adapters,
+            head_tracker,
+            subsidy_service,
+            statuses: Arc::new(Mutex::new(statuses)),
+            backoff,
+            duties: Arc::new(Mutex::new(BTreeMap::new())),
+            identity,
+            risk_manager,
+            decisions,
+            lanes,
+            genesis_timestamp: blockchain.genesis_timestamp,
+            slot_duration_seconds: blockchain.slot_duration_seconds.max(1),
+        })
+    }
+    
+    /// Extra-data, public key and graffiti of this deployment
+    pub fn identity(&self) -> &BuilderIdentity {
+        &self.identity
+    }
+    
+    /// Submission outcomes, recent errors and backoff state for every relay
+    pub fn relay_statuses(&self) -> Vec<RelayStatus> {
+        let mut statuses: Vec<RelayStatus> = self.statuses.lock().values().cloned().collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+    
+    /// Beacon chain slot at the current time
+    pub fn current_slot(&self) -> u64 {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        now.as_secs().saturating_sub(self.genesis_timestamp) / self.slot_duration_seconds
+    }
+    
+    /// Registered proposer of a slot, if any relay knows of one
+    pub fn proposer_duty(&self, slot: u64) -> Option<ProposerDuty> {
+        self.duties.lock().get(&slot).cloned()
+    }
+    
+    /// Last slot the merged proposer schedule reaches, `None` before any relay returned one
+    pub fn duties_through(&self) -> Option<u64> {
+        self.duties.lock().keys().next_back().copied()
+    }
+    
+    /// Refresh the proposer schedule from every relay, dropping past slots
+    pub async fn refresh_proposer_duties(&self) -> Result<()> {
+        let results = join_all(self.active_adapters().into_iter().map(|adapter| async move {
+            (adapter.name().to_string(), adapter.proposer_duties().await)
+        }))
+        .await;
+        
+        // Duties are keyed by beacon slot, which the execution head number says nothing about
+        let current_slot = self.current_slot();
+        let mut duties = self.duties.lock();
+        duties.retain(|slot, _| *slot >= current_slot);
+        
+        for (relay, result) in results {
+            match result {
+                Ok(relay_duties) => {
+                    for duty in relay_duties.into_iter().filter(|duty| duty.slot >= current_slot) {
+                        duties.entry(duty.slot).or_insert(duty);
+                    }
+                }
+                Err(e) => warn!("Failed to fetch proposer duties from {}: {}", relay, e),
+            }
+        }
+        
+        metrics::gauge!("relay_proposer_duties", duties.len() as f64);
+        debug!("Tracking {} upcoming proposer duties", duties.len());
+        Ok(())
+    }
+    
+    /// Adapters not currently backed off
+    fn active_adapters(&self) -> Vec<&Arc<dyn RelayAdapter>> {
+        let statuses = self.statuses.lock();
+        self.adapters
+            .iter()
+            .filter(|adapter| !statuses.get(adapter.name()).map_or(false, |s| s.is_backed_off()))
+            .collect()
+    }
+    
+    /// Submit a bid to every relay concurrently, on the critical lane
+    ///
+    /// The bid is copied to the lane, which costs far less than having its submission queued
+    /// behind ingestion work on the main runtime at the slot boundary. In a dry run nothing is
+    /// sent and there are no outcomes.
+    pub async fn submit_bid(&self, bid: &BidSubmission, cancellable: bool) -> Result<Vec<RelayOutcome>> {
+        let relays = self.clone();
+        let bid = bid.clone();
+        self.lanes
+            .run(Lane::Critical, "bid_submission", async move {
+                relays.fan_out_bid(&bid, cancellable).await
+            })
+            .await?
+    }
+    
+    async fn fan_out_bid(&self, bid: &BidSubmission, cancellable: bool) -> Result<Vec<RelayOutcome>> {
+        if !self.head_tracker.can_bid() {
+            return Err(anyhow!("Head is stale, not bidding for slot {}", bid.slot));
+        }
+        self.identity.verify(bid)?;
+        
+        // Relays reject blocks for slots without a registered validator; skip the round trip
+        // once the schedule is known
+        {
+            let duties = self.duties.lock();
+            if !duties.is_empty() && !duties.contains_key(&bid.slot) {
+                return Err(anyhow!("No registered proposer for slot {}", bid.slot));
+            }
+        }
+        
+        let active = self.active_adapters();
+        for adapter in &self.adapters {
+            if !active.iter().any(|active_adapter| active_adapter.name() == adapter.name()) {
+                let relay = adapter.name().to_string();
+                metrics::counter!("relay_submissions_total", 1, "relay" => relay, "outcome" => "backed_off");
+            }
+        }
+        
+        if self.risk_manager.is_dry_run() {
+            metrics::counter!("dry_run_suppressed_total", 1, "kind" => "bid");
+            debug!(
+                "Dry run, not submitting bid of {} wei for slot {} to {} relays",
+                bid.value,
+                bid.slot,
+                active.len()
+            );
+            return Ok(Vec::new());
+        }
+        
+        let submitted_at = Utc::now();
+        let tx_hashes = bid.tx_hashes();
+        let tx_hashes = &tx_hashes;
+        let submissions = active.into_iter().map(|adapter| async move {
+            let result = adapter.submit_bid(bid, cancellable).await;
+            let error = result.as_ref().err();
+            audit::bid_submitted(bid.block_hash, tx_hashes, bid.slot, bid.value, adapter.name(), error);
+            
+            let outcome = match &result {
+                Ok(_) => "accepted",
+                Err(e) => e.kind.as_str(),
+            };
+            metrics::counter!("relay_submissions_total", 1, "relay" => adapter.name().to_string(), "outcome" => outcome);
+            if let Err(e) = &result {
+                warn!("Bid for slot {} failed: {}", bid.slot, e);
+            }
+            
+            if let Some(status) = self.statuses.lock().get_mut(adapter.name()) {
+                status.record(bid.slot, &result, &self.backoff);
+            }
+            
+            RelayOutcome {
+                relay: adapter.name().to_string(),
+                result,
+            }
+        });
+        
+        let outcomes = join_all(submissions).await;
+        self.spawn_record_bid(bid, &outcomes, submitted_at);
+        
+        if let Some(subsidy) = &bid.subsidy {
+            if outcomes.iter().any(|outcome| outcome.result.is_ok()) {
+                self.subsidy_service.record_bid(bid.slot, bid.block_hash, subsidy.clone());
+            }
+        }
+        
+        Ok(outcomes)
+    }
+    
+    /// Record the bid per relay and in the slot's decision log off the submission path
+    fn spawn_record_bid(&self, bid: &BidSubmission, outcomes: &[RelayOutcome], submitted_at: DateTime<Utc>) {
+        let db_pool = self.db_pool.clone();
+        let health = self.health.clone();
+        let decisions = self.decisions.clone();
+        let subsidy = bid.subsidy.as_ref().map(|subsidy| subsidy.amount);
+        let record = Arc::new(BuilderBidRecord {
+            slot: bid.slot,
+            block_hash: bid.block_hash,
+            value: bid.value,
+            builder_pubkey: bid.builder_pubkey().map(str::to_string),
+            extra_data: bid.extra_data().map(|extra_data| extra_data.to_string()),
+            graffiti: self.identity.graffiti.clone(),
+            submissions: outcomes
+                .iter()
+                .map(|outcome| RelaySubmission {
+                    relay: outcome.relay.clone(),
+                    accepted: outcome.result.is_ok(),
+                    latency_ms: outcome.result.as_ref().ok().map(|receipt| receipt.latency_ms),
+                    error: outcome.result.as_ref().err().map(|e| e.message.clone()),
+                })
+                .collect(),
+        });
+        
+        // Bookkeeping, kept off the critical lane the bid was submitted on
+        self.lanes.spawn(Lane::Normal, "record_bid", async move {
+            let (slot, block_hash, value) = (record.slot, record.block_hash, record.value);
+            let bids = record.clone();
+            let write: DeferredWrite = Arc::new(move |pool: DbPool| -> BoxFuture<'static, Result<()>> {
+                let bids = bids.clone();
+                Box::pin(async move { bids.store(&pool).await })
+            });
+            if let Err(e) = health.write_or_defer(&db_pool, write).await {
+                warn!("Failed to record bids for slot {}: {}", slot, e);
+            }
+            
+            if let Err(e) = decisions
+                .record_bid(slot, block_hash, value, subsidy, &record.submissions, submitted_at)
+                .await
+            {
+                warn!("Failed to record bid decision for slot {}: {}", slot, e);
+            }
+        });
+    }
+}
+
+/// One bid as recorded in `builder_bids`, a row per relay it went to
+struct BuilderBidRecord {
+    slot: u64,
+    block_hash: H256,
+    value: U256,
+    builder_pubkey: Option<String>,
+    extra_data: Option<String>,
+    graffiti: String,
+    submissions: Vec<RelaySubmission>,
+}
+
+impl BuilderBidRecord {
+    async fn store(&self, db_pool: &DbPool) -> Result<()> {
+        let mut db_tx = db_pool.begin().await.context("Failed to begin bid transaction")?;
+        for submission in &self.submissions {
+            sqlx::query(
+                "INSERT INTO builder_bids
+                     (slot, relay, block_hash, value_wei, accepted, builder_pubkey, extra_data, graffiti)
+                 VALUES ($1, $2, $3, $4::NUMERIC, $5, $6, $7, $8)",
+            )
+            .bind(self.slot as i64)
+            .bind(&submission.relay)
+            .bind(format!("{:?}", self.block_hash))
+            .bind(self.value.to_string())
+            .bind(submission.accepted)
+            .bind(&self.builder_pubkey)
+            .bind(&self.extra_data)
+            .bind(&self.graffiti)
+            .execute(&mut *db_tx)
+            .await
+            .context("Failed to record bid")?;
+        }
+        db_tx.commit().await.context("Failed to commit bids")?;
+        
+        Ok(())
+    }
+}
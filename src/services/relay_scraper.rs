@@ -0,0 +1,291 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::{
+    config::{RelayConfig, RelayScraperConfig},
+    database::{DbHealth, DbPool},
+};
+
+/// Data API path for payloads delivered to proposers
+const DELIVERED_PAYLOADS_PATH: &str = "/relay/v1/data/bidtraces/proposer_payload_delivered";
+
+/// Data API path for bids received by the relay
+const RECEIVED_BIDS_PATH: &str = "/relay/v1/data/bidtraces/builder_blocks_received";
+
+/// A bid trace as returned by the relay data API; numbers are decimal strings
+#[derive(Debug, Deserialize)]
+struct BidTrace {
+    slot: String,
+    block_hash: String,
+    builder_pubkey: String,
+    #[serde(default)]
+    proposer_pubkey: String,
+    #[serde(default)]
+    proposer_fee_recipient: String,
+    value: String,
+    #[serde(default)]
+    block_number: String,
+    #[serde(default)]
+    gas_used: String,
+    #[serde(default)]
+    num_tx: String,
+    #[serde(default)]
+    timestamp_ms: String,
+}
+
+/// How our bids compared to the market for one slot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlotMarketComparison {
+    pub slot: u64,
+    /// Value of the payload delivered to the proposer, the market-clearing bid
+    pub winning_value_wei: String,
+    pub winning_builder: String,
+    /// Highest bid any relay reported receiving
+    pub top_bid_wei: Option<String>,
+    /// Highest bid we submitted, if we bid
+    pub our_top_bid_wei: Option<String>,
+    /// Winning value minus our top bid, negative when we outbid the winner
+    pub gap_wei: Option<String>,
+    /// Graffiti of the deployment that placed our top bid
+    pub our_graffiti: Option<String>,
+    /// Whether the winning builder is a public key we bid with; every bid we record carries one,
+    /// as bidding requires `builder_pubkey`
+    pub won: bool,
+}
+
+/// Periodically pulls delivered payloads and bid traces from public relay data APIs
+#[derive(Clone)]
+pub struct RelayScraper {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// HTTP client for the data APIs
+    http: reqwest::Client,
+    /// Relays to scrape
+    relays: Vec<RelayConfig>,
+    /// Configuration
+    config: RelayScraperConfig,
+}
+
+impl RelayScraper {
+    /// Create a new relay scraper
+    pub fn new(
+        db_pool: DbPool,
+        health: DbHealth,
+        relays: Vec<RelayConfig>,
+        config: RelayScraperConfig,
+    ) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to create relay data API client")?;
+        
+        Ok(Self {
+            db_pool,
+            health,
+            http,
+            relays,
+            config,
+        })
+    }
+    
+    /// Whether scraping is enabled
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Scrape every relay, continuing past relays that fail
+    pub async fn scrape(&self) -> Result<()> {
+        for relay in &self.relays {
+            if let Err(e) = self.scrape_relay(relay).await {
+                warn!("Failed to scrape relay {}: {}", relay.name, e);
+            }
+        }
+        
+        Ok(())
+    }
+    
+    async fn scrape_relay(&self, relay: &RelayConfig) -> Result<()> {
+        let limit = self.config.page_limit.to_string();
+        let payloads = self
+            .fetch(relay, DELIVERED_PAYLOADS_PATH, &[("limit", limit.as_str())])
+            .await?;
+        let new_slots = self.health.retry(|| self.store_payloads(relay, &payloads)).await?;
+        
+        // Bid traces are fetched once per slot, resuming the slots an earlier scrape failed on
+        let pending = self.health.retry(|| self.unscraped_slots(relay)).await?;
+        
+        for slot in pending {
+            if let Err(e) = self.scrape_bids(relay, slot).await {
+                warn!("Failed to scrape bid traces for slot {} from relay {}: {}", slot, relay.name, e);
+                continue;
+            }
+            self.health.retry(|| self.mark_scraped(relay, slot)).await?;
+        }
+        
+        debug!("Scraped {} new slots from relay {}", new_slots, relay.name);
+        Ok(())
+    }
+    
+    async fn scrape_bids(&self, relay: &RelayConfig, slot: i64) -> Result<()> {
+        let slot_param = slot.to_string();
+        let bids = self
+            .fetch(relay, RECEIVED_BIDS_PATH, &[("slot", slot_param.as_str())])
+            .await?;
+        
+        self.health.retry(|| self.store_bids(relay, slot, &bids)).await
+    }
+    
+    /// Store a relay's delivered payloads, returning how many slots are new
+    async fn store_payloads(&self, relay: &RelayConfig, payloads: &[BidTrace]) -> Result<u64> {
+        let mut new_slots = 0;
+        for payload in payloads {
+            let slot: i64 = match payload.slot.parse() {
+                Ok(slot) => slot,
+                Err(_) => {
+                    warn!("Skipping delivered payload with invalid slot {:?} from {}", payload.slot, relay.name);
+                    continue;
+                }
+            };
+            
+            new_slots += sqlx::query(
+                "INSERT INTO relay_delivered_payloads
+                 (relay, slot, block_number, block_hash, builder_pubkey, proposer_pubkey, proposer_fee_recipient,
+                  value_wei, gas_used, num_tx)
+                 VALUES ($1, $2, $3, $4, $5, $6, NULLIF($7, ''), $8::NUMERIC, $9, $10)
+                 ON CONFLICT (relay, slot) DO NOTHING",
+            )
+            .bind(&relay.name)
+            .bind(slot)
+            .bind(payload.block_number.parse::<i64>().unwrap_or_default())
+            .bind(&payload.block_hash)
+            .bind(&payload.builder_pubkey)
+            .bind(&payload.proposer_pubkey)
+            .bind(&payload.proposer_fee_recipient)
+            .bind(&payload.value)
+            .bind(payload.gas_used.parse::<i64>().unwrap_or_default())
+            .bind(payload.num_tx.parse::<i64>().unwrap_or_default())
+            .execute(&self.db_pool)
+            .await
+            .context("Failed to store delivered payload")?
+            .rows_affected();
+        }
+        
+        Ok(new_slots)
+    }
+    
+    async fn unscraped_slots(&self, relay: &RelayConfig) -> Result<Vec<i64>> {
+        sqlx::query_scalar(
+            "SELECT slot FROM relay_delivered_payloads
+             WHERE relay = $1 AND bids_scraped_at IS NULL
+             ORDER BY slot DESC
+             LIMIT $2",
+        )
+        .bind(&relay.name)
+        .bind(self.config.page_limit as i64)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to load slots awaiting bid traces")
+    }
+    
+    async fn mark_scraped(&self, relay: &RelayConfig, slot: i64) -> Result<()> {
+        sqlx::query(
+            "UPDATE relay_delivered_payloads SET bids_scraped_at = NOW()
+             WHERE relay = $1 AND slot = $2",
+        )
+        .bind(&relay.name)
+        .bind(slot)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to mark bid traces scraped")?;
+        
+        Ok(())
+    }
+    
+    async fn store_bids(&self, relay: &RelayConfig, slot: i64, bids: &[BidTrace]) -> Result<()> {
+        for bid in bids {
+            sqlx::query(
+                "INSERT INTO relay_bid_traces (relay, slot, block_hash, builder_pubkey, value_wei, received_at_ms)
+                 VALUES ($1, $2, $3, $4, $5::NUMERIC, $6)
+                 ON CONFLICT (relay, slot, block_hash) DO NOTHING",
+            )
+            .bind(&relay.name)
+            .bind(slot)
+            .bind(&bid.block_hash)
+            .bind(&bid.builder_pubkey)
+            .bind(&bid.value)
+            .bind(bid.timestamp_ms.parse::<i64>().unwrap_or_default())
+            .execute(&self.db_pool)
+            .await
+            .context("Failed to store bid trace")?;
+        }
+        
+        Ok(())
+    }
+    
+    async fn fetch(&self, relay: &RelayConfig, path: &str, query: &[(&str, &str)]) -> Result<Vec<BidTrace>> {
+        let response = self
+            .http
+            .get(format!("{}{}", relay.url.trim_end_matches('/'), path))
+            .query(query)
+            .send()
+            .await?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow!("Relay {} data API returned {}", relay.name, response.status()));
+        }
+        
+        Ok(response.json().await?)
+    }
+    
+    /// Compare our bids to the market-clearing bid for the most recent scraped slots
+    pub async fn slot_comparisons(&self, limit: i64) -> Result<Vec<SlotMarketComparison>> {
+        self.health.retry(|| self.slot_comparisons_once(limit)).await
+    }
+    
+    async fn slot_comparisons_once(&self, limit: i64) -> Result<Vec<SlotMarketComparison>> {
+        let rows = sqlx::query(
+            "WITH winners AS (
+                 SELECT DISTINCT ON (slot) slot, value_wei, builder_pubkey
+                 FROM relay_delivered_payloads
+                 ORDER BY slot DESC, value_wei DESC
+                 LIMIT $1
+             )
+             SELECT w.slot,
+                    w.value_wei::TEXT AS winning_value_wei,
+                    w.builder_pubkey AS winning_builder,
+                    (SELECT MAX(t.value_wei) FROM relay_bid_traces t WHERE t.slot = w.slot)::TEXT AS top_bid_wei,
+                    (SELECT MAX(b.value_wei) FROM builder_bids b WHERE b.slot = w.slot)::TEXT AS our_top_bid_wei,
+                    (w.value_wei - (SELECT MAX(b.value_wei) FROM builder_bids b WHERE b.slot = w.slot))::TEXT AS gap_wei,
+                    (SELECT b.graffiti FROM builder_bids b WHERE b.slot = w.slot
+                     ORDER BY b.value_wei DESC LIMIT 1) AS our_graffiti,
+                    EXISTS (SELECT 1 FROM builder_bids b
+                            WHERE b.slot = w.slot AND LOWER(b.builder_pubkey) = LOWER(w.builder_pubkey)) AS won
+             FROM winners w
+             ORDER BY w.slot DESC",
+        )
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to compare bids to market")?;
+        
+        rows.into_iter()
+            .map(|row| {
+                Ok(SlotMarketComparison {
+                    slot: row.try_get::<i64, _>("slot")? as u64,
+                    winning_value_wei: row.try_get("winning_value_wei")?,
+                    winning_builder: row.try_get("winning_builder")?,
+                    top_bid_wei: row.try_get("top_bid_wei")?,
+                    our_top_bid_wei: row.try_get("our_top_bid_wei")?,
+                    gap_wei: row.try_get("gap_wei")?,
+                    our_graffiti: row.try_get("our_graffiti")?,
+                    won: row.try_get("won")?,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,169 @@
+use anyhow::Result;
+use ethers::types::{H256, U256};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, sync::Arc};
+use tracing::debug;
+
+use crate::{
+    api::models,
+    blockchain::BlockchainClient,
+    services::{
+        build_decisions::{BuildDecision, BuildDecisionLog, TemplateSummary},
+        recovery::RecoveryService,
+        transaction::{InclusionCandidate, TransactionService},
+    },
+};
+
+/// Reconstruction of what the builder knew at a past slot, with a re-run of the ordering
+///
+/// The re-run is compared against the slot's recorded build decision: its template summary
+/// and the last bid submitted. Sensitive candidates are never archived, so a slot whose
+/// template held some replays without them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlotReplay {
+    /// Beacon slot that was built for
+    pub slot: u64,
+    /// Head block the builder was building on
+    pub head_block: u64,
+    /// Template and bid recorded for the slot
+    pub decision: BuildDecision,
+    /// Candidates known at the head, with their simulated profit, in recorded template order
+    pub candidates: Vec<InclusionCandidate>,
+    /// Template order that was recorded at the time
+    pub recorded_order: Vec<H256>,
+    /// Template order produced by re-running the current ordering
+    pub replayed_order: Vec<H256>,
+    /// Transactions whose position differs between recorded and replayed order
+    pub reordered: Vec<H256>,
+    /// Summary of the replayed template
+    pub replayed: TemplateSummary,
+    /// Whether the replayed template has the ordering hash the recorded decision has
+    pub ordering_matches: bool,
+    /// What the replayed template would bid, its value plus the subsidy the recorded bid got
+    #[serde(with = "models::wei")]
+    pub replayed_bid: U256,
+    /// Whether the block we bid is the one that landed, None if no bid was submitted
+    pub won: Option<bool>,
+    /// Candidates that actually landed in the following block
+    pub landed: Vec<H256>,
+    /// Candidates that did not land in the following block
+    pub missed: Vec<H256>,
+}
+
+/// Service for replaying past slots from persisted records
+#[derive(Clone)]
+pub struct ReplayService {
+    /// Blockchain client
+    blockchain_client: Arc<BlockchainClient>,
+    /// Recovery service holding the per-head archive
+    recovery_service: RecoveryService,
+    /// Per-slot build decisions, mapping a slot to its head and what was bid
+    build_decisions: BuildDecisionLog,
+    /// Transaction service, ordering templates as live building does
+    transaction_service: TransactionService,
+}
+
+impl ReplayService {
+    /// Create a new replay service
+    pub fn new(
+        blockchain_client: Arc<BlockchainClient>,
+        recovery_service: RecoveryService,
+        build_decisions: BuildDecisionLog,
+        transaction_service: TransactionService,
+    ) -> Result<Self> {
+        Ok(Self {
+            blockchain_client,
+            recovery_service,
+            build_decisions,
+            transaction_service,
+        })
+    }
+    
+    /// Replay a past beacon slot, returning None if its template or in-flight state wasn't recorded
+    pub async fn replay_slot(&self, slot: u64) -> Result<Option<SlotReplay>> {
+        let decision = match self.build_decisions.get(slot).await? {
+            Some(decision) => decision,
+            None => return Ok(None),
+        };
+        // Slots bid before heads were recorded, or never templated, have nothing to replay from
+        let head_block = match decision.head_block {
+            Some(head_block) => head_block,
+            None => return Ok(None),
+        };
+        let state = match self.recovery_service.load_snapshot(head_block).await? {
+            Some(state) => state,
+            None => return Ok(None),
+        };
+        
+        debug!(
+            "Replaying slot {} on block {} with {} candidates",
+            slot,
+            head_block,
+            state.candidates.len()
+        );
+        
+        let recorded_order: Vec<H256> = state.candidates.iter().map(|c| c.tx.hash).collect();
+        let next_block = self.blockchain_client.get_block_with_hashes(head_block + 1).await?;
+        
+        // Re-run the configured ordering over the reconstructed candidate set, for the block built
+        let base_fee = match next_block.as_ref().and_then(|block| block.base_fee_per_gas) {
+            Some(base_fee) => base_fee,
+            None => self
+                .blockchain_client
+                .get_block_with_hashes(head_block)
+                .await?
+                .and_then(|block| block.base_fee_per_gas)
+                .unwrap_or_default(),
+        };
+        let context = self.transaction_service.block_context(base_fee);
+        let (template, replayed) = self.transaction_service.order_template(state.candidates.clone(), &context);
+        let replayed_order: Vec<H256> = template.iter().map(|c| c.tx.hash).collect();
+        
+        let reordered = recorded_order
+            .iter()
+            .zip(replayed_order.iter())
+            .filter(|(recorded, replayed)| recorded != replayed)
+            .map(|(recorded, _)| *recorded)
+            .collect();
+        
+        let replayed_hash = format!("{:?}", replayed.ordering_hash);
+        let ordering_matches = decision.ordering_hash.as_deref() == Some(replayed_hash.as_str());
+        let subsidy = decision
+            .subsidy_wei
+            .as_deref()
+            .and_then(|subsidy| U256::from_dec_str(subsidy).ok())
+            .unwrap_or_default();
+        let replayed_bid = replayed.value.saturating_add(subsidy);
+        
+        // Compare against what actually landed in the next block
+        let won = decision.block_hash.as_ref().map(|bid_hash| {
+            next_block
+                .as_ref()
+                .and_then(|block| block.hash)
+                .map_or(false, |hash| format!("{:?}", hash) == *bid_hash)
+        });
+        let landed_hashes: HashSet<H256> = next_block
+            .map(|block| block.transactions.into_iter().collect())
+            .unwrap_or_default();
+        
+        let (landed, missed) = recorded_order
+            .iter()
+            .partition(|hash| landed_hashes.contains(hash));
+        
+        Ok(Some(SlotReplay {
+            slot,
+            head_block,
+            decision,
+            candidates: state.candidates,
+            recorded_order,
+            replayed_order,
+            reordered,
+            replayed,
+            ordering_matches,
+            replayed_bid,
+            won,
+            landed,
+            missed,
+        }))
+    }
+}
@@ -0,0 +1,364 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::types::{Block, Transaction, TransactionReceipt, H256, U256};
+use parking_lot::{Mutex, RwLock};
+use sqlx::{postgres::PgRow, Row};
+use std::{collections::HashMap, sync::Arc};
+use tracing::debug;
+
+use crate::{
+    blockchain::BlockchainClient,
+    config::SearcherReputationConfig,
+    database::{DbHealth, DbPool},
+    services::simulation::SimulationPriority,
+    utils::units::wei_to_eth,
+};
+
+/// Landings whose block hasn't been booked within this many blocks are dropped unscored
+const MAX_SETTLEMENT_LAG_BLOCKS: u64 = 64;
+
+/// Share of the score from the landing rate of simulated transactions
+const LANDING_WEIGHT: f64 = 0.4;
+
+/// Share of the score from the simulation success rate
+const SUCCESS_WEIGHT: f64 = 0.4;
+
+/// Share of the score from paid against promised payment
+const PAYMENT_WEIGHT: f64 = 0.2;
+
+/// A bundle refused because its searcher's reputation is low while the builder is loaded
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("searcher {searcher} is throttled while the builder is under load")]
+pub struct Throttled {
+    pub searcher: String,
+}
+
+/// Track record of one searcher
+#[derive(Debug, Clone, Default)]
+pub struct SearcherStats {
+    pub bundles_submitted: u64,
+    /// Bundles refused under load
+    pub bundles_throttled: u64,
+    pub txs_simulated: u64,
+    pub txs_failed: u64,
+    pub txs_landed: u64,
+    /// Simulated coinbase payment of the landed transactions
+    pub promised: U256,
+    /// Priority fees and coinbase transfers the landed transactions actually paid
+    ///
+    /// Transfers from inside contract calls are traced; on nodes without block traces only
+    /// plain transfers to the fee recipient count.
+    pub paid: U256,
+}
+
+impl SearcherStats {
+    fn add(&mut self, other: &Self) {
+        self.bundles_submitted += other.bundles_submitted;
+        self.bundles_throttled += other.bundles_throttled;
+        self.txs_simulated += other.txs_simulated;
+        self.txs_failed += other.txs_failed;
+        self.txs_landed += other.txs_landed;
+        self.promised = self.promised.saturating_add(other.promised);
+        self.paid = self.paid.saturating_add(other.paid);
+    }
+    
+    /// Score between 0 and 1, 1.0 until there are enough observations
+    pub fn score(&self, min_observations: u64) -> f64 {
+        if self.txs_simulated == 0 || self.txs_simulated < min_observations {
+            return 1.0;
+        }
+        
+        let succeeded = self.txs_simulated.saturating_sub(self.txs_failed);
+        let success = succeeded as f64 / self.txs_simulated as f64;
+        let landing = if succeeded == 0 {
+            0.0
+        } else {
+            (self.txs_landed as f64 / succeeded as f64).min(1.0)
+        };
+        let payment = if self.promised.is_zero() {
+            1.0
+        } else {
+            (wei_to_eth(self.paid) / wei_to_eth(self.promised)).min(1.0)
+        };
+        
+        LANDING_WEIGHT * landing + SUCCESS_WEIGHT * success + PAYMENT_WEIGHT * payment
+    }
+}
+
+/// A searcher transaction seen landing, waiting for its receipt
+struct PendingLanding {
+    searcher: String,
+    promised: U256,
+    block_number: u64,
+}
+
+/// Per-searcher reputation from landing rate, simulation failures and payment accuracy
+///
+/// Events are counted in memory and written to `searcher_reputation` by the flush job, so
+/// recording never waits on the database. Scores order sealed bundle simulation and decide
+/// which searchers are refused new bundles while the simulation queue is deep.
+#[derive(Clone)]
+pub struct ReputationService {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Configuration
+    config: SearcherReputationConfig,
+    /// Persisted track records as of the last flush
+    stored: Arc<RwLock<HashMap<String, SearcherStats>>>,
+    /// Counts recorded since the last flush
+    unflushed: Arc<Mutex<HashMap<String, SearcherStats>>>,
+    /// Landed searcher transactions waiting for their block's receipts
+    landings: Arc<Mutex<HashMap<H256, PendingLanding>>>,
+}
+
+impl ReputationService {
+    /// Create a new reputation service
+    pub fn new(db_pool: DbPool, health: DbHealth, config: SearcherReputationConfig) -> Result<Self> {
+        Ok(Self {
+            db_pool,
+            health,
+            config,
+            stored: Arc::new(RwLock::new(HashMap::new())),
+            unflushed: Arc::new(Mutex::new(HashMap::new())),
+            landings: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+    
+    /// Whether searchers are scored
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Load the persisted track records
+    pub async fn load(&self) -> Result<()> {
+        if !self.enabled() {
+            return Ok(());
+        }
+        
+        let stored = self.health.retry(|| self.load_once()).await?;
+        debug!("Loaded reputation of {} searchers", stored.len());
+        *self.stored.write() = stored;
+        
+        Ok(())
+    }
+    
+    fn record(&self, searcher: &str, update: impl FnOnce(&mut SearcherStats)) {
+        if !self.enabled() {
+            return;
+        }
+        
+        update(self.unflushed.lock().entry(searcher.to_string()).or_default());
+    }
+    
+    /// Record an accepted bundle
+    pub fn record_bundle_submitted(&self, searcher: &str) {
+        self.record(searcher, |stats| stats.bundles_submitted += 1);
+    }
+    
+    /// Record the simulation of one of a searcher's transactions
+    pub fn record_simulation(&self, searcher: &str, succeeded: bool) {
+        self.record(searcher, |stats| {
+            stats.txs_simulated += 1;
+            if !succeeded {
+                stats.txs_failed += 1;
+            }
+        });
+    }
+    
+    /// Record a searcher transaction included in a block, scored once the block is booked
+    pub fn record_landed(&self, searcher: &str, tx_hash: H256, promised: U256, block_number: u64) {
+        if !self.enabled() {
+            return;
+        }
+        
+        self.landings.lock().insert(
+            tx_hash,
+            PendingLanding {
+                searcher: searcher.to_string(),
+                promised,
+                block_number,
+            },
+        );
+    }
+    
+    /// Score the landings in a booked block against what their transactions paid
+    pub async fn settle_block(
+        &self,
+        blockchain_client: &BlockchainClient,
+        block: &Block<Transaction>,
+    ) -> Result<()> {
+        let block_number = block.number.unwrap_or_default().as_u64();
+        let any_due = {
+            let mut landings = self.landings.lock();
+            landings.retain(|_, landing| landing.block_number + MAX_SETTLEMENT_LAG_BLOCKS >= block_number);
+            block.transactions.iter().any(|tx| landings.contains_key(&tx.hash))
+        };
+        
+        if !any_due {
+            return Ok(());
+        }
+        
+        let receipts: HashMap<H256, TransactionReceipt> = blockchain_client
+            .get_block_receipts(block_number)
+            .await?
+            .into_iter()
+            .map(|receipt| (receipt.transaction_hash, receipt))
+            .collect();
+        // Searchers mostly pay with a transfer to the fee recipient rather than priority fees
+        let payments = blockchain_client.get_block_payments(block, &receipts).await?;
+        
+        // Taken only once the receipts are in, so a failed fetch leaves them for a retry and a
+        // second pass over the block finds nothing left to count
+        let due: Vec<(H256, PendingLanding)> = {
+            let mut landings = self.landings.lock();
+            block
+                .transactions
+                .iter()
+                .filter_map(|tx| landings.remove(&tx.hash).map(|landing| (tx.hash, landing)))
+                .collect()
+        };
+        
+        for (tx_hash, landing) in due {
+            let paid = payments.get(&tx_hash).copied().unwrap_or_default();
+            self.record(&landing.searcher, |stats| {
+                stats.txs_landed += 1;
+                stats.promised = stats.promised.saturating_add(landing.promised);
+                stats.paid = stats.paid.saturating_add(paid);
+            });
+        }
+        
+        Ok(())
+    }
+    
+    /// Current score of a searcher between 0 and 1, unflushed counts included
+    pub fn score(&self, searcher: &str) -> f64 {
+        if !self.enabled() {
+            return 1.0;
+        }
+        
+        let mut stats = self.stored.read().get(searcher).cloned().unwrap_or_default();
+        if let Some(unflushed) = self.unflushed.lock().get(searcher) {
+            stats.add(unflushed);
+        }
+        stats.score(self.config.min_observations)
+    }
+    
+    /// Simulation lane for a searcher with this score
+    pub fn priority(&self, score: f64) -> SimulationPriority {
+        if score < self.config.low_priority_below {
+            SimulationPriority::Low
+        } else {
+            SimulationPriority::Normal
+        }
+    }
+    
+    /// Refuse a low-reputation searcher's bundle while the simulation queue is this deep
+    pub fn admit(&self, searcher: &str, queued_simulations: usize) -> Result<(), Throttled> {
+        let loaded = queued_simulations >= self.config.high_load_queue_depth;
+        if !loaded || self.score(searcher) >= self.config.throttle_below {
+            return Ok(());
+        }
+        
+        self.record(searcher, |stats| stats.bundles_throttled += 1);
+        metrics::counter!("searcher_bundles_throttled_total", 1);
+        
+        Err(Throttled {
+            searcher: searcher.to_string(),
+        })
+    }
+    
+    /// Write the counts recorded since the last flush
+    pub async fn flush(&self) -> Result<()> {
+        let mut pending: Vec<_> = std::mem::take(&mut *self.unflushed.lock()).into_iter().collect();
+        
+        while let Some((searcher, delta)) = pending.pop() {
+            match self.health.retry(|| self.store(&searcher, &delta)).await {
+                Ok(stats) => {
+                    let score = stats.score(self.config.min_observations);
+                    metrics::gauge!("searcher_reputation_score", score, "searcher" => searcher.clone());
+                    self.stored.write().insert(searcher, stats);
+                }
+                Err(e) => {
+                    // Keep what wasn't written for the next flush
+                    let mut unflushed = self.unflushed.lock();
+                    for (searcher, delta) in pending.into_iter().chain(Some((searcher, delta))) {
+                        unflushed.entry(searcher).or_default().add(&delta);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    async fn load_once(&self) -> Result<HashMap<String, SearcherStats>> {
+        let rows = sqlx::query(
+            "SELECT searcher, bundles_submitted, bundles_throttled, txs_simulated, txs_failed, txs_landed,
+                    promised_wei::TEXT AS promised, paid_wei::TEXT AS paid
+             FROM searcher_reputation",
+        )
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to load searcher reputation")?;
+        
+        let mut stored = HashMap::with_capacity(rows.len());
+        for row in rows {
+            stored.insert(row.try_get("searcher")?, stats_from_row(&row)?);
+        }
+        Ok(stored)
+    }
+    
+    /// Add counts to a searcher's row, returning the new totals
+    async fn store(&self, searcher: &str, delta: &SearcherStats) -> Result<SearcherStats> {
+        let row = sqlx::query(
+            "INSERT INTO searcher_reputation
+                 (searcher, bundles_submitted, bundles_throttled, txs_simulated, txs_failed, txs_landed,
+                  promised_wei, paid_wei, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7::NUMERIC, $8::NUMERIC, NOW())
+             ON CONFLICT (searcher) DO UPDATE SET
+                 bundles_submitted = searcher_reputation.bundles_submitted + EXCLUDED.bundles_submitted,
+                 bundles_throttled = searcher_reputation.bundles_throttled + EXCLUDED.bundles_throttled,
+                 txs_simulated = searcher_reputation.txs_simulated + EXCLUDED.txs_simulated,
+                 txs_failed = searcher_reputation.txs_failed + EXCLUDED.txs_failed,
+                 txs_landed = searcher_reputation.txs_landed + EXCLUDED.txs_landed,
+                 promised_wei = searcher_reputation.promised_wei + EXCLUDED.promised_wei,
+                 paid_wei = searcher_reputation.paid_wei + EXCLUDED.paid_wei,
+                 updated_at = NOW()
+             RETURNING bundles_submitted, bundles_throttled, txs_simulated, txs_failed, txs_landed,
+                       promised_wei::TEXT AS promised, paid_wei::TEXT AS paid",
+        )
+        .bind(searcher)
+        .bind(delta.bundles_submitted as i64)
+        .bind(delta.bundles_throttled as i64)
+        .bind(delta.txs_simulated as i64)
+        .bind(delta.txs_failed as i64)
+        .bind(delta.txs_landed as i64)
+        .bind(delta.promised.to_string())
+        .bind(delta.paid.to_string())
+        .fetch_one(&self.db_pool)
+        .await
+        .with_context(|| format!("Failed to store reputation of {}", searcher))?;
+        
+        stats_from_row(&row)
+    }
+}
+
+fn stats_from_row(row: &PgRow) -> Result<SearcherStats> {
+    let count = |column: &str| -> Result<u64> { Ok(row.try_get::<i64, _>(column)?.max(0) as u64) };
+    let wei = |column: &str| -> Result<U256> {
+        let text: String = row.try_get(column)?;
+        U256::from_dec_str(&text).map_err(|e| anyhow!("Invalid wei amount in {}: {}", column, e))
+    };
+    
+    Ok(SearcherStats {
+        bundles_submitted: count("bundles_submitted")?,
+        bundles_throttled: count("bundles_throttled")?,
+        txs_simulated: count("txs_simulated")?,
+        txs_failed: count("txs_failed")?,
+        txs_landed: count("txs_landed")?,
+        promised: wei("promised")?,
+        paid: wei("paid")?,
+    })
+}
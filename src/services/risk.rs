@@ -0,0 +1,284 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use ethers::types::U256;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::{error, info};
+
+use crate::{
+    api::models,
+    config::{ExecutionMode, RiskConfig},
+    services::{
+        alerting::{Alert, AlertManager, Severity},
+        controls::SubsystemControls,
+    },
+    utils::units::wei_to_eth,
+};
+
+/// Subsystem whose pause flips the process to a dry run, persisted like any other pause
+pub const LIVE_SUBMISSION: &str = "live_submission";
+
+/// Why live submission was halted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Halt {
+    pub reason: String,
+    /// Halted by an operator rather than a tripped threshold
+    pub manual: bool,
+    pub at: DateTime<Utc>,
+}
+
+/// Risk windows and whether live submission is halted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskStatus {
+    pub enabled: bool,
+    pub execution_mode: ExecutionMode,
+    /// Whether transactions, bundles and bids are currently held back
+    pub dry_run: bool,
+    pub halted: bool,
+    /// Unknown for a halt persisted before a restart
+    pub halt: Option<Halt>,
+    pub window_seconds: u64,
+    #[serde(with = "models::wei")]
+    pub losses: U256,
+    pub bundles: usize,
+    pub failed_bundles: usize,
+    pub landed_candidates: usize,
+    pub unexpected_reverts: usize,
+}
+
+#[derive(Default)]
+struct RiskWindows {
+    losses: VecDeque<(Instant, U256)>,
+    /// Bundle submissions and whether the relay accepted them
+    bundles: VecDeque<(Instant, bool)>,
+    /// Landed candidates and whether they reverted despite simulating successfully
+    landed: VecDeque<(Instant, bool)>,
+}
+
+impl RiskWindows {
+    fn prune(&mut self, window: Duration) {
+        let now = Instant::now();
+        let expired = |at: &Instant| now.duration_since(*at) > window;
+        while self.losses.front().map_or(false, |(at, _)| expired(at)) {
+            self.losses.pop_front();
+        }
+        while self.bundles.front().map_or(false, |(at, _)| expired(at)) {
+            self.bundles.pop_front();
+        }
+        while self.landed.front().map_or(false, |(at, _)| expired(at)) {
+            self.landed.pop_front();
+        }
+    }
+    
+    fn losses(&self) -> U256 {
+        self.losses.iter().fold(U256::zero(), |sum, (_, loss)| sum.saturating_add(*loss))
+    }
+    
+    fn failed_bundles(&self) -> usize {
+        self.bundles.iter().filter(|(_, accepted)| !accepted).count()
+    }
+    
+    fn unexpected_reverts(&self) -> usize {
+        self.landed.iter().filter(|(_, reverted)| *reverted).count()
+    }
+}
+
+/// Rate of failures among samples, once there are enough samples to judge
+fn rate(failures: usize, samples: usize, min_samples: usize) -> Option<f64> {
+    (samples >= min_samples.max(1)).then(|| failures as f64 / samples as f64)
+}
+
+/// Circuit breaker over live submission
+///
+/// Losses, relay-rejected bundles and candidates that reverted on chain after simulating
+/// successfully are tracked over a sliding window. Crossing a configured threshold halts live
+/// submission: everything runs on as a dry run until an operator resumes it. The halt is held as
+/// a pause of the `live_submission` subsystem, so it survives restarts.
+#[derive(Clone)]
+pub struct RiskManager {
+    /// Persisted pause of live submission
+    controls: SubsystemControls,
+    /// Alert manager, notified when submission is halted
+    alert_manager: AlertManager,
+    /// Configured execution mode, a dry run whatever the breaker says
+    execution_mode: ExecutionMode,
+    windows: Arc<Mutex<RiskWindows>>,
+    halt: Arc<Mutex<Option<Halt>>>,
+    max_loss: U256,
+    /// Configuration
+    config: RiskConfig,
+}
+
+impl RiskManager {
+    /// Create a new risk manager
+    pub fn new(
+        config: RiskConfig,
+        execution_mode: ExecutionMode,
+        controls: SubsystemControls,
+        alert_manager: AlertManager,
+    ) -> Result<Self> {
+        let max_loss = U256::from_dec_str(&config.max_loss_wei).context("Invalid risk max_loss_wei")?;
+        
+        controls.register(LIVE_SUBMISSION);
+        if controls.is_paused(LIVE_SUBMISSION) {
+            error!("Live submission is halted by persisted state, running as a dry run");
+        }
+        
+        Ok(Self {
+            controls,
+            alert_manager,
+            execution_mode,
+            windows: Arc::new(Mutex::new(RiskWindows::default())),
+            halt: Arc::new(Mutex::new(None)),
+            max_loss,
+            config,
+        })
+    }
+    
+    /// Whether the breaker trips on its own
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Whether transactions, bundles and bids must be held back
+    pub fn is_dry_run(&self) -> bool {
+        self.execution_mode.is_dry_run() || self.controls.is_paused(LIVE_SUBMISSION)
+    }
+    
+    /// Book a realized loss, such as paying the proposer more than the block earned
+    pub async fn record_loss(&self, loss: U256) {
+        self.windows.lock().losses.push_back((Instant::now(), loss));
+        self.evaluate().await;
+    }
+    
+    /// Book a bundle submission and whether the relay accepted it
+    pub async fn record_bundle(&self, accepted: bool) {
+        self.windows.lock().bundles.push_back((Instant::now(), accepted));
+        self.evaluate().await;
+    }
+    
+    /// Book candidates that landed, of which `reverted` reverted on chain
+    pub async fn record_landed(&self, landed: usize, reverted: usize) {
+        {
+            let mut windows = self.windows.lock();
+            let now = Instant::now();
+            for index in 0..landed {
+                windows.landed.push_back((now, index < reverted));
+            }
+        }
+        self.evaluate().await;
+    }
+    
+    /// Halt live submission by hand
+    pub async fn halt(&self, reason: &str) -> Result<()> {
+        self.set_halted(reason.to_string(), true).await
+    }
+    
+    /// Resume live submission, starting the windows afresh so the same events don't trip it again
+    pub async fn resume(&self) -> Result<()> {
+        self.controls.set_paused(LIVE_SUBMISSION, false).await?;
+        *self.windows.lock() = RiskWindows::default();
+        *self.halt.lock() = None;
+        metrics::gauge!("risk_halted", 0.0);
+        info!("Live submission resumed");
+        
+        Ok(())
+    }
+    
+    pub fn status(&self) -> RiskStatus {
+        let mut windows = self.windows.lock();
+        windows.prune(Duration::from_secs(self.config.window_seconds));
+        let halted = self.controls.is_paused(LIVE_SUBMISSION);
+        
+        RiskStatus {
+            enabled: self.config.enabled,
+            execution_mode: self.execution_mode,
+            dry_run: self.is_dry_run(),
+            halted,
+            halt: self.halt.lock().clone().filter(|_| halted),
+            window_seconds: self.config.window_seconds,
+            losses: windows.losses(),
+            bundles: windows.bundles.len(),
+            failed_bundles: windows.failed_bundles(),
+            landed_candidates: windows.landed.len(),
+            unexpected_reverts: windows.unexpected_reverts(),
+        }
+    }
+    
+    /// Halt live submission if a threshold is crossed
+    async fn evaluate(&self) {
+        self.windows.lock().prune(Duration::from_secs(self.config.window_seconds));
+        if !self.config.enabled || self.controls.is_paused(LIVE_SUBMISSION) {
+            return;
+        }
+        
+        let breach = {
+            let windows = self.windows.lock();
+            let min_samples = self.config.min_samples;
+            let losses = windows.losses();
+            let failed_bundle_rate = rate(windows.failed_bundles(), windows.bundles.len(), min_samples)
+                .filter(|rate| *rate > self.config.max_failed_bundle_rate);
+            let revert_rate = rate(windows.unexpected_reverts(), windows.landed.len(), min_samples)
+                .filter(|rate| *rate > self.config.max_unexpected_revert_rate);
+            
+            if losses > self.max_loss {
+                Some(format!(
+                    "lost {} ETH in the last {}s, limit {} ETH",
+                    wei_to_eth(losses),
+                    self.config.window_seconds,
+                    wei_to_eth(self.max_loss)
+                ))
+            } else if let Some(rate) = failed_bundle_rate {
+                Some(format!(
+                    "{:.0}% of bundles failed in the last {}s, limit {:.0}%",
+                    rate * 100.0,
+                    self.config.window_seconds,
+                    self.config.max_failed_bundle_rate * 100.0
+                ))
+            } else {
+                revert_rate.map(|rate| {
+                    format!(
+                        "{:.0}% of landed candidates reverted unexpectedly in the last {}s, limit {:.0}%",
+                        rate * 100.0,
+                        self.config.window_seconds,
+                        self.config.max_unexpected_revert_rate * 100.0
+                    )
+                })
+            }
+        };
+        
+        if let Some(reason) = breach {
+            if let Err(e) = self.set_halted(reason, false).await {
+                error!("Failed to halt live submission: {}", e);
+            }
+        }
+    }
+    
+    async fn set_halted(&self, reason: String, manual: bool) -> Result<()> {
+        self.controls.set_paused(LIVE_SUBMISSION, true).await?;
+        *self.halt.lock() = Some(Halt {
+            reason: reason.clone(),
+            manual,
+            at: Utc::now(),
+        });
+        metrics::gauge!("risk_halted", 1.0);
+        metrics::counter!("risk_halts_total", 1, "trigger" => if manual { "manual" } else { "threshold" });
+        error!("Live submission halted, running as a dry run: {}", reason);
+        
+        self.alert_manager
+            .fire(Alert::new(
+                "risk:halted",
+                Severity::Critical,
+                "risk",
+                format!("Live submission halted: {}", reason),
+            ))
+            .await;
+        
+        Ok(())
+    }
+}
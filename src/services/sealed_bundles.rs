@@ -0,0 +1,328 @@
+use anyhow::{anyhow, Context, Result};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Nonce,
+};
+use chrono::{DateTime, Utc};
+use ethers::{
+    types::{Bytes, Transaction},
+    utils::rlp,
+};
+use hkdf::Hkdf;
+use parking_lot::{Mutex, RwLock};
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::VecDeque, sync::Arc};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+use crate::{
+    config::SealedBundlesConfig,
+    services::{
+        reputation::ReputationService,
+        simulation::SimulationService,
+        transaction::{InclusionCandidate, TransactionService, TxSource},
+    },
+    utils::sensitive::Sensitive,
+};
+
+/// HKDF context binding derived keys to this scheme
+const HKDF_INFO: &[u8] = b"mev-capture sealed bundle v1";
+
+/// A published sealing key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealingKeyInfo {
+    /// Short identifier searchers echo back with each sealed bundle
+    pub key_id: String,
+    /// Hex-encoded X25519 public key
+    pub public_key: String,
+    pub created_at: DateTime<Utc>,
+    /// Whether new bundles should be sealed to this key
+    pub current: bool,
+}
+
+/// A bundle encrypted to one of our sealing keys
+///
+/// The ciphertext is ChaCha20-Poly1305 under a key derived with HKDF-SHA256 from the
+/// X25519 shared secret, salted with the ephemeral and recipient public keys. The target
+/// block number, big-endian, is the associated data. The plaintext is JSON
+/// `{"txs": ["0x<signed raw tx>", ...]}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedBundle {
+    pub key_id: String,
+    /// Hex-encoded sender ephemeral X25519 public key
+    pub ephemeral_public_key: String,
+    /// Hex-encoded 12-byte nonce
+    pub nonce: String,
+    /// Hex-encoded ciphertext including the authentication tag
+    pub ciphertext: String,
+    /// Block the bundle should be included in
+    pub target_block: u64,
+}
+
+/// Decrypted bundle contents
+#[derive(Deserialize)]
+struct BundlePlaintext {
+    txs: Vec<Bytes>,
+}
+
+struct SealingKey {
+    key_id: String,
+    secret: StaticSecret,
+    public: PublicKey,
+    created_at: DateTime<Utc>,
+}
+
+impl SealingKey {
+    fn generate() -> Self {
+        let secret = StaticSecret::random_from_rng(OsRng);
+        let public = PublicKey::from(&secret);
+        let key_id = hex::encode(&Sha256::digest(public.as_bytes())[..8]);
+        
+        Self {
+            key_id,
+            secret,
+            public,
+            created_at: Utc::now(),
+        }
+    }
+    
+    fn open(&self, bundle: &SealedBundle) -> Result<Zeroizing<Vec<u8>>> {
+        let ephemeral: [u8; 32] = decode_hex(&bundle.ephemeral_public_key)?
+            .try_into()
+            .map_err(|_| anyhow!("Ephemeral public key must be 32 bytes"))?;
+        let ephemeral = PublicKey::from(ephemeral);
+        let nonce: [u8; 12] = decode_hex(&bundle.nonce)?
+            .try_into()
+            .map_err(|_| anyhow!("Nonce must be 12 bytes"))?;
+        let ciphertext = decode_hex(&bundle.ciphertext)?;
+        
+        let shared = self.secret.diffie_hellman(&ephemeral);
+        let mut salt = Vec::with_capacity(64);
+        salt.extend_from_slice(ephemeral.as_bytes());
+        salt.extend_from_slice(self.public.as_bytes());
+        
+        let mut key = Zeroizing::new([0u8; 32]);
+        Hkdf::<Sha256>::new(Some(&salt), shared.as_bytes())
+            .expand(HKDF_INFO, key.as_mut())
+            .map_err(|_| anyhow!("Failed to derive bundle key"))?;
+        
+        let plaintext = ChaCha20Poly1305::new(key.as_ref().into())
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: &bundle.target_block.to_be_bytes(),
+                },
+            )
+            .map_err(|_| anyhow!("Failed to decrypt sealed bundle"))?;
+        
+        Ok(Zeroizing::new(plaintext))
+    }
+}
+
+/// A sealed bundle awaiting its target block
+struct PendingSealedBundle {
+    id: Uuid,
+    owner: String,
+    bundle: SealedBundle,
+}
+
+/// Accepts encrypted bundles and decrypts them only in memory when their block is built
+///
+/// Sealing keys live only in memory, so bundles sealed to keys from a previous process
+/// cannot be opened; searchers should fetch the published keys before each submission.
+#[derive(Clone)]
+pub struct SealedBundleService {
+    /// Configuration
+    config: SealedBundlesConfig,
+    /// Sealing keys, newest first
+    keys: Arc<RwLock<VecDeque<SealingKey>>>,
+    /// Sealed bundles waiting for their target block, never decrypted before then
+    pending: Arc<Mutex<Vec<PendingSealedBundle>>>,
+    /// Transaction service, receives opened bundles as inclusion candidates
+    transaction_service: TransactionService,
+    /// Simulation service, prices opened bundles
+    simulation_service: SimulationService,
+    /// Searcher track records, ordering opened bundles and refusing new ones under load
+    reputation: ReputationService,
+}
+
+impl SealedBundleService {
+    /// Create a new sealed bundle service with a fresh sealing key
+    pub fn new(
+        config: SealedBundlesConfig,
+        transaction_service: TransactionService,
+        simulation_service: SimulationService,
+        reputation: ReputationService,
+    ) -> Result<Self> {
+        let mut keys = VecDeque::new();
+        keys.push_front(SealingKey::generate());
+        
+        Ok(Self {
+            config,
+            keys: Arc::new(RwLock::new(keys)),
+            pending: Arc::new(Mutex::new(Vec::new())),
+            transaction_service,
+            simulation_service,
+            reputation,
+        })
+    }
+    
+    /// Whether sealed bundle submission is enabled
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Published sealing keys, newest first
+    pub fn public_keys(&self) -> Vec<SealingKeyInfo> {
+        self.keys
+            .read()
+            .iter()
+            .enumerate()
+            .map(|(index, key)| SealingKeyInfo {
+                key_id: key.key_id.clone(),
+                public_key: hex::encode(key.public.as_bytes()),
+                created_at: key.created_at,
+                current: index == 0,
+            })
+            .collect()
+    }
+    
+    /// Rotate the current key once it is older than the rotation period
+    pub fn rotate_if_due(&self) {
+        let age = self.keys.read().front().map(|key| Utc::now() - key.created_at);
+        let period = chrono::Duration::seconds(self.config.key_rotation_seconds as i64);
+        
+        if age.map_or(true, |age| age >= period) {
+            self.rotate();
+        }
+    }
+    
+    /// Generate a new current key, retiring the oldest beyond the retention count
+    pub fn rotate(&self) {
+        let key = SealingKey::generate();
+        info!("Rotated sealing key, new key id {}", key.key_id);
+        
+        let mut keys = self.keys.write();
+        keys.push_front(key);
+        keys.truncate(self.config.retained_keys.max(1));
+    }
+    
+    /// Accept a sealed bundle without decrypting it
+    ///
+    /// Fails with [`Throttled`](crate::services::reputation::Throttled) for low-reputation
+    /// searchers while the simulation queue is deep.
+    pub fn submit(&self, owner: String, bundle: SealedBundle) -> Result<Uuid> {
+        if !self.keys.read().iter().any(|key| key.key_id == bundle.key_id) {
+            return Err(anyhow!("Unknown or retired sealing key: {}", bundle.key_id));
+        }
+        
+        let (queued, _) = self.simulation_service.queue_depth();
+        self.reputation.admit(&owner, queued)?;
+        
+        let mut pending = self.pending.lock();
+        if pending.len() >= self.config.max_pending {
+            return Err(anyhow!("Too many pending sealed bundles"));
+        }
+        
+        let id = Uuid::new_v4();
+        debug!("Accepted sealed bundle {} from {} for block {}", id, owner, bundle.target_block);
+        self.reputation.record_bundle_submitted(&owner);
+        pending.push(PendingSealedBundle { id, owner, bundle });
+        
+        Ok(id)
+    }
+    
+    /// Open the bundles targeting a block and hand them to the builder as candidates
+    ///
+    /// Called once the parent block is known. Bundles for earlier blocks are dropped.
+    pub async fn include_for_block(&self, block_number: u64) -> Result<usize> {
+        let due: Vec<PendingSealedBundle> = {
+            let mut pending = self.pending.lock();
+            pending.retain(|p| p.bundle.target_block >= block_number);
+            let (due, rest) = std::mem::take(&mut *pending)
+                .into_iter()
+                .partition(|p| p.bundle.target_block == block_number);
+            *pending = rest;
+            due
+        };
+        
+        // Searchers with the better track record are simulated first
+        let mut due: Vec<(f64, PendingSealedBundle)> = due
+            .into_iter()
+            .map(|pending| (self.reputation.score(&pending.owner), pending))
+            .collect();
+        due.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+        
+        let mut included = 0;
+        for (score, pending) in due {
+            let priority = self.reputation.priority(score);
+            match self.open(&pending.bundle) {
+                Ok(txs) => {
+                    // Opened contents are sensitive flow: in memory only until they land
+                    for tx in txs {
+                        let simulation = self.simulation_service.simulate_sensitive(&tx, priority).await;
+                        self.reputation.record_simulation(&pending.owner, simulation.is_ok());
+                        let profit = match simulation {
+                            Ok(profit) => profit,
+                            Err(e) => {
+                                warn!("Failed to simulate sealed bundle {} transaction: {}", pending.id, e);
+                                continue;
+                            }
+                        };
+                        
+                        let source = TxSource::Searcher(pending.owner.clone());
+                        self.transaction_service
+                            .mark_sensitive_for_inclusion(
+                                tx.map(|tx| InclusionCandidate {
+                                    tx: Arc::new(tx),
+                                    profit,
+                                    source,
+                                    strategy: None,
+                                }),
+                                pending.bundle.target_block,
+                            )
+                            .await;
+                    }
+                    included += 1;
+                }
+                Err(e) => warn!("Failed to open sealed bundle {} from {}: {}", pending.id, pending.owner, e),
+            }
+        }
+        
+        Ok(included)
+    }
+    
+    /// Decrypt and decode a sealed bundle's transactions
+    fn open(&self, bundle: &SealedBundle) -> Result<Vec<Sensitive<Transaction>>> {
+        let plaintext = {
+            let keys = self.keys.read();
+            let key = keys
+                .iter()
+                .find(|key| key.key_id == bundle.key_id)
+                .ok_or_else(|| anyhow!("Sealing key {} has been retired", bundle.key_id))?;
+            key.open(bundle)?
+        };
+        
+        let contents: BundlePlaintext =
+            serde_json::from_slice(&plaintext).context("Invalid sealed bundle contents")?;
+        
+        contents
+            .txs
+            .iter()
+            .map(|raw| {
+                rlp::decode::<Transaction>(raw)
+                    .map(Sensitive::new)
+                    .map_err(|_| anyhow!("Invalid transaction in sealed bundle"))
+            })
+            .collect()
+    }
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.trim_start_matches("0x")).context("Invalid hex encoding")
+}
@@ -0,0 +1,420 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, Row};
+use std::{str::FromStr, sync::Arc};
+use tracing::{debug, warn};
+
+use crate::{
+    blockchain::BlockchainClient,
+    database::{DbHealth, DbPool},
+    services::{
+        alerting::{Alert, AlertManager, Severity},
+        risk::RiskManager,
+    },
+};
+
+/// Slots reconciled per run, older slots are picked up on the next run
+const RECONCILE_BATCH: i64 = 100;
+
+/// A mismatch between what we submitted, what landed and what we booked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Discrepancy {
+    /// A relay reports delivering our block but the canonical block differs
+    NotOnChain,
+    /// Our block landed without a final transaction paying at least the bid value to the
+    /// proposer's fee recipient
+    MissingPayment,
+    /// Our block landed but no built block was recorded for P&L
+    LandedUnrecorded,
+}
+
+impl Discrepancy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NotOnChain => "not_on_chain",
+            Self::MissingPayment => "missing_payment",
+            Self::LandedUnrecorded => "landed_unrecorded",
+        }
+    }
+    
+    fn severity(&self) -> Severity {
+        match self {
+            Self::MissingPayment => Severity::Critical,
+            Self::NotOnChain | Self::LandedUnrecorded => Severity::Warning,
+        }
+    }
+}
+
+impl FromStr for Discrepancy {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "not_on_chain" => Ok(Self::NotOnChain),
+            "missing_payment" => Ok(Self::MissingPayment),
+            "landed_unrecorded" => Ok(Self::LandedUnrecorded),
+            other => Err(anyhow::anyhow!("Unknown discrepancy: {}", other)),
+        }
+    }
+}
+
+/// Reconciled outcome of one slot we bid on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlotReconciliation {
+    pub slot: u64,
+    /// Whether a relay delivered one of our blocks
+    pub won: bool,
+    pub block_number: Option<u64>,
+    pub block_hash: Option<String>,
+    /// Relays reporting delivery of our block
+    pub relays: Vec<String>,
+    /// Bid value the relays report delivering
+    pub bid_value_wei: Option<String>,
+    /// Value of the block's final transaction, our payment to the proposer
+    pub paid_wei: Option<String>,
+    /// Value booked in the built block record
+    pub recorded_value_wei: Option<String>,
+    /// Subsidy booked for the block
+    pub subsidy_wei: Option<String>,
+    pub discrepancies: Vec<Discrepancy>,
+    pub reconciled_at: DateTime<Utc>,
+}
+
+/// Reconciles finished slots against relay data, the chain and our ledgers
+#[derive(Clone)]
+pub struct SettlementReconciler {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Blockchain client, checks the canonical block and payment
+    blockchain_client: Arc<BlockchainClient>,
+    /// Alert manager, notified of discrepancies
+    alert_manager: AlertManager,
+    /// Told about blocks that paid the proposer more than they earned
+    risk_manager: RiskManager,
+}
+
+impl SettlementReconciler {
+    /// Create a new settlement reconciler
+    pub fn new(
+        db_pool: DbPool,
+        health: DbHealth,
+        blockchain_client: Arc<BlockchainClient>,
+        alert_manager: AlertManager,
+        risk_manager: RiskManager,
+    ) -> Result<Self> {
+        Ok(Self {
+            db_pool,
+            health,
+            blockchain_client,
+            alert_manager,
+            risk_manager,
+        })
+    }
+    
+    /// Reconcile every slot we bid on whose delivered payload has been scraped
+    pub async fn reconcile_pending(&self) -> Result<()> {
+        let slots = self.health.retry(|| self.unreconciled_slots()).await?;
+        
+        // Blocks are only judged once final, a block missing from a reorged view may come back
+        let finalized = self.blockchain_client.get_finalized_block_number().await?;
+        
+        for slot in slots {
+            let reconciliation = match self.reconcile_slot(slot, finalized).await {
+                Ok(Some(reconciliation)) => reconciliation,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to reconcile slot {}: {}", slot, e);
+                    continue;
+                }
+            };
+            if let Err(e) = self.health.retry(|| self.store(&reconciliation)).await {
+                warn!("Failed to store reconciliation of slot {}: {}", slot, e);
+                continue;
+            }
+            
+            for discrepancy in &reconciliation.discrepancies {
+                self.raise(&reconciliation, *discrepancy).await;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Reconcile one slot, `None` while our delivered block is not yet final
+    async fn reconcile_slot(&self, slot: i64, finalized: Option<u64>) -> Result<Option<SlotReconciliation>> {
+        let (our_hashes, delivered) = self.health.retry(|| self.slot_records(slot)).await?;
+        
+        let mut reconciliation = SlotReconciliation {
+            slot: slot as u64,
+            won: false,
+            block_number: None,
+            block_hash: None,
+            relays: Vec::new(),
+            bid_value_wei: None,
+            paid_wei: None,
+            recorded_value_wei: None,
+            subsidy_wei: None,
+            discrepancies: Vec::new(),
+            reconciled_at: Utc::now(),
+        };
+        
+        let mut fee_recipient: Option<String> = None;
+        for row in delivered {
+            let block_hash: String = row.try_get("block_hash")?;
+            if !our_hashes.contains(&block_hash) {
+                continue;
+            }
+            
+            fee_recipient = fee_recipient.or(row.try_get("proposer_fee_recipient")?);
+            reconciliation.won = true;
+            reconciliation.block_number = Some(row.try_get::<i64, _>("block_number")? as u64);
+            reconciliation.block_hash = Some(block_hash);
+            reconciliation.relays.push(row.try_get("relay")?);
+            reconciliation.bid_value_wei = Some(row.try_get("value_wei")?);
+        }
+        
+        let (block_number, block_hash) = match (reconciliation.block_number, reconciliation.block_hash.clone()) {
+            (Some(number), Some(hash)) => (number, hash),
+            _ => return Ok(Some(reconciliation)),
+        };
+        if finalized.map_or(true, |finalized| block_number > finalized) {
+            debug!("Block {} for slot {} is not final yet, reconciling later", block_number, slot);
+            return Ok(None);
+        }
+        
+        // What the chain says: our block is canonical and ends with the proposer payment
+        let block = self.blockchain_client.get_block_with_transactions(block_number).await?;
+        let canonical = block
+            .as_ref()
+            .and_then(|b| b.hash)
+            .map_or(false, |hash| format!("{:?}", hash) == block_hash);
+        
+        if !canonical {
+            reconciliation.discrepancies.push(Discrepancy::NotOnChain);
+        } else {
+            // A final transaction to anyone but the registered fee recipient pays nothing
+            let paid = block
+                .and_then(|b| b.transactions.last().cloned())
+                .filter(|tx| {
+                    fee_recipient.as_deref().map_or(true, |recipient| {
+                        tx.to.map_or(false, |to| format!("{:?}", to) == recipient)
+                    })
+                })
+                .map(|tx| tx.value)
+                .unwrap_or_default();
+            let bid_value = reconciliation
+                .bid_value_wei
+                .as_deref()
+                .map(U256::from_dec_str)
+                .transpose()?
+                .unwrap_or_default();
+            
+            if paid < bid_value {
+                reconciliation.discrepancies.push(Discrepancy::MissingPayment);
+            }
+            reconciliation.paid_wei = Some(paid.to_string());
+        }
+        
+        // What our P&L says
+        let (recorded_value_wei, subsidy_wei) = self.health.retry(|| self.ledger_records(&block_hash)).await?;
+        reconciliation.recorded_value_wei = recorded_value_wei;
+        reconciliation.subsidy_wei = subsidy_wei;
+        
+        if canonical && reconciliation.recorded_value_wei.is_none() {
+            reconciliation.discrepancies.push(Discrepancy::LandedUnrecorded);
+        }
+        
+        // Paying the proposer more than the block earned is a loss, beyond a budgeted subsidy
+        let paid = reconciliation.paid_wei.as_deref().map(U256::from_dec_str).transpose()?;
+        let earned = reconciliation.recorded_value_wei.as_deref().map(U256::from_dec_str).transpose()?;
+        let subsidy = reconciliation.subsidy_wei.as_deref().map(U256::from_dec_str).transpose()?;
+        if let (Some(paid), Some(earned)) = (paid, earned) {
+            let covered = earned.saturating_add(subsidy.unwrap_or_default());
+            if paid > covered {
+                self.risk_manager.record_loss(paid - covered).await;
+            }
+        }
+        
+        debug!(
+            "Reconciled slot {}: won={} discrepancies={:?}",
+            slot, reconciliation.won, reconciliation.discrepancies
+        );
+        
+        Ok(Some(reconciliation))
+    }
+    
+    async fn unreconciled_slots(&self) -> Result<Vec<i64>> {
+        sqlx::query_scalar(
+            "SELECT DISTINCT b.slot
+             FROM builder_bids b
+             JOIN relay_delivered_payloads d ON d.slot = b.slot
+             WHERE b.accepted
+               AND NOT EXISTS (SELECT 1 FROM slot_reconciliations r WHERE r.slot = b.slot)
+             ORDER BY b.slot
+             LIMIT $1",
+        )
+        .bind(RECONCILE_BATCH)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to fetch slots to reconcile")
+    }
+    
+    /// Hashes of our accepted bids for a slot, and the payloads relays delivered for it
+    async fn slot_records(&self, slot: i64) -> Result<(Vec<String>, Vec<PgRow>)> {
+        let our_hashes: Vec<String> = sqlx::query_scalar(
+            "SELECT DISTINCT LOWER(block_hash) FROM builder_bids WHERE slot = $1 AND accepted",
+        )
+        .bind(slot)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to fetch our bids")?;
+        
+        let delivered = sqlx::query(
+            "SELECT relay, block_number, LOWER(block_hash) AS block_hash, value_wei::TEXT AS value_wei,
+                    LOWER(proposer_fee_recipient) AS proposer_fee_recipient
+             FROM relay_delivered_payloads
+             WHERE slot = $1
+             ORDER BY relay",
+        )
+        .bind(slot)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to fetch delivered payloads")?;
+        
+        Ok((our_hashes, delivered))
+    }
+    
+    /// Value booked for a block and the subsidy paid with it
+    async fn ledger_records(&self, block_hash: &str) -> Result<(Option<String>, Option<String>)> {
+        let recorded_value_wei = sqlx::query_scalar(
+            "SELECT value_wei FROM built_blocks WHERE LOWER(block_hash) = $1",
+        )
+        .bind(block_hash)
+        .fetch_optional(&self.db_pool)
+        .await
+        .context("Failed to fetch built block")?;
+        
+        let subsidy_wei = sqlx::query_scalar(
+            "SELECT amount_wei::TEXT FROM subsidy_ledger WHERE LOWER(block_hash) = $1",
+        )
+        .bind(block_hash)
+        .fetch_optional(&self.db_pool)
+        .await
+        .context("Failed to fetch subsidy")?;
+        
+        Ok((recorded_value_wei, subsidy_wei))
+    }
+    
+    async fn store(&self, reconciliation: &SlotReconciliation) -> Result<()> {
+        let discrepancies: Vec<&str> = reconciliation.discrepancies.iter().map(|d| d.as_str()).collect();
+        
+        sqlx::query(
+            "INSERT INTO slot_reconciliations
+             (slot, won, block_number, block_hash, relays, bid_value_wei, paid_wei,
+              recorded_value_wei, subsidy_wei, discrepancies, reconciled_at)
+             VALUES ($1, $2, $3, $4, $5, $6::NUMERIC, $7::NUMERIC, $8::NUMERIC, $9::NUMERIC, $10, $11)
+             ON CONFLICT (slot) DO NOTHING",
+        )
+        .bind(reconciliation.slot as i64)
+        .bind(reconciliation.won)
+        .bind(reconciliation.block_number.map(|n| n as i64))
+        .bind(&reconciliation.block_hash)
+        .bind(&reconciliation.relays)
+        .bind(&reconciliation.bid_value_wei)
+        .bind(&reconciliation.paid_wei)
+        .bind(&reconciliation.recorded_value_wei)
+        .bind(&reconciliation.subsidy_wei)
+        .bind(discrepancies)
+        .bind(reconciliation.reconciled_at)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to store slot reconciliation")?;
+        
+        Ok(())
+    }
+    
+    async fn raise(&self, reconciliation: &SlotReconciliation, discrepancy: Discrepancy) {
+        let block = reconciliation.block_hash.as_deref().unwrap_or_default();
+        let message = match discrepancy {
+            Discrepancy::NotOnChain => format!(
+                "Relays {} report delivering our block {} for slot {} but it is not canonical",
+                reconciliation.relays.join(","),
+                block,
+                reconciliation.slot
+            ),
+            Discrepancy::MissingPayment => format!(
+                "Block {} for slot {} paid {} wei to the proposer, bid was {} wei",
+                block,
+                reconciliation.slot,
+                reconciliation.paid_wei.as_deref().unwrap_or("0"),
+                reconciliation.bid_value_wei.as_deref().unwrap_or("0")
+            ),
+            Discrepancy::LandedUnrecorded => format!(
+                "Block {} for slot {} landed but has no built block record",
+                block, reconciliation.slot
+            ),
+        };
+        
+        warn!("Settlement discrepancy: {}", message);
+        metrics::counter!("settlement_discrepancies_total", 1, "kind" => discrepancy.as_str());
+        
+        self.alert_manager
+            .fire(Alert::new(
+                format!("settlement:{}:{}", discrepancy.as_str(), reconciliation.slot),
+                discrepancy.severity(),
+                "settlement",
+                message,
+            ))
+            .await;
+    }
+    
+    /// Most recent reconciled slots, optionally only those with discrepancies
+    pub async fn report(&self, limit: i64, discrepancies_only: bool) -> Result<Vec<SlotReconciliation>> {
+        self.health.retry(|| self.report_once(limit, discrepancies_only)).await
+    }
+    
+    async fn report_once(&self, limit: i64, discrepancies_only: bool) -> Result<Vec<SlotReconciliation>> {
+        let rows = sqlx::query(
+            "SELECT slot, won, block_number, block_hash, relays,
+                    bid_value_wei::TEXT AS bid_value_wei, paid_wei::TEXT AS paid_wei,
+                    recorded_value_wei::TEXT AS recorded_value_wei, subsidy_wei::TEXT AS subsidy_wei,
+                    discrepancies, reconciled_at
+             FROM slot_reconciliations
+             WHERE NOT $2 OR discrepancies <> '{}'
+             ORDER BY slot DESC
+             LIMIT $1",
+        )
+        .bind(limit)
+        .bind(discrepancies_only)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to fetch slot reconciliations")?;
+        
+        rows.into_iter()
+            .map(|row| {
+                Ok(SlotReconciliation {
+                    slot: row.try_get::<i64, _>("slot")? as u64,
+                    won: row.try_get("won")?,
+                    block_number: row.try_get::<Option<i64>, _>("block_number")?.map(|n| n as u64),
+                    block_hash: row.try_get("block_hash")?,
+                    relays: row.try_get("relays")?,
+                    bid_value_wei: row.try_get("bid_value_wei")?,
+                    paid_wei: row.try_get("paid_wei")?,
+                    recorded_value_wei: row.try_get("recorded_value_wei")?,
+                    subsidy_wei: row.try_get("subsidy_wei")?,
+                    discrepancies: row
+                        .try_get::<Vec<String>, _>("discrepancies")?
+                        .iter()
+                        .map(|d| d.parse())
+                        .collect::<Result<_>>()?,
+                    reconciled_at: row.try_get("reconciled_at")?,
+                })
+            })
+            .collect()
+    }
+}
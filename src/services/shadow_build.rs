@@ -0,0 +1,322 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use ethers::types::{Address, Transaction, U256};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, Row};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use crate::{
+    api::models,
+    blockchain::BlockchainClient,
+    config::ShadowBuildConfig,
+    database::{DbHealth, DbPool},
+    services::{
+        ordering::{BlockContext, OrderingStrategy},
+        simulation::RevmEngine,
+        transaction::InclusionCandidate,
+    },
+    utils::units::wei_to_eth,
+};
+
+/// Our ordering of a lost slot's landed transactions against the landed order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowBuild {
+    pub slot: u64,
+    pub block_number: u64,
+    pub block_hash: String,
+    /// False when the delivered block was not canonical, leaving nothing to score
+    pub canonical: bool,
+    /// Ordering strategy the shadow block was built with
+    pub ordering: String,
+    pub landed_txs: usize,
+    pub shadow_txs: usize,
+    #[serde(with = "models::wei_opt")]
+    pub landed_value: Option<U256>,
+    #[serde(with = "models::wei_opt")]
+    pub shadow_value: Option<U256>,
+    /// Shadow value over landed value
+    pub efficiency: Option<f64>,
+    pub scored_at: DateTime<Utc>,
+}
+
+/// Builder efficiency over one day of lost slots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EfficiencyBucket {
+    pub day: DateTime<Utc>,
+    pub slots: i64,
+    pub mean_efficiency: Option<f64>,
+    /// Total landed value minus total shadow value, negative when our ordering was worth more
+    pub value_gap_eth: f64,
+}
+
+/// Builder efficiency, daily and for the most recent lost slots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EfficiencyReport {
+    pub daily: Vec<EfficiencyBucket>,
+    pub recent: Vec<ShadowBuild>,
+}
+
+/// Shadow builds of slots we lost, scoring our ordering against the winner's
+///
+/// Each block that beat us is rebuilt from its own transactions: they are simulated in the
+/// landed order on the parent state, handed to our ordering strategy with the profit that run
+/// measured, and our order is simulated on the same state. Both runs see the same order flow,
+/// so the ratio of the two values reflects ordering quality alone. The winner's payment to the
+/// proposer is left out of both, and the shadow block gets the gas the landed transactions
+/// reserved, the space the winner fit them in.
+#[derive(Clone)]
+pub struct ShadowBuildService {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Blockchain client, source of landed blocks
+    blockchain_client: Arc<BlockchainClient>,
+    /// Replays landed and shadow orders on the parent state
+    engine: Arc<RevmEngine>,
+    /// Our configured ordering strategy
+    ordering: Arc<dyn OrderingStrategy>,
+    /// Configuration
+    config: ShadowBuildConfig,
+}
+
+impl ShadowBuildService {
+    /// Create a new shadow build service
+    pub fn new(
+        db_pool: DbPool,
+        health: DbHealth,
+        blockchain_client: Arc<BlockchainClient>,
+        ordering: Arc<dyn OrderingStrategy>,
+        config: ShadowBuildConfig,
+        fee_recipient: Address,
+    ) -> Result<Self> {
+        Ok(Self {
+            db_pool,
+            health,
+            engine: Arc::new(RevmEngine::new(blockchain_client.clone(), fee_recipient)),
+            blockchain_client,
+            ordering,
+            config,
+        })
+    }
+    
+    /// Whether lost slots are shadow-built
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Shadow-build reconciled slots we lost that have not been scored, most recent first
+    pub async fn score_pending(&self) -> Result<()> {
+        let rows = self.health.retry(|| self.unscored_slots()).await?;
+        
+        for row in rows {
+            let slot = row.try_get::<i64, _>("slot")? as u64;
+            let block_number = row.try_get::<i64, _>("block_number")? as u64;
+            let block_hash: String = row.try_get("block_hash")?;
+            
+            // A failed replay is retried on the next run, the state may just be unavailable
+            let build = match self.score_slot(slot, block_number, block_hash).await {
+                Ok(build) => build,
+                Err(e) => {
+                    warn!("Failed to shadow-build slot {}: {}", slot, e);
+                    continue;
+                }
+            };
+            self.health.retry(|| self.store(&build)).await?;
+            
+            if let Some(efficiency) = build.efficiency {
+                metrics::gauge!("builder_efficiency", efficiency);
+                metrics::histogram!("builder_efficiency_ratio", efficiency);
+            }
+        }
+        
+        Ok(())
+    }
+    
+    async fn score_slot(&self, slot: u64, block_number: u64, block_hash: String) -> Result<ShadowBuild> {
+        let mut build = ShadowBuild {
+            slot,
+            block_number,
+            block_hash,
+            canonical: false,
+            ordering: self.ordering.name().to_string(),
+            landed_txs: 0,
+            shadow_txs: 0,
+            landed_value: None,
+            shadow_value: None,
+            efficiency: None,
+            scored_at: Utc::now(),
+        };
+        
+        let block = self
+            .blockchain_client
+            .get_block_with_transactions(block_number)
+            .await?
+            .ok_or_else(|| anyhow!("Block {} not found", block_number))?;
+        build.canonical = block.hash.map_or(false, |hash| format!("{:?}", hash) == build.block_hash);
+        if !build.canonical {
+            return Ok(build);
+        }
+        
+        let coinbase = block.author.unwrap_or_default();
+        let landed: Vec<Transaction> = block.transactions.into_iter().filter(|tx| tx.from != coinbase).collect();
+        let landed_run = self.engine.simulate_bundle_in(&landed, block_number).await?;
+        
+        let candidates: Vec<InclusionCandidate> = landed
+            .iter()
+            .zip(&landed_run.txs)
+            .map(|(tx, result)| InclusionCandidate {
+                tx: Arc::new(tx.clone()),
+                profit: result.coinbase_profit,
+                source: Default::default(),
+                strategy: None,
+            })
+            .collect();
+        let context = BlockContext {
+            base_fee: block.base_fee_per_gas.unwrap_or_default(),
+            gas_limit: landed.iter().map(|tx| tx.gas.min(U256::from(u64::MAX)).as_u64()).sum(),
+        };
+        let shadow: Vec<Transaction> = self
+            .ordering
+            .order(candidates, &context)
+            .into_iter()
+            .map(|candidate| (*candidate.tx).clone())
+            .collect();
+        let shadow_run = self.engine.simulate_bundle_in(&shadow, block_number).await?;
+        
+        build.landed_txs = landed.len();
+        build.shadow_txs = shadow.len();
+        build.landed_value = Some(landed_run.coinbase_profit);
+        build.shadow_value = Some(shadow_run.coinbase_profit);
+        build.efficiency = (!landed_run.coinbase_profit.is_zero())
+            .then(|| wei_to_eth(shadow_run.coinbase_profit) / wei_to_eth(landed_run.coinbase_profit));
+        
+        debug!(
+            "Shadow-built slot {} (block {}): landed {} wei, ours {} wei",
+            slot, block_number, landed_run.coinbase_profit, shadow_run.coinbase_profit
+        );
+        
+        Ok(build)
+    }
+    
+    async fn unscored_slots(&self) -> Result<Vec<PgRow>> {
+        sqlx::query(
+            "SELECT DISTINCT ON (r.slot) r.slot, d.block_number, LOWER(d.block_hash) AS block_hash
+             FROM slot_reconciliations r
+             JOIN relay_delivered_payloads d ON d.slot = r.slot
+             WHERE NOT r.won
+               AND NOT EXISTS (SELECT 1 FROM shadow_builds s WHERE s.slot = r.slot)
+             ORDER BY r.slot DESC, d.relay
+             LIMIT $1",
+        )
+        .bind(self.config.max_slots_per_run as i64)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to fetch slots to shadow-build")
+    }
+    
+    async fn store(&self, build: &ShadowBuild) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO shadow_builds
+             (slot, block_number, block_hash, canonical, ordering, landed_txs, shadow_txs,
+              landed_value_wei, shadow_value_wei, efficiency, scored_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8::NUMERIC, $9::NUMERIC, $10, $11)
+             ON CONFLICT (slot) DO NOTHING",
+        )
+        .bind(build.slot as i64)
+        .bind(build.block_number as i64)
+        .bind(&build.block_hash)
+        .bind(build.canonical)
+        .bind(&build.ordering)
+        .bind(build.landed_txs as i32)
+        .bind(build.shadow_txs as i32)
+        .bind(build.landed_value.map(|value| value.to_string()))
+        .bind(build.shadow_value.map(|value| value.to_string()))
+        .bind(build.efficiency)
+        .bind(build.scored_at)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to store shadow build")?;
+        
+        Ok(())
+    }
+    
+    /// Daily efficiency over the trailing days and the most recent scored slots
+    pub async fn report(&self, days: i64, limit: i64) -> Result<EfficiencyReport> {
+        self.health.retry(|| self.report_once(days, limit)).await
+    }
+    
+    async fn report_once(&self, days: i64, limit: i64) -> Result<EfficiencyReport> {
+        let daily = sqlx::query(
+            "SELECT date_trunc('day', scored_at) AS day, COUNT(*) AS slots, AVG(efficiency) AS mean_efficiency,
+                    (COALESCE(SUM(landed_value_wei), 0) - COALESCE(SUM(shadow_value_wei), 0))::TEXT AS value_gap
+             FROM shadow_builds
+             WHERE canonical AND scored_at >= NOW() - make_interval(days => $1::INT)
+             GROUP BY 1
+             ORDER BY 1",
+        )
+        .bind(days)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to summarize shadow builds")?;
+        
+        let daily = daily
+            .iter()
+            .map(|row| {
+                let gap: String = row.try_get("value_gap")?;
+                let (negative, magnitude) = match gap.strip_prefix('-') {
+                    Some(magnitude) => (true, magnitude),
+                    None => (false, gap.as_str()),
+                };
+                let magnitude =
+                    U256::from_dec_str(magnitude).map_err(|e| anyhow!("Invalid value gap {}: {}", gap, e))?;
+                let value_gap_eth = wei_to_eth(magnitude);
+                Ok(EfficiencyBucket {
+                    day: row.try_get("day")?,
+                    slots: row.try_get("slots")?,
+                    mean_efficiency: row.try_get("mean_efficiency")?,
+                    value_gap_eth: if negative { -value_gap_eth } else { value_gap_eth },
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        
+        let rows = sqlx::query(
+            "SELECT slot, block_number, block_hash, canonical, ordering, landed_txs, shadow_txs,
+                    landed_value_wei::TEXT AS landed_value_wei, shadow_value_wei::TEXT AS shadow_value_wei,
+                    efficiency, scored_at
+             FROM shadow_builds
+             ORDER BY slot DESC
+             LIMIT $1",
+        )
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to fetch shadow builds")?;
+        
+        let value = |raw: Option<String>| -> Result<Option<U256>> {
+            raw.map(|raw| U256::from_dec_str(&raw).map_err(|e| anyhow!("Invalid value {}: {}", raw, e)))
+                .transpose()
+        };
+        let recent = rows
+            .iter()
+            .map(|row| {
+                Ok(ShadowBuild {
+                    slot: row.try_get::<i64, _>("slot")? as u64,
+                    block_number: row.try_get::<i64, _>("block_number")? as u64,
+                    block_hash: row.try_get("block_hash")?,
+                    canonical: row.try_get("canonical")?,
+                    ordering: row.try_get("ordering")?,
+                    landed_txs: row.try_get::<i32, _>("landed_txs")? as usize,
+                    shadow_txs: row.try_get::<i32, _>("shadow_txs")? as usize,
+                    landed_value: value(row.try_get("landed_value_wei")?)?,
+                    shadow_value: value(row.try_get("shadow_value_wei")?)?,
+                    efficiency: row.try_get("efficiency")?,
+                    scored_at: row.try_get("scored_at")?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        
+        Ok(EfficiencyReport { daily, recent })
+    }
+}
@@ -1,10 +1,52 @@
-use anyhow::{anyhow, Result};
-use ethers::types::{Transaction, U256};
-use std::{sync::Arc, time::Duration};
-use tokio::sync::Semaphore;
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use ethers::types::{Address, Transaction, H256, U256, U512};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::{Semaphore, SemaphorePermit};
 use tracing::{debug, error, warn};
 
-use crate::{blockchain::BlockchainClient, config::TxOrderingConfig};
+use crate::{
+    api::models,
+    blockchain::{
+        simulator::{ExecutionTrace, ForkBlock, ForkSimulator},
+        BlockchainClient,
+    },
+    config::TxOrderingConfig,
+    database::{repositories::TokenRepository, resilience::DeferredWrite, DbHealth, DbPool},
+    models::TokenAmount,
+    services::{prices::PriceService, simulation_pool::SimulationPool},
+    utils::sensitive::Sensitive,
+};
+
+/// Number of recent disagreements kept in memory for the calibration report
+const RECENT_DISAGREEMENTS: usize = 100;
+
+/// Low priority simulations hold at most one in this many workers
+const LOW_PRIORITY_WORKER_DIVISOR: usize = 4;
+
+/// Most shadow comparisons running at once, further samples are dropped
+const MAX_SHADOW_COMPARISONS: usize = 16;
+
+/// Which lane a simulation waits in for a worker
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SimulationPriority {
+    #[default]
+    Normal,
+    /// Limited to a share of the workers, so it never starves normal priority work
+    Low,
+}
 
 /// Service for simulating transactions to evaluate profit potential
 #[derive(Clone)]
@@ -15,69 +57,640 @@ pub struct SimulationService {
     config: TxOrderingConfig,
     /// Semaphore for limiting concurrent simulations
     semaphore: Arc<Semaphore>,
+    /// Simulations waiting for a permit
+    queued: Arc<AtomicUsize>,
+    /// Admission of low priority simulations to the workers
+    low_priority: Arc<Semaphore>,
+    /// Engine whose results drive decisions
+    primary: Arc<dyn SimulationEngine>,
+    /// Engine run on a sample of transactions for comparison only
+    shadow: Option<Arc<dyn SimulationEngine>>,
+    /// Bound on shadow comparisons in flight
+    shadow_permits: Arc<Semaphore>,
+    /// Cross-engine comparison results
+    calibration: Arc<Calibration>,
+    /// Database pool for recorded disagreements
+    db_pool: DbPool,
+    /// Disagreements are deferred while the database is down
+    health: DbHealth,
+    /// Dedicated runtime simulations run on, None to share the main runtime
+    pool: Option<SimulationPool>,
+    /// Executes bundles whatever the primary engine, since later transactions need earlier state
+    bundle_engine: Arc<RevmEngine>,
+    /// Token prices, valuing the searcher's token deltas
+    prices: PriceService,
+    /// Token registry, denominating the searcher's token deltas
+    tokens: TokenRepository,
 }
 
 /// Simulation result with estimated profit/loss
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SimulationResult {
     /// Transaction hash
     pub tx_hash: ethers::types::H256,
     /// Estimated profit in wei (can be negative)
+    #[serde(with = "models::wei")]
     pub profit: U256,
     /// Estimated gas used
+    #[serde(with = "models::wei")]
     pub gas_used: U256,
     /// Simulation successful
     pub success: bool,
     /// Simulation duration
     pub duration: Duration,
+    /// State diffs, logs and balance deltas, from engines that execute the transaction
+    #[serde(default)]
+    pub execution: Option<ExecutionTrace>,
+    /// Net USD value of the searcher's token deltas, when every token could be priced
+    #[serde(default)]
+    pub searcher_token_value_usd: Option<f64>,
+    /// The searcher's token deltas in whole units, for tokens whose metadata could be read
+    #[serde(default)]
+    pub searcher_token_amounts: Vec<TokenAmount>,
+}
+
+/// Outcome of one transaction in a simulated bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleTxResult {
+    pub tx_hash: H256,
+    #[serde(with = "models::checksum")]
+    pub from: Address,
+    pub success: bool,
+    pub gas_used: u64,
+    /// Revert data or halt reason when the transaction failed
+    pub failure: Option<String>,
+    /// Fee recipient balance increase from this transaction
+    #[serde(with = "models::wei")]
+    pub coinbase_profit: U256,
+    /// Sender ETH balance change in wei, a signed decimal string
+    pub sender_eth_delta: String,
+}
+
+/// Sequential simulation of a bundle on top of our latest block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleSimulationResult {
+    pub fork_block: u64,
+    /// Every transaction succeeded
+    pub success: bool,
+    pub gas_used: u64,
+    /// Total fee recipient balance increase, the bundle's value to the block
+    #[serde(with = "models::wei")]
+    pub coinbase_profit: U256,
+    pub txs: Vec<BundleTxResult>,
+    pub duration: Duration,
+}
+
+/// A backend capable of simulating a single transaction
+#[async_trait]
+pub trait SimulationEngine: Send + Sync {
+    /// Engine name as used in configuration
+    fn name(&self) -> &'static str;
+    
+    /// Simulate a transaction
+    async fn simulate(&self, tx: &Transaction) -> Result<SimulationResult>;
+}
+
+/// Gas-price premium heuristic, the original simulation model
+pub struct HeuristicEngine {
+    blockchain_client: Arc<BlockchainClient>,
+}
+
+impl HeuristicEngine {
+    pub fn new(blockchain_client: Arc<BlockchainClient>) -> Self {
+        Self { blockchain_client }
+    }
+}
+
+#[async_trait]
+impl SimulationEngine for HeuristicEngine {
+    fn name(&self) -> &'static str {
+        "heuristic"
+    }
+    
+    async fn simulate(&self, tx: &Transaction) -> Result<SimulationResult> {
+        let start = Instant::now();
+        
+        // This is a simple evaluation based on gas price
+        let current_gas_price = self.blockchain_client.get_cached_gas_price().await?;
+        let tx_gas_price = tx.gas_price.unwrap_or(U256::zero());
+        
+        // Calculate profit (this is highly simplified - real MEV would involve much more complex analysis)
+        let gas_limit = tx.gas;
+        let estimated_gas_used = gas_limit.saturating_mul(U256::from(80)) / U256::from(100); // Assume 80% gas usage
+        
+        // Check if the transaction offers a premium over current gas price
+        let profit = if tx_gas_price > current_gas_price {
+            let premium = tx_gas_price.saturating_sub(current_gas_price);
+            premium.saturating_mul(estimated_gas_used)
+        } else {
+            U256::zero()
+        };
+        
+        Ok(SimulationResult {
+            tx_hash: tx.hash,
+            profit,
+            gas_used: estimated_gas_used,
+            success: true,
+            duration: start.elapsed(),
+            execution: None,
+            searcher_token_value_usd: None,
+            searcher_token_amounts: Vec::new(),
+        })
+    }
+}
+
+/// Executes transactions with revm against state forked at our latest block
+pub struct RevmEngine {
+    blockchain_client: Arc<BlockchainClient>,
+    simulator: ForkSimulator,
+    /// Coinbase of the simulated block, our fee recipient rather than the fork block's author
+    coinbase: Address,
+    /// Header of the latest fork block, refetched when the head moves
+    fork_block: Mutex<Option<ForkBlock>>,
+}
+
+impl RevmEngine {
+    pub fn new(blockchain_client: Arc<BlockchainClient>, coinbase: Address) -> Self {
+        let simulator = ForkSimulator::new(
+            blockchain_client.http_provider().clone(),
+            blockchain_client.chain_id(),
+        );
+        
+        Self {
+            blockchain_client,
+            simulator,
+            coinbase,
+            fork_block: Mutex::new(None),
+        }
+    }
+    
+    async fn latest_fork_block(&self) -> Result<ForkBlock> {
+        let number = self.blockchain_client.get_block_number().await?;
+        if let Some(block) = *self.fork_block.lock() {
+            if block.number == number {
+                return Ok(block);
+            }
+        }
+        
+        let block = self.fork_block_at(number).await?;
+        *self.fork_block.lock() = Some(block);
+        
+        Ok(block)
+    }
+    
+    async fn fork_block_at(&self, number: u64) -> Result<ForkBlock> {
+        let header = self
+            .blockchain_client
+            .get_block_with_hashes(number)
+            .await?
+            .ok_or_else(|| anyhow!("Block {} not found", number))?;
+        let block = ForkBlock {
+            number,
+            timestamp: header.timestamp,
+            base_fee: header.base_fee_per_gas.unwrap_or_default(),
+            gas_limit: header.gas_limit,
+            coinbase: self.coinbase,
+        };
+        
+        Ok(block)
+    }
+    
+    /// Execute transactions in order against the latest fork block
+    pub async fn simulate_bundle(&self, txs: &[Transaction]) -> Result<BundleSimulationResult> {
+        let block = self.latest_fork_block().await?;
+        self.simulate_bundle_on(txs, block).await
+    }
+    
+    /// Execute transactions in order against the post-state of the given block
+    ///
+    /// Blocks older than the node's pruning window need an archive node.
+    pub async fn simulate_bundle_at(&self, txs: &[Transaction], number: u64) -> Result<BundleSimulationResult> {
+        let block = self.fork_block_at(number).await?;
+        self.simulate_bundle_on(txs, block).await
+    }
+    
+    /// Execute transactions in order as the body of the given block, on its parent's state
+    pub async fn simulate_bundle_in(&self, txs: &[Transaction], number: u64) -> Result<BundleSimulationResult> {
+        let header = self.fork_block_at(number).await?;
+        let block = ForkBlock {
+            number: number.saturating_sub(1),
+            ..header
+        };
+        self.simulate_bundle_on(txs, block).await
+    }
+    
+    async fn simulate_bundle_on(&self, txs: &[Transaction], block: ForkBlock) -> Result<BundleSimulationResult> {
+        let start = Instant::now();
+        let traces = self.simulator.execute_bundle(txs, block).await?;
+        
+        let results: Vec<BundleTxResult> = txs
+            .iter()
+            .zip(traces)
+            .map(|(tx, trace)| BundleTxResult {
+                tx_hash: tx.hash,
+                from: tx.from,
+                success: trace.success,
+                gas_used: trace.gas_used,
+                failure: trace.failure,
+                coinbase_profit: trace.coinbase_profit,
+                sender_eth_delta: trace.searcher_eth_delta,
+            })
+            .collect();
+        
+        Ok(BundleSimulationResult {
+            fork_block: block.number,
+            success: results.iter().all(|tx| tx.success),
+            gas_used: results.iter().map(|tx| tx.gas_used).sum(),
+            coinbase_profit: results
+                .iter()
+                .fold(U256::zero(), |sum, tx| sum.saturating_add(tx.coinbase_profit)),
+            txs: results,
+            duration: start.elapsed(),
+        })
+    }
+}
+
+#[async_trait]
+impl SimulationEngine for RevmEngine {
+    fn name(&self) -> &'static str {
+        "revm"
+    }
+    
+    async fn simulate(&self, tx: &Transaction) -> Result<SimulationResult> {
+        let start = Instant::now();
+        let block = self.latest_fork_block().await?;
+        let execution = self.simulator.execute(tx, block).await?;
+        
+        Ok(SimulationResult {
+            tx_hash: tx.hash,
+            profit: execution.coinbase_profit,
+            gas_used: U256::from(execution.gas_used),
+            success: execution.success,
+            duration: start.elapsed(),
+            execution: Some(execution),
+            searcher_token_value_usd: None,
+            searcher_token_amounts: Vec::new(),
+        })
+    }
+}
+
+/// A sampled transaction where the primary and shadow engines disagreed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineDisagreement {
+    pub tx_hash: H256,
+    pub primary_engine: String,
+    pub shadow_engine: String,
+    #[serde(with = "models::wei")]
+    pub primary_profit: U256,
+    #[serde(with = "models::wei")]
+    pub shadow_profit: U256,
+    pub primary_success: bool,
+    pub shadow_success: bool,
+    pub observed_at: DateTime<Utc>,
+}
+
+/// Aggregate comparison between the primary and shadow engines
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CalibrationReport {
+    pub primary_engine: String,
+    pub shadow_engine: Option<String>,
+    /// Transactions run through both engines
+    pub samples: u64,
+    /// Samples where the engines disagreed on success
+    pub success_mismatches: u64,
+    /// Samples where the profit differed by more than the tolerance
+    pub profit_mismatches: u64,
+    /// Mean absolute profit delta in wei across all samples
+    pub mean_abs_profit_delta_wei: f64,
+    pub recent_disagreements: Vec<EngineDisagreement>,
+}
+
+/// Running cross-engine comparison state
+#[derive(Default)]
+struct Calibration {
+    report: Mutex<CalibrationReport>,
+    recent: Mutex<VecDeque<EngineDisagreement>>,
+}
+
+/// Counts a simulation as queued until it is dropped, also when its caller gives up waiting
+struct Queued<'a>(&'a AtomicUsize);
+
+impl<'a> Queued<'a> {
+    fn new(queued: &'a AtomicUsize) -> Self {
+        queued.fetch_add(1, Ordering::Relaxed);
+        Self(queued)
+    }
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
 impl SimulationService {
     /// Create a new simulation service
     pub fn new(
+        db_pool: DbPool,
+        health: DbHealth,
         blockchain_client: Arc<BlockchainClient>,
         config: TxOrderingConfig,
+        fee_recipient: Address,
+        prices: PriceService,
+        tokens: TokenRepository,
     ) -> Result<Self> {
         let worker_threads = config.worker_threads;
         let semaphore = Arc::new(Semaphore::new(worker_threads));
+        let low_priority = Arc::new(Semaphore::new((worker_threads / LOW_PRIORITY_WORKER_DIVISOR).max(1)));
+        
+        let primary = create_engine(&config.simulation_engine, &blockchain_client, fee_recipient)?;
+        let shadow = config
+            .shadow_engine
+            .as_deref()
+            .map(|name| create_engine(name, &blockchain_client, fee_recipient))
+            .transpose()?;
+        
+        let pool = SimulationPool::new(&config)?;
+        let bundle_engine = Arc::new(RevmEngine::new(blockchain_client.clone(), fee_recipient));
+        
+        let calibration = Calibration::default();
+        {
+            let mut report = calibration.report.lock();
+            report.primary_engine = primary.name().to_string();
+            report.shadow_engine = shadow.as_ref().map(|engine| engine.name().to_string());
+        }
         
         Ok(Self {
             blockchain_client,
             config,
             semaphore,
+            queued: Arc::new(AtomicUsize::new(0)),
+            low_priority,
+            primary,
+            shadow,
+            shadow_permits: Arc::new(Semaphore::new(MAX_SHADOW_COMPARISONS)),
+            calibration: Arc::new(calibration),
+            db_pool,
+            health,
+            pool,
+            bundle_engine,
+            prices,
+            tokens,
         })
     }
     
+    /// Wait for a simulation slot, counted as queued meanwhile
+    async fn permit(&self) -> Result<SemaphorePermit<'_>> {
+        let _queued = Queued::new(&self.queued);
+        
+        Ok(self.semaphore.acquire().await?)
+    }
+    
+    /// Wait for a simulation slot, low priority work passing through its lane first
+    ///
+    /// However many low priority simulations are waiting, normal priority ones only ever queue
+    /// behind the lane's share of the workers.
+    async fn prioritized_permit(
+        &self,
+        priority: SimulationPriority,
+    ) -> Result<(Option<SemaphorePermit<'_>>, SemaphorePermit<'_>)> {
+        let lane = match priority {
+            SimulationPriority::Normal => None,
+            SimulationPriority::Low => Some(self.low_priority.acquire().await?),
+        };
+        
+        Ok((lane, self.permit().await?))
+    }
+    
+    /// Simulations waiting for a slot and simulations running
+    pub fn queue_depth(&self) -> (usize, usize) {
+        let running = self.config.worker_threads.saturating_sub(self.semaphore.available_permits());
+        (self.queued.load(Ordering::Relaxed), running)
+    }
+    
     /// Simulate a transaction to evaluate profit potential
     pub async fn simulate_transaction(&self, tx: &Transaction) -> Result<U256> {
         let tx_hash = tx.hash;
         debug!("Simulating transaction: {}", tx_hash);
         
         // Limit concurrent simulations
-        let _permit = self.semaphore.acquire().await?;
+        let _permit = self.permit().await?;
+        
+        let result = self
+            .run_engine(self.primary.clone(), tx.clone())
+            .await
+            .with_context(|| format!("Simulation of {} failed", tx_hash))?;
+        
+        if self.should_sample(tx_hash) {
+            self.spawn_shadow_comparison(tx.clone(), result.clone());
+        }
+        
+        debug!("Simulation result for {}: profit={}", tx_hash, result.profit);
+        
+        Ok(result.profit)
+    }
+    
+    /// Simulate a transaction with the primary engine, returning the full result
+    pub async fn simulate_detailed(&self, tx: &Transaction) -> Result<SimulationResult> {
+        let _permit = self.permit().await?;
+        
+        let mut result = self
+            .run_engine(self.primary.clone(), tx.clone())
+            .await
+            .with_context(|| format!("Simulation of {} failed", tx.hash))?;
         
-        // Set simulation timeout
+        let deltas = result.execution.as_ref().map(|execution| &execution.searcher_token_deltas);
+        if let Some(deltas) = deltas.filter(|deltas| !deltas.is_empty()) {
+            if self.prices.enabled() {
+                result.searcher_token_value_usd = self.prices.value_deltas_usd(deltas).await;
+            }
+            
+            let tokens: Vec<Address> = deltas.iter().map(|delta| delta.token).collect();
+            let metadata = self.tokens.get_many(&tokens).await;
+            result.searcher_token_amounts = deltas
+                .iter()
+                .filter_map(|delta| metadata.get(&delta.token)?.amount(&delta.delta))
+                .collect();
+        }
+        
+        Ok(result)
+    }
+    
+    /// Simulate sensitive flow without logging, shadow sampling or anything else that persists it
+    pub async fn simulate_sensitive(
+        &self,
+        tx: &Sensitive<Transaction>,
+        priority: SimulationPriority,
+    ) -> Result<U256> {
+        let _permit = self.prioritized_permit(priority).await?;
+        
+        let result = self
+            .run_engine(self.primary.clone(), tx.expose().clone())
+            .await
+            .map_err(|_| anyhow!("Simulation failed"))?;
+        
+        Ok(result.profit)
+    }
+    
+    /// Run an engine under the simulation timeout, on the dedicated runtime when configured
+    async fn run_engine(&self, engine: Arc<dyn SimulationEngine>, tx: Transaction) -> Result<SimulationResult> {
         let timeout = Duration::from_millis(self.config.max_simulation_time_ms);
+        let simulation = async move {
+            tokio::time::timeout(timeout, engine.simulate(&tx))
+                .await
+                .map_err(|_| anyhow!("Simulation timed out after {:?}", timeout))?
+        };
         
-        // This would be a more complex implementation in a real system
-        // For now, let's simulate a simple evaluation based on gas price
-        let current_gas_price = self.blockchain_client.get_cached_gas_price().await?;
-        let tx_gas_price = tx.gas_price.unwrap_or(U256::zero());
+        match &self.pool {
+            Some(pool) => pool.run(simulation).await?,
+            None => simulation.await,
+        }
+    }
+    
+    /// Current cross-engine calibration report
+    pub fn calibration_report(&self) -> CalibrationReport {
+        let mut report = self.calibration.report.lock().clone();
+        report.recent_disagreements = self.calibration.recent.lock().iter().cloned().collect();
+        report
+    }
+    
+    /// Deterministically select a sample of transactions by hash
+    fn should_sample(&self, tx_hash: H256) -> bool {
+        if self.shadow.is_none() || self.config.shadow_sample_rate <= 0.0 {
+            return false;
+        }
         
-        // Calculate profit (this is highly simplified - real MEV would involve much more complex analysis)
-        let gas_limit = tx.gas;
-        let estimated_gas_used = gas_limit.saturating_mul(U256::from(80)).div(U256::from(100)); // Assume 80% gas usage
+        let bucket = tx_hash.to_low_u64_be() % 10_000;
+        (bucket as f64) < self.config.shadow_sample_rate * 10_000.0
+    }
+    
+    /// Run the shadow engine off the hot path and record any disagreement
+    fn spawn_shadow_comparison(&self, tx: Transaction, primary: SimulationResult) {
+        // Comparisons are a sample anyway, skip one rather than pile them up behind a slow engine
+        let permit = match self.shadow_permits.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                metrics::counter!("simulation_shadow_skipped_total", 1);
+                return;
+            }
+        };
+        let service = self.clone();
         
-        // Check if the transaction offers a premium over current gas price
-        let profit = if tx_gas_price > current_gas_price {
-            let premium = tx_gas_price.saturating_sub(current_gas_price);
-            premium.saturating_mul(estimated_gas_used)
+        tokio::spawn(async move {
+            let _permit = permit;
+            let shadow_engine = match &service.shadow {
+                Some(engine) => engine.clone(),
+                None => return,
+            };
+            
+            let shadow = match service.run_engine(shadow_engine.clone(), tx.clone()).await {
+                Ok(result) => result,
+                Err(e) => {
+                    debug!("Shadow simulation of {} failed: {}", tx.hash, e);
+                    SimulationResult {
+                        tx_hash: tx.hash,
+                        profit: U256::zero(),
+                        gas_used: U256::zero(),
+                        success: false,
+                        duration: Duration::ZERO,
+                        execution: None,
+                        searcher_token_value_usd: None,
+                        searcher_token_amounts: Vec::new(),
+                    }
+                }
+            };
+            
+            if let Err(e) = service.record_comparison(shadow_engine.name(), &primary, &shadow).await {
+                warn!("Failed to record simulation comparison for {}: {}", tx.hash, e);
+            }
+        });
+    }
+    
+    async fn record_comparison(
+        &self,
+        shadow_engine: &str,
+        primary: &SimulationResult,
+        shadow: &SimulationResult,
+    ) -> Result<()> {
+        let delta = if primary.profit > shadow.profit {
+            primary.profit - shadow.profit
         } else {
-            U256::zero()
+            shadow.profit - primary.profit
         };
+        let delta_wei = delta.min(U256::from(u128::MAX)).as_u128() as f64;
+        
+        let tolerance = primary
+            .profit
+            .max(shadow.profit)
+            .full_mul(U256::from(self.config.profit_tolerance_bps))
+            / U512::from(10_000);
+        let tolerance = U256::try_from(tolerance).unwrap_or(U256::MAX);
+        let success_mismatch = primary.success != shadow.success;
+        let profit_mismatch = delta > tolerance;
+        
+        {
+            let mut report = self.calibration.report.lock();
+            report.samples += 1;
+            report.mean_abs_profit_delta_wei +=
+                (delta_wei - report.mean_abs_profit_delta_wei) / report.samples as f64;
+            if success_mismatch {
+                report.success_mismatches += 1;
+            }
+            if profit_mismatch {
+                report.profit_mismatches += 1;
+            }
+        }
+        
+        if !success_mismatch && !profit_mismatch {
+            return Ok(());
+        }
+        
+        let disagreement = EngineDisagreement {
+            tx_hash: primary.tx_hash,
+            primary_engine: self.primary.name().to_string(),
+            shadow_engine: shadow_engine.to_string(),
+            primary_profit: primary.profit,
+            shadow_profit: shadow.profit,
+            primary_success: primary.success,
+            shadow_success: shadow.success,
+            observed_at: Utc::now(),
+        };
+        
+        {
+            let mut recent = self.calibration.recent.lock();
+            if recent.len() >= RECENT_DISAGREEMENTS {
+                recent.pop_front();
+            }
+            recent.push_back(disagreement.clone());
+        }
         
-        debug!("Simulation result for {}: profit={}", tx_hash, profit);
+        let disagreement = Arc::new(disagreement);
+        let write: DeferredWrite = Arc::new(move |pool: DbPool| -> BoxFuture<'static, Result<()>> {
+            let disagreement = disagreement.clone();
+            Box::pin(async move { record_disagreement(&pool, &disagreement).await })
+        });
         
-        Ok(profit)
+        self.health.write_or_defer(&self.db_pool, write).await
+    }
+    
+    /// Simulate an ordered bundle, each transaction seeing the state left by the ones before
+    ///
+    /// Runs under the per-transaction timeout scaled by the bundle length.
+    pub async fn simulate_bundle(&self, txs: Vec<Transaction>) -> Result<BundleSimulationResult> {
+        let _permit = self.permit().await?;
+        
+        let engine = self.bundle_engine.clone();
+        let timeout = Duration::from_millis(self.config.max_simulation_time_ms) * txs.len().max(1) as u32;
+        let simulation = async move {
+            tokio::time::timeout(timeout, engine.simulate_bundle(&txs))
+                .await
+                .map_err(|_| anyhow!("Bundle simulation timed out after {:?}", timeout))?
+        };
+        
+        match &self.pool {
+            Some(pool) => pool.run(simulation).await?,
+            None => simulation.await,
+        }
     }
     
     /// Estimate the profit for a bundle of transactions
@@ -98,10 +711,92 @@ impl SimulationService {
         Ok(total_profit)
     }
     
+    /// Wait until all in-flight simulations have completed
+    pub async fn wait_idle(&self) -> Result<()> {
+        let _permits = self
+            .semaphore
+            .acquire_many(self.config.worker_threads as u32)
+            .await?;
+        
+        Ok(())
+    }
+    
     /// Shutdown the simulation service
     pub async fn shutdown(&self) -> Result<()> {
         debug!("Shutting down simulation service");
         // Any cleanup needed
         Ok(())
     }
-} 
\ No newline at end of file
+}
+
+/// Build a simulation engine by its configured name
+fn create_engine(
+    name: &str,
+    blockchain_client: &Arc<BlockchainClient>,
+    fee_recipient: Address,
+) -> Result<Arc<dyn SimulationEngine>> {
+    match name {
+        "heuristic" => Ok(Arc::new(HeuristicEngine::new(blockchain_client.clone()))),
+        "revm" => Ok(Arc::new(RevmEngine::new(blockchain_client.clone(), fee_recipient))),
+        other => {
+            error!("Unknown simulation engine: {}", other);
+            Err(anyhow!("Unknown simulation engine: {}", other))
+        }
+    }
+}
+
+async fn record_disagreement(db_pool: &DbPool, disagreement: &EngineDisagreement) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO simulation_disagreements
+         (tx_hash, primary_engine, shadow_engine, primary_profit_wei, shadow_profit_wei,
+          primary_success, shadow_success, observed_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(format!("{:?}", disagreement.tx_hash))
+    .bind(&disagreement.primary_engine)
+    .bind(&disagreement.shadow_engine)
+    .bind(disagreement.primary_profit.to_string())
+    .bind(disagreement.shadow_profit.to_string())
+    .bind(disagreement.primary_success)
+    .bind(disagreement.shadow_success)
+    .bind(disagreement.observed_at)
+    .execute(db_pool)
+    .await
+    .context("Failed to record simulation disagreement")?;
+    
+    Ok(())
+}
+
+/// Recorded disagreements between the engines on a transaction, oldest first
+pub async fn recorded_disagreements(db_pool: &DbPool, tx_hash: H256) -> Result<Vec<EngineDisagreement>> {
+    let rows = sqlx::query(
+        "SELECT primary_engine, shadow_engine, primary_profit_wei, shadow_profit_wei,
+                primary_success, shadow_success, observed_at
+         FROM simulation_disagreements
+         WHERE tx_hash = $1
+         ORDER BY observed_at",
+    )
+    .bind(format!("{:?}", tx_hash))
+    .fetch_all(db_pool)
+    .await
+    .context("Failed to fetch simulation disagreements")?;
+    
+    rows.iter()
+        .map(|row| {
+            let profit = |column: &str| -> Result<U256> {
+                let text: String = row.try_get(column)?;
+                U256::from_dec_str(&text).map_err(|e| anyhow!("Invalid amount in {}: {}", column, e))
+            };
+            Ok(EngineDisagreement {
+                tx_hash,
+                primary_engine: row.try_get("primary_engine")?,
+                shadow_engine: row.try_get("shadow_engine")?,
+                primary_profit: profit("primary_profit_wei")?,
+                shadow_profit: profit("shadow_profit_wei")?,
+                primary_success: row.try_get("primary_success")?,
+                shadow_success: row.try_get("shadow_success")?,
+                observed_at: row.try_get("observed_at")?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,98 @@
+use anyhow::{anyhow, Context, Result};
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+};
+use tokio::runtime::{Builder, Handle};
+use tracing::{info, warn};
+
+use crate::config::TxOrderingConfig;
+
+/// Dedicated runtime simulations are spawned onto
+///
+/// The runtime lives on its own thread for the life of the process, so it is never
+/// dropped from within an async context and its workers, including the blocking pool
+/// revm executes on, are never shared with the API or monitor reactors.
+#[derive(Clone)]
+pub struct SimulationPool {
+    handle: Handle,
+}
+
+impl SimulationPool {
+    /// Start the dedicated runtime, or return None when simulations share the main runtime
+    pub fn new(config: &TxOrderingConfig) -> Result<Option<Self>> {
+        if !config.dedicated_simulation_runtime {
+            return Ok(None);
+        }
+        
+        let threads = config.simulation_threads.max(1);
+        let cores = Arc::new(config.simulation_cpu_affinity.clone());
+        let available: Vec<usize> = core_affinity::get_core_ids()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|core| core.id)
+            .collect();
+        if let Some(core) = cores.iter().find(|core| !available.contains(core)) {
+            return Err(anyhow!("Simulation CPU affinity names unavailable core {}", core));
+        }
+        
+        let next_core = Arc::new(AtomicUsize::new(0));
+        let mut builder = Builder::new_multi_thread();
+        builder
+            .worker_threads(threads)
+            .max_blocking_threads(threads)
+            .thread_name("simulation-worker")
+            .enable_all()
+            .on_thread_start(move || {
+                if cores.is_empty() {
+                    return;
+                }
+                
+                let core = cores[next_core.fetch_add(1, Ordering::Relaxed) % cores.len()];
+                if !core_affinity::set_for_current(core_affinity::CoreId { id: core }) {
+                    warn!("Failed to pin simulation thread to core {}", core);
+                }
+            });
+        
+        let (sender, receiver) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("simulation-runtime".to_string())
+            .spawn(move || match builder.build() {
+                Ok(runtime) => {
+                    let _ = sender.send(Ok(runtime.handle().clone()));
+                    runtime.block_on(std::future::pending::<()>());
+                }
+                Err(e) => {
+                    let _ = sender.send(Err(e));
+                }
+            })
+            .context("Failed to spawn simulation runtime thread")?;
+        
+        let handle = receiver
+            .recv()
+            .context("Simulation runtime thread exited during startup")?
+            .context("Failed to build simulation runtime")?;
+        
+        info!(
+            "Started simulation runtime with {} threads pinned to {:?}",
+            threads, config.simulation_cpu_affinity
+        );
+        
+        Ok(Some(Self { handle }))
+    }
+    
+    /// Run a future on the simulation runtime and wait for its output
+    pub async fn run<F>(&self, future: F) -> Result<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.handle
+            .spawn(future)
+            .await
+            .context("Simulation task panicked")
+    }
+}
@@ -0,0 +1,187 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    blockchain::fees::FeeEstimator,
+    config::SnapshotConfig,
+    core::{arbitrage::ArbitrageEngine, liquidation::LiquidationMonitor},
+};
+
+/// Keys the hot state is stored under
+const FEE_WINDOW_KEY: &str = "fee_window";
+const POOL_STATES_KEY: &str = "arbitrage_pool_states";
+const POSITIONS_KEY: &str = "liquidation_positions";
+
+/// A stored value and when it was taken
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    saved_at: DateTime<Utc>,
+    /// Head the state was read at, missing from snapshots that can't be checked against the chain
+    #[serde(default)]
+    block: Option<u64>,
+    state: T,
+}
+
+/// Local key-value store of in-memory hot state for warm restarts
+///
+/// Pool reserves, the fee estimator window and tracked lending positions are saved on shutdown
+/// and loaded on startup, so strategies are effective again without re-reading them all over
+/// RPC. Restored state is only a head start: every component replaces it with chain reads on
+/// its next refresh. Each snapshot keeps the head it was read at, and one older than
+/// `max_age_seconds`, more than `max_age_blocks` behind the head or ahead of it, as after
+/// switching to another chain or fork, is discarded.
+#[derive(Clone)]
+pub struct SnapshotStore {
+    /// Open embedded database, `None` when snapshots are disabled
+    db: Option<sled::Db>,
+    /// Configuration
+    config: SnapshotConfig,
+}
+
+impl SnapshotStore {
+    /// Create a new snapshot store, opening the database at the configured path
+    pub fn new(config: SnapshotConfig) -> Result<Self> {
+        let db = if config.enabled {
+            let db = sled::open(&config.path).context(format!("Failed to open snapshot store {}", config.path))?;
+            info!("Snapshotting hot state to {}", config.path);
+            Some(db)
+        } else {
+            None
+        };
+        
+        Ok(Self { db, config })
+    }
+    
+    /// Whether hot state is snapshotted
+    pub fn enabled(&self) -> bool {
+        self.db.is_some()
+    }
+    
+    /// Store a value read at a block under a key, replacing the previous snapshot
+    pub fn save<T: Serialize>(&self, key: &str, block: u64, state: &T) -> Result<()> {
+        let db = match &self.db {
+            Some(db) => db,
+            None => return Ok(()),
+        };
+        
+        let bytes = serde_json::to_vec(&Envelope {
+            saved_at: Utc::now(),
+            block: Some(block),
+            state,
+        })
+        .context(format!("Failed to encode snapshot {}", key))?;
+        db.insert(key, bytes).context(format!("Failed to store snapshot {}", key))?;
+        
+        Ok(())
+    }
+    
+    /// Value stored under a key, `None` when there is none or it is too old for the head
+    pub fn load<T: DeserializeOwned>(&self, key: &str, head: u64) -> Result<Option<T>> {
+        let db = match &self.db {
+            Some(db) => db,
+            None => return Ok(None),
+        };
+        let bytes = match db.get(key).context(format!("Failed to read snapshot {}", key))? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        
+        let envelope: Envelope<T> =
+            serde_json::from_slice(&bytes).context(format!("Failed to decode snapshot {}", key))?;
+        let age = Utc::now() - envelope.saved_at;
+        if age > Duration::seconds(self.config.max_age_seconds as i64) {
+            info!("Discarding snapshot {} taken {}s ago", key, age.num_seconds());
+            return Ok(None);
+        }
+        match envelope.block {
+            Some(block) if block <= head && head - block <= self.config.max_age_blocks => {}
+            Some(block) => {
+                info!("Discarding snapshot {} read at block {}, the head is {}", key, block, head);
+                return Ok(None);
+            }
+            None => {
+                info!("Discarding snapshot {} without the block it was read at", key);
+                return Ok(None);
+            }
+        }
+        
+        Ok(Some(envelope.state))
+    }
+    
+    /// Seed the hot state of the components from the last snapshot, if recent for the head
+    ///
+    /// A snapshot that can't be read is logged and skipped, the component then bootstraps from
+    /// chain as it would on a cold start.
+    pub fn restore(
+        &self,
+        head: u64,
+        fee_estimator: &FeeEstimator,
+        arbitrage_engine: &ArbitrageEngine,
+        liquidation_monitor: &LiquidationMonitor,
+    ) {
+        if !self.enabled() {
+            return;
+        }
+        
+        match self.load(FEE_WINDOW_KEY, head) {
+            Ok(Some(window)) => {
+                let blocks = fee_estimator.restore(window);
+                metrics::gauge!("snapshot_restored_entries", blocks as f64, "state" => FEE_WINDOW_KEY);
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to restore fee window: {:#}", e),
+        }
+        match self.load(POOL_STATES_KEY, head) {
+            Ok(Some(states)) => {
+                let pools = arbitrage_engine.restore_pool_states(states);
+                metrics::gauge!("snapshot_restored_entries", pools as f64, "state" => POOL_STATES_KEY);
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to restore pool states: {:#}", e),
+        }
+        match self.load(POSITIONS_KEY, head) {
+            Ok(Some(positions)) => {
+                let positions = liquidation_monitor.restore_positions(positions);
+                metrics::gauge!("snapshot_restored_entries", positions as f64, "state" => POSITIONS_KEY);
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to restore liquidation positions: {:#}", e),
+        }
+        
+        info!("Restored hot state from snapshot {}", self.config.path);
+    }
+    
+    /// Snapshot the hot state of the components, read at `head`, and write it to disk
+    ///
+    /// Nothing is saved before a head was seen, as the state couldn't be checked on restore.
+    pub async fn save_all(
+        &self,
+        head: Option<u64>,
+        fee_estimator: &FeeEstimator,
+        arbitrage_engine: &ArbitrageEngine,
+        liquidation_monitor: &LiquidationMonitor,
+    ) -> Result<()> {
+        let db = match &self.db {
+            Some(db) => db.clone(),
+            None => return Ok(()),
+        };
+        let head = match head {
+            Some(head) => head,
+            None => {
+                info!("No head seen, not snapshotting hot state");
+                return Ok(());
+            }
+        };
+        
+        self.save(FEE_WINDOW_KEY, head, &fee_estimator.snapshot())?;
+        self.save(POOL_STATES_KEY, head, &arbitrage_engine.pool_states())?;
+        self.save(POSITIONS_KEY, head, &liquidation_monitor.positions())?;
+        
+        let bytes = db.flush_async().await.context("Failed to flush snapshot store")?;
+        metrics::counter!("snapshot_bytes_written_total", bytes as u64);
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,937 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use ethers::{
+    types::{Address, Block, Log, Transaction, H256, U256, U512},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, Row};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use tracing::{debug, info};
+
+use crate::{
+    api::models,
+    blockchain::BlockchainClient,
+    config::{BlockchainConfig, LiquidStakingConfig},
+    database::{DbHealth, DbPool},
+    services::deposits::DEPOSIT_EVENT_SIGNATURE,
+    utils::units::{wei_to_eth, EthAmount},
+};
+
+/// Beacon chain slots per epoch
+pub const SLOTS_PER_EPOCH: u64 = 32;
+
+/// Pool event booked as a stake position
+const STAKED_EVENT: &str = "Staked(address,uint256,uint256)";
+
+/// Pool event booked as a withdrawal request
+const UNSTAKE_REQUESTED_EVENT: &str = "UnstakeRequested(address,uint256,uint256)";
+
+/// Pool event booked as the claim of the account's oldest open request for the same shares
+const WITHDRAWAL_CLAIMED_EVENT: &str = "WithdrawalClaimed(address,uint256,uint256)";
+
+/// Days of APR history returned with a portfolio
+const PORTFOLIO_APR_DAYS: i32 = 30;
+
+/// Computed epochs recomputed on each refresh, picking up rewards booked late; about a day
+const APR_RECOMPUTE_EPOCHS: u64 = 225;
+
+/// One stake deposit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StakePosition {
+    pub validator_index: Option<u64>,
+    pub amount: EthAmount,
+    pub shares: EthAmount,
+    pub tx_hash: String,
+    pub staked_at: DateTime<Utc>,
+}
+
+/// Rewards credited to an account over its lifetime
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccruedRewards {
+    pub consensus: EthAmount,
+    pub mev: EthAmount,
+    pub commission: EthAmount,
+    /// Consensus plus MEV rewards after commission
+    pub net: EthAmount,
+}
+
+/// An unstake request that has not been claimed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingWithdrawal {
+    pub id: i64,
+    pub amount: EthAmount,
+    pub requested_epoch: u64,
+    pub claimable_epoch: u64,
+    /// When the claimable epoch starts
+    pub claimable_at: DateTime<Utc>,
+    pub claimable: bool,
+}
+
+/// Realized APR of one day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AprPoint {
+    pub day: NaiveDate,
+    pub gross_apr: f64,
+    /// After commission
+    pub net_apr: f64,
+}
+
+/// Pool-wide realized APR over a trailing window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AprSummary {
+    pub window_days: u32,
+    /// Epochs with rewards in the window
+    pub epochs: u64,
+    /// Consensus plus MEV yield before commission
+    pub gross_apr: f64,
+    pub net_apr: f64,
+    pub consensus_apr: f64,
+    /// Yield from our share of MEV revenue
+    pub mev_apr: f64,
+    pub daily: Vec<AprPoint>,
+}
+
+/// Ether backing the pool and the shares issued against it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PoolTotals {
+    /// Deposits plus net rewards, less requested withdrawals
+    pub pooled: U256,
+    pub shares: U256,
+}
+
+impl PoolTotals {
+    /// Ether per share, 1 before any shares are issued
+    pub fn exchange_rate(&self) -> f64 {
+        if self.shares.is_zero() {
+            1.0
+        } else {
+            wei_to_eth(self.pooled) / wei_to_eth(self.shares)
+        }
+    }
+    
+    /// Shares a deposit of `amount` would be issued at the current rate
+    pub fn shares_for(&self, amount: U256) -> U256 {
+        if self.shares.is_zero() || self.pooled.is_zero() {
+            return amount;
+        }
+        (amount.full_mul(self.shares) / U512::from(self.pooled))
+            .try_into()
+            .unwrap_or(U256::MAX)
+    }
+}
+
+/// Preview of a stake deposit, computed without booking anything
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StakeQuote {
+    pub amount: EthAmount,
+    pub expected_shares: EthAmount,
+    /// Ether per share
+    pub exchange_rate: f64,
+    /// Realized APR over the trailing window, taken as the projection
+    pub projected_gross_apr: f64,
+    pub projected_net_apr: f64,
+    pub apr_window_days: u32,
+    /// Epochs between an unstake request and its claim
+    pub withdrawal_delay_epochs: u64,
+    pub withdrawal_delay_seconds: u64,
+    /// When a withdrawal requested now could be claimed
+    pub claimable_at_if_requested_now: DateTime<Utc>,
+}
+
+/// Everything an account holds with us, in one response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StakingPortfolio {
+    #[serde(with = "models::checksum")]
+    pub account: Address,
+    /// Deposited minus requested withdrawals
+    pub total_staked: EthAmount,
+    pub total_shares: EthAmount,
+    pub positions: Vec<StakePosition>,
+    pub rewards: AccruedRewards,
+    pub pending_withdrawals: Vec<PendingWithdrawal>,
+    pub apr_history: Vec<AprPoint>,
+}
+
+/// A stake to book as a position
+#[derive(Debug, Clone)]
+pub struct StakeDeposit {
+    pub account: Address,
+    pub validator_index: Option<u64>,
+    pub amount: U256,
+    pub shares: U256,
+    pub tx_hash: H256,
+    /// Deposit contract log funding the position, when the stake was deposited to a
+    /// validator in its own transaction
+    pub deposit_log_index: Option<u64>,
+    pub staked_at: DateTime<Utc>,
+}
+
+/// Rewards credited to one account for one epoch
+#[derive(Debug, Clone)]
+pub struct EpochReward {
+    pub account: Address,
+    pub epoch: u64,
+    pub consensus: U256,
+    pub mev: U256,
+    pub commission: U256,
+    /// Stake the rewards accrued on
+    pub principal: U256,
+}
+
+/// Postgres ledger of stake deposits, rewards and withdrawals
+///
+/// Stakes, unstake requests, withdrawal claims and the execution rewards of blocks proposed to the pool are
+/// booked from confirmed blocks, whichever path submitted them; reads aggregate per account
+/// for the API.
+#[derive(Clone)]
+pub struct StakingLedger {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Pool contract whose events and rewards are booked, unset when booking is off
+    pool: Option<Address>,
+    /// Beacon deposit contract, whose logs in a stake's transaction fund its position
+    deposit_contract: Option<Address>,
+    /// Operator commission taken from rewards, in basis points
+    commission_bps: u32,
+    /// Last epoch whose rewards were credited by this process
+    credited_epoch: Arc<AtomicU64>,
+    /// Beacon chain genesis, anchors epoch timestamps
+    genesis_timestamp: u64,
+    /// Seconds per slot
+    slot_duration_seconds: u64,
+    /// Epochs between an unstake request and its claim
+    withdrawal_delay_epochs: u64,
+}
+
+impl StakingLedger {
+    /// Create a new staking ledger
+    pub fn new(
+        db_pool: DbPool,
+        health: DbHealth,
+        blockchain: &BlockchainConfig,
+        staking: &LiquidStakingConfig,
+    ) -> Result<Self> {
+        let pool = if staking.pool_contract.is_empty() {
+            None
+        } else {
+            Some(staking.pool_contract.parse().context("Invalid staking pool contract address")?)
+        };
+        
+        Ok(Self {
+            db_pool,
+            health,
+            pool,
+            deposit_contract: staking.deposit_reconciliation.deposit_contract.parse().ok(),
+            commission_bps: staking.validator_commission_bps,
+            credited_epoch: Arc::new(AtomicU64::new(0)),
+            genesis_timestamp: blockchain.genesis_timestamp,
+            slot_duration_seconds: blockchain.slot_duration_seconds.max(1),
+            withdrawal_delay_epochs: staking.withdrawal_delay_epochs as u64,
+        })
+    }
+    
+    /// Epoch in progress now
+    pub fn current_epoch(&self) -> u64 {
+        let elapsed = (Utc::now().timestamp() as u64).saturating_sub(self.genesis_timestamp);
+        elapsed / self.epoch_seconds()
+    }
+    
+    /// Wall-clock start of an epoch
+    pub fn epoch_start(&self, epoch: u64) -> DateTime<Utc> {
+        let seconds = self.genesis_timestamp + epoch * self.epoch_seconds();
+        Utc.timestamp_opt(seconds as i64, 0).single().unwrap_or_else(Utc::now)
+    }
+    
+    /// Epochs in a year at the configured slot time
+    pub fn epochs_per_year(&self) -> f64 {
+        365.0 * 86_400.0 / self.epoch_seconds() as f64
+    }
+    
+    /// Operator commission taken from rewards, in basis points
+    pub fn commission_bps(&self) -> u32 {
+        self.commission_bps
+    }
+    
+    /// Seconds per epoch
+    pub fn epoch_seconds(&self) -> u64 {
+        self.slot_duration_seconds * SLOTS_PER_EPOCH
+    }
+    
+    /// Epoch a block timestamp falls in
+    fn epoch_at(&self, timestamp: u64) -> u64 {
+        timestamp.saturating_sub(self.genesis_timestamp) / self.epoch_seconds()
+    }
+    
+    /// Book a confirmed block's pool events and rewards, crediting the previous epoch's rewards
+    /// on the first block of a new one
+    ///
+    /// Every write is keyed by transaction or block, so booking a block twice changes nothing.
+    pub async fn book_block(&self, blockchain_client: &BlockchainClient, block: &Block<Transaction>) -> Result<()> {
+        let pool = match self.pool {
+            Some(pool) => pool,
+            None => return Ok(()),
+        };
+        let block_number = block.number.unwrap_or_default().as_u64();
+        let timestamp = block.timestamp.as_u64();
+        let booked_at = Utc.timestamp_opt(timestamp as i64, 0).single().unwrap_or_else(Utc::now);
+        let epoch = self.epoch_at(timestamp);
+        
+        let proposed = block.author == Some(pool);
+        let touched = block.transactions.iter().any(|tx| tx.to == Some(pool));
+        if proposed || touched {
+            let receipts = blockchain_client.get_block_receipts(block_number).await?;
+            let base_fee = block.base_fee_per_gas.unwrap_or_default();
+            
+            let mut reward = U256::zero();
+            for (tx, receipt) in block.transactions.iter().zip(&receipts) {
+                if receipt.status.map_or(false, |status| status.is_zero()) {
+                    continue;
+                }
+                // Stakes deposited to a validator in the same transaction take its deposits in order
+                let deposit_topic = H256::from(keccak256(DEPOSIT_EVENT_SIGNATURE));
+                let mut deposits = receipt
+                    .logs
+                    .iter()
+                    .filter(|log| Some(log.address) == self.deposit_contract)
+                    .filter(|log| log.topics.first() == Some(&deposit_topic))
+                    .filter_map(|log| log.log_index.map(|index| index.as_u64()));
+                for log in receipt.logs.iter().filter(|log| log.address == pool) {
+                    self.book_event(log, tx.hash, &mut deposits, booked_at).await?;
+                }
+                if proposed {
+                    let price = receipt.effective_gas_price.unwrap_or_default();
+                    let tip = receipt.gas_used.unwrap_or_default().saturating_mul(price.saturating_sub(base_fee));
+                    reward = reward.saturating_add(tip);
+                    // Builder payments are plain transfers; stake calls carry calldata
+                    if tx.to == Some(pool) && tx.input.is_empty() {
+                        reward = reward.saturating_add(tx.value);
+                    }
+                }
+            }
+            
+            if proposed && !reward.is_zero() {
+                let block_hash = block.hash.unwrap_or_default();
+                self.health
+                    .retry(|| self.record_block_reward(block_number, block_hash, epoch, reward))
+                    .await?;
+            }
+        }
+        
+        if epoch > 0 && self.credited_epoch.load(Ordering::Relaxed) < epoch - 1 {
+            self.credit_epoch(epoch - 1).await?;
+            self.credited_epoch.fetch_max(epoch - 1, Ordering::Relaxed);
+        }
+        
+        Ok(())
+    }
+    
+    /// Book one pool log, ignoring events other than stakes, unstake requests and claims
+    async fn book_event(
+        &self,
+        log: &Log,
+        tx_hash: H256,
+        deposits: &mut impl Iterator<Item = u64>,
+        booked_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let (topic, account) = match log.topics.as_slice() {
+            [topic, account, ..] => (*topic, Address::from(*account)),
+            _ => return Ok(()),
+        };
+        if log.data.len() < 64 {
+            return Ok(());
+        }
+        let amount = U256::from_big_endian(&log.data[..32]);
+        let shares = U256::from_big_endian(&log.data[32..64]);
+        
+        if topic == H256::from(keccak256(STAKED_EVENT)) {
+            self.record_stake(&StakeDeposit {
+                account,
+                validator_index: None,
+                amount,
+                shares,
+                tx_hash,
+                deposit_log_index: deposits.next(),
+                staked_at: booked_at,
+            })
+            .await
+        } else if topic == H256::from(keccak256(UNSTAKE_REQUESTED_EVENT)) {
+            self.request_withdrawal(account, amount, shares, tx_hash, booked_at).await.map(|_| ())
+        } else if topic == H256::from(keccak256(WITHDRAWAL_CLAIMED_EVENT)) {
+            let log_index = log.log_index.unwrap_or_default().as_u64();
+            if !self.mark_claimed(account, shares, tx_hash, log_index, booked_at).await? {
+                debug!("Claim in {:?} matched no open withdrawal of {:?}", tx_hash, account);
+            }
+            Ok(())
+        } else {
+            Ok(())
+        }
+    }
+    
+    /// Book the execution rewards of a block proposed to the pool, replacing a reorged block's
+    async fn record_block_reward(
+        &self,
+        block_number: u64,
+        block_hash: H256,
+        epoch: u64,
+        amount: U256,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO staking_block_rewards (block_number, block_hash, epoch, amount_wei)
+             VALUES ($1, $2, $3, $4::NUMERIC)
+             ON CONFLICT (block_number) DO UPDATE SET
+                 block_hash = EXCLUDED.block_hash,
+                 epoch = EXCLUDED.epoch,
+                 amount_wei = EXCLUDED.amount_wei,
+                 booked_at = NOW()",
+        )
+        .bind(block_number as i64)
+        .bind(format!("{:?}", block_hash))
+        .bind(epoch as i64)
+        .bind(amount.to_string())
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to record staking block reward")?;
+        
+        Ok(())
+    }
+    
+    /// Split an epoch's booked block rewards across accounts by their principal at its end
+    ///
+    /// Consensus rewards aren't visible from the execution layer and are credited as zero.
+    async fn credit_epoch(&self, epoch: u64) -> Result<()> {
+        let earned = self.health.retry(|| self.epoch_earnings(epoch)).await?;
+        if earned.is_zero() {
+            return Ok(());
+        }
+        
+        let principals = self.health.retry(|| self.principals_at_end(epoch)).await?;
+        let total = principals.iter().fold(U256::zero(), |sum, (_, principal)| sum.saturating_add(*principal));
+        if total.is_zero() {
+            return Ok(());
+        }
+        
+        for (account, principal) in principals {
+            let mev: U256 = (earned.full_mul(principal) / U512::from(total)).try_into().unwrap_or(U256::MAX);
+            let commission = mev.full_mul(U256::from(self.commission_bps)) / U512::from(10_000);
+            self.record_reward(&EpochReward {
+                account,
+                epoch,
+                consensus: U256::zero(),
+                mev,
+                commission: commission.try_into().unwrap_or(U256::MAX),
+                principal,
+            })
+            .await?;
+        }
+        
+        info!("Credited {} wei of epoch {} rewards across the pool", earned, epoch);
+        Ok(())
+    }
+    
+    /// Block rewards booked for an epoch
+    async fn epoch_earnings(&self, epoch: u64) -> Result<U256> {
+        let earned = sqlx::query(
+            "SELECT COALESCE(SUM(amount_wei), 0)::TEXT AS amount FROM staking_block_rewards WHERE epoch = $1",
+        )
+        .bind(epoch as i64)
+        .fetch_one(&self.db_pool)
+        .await
+        .context("Failed to load epoch block rewards")?;
+        
+        Ok(wei(&earned, "amount")?.wei())
+    }
+    
+    /// Accounts with principal staked at the end of an epoch, and their principal
+    async fn principals_at_end(&self, epoch: u64) -> Result<Vec<(Address, U256)>> {
+        let rows = sqlx::query(
+            "SELECT account, SUM(amount)::TEXT AS principal FROM (
+                 SELECT account, amount_wei AS amount FROM stake_positions WHERE staked_at < $1
+                 UNION ALL
+                 SELECT account, -amount_wei FROM staking_withdrawals WHERE requested_at < $1
+             ) flows
+             GROUP BY account
+             HAVING SUM(amount) > 0",
+        )
+        .bind(self.epoch_start(epoch + 1))
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to load staking principals")?;
+        
+        let mut principals = Vec::with_capacity(rows.len());
+        for row in rows {
+            let account: String = row.try_get("account")?;
+            let account: Address = account
+                .parse()
+                .map_err(|e| anyhow!("Invalid staking account {}: {}", account, e))?;
+            principals.push((account, wei(&row, "principal")?.wei()));
+        }
+        Ok(principals)
+    }
+    
+    /// Book a stake deposit; replays of the same transaction are ignored
+    pub async fn record_stake(&self, deposit: &StakeDeposit) -> Result<()> {
+        self.health.retry(|| self.record_stake_once(deposit)).await
+    }
+    
+    /// Book an account's rewards for an epoch; each epoch is credited once
+    pub async fn record_reward(&self, reward: &EpochReward) -> Result<()> {
+        self.health.retry(|| self.record_reward_once(reward)).await
+    }
+    
+    /// Book an unstake request, returning its id and claimable epoch, or `None` if the
+    /// requesting transaction was already booked
+    pub async fn request_withdrawal(
+        &self,
+        account: Address,
+        amount: U256,
+        shares: U256,
+        tx_hash: H256,
+        requested_at: DateTime<Utc>,
+    ) -> Result<Option<(i64, u64)>> {
+        self.health
+            .retry(|| self.request_withdrawal_once(account, amount, shares, tx_hash, requested_at))
+            .await
+    }
+    
+    /// Mark an account's oldest open withdrawal for `shares` claimed by a pool log, returning
+    /// false if none was open or the log was already booked
+    pub async fn mark_claimed(
+        &self,
+        account: Address,
+        shares: U256,
+        tx_hash: H256,
+        log_index: u64,
+        claimed_at: DateTime<Utc>,
+    ) -> Result<bool> {
+        self.health
+            .retry(|| self.mark_claimed_once(account, shares, tx_hash, log_index, claimed_at))
+            .await
+    }
+    
+    /// Aggregate booked rewards into the per-epoch APR series, returning the epochs written
+    ///
+    /// Only completed epochs are computed. The most recent ones are recomputed on every run
+    /// so rewards booked after their epoch was first aggregated are still counted.
+    pub async fn refresh_apr(&self) -> Result<u64> {
+        self.health.retry(|| self.refresh_apr_once()).await
+    }
+    
+    /// Pool-wide APR over the trailing days, overall and per day
+    pub async fn apr_summary(&self, days: u32) -> Result<AprSummary> {
+        self.health.retry(|| self.apr_summary_once(days)).await
+    }
+    
+    /// Pool-wide pooled ether and outstanding shares
+    pub async fn pool_totals(&self) -> Result<PoolTotals> {
+        self.health.retry(|| self.pool_totals_once()).await
+    }
+    
+    /// Preview staking `amount`: shares issued, exchange rate, projected APR and withdrawal timing
+    pub async fn quote(&self, amount: U256, apr_window_days: u32) -> Result<StakeQuote> {
+        let totals = self.pool_totals().await?;
+        let apr = self.apr_summary(apr_window_days).await?;
+        let claimable_epoch = self.current_epoch() + self.withdrawal_delay_epochs;
+        
+        Ok(StakeQuote {
+            amount: EthAmount::from_wei(amount),
+            expected_shares: EthAmount::from_wei(totals.shares_for(amount)),
+            exchange_rate: totals.exchange_rate(),
+            projected_gross_apr: apr.gross_apr,
+            projected_net_apr: apr.net_apr,
+            apr_window_days,
+            withdrawal_delay_epochs: self.withdrawal_delay_epochs,
+            withdrawal_delay_seconds: self.withdrawal_delay_epochs * self.epoch_seconds(),
+            claimable_at_if_requested_now: self.epoch_start(claimable_epoch),
+        })
+    }
+    
+    /// Positions, rewards, pending withdrawals and recent APR of an account
+    pub async fn portfolio(&self, account: Address) -> Result<StakingPortfolio> {
+        self.health.retry(|| self.portfolio_once(account)).await
+    }
+    
+    /// Daily realized APR of an account over the trailing days
+    async fn apr_history(&self, account: &str, days: i32) -> Result<Vec<AprPoint>> {
+        let rows = sqlx::query(
+            "SELECT credited_at::DATE AS day,
+                    SUM(consensus_wei + mev_wei)::FLOAT8 AS gross,
+                    SUM(commission_wei)::FLOAT8 AS commission,
+                    AVG(principal_wei)::FLOAT8 AS principal,
+                    COUNT(*) AS epochs
+             FROM staking_rewards
+             WHERE account = $1 AND credited_at > NOW() - make_interval(days => $2)
+             GROUP BY day ORDER BY day",
+        )
+        .bind(account)
+        .bind(days)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to load APR history")?;
+        
+        let epochs_per_year = self.epochs_per_year();
+        let mut history = Vec::with_capacity(rows.len());
+        for row in rows {
+            let gross: f64 = row.try_get("gross")?;
+            let commission: f64 = row.try_get("commission")?;
+            let principal: f64 = row.try_get("principal")?;
+            let epochs = row.try_get::<i64, _>("epochs")? as f64;
+            if principal <= 0.0 || epochs <= 0.0 {
+                continue;
+            }
+            
+            // Annualize the day's per-epoch yield
+            let annualize = epochs_per_year / epochs / principal;
+            history.push(AprPoint {
+                day: row.try_get("day")?,
+                gross_apr: gross * annualize,
+                net_apr: (gross - commission) * annualize,
+            });
+        }
+        
+        Ok(history)
+    }
+    
+    async fn record_stake_once(&self, deposit: &StakeDeposit) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO stake_positions
+                 (account, validator_index, amount_wei, shares, tx_hash, deposit_log_index, staked_at)
+             VALUES ($1, $2, $3::NUMERIC, $4::NUMERIC, $5, $6, $7)
+             ON CONFLICT (tx_hash) DO NOTHING",
+        )
+        .bind(format!("{:?}", deposit.account))
+        .bind(deposit.validator_index.map(|index| index as i64))
+        .bind(deposit.amount.to_string())
+        .bind(deposit.shares.to_string())
+        .bind(format!("{:?}", deposit.tx_hash))
+        .bind(deposit.deposit_log_index.map(|index| index as i64))
+        .bind(deposit.staked_at)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to record stake")?;
+        
+        Ok(())
+    }
+    
+    async fn record_reward_once(&self, reward: &EpochReward) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO staking_rewards (account, epoch, consensus_wei, mev_wei, commission_wei, principal_wei)
+             VALUES ($1, $2, $3::NUMERIC, $4::NUMERIC, $5::NUMERIC, $6::NUMERIC)
+             ON CONFLICT (account, epoch) DO NOTHING",
+        )
+        .bind(format!("{:?}", reward.account))
+        .bind(reward.epoch as i64)
+        .bind(reward.consensus.to_string())
+        .bind(reward.mev.to_string())
+        .bind(reward.commission.to_string())
+        .bind(reward.principal.to_string())
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to record staking reward")?;
+        
+        Ok(())
+    }
+    
+    async fn request_withdrawal_once(
+        &self,
+        account: Address,
+        amount: U256,
+        shares: U256,
+        tx_hash: H256,
+        requested_at: DateTime<Utc>,
+    ) -> Result<Option<(i64, u64)>> {
+        let requested_epoch = self.epoch_at(requested_at.timestamp().max(0) as u64);
+        let claimable_epoch = requested_epoch + self.withdrawal_delay_epochs;
+        
+        let row = sqlx::query(
+            "INSERT INTO staking_withdrawals
+                 (account, amount_wei, shares, requested_epoch, claimable_epoch, tx_hash, requested_at)
+             VALUES ($1, $2::NUMERIC, $3::NUMERIC, $4, $5, $6, $7)
+             ON CONFLICT (tx_hash) DO NOTHING
+             RETURNING id",
+        )
+        .bind(format!("{:?}", account))
+        .bind(amount.to_string())
+        .bind(shares.to_string())
+        .bind(requested_epoch as i64)
+        .bind(claimable_epoch as i64)
+        .bind(format!("{:?}", tx_hash))
+        .bind(requested_at)
+        .fetch_optional(&self.db_pool)
+        .await
+        .context("Failed to record withdrawal request")?;
+        
+        match row {
+            Some(row) => Ok(Some((row.try_get("id")?, claimable_epoch))),
+            None => Ok(None),
+        }
+    }
+    
+    async fn mark_claimed_once(
+        &self,
+        account: Address,
+        shares: U256,
+        tx_hash: H256,
+        log_index: u64,
+        claimed_at: DateTime<Utc>,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE staking_withdrawals
+             SET claimed_at = $5, claim_tx_hash = $3, claim_log_index = $4
+             WHERE id = (SELECT id FROM staking_withdrawals
+                         WHERE account = $1 AND shares = $2::NUMERIC AND claimed_at IS NULL
+                         ORDER BY requested_at, id LIMIT 1)
+               AND NOT EXISTS (SELECT 1 FROM staking_withdrawals
+                               WHERE claim_tx_hash = $3 AND claim_log_index = $4)",
+        )
+        .bind(format!("{:?}", account))
+        .bind(shares.to_string())
+        .bind(format!("{:?}", tx_hash))
+        .bind(log_index as i64)
+        .bind(claimed_at)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to mark withdrawal claimed")?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
+    async fn refresh_apr_once(&self) -> Result<u64> {
+        let row = sqlx::query("SELECT MAX(epoch) AS epoch FROM staking_apr")
+            .fetch_one(&self.db_pool)
+            .await
+            .context("Failed to load last APR epoch")?;
+        let from = row
+            .try_get::<Option<i64>, _>("epoch")?
+            .map_or(0, |epoch| (epoch as u64).saturating_sub(APR_RECOMPUTE_EPOCHS));
+        
+        let result = sqlx::query(
+            "INSERT INTO staking_apr
+                 (epoch, consensus_wei, mev_wei, commission_wei, principal_wei, gross_apr, net_apr, epoch_start)
+             SELECT epoch,
+                    SUM(consensus_wei),
+                    SUM(mev_wei),
+                    SUM(commission_wei),
+                    SUM(principal_wei),
+                    (SUM(consensus_wei + mev_wei) / SUM(principal_wei))::FLOAT8 * $3,
+                    (SUM(consensus_wei + mev_wei - commission_wei) / SUM(principal_wei))::FLOAT8 * $3,
+                    to_timestamp(($4 + epoch * $5)::FLOAT8)
+             FROM staking_rewards
+             WHERE epoch >= $1 AND epoch < $2
+             GROUP BY epoch
+             HAVING SUM(principal_wei) > 0
+             ON CONFLICT (epoch) DO UPDATE SET
+                 consensus_wei = EXCLUDED.consensus_wei,
+                 mev_wei = EXCLUDED.mev_wei,
+                 commission_wei = EXCLUDED.commission_wei,
+                 principal_wei = EXCLUDED.principal_wei,
+                 gross_apr = EXCLUDED.gross_apr,
+                 net_apr = EXCLUDED.net_apr,
+                 computed_at = NOW()",
+        )
+        .bind(from as i64)
+        .bind(self.current_epoch() as i64)
+        .bind(self.epochs_per_year())
+        .bind(self.genesis_timestamp as i64)
+        .bind(self.epoch_seconds() as i64)
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to compute staking APR")?;
+        
+        let written = result.rows_affected();
+        debug!("Computed staking APR for {} epochs from {}", written, from);
+        Ok(written)
+    }
+    
+    async fn apr_summary_once(&self, days: u32) -> Result<AprSummary> {
+        let epochs_per_year = self.epochs_per_year();
+        
+        let totals = sqlx::query(
+            "SELECT COUNT(*) AS epochs,
+                    COALESCE(SUM(consensus_wei), 0)::FLOAT8 AS consensus,
+                    COALESCE(SUM(mev_wei), 0)::FLOAT8 AS mev,
+                    COALESCE(SUM(commission_wei), 0)::FLOAT8 AS commission,
+                    COALESCE(SUM(principal_wei), 0)::FLOAT8 AS principal
+             FROM staking_apr
+             WHERE epoch_start > NOW() - make_interval(days => $1)",
+        )
+        .bind(days as i32)
+        .fetch_one(&self.db_pool)
+        .await
+        .context("Failed to load staking APR")?;
+        
+        let consensus: f64 = totals.try_get("consensus")?;
+        let mev: f64 = totals.try_get("mev")?;
+        let commission: f64 = totals.try_get("commission")?;
+        let principal: f64 = totals.try_get("principal")?;
+        // Principal is summed per epoch, so this is the principal-weighted yield of an epoch
+        let annualize = if principal > 0.0 { epochs_per_year / principal } else { 0.0 };
+        
+        let rows = sqlx::query(
+            "SELECT epoch_start::DATE AS day,
+                    (SUM(consensus_wei + mev_wei) / SUM(principal_wei))::FLOAT8 * $2 AS gross_apr,
+                    (SUM(consensus_wei + mev_wei - commission_wei) / SUM(principal_wei))::FLOAT8 * $2 AS net_apr
+             FROM staking_apr
+             WHERE epoch_start > NOW() - make_interval(days => $1)
+             GROUP BY day ORDER BY day",
+        )
+        .bind(days as i32)
+        .bind(epochs_per_year)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to load daily staking APR")?;
+        
+        let mut daily = Vec::with_capacity(rows.len());
+        for row in rows {
+            daily.push(AprPoint {
+                day: row.try_get("day")?,
+                gross_apr: row.try_get("gross_apr")?,
+                net_apr: row.try_get("net_apr")?,
+            });
+        }
+        
+        Ok(AprSummary {
+            window_days: days,
+            epochs: totals.try_get::<i64, _>("epochs")? as u64,
+            gross_apr: (consensus + mev) * annualize,
+            net_apr: (consensus + mev - commission) * annualize,
+            consensus_apr: consensus * annualize,
+            mev_apr: mev * annualize,
+            daily,
+        })
+    }
+    
+    async fn pool_totals_once(&self) -> Result<PoolTotals> {
+        let row = sqlx::query(
+            "SELECT ((SELECT COALESCE(SUM(amount_wei), 0) FROM stake_positions)
+                     + (SELECT COALESCE(SUM(consensus_wei + mev_wei - commission_wei), 0) FROM staking_rewards)
+                     - (SELECT COALESCE(SUM(amount_wei), 0) FROM staking_withdrawals))::TEXT AS pooled,
+                    ((SELECT COALESCE(SUM(shares), 0) FROM stake_positions)
+                     - (SELECT COALESCE(SUM(shares), 0) FROM staking_withdrawals))::TEXT AS shares",
+        )
+        .fetch_one(&self.db_pool)
+        .await
+        .context("Failed to load pool totals")?;
+        
+        // Negative totals only arise from inconsistent bookings; treat them as empty
+        let total = |column: &str| -> Result<U256> {
+            let text: String = row.try_get(column)?;
+            if text.starts_with('-') {
+                return Ok(U256::zero());
+            }
+            U256::from_dec_str(&text).map_err(|e| anyhow!("Invalid pool {}: {}", column, e))
+        };
+        
+        Ok(PoolTotals {
+            pooled: total("pooled")?,
+            shares: total("shares")?,
+        })
+    }
+    
+    async fn portfolio_once(&self, account: Address) -> Result<StakingPortfolio> {
+        let key = format!("{:?}", account);
+        
+        let rows = sqlx::query(
+            "SELECT validator_index, amount_wei::TEXT AS amount, shares::TEXT AS shares, tx_hash, staked_at
+             FROM stake_positions WHERE account = $1 ORDER BY staked_at",
+        )
+        .bind(&key)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to load stake positions")?;
+        
+        let mut positions = Vec::with_capacity(rows.len());
+        for row in rows {
+            positions.push(StakePosition {
+                validator_index: row.try_get::<Option<i64>, _>("validator_index")?.map(|index| index as u64),
+                amount: wei(&row, "amount")?,
+                shares: wei(&row, "shares")?,
+                tx_hash: row.try_get("tx_hash")?,
+                staked_at: row.try_get("staked_at")?,
+            });
+        }
+        
+        let row = sqlx::query(
+            "SELECT COALESCE(SUM(consensus_wei), 0)::TEXT AS consensus,
+                    COALESCE(SUM(mev_wei), 0)::TEXT AS mev,
+                    COALESCE(SUM(commission_wei), 0)::TEXT AS commission
+             FROM staking_rewards WHERE account = $1",
+        )
+        .bind(&key)
+        .fetch_one(&self.db_pool)
+        .await
+        .context("Failed to load staking rewards")?;
+        
+        let consensus = wei(&row, "consensus")?;
+        let mev = wei(&row, "mev")?;
+        let commission = wei(&row, "commission")?;
+        let net = (consensus.wei() + mev.wei()).saturating_sub(commission.wei());
+        let rewards = AccruedRewards {
+            consensus,
+            mev,
+            commission,
+            net: EthAmount::from_wei(net),
+        };
+        
+        let rows = sqlx::query(
+            "SELECT id, amount_wei::TEXT AS amount, shares::TEXT AS shares, requested_epoch, claimable_epoch, claimed_at
+             FROM staking_withdrawals WHERE account = $1 ORDER BY requested_epoch",
+        )
+        .bind(&key)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to load withdrawals")?;
+        
+        let current_epoch = self.current_epoch();
+        let mut withdrawn = (U256::zero(), U256::zero());
+        let mut pending_withdrawals = Vec::new();
+        for row in rows {
+            let amount = wei(&row, "amount")?;
+            withdrawn.0 += amount.wei();
+            withdrawn.1 += wei(&row, "shares")?.wei();
+            
+            if row.try_get::<Option<DateTime<Utc>>, _>("claimed_at")?.is_some() {
+                continue;
+            }
+            let claimable_epoch = row.try_get::<i64, _>("claimable_epoch")? as u64;
+            pending_withdrawals.push(PendingWithdrawal {
+                id: row.try_get("id")?,
+                amount,
+                requested_epoch: row.try_get::<i64, _>("requested_epoch")? as u64,
+                claimable_epoch,
+                claimable_at: self.epoch_start(claimable_epoch),
+                claimable: claimable_epoch <= current_epoch,
+            });
+        }
+        
+        let deposited = positions.iter().fold(U256::zero(), |sum, p| sum + p.amount.wei());
+        let shares = positions.iter().fold(U256::zero(), |sum, p| sum + p.shares.wei());
+        
+        let apr_history = self.apr_history(&key, PORTFOLIO_APR_DAYS).await?;
+        
+        debug!("Built staking portfolio for {:?} with {} positions", account, positions.len());
+        
+        Ok(StakingPortfolio {
+            account,
+            total_staked: EthAmount::from_wei(deposited.saturating_sub(withdrawn.0)),
+            total_shares: EthAmount::from_wei(shares.saturating_sub(withdrawn.1)),
+            positions,
+            rewards,
+            pending_withdrawals,
+            apr_history,
+        })
+    }
+}
+
+/// Read a NUMERIC wei column selected as text
+fn wei(row: &PgRow, column: &str) -> Result<EthAmount> {
+    let text: String = row.try_get(column)?;
+    U256::from_dec_str(&text)
+        .map(EthAmount::from_wei)
+        .map_err(|e| anyhow!("Invalid wei amount in {}: {}", column, e))
+}
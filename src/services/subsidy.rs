@@ -0,0 +1,252 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::types::{H256, U256};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::{collections::HashMap, sync::Arc};
+use tracing::{debug, info};
+
+use crate::{
+    api::models,
+    config::{SubsidyConfig, SubsidyRuleConfig},
+    database::{DbHealth, DbPool},
+};
+
+/// Subsidy added to a bid above the value it extracts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subsidy {
+    /// Rule that granted the subsidy
+    pub rule: String,
+    #[serde(with = "models::wei")]
+    pub amount: U256,
+}
+
+/// What is known about a slot when pricing a bid
+#[derive(Debug, Clone)]
+pub struct SlotContext {
+    pub slot: u64,
+    pub proposer_pubkey: String,
+}
+
+/// When a subsidy rule applies
+#[derive(Debug, Clone)]
+enum SubsidyCondition {
+    /// The slot's proposer is one of our own validators
+    OwnValidator(Vec<String>),
+    /// We haven't won a slot in at least this many slots
+    SlotsSinceLastWin(u64),
+}
+
+#[derive(Debug, Clone)]
+struct SubsidyRule {
+    name: String,
+    condition: SubsidyCondition,
+    max_subsidy: U256,
+}
+
+impl SubsidyRule {
+    fn from_config(config: &SubsidyRuleConfig) -> Result<Self> {
+        let condition = match config.condition.as_str() {
+            "own_validator" => SubsidyCondition::OwnValidator(config.proposer_pubkeys.clone()),
+            "reputation" => SubsidyCondition::SlotsSinceLastWin(config.slots_since_last_win),
+            other => return Err(anyhow!("Unknown subsidy condition: {}", other)),
+        };
+        
+        Ok(Self {
+            name: config.name.clone(),
+            condition,
+            max_subsidy: U256::from_dec_str(&config.max_subsidy_wei)
+                .context(format!("Invalid max subsidy for rule {}", config.name))?,
+        })
+    }
+    
+    fn applies(&self, slot: &SlotContext, last_won_slot: Option<u64>) -> bool {
+        match &self.condition {
+            SubsidyCondition::OwnValidator(pubkeys) => pubkeys.iter().any(|p| *p == slot.proposer_pubkey),
+            SubsidyCondition::SlotsSinceLastWin(slots) => {
+                last_won_slot.map_or(true, |won| slot.slot.saturating_sub(won) >= *slots)
+            }
+        }
+    }
+}
+
+/// Subsidy a submitted bid carries, paid only if its block lands
+#[derive(Debug, Clone)]
+struct PendingSubsidy {
+    slot: u64,
+    subsidy: Subsidy,
+}
+
+/// Service deciding when to bid above extracted value, and tracking spend against the budget
+#[derive(Clone)]
+pub struct SubsidyService {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries
+    health: DbHealth,
+    /// Configuration
+    config: SubsidyConfig,
+    /// Parsed subsidy rules, first match wins
+    rules: Arc<Vec<SubsidyRule>>,
+    /// Budget per period
+    budget: U256,
+    /// Subsidized bids submitted but not yet landed, by block hash
+    pending: Arc<Mutex<HashMap<H256, PendingSubsidy>>>,
+    /// Subsidy paid within the current budget period
+    spent: Arc<RwLock<U256>>,
+    /// Most recent slot we won with a subsidized bid or otherwise
+    last_won_slot: Arc<RwLock<Option<u64>>>,
+}
+
+impl SubsidyService {
+    /// Create a new subsidy service
+    pub fn new(db_pool: DbPool, health: DbHealth, config: SubsidyConfig) -> Result<Self> {
+        let rules = config
+            .rules
+            .iter()
+            .map(SubsidyRule::from_config)
+            .collect::<Result<Vec<_>>>()?;
+        let budget = U256::from_dec_str(&config.budget_wei).context("Invalid subsidy budget")?;
+        
+        Ok(Self {
+            db_pool,
+            health,
+            config,
+            rules: Arc::new(rules),
+            budget,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            spent: Arc::new(RwLock::new(U256::zero())),
+            last_won_slot: Arc::new(RwLock::new(None)),
+        })
+    }
+    
+    /// Whether subsidy mode is enabled
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Budget left in the current period
+    pub fn remaining_budget(&self) -> U256 {
+        self.budget.saturating_sub(*self.spent.read())
+    }
+    
+    /// Subsidy to add to a bid for this slot, capped by the rule and the remaining budget
+    pub fn subsidy_for(&self, slot: &SlotContext) -> Option<Subsidy> {
+        if !self.enabled() {
+            return None;
+        }
+        
+        let last_won_slot = *self.last_won_slot.read();
+        let rule = self.rules.iter().find(|rule| rule.applies(slot, last_won_slot))?;
+        
+        // Subsidized bids for other slots may still land; only one bid per slot can, so reserve the largest
+        let reserved = {
+            let pending = self.pending.lock();
+            let mut by_slot: HashMap<u64, U256> = HashMap::new();
+            for p in pending.values().filter(|p| p.slot != slot.slot) {
+                let max = by_slot.entry(p.slot).or_default();
+                *max = (*max).max(p.subsidy.amount);
+            }
+            by_slot.values().fold(U256::zero(), |acc, amount| acc.saturating_add(*amount))
+        };
+        
+        let amount = rule.max_subsidy.min(self.remaining_budget().saturating_sub(reserved));
+        if amount.is_zero() {
+            return None;
+        }
+        
+        Some(Subsidy {
+            rule: rule.name.clone(),
+            amount,
+        })
+    }
+    
+    /// Remember a submitted subsidized bid so its spend is recorded if it lands
+    pub fn record_bid(&self, slot: u64, block_hash: H256, subsidy: Subsidy) {
+        let mut pending = self.pending.lock();
+        // Bids for slots well in the past can no longer land
+        pending.retain(|_, p| p.slot + 2 >= slot);
+        pending.insert(block_hash, PendingSubsidy { slot, subsidy });
+    }
+    
+    /// Record a landed block, booking its subsidy if it was one of our subsidized bids
+    pub async fn settle_block(&self, block_hash: H256) -> Result<()> {
+        // Left pending until booked, so a failed insert is retried on the next pass
+        let settled = self.pending.lock().get(&block_hash).cloned();
+        let settled = match settled {
+            Some(settled) => settled,
+            None => return Ok(()),
+        };
+        
+        let inserted = self.health.retry(|| self.book(block_hash, &settled)).await?;
+        self.pending.lock().remove(&block_hash);
+        
+        // Already booked by an earlier pass over this block
+        if inserted == 0 {
+            return Ok(());
+        }
+        
+        {
+            let mut spent = self.spent.write();
+            *spent = spent.saturating_add(settled.subsidy.amount);
+        }
+        self.record_win(settled.slot);
+        self.pending.lock().retain(|_, p| p.slot > settled.slot);
+        
+        info!(
+            "Paid {} wei subsidy under rule {} for slot {}",
+            settled.subsidy.amount, settled.subsidy.rule, settled.slot
+        );
+        
+        Ok(())
+    }
+    
+    /// Record that we won a slot, for the reputation rule
+    pub fn record_win(&self, slot: u64) {
+        let mut last_won_slot = self.last_won_slot.write();
+        if last_won_slot.map_or(true, |won| slot > won) {
+            *last_won_slot = Some(slot);
+        }
+    }
+    
+    /// Reload spend within the budget period from the ledger
+    pub async fn refresh_spend(&self) -> Result<()> {
+        let spent = self.health.retry(|| self.spent_once()).await?;
+        *self.spent.write() = spent;
+        debug!("Subsidy spend in budget period: {} of {} wei", spent, self.budget);
+        
+        Ok(())
+    }
+    
+    /// Book a landed subsidy, returning the rows inserted, none when it was booked before
+    async fn book(&self, block_hash: H256, settled: &PendingSubsidy) -> Result<u64> {
+        let inserted = sqlx::query(
+            "INSERT INTO subsidy_ledger (slot, block_hash, rule, amount_wei, paid_at)
+             VALUES ($1, $2, $3, $4::NUMERIC, NOW())
+             ON CONFLICT (block_hash) DO NOTHING",
+        )
+        .bind(settled.slot as i64)
+        .bind(format!("{:?}", block_hash))
+        .bind(&settled.subsidy.rule)
+        .bind(settled.subsidy.amount.to_string())
+        .execute(&self.db_pool)
+        .await
+        .context("Failed to record subsidy spend")?
+        .rows_affected();
+        
+        Ok(inserted)
+    }
+    
+    async fn spent_once(&self) -> Result<U256> {
+        let row = sqlx::query(
+            "SELECT COALESCE(SUM(amount_wei), 0)::TEXT AS spent FROM subsidy_ledger
+             WHERE paid_at > NOW() - make_interval(hours => $1)",
+        )
+        .bind(self.config.budget_period_hours as i32)
+        .fetch_one(&self.db_pool)
+        .await
+        .context("Failed to load subsidy spend")?;
+        
+        Ok(U256::from_dec_str(row.try_get("spent")?)?)
+    }
+}
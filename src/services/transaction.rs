@@ -1,16 +1,363 @@
-use anyhow::{anyhow, Result};
-use ethers::types::{Transaction, H256, U256};
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{debug, error, info, warn};
+use anyhow::{anyhow, Context, Result};
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::{
+        transaction::eip2718::TypedTransaction, Address, BlockNumber, Bytes, Eip1559TransactionRequest,
+        Transaction, H256, U256,
+    },
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+use futures::stream::{self, StreamExt};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::{RwLock, Semaphore};
+use tracing::{debug, error, info, warn, Instrument, Span};
 
 use crate::{
-    blockchain::BlockchainClient,
-    database::DbPool,
-    services::simulation::SimulationService,
-    utils::metrics::MetricsTimer,
+    api::models,
+    blockchain::{
+        fees::{FeeEstimator, Urgency},
+        client::is_node_rejection,
+        signer::SignerRegistry,
+        transaction::{InFlightTx, NonceManager},
+        BlockchainClient,
+    },
+    config::{CacheSettings, PrivacyConfig, PrivateSubmissionConfig},
+    database::{repositories::MempoolRepository, DbPool, RedisPool},
+    services::{
+        analytics::{AnalyticsSink, PendingTxObservation, SimulationObservation},
+        build_decisions::TemplateSummary,
+        drain::DrainController,
+        events::{EventBus, Topic},
+        gas_golf::{GasGolfer, GasSavings, Route},
+        labels::{LabelRegistry, TxLabels},
+        mempool::{MempoolStats, MempoolView, PendingTxFilter, PendingTxPage, PendingTxView},
+        mempool_recorder::MempoolRecorder,
+        ordering::{self, BlockContext, OrderingStrategy},
+        reputation::ReputationService,
+        risk::RiskManager,
+        simulation::{SimulationPriority, SimulationService},
+    },
+    utils::{
+        audit,
+        cache::{BoundedCache, EvictionPolicy},
+        metrics::MetricsTimer,
+        sensitive::Sensitive,
+        telemetry,
+    },
 };
 
+/// Where an ingested transaction came from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "id", rename_all = "snake_case")]
+pub enum TxSource {
+    /// Seen in the node's public mempool
+    #[default]
+    PublicMempool,
+    /// Submitted through our own API
+    PrivateApi,
+    /// Submitted by a specific searcher, identified by API key
+    Searcher(String),
+    /// Received from the bloXroute stream
+    Bloxroute,
+}
+
+impl TxSource {
+    /// Low-cardinality kind, suitable as a metrics label
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::PublicMempool => "public_mempool",
+            Self::PrivateApi => "private_api",
+            Self::Searcher(_) => "searcher",
+            Self::Bloxroute => "bloxroute",
+        }
+    }
+    
+    /// API key name of the searcher that submitted the transaction, if any
+    pub fn owner(&self) -> Option<String> {
+        match self {
+            Self::Searcher(key) => Some(key.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TxSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Searcher(key) => write!(f, "searcher:{}", key),
+            other => f.write_str(other.kind()),
+        }
+    }
+}
+
+impl FromStr for TxSource {
+    type Err = anyhow::Error;
+    
+    /// Parse a source as displayed
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "public_mempool" => Ok(Self::PublicMempool),
+            "private_api" => Ok(Self::PrivateApi),
+            "bloxroute" => Ok(Self::Bloxroute),
+            other => other
+                .strip_prefix("searcher:")
+                .map(|key| Self::Searcher(key.to_string()))
+                .ok_or_else(|| anyhow!("Unknown transaction source: {}", other)),
+        }
+    }
+}
+
+/// A signed transaction whose send failed without the node rejecting it
+///
+/// The request may have timed out after the node took the transaction, so its nonce stays in
+/// flight and whoever sent it has to settle it by receipt and nonce like a sent one.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("transaction {tx_hash:?} from {from:?} at nonce {nonce} may have been sent: {reason}")]
+pub struct SendUnconfirmed {
+    pub tx_hash: H256,
+    pub from: Address,
+    pub nonce: u64,
+    pub reason: String,
+}
+
+/// A profitable transaction marked for inclusion in the next block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InclusionCandidate {
+    /// The full transaction, shared with the ingestion path rather than copied
+    pub tx: Arc<Transaction>,
+    /// Simulated profit in wei
+    #[serde(with = "models::wei")]
+    pub profit: U256,
+    /// Where the transaction came from
+    #[serde(default)]
+    pub source: TxSource,
+    /// Strategy that sent the transaction, `None` for order flow we only include
+    #[serde(default)]
+    pub strategy: Option<String>,
+}
+
+/// Opportunity feed event, a candidate tagged with the known entities it involves
+#[derive(Serialize)]
+struct OpportunityEvent<'a> {
+    #[serde(flatten)]
+    candidate: &'a InclusionCandidate,
+    labels: TxLabels,
+}
+
+/// How a user-submitted transaction reaches block builders
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxPrivacy {
+    /// Broadcast by our node to the public mempool
+    #[default]
+    Public,
+    /// Sent to Flashbots Protect, never visible in the public mempool
+    Protect,
+    /// Sent to MEV-Share, revealing only the configured hints to backrunning searchers
+    MevShare,
+}
+
+impl TxPrivacy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Public => "public",
+            Self::Protect => "protect",
+            Self::MevShare => "mev_share",
+        }
+    }
+}
+
+/// Where a transaction is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxLifecycle {
+    /// Known to us or the node but not yet mined
+    Pending,
+    /// Mined and executed successfully
+    Included,
+    /// Mined but reverted
+    Reverted,
+    /// Neither pending nor mined, as far as the node knows
+    Unknown,
+    /// The lookup failed, see `error`
+    Error,
+}
+
+/// Status and receipt summary of one transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxStatusSummary {
+    pub tx_hash: H256,
+    pub status: TxLifecycle,
+    pub block_number: Option<u64>,
+    /// Blocks on top of the including block, counting it
+    pub confirmations: Option<u64>,
+    pub gas_used: Option<u64>,
+    #[serde(with = "models::wei_opt")]
+    pub effective_gas_price: Option<U256>,
+    pub error: Option<String>,
+}
+
+impl TxStatusSummary {
+    fn new(tx_hash: H256, status: TxLifecycle) -> Self {
+        Self {
+            tx_hash,
+            status,
+            block_number: None,
+            confirmations: None,
+            gas_used: None,
+            effective_gas_price: None,
+            error: None,
+        }
+    }
+}
+
+/// Lookups in flight at once for a bulk status request
+const STATUS_LOOKUP_CONCURRENCY: usize = 16;
+
+/// Blocks a pending sensitive transaction stays a candidate without landing
+const SENSITIVE_TX_TTL_BLOCKS: u64 = 25;
+
+/// Pending transaction writes in flight at once; beyond this, transactions go unpersisted
+const MAX_MEMPOOL_WRITES: usize = 256;
+
+/// Recent strategy transactions remembered with the strategy that sent them
+const STRATEGY_SENDS_CAPACITY: usize = 10_000;
+
+#[derive(Deserialize)]
+struct PrivateRpcResponse {
+    result: Option<H256>,
+    error: Option<PrivateRpcError>,
+}
+
+#[derive(Deserialize)]
+struct PrivateRpcError {
+    message: String,
+}
+
+/// Sends signed transactions to private order flow endpoints instead of the public mempool
+#[derive(Clone)]
+pub struct PrivateTxSubmitter {
+    /// HTTP client for the private endpoints
+    http: reqwest::Client,
+    /// Key signing the MEV-Share authentication header
+    signer: Option<Arc<LocalWallet>>,
+    /// Configuration
+    config: PrivateSubmissionConfig,
+}
+
+impl PrivateTxSubmitter {
+    /// Create a new private submitter, signing MEV-Share requests with the Flashbots key if any
+    pub fn new(config: PrivateSubmissionConfig, signing_key: Option<&str>) -> Result<Self> {
+        let signer = signing_key
+            .map(|key| key.parse::<LocalWallet>())
+            .transpose()
+            .context("Invalid private submission signing key")?
+            .map(Arc::new);
+        
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .context("Failed to create private submission HTTP client")?;
+        
+        Ok(Self { http, signer, config })
+    }
+    
+    /// Send a raw transaction through the given private route
+    pub async fn submit(&self, raw_tx: Bytes, privacy: TxPrivacy) -> Result<H256> {
+        let tx_hash = H256::from(keccak256(&raw_tx));
+        let (result, endpoint) = match privacy {
+            TxPrivacy::Public => return Err(anyhow!("Public transactions are not sent privately")),
+            TxPrivacy::Protect => (self.send_protect(raw_tx).await, &self.config.protect_url),
+            TxPrivacy::MevShare => (self.send_mev_share(raw_tx).await, &self.config.mev_share_url),
+        };
+        audit::private_transaction_submitted(tx_hash, privacy.as_str(), endpoint, result.as_ref().err());
+        
+        let outcome = if result.is_ok() { "accepted" } else { "rejected" };
+        metrics::counter!(
+            "private_transactions_submitted_total",
+            1,
+            "route" => privacy.as_str(),
+            "outcome" => outcome
+        );
+        
+        result
+    }
+    
+    async fn send_protect(&self, raw_tx: Bytes) -> Result<H256> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_sendRawTransaction",
+            "params": [raw_tx],
+        });
+        let request = self.http.post(&self.config.protect_url).json(&body);
+        
+        Self::send(request).await
+    }
+    
+    async fn send_mev_share(&self, raw_tx: Bytes) -> Result<H256> {
+        let signer = self
+            .signer
+            .as_ref()
+            .ok_or_else(|| anyhow!("No Flashbots signing key configured for MEV-Share"))?;
+        
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_sendPrivateTransaction",
+            "params": [{
+                "tx": raw_tx,
+                "preferences": {
+                    "privacy": { "hints": self.config.mev_share_hints },
+                },
+            }],
+        })
+        .to_string();
+        
+        // Same body hash signature as bundle submission
+        let body_hash = format!("0x{}", hex::encode(keccak256(body.as_bytes())));
+        let signature = signer.sign_message(body_hash).await?;
+        let auth = format!("{:?}:0x{}", signer.address(), signature);
+        
+        let request = self
+            .http
+            .post(&self.config.mev_share_url)
+            .header("Content-Type", "application/json")
+            .header("X-Flashbots-Signature", auth)
+            .body(body);
+        
+        Self::send(request).await
+    }
+    
+    async fn send(request: reqwest::RequestBuilder) -> Result<H256> {
+        let response: PrivateRpcResponse = request
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        
+        if let Some(error) = response.error {
+            return Err(anyhow!("Private endpoint error: {}", error.message));
+        }
+        
+        response
+            .result
+            .ok_or_else(|| anyhow!("Private endpoint returned no transaction hash"))
+    }
+}
+
 /// Service for handling transactions
 #[derive(Clone)]
 pub struct TransactionService {
@@ -22,6 +369,53 @@ pub struct TransactionService {
     simulation_service: SimulationService,
     /// Current gas price
     current_gas_price: Arc<RwLock<U256>>,
+    /// Drain controller, used to refuse submissions while draining
+    drain: DrainController,
+    /// Firehose sink for observations and simulation results
+    analytics_sink: AnalyticsSink,
+    /// Profitable transactions marked for inclusion in the next block
+    inclusion_candidates: Arc<RwLock<HashMap<H256, InclusionCandidate>>>,
+    /// Publishes new candidates to the opportunity feed
+    event_bus: EventBus,
+    /// Candidates from sensitive flow, held in memory only, with the last block they may land in
+    sensitive_candidates: Arc<RwLock<HashMap<H256, (u64, Sensitive<InclusionCandidate>)>>>,
+    /// Block currently being built, sensitive candidates targeting earlier ones are expired
+    building_block: Arc<AtomicU64>,
+    /// Which sources are handled as sensitive
+    privacy: Arc<PrivacyConfig>,
+    /// Enriched view of non-sensitive pending transactions
+    mempool: MempoolView,
+    /// Persisted pending transactions, written when `persist_mempool` is set
+    mempool_repository: MempoolRepository,
+    persist_mempool: bool,
+    /// Bounds the background writes of pending transactions
+    mempool_write_permits: Arc<Semaphore>,
+    /// Hashes of recently confirmed transactions, so late pending announcements aren't fetched
+    recently_confirmed: Arc<BoundedCache<H256, ()>>,
+    /// Strategy that sent each of our recent transactions, to attribute what they land
+    strategy_sends: Arc<BoundedCache<H256, String>>,
+    /// EIP-1559 fees for transactions we send
+    fee_estimator: FeeEstimator,
+    /// Nonces for transactions we send
+    nonce_manager: NonceManager,
+    /// Named accounts whose transactions are signed locally
+    signers: SignerRegistry,
+    /// Private routes for user-submitted transactions
+    private_submitter: PrivateTxSubmitter,
+    /// Picks and orders candidates into block templates
+    ordering: Arc<dyn OrderingStrategy>,
+    /// Gas a block template may fill
+    block_gas_budget: u64,
+    /// Dry run switch, when on transactions are signed but never broadcast
+    risk_manager: RiskManager,
+    /// Known entities pending transactions and opportunities are tagged with
+    labels: LabelRegistry,
+    /// Optimizes the transactions we originate before they are priced and signed
+    gas_golf: GasGolfer,
+    /// Appends non-sensitive pending transactions to the replayable recording
+    recorder: MempoolRecorder,
+    /// Scores the searchers whose transactions we simulate
+    reputation: ReputationService,
 }
 
 impl TransactionService {
@@ -30,30 +424,126 @@ impl TransactionService {
         db_pool: DbPool,
         blockchain_client: Arc<BlockchainClient>,
         simulation_service: SimulationService,
+        drain: DrainController,
+        analytics_sink: AnalyticsSink,
+        event_bus: EventBus,
+        privacy: PrivacyConfig,
+        confirmed_txs: CacheSettings,
+        fee_estimator: FeeEstimator,
+        nonce_manager: NonceManager,
+        signers: SignerRegistry,
+        mempool_repository: MempoolRepository,
+        persist_mempool: bool,
+        private_submitter: PrivateTxSubmitter,
+        ordering: Arc<dyn OrderingStrategy>,
+        block_gas_budget: u64,
+        risk_manager: RiskManager,
+        labels: LabelRegistry,
+        gas_golf: GasGolfer,
+        recorder: MempoolRecorder,
+        reputation: ReputationService,
     ) -> Result<Self> {
         Ok(Self {
             db_pool,
             blockchain_client,
             simulation_service,
             current_gas_price: Arc::new(RwLock::new(U256::zero())),
+            drain,
+            analytics_sink,
+            inclusion_candidates: Arc::new(RwLock::new(HashMap::new())),
+            event_bus,
+            sensitive_candidates: Arc::new(RwLock::new(HashMap::new())),
+            building_block: Arc::new(AtomicU64::new(0)),
+            privacy: Arc::new(privacy),
+            mempool: MempoolView::new(),
+            mempool_repository,
+            persist_mempool,
+            mempool_write_permits: Arc::new(Semaphore::new(MAX_MEMPOOL_WRITES)),
+            recently_confirmed: Arc::new(BoundedCache::new("confirmed_txs", &confirmed_txs)),
+            strategy_sends: Arc::new(BoundedCache::new(
+                "strategy_sends",
+                &CacheSettings {
+                    capacity: STRATEGY_SENDS_CAPACITY,
+                    policy: EvictionPolicy::Lru,
+                },
+            )),
+            fee_estimator,
+            nonce_manager,
+            signers,
+            private_submitter,
+            ordering,
+            block_gas_budget,
+            risk_manager,
+            labels,
+            gas_golf,
+            recorder,
+            reputation,
         })
     }
     
+    /// Whether flow from a source must stay in memory only
+    pub fn is_sensitive(&self, source: &TxSource) -> bool {
+        let source_name = source.to_string();
+        // Searchers submit through the private API, so its flow covers theirs
+        let via_api = matches!(source, TxSource::Searcher(_));
+        self.privacy
+            .sensitive_sources
+            .iter()
+            .any(|s| s == source.kind() || *s == source_name || (via_api && s == "private_api"))
+    }
+    
+    /// Whether a transaction is already pending in our view or was recently confirmed
+    ///
+    /// Announcements for known transactions can be dropped without fetching the body.
+    pub fn is_known(&self, tx_hash: &H256) -> bool {
+        self.mempool.contains(tx_hash) || self.recently_confirmed.contains(tx_hash)
+    }
+    
     /// Process a pending transaction
-    pub async fn process_pending_transaction(&self, tx: Transaction) -> Result<()> {
+    pub async fn process_pending_transaction(&self, tx: Transaction, source: TxSource) -> Result<()> {
+        if self.is_sensitive(&source) {
+            return self.process_sensitive_transaction(Sensitive::new(tx), source).await;
+        }
+        
         let tx_hash = tx.hash;
-        debug!("Processing pending transaction: {}", tx_hash);
+        let span = telemetry::pending_tx_span(tx_hash);
+        let result = self.process_public_transaction(tx, source).instrument(span.clone()).await;
+        if let Err(e) = &result {
+            telemetry::failure_span(tx_hash, &span)
+                .in_scope(|| warn!("Failed to process transaction {}: {}", tx_hash, e));
+        }
+        
+        result
+    }
+    
+    async fn process_public_transaction(&self, tx: Transaction, source: TxSource) -> Result<()> {
+        let tx = Arc::new(tx);
+        let tx_hash = tx.hash;
+        debug!("Processing pending transaction: {} from {}", tx_hash, source);
+        self.recorder.record(&tx, &source);
         
         // Update metrics
-        metrics::counter!("transactions_received_total", 1);
+        metrics::counter!("transactions_received_total", 1, "source" => source.kind());
         
-        // Record transaction in database
-        self.store_transaction(&tx).await?;
+        // Record transaction in database, off the simulation path
+        self.store_transaction(&tx, &source);
+        let labels = self.labels.tag(&tx);
+        self.analytics_sink.record_pending_tx(PendingTxObservation::from_transaction(&tx, &labels));
+        self.mempool.insert(&tx, source.clone(), labels);
         
         // Simulate transaction to evaluate profit potential
         let timer = MetricsTimer::new("transaction_simulation_time_seconds");
         let simulation_result = self.simulation_service.simulate_transaction(&tx).await;
-        timer.stop();
+        let duration = timer.stop();
+        
+        if let Some(searcher) = source.owner() {
+            self.reputation.record_simulation(&searcher, simulation_result.is_ok());
+        }
+        self.analytics_sink.record_simulation(SimulationObservation::new(
+            tx_hash,
+            simulation_result.as_ref().ok().copied(),
+            duration,
+        ));
         
         match simulation_result {
             Ok(profit) => {
@@ -61,17 +551,64 @@ impl TransactionService {
                 
                 // Update profit information
                 self.update_transaction_profit(tx_hash, profit).await?;
+                self.mempool.set_profit(tx_hash, profit);
                 
                 // If profitable, consider for inclusion in next block
                 if profit > U256::zero() {
                     debug!("Transaction {} is profitable, marking for inclusion", tx_hash);
-                    self.mark_transaction_for_inclusion(tx_hash).await?;
+                    let strategy = self.strategy_sends.get(&tx_hash);
+                    self.mark_transaction_for_inclusion(InclusionCandidate {
+                        tx,
+                        profit,
+                        source,
+                        strategy,
+                    })
+                    .await?;
                 }
                 
                 metrics::counter!("transactions_processed_total", 1);
             }
             Err(e) => {
-                warn!("Failed to simulate transaction {}: {}", tx_hash, e);
+                telemetry::failure_span(tx_hash, &Span::current())
+                    .in_scope(|| warn!("Failed to simulate transaction {}: {}", tx_hash, e));
+                metrics::counter!("transactions_dropped_total", 1);
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Process a pending transaction from sensitive flow, recording only aggregate counters
+    pub async fn process_sensitive_transaction(
+        &self,
+        tx: Sensitive<Transaction>,
+        source: TxSource,
+    ) -> Result<()> {
+        metrics::counter!("transactions_received_total", 1, "source" => source.kind());
+        metrics::counter!("sensitive_transactions_total", 1, "source" => source.kind());
+        
+        let simulation = self.simulation_service.simulate_sensitive(&tx, SimulationPriority::Normal).await;
+        if let Some(searcher) = source.owner() {
+            self.reputation.record_simulation(&searcher, simulation.is_ok());
+        }
+        match simulation {
+            Ok(profit) => {
+                if profit > U256::zero() {
+                    let expires_after = self.building_block.load(Ordering::Relaxed) + SENSITIVE_TX_TTL_BLOCKS;
+                    self.mark_sensitive_for_inclusion(
+                        tx.map(|tx| InclusionCandidate {
+                            tx: Arc::new(tx),
+                            profit,
+                            source,
+                            strategy: None,
+                        }),
+                        expires_after,
+                    )
+                    .await;
+                }
+                metrics::counter!("transactions_processed_total", 1);
+            }
+            Err(_) => {
                 metrics::counter!("transactions_dropped_total", 1);
             }
         }
@@ -80,6 +617,9 @@ impl TransactionService {
     }
     
     /// Process a confirmed transaction
+    ///
+    /// Candidates are evicted separately by `evict_confirmed` as soon as the block arrives; this
+    /// is the slower bookkeeping that can run behind the head.
     pub async fn process_confirmed_transaction(&self, tx: Transaction) -> Result<()> {
         let tx_hash = tx.hash;
         debug!("Processing confirmed transaction: {}", tx_hash);
@@ -90,22 +630,427 @@ impl TransactionService {
         Ok(())
     }
     
-    /// Submit a raw transaction to the blockchain
-    pub async fn submit_transaction(&self, raw_tx: Vec<u8>) -> Result<H256> {
-        let tx_hash = self.blockchain_client
-            .send_raw_transaction(raw_tx.into())
-            .await?;
+    /// Drop a block's transactions from the candidate set and mempool view
+    ///
+    /// Returns the sensitive candidates and the searchers' public ones that landed, for
+    /// attribution to their searchers.
+    pub async fn evict_confirmed(&self, tx_hashes: &[H256]) -> Vec<Sensitive<InclusionCandidate>> {
+        let mut landed = Vec::new();
+        {
+            let mut candidates = self.inclusion_candidates.write().await;
+            for tx_hash in tx_hashes {
+                if let Some(candidate) = candidates.remove(tx_hash) {
+                    telemetry::landed_span(*tx_hash, candidate.profit)
+                        .in_scope(|| debug!("Inclusion candidate {} landed", tx_hash));
+                    if matches!(candidate.source, TxSource::Searcher(_)) {
+                        landed.push(Sensitive::new(candidate));
+                    }
+                }
+            }
+        }
+        {
+            let mut sensitive = self.sensitive_candidates.write().await;
+            landed.extend(
+                tx_hashes
+                    .iter()
+                    .filter_map(|tx_hash| sensitive.remove(tx_hash))
+                    .map(|(_, candidate)| candidate),
+            );
+        }
+        for tx_hash in tx_hashes {
+            self.mempool.remove(tx_hash);
+            self.recently_confirmed.insert(*tx_hash, ());
+        }
+        if self.persist_mempool {
+            if let Err(e) = self.mempool_repository.mark_included(tx_hashes).await {
+                warn!("Failed to mark persisted mempool transactions included: {}", e);
+            }
+        }
+        
+        landed
+    }
+    
+    /// Submit a raw transaction, to the public mempool or through a private route
+    pub async fn submit_transaction(&self, raw_tx: Vec<u8>, privacy: TxPrivacy) -> Result<H256> {
+        if self.drain.is_draining() {
+            return Err(anyhow!("Service is draining, not accepting new transactions"));
+        }
+        let _in_flight = self.drain.track("send");
+        
+        // A signed transaction isn't ours to hold back; refuse it rather than claim it was sent
+        if self.risk_manager.is_dry_run() {
+            metrics::counter!("dry_run_suppressed_total", 1, "kind" => "transaction");
+            return Err(anyhow!("Dry run, not submitting transactions"));
+        }
+        
+        let tx_hash = match privacy {
+            TxPrivacy::Public => {
+                let tx_hash = H256::from(keccak256(&raw_tx));
+                let result = self.blockchain_client.send_raw_transaction(raw_tx.into()).await;
+                audit::public_transaction_submitted(tx_hash, result.as_ref().err());
+                result?
+            }
+            _ => self.private_submitter.submit(raw_tx.into(), privacy).await?,
+        };
+        
+        info!("Submitted transaction {} via {}", tx_hash, privacy.as_str());
+        
+        Ok(tx_hash)
+    }
+    
+    /// Whether sends are suppressed, nothing leaving for the network
+    pub fn is_dry_run(&self) -> bool {
+        self.risk_manager.is_dry_run()
+    }
+    
+    /// Hash of the transaction in flight at one of our nonces, following replacements
+    pub async fn in_flight_hash(&self, address: Address, nonce: u64) -> Result<Option<H256>> {
+        Ok(self.nonce_manager.in_flight(address, nonce).await?.map(|tx| tx.tx_hash))
+    }
+    
+    /// Address of one of our named accounts
+    pub fn account(&self, name: &str) -> Result<Address> {
+        self.signers.address(name)
+    }
+    
+    /// Sign transactions from one of our named accounts at consecutive nonces without sending them
+    ///
+    /// For bundles, which land whole or not at all: the nonces follow the node's pending count
+    /// rather than being reserved, so the account should send nothing outside bundles.
+    pub async fn sign_bundle(
+        &self,
+        account: &str,
+        txs: Vec<Eip1559TransactionRequest>,
+        urgency: Urgency,
+    ) -> Result<Vec<(H256, Bytes)>> {
+        let from = self.signers.address(account)?;
+        let fees = self.fee_estimator.suggest_fees(urgency).await?;
+        let nonce = self.blockchain_client.get_transaction_count(from, BlockNumber::Pending).await?;
+        
+        let mut signed = Vec::with_capacity(txs.len());
+        for (offset, tx) in txs.into_iter().enumerate() {
+            let mut typed: TypedTransaction = tx
+                .from(from)
+                .nonce(nonce + offset as u64)
+                .max_fee_per_gas(fees.max_fee_per_gas)
+                .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+                .into();
+            let raw = self.signers.sign(account, &mut typed).await?;
+            signed.push((H256::from(keccak256(&raw)), raw));
+        }
+        
+        Ok(signed)
+    }
+    
+    /// Sign a transaction under one of our named accounts and send it
+    pub async fn send_transaction_as(
+        &self,
+        account: &str,
+        tx: Eip1559TransactionRequest,
+        urgency: Urgency,
+    ) -> Result<H256> {
+        let from = self.signers.address(account)?;
+        self.send_transaction(tx.from(from), urgency).await
+    }
+    
+    /// Send a transaction, pricing it with EIP-1559 fees for the given urgency
+    ///
+    /// Transactions from one of our named accounts are signed locally, anything else is
+    /// signed by the node.
+    pub async fn send_transaction(&self, tx: Eip1559TransactionRequest, urgency: Urgency) -> Result<H256> {
+        if !self.gas_golf.enabled() {
+            return self.submit(tx, urgency, None).await;
+        }
+        
+        let golfed = self.gas_golf.golf_tx("direct", tx).await;
+        self.submit(golfed.tx, urgency, Some(golfed.savings)).await
+    }
+    
+    /// Send the cheapest of equivalent routes, returning the hashes of its transactions in order
+    ///
+    /// Routes are listed in order of preference. Without gas golf, or when no route can be
+    /// priced, the first is sent as built. The transactions are attributed to `strategy` when
+    /// they land.
+    pub async fn send_route(&self, strategy: &str, routes: Vec<Route>, urgency: Urgency) -> Result<Vec<H256>> {
+        let first = routes.first().cloned().ok_or_else(|| anyhow!("No route to send"))?;
+        let golfed = if self.gas_golf.enabled() {
+            match self.gas_golf.golf(routes).await {
+                Ok(golfed) => Some(golfed),
+                Err(e) => {
+                    warn!("Sending route {} as built: {}", first.name, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        
+        let txs: Vec<(Eip1559TransactionRequest, Option<GasSavings>)> = match golfed {
+            Some(golfed) => golfed.into_iter().map(|golfed| (golfed.tx, Some(golfed.savings))).collect(),
+            None => first.txs.into_iter().map(|tx| (tx, None)).collect(),
+        };
+        
+        let mut tx_hashes = Vec::with_capacity(txs.len());
+        for (tx, savings) in txs {
+            let tx_hash = self.submit(tx, urgency, savings).await?;
+            // Attributed as each goes out, so a later failure leaves the sent ones attributed
+            self.strategy_sends.insert(tx_hash, strategy.to_string());
+            tx_hashes.push(tx_hash);
+        }
+        
+        Ok(tx_hashes)
+    }
+    
+    /// Price, sign and send one transaction, recording what gas golf expects it to save
+    async fn submit(
+        &self,
+        mut tx: Eip1559TransactionRequest,
+        urgency: Urgency,
+        savings: Option<GasSavings>,
+    ) -> Result<H256> {
+        if self.drain.is_draining() {
+            return Err(anyhow!("Service is draining, not accepting new transactions"));
+        }
+        let _in_flight = self.drain.track("send");
+        
+        let fees = self.fee_estimator.suggest_fees(urgency).await?;
+        tx = tx
+            .max_fee_per_gas(fees.max_fee_per_gas)
+            .max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+        
+        // Decided once, so a breaker tripping mid-send can't free the nonce of a sent transaction
+        let dry_run = self.risk_manager.is_dry_run();
+        
+        // Nonces of our own accounts are allocated locally so concurrent senders never collide
+        let reservation = match tx.from {
+            Some(from) => {
+                let reservation = self.nonce_manager.reserve(from).await?;
+                tx = tx.nonce(reservation.nonce);
+                Some(reservation)
+            }
+            None => None,
+        };
+        
+        let (from, nonce, value) = (tx.from, tx.nonce, tx.value.unwrap_or_default());
+        let result = self.broadcast(tx, dry_run).await;
+        if !dry_run {
+            audit::transaction_sent(result.as_ref().ok().copied(), from, nonce, value, result.as_ref().err());
+        }
+        let tx_hash = match result {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                // The node may hold an unconfirmed send, so its nonce is tracked rather than reused
+                let unconfirmed = e.downcast_ref::<SendUnconfirmed>().map(|unconfirmed| unconfirmed.tx_hash);
+                match (reservation, unconfirmed) {
+                    (Some(reservation), Some(tx_hash)) => {
+                        if let Err(e) = self.nonce_manager.mark_sent(reservation, tx_hash).await {
+                            warn!("Failed to track unconfirmed transaction {:?}: {}", tx_hash, e);
+                        }
+                    }
+                    (Some(reservation), None) => self.nonce_manager.release(reservation).await?,
+                    (None, _) => {}
+                }
+                return Err(e);
+            }
+        };
+        // Nothing was broadcast in a dry run, so the nonce is still free
+        if let Some(reservation) = reservation {
+            if dry_run {
+                self.nonce_manager.release(reservation).await?;
+            } else if let Err(e) = self.nonce_manager.mark_sent(reservation, tx_hash).await {
+                // Failing here would have the caller treat a broadcast transaction as unsent
+                warn!("Failed to track sent transaction {:?}, the next resync picks it up: {}", tx_hash, e);
+            }
+        }
         
-        info!("Submitted transaction: {}", tx_hash);
+        info!(
+            "Sent transaction {} with max fee {} and tip {}",
+            tx_hash, fees.max_fee_per_gas, fees.max_priority_fee_per_gas
+        );
+        
+        if let Some(savings) = savings {
+            let gas_price = (fees.next_base_fee + fees.max_priority_fee_per_gas).min(fees.max_fee_per_gas);
+            if let Err(e) = self.gas_golf.record(tx_hash, &savings, gas_price, dry_run).await {
+                warn!("Failed to record gas savings of {}: {}", tx_hash, e);
+            }
+        }
         
         Ok(tx_hash)
     }
     
+    /// Re-send outbound transactions stuck in the mempool at their nonce with bumped fees
+    ///
+    /// Transactions the node no longer knows have their nonce freed for the next send. In a
+    /// dry run nothing was broadcast, so nothing is replaced.
+    pub async fn replace_stuck_transactions(&self) -> Result<usize> {
+        if self.risk_manager.is_dry_run() {
+            debug!("Dry run, not replacing stuck transactions");
+            return Ok(0);
+        }
+        let stuck = self.nonce_manager.resync_all().await?;
+        
+        let mut replaced = 0;
+        for (address, in_flight) in stuck {
+            match self.replace_stuck_transaction(address, &in_flight).await {
+                Ok(true) => replaced += 1,
+                Ok(false) => {}
+                Err(e) => warn!("Failed to replace stuck transaction {}: {}", in_flight.tx_hash, e),
+            }
+        }
+        
+        Ok(replaced)
+    }
+    
+    /// Re-send one stuck transaction, returning whether a replacement went out
+    async fn replace_stuck_transaction(&self, address: Address, in_flight: &InFlightTx) -> Result<bool> {
+        let original = match self.blockchain_client.get_transaction(in_flight.tx_hash).await? {
+            Some(tx) if tx.block_number.is_none() => tx,
+            Some(_) => return Ok(false),
+            None => {
+                self.nonce_manager.mark_dropped(address, in_flight.nonce).await?;
+                return Ok(false);
+            }
+        };
+        
+        // Nodes only accept a replacement raising both fees by at least 10%
+        let fees = self.fee_estimator.suggest_fees(Urgency::High).await?;
+        let bump = |fee: Option<U256>| fee.map(|fee| fee + fee / 8 + 1).unwrap_or_default();
+        let max_priority_fee = fees
+            .max_priority_fee_per_gas
+            .max(bump(original.max_priority_fee_per_gas.or(original.gas_price)));
+        let max_fee = fees
+            .max_fee_per_gas
+            .max(bump(original.max_fee_per_gas.or(original.gas_price)))
+            .max(max_priority_fee);
+        
+        let mut replacement = Eip1559TransactionRequest::new()
+            .from(address)
+            .nonce(in_flight.nonce)
+            .value(original.value)
+            .data(original.input.clone())
+            .gas(original.gas)
+            .max_fee_per_gas(max_fee)
+            .max_priority_fee_per_gas(max_priority_fee);
+        if let Some(to) = original.to {
+            replacement = replacement.to(to);
+        }
+        if let Some(access_list) = original.access_list.clone() {
+            replacement = replacement.access_list(access_list);
+        }
+        
+        let tx_hash = self.broadcast(replacement, false).await?;
+        self.nonce_manager.mark_replaced(address, in_flight.nonce, tx_hash).await?;
+        if let Some(strategy) = self.strategy_sends.get(&in_flight.tx_hash) {
+            self.strategy_sends.insert(tx_hash, strategy);
+        }
+        info!(
+            "Replaced stuck transaction {} at nonce {} with {} (max fee {})",
+            in_flight.tx_hash, in_flight.nonce, tx_hash, max_fee
+        );
+        metrics::counter!("transactions_replaced_total", 1);
+        Ok(true)
+    }
+    
+    /// Sign locally when the sender is one of our named accounts, otherwise let the node sign
+    ///
+    /// In a dry run nothing is sent; the hash returned is that of the locally signed
+    /// transaction, or the signing hash of one the node would have signed.
+    async fn broadcast(&self, tx: Eip1559TransactionRequest, dry_run: bool) -> Result<H256> {
+        let account = tx.from.and_then(|from| self.signers.name_of(from));
+        let account = match account {
+            Some(account) => account,
+            None if dry_run => {
+                let typed: TypedTransaction = tx.into();
+                metrics::counter!("dry_run_suppressed_total", 1, "kind" => "transaction");
+                info!("Dry run, not sending node-signed transaction from {:?}", typed.from());
+                return Ok(typed.sighash());
+            }
+            None => return Ok(self.blockchain_client.send_transaction(tx.into()).await?.tx_hash()),
+        };
+        
+        let mut typed: TypedTransaction = tx.into();
+        if typed.gas().is_none() {
+            let gas = self.blockchain_client.estimate_gas(&typed).await?;
+            typed.set_gas(gas);
+        }
+        let raw = self.signers.sign(account, &mut typed).await?;
+        let tx_hash = H256::from(keccak256(&raw));
+        
+        if dry_run {
+            metrics::counter!("dry_run_suppressed_total", 1, "kind" => "transaction");
+            info!("Dry run, not sending transaction {} signed by {}", tx_hash, account);
+            return Ok(tx_hash);
+        }
+        
+        // The node may have taken it before the request timed out or the connection dropped
+        match self.blockchain_client.send_raw_transaction(raw).await {
+            Err(e) if !is_node_rejection(&e) => Err(SendUnconfirmed {
+                tx_hash,
+                from: typed.from().copied().unwrap_or_default(),
+                nonce: typed.nonce().map_or(0, |nonce| nonce.as_u64()),
+                reason: e.to_string(),
+            }
+            .into()),
+            result => result,
+        }
+    }
+    
     /// Get transaction by hash
     pub async fn get_transaction(&self, tx_hash: H256) -> Result<Option<Transaction>> {
         self.blockchain_client.get_transaction(tx_hash).await
     }
     
+    /// Status of each transaction, in request order
+    ///
+    /// A failed lookup is reported in that transaction's summary rather than failing the batch.
+    pub async fn transaction_statuses(&self, tx_hashes: &[H256]) -> Vec<TxStatusSummary> {
+        let head = match self.blockchain_client.get_block_number().await {
+            Ok(head) => Some(head),
+            Err(e) => {
+                warn!("Failed to fetch head for transaction statuses: {}", e);
+                None
+            }
+        };
+        
+        stream::iter(tx_hashes.iter().copied())
+            .map(|tx_hash| async move {
+                self.transaction_status(tx_hash, head).await.unwrap_or_else(|e| {
+                    debug!("Status lookup for {:?} failed: {}", tx_hash, e);
+                    let mut summary = TxStatusSummary::new(tx_hash, TxLifecycle::Error);
+                    summary.error = Some(e.to_string());
+                    summary
+                })
+            })
+            .buffered(STATUS_LOOKUP_CONCURRENCY)
+            .collect()
+            .await
+    }
+    
+    async fn transaction_status(&self, tx_hash: H256, head: Option<u64>) -> Result<TxStatusSummary> {
+        if let Some(receipt) = self.blockchain_client.get_transaction_receipt(tx_hash).await? {
+            let status = if receipt.status.map_or(true, |status| status.as_u64() == 1) {
+                TxLifecycle::Included
+            } else {
+                TxLifecycle::Reverted
+            };
+            let block_number = receipt.block_number.map(|number| number.as_u64());
+            
+            let mut summary = TxStatusSummary::new(tx_hash, status);
+            summary.block_number = block_number;
+            summary.confirmations = block_number
+                .zip(head)
+                .map(|(block, head)| head.saturating_sub(block) + 1);
+            summary.gas_used = receipt.gas_used.map(|gas| gas.as_u64());
+            summary.effective_gas_price = receipt.effective_gas_price;
+            return Ok(summary);
+        }
+        
+        let pending = self.mempool.contains(&tx_hash) || self.get_transaction(tx_hash).await?.is_some();
+        let status = if pending { TxLifecycle::Pending } else { TxLifecycle::Unknown };
+        
+        Ok(TxStatusSummary::new(tx_hash, status))
+    }
+    
     /// Update the current gas price
     pub async fn update_gas_price(&self, gas_price: U256) -> Result<()> {
         let mut current = self.current_gas_price.write().await;
@@ -124,12 +1069,29 @@ impl TransactionService {
     }
     
     /// Store a transaction in the database
-    async fn store_transaction(&self, tx: &Transaction) -> Result<()> {
-        // This would store the transaction in the database
-        // For brevity, we'll skip the actual SQL query implementation
-        debug!("Storing transaction {} in database", tx.hash);
+    ///
+    /// Written in the background; when too many writes are outstanding the transaction is
+    /// left unpersisted rather than holding up ingestion.
+    fn store_transaction(&self, tx: &Arc<Transaction>, source: &TxSource) {
+        if !self.persist_mempool {
+            return;
+        }
+        let permit = match self.mempool_write_permits.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                metrics::counter!("mempool_writes_dropped_total", 1);
+                return;
+            }
+        };
         
-        Ok(())
+        let (repository, tx, source) = (self.mempool_repository.clone(), tx.clone(), source.to_string());
+        tokio::spawn(async move {
+            debug!("Storing transaction {} in database", tx.hash);
+            if let Err(e) = repository.record(&tx, &source).await {
+                warn!("Failed to store pending transaction {}: {}", tx.hash, e);
+            }
+            drop(permit);
+        });
     }
     
     /// Update transaction profit information
@@ -141,13 +1103,168 @@ impl TransactionService {
     }
     
     /// Mark a transaction for inclusion in the next block
-    async fn mark_transaction_for_inclusion(&self, tx_hash: H256) -> Result<()> {
-        // This would mark the transaction for inclusion in the database
-        debug!("Marking transaction {} for inclusion in next block", tx_hash);
+    pub async fn mark_transaction_for_inclusion(&self, candidate: InclusionCandidate) -> Result<()> {
+        debug!("Marking transaction {} for inclusion in next block", candidate.tx.hash);
+        
+        let event = OpportunityEvent {
+            candidate: &candidate,
+            labels: self.labels.tag(&candidate.tx),
+        };
+        self.event_bus.publish(Topic::Opportunities, candidate.source.owner(), &event);
+        self.inclusion_candidates.write().await.insert(candidate.tx.hash, candidate);
         
         Ok(())
     }
     
+    /// Mark a sensitive transaction for inclusion without publishing, logging or persisting it
+    ///
+    /// The candidate is offered to block templates up to and including `expires_after`.
+    pub async fn mark_sensitive_for_inclusion(&self, candidate: Sensitive<InclusionCandidate>, expires_after: u64) {
+        let tx_hash = candidate.expose().tx.hash;
+        self.sensitive_candidates.write().await.insert(tx_hash, (expires_after, candidate));
+    }
+    
+    /// Start building a block, dropping the sensitive candidates that can no longer land
+    pub async fn expire_sensitive_candidates(&self, building_block: u64) -> usize {
+        self.building_block.store(building_block, Ordering::Relaxed);
+        
+        let mut sensitive = self.sensitive_candidates.write().await;
+        let held = sensitive.len();
+        sensitive.retain(|_, (expires_after, _)| *expires_after >= building_block);
+        let expired = held - sensitive.len();
+        if expired > 0 {
+            metrics::counter!("sensitive_candidates_expired_total", expired as u64);
+        }
+        
+        expired
+    }
+    
+    /// Get the sensitive candidates, for in-memory block building only
+    pub async fn sensitive_candidates(&self) -> Vec<Sensitive<InclusionCandidate>> {
+        self.sensitive_candidates
+            .read()
+            .await
+            .values()
+            .map(|(_, candidate)| candidate.clone())
+            .collect()
+    }
+    
+    /// Get the transactions currently marked for inclusion
+    pub async fn inclusion_candidates(&self) -> Vec<InclusionCandidate> {
+        self.inclusion_candidates.read().await.values().cloned().collect()
+    }
+    
+    /// Get the inclusion candidates owned by a searcher, or all of them when `owner` is None
+    pub async fn inclusion_candidates_owned_by(&self, owner: Option<&str>) -> Vec<InclusionCandidate> {
+        self.inclusion_candidates
+            .read()
+            .await
+            .values()
+            .filter(|c| owner.map_or(true, |owner| c.source.owner().as_deref() == Some(owner)))
+            .cloned()
+            .collect()
+    }
+    
+    /// Page through the enriched view of pending transactions, hiding others' private flow unless `viewer` is None
+    pub fn pending_transactions(&self, filter: &PendingTxFilter, viewer: Option<&str>) -> PendingTxPage {
+        self.mempool.query(filter, viewer)
+    }
+    
+    /// The enriched view of one pending transaction, while it is tracked
+    pub fn pending_transaction(&self, tx_hash: &H256) -> Option<PendingTxView> {
+        self.mempool.get(tx_hash)
+    }
+    
+    /// Record a new block's base fee and gas usage for mempool congestion tracking
+    pub fn record_block_fees(&self, base_fee: U256, gas_used: U256, gas_limit: U256) {
+        self.mempool.record_block(base_fee, gas_used, gas_limit);
+    }
+    
+    /// Recompute rolling mempool statistics and emit them as metrics
+    pub fn refresh_mempool_stats(&self) -> MempoolStats {
+        self.mempool.refresh_stats()
+    }
+    
+    /// Publish changed mempool profit scores to Redis
+    pub async fn publish_mempool_scores(&self, redis: &RedisPool) {
+        self.mempool.publish_scores(redis).await;
+    }
+    
+    /// Latest rolling mempool statistics
+    pub fn mempool_stats(&self) -> MempoolStats {
+        self.mempool.stats()
+    }
+    
+    /// Inclusion candidates held, public and sensitive
+    pub async fn candidate_counts(&self) -> (usize, usize) {
+        let public = self.inclusion_candidates.read().await.len();
+        let sensitive = self.sensitive_candidates.read().await.len();
+        (public, sensitive)
+    }
+    
+    /// Inclusion candidates picked for the next block by the configured strategy, in block order
+    ///
+    /// Public flow only, so callers may persist or forward it. Blocks we build ourselves come
+    /// from `summarized_block_template`, which also offers the sensitive candidates.
+    pub async fn block_template(&self) -> Vec<InclusionCandidate> {
+        self.order_template(self.inclusion_candidates().await, &self.next_block_context()).0
+    }
+    
+    /// Block template with the summary its slot's build decision is recorded from
+    ///
+    /// Sensitive candidates, sealed bundles included, are ordered alongside the public ones. The
+    /// template holds them in plaintext: it must only go into the payload, while the summary is
+    /// aggregate and safe to record.
+    pub async fn summarized_block_template(&self) -> (Vec<InclusionCandidate>, TemplateSummary) {
+        let mut candidates = self.inclusion_candidates().await;
+        candidates.extend(
+            self.sensitive_candidates()
+                .await
+                .iter()
+                .map(|candidate| candidate.expose().clone()),
+        );
+        
+        self.order_template(candidates, &self.next_block_context())
+    }
+    
+    /// Context of a block with the given base fee, holding at most the template gas budget
+    pub fn block_context(&self, base_fee: U256) -> BlockContext {
+        BlockContext {
+            base_fee,
+            gas_limit: self.block_gas_budget,
+        }
+    }
+    
+    /// Context of the block being built, on the latest base fee the mempool saw
+    fn next_block_context(&self) -> BlockContext {
+        self.block_context(self.mempool.stats().latest_base_fee_wei.unwrap_or_default())
+    }
+    
+    /// Order candidates into a template for a block with the configured strategy and summarize it
+    pub fn order_template(
+        &self,
+        candidates: Vec<InclusionCandidate>,
+        context: &BlockContext,
+    ) -> (Vec<InclusionCandidate>, TemplateSummary) {
+        // Candidates share their transactions, so keeping them for the conflict count is cheap
+        let considered = candidates.clone();
+        
+        let template = self.ordering.order(candidates, context);
+        let summary = TemplateSummary::new(
+            considered.len(),
+            ordering::conflicts_resolved(&considered, &template),
+            &template,
+        );
+        debug!(
+            "{} ordering picked {} of {} candidates, {} left out for conflicts",
+            self.ordering.name(),
+            summary.included,
+            summary.candidates,
+            summary.conflicts_resolved
+        );
+        (template, summary)
+    }
+    
     /// Update transaction status
     async fn update_transaction_status(&self, tx_hash: H256, status: &str) -> Result<()> {
         // This would update the transaction's status in the database
@@ -162,4 +1279,14 @@ impl TransactionService {
         // Perform any cleanup here
         Ok(())
     }
-} 
\ No newline at end of file
+}
+
+/// Order candidates by simulated profit, highest first, breaking ties by gas price
+pub fn order_candidates(candidates: &mut [InclusionCandidate]) {
+    candidates.sort_by(|a, b| {
+        b.profit
+            .cmp(&a.profit)
+            .then_with(|| b.tx.gas_price.unwrap_or_default().cmp(&a.tx.gas_price.unwrap_or_default()))
+            .then_with(|| a.tx.hash.cmp(&b.tx.hash))
+    });
+}
@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use std::{collections::HashMap, time::Duration};
+use tracing::{debug, warn};
+
+use crate::{
+    config::HeartbeatConfig,
+    services::alerting::{Alert, AlertManager, Severity},
+    utils::heartbeat::HeartbeatRegistry,
+};
+
+/// Watches subsystem heartbeats internally and forwards them to an external monitor
+#[derive(Clone)]
+pub struct Watchdog {
+    /// Shared heartbeat registry
+    heartbeats: HeartbeatRegistry,
+    /// Alert manager for stale subsystems
+    alert_manager: AlertManager,
+    /// HTTP client for outbound pings
+    http: reqwest::Client,
+    /// Configuration
+    config: HeartbeatConfig,
+}
+
+impl Watchdog {
+    /// Create a new watchdog
+    pub fn new(
+        heartbeats: HeartbeatRegistry,
+        alert_manager: AlertManager,
+        config: HeartbeatConfig,
+    ) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to create heartbeat HTTP client")?;
+        
+        Ok(Self {
+            heartbeats,
+            alert_manager,
+            http,
+            config,
+        })
+    }
+    
+    /// Names of subsystems whose heartbeat has gone stale
+    pub fn stale_subsystems(&self) -> Vec<&'static str> {
+        let min_stale_after = Duration::from_secs(self.config.stale_after_seconds);
+        
+        self.heartbeats
+            .snapshot()
+            .into_iter()
+            .filter(|(_, heartbeat)| {
+                let stale_after = min_stale_after.max(heartbeat.expected_interval * 3);
+                heartbeat.last_beat.elapsed() > stale_after
+            })
+            .map(|(name, _)| name)
+            .collect()
+    }
+    
+    /// Alert on stale subsystems and ping the external monitor for healthy ones
+    pub async fn check(&self) -> Result<()> {
+        let stale = self.stale_subsystems();
+        
+        for name in &stale {
+            self.alert_manager
+                .fire(Alert::new(
+                    format!("watchdog:{}", name),
+                    Severity::Critical,
+                    "watchdog",
+                    format!("Subsystem {} heartbeat is stale", name),
+                ))
+                .await;
+        }
+        
+        // Only healthy subsystems ping out, so the external monitor notices stuck ones
+        let ping_urls: &HashMap<String, String> = &self.config.ping_urls;
+        for (name, _) in self.heartbeats.snapshot() {
+            if stale.contains(&name) {
+                continue;
+            }
+            
+            if let Some(url) = ping_urls.get(name) {
+                match self.http.get(url).send().await {
+                    Ok(response) if response.status().is_success() => {
+                        debug!("Sent heartbeat ping for {}", name);
+                    }
+                    Ok(response) => warn!("Heartbeat ping for {} returned {}", name, response.status()),
+                    Err(e) => warn!("Failed to send heartbeat ping for {}: {}", name, e),
+                }
+            }
+        }
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,305 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use sqlx::Row;
+use std::{
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::{
+    config::WebhookConfig,
+    database::{resilience::DeferredWrite, DbHealth, DbPool},
+    services::{
+        alerting::{Alert, AlertSink},
+        drain::DrainController,
+    },
+};
+
+/// Headers of a delivery: its id, shared by every attempt, and the signed send time
+pub const DELIVERY_ID_HEADER: &str = "x-webhook-id";
+pub const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+
+/// Prefix of the signature, changed if what is signed ever changes
+pub const SIGNATURE_VERSION: &str = "v1";
+
+/// Longest wait between attempts of one delivery
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Signature of a delivery: `v1=` and the hex HMAC-SHA256 of `{delivery_id}.{timestamp}.{body}`
+///
+/// The id and Unix timestamp are signed with the body, so a receiver bounding the age and
+/// remembering the ids it accepted can't be fed an old delivery under a fresh timestamp or id.
+pub fn signature(secret: &str, delivery_id: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(format!("{}.{}.", delivery_id, timestamp).as_bytes());
+    mac.update(body);
+    
+    format!("{}={}", SIGNATURE_VERSION, hex::encode(mac.finalize().into_bytes()))
+}
+
+/// How a delivery attempt ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryOutcome {
+    Delivered,
+    /// The receiver answered 409 Conflict, it already processed this delivery id
+    Replayed,
+    Failed,
+}
+
+impl DeliveryOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Delivered => "delivered",
+            Self::Replayed => "replayed",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl FromStr for DeliveryOutcome {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "delivered" => Ok(Self::Delivered),
+            "replayed" => Ok(Self::Replayed),
+            "failed" => Ok(Self::Failed),
+            other => Err(anyhow!("Unknown delivery outcome: {}", other)),
+        }
+    }
+}
+
+/// One attempt to deliver an alert to a webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryAttempt {
+    pub webhook: String,
+    pub delivery_id: Uuid,
+    pub alert_key: String,
+    /// 1 for the first attempt
+    pub attempt: u32,
+    pub outcome: DeliveryOutcome,
+    /// Status the receiver answered with, missing when the request got no response
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub latency_ms: u64,
+    pub attempted_at: DateTime<Utc>,
+}
+
+/// Log of webhook delivery attempts, queried per webhook
+#[derive(Clone)]
+pub struct WebhookDeliveryLog {
+    /// Database pool
+    db_pool: DbPool,
+    /// Connection-loss retries and deferred writes
+    health: DbHealth,
+}
+
+impl WebhookDeliveryLog {
+    /// Create a new delivery log
+    pub fn new(db_pool: DbPool, health: DbHealth) -> Self {
+        Self { db_pool, health }
+    }
+    
+    /// Log a delivery attempt, deferred while the database is down
+    pub async fn record(&self, attempt: &DeliveryAttempt) -> Result<()> {
+        let attempt = Arc::new(attempt.clone());
+        let write: DeferredWrite = Arc::new(move |pool: DbPool| -> BoxFuture<'static, Result<()>> {
+            let attempt = attempt.clone();
+            Box::pin(async move { record_attempt(&pool, &attempt).await })
+        });
+        
+        self.health.write_or_defer(&self.db_pool, write).await
+    }
+    
+    /// Most recent delivery attempts to a webhook, newest first
+    pub async fn attempts(&self, webhook: &str, limit: i64) -> Result<Vec<DeliveryAttempt>> {
+        self.health.retry(|| self.attempts_once(webhook, limit)).await
+    }
+    
+    async fn attempts_once(&self, webhook: &str, limit: i64) -> Result<Vec<DeliveryAttempt>> {
+        let rows = sqlx::query(
+            "SELECT webhook, delivery_id, alert_key, attempt, outcome, status_code, error, latency_ms, attempted_at
+             FROM webhook_deliveries
+             WHERE webhook = $1
+             ORDER BY attempted_at DESC, id DESC
+             LIMIT $2",
+        )
+        .bind(webhook)
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await
+        .context("Failed to fetch webhook delivery attempts")?;
+        
+        rows.iter()
+            .map(|row| {
+                Ok(DeliveryAttempt {
+                    webhook: row.try_get("webhook")?,
+                    delivery_id: row.try_get("delivery_id")?,
+                    alert_key: row.try_get("alert_key")?,
+                    attempt: row.try_get::<i32, _>("attempt")? as u32,
+                    outcome: row.try_get::<String, _>("outcome")?.parse()?,
+                    status_code: row.try_get::<Option<i32>, _>("status_code")?.map(|status| status as u16),
+                    error: row.try_get("error")?,
+                    latency_ms: row.try_get::<i64, _>("latency_ms")? as u64,
+                    attempted_at: row.try_get("attempted_at")?,
+                })
+            })
+            .collect()
+    }
+}
+
+async fn record_attempt(db_pool: &DbPool, attempt: &DeliveryAttempt) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO webhook_deliveries
+         (webhook, delivery_id, alert_key, attempt, outcome, status_code, error, latency_ms, attempted_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+    )
+    .bind(&attempt.webhook)
+    .bind(attempt.delivery_id)
+    .bind(&attempt.alert_key)
+    .bind(attempt.attempt as i32)
+    .bind(attempt.outcome.as_str())
+    .bind(attempt.status_code.map(|status| status as i32))
+    .bind(&attempt.error)
+    .bind(attempt.latency_ms as i64)
+    .bind(attempt.attempted_at)
+    .execute(db_pool)
+    .await
+    .context("Failed to record webhook delivery attempt")?;
+    
+    Ok(())
+}
+
+/// Posts alerts to a named webhook, signed when it has a secret
+///
+/// Each alert is one delivery with its own id, retried with backoff on failure and signed
+/// afresh on every attempt. A receiver that deduplicates by id answers 409 to a delivery it
+/// has already processed, such as one whose response we lost, which ends the retries. Every
+/// attempt is logged under the webhook's name. Deliveries run in the background so a slow
+/// receiver doesn't hold up the other sinks.
+#[derive(Clone)]
+pub struct SignedWebhookSink {
+    http: reqwest::Client,
+    config: WebhookConfig,
+    max_attempts: u32,
+    log: WebhookDeliveryLog,
+    /// Deliveries under way are waited for when draining
+    drain: DrainController,
+}
+
+impl SignedWebhookSink {
+    pub fn new(
+        http: reqwest::Client,
+        config: WebhookConfig,
+        max_attempts: u32,
+        log: WebhookDeliveryLog,
+        drain: DrainController,
+    ) -> Self {
+        Self {
+            http,
+            config,
+            max_attempts,
+            log,
+            drain,
+        }
+    }
+    
+    async fn deliver(&self, delivery_id: Uuid, alert_key: String, body: Vec<u8>) {
+        for attempt in 1..=self.max_attempts {
+            let attempted_at = Utc::now();
+            let timestamp = attempted_at.timestamp();
+            let mut request = self
+                .http
+                .post(&self.config.url)
+                .header("Content-Type", "application/json")
+                .header(DELIVERY_ID_HEADER, delivery_id.to_string())
+                .header(TIMESTAMP_HEADER, timestamp.to_string());
+            if let Some(secret) = &self.config.secret {
+                let signature = signature(secret, &delivery_id.to_string(), timestamp, &body);
+                request = request.header(SIGNATURE_HEADER, signature);
+            }
+            
+            let started = Instant::now();
+            let (outcome, status_code, error) = match request.body(body.clone()).send().await {
+                Ok(response) if response.status().is_success() => {
+                    (DeliveryOutcome::Delivered, Some(response.status().as_u16()), None)
+                }
+                Ok(response) if response.status() == StatusCode::CONFLICT => {
+                    (DeliveryOutcome::Replayed, Some(response.status().as_u16()), None)
+                }
+                Ok(response) => (
+                    DeliveryOutcome::Failed,
+                    Some(response.status().as_u16()),
+                    Some(format!("Receiver answered {}", response.status())),
+                ),
+                Err(e) => (DeliveryOutcome::Failed, None, Some(e.to_string())),
+            };
+            metrics::counter!("webhook_deliveries_total", 1, "outcome" => outcome.as_str());
+            
+            let record = DeliveryAttempt {
+                webhook: self.config.name.clone(),
+                delivery_id,
+                alert_key: alert_key.clone(),
+                attempt,
+                outcome,
+                status_code,
+                error,
+                latency_ms: started.elapsed().as_millis() as u64,
+                attempted_at,
+            };
+            if let Err(e) = self.log.record(&record).await {
+                warn!("Failed to log delivery {} to webhook {}: {}", delivery_id, self.config.name, e);
+            }
+            
+            match outcome {
+                DeliveryOutcome::Delivered => return,
+                DeliveryOutcome::Replayed => {
+                    debug!("Webhook {} already processed delivery {}", self.config.name, delivery_id);
+                    return;
+                }
+                DeliveryOutcome::Failed if attempt < self.max_attempts => {
+                    let backoff = Duration::from_secs(1 << (attempt - 1).min(6)).min(MAX_BACKOFF);
+                    tokio::time::sleep(backoff).await;
+                }
+                DeliveryOutcome::Failed => {
+                    warn!(
+                        "Giving up on delivery {} of alert {} to webhook {} after {} attempts",
+                        delivery_id, alert_key, self.config.name, attempt
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl AlertSink for SignedWebhookSink {
+    async fn notify(&self, alert: &Alert) -> Result<()> {
+        let body = serde_json::to_vec(&json!({
+            "text": format!("[{:?}] {}: {}", alert.severity, alert.source, alert.message),
+            "alert": alert,
+        }))?;
+        
+        let sink = self.clone();
+        let alert_key = alert.key.clone();
+        let in_flight = self.drain.track("webhook_delivery");
+        tokio::spawn(async move {
+            sink.deliver(Uuid::new_v4(), alert_key, body).await;
+            drop(in_flight);
+        });
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::{
+    abi::{self, Token},
+    providers::{Http, Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{
+        transaction::eip2718::TypedTransaction, Address, Block, BlockNumber, Bytes, Eip1559TransactionRequest,
+        Transaction, TransactionReceipt, H256, U256,
+    },
+    utils::{id, rlp, Anvil, AnvilInstance},
+};
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::{
+    blockchain::{self, monitor, BlockchainClient},
+    config::{defaults, Config, ExecutionMode},
+    core::strategy::Opportunity,
+    database,
+    services::{transaction::TxSource, ServiceContext},
+};
+
+/// Node the fork is taken from, and optionally the block it is pinned to
+const FORK_URL_ENV: &str = "ANVIL_FORK_URL";
+const FORK_BLOCK_ENV: &str = "ANVIL_FORK_BLOCK";
+
+/// Postgres and Redis the services run against, the configured defaults when unset
+const DATABASE_URL_ENV: &str = "TEST_DATABASE_URL";
+const REDIS_URL_ENV: &str = "TEST_REDIS_URL";
+
+/// An anvil fork of a live chain with our services running against it
+///
+/// Anvil must be on PATH. Mining is off, so a test decides what goes into each block: sent
+/// transactions wait in anvil's pool until `mine`, and `next_block` seals them and hands the
+/// block to the services the way the monitor would. The services run as a dry run, so nothing
+/// reaches a relay or a real node, with the Postgres and Redis named by `TEST_DATABASE_URL` and
+/// `TEST_REDIS_URL`; they are created on first use, so RPC-only tests need neither.
+pub struct AnvilHarness {
+    /// Keeps the anvil process alive, killed on drop
+    anvil: AnvilInstance,
+    /// Raw provider for anvil's cheat RPCs
+    provider: Provider<Http>,
+    pub blockchain_client: Arc<BlockchainClient>,
+    /// Accounts anvil funded at startup, with their keys
+    pub accounts: Vec<LocalWallet>,
+    /// Configuration the services are created from, adjustable before first use
+    pub config: Config,
+    services: Option<Arc<ServiceContext>>,
+}
+
+impl AnvilHarness {
+    /// Fork from `ANVIL_FORK_URL`, pinned to `ANVIL_FORK_BLOCK` when set
+    pub async fn from_env() -> Result<Self> {
+        let fork_url = std::env::var(FORK_URL_ENV).context(format!("{} is not set", FORK_URL_ENV))?;
+        let fork_block = std::env::var(FORK_BLOCK_ENV)
+            .ok()
+            .map(|block| block.parse::<u64>())
+            .transpose()
+            .context(format!("Invalid {}", FORK_BLOCK_ENV))?;
+        
+        Self::fork(&fork_url, fork_block).await
+    }
+    
+    /// Start anvil forking `fork_url`, at its latest block unless one is given
+    pub async fn fork(fork_url: &str, fork_block: Option<u64>) -> Result<Self> {
+        let mut anvil = Anvil::new().fork(fork_url).arg("--no-mining");
+        if let Some(fork_block) = fork_block {
+            anvil = anvil.fork_block_number(fork_block);
+        }
+        let anvil = anvil.spawn();
+        
+        let provider = Provider::<Http>::try_from(anvil.endpoint()).context("Failed to connect to anvil")?;
+        let chain_id = provider.get_chainid().await?.as_u64();
+        
+        let mut config = defaults::default_config();
+        config.blockchain.rpc_url = anvil.endpoint();
+        config.blockchain.ws_url = anvil.ws_endpoint();
+        config.blockchain.chain_id = chain_id;
+        config.execution_mode = ExecutionMode::DryRun;
+        config.services.mempool_recording.enabled = false;
+        if let Ok(url) = std::env::var(DATABASE_URL_ENV) {
+            config.database.url = url;
+        }
+        if let Ok(url) = std::env::var(REDIS_URL_ENV) {
+            config.redis.url = url;
+        }
+        
+        let blockchain_client = blockchain::create_client(&config.blockchain, &config.caches, None).await?;
+        let accounts = anvil
+            .keys()
+            .iter()
+            .map(|key| LocalWallet::from(key.clone()).with_chain_id(chain_id))
+            .collect();
+        
+        info!("Anvil fork of chain {} listening on {}", chain_id, anvil.endpoint());
+        
+        Ok(Self {
+            anvil,
+            provider,
+            blockchain_client,
+            accounts,
+            config,
+            services: None,
+        })
+    }
+    
+    /// HTTP endpoint of the fork
+    pub fn endpoint(&self) -> String {
+        self.anvil.endpoint()
+    }
+    
+    /// Services running against the fork, created with the migrations applied on first use
+    pub async fn services(&mut self) -> Result<Arc<ServiceContext>> {
+        if let Some(services) = &self.services {
+            return Ok(services.clone());
+        }
+        
+        let db_pool = database::connect(&self.config.database).await?;
+        database::run_migrations(&db_pool).await?;
+        let redis = database::connect_redis(&self.config.redis).await?;
+        let services = Arc::new(
+            ServiceContext::new(db_pool, redis, self.blockchain_client.clone(), &self.config).await?,
+        );
+        
+        self.services = Some(services.clone());
+        Ok(services)
+    }
+    
+    /// Set an account's ETH balance
+    pub async fn fund(&self, account: Address, amount: U256) -> Result<()> {
+        self.cheat("anvil_setBalance", (account, amount)).await?;
+        Ok(())
+    }
+    
+    /// Move ERC-20 tokens to an account from a holder on the forked chain, such as a whale
+    pub async fn fund_token(&self, token: Address, holder: Address, account: Address, amount: U256) -> Result<()> {
+        // The holder may be a contract without ETH for gas
+        self.fund(holder, U256::exp10(18)).await?;
+        self.impersonate(holder).await?;
+        
+        let mut data = id("transfer(address,uint256)").to_vec();
+        data.extend(abi::encode(&[Token::Address(account), Token::Uint(amount)]));
+        let tx_hash = self
+            .send(Eip1559TransactionRequest::new().from(holder).to(token).data(Bytes::from(data)))
+            .await?;
+        self.stop_impersonating(holder).await?;
+        self.mine(1).await?;
+        
+        match self.receipt(tx_hash).await?.status.map(|status| status.as_u64()) {
+            Some(1) => Ok(()),
+            _ => Err(anyhow!("Transfer of {:?} from {:?} reverted", token, holder)),
+        }
+    }
+    
+    /// Let transactions be sent from an account without its key
+    pub async fn impersonate(&self, account: Address) -> Result<()> {
+        self.cheat("anvil_impersonateAccount", [account]).await?;
+        Ok(())
+    }
+    
+    pub async fn stop_impersonating(&self, account: Address) -> Result<()> {
+        self.cheat("anvil_stopImpersonatingAccount", [account]).await?;
+        Ok(())
+    }
+    
+    /// Deploy a fixture contract from the first account, returning its address
+    pub async fn deploy(&self, bytecode: Bytes, constructor_args: &[Token]) -> Result<Address> {
+        let mut data = bytecode.to_vec();
+        data.extend(abi::encode(constructor_args));
+        let deployer = self.accounts.first().ok_or_else(|| anyhow!("Anvil has no accounts"))?.address();
+        
+        let tx_hash = self
+            .send(Eip1559TransactionRequest::new().from(deployer).data(Bytes::from(data)))
+            .await?;
+        self.mine(1).await?;
+        
+        self.receipt(tx_hash)
+            .await?
+            .contract_address
+            .ok_or_else(|| anyhow!("Deployment {:?} created no contract", tx_hash))
+    }
+    
+    /// Send a transaction for anvil to sign, from one of its accounts or an impersonated one
+    ///
+    /// It waits in anvil's pool until the next `mine`.
+    pub async fn send(&self, tx: Eip1559TransactionRequest) -> Result<H256> {
+        let pending = self
+            .provider
+            .send_transaction(tx, None)
+            .await
+            .context("Anvil refused the transaction")?;
+        
+        Ok(pending.tx_hash())
+    }
+    
+    /// Sign a transaction with one of our keys, filling nonce, gas and fees from the fork
+    ///
+    /// The signed transaction can be simulated or fed to the services without being sent.
+    pub async fn sign(&self, wallet: &LocalWallet, tx: Eip1559TransactionRequest) -> Result<Transaction> {
+        let mut typed: TypedTransaction = tx.from(wallet.address()).chain_id(wallet.chain_id()).into();
+        self.provider
+            .fill_transaction(&mut typed, Some(BlockNumber::Pending.into()))
+            .await
+            .context("Failed to fill the transaction")?;
+        let signature = wallet.sign_transaction(&typed).await?;
+        let raw = typed.rlp_signed(&signature);
+        
+        rlp::decode::<Transaction>(&raw).map_err(|e| anyhow!("Failed to decode the signed transaction: {}", e))
+    }
+    
+    /// Sign and send a transaction with one of our keys, returning it as signed
+    pub async fn send_signed(&self, wallet: &LocalWallet, tx: Eip1559TransactionRequest) -> Result<Transaction> {
+        let signed = self.sign(wallet, tx).await?;
+        self.provider
+            .send_raw_transaction(signed.rlp())
+            .await
+            .context("Anvil refused the signed transaction")?;
+        
+        Ok(signed)
+    }
+    
+    /// Seal blocks from anvil's pool
+    pub async fn mine(&self, blocks: u64) -> Result<()> {
+        self.cheat("anvil_mine", [U256::from(blocks)]).await?;
+        Ok(())
+    }
+    
+    pub async fn latest_block(&self) -> Result<Block<Transaction>> {
+        let number = self.blockchain_client.get_block_number().await?;
+        self.blockchain_client
+            .get_block_with_transactions(number)
+            .await?
+            .ok_or_else(|| anyhow!("Anvil has no block {}", number))
+    }
+    
+    pub async fn receipt(&self, tx_hash: H256) -> Result<TransactionReceipt> {
+        self.blockchain_client
+            .get_transaction_receipt(tx_hash)
+            .await?
+            .ok_or_else(|| anyhow!("Transaction {:?} was not mined", tx_hash))
+    }
+    
+    /// Simulated profit of a signed transaction, through the simulation service
+    pub async fn simulate(&mut self, tx: &Transaction) -> Result<U256> {
+        self.services().await?.simulation_service.simulate_transaction(tx).await
+    }
+    
+    /// Feed a signed transaction to the services and strategies as if it arrived from the mempool
+    pub async fn observe_pending(&mut self, tx: Transaction) -> Result<Vec<Opportunity>> {
+        let services = self.services().await?;
+        monitor::ingest_pending_transaction(&services, tx, TxSource::PublicMempool).await
+    }
+    
+    /// Seal a block and hand it to the block building service, returning the sealed block
+    pub async fn next_block(&mut self) -> Result<Block<Transaction>> {
+        let services = self.services().await?;
+        self.mine(1).await?;
+        let block = self.latest_block().await?;
+        services.block_building_service.process_new_block(block.clone()).await?;
+        
+        Ok(block)
+    }
+    
+    async fn cheat<P: serde::Serialize + Send + Sync>(&self, method: &str, params: P) -> Result<Value> {
+        self.provider
+            .request(method, params)
+            .await
+            .context(format!("Anvil {} failed", method))
+    }
+}
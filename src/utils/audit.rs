@@ -0,0 +1,116 @@
+use ethers::types::{Address, H256, U256};
+use std::fmt::Display;
+use tracing::info;
+
+/// Tracing target of submission audit events, routed to the audit log when one is configured
+pub const AUDIT_TARGET: &str = "audit";
+
+fn outcome<E: Display>(error: Option<&E>) -> (&'static str, String) {
+    match error {
+        Some(error) => ("rejected", error.to_string()),
+        None => ("accepted", String::new()),
+    }
+}
+
+fn hashes(txs: &[H256]) -> String {
+    serde_json::to_string(txs).unwrap_or_default()
+}
+
+/// Record a bundle sent to a relay via `eth_sendBundle`
+pub fn bundle_submitted<E: Display>(
+    bundle_hash: Option<H256>,
+    txs: &[H256],
+    target_block: u64,
+    value: U256,
+    relay: &str,
+    error: Option<&E>,
+) {
+    let (outcome, error) = outcome(error);
+    info!(
+        target: AUDIT_TARGET,
+        kind = "bundle",
+        bundle_id = %bundle_hash.map(|hash| format!("{:?}", hash)).unwrap_or_default(),
+        txs = %hashes(txs),
+        target_block,
+        value_wei = %value,
+        relay,
+        outcome,
+        error = %error,
+        "bundle submission"
+    );
+}
+
+/// Record a block bid sent to one relay
+pub fn bid_submitted<E: Display>(
+    block_hash: H256,
+    txs: &[H256],
+    slot: u64,
+    value: U256,
+    relay: &str,
+    error: Option<&E>,
+) {
+    let (outcome, error) = outcome(error);
+    info!(
+        target: AUDIT_TARGET,
+        kind = "bid",
+        bundle_id = ?block_hash,
+        txs = %hashes(txs),
+        slot,
+        value_wei = %value,
+        relay,
+        outcome,
+        error = %error,
+        "bid submission"
+    );
+}
+
+/// Record a user transaction sent through a private route
+pub fn private_transaction_submitted<E: Display>(tx_hash: H256, route: &str, endpoint: &str, error: Option<&E>) {
+    let (outcome, error) = outcome(error);
+    info!(
+        target: AUDIT_TARGET,
+        kind = "private_transaction",
+        tx_hash = ?tx_hash,
+        route,
+        relay = endpoint,
+        outcome,
+        error = %error,
+        "private transaction submission"
+    );
+}
+
+/// Record a user transaction forwarded to the public mempool
+pub fn public_transaction_submitted<E: Display>(tx_hash: H256, error: Option<&E>) {
+    let (outcome, error) = outcome(error);
+    info!(
+        target: AUDIT_TARGET,
+        kind = "public_transaction",
+        tx_hash = ?tx_hash,
+        route = "public",
+        outcome,
+        error = %error,
+        "public transaction submission"
+    );
+}
+
+/// Record a transaction we originated and broadcast, `tx_hash` missing when it was refused
+pub fn transaction_sent<E: Display>(
+    tx_hash: Option<H256>,
+    from: Option<Address>,
+    nonce: Option<U256>,
+    value: U256,
+    error: Option<&E>,
+) {
+    let (outcome, error) = outcome(error);
+    info!(
+        target: AUDIT_TARGET,
+        kind = "transaction",
+        tx_hash = %tx_hash.map(|hash| format!("{:?}", hash)).unwrap_or_default(),
+        from = %from.map(|from| format!("{:?}", from)).unwrap_or_default(),
+        nonce = %nonce.map(|nonce| nonce.to_string()).unwrap_or_default(),
+        value_wei = %value,
+        outcome,
+        error = %error,
+        "transaction submission"
+    );
+}
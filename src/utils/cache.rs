@@ -0,0 +1,157 @@
+use lru::LruCache;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    num::NonZeroUsize,
+};
+
+use crate::config::CacheSettings;
+
+/// Which entry a full cache drops to make room
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EvictionPolicy {
+    /// Least recently used
+    Lru,
+    /// Least frequently used, ties broken by least recent use
+    Lfu,
+}
+
+/// Least-frequently-used store, ordered by (hits, last use)
+struct LfuStore<K, V> {
+    entries: HashMap<K, (V, u64, u64)>,
+    order: BTreeMap<(u64, u64), K>,
+    tick: u64,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> LfuStore<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            capacity,
+        }
+    }
+    
+    fn get(&mut self, key: &K) -> Option<&V> {
+        self.tick += 1;
+        let tick = self.tick;
+        let (_, hits, last_used) = self.entries.get_mut(key)?;
+        self.order.remove(&(*hits, *last_used));
+        *hits += 1;
+        *last_used = tick;
+        self.order.insert((*hits, tick), key.clone());
+        self.entries.get(key).map(|(value, _, _)| value)
+    }
+    
+    /// Insert an entry, returning whether another was evicted
+    fn put(&mut self, key: K, value: V) -> bool {
+        self.tick += 1;
+        if let Some((old, hits, last_used)) = self.entries.get_mut(&key) {
+            *old = value;
+            self.order.remove(&(*hits, *last_used));
+            *last_used = self.tick;
+            self.order.insert((*hits, self.tick), key);
+            return false;
+        }
+        
+        let mut evicted = false;
+        if self.entries.len() >= self.capacity {
+            if let Some((_, victim)) = self.order.pop_first() {
+                self.entries.remove(&victim);
+                evicted = true;
+            }
+        }
+        
+        self.order.insert((1, self.tick), key.clone());
+        self.entries.insert(key, (value, 1, self.tick));
+        evicted
+    }
+    
+    fn remove(&mut self, key: &K) {
+        if let Some((_, hits, last_used)) = self.entries.remove(key) {
+            self.order.remove(&(hits, last_used));
+        }
+    }
+}
+
+enum Store<K: Hash + Eq, V> {
+    Lru(LruCache<K, V>),
+    Lfu(LfuStore<K, V>),
+}
+
+/// Size-bounded cache with a configurable eviction policy, reporting hits, misses and evictions
+pub struct BoundedCache<K: Hash + Eq, V> {
+    /// Cache name, used as the metrics label
+    name: &'static str,
+    store: Mutex<Store<K, V>>,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> BoundedCache<K, V> {
+    pub fn new(name: &'static str, settings: &CacheSettings) -> Self {
+        let capacity = settings.capacity.max(1);
+        let store = match settings.policy {
+            EvictionPolicy::Lru => Store::Lru(LruCache::new(
+                NonZeroUsize::new(capacity).expect("capacity is non-zero"),
+            )),
+            EvictionPolicy::Lfu => Store::Lfu(LfuStore::new(capacity)),
+        };
+        
+        Self {
+            name,
+            store: Mutex::new(store),
+        }
+    }
+    
+    /// Look up an entry, counting it as a use
+    pub fn get(&self, key: &K) -> Option<V> {
+        let value = match &mut *self.store.lock() {
+            Store::Lru(cache) => cache.get(key).cloned(),
+            Store::Lfu(cache) => cache.get(key).cloned(),
+        };
+        
+        let outcome = if value.is_some() { "cache_hits_total" } else { "cache_misses_total" };
+        metrics::counter!(outcome, 1, "cache" => self.name);
+        value
+    }
+    
+    /// Whether an entry is present, counting it as a use
+    pub fn contains(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+    
+    /// Insert an entry, evicting another if the cache is full
+    pub fn insert(&self, key: K, value: V) {
+        let mut store = self.store.lock();
+        let (evicted, len) = match &mut *store {
+            Store::Lru(cache) => {
+                let evicted = !cache.contains(&key) && cache.len() == cache.cap().get();
+                cache.put(key, value);
+                (evicted, cache.len())
+            }
+            Store::Lfu(cache) => {
+                let evicted = cache.put(key, value);
+                (evicted, cache.entries.len())
+            }
+        };
+        
+        if evicted {
+            metrics::counter!("cache_evictions_total", 1, "cache" => self.name);
+        }
+        metrics::gauge!("cache_entries", len as f64, "cache" => self.name);
+    }
+    
+    /// Drop an entry
+    pub fn remove(&self, key: &K) {
+        match &mut *self.store.lock() {
+            Store::Lru(cache) => {
+                cache.pop(key);
+            }
+            Store::Lfu(cache) => cache.remove(key),
+        }
+    }
+}
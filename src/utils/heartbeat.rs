@@ -0,0 +1,55 @@
+use dashmap::DashMap;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Liveness state of one subsystem
+#[derive(Debug, Clone, Copy)]
+pub struct Heartbeat {
+    /// When the subsystem last reported progress
+    pub last_beat: Instant,
+    /// How often the subsystem is expected to beat
+    pub expected_interval: Duration,
+}
+
+/// Registry of per-subsystem heartbeats, shared by every loop that reports liveness
+#[derive(Clone, Default)]
+pub struct HeartbeatRegistry {
+    beats: Arc<DashMap<&'static str, Heartbeat>>,
+}
+
+impl HeartbeatRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Register a subsystem so the watchdog starts tracking it
+    pub fn register(&self, name: &'static str, expected_interval: Duration) {
+        self.beats.insert(
+            name,
+            Heartbeat {
+                last_beat: Instant::now(),
+                expected_interval,
+            },
+        );
+    }
+    
+    /// Record that a subsystem made progress
+    pub fn beat(&self, name: &'static str) {
+        if let Some(mut heartbeat) = self.beats.get_mut(name) {
+            heartbeat.last_beat = Instant::now();
+        }
+    }
+    
+    /// Stop tracking a subsystem, e.g. when it shuts down cleanly
+    pub fn unregister(&self, name: &'static str) {
+        self.beats.remove(name);
+    }
+    
+    /// Snapshot of every registered subsystem
+    pub fn snapshot(&self) -> Vec<(&'static str, Heartbeat)> {
+        self.beats.iter().map(|entry| (*entry.key(), *entry.value())).collect()
+    }
+}
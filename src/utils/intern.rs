@@ -0,0 +1,50 @@
+use dashmap::DashMap;
+use ethers::types::Address;
+use std::sync::{Arc, OnceLock};
+
+/// Distinct addresses held before the interner is reset
+const MAX_INTERNED_ADDRESSES: usize = 100_000;
+
+/// Shared hex strings for frequently repeated addresses
+///
+/// Routers, tokens and busy senders appear in a large share of pending transactions,
+/// so their formatted form is allocated once and shared. The table is cleared when it
+/// fills rather than tracking recency, keeping lookups lock-free on the hot path.
+pub struct AddressInterner {
+    strings: DashMap<Address, Arc<str>>,
+    capacity: usize,
+}
+
+impl AddressInterner {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            strings: DashMap::new(),
+            capacity,
+        }
+    }
+    
+    /// The `0x`-prefixed lowercase hex form of an address
+    pub fn intern(&self, address: Address) -> Arc<str> {
+        if let Some(interned) = self.strings.get(&address) {
+            return interned.clone();
+        }
+        
+        if self.strings.len() >= self.capacity {
+            metrics::counter!("address_interner_resets_total", 1);
+            self.strings.clear();
+        }
+        
+        self.strings
+            .entry(address)
+            .or_insert_with(|| Arc::from(format!("{:?}", address)))
+            .clone()
+    }
+}
+
+/// Process-wide address interner
+pub fn address_str(address: Address) -> Arc<str> {
+    static INTERNER: OnceLock<AddressInterner> = OnceLock::new();
+    INTERNER
+        .get_or_init(|| AddressInterner::new(MAX_INTERNED_ADDRESSES))
+        .intern(address)
+}
@@ -1,59 +1,81 @@
 use anyhow::{Context, Result};
-use std::io;
+use std::{fs::File, sync::Mutex};
+use tracing::Level;
 use tracing_subscriber::{
-    filter::EnvFilter,
+    filter::{EnvFilter, Targets},
     fmt::{self, format::FmtSpan},
     layer::SubscriberExt,
     util::SubscriberInitExt,
+    Layer,
 };
 
-use crate::config::LoggingConfig;
+use crate::{
+    config::LoggingConfig,
+    utils::{audit::AUDIT_TARGET, telemetry},
+};
 
 /// Initialize the logging subsystem based on configuration
 pub fn init(config: &LoggingConfig) -> Result<()> {
-    let env_filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new(&config.level));
+    telemetry::init(&config.trace_sampling);
+    let level_filter = || EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.level));
+
+    // If a file path is provided, add file logging
+    let file_layer = match &config.file_path {
+        Some(file_path) => Some(
+            fmt::Layer::new()
+                .with_writer(Mutex::new(open_append(file_path)?))
+                .with_ansi(false)
+                .with_filter(level_filter()),
+        ),
+        None => None,
+    };
 
-    let fmt_layer = fmt::Layer::new()
-        .with_span_events(FmtSpan::CLOSE)
-        .with_target(true);
+    // Submission audit events go to their own JSON file regardless of the log level
+    let audit_layer = match &config.audit_file_path {
+        Some(audit_file_path) => Some(
+            fmt::Layer::new()
+                .json()
+                .flatten_event(true)
+                .with_current_span(false)
+                .with_writer(Mutex::new(open_append(audit_file_path)?))
+                .with_filter(Targets::new().with_target(AUDIT_TARGET, Level::INFO)),
+        ),
+        None => None,
+    };
 
     let subscriber = tracing_subscriber::registry()
-        .with(env_filter);
+        .with(file_layer)
+        .with(audit_layer);
 
     if config.json_format {
         let json_layer = fmt::Layer::new()
             .json()
             .with_current_span(true)
-            .with_span_list(true);
+            .with_span_list(true)
+            .with_filter(level_filter());
         
         subscriber.with(json_layer).init();
     } else {
-        subscriber.with(fmt_layer).init();
-    }
-
-    // If a file path is provided, add file logging
-    if let Some(file_path) = &config.file_path {
-        let file = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(file_path)
-            .context("Failed to open log file")?;
-        
-        let file_layer = fmt::Layer::new()
-            .with_writer(io::BufWriter::new(file))
-            .with_ansi(false);
+        let fmt_layer = fmt::Layer::new()
+            .with_span_events(FmtSpan::CLOSE)
+            .with_target(true)
+            .with_filter(level_filter());
         
-        tracing_subscriber::registry()
-            .with(env_filter)
-            .with(file_layer)
-            .init();
+        subscriber.with(fmt_layer).init();
     }
 
     Ok(())
 }
 
+fn open_append(path: &str) -> Result<File> {
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open log file {}", path))
+}
+
 /// Helper to log unhandled errors within async contexts
 pub fn log_error<E: std::fmt::Display>(err: E) {
     tracing::error!("Error: {}", err);
-} 
\ No newline at end of file
+}
\ No newline at end of file
@@ -1,16 +1,41 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 use prometheus::{Encoder, Registry, TextEncoder};
-use std::time::{Duration, Instant};
+use std::{
+    collections::HashMap,
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+/// Handle to the installed metrics recorder
+static RECORDER: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Install the global metrics recorder and register all application metrics
+pub fn install_recorder() -> Result<()> {
+    let handle = PrometheusBuilder::new()
+        .install_recorder()
+        .context("Failed to install metrics recorder")?;
+    let _ = RECORDER.set(handle);
+    
+    register_metrics();
+    Ok(())
+}
 
 /// Register all application metrics
 pub fn register_metrics() {
     // Transaction metrics
     register_transaction_metrics();
     
+    // Mempool view metrics
+    register_mempool_metrics();
+    
     // Block building metrics
     register_block_metrics();
     
+    // Strategy metrics
+    register_strategy_metrics();
+    
     // API metrics
     register_api_metrics();
     
@@ -19,6 +44,18 @@ pub fn register_metrics() {
     
     // Blockchain client metrics
     register_blockchain_metrics();
+    
+    // Business KPI metrics
+    register_kpi_metrics();
+    
+    // Alerting metrics
+    register_alert_metrics();
+    
+    // Bounded cache metrics
+    register_cache_metrics();
+    
+    // Staking and commission metrics
+    register_staking_metrics();
 }
 
 fn register_transaction_metrics() {
@@ -30,6 +67,69 @@ fn register_transaction_metrics() {
     // Transaction timing
     histogram!("transaction_processing_time_seconds", "Time to process a transaction");
     histogram!("transaction_simulation_time_seconds", "Time to simulate a transaction");
+    
+    // Confirmed transaction workers
+    histogram!("confirmed_block_processing_seconds", "Time to process a confirmed block's transactions off the head path");
+    gauge!("confirmed_block_queue_depth", "Confirmed blocks waiting for the workers");
+    counter!("confirmed_block_queue_full_total", "Times the block loop waited on a full confirmed block queue");
+    
+    // Outbound transactions
+    counter!("nonce_gaps_total", "Unused nonces found below an account's next nonce and queued for reuse");
+    counter!("dry_run_suppressed_total", "Transactions, bundles and bids not sent because of a dry run, by kind");
+    gauge!("nonce_stuck_transactions", "Outbound transactions pending longer than stuck_tx_seconds");
+    counter!("transactions_replaced_total", "Stuck outbound transactions re-sent with higher fees");
+    counter!("private_transactions_submitted_total", "User transactions sent to a private endpoint, by route and outcome");
+    counter!("gas_golf_transactions_total", "Outbound transactions optimized before signing, by route");
+    counter!("gas_golf_saved_gas_total", "Gas the optimizer expects to save on outbound transactions, by route");
+    counter!("access_lists_attached_total", "Gas golfed transactions given an access list simulation showed to save gas");
+    counter!("access_list_saved_gas_total", "Gas simulation showed attached access lists to save");
+}
+
+fn register_mempool_metrics() {
+    counter!("pending_tx_bodies_received_total", "Pending transactions announced with their body, so none was fetched");
+    counter!("sensitive_candidates_expired_total", "Sensitive candidates dropped after their last target block");
+    counter!("bloxroute_txs_received_total", "Pending transactions first seen on the bloXroute stream");
+    counter!("bloxroute_txs_duplicate_total", "bloXroute transactions skipped because the mempool already had them");
+    counter!("pending_tx_fetches_skipped_total", "Pending announcements dropped without fetching, as the transaction was already known");
+    counter!("address_interner_resets_total", "Times the address interner filled and was cleared");
+    counter!("mempool_recorded_transactions_total", "Pending transactions appended to the replayable recording");
+    counter!("mempool_recorded_bytes_total", "Bytes appended to the replayable recording");
+    counter!("mempool_recording_dropped_total", "Pending transactions left out of the recording as writes fell behind");
+    counter!("mempool_writes_dropped_total", "Pending transactions left unpersisted as database writes fell behind");
+    gauge!("snapshot_restored_entries", "Entries of hot state restored from the snapshot at startup");
+    counter!("snapshot_bytes_written_total", "Bytes flushed to the hot state snapshot store");
+    gauge!("mempool_pending_transactions", "Pending transactions tracked in the mempool view");
+    gauge!("mempool_pending_bytes", "Encoded size of pending transactions in bytes");
+    gauge!("mempool_pending_gas", "Gas limit summed over pending transactions");
+    gauge!("mempool_pending_by_type", "Pending transactions by transaction type");
+    gauge!("mempool_arrivals_last_minute", "Transactions first seen in the last minute");
+    gauge!("mempool_median_effective_tip_gwei", "Median tip pending transactions pay at the latest base fee in gwei");
+    gauge!("mempool_base_fee_trend", "Mean per-block base fee change over recent blocks");
+    gauge!("mempool_congestion_score", "Congestion score from 0 (idle) to 1 (congested)");
+    gauge!("fee_estimator_next_base_fee_gwei", "Predicted base fee of the next block in gwei");
+    counter!("sandwich_opportunities_total", "Sandwich opportunities found around pending swaps");
+    gauge!("sandwich_opportunities_open", "Sandwich opportunities awaiting their target block");
+    counter!("arbitrage_opportunities_total", "Profitable arbitrage cycles found across tracked pools");
+    gauge!("arbitrage_pools_tracked", "Pools in the arbitrage graph");
+    counter!("liquidation_opportunities_total", "Profitable liquidations found on Aave and Compound");
+    gauge!("liquidation_positions_tracked", "Lending positions whose health is tracked");
+    counter!("liquidation_positions_skipped_total", "Large borrowers not tracked as the position limit was reached");
+    counter!("liquidation_blocks_skipped_total", "Blocks not checked as the previous block's check was still running");
+    gauge!("token_price_usd", "Aggregated USD price, by token");
+    counter!("price_source_errors_total", "Price sources that failed to answer or returned an unusable price, by source");
+    counter!("token_metadata_fetches_total", "ERC-20 metadata reads from chain, on first use or once stale");
+    gauge!("address_labels", "Addresses with a known entity label, imported or entered by an operator");
+    
+    // Internal queues
+    gauge!("simulation_queue_depth", "Simulations waiting for a free simulation slot");
+    counter!("simulation_shadow_skipped_total", "Sampled shadow comparisons skipped because too many were running");
+    gauge!("simulations_in_flight", "Simulations currently running");
+    gauge!("inclusion_candidates", "Inclusion candidates held for the next block, by flow");
+    gauge!("analytics_outbox_rows", "Analytics rows buffered and not yet written to ClickHouse");
+    counter!("analytics_rows_dropped_total", "Analytics rows dropped as the buffer filled while ClickHouse was failing, by table");
+    gauge!("event_bus_backlog", "Stream events the slowest subscriber has yet to receive");
+    gauge!("event_bus_subscribers", "WebSocket and SSE subscribers to the event bus");
+    histogram!("ws_send_queue_occupancy", "Messages queued for a WebSocket client when an event is added");
 }
 
 fn register_block_metrics() {
@@ -37,17 +137,47 @@ fn register_block_metrics() {
     counter!("blocks_built_total", "Total number of blocks built");
     counter!("blocks_submitted_total", "Total number of blocks submitted");
     counter!("blocks_accepted_total", "Total number of blocks accepted by the network");
+    counter!("candidates_landed_elsewhere_total", "Template candidates that landed in blocks built by other builders");
+    counter!("relay_submissions_total", "Total number of bid submissions to relays, by relay and outcome");
+    gauge!("relay_proposer_duties", "Upcoming slots with a validator registered at any relay");
     
     // Block timing and size
     histogram!("block_building_time_seconds", "Time to build a block");
+    histogram!("task_schedule_delay_seconds", "Time a task on a lane waited to be first polled, by lane and task");
+    histogram!("task_duration_seconds", "Time a task on a lane ran for, by lane and task");
     gauge!("block_fullness_ratio", "Ratio of block gas used to gas limit");
     histogram!("block_profit_eth", "Profit extracted per block in ETH");
+    
+    // Shadow builds of lost slots
+    gauge!("builder_efficiency", "Value of our ordering over the winner's, for the last lost slot scored");
+    histogram!("builder_efficiency_ratio", "Value of our ordering over the winner's, per lost slot");
+    
+    // Maintenance windows
+    gauge!("maintenance_window_open", "Whether the upcoming slots were quiet enough for maintenance when last checked");
+    histogram!("maintenance_deferral_seconds", "Time a maintenance job waited for a quiet window, by job");
+    counter!("maintenance_runs_skipped_total", "Maintenance runs skipped for lack of a quiet window, by job");
+    
+    // Searcher reputation
+    counter!("searcher_bundles_throttled_total", "Sealed bundles refused to low-reputation searchers under load");
+    gauge!("searcher_reputation_score", "Reputation score per searcher as of the last flush");
+}
+
+fn register_strategy_metrics() {
+    counter!("strategy_hooks_total", "Strategy hook calls, by strategy, hook and outcome");
+    counter!("strategy_opportunities_total", "Opportunities found, by strategy");
+    histogram!("strategy_hook_seconds", "Time a strategy spent in one hook, by strategy and hook");
+    gauge!("strategy_enabled", "Whether a strategy is enabled, after it was last toggled");
+    gauge!("strategy_scheduled", "Whether a scheduled strategy is within its windows and conditions, by strategy");
 }
 
 fn register_api_metrics() {
     // API request metrics
     counter!("api_requests_total", "Total number of API requests");
     counter!("api_errors_total", "Total number of API errors");
+    counter!("api_rate_limited_total", "API requests rejected by the rate limiter");
+    counter!("api_rate_limit_local_total", "API requests limited in process as Redis was degraded");
+    counter!("api_rate_limiter_errors_total", "Shared rate limit checks that failed and fell back to the local bucket");
+    counter!("ws_events_dropped_total", "Events dropped for WebSocket clients reading slower than they are sent");
     
     // API timing
     histogram!("api_request_duration_seconds", "API request duration in seconds");
@@ -58,6 +188,16 @@ fn register_database_metrics() {
     gauge!("db_connections_active", "Number of active database connections");
     counter!("db_queries_total", "Total number of database queries");
     histogram!("db_query_duration_seconds", "Database query duration in seconds");
+    gauge!("db_healthy", "Whether the last database operation reached Postgres");
+    counter!("db_retries_total", "Database operations retried after losing the connection");
+    gauge!("db_buffered_writes", "Non-critical database writes waiting for the database to come back");
+    counter!("db_buffered_writes_dropped_total", "Buffered database writes dropped because the buffer was full");
+    
+    // Redis metrics
+    counter!("redis_command_retries_total", "Redis commands retried after a timeout or transport failure");
+    counter!("redis_command_failures_total", "Redis commands that failed after all retries");
+    counter!("redis_cache_skipped_total", "Non-critical Redis writes and reads skipped while Redis was degraded");
+    gauge!("redis_degraded", "Whether Redis recently stopped answering and non-critical caching is skipped");
 }
 
 fn register_blockchain_metrics() {
@@ -65,7 +205,80 @@ fn register_blockchain_metrics() {
     counter!("blockchain_requests_total", "Total number of blockchain client requests");
     counter!("blockchain_errors_total", "Total number of blockchain client errors");
     gauge!("blockchain_current_block", "Current blockchain block height");
+    gauge!("builder_head_stale", "Whether the head we build on has stopped advancing");
+    counter!("builder_stale_head_total", "Total number of times the head went stale");
+    counter!("block_storage_raw_bytes_total", "Bytes of confirmed block JSON stored, before compression and deduplication");
+    counter!("block_storage_written_bytes_total", "Bytes of compressed block bodies and new calldata written");
+    counter!("calldata_blobs_deduplicated_total", "Stored transaction calldata referencing an already stored copy");
+    counter!("blocks_backfilled_total", "Blocks missed while offline or disconnected and processed after the fact");
+    counter!("blocks_backfill_missing_total", "Blocks a backfill skipped because the node didn't return them");
+    counter!("indexed_events_total", "Events of configured contracts decoded and stored, by contract");
+    counter!("indexed_events_removed_total", "Indexed events removed because their block was reorged out");
+    counter!("canary_runs_total", "Canary self-transfers sent through the pipeline");
+    counter!("canary_failures_total", "Canaries that did not confirm, by the stage they failed at");
+    histogram!("canary_stage_seconds", "Time from sending a canary to it reaching a pipeline stage, by stage");
+    gauge!("canary_success_rate", "Share of the last window of canaries that confirmed");
+    gauge!("canary_latency_seconds", "Median send-to-confirmation latency over the last window of canaries");
     histogram!("blockchain_request_duration_seconds", "Blockchain request duration in seconds");
+    counter!("rpc_timeouts_total", "HTTP RPC requests that missed their class deadline, by class");
+    counter!("rpc_retries_total", "HTTP RPC requests retried after a timeout or transport failure, by class");
+    counter!("rpc_batches_total", "JSON-RPC batches sent to the primary node, by method");
+    counter!("rpc_batched_requests_total", "Requests sent inside JSON-RPC batches, by method");
+    counter!("rpc_batch_item_errors_total", "Requests inside JSON-RPC batches the node failed, by method");
+    counter!("multicalls_total", "Multicall3 calls aggregating contract reads");
+    counter!("multicall_reads_total", "Contract reads sent inside Multicall3 calls");
+    counter!("abi_fetches_total", "Contract ABIs fetched from a verified contract registry, by source");
+    counter!("rpc_compute_units_total", "Compute units spent on HTTP RPC requests, by provider and priority");
+    gauge!("rpc_compute_units_available", "Compute units left in the provider's budget, by provider");
+    histogram!("rpc_rate_limit_wait_seconds", "Time HTTP RPC requests waited for budget, by provider and priority");
+}
+
+fn register_kpi_metrics() {
+    // Profit over the trailing hour
+    gauge!("kpi_gross_profit_eth_per_hour", "Simulated profit of distinct candidates over the last hour in ETH");
+    gauge!("kpi_realized_profit_eth_per_hour", "Profit of landed candidates over the last hour in ETH");
+    
+    gauge!("kpi_realized_profit_eth_per_hour_by_source", "Profit of landed candidates over the last hour in ETH, by flow source");
+    
+    // Inclusion
+    gauge!("kpi_landed_bundle_rate", "Share of candidates over the last hour that landed on-chain");
+    
+    gauge!("kpi_relay_win_rate", "Share of slots bid at a relay over the last hour that it delivered our block for, by relay");
+    
+    // Costs
+    gauge!("kpi_subsidy_cost_eth_per_hour", "Subsidy paid above extracted value over the last hour in ETH");
+    
+    // Staking
+    gauge!("kpi_tvl_eth", "Ether pooled by stakers in ETH");
+    gauge!("kpi_staking_apr", "Realized staking APR over the trailing week, gross or net of commission");
+}
+
+fn register_cache_metrics() {
+    counter!("cache_hits_total", "Bounded cache lookups that found an entry");
+    counter!("cache_misses_total", "Bounded cache lookups that found nothing");
+    counter!("cache_evictions_total", "Entries evicted from full bounded caches");
+    gauge!("cache_entries", "Entries held per bounded cache");
+}
+
+fn register_staking_metrics() {
+    gauge!("commission_balance_eth", "Operator commission accrued and not yet paid out, in ETH");
+    counter!("commission_payouts_total", "Commission payouts to the operator treasury, by outcome");
+    counter!("deposit_discrepancies_total", "Mismatches between deposit contract events and booked stake, by kind");
+    counter!("permit_deposits_total", "Token stakes relayed with an EIP-2612 permit");
+}
+
+fn register_alert_metrics() {
+    counter!("alerts_fired_total", "Total number of alerts delivered to sinks");
+    counter!("webhook_deliveries_total", "Attempts to deliver alerts to named webhooks, by outcome");
+    
+    // Circuit breaker
+    gauge!("risk_halted", "Whether live submission is halted by the circuit breaker or an operator");
+    counter!("risk_halts_total", "Times live submission was halted, by trigger");
+    
+    // Exploit detection
+    counter!("exploit_alerts_total", "Security alerts raised from the public mempool, by heuristic");
+    counter!("exploit_simulations_total", "Suspicious pending transactions simulated for exploit detection");
+    counter!("exploit_screen_skipped_total", "Suspicious pending transactions not simulated as too many were in flight");
 }
 
 /// Returns current metrics in Prometheus format
@@ -75,7 +288,29 @@ pub fn get_prometheus_metrics() -> Result<String> {
     let mut buffer = Vec::new();
     encoder.encode(&metric_families, &mut buffer)?;
     
-    Ok(String::from_utf8(buffer)?)
+    let mut output = String::from_utf8(buffer)?;
+    if let Some(handle) = RECORDER.get() {
+        output.push_str(&handle.render());
+    }
+    
+    Ok(output)
+}
+
+/// Returns the current value of every counter and gauge series, keyed as `name{labels}`
+pub fn snapshot() -> HashMap<String, f64> {
+    let rendered = match RECORDER.get() {
+        Some(handle) => handle.render(),
+        None => return HashMap::new(),
+    };
+    
+    rendered
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+        .filter_map(|line| {
+            let (series, value) = line.rsplit_once(' ')?;
+            Some((series.to_string(), value.parse().ok()?))
+        })
+        .collect()
 }
 
 /// Timer utility for measuring and recording performance metrics
@@ -97,4 +332,4 @@ impl MetricsTimer {
         histogram!(self.name, duration.as_secs_f64());
         duration
     }
-} 
\ No newline at end of file
+}
\ No newline at end of file
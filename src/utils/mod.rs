@@ -1,4 +1,12 @@
+pub mod audit;
+pub mod cache;
+pub mod heartbeat;
+pub mod intern;
 pub mod logging;
 pub mod metrics;
 pub mod result_ext;
-pub mod time; 
\ No newline at end of file
+pub mod sensitive;
+pub mod tasks;
+pub mod telemetry;
+pub mod time;
+pub mod units;
\ No newline at end of file
@@ -0,0 +1,31 @@
+use std::fmt;
+
+/// Wrapper for data covered by a flow-privacy agreement
+///
+/// Deliberately implements neither `Serialize` nor `Display`, and redacts itself in `Debug`,
+/// so wrapped values cannot reach Postgres, Redis, analytics or logs without an explicit
+/// call to [`Sensitive::expose`].
+#[derive(Clone)]
+pub struct Sensitive<T>(T);
+
+impl<T> Sensitive<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+    
+    /// Borrow the wrapped value; callers must keep it in memory only
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+    
+    /// Transform the wrapped value without unwrapping it
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Sensitive<U> {
+        Sensitive(f(self.0))
+    }
+}
+
+impl<T> fmt::Debug for Sensitive<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Sensitive(<redacted>)")
+    }
+}
@@ -0,0 +1,236 @@
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use std::{
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    runtime::{Builder, Handle, Runtime},
+    sync::watch,
+    task::JoinHandle,
+    time::interval,
+};
+use tracing::{info, warn};
+
+use crate::{config::TaskLaneConfig, utils::heartbeat::HeartbeatRegistry};
+
+/// Priority class of a task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    /// Work that must finish within the slot it is for, such as bid submission
+    Critical,
+    Normal,
+    /// Long-running analytics and backfill work that can wait
+    Background,
+}
+
+impl Lane {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Critical => "critical",
+            Self::Normal => "normal",
+            Self::Background => "background",
+        }
+    }
+}
+
+/// Runtimes of the lanes that don't run on the main runtime
+struct LaneRuntimes {
+    main: Handle,
+    critical: Option<Runtime>,
+    background: Option<Runtime>,
+}
+
+impl Drop for LaneRuntimes {
+    fn drop(&mut self) {
+        // A runtime dropped normally blocks on its workers, which panics inside another runtime
+        for runtime in [self.critical.take(), self.background.take()].into_iter().flatten() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// Runs tasks on the runtime of their lane
+///
+/// Critical work gets a small runtime of its own, so a main runtime busy with mempool ingestion
+/// can't delay a bid at the slot boundary, and background work another, so a long export or
+/// backfill can't occupy the main runtime's workers. Normal work stays on the main runtime, as
+/// does every lane when lanes are disabled. Tasks report how long they waited to be first
+/// polled and how long they ran, by lane.
+#[derive(Clone)]
+pub struct TaskLanes {
+    runtimes: Arc<LaneRuntimes>,
+}
+
+impl TaskLanes {
+    /// Start the lane runtimes, normal work stays on the runtime this is called from
+    pub fn new(config: &TaskLaneConfig) -> Result<Self> {
+        if !config.enabled {
+            return Ok(Self::current());
+        }
+        
+        let runtime = |name: &str, worker_threads: usize| {
+            Builder::new_multi_thread()
+                .worker_threads(worker_threads)
+                .thread_name(name)
+                .enable_all()
+                .build()
+                .context(format!("Failed to start the {} runtime", name))
+        };
+        let runtimes = LaneRuntimes {
+            main: Handle::current(),
+            critical: Some(runtime("slot-critical", config.critical_worker_threads)?),
+            background: Some(runtime("background", config.background_worker_threads)?),
+        };
+        info!(
+            "Task lanes started with {} critical and {} background workers",
+            config.critical_worker_threads, config.background_worker_threads
+        );
+        
+        Ok(Self {
+            runtimes: Arc::new(runtimes),
+        })
+    }
+    
+    /// Lanes that all run on the runtime this is called from
+    pub fn current() -> Self {
+        Self {
+            runtimes: Arc::new(LaneRuntimes {
+                main: Handle::current(),
+                critical: None,
+                background: None,
+            }),
+        }
+    }
+    
+    /// Runtime a lane's tasks run on
+    pub fn handle(&self, lane: Lane) -> Handle {
+        let runtime = match lane {
+            Lane::Critical => self.runtimes.critical.as_ref(),
+            Lane::Normal => None,
+            Lane::Background => self.runtimes.background.as_ref(),
+        };
+        runtime.map_or_else(|| self.runtimes.main.clone(), |runtime| runtime.handle().clone())
+    }
+    
+    /// Spawn a task on its lane
+    pub fn spawn<F>(&self, lane: Lane, task: &'static str, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let spawned_at = Instant::now();
+        self.handle(lane).spawn(async move {
+            let started = Instant::now();
+            metrics::histogram!(
+                "task_schedule_delay_seconds",
+                (started - spawned_at).as_secs_f64(),
+                "lane" => lane.as_str(),
+                "task" => task
+            );
+            
+            let output = future.await;
+            metrics::histogram!(
+                "task_duration_seconds",
+                started.elapsed().as_secs_f64(),
+                "lane" => lane.as_str(),
+                "task" => task
+            );
+            output
+        })
+    }
+    
+    /// Run a future on its lane and wait for its output
+    pub async fn run<F>(&self, lane: Lane, task: &'static str, future: F) -> Result<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawn(lane, task, future)
+            .await
+            .context(format!("Task {} on the {} lane panicked", task, lane.as_str()))
+    }
+}
+
+/// Supervisor for periodic background jobs with coordinated shutdown
+pub struct BackgroundTasks {
+    /// Signals all jobs to stop
+    shutdown_sender: watch::Sender<bool>,
+    /// Running jobs by name
+    handles: Mutex<Vec<(&'static str, JoinHandle<()>)>>,
+    /// Heartbeats reported after every job run
+    heartbeats: HeartbeatRegistry,
+    /// Lanes jobs run on
+    lanes: TaskLanes,
+}
+
+impl BackgroundTasks {
+    /// Create a new supervisor with no running jobs
+    pub fn new(heartbeats: HeartbeatRegistry, lanes: TaskLanes) -> Self {
+        let (shutdown_sender, _) = watch::channel(false);
+        
+        Self {
+            shutdown_sender,
+            handles: Mutex::new(Vec::new()),
+            heartbeats,
+            lanes,
+        }
+    }
+    
+    /// Spawn a job that runs every `period` until shutdown
+    pub fn spawn_periodic<F, Fut>(&self, name: &'static str, period: Duration, job: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.spawn_periodic_on(Lane::Normal, name, period, job)
+    }
+    
+    /// Spawn a job that runs every `period` on a lane until shutdown
+    pub fn spawn_periodic_on<F, Fut>(&self, lane: Lane, name: &'static str, period: Duration, job: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let mut shutdown_rx = self.shutdown_sender.subscribe();
+        let heartbeats = self.heartbeats.clone();
+        heartbeats.register(name, period);
+        
+        let handle = self.lanes.handle(lane).spawn(async move {
+            info!("Background job {} started on the {} lane", name, lane.as_str());
+            let mut interval = interval(period);
+            
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        if let Err(e) = job().await {
+                            warn!("Background job {} failed: {}", name, e);
+                        }
+                        heartbeats.beat(name);
+                    }
+                    _ = shutdown_rx.changed() => {
+                        break;
+                    }
+                }
+            }
+            
+            heartbeats.unregister(name);
+            info!("Background job {} stopped", name);
+        });
+        
+        self.handles.lock().push((name, handle));
+    }
+    
+    /// Stop all jobs and wait for them to finish
+    pub async fn shutdown(&self) {
+        let _ = self.shutdown_sender.send(true);
+        
+        let handles = std::mem::take(&mut *self.handles.lock());
+        for (name, handle) in handles {
+            if let Err(e) = handle.await {
+                warn!("Error waiting for background job {} to complete: {}", name, e);
+            }
+        }
+    }
+}
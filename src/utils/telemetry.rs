@@ -0,0 +1,65 @@
+use ethers::types::{H256, U256};
+use std::sync::OnceLock;
+use tracing::{error_span, info_span, Span};
+
+use crate::config::TraceSamplingConfig;
+
+/// Sampling in effect, installed by logging initialization
+static SAMPLING: OnceLock<TraceSamplingConfig> = OnceLock::new();
+
+/// Sample everything until configured, so tools and tests see every span
+const UNCONFIGURED: TraceSamplingConfig = TraceSamplingConfig {
+    head_rate: 1.0,
+    always_sample_errors: true,
+    always_sample_landed: true,
+};
+
+/// Install the sampling configuration; later calls are ignored
+pub fn init(config: &TraceSamplingConfig) {
+    let _ = SAMPLING.set(config.clone());
+}
+
+fn sampling() -> &'static TraceSamplingConfig {
+    SAMPLING.get().unwrap_or(&UNCONFIGURED)
+}
+
+/// Whether a transaction falls in the head-sampled share of traffic
+///
+/// Decided from the hash rather than at random, so every span of one transaction, on every
+/// instance, makes the same call.
+fn head_sampled(tx_hash: &H256) -> bool {
+    let mut low = [0u8; 8];
+    low.copy_from_slice(&tx_hash.as_bytes()[24..]);
+    (u64::from_be_bytes(low) as f64) < sampling().head_rate * u64::MAX as f64
+}
+
+/// Span for processing one pending transaction, disabled outside the head sample
+pub fn pending_tx_span(tx_hash: H256) -> Span {
+    if head_sampled(&tx_hash) {
+        info_span!("pending_tx", tx_hash = ?tx_hash, sampled = "head")
+    } else {
+        Span::none()
+    }
+}
+
+/// Span to report a transaction's failure in
+///
+/// The transaction's own span if it was sampled, otherwise a new one when errors are always sampled.
+pub fn failure_span(tx_hash: H256, span: &Span) -> Span {
+    if !span.is_disabled() {
+        span.clone()
+    } else if sampling().always_sample_errors {
+        error_span!("pending_tx", tx_hash = ?tx_hash, sampled = "error")
+    } else {
+        Span::none()
+    }
+}
+
+/// Span for an inclusion candidate that landed on chain
+pub fn landed_span(tx_hash: H256, profit: U256) -> Span {
+    if sampling().always_sample_landed || head_sampled(&tx_hash) {
+        info_span!("landed_opportunity", tx_hash = ?tx_hash, profit_wei = %profit, sampled = "landed")
+    } else {
+        Span::none()
+    }
+}
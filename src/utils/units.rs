@@ -0,0 +1,101 @@
+use ethers::types::U256;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+/// Wei per ether
+const WEI_PER_ETH: f64 = 1e18;
+
+/// Convert a wei amount to ether as a float, for metrics and display only
+pub fn wei_to_eth(wei: U256) -> f64 {
+    if wei > U256::from(u128::MAX) {
+        return f64::MAX;
+    }
+    
+    wei.as_u128() as f64 / WEI_PER_ETH
+}
+
+/// Decimals of ether
+const ETH_DECIMALS: usize = 18;
+
+/// An exact ether amount, held in wei
+///
+/// Parsed from and rendered as decimal ether strings such as `"0.1"`, so amounts crossing
+/// the config and API boundary never pass through a float. Floats are rejected outright.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EthAmount(U256);
+
+impl EthAmount {
+    pub fn from_wei(wei: U256) -> Self {
+        Self(wei)
+    }
+    
+    pub fn wei(&self) -> U256 {
+        self.0
+    }
+}
+
+/// Why a string is not a valid ether amount
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AmountError {
+    #[error("amount is empty")]
+    Empty,
+    #[error("amount must be a plain decimal number of ether, got {0:?}")]
+    Malformed(String),
+    #[error("amount has more than 18 decimal places")]
+    TooPrecise,
+    #[error("amount is too large")]
+    Overflow,
+}
+
+impl FromStr for EthAmount {
+    type Err = AmountError;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(AmountError::Empty);
+        }
+        
+        let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+        let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+            return Err(AmountError::Malformed(s.to_string()));
+        }
+        if fraction.len() > ETH_DECIMALS {
+            return Err(AmountError::TooPrecise);
+        }
+        
+        let digits = format!("{}{:0<width$}", whole, fraction, width = ETH_DECIMALS);
+        let digits = digits.trim_start_matches('0');
+        if digits.is_empty() {
+            return Ok(Self(U256::zero()));
+        }
+        U256::from_dec_str(digits).map(Self).map_err(|_| AmountError::Overflow)
+    }
+}
+
+impl fmt::Display for EthAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = format!("{:0>width$}", self.0.to_string(), width = ETH_DECIMALS + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - ETH_DECIMALS);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            write!(f, "{}", whole)
+        } else {
+            write!(f, "{}.{}", whole, fraction)
+        }
+    }
+}
+
+impl Serialize for EthAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for EthAmount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        raw.parse().map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,72 @@
+//! Sandwich detection against an anvil fork of mainnet
+//!
+//! Run with `ANVIL_FORK_URL=<mainnet node> cargo test --features testing --test anvil_sandwich`.
+//! Needs `anvil` on PATH and the Postgres and Redis named by `TEST_DATABASE_URL` and
+//! `TEST_REDIS_URL`.
+
+#![cfg(feature = "testing")]
+
+use anyhow::{anyhow, Result};
+use ethers::{
+    abi::{self, Token},
+    signers::Signer,
+    types::{Address, Bytes, Eip1559TransactionRequest, U256},
+    utils::id,
+};
+use mev_capture::{core::strategy::Opportunity, testing::AnvilHarness};
+
+const V2_ROUTER: &str = "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D";
+const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+/// A pending WETH to USDC swap with no slippage bound is found as a sandwich for the next block
+#[tokio::test]
+async fn pending_swap_is_sandwiched() -> Result<()> {
+    let router: Address = V2_ROUTER.parse()?;
+    let weth: Address = WETH.parse()?;
+    let usdc: Address = USDC.parse()?;
+    
+    let mut harness = AnvilHarness::from_env().await?;
+    harness.config.services.sandwich.enabled = true;
+    let services = harness.services().await?;
+    
+    // The detector targets the block after the head, which the block monitor would have recorded
+    let head = harness.latest_block().await?;
+    let head_number = head.number.unwrap_or_default().as_u64();
+    services.head_tracker.record_head(head_number, head.hash.unwrap_or_default(), false);
+    
+    let victim = harness.accounts.first().cloned().ok_or_else(|| anyhow!("Anvil has no accounts"))?;
+    harness.fund(victim.address(), U256::exp10(18) * 1_000).await?;
+    
+    let mut data = id("swapExactETHForTokens(uint256,address[],address,uint256)").to_vec();
+    data.extend(abi::encode(&[
+        Token::Uint(U256::zero()),
+        Token::Array(vec![Token::Address(weth), Token::Address(usdc)]),
+        Token::Address(victim.address()),
+        Token::Uint(U256::from(u64::MAX)),
+    ]));
+    let swap = Eip1559TransactionRequest::new()
+        .to(router)
+        .value(U256::exp10(18) * 200)
+        .data(Bytes::from(data))
+        .gas(300_000);
+    let tx = harness.sign(&victim, swap).await?;
+    
+    let found = harness.observe_pending(tx.clone()).await?;
+    let sandwich = found
+        .iter()
+        .find_map(|opportunity| match opportunity {
+            Opportunity::Sandwich(sandwich) if sandwich.victim == tx.hash => Some(sandwich),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("No sandwich found around {:?} in {:?}", tx.hash, found))?;
+    
+    assert_eq!(sandwich.token_in, weth);
+    assert_eq!(sandwich.token_out, usdc);
+    assert_eq!(sandwich.target_block, head_number + 1);
+    assert!(!sandwich.frontrun_amount_in.is_zero());
+    assert!(sandwich.backrun_amount_out > sandwich.frontrun_amount_in);
+    assert!(!sandwich.expected_profit.is_zero());
+    
+    Ok(())
+}